@@ -51,4 +51,10 @@ public class TaskDispatchItem {
 
     @JsonProperty("priority")
     private Integer priority;
+
+    /**
+     * 服务端重试次数，回传结果时原样带回
+     */
+    @JsonProperty("retry_attempt")
+    private Integer retryAttempt;
 }
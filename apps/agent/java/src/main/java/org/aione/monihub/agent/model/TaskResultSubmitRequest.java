@@ -73,4 +73,10 @@ public class TaskResultSubmitRequest {
      */
     @JsonProperty("duration_ms")
     private Long durationMs;
+
+    /**
+     * 下发时的重试次数
+     */
+    @JsonProperty("retry_attempt")
+    private Integer retryAttempt;
 }
\ No newline at end of file
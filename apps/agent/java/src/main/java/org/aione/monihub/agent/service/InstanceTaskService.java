@@ -219,6 +219,7 @@ public class InstanceTaskService {
                     req.setStartTime(formatTime(result.getStartTime()));
                     req.setEndTime(formatTime(result.getEndTime()));
                     req.setDurationMs(result.getDurationMs());
+                    req.setRetryAttempt(task.getRetryAttempt());
 
                     String json = objectMapper.writeValueAsString(req);
                     okhttp3.HttpUrl url = okhttp3.HttpUrl.parse(agentConfig.getServerUrl() + "/api/open/instances/tasks/result");
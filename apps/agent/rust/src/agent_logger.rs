@@ -8,15 +8,22 @@ static STATE: OnceCell<AppState> = OnceCell::new();
 static LOGGER_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 pub fn init(cfg: &crate::config::Config) {
+    // 过滤器放开到 Debug，实际级别由 set_debug 控制，便于运行时切换
     let mut b = Builder::from_default_env();
-    if cfg.debug {
-        b.filter_level(LevelFilter::Debug);
-    } else {
-        b.filter_level(LevelFilter::Warn);
-    }
+    b.filter_level(LevelFilter::Debug);
     if b.try_init().is_ok() {
         LOGGER_INITIALIZED.store(true, Ordering::Relaxed);
     }
+    set_debug(cfg.debug);
+}
+
+/// 切换调试日志级别
+pub fn set_debug(debug: bool) {
+    log::set_max_level(if debug {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Warn
+    });
 }
 
 pub fn set_state(state: AppState) {
@@ -25,7 +32,7 @@ pub fn set_state(state: AppState) {
 
 pub fn info(message: &str) {
     if let Some(state) = STATE.get() {
-        if state.cfg.debug {
+        if state.debug_enabled() {
             log::info!("{}", message);
             state.logs.push("INFO", message);
         }
@@ -52,7 +59,7 @@ pub fn error(message: &str) {
 #[allow(dead_code)]
 pub fn debug(message: &str) {
     if let Some(state) = STATE.get() {
-        if state.cfg.debug {
+        if state.debug_enabled() {
             log::debug!("{}", message);
             state.logs.push("DEBUG", message);
         }
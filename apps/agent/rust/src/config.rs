@@ -2,9 +2,10 @@ use crate::agent_logger;
 /// 配置模块
 ///
 /// 提供 Agent 运行所需的所有配置项：服务端地址、代理、上报与任务参数等。
-/// 支持从 YAML 文件或环境变量加载，并自动为 `instance_id` 进行本地持久化。
+/// 支持从 YAML 文件或环境变量加载，并自动为 `instance_id` 与实例令牌进行本地持久化。
 use dirs::cache_dir;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
@@ -22,6 +23,17 @@ pub struct Config {
     pub task: TaskConfig,
     pub file: FileConfig,
     pub http: HttpConfig,
+    pub auth: AuthConfig,
+    pub terminal: TerminalConfig,
+    pub monitor: MonitorConfig,
+    pub environment: EnvironmentConfig,
+    pub log_spool: LogSpoolConfig,
+    pub log_tail: LogTailConfig,
+    pub shell_policy: ShellPolicyConfig,
+    pub upgrade: UpgradeConfig,
+    pub config_watch: ConfigWatchConfig,
+    /// 实例标签，随上报写入 custom_fields.tags，用于任务目标选择
+    pub tags: Vec<String>,
     pub agent_instance_id: Option<String>,
     pub instance_id: Option<String>,
 }
@@ -50,6 +62,8 @@ pub struct TaskConfig {
     pub long_poll_enabled: bool,
     pub long_poll_timeout_seconds: u64,
     pub max_concurrent_tasks: usize,
+    /// 执行过程中分段上报脚本输出
+    pub stream_output: bool,
 }
 impl Default for TaskConfig {
     fn default() -> Self {
@@ -58,6 +72,7 @@ impl Default for TaskConfig {
             long_poll_enabled: true,
             long_poll_timeout_seconds: 30,
             max_concurrent_tasks: 2,
+            stream_output: true,
         }
     }
 }
@@ -100,6 +115,332 @@ impl Default for HttpConfig {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 实例认证配置：注册密钥用于换取实例令牌，令牌按周期轮换
+pub struct AuthConfig {
+    pub enrollment_secret: Option<String>,
+    pub token_rotate_hours: u64,
+}
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enrollment_secret: None,
+            token_rotate_hours: 24,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 远程终端配置
+pub struct TerminalConfig {
+    pub enabled: bool,
+    pub shell: Option<String>,
+    pub max_sessions: usize,
+    pub reconnect_seconds: u64,
+}
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shell: None,
+            max_sessions: 4,
+            reconnect_seconds: 5,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+/// 被监控应用进程：优先按 PID，其次按进程名精确匹配，均未配置时不采集
+pub struct MonitorConfig {
+    pub process_pid: Option<u32>,
+    pub process_name: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 环境变量上报配置：`include` 为空时上报全部（`exclude` 除外），敏感变量值打码
+pub struct EnvironmentConfig {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// 额外需要打码的变量名模式
+    pub mask: Vec<String>,
+    /// 是否启用默认敏感模式（*_PASSWORD、*_TOKEN、*_SECRET、AWS 密钥等）
+    pub mask_defaults: bool,
+}
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            mask: Vec::new(),
+            mask_defaults: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// Agent 日志落盘缓冲与上报配置
+pub struct LogSpoolConfig {
+    /// 缓冲文件路径，默认 <cache_dir>/monihub/<application_code>/agent-logs.jsonl
+    pub path: Option<String>,
+    /// 缓冲条数上限，超出时丢弃最早的日志
+    pub max_entries: usize,
+    pub batch_size: usize,
+    pub flush_interval_seconds: u64,
+    /// 上报失败后的最大退避间隔
+    pub max_backoff_seconds: u64,
+}
+impl Default for LogSpoolConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_entries: 10000,
+            batch_size: 200,
+            flush_interval_seconds: 5,
+            max_backoff_seconds: 300,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 应用日志文件采集配置
+pub struct LogTailConfig {
+    pub enabled: bool,
+    /// 扫描文件与读取新增内容的间隔
+    pub scan_interval_seconds: u64,
+    /// 应用日志缓冲条数上限，与 Agent 自身日志分开缓冲，超出时丢弃最早的应用日志
+    pub spool_max_entries: usize,
+    pub files: Vec<LogFileConfig>,
+}
+impl Default for LogTailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            scan_interval_seconds: 2,
+            spool_max_entries: 10000,
+            files: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// 日志文件格式
+pub enum LogFileFormat {
+    /// 普通文本，按行首判断多行合并，正则提取级别
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象
+    Json,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 单组日志文件采集规则
+pub struct LogFileConfig {
+    /// 文件路径，支持 `*`、`?`、`**` 通配
+    pub paths: Vec<String>,
+    pub format: LogFileFormat,
+    /// 新事件起始行正则；不匹配的行并入上一事件。缺省时缩进行、异常类型行与 `Caused by:` 视为续行
+    pub multiline_start: Option<String>,
+    /// 级别提取正则，优先取命名分组 `level`，否则取第一个分组
+    pub level_pattern: Option<String>,
+    /// JSON 格式的级别/消息/时间字段名
+    pub level_field: String,
+    pub message_field: String,
+    pub timestamp_field: String,
+    /// 原始级别到 trace/debug/info/warn/error/fatal 的映射（不区分大小写）
+    pub level_map: HashMap<String, String>,
+    /// 首次发现且无记录偏移的文件是否从头读取，默认从末尾开始
+    pub from_beginning: bool,
+}
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            format: LogFileFormat::Text,
+            multiline_start: None,
+            level_pattern: None,
+            level_field: "level".to_string(),
+            message_field: "message".to_string(),
+            timestamp_field: "timestamp".to_string(),
+            level_map: HashMap::new(),
+            from_beginning: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 脚本任务（shell_exec）执行策略，运行代码任务（run_code）同受其约束
+pub struct ShellPolicyConfig {
+    /// 关闭后拒绝所有脚本与运行代码任务
+    pub enabled: bool,
+    /// 允许任务指定的运行用户，为空时不允许切换用户
+    pub allowed_users: Vec<String>,
+    /// 任务未指定用户时使用的运行用户，为空时以 Agent 自身用户运行
+    pub default_user: Option<String>,
+    /// 允许任务指定的运行用户组，为空时使用运行用户的主组
+    pub allowed_groups: Vec<String>,
+    /// 允许的工作目录前缀，为空时不限制
+    pub allowed_workdirs: Vec<String>,
+    /// 单个输出流最多保留的字节数，任务配置不可超过该值
+    pub max_output_bytes: usize,
+    /// 默认资源限制，同时作为任务可申请的上限
+    pub limits: ResourceLimits,
+    /// Linux 下是否使用 cgroup v2 限制内存/CPU/进程数
+    pub use_cgroup: bool,
+    /// cgroup 父目录（相对 /sys/fs/cgroup）
+    pub cgroup_parent: String,
+}
+impl Default for ShellPolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_users: Vec::new(),
+            default_user: None,
+            allowed_groups: Vec::new(),
+            allowed_workdirs: Vec::new(),
+            max_output_bytes: 1024 * 1024,
+            limits: ResourceLimits::default(),
+            use_cgroup: true,
+            cgroup_parent: "monihub".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// Agent 自升级配置
+pub struct UpgradeConfig {
+    /// 关闭后拒绝升级任务
+    pub enabled: bool,
+    /// 发布包签名公钥（Base64 编码的 32 字节 Ed25519 公钥）
+    pub public_key: Option<String>,
+    /// 是否允许安装未签名的发布包（仍校验 SHA-256）
+    pub allow_unsigned: bool,
+    /// 新版本启动后等待首次上报成功的时长，超时回滚到旧版本
+    pub health_timeout_seconds: u64,
+}
+impl Default for UpgradeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            public_key: None,
+            allow_unsigned: false,
+            health_timeout_seconds: 120,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+/// 配置中心订阅：将服务端配置写入本地文件，变更后执行重载命令
+pub struct ConfigWatchConfig {
+    pub enabled: bool,
+    /// 条目未指定环境时使用的环境（dev/test/staging/prod）
+    pub environment: String,
+    /// 单次 watch 长轮询的等待秒数（服务端最大 60）
+    pub timeout_seconds: u64,
+    pub files: Vec<ConfigFileConfig>,
+}
+impl Default for ConfigWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            environment: "prod".to_string(),
+            timeout_seconds: 30,
+            files: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+/// 配置文件格式
+pub enum ConfigFileFormat {
+    #[default]
+    Json,
+    Yaml,
+    /// 嵌套字段展开为 `a.b[0].c=value`
+    Properties,
+    /// 按原文写入（text/html 配置）
+    Raw,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+/// 单个订阅的配置
+pub struct ConfigFileConfig {
+    pub code: String,
+    pub environment: Option<String>,
+    /// 写入的本地文件路径
+    pub path: String,
+    pub format: ConfigFileFormat,
+    /// 文件内容变化后执行的命令（sh -c / cmd /C）
+    pub reload_command: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+/// 进程资源限制，未设置的项不限制
+pub struct ResourceLimits {
+    pub memory_mb: Option<u64>,
+    pub cpu_seconds: Option<u64>,
+    /// CPU 配额百分比（100 为单核），仅 cgroup 生效
+    pub cpu_percent: Option<u64>,
+    pub max_processes: Option<u64>,
+    pub max_open_files: Option<u64>,
+    pub max_file_size_mb: Option<u64>,
+}
+impl ResourceLimits {
+    /// 合并任务申请与策略上限：两者都有时取较小值
+    pub fn clamp_by(self, policy: ResourceLimits) -> ResourceLimits {
+        fn pick(task: Option<u64>, policy: Option<u64>) -> Option<u64> {
+            match (task, policy) {
+                (Some(t), Some(p)) => Some(t.min(p)),
+                (t, p) => t.or(p),
+            }
+        }
+        ResourceLimits {
+            memory_mb: pick(self.memory_mb, policy.memory_mb),
+            cpu_seconds: pick(self.cpu_seconds, policy.cpu_seconds),
+            cpu_percent: pick(self.cpu_percent, policy.cpu_percent),
+            max_processes: pick(self.max_processes, policy.max_processes),
+            max_open_files: pick(self.max_open_files, policy.max_open_files),
+            max_file_size_mb: pick(self.max_file_size_mb, policy.max_file_size_mb),
+        }
+    }
+}
+
+#[derive(Clone)]
+/// 运行时配置：可由服务端下发并热加载的配置项
+pub struct RuntimeConfig {
+    /// 已生效的服务端配置版本，None 表示仍使用本地配置
+    pub revision: Option<i32>,
+    pub debug: bool,
+    pub report: ReportConfig,
+    pub task: TaskConfig,
+    pub http: HttpConfig,
+}
+impl RuntimeConfig {
+    /// 以本地配置作为初始值
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            revision: None,
+            debug: cfg.debug,
+            report: cfg.report.clone(),
+            task: cfg.task.clone(),
+            http: cfg.http.clone(),
+        }
+    }
+}
+
 impl Config {
     /// 加载配置:优先级为 启动参数 > 环境变量 > 配置文件 > 默认值
     /// 若 `instance_id` 缺失或为空,将生成并持久化一个新的实例 ID。
@@ -129,6 +470,16 @@ impl Config {
                     if !file_cfg.application_code.is_empty() {
                         config.application_code = file_cfg.application_code;
                     }
+                    config.auth = file_cfg.auth;
+                    config.terminal = file_cfg.terminal;
+                    config.monitor = file_cfg.monitor;
+                    config.environment = file_cfg.environment;
+                    config.log_spool = file_cfg.log_spool;
+                    config.log_tail = file_cfg.log_tail;
+                    config.shell_policy = file_cfg.shell_policy;
+                    config.upgrade = file_cfg.upgrade;
+                    config.config_watch = file_cfg.config_watch;
+                    config.tags = file_cfg.tags;
                 }
                 Err(err) => {
                     agent_logger::error(&format!(
@@ -163,6 +514,30 @@ impl Config {
                 config.debug = debug_val;
             }
         }
+        if let Ok(env_tags) = std::env::var("MONIHUB_TAGS") {
+            if !env_tags.is_empty() {
+                config.tags = env_tags
+                    .split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+            }
+        }
+        if let Ok(env_pid) = std::env::var("MONIHUB_MONITOR_PID") {
+            if let Ok(pid) = env_pid.trim().parse::<u32>() {
+                config.monitor.process_pid = Some(pid);
+            }
+        }
+        if let Ok(env_process) = std::env::var("MONIHUB_MONITOR_PROCESS") {
+            if !env_process.is_empty() {
+                config.monitor.process_name = Some(env_process);
+            }
+        }
+        if let Ok(env_secret) = std::env::var("MONIHUB_ENROLLMENT_SECRET") {
+            if !env_secret.is_empty() {
+                config.auth.enrollment_secret = Some(env_secret);
+            }
+        }
 
         // 3. 从启动参数加载(最高优先级)
         if let Some(url) = cli_server_url {
@@ -200,12 +575,22 @@ impl Config {
         Self {
             server_url: String::from("http://localhost:9080"),
             agent_type: String::from("rust-agent"),
-            agent_version: String::from("1.0.0"),
+            agent_version: String::from(env!("CARGO_PKG_VERSION")),
             debug: false,
             report: ReportConfig::default(),
             task: TaskConfig::default(),
             file: FileConfig::default(),
             http: HttpConfig::default(),
+            auth: AuthConfig::default(),
+            terminal: TerminalConfig::default(),
+            monitor: MonitorConfig::default(),
+            environment: EnvironmentConfig::default(),
+            log_spool: LogSpoolConfig::default(),
+            log_tail: LogTailConfig::default(),
+            shell_policy: ShellPolicyConfig::default(),
+            upgrade: UpgradeConfig::default(),
+            config_watch: ConfigWatchConfig::default(),
+            tags: Vec::new(),
             agent_instance_id: None,
             application_code: String::new(),
             instance_id: None,
@@ -215,12 +600,72 @@ impl Config {
     fn from_file(path: &str) -> anyhow::Result<Self> {
         Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
     }
-    /// 将实例 ID 持久化到本地缓存目录，并在不存在时生成一个新 ID
-    fn persist_instance_id(config: Config) -> anyhow::Result<String> {
+    /// 本地缓存配置文件路径：<cache_dir>/monihub/<application_code>/config.json
+    fn cache_config_path(application_code: &str) -> anyhow::Result<PathBuf> {
         let dir = cache_dir().unwrap_or_else(|| PathBuf::from("."));
-        let base = dir.join("monihub").join(config.application_code);
+        let base = dir.join("monihub").join(application_code);
         fs::create_dir_all(&base)?;
-        let f = base.join("config.json");
+        Ok(base.join("config.json"))
+    }
+    /// 日志缓冲文件路径，未配置时放在本地缓存目录下
+    pub fn log_spool_path(&self) -> PathBuf {
+        if let Some(p) = self.log_spool.path.as_ref().filter(|p| !p.is_empty()) {
+            return PathBuf::from(p);
+        }
+        Self::cache_config_path(&self.application_code)
+            .ok()
+            .and_then(|f| f.parent().map(|d| d.join("agent-logs.jsonl")))
+            .unwrap_or_else(|| PathBuf::from("agent-logs.jsonl"))
+    }
+    /// 应用日志缓冲文件路径，与 Agent 日志缓冲文件同目录
+    pub fn app_log_spool_path(&self) -> PathBuf {
+        self.log_spool_path().with_file_name("app-logs.jsonl")
+    }
+    /// 日志文件采集偏移记录路径
+    pub fn log_offsets_path(&self) -> PathBuf {
+        Self::cache_config_path(&self.application_code)
+            .ok()
+            .and_then(|f| f.parent().map(|d| d.join("log-offsets.json")))
+            .unwrap_or_else(|| PathBuf::from("log-offsets.json"))
+    }
+    /// 升级状态文件路径，记录待确认的升级以便新版本启动后确认或回滚
+    pub fn upgrade_state_path(&self) -> PathBuf {
+        Self::cache_config_path(&self.application_code)
+            .ok()
+            .and_then(|f| f.parent().map(|d| d.join("upgrade-state.json")))
+            .unwrap_or_else(|| PathBuf::from("upgrade-state.json"))
+    }
+    /// 读取本地缓存的实例令牌
+    pub fn load_agent_token(&self) -> Option<String> {
+        let f = Self::cache_config_path(&self.application_code).ok()?;
+        let content = fs::read_to_string(f).ok()?;
+        let obj = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+        obj.get("agent_token")
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+    }
+    /// 持久化实例令牌到本地缓存（None 表示清除）
+    pub fn persist_agent_token(&self, token: Option<&str>) -> anyhow::Result<()> {
+        let f = Self::cache_config_path(&self.application_code)?;
+        let mut obj = fs::read_to_string(&f)
+            .ok()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .and_then(|v| v.as_object().cloned())
+            .unwrap_or_default();
+        match token {
+            Some(t) => obj.insert("agent_token".to_string(), serde_json::json!(t)),
+            None => obj.remove("agent_token"),
+        };
+        fs::write(
+            &f,
+            serde_json::to_string_pretty(&serde_json::Value::Object(obj))?,
+        )?;
+        Ok(())
+    }
+    /// 将实例 ID 持久化到本地缓存目录，并在不存在时生成一个新 ID
+    fn persist_instance_id(config: Config) -> anyhow::Result<String> {
+        let f = Self::cache_config_path(&config.application_code)?;
         if f.exists() {
             let content = fs::read_to_string(&f)?;
             return match serde_json::from_str::<serde_json::Value>(&content) {
@@ -0,0 +1,351 @@
+/// Agent 升级处理器
+///
+/// 从服务端查询适用于本机平台的发布包，下载后校验 SHA-256 与 Ed25519 签名，
+/// 试运行 `--version` 确认新文件可执行，再替换当前可执行文件并重新执行自身。
+/// 新版本的健康确认与超时回滚见 `services::self_update`。
+/// 任务内容关键字段：version（为空使用应用目标版本）、health_timeout_seconds。
+use crate::{
+    agent_logger,
+    config::UpgradeConfig,
+    handlers::TaskFailure,
+    models::TaskDispatchItem,
+    services::{self_update, AppState},
+    utils::http_util,
+};
+use anyhow::Result;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::time::{timeout, Duration};
+
+/// 同一时间只允许一个升级任务
+static UPGRADING: AtomicBool = AtomicBool::new(false);
+
+/// 服务端返回的发布包信息
+#[derive(Deserialize)]
+struct ReleaseManifest {
+    version: String,
+    platform: String,
+    file_size: i64,
+    sha256: String,
+    signature: Option<String>,
+    download_url: String,
+}
+
+fn failure(reason: &'static str, detail: String, data: Value) -> anyhow::Error {
+    TaskFailure {
+        reason,
+        detail,
+        data,
+    }
+    .into()
+}
+
+pub async fn execute(state: &AppState, item: &TaskDispatchItem, timeout_sec: u64) -> Result<Value> {
+    if !state.cfg.upgrade.enabled {
+        return Err(anyhow::anyhow!("agent upgrade is disabled by local config"));
+    }
+    if UPGRADING.swap(true, Ordering::SeqCst) {
+        return Err(anyhow::anyhow!("another upgrade is in progress"));
+    }
+    let result = timeout(
+        Duration::from_secs(timeout_sec.max(30)),
+        upgrade(state, item),
+    )
+    .await;
+    UPGRADING.store(false, Ordering::SeqCst);
+    result.map_err(|_| anyhow::anyhow!("upgrade timeout"))?
+}
+
+async fn upgrade(state: &AppState, item: &TaskDispatchItem) -> Result<Value> {
+    let version = item
+        .task_content
+        .get("version")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty());
+    let health_timeout = item
+        .task_content
+        .get("health_timeout_seconds")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(state.cfg.upgrade.health_timeout_seconds);
+    let current = state.cfg.agent_version.clone();
+
+    if let Some(pending) = self_update::load_state(state) {
+        return Err(anyhow::anyhow!(
+            "previous upgrade to {} is not confirmed yet",
+            pending.to_version
+        ));
+    }
+
+    let manifest = fetch_manifest(state, version).await?;
+    if manifest.version == current {
+        return Ok(json!({
+            "status": "up_to_date",
+            "version": current,
+            "platform": manifest.platform,
+        }));
+    }
+
+    let bytes = download(state, &manifest).await?;
+    verify(&state.cfg.upgrade, &manifest, &bytes)?;
+
+    let exe = std::env::current_exe()?;
+    let new = self_update::with_suffix(&exe, ".new");
+    let backup = self_update::with_suffix(&exe, ".bak");
+    tokio::fs::write(&new, &bytes).await?;
+    copy_permissions(&exe, &new);
+    if let Err(e) = smoke_test(&new, &manifest.version).await {
+        let _ = std::fs::remove_file(&new);
+        return Err(e);
+    }
+
+    self_update::install(&new, &exe, &backup)
+        .map_err(|e| anyhow::anyhow!("replace agent binary failed: {}", e))?;
+    let upgrade = self_update::UpgradeState {
+        from_version: current.clone(),
+        to_version: manifest.version.clone(),
+        exe: exe.clone(),
+        backup,
+        record_id: item.record_id.clone(),
+        deadline: chrono::Utc::now().timestamp() + health_timeout as i64,
+        starts: 0,
+    };
+    self_update::save_state(state, &upgrade)?;
+
+    agent_logger::warn(&format!(
+        "Agent 升级 {} -> {} 已安装，即将重新启动",
+        current, manifest.version
+    ));
+    self_update::schedule_restart(exe, Duration::from_secs(3));
+    Ok(json!({
+        "status": "restarting",
+        "from_version": current,
+        "to_version": manifest.version,
+        "platform": manifest.platform,
+        "sha256": manifest.sha256,
+        "signed": manifest.signature.is_some(),
+        "health_timeout_seconds": health_timeout,
+    }))
+}
+
+async fn fetch_manifest(state: &AppState, version: Option<&str>) -> Result<ReleaseManifest> {
+    let platform = self_update::platform();
+    let mut params = vec![
+        (
+            "agent_instance_id",
+            state.cfg.agent_instance_id.clone().unwrap_or_default(),
+        ),
+        ("platform", platform.clone()),
+    ];
+    if let Some(v) = version {
+        params.push(("version", v.to_string()));
+    }
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/api/open/instances/agent-release", state.cfg.server_url),
+        &params,
+    )?;
+    let resp = http_util::get(url.to_string()).await?;
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(failure(
+            "release_not_found",
+            format!("查询发布包失败 http={} body={}", status, body),
+            json!({"platform": platform, "version": version, "http_status": status}),
+        ));
+    }
+    Ok(resp.json::<ReleaseManifest>().await?)
+}
+
+async fn download(state: &AppState, manifest: &ReleaseManifest) -> Result<Vec<u8>> {
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}{}", state.cfg.server_url, manifest.download_url),
+        &[(
+            "agent_instance_id",
+            state.cfg.agent_instance_id.clone().unwrap_or_default(),
+        )],
+    )?;
+    let resp = http_util::get(url.to_string()).await?;
+    if !resp.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "download release failed http={}",
+            resp.status().as_u16()
+        ));
+    }
+    let bytes = resp.bytes().await?.to_vec();
+    if bytes.len() as i64 != manifest.file_size {
+        return Err(failure(
+            "checksum_mismatch",
+            format!(
+                "发布包大小不一致: 期望 {} 实际 {}",
+                manifest.file_size,
+                bytes.len()
+            ),
+            json!({"expected_size": manifest.file_size, "actual_size": bytes.len()}),
+        ));
+    }
+    Ok(bytes)
+}
+
+/// 校验 SHA-256，并按本地配置的公钥校验签名
+fn verify(cfg: &UpgradeConfig, manifest: &ReleaseManifest, bytes: &[u8]) -> Result<()> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if !actual.eq_ignore_ascii_case(&manifest.sha256) {
+        return Err(failure(
+            "checksum_mismatch",
+            "发布包 SHA-256 校验失败".to_string(),
+            json!({"expected_sha256": manifest.sha256, "actual_sha256": actual}),
+        ));
+    }
+
+    let public_key = cfg.public_key.as_deref().filter(|k| !k.is_empty());
+    let signature = manifest.signature.as_deref().filter(|s| !s.is_empty());
+    match (public_key, signature) {
+        (Some(key), Some(signature)) => {
+            let b64 = base64::engine::general_purpose::STANDARD;
+            let key: [u8; 32] = b64
+                .decode(key.trim())?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("invalid upgrade.public_key"))?;
+            let key = VerifyingKey::from_bytes(&key)?;
+            let signature = Signature::from_slice(&b64.decode(signature.trim())?)?;
+            key.verify_strict(bytes, &signature).map_err(|_| {
+                failure(
+                    "signature_invalid",
+                    "发布包签名校验失败".to_string(),
+                    json!({"version": manifest.version, "platform": manifest.platform}),
+                )
+            })
+        }
+        _ if cfg.allow_unsigned => {
+            agent_logger::warn("未校验发布包签名（upgrade.allow_unsigned=true）");
+            Ok(())
+        }
+        (None, _) => Err(failure(
+            "signature_invalid",
+            "未配置 upgrade.public_key，拒绝安装发布包".to_string(),
+            json!({"signed": signature.is_some()}),
+        )),
+        (Some(_), None) => Err(failure(
+            "signature_invalid",
+            "发布包未签名，拒绝安装".to_string(),
+            json!({"signed": false}),
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn copy_permissions(from: &Path, to: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(from)
+        .map(|m| m.permissions().mode())
+        .unwrap_or(0o755);
+    let _ = std::fs::set_permissions(to, std::fs::Permissions::from_mode(mode | 0o500));
+}
+
+#[cfg(not(unix))]
+fn copy_permissions(_from: &Path, _to: &Path) {}
+
+/// 试运行新文件的 `--version`，输出需包含目标版本号
+async fn smoke_test(new: &Path, version: &str) -> Result<()> {
+    let output = timeout(
+        Duration::from_secs(10),
+        tokio::process::Command::new(new)
+            .arg("--version")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("smoke test timeout"))?;
+    let output = match output {
+        Ok(o) => o,
+        Err(e) => {
+            return Err(failure(
+                "smoke_test_failed",
+                format!("新版本无法执行: {}", e),
+                json!({"version": version}),
+            ))
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() || !stdout.contains(version) {
+        return Err(failure(
+            "smoke_test_failed",
+            format!("新版本试运行结果与目标版本 {} 不符", version),
+            json!({
+                "version": version,
+                "exit_code": output.status.code(),
+                "stdout": stdout,
+                "stderr": String::from_utf8_lossy(&output.stderr),
+            }),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const PACKAGE: &[u8] = b"agent binary";
+
+    fn manifest(signature: Option<String>) -> ReleaseManifest {
+        ReleaseManifest {
+            version: "1.2.0".to_string(),
+            platform: "linux-x86_64".to_string(),
+            file_size: PACKAGE.len() as i64,
+            sha256: format!("{:x}", Sha256::digest(PACKAGE)),
+            signature,
+            download_url: String::new(),
+        }
+    }
+
+    fn reason(err: anyhow::Error) -> &'static str {
+        err.downcast_ref::<TaskFailure>().unwrap().reason
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let cfg = UpgradeConfig {
+            public_key: Some(b64.encode(key.verifying_key().to_bytes())),
+            ..Default::default()
+        };
+        let signed = manifest(Some(b64.encode(key.sign(PACKAGE).to_bytes())));
+        assert!(verify(&cfg, &signed, PACKAGE).is_ok());
+
+        // 内容被篡改：SHA-256 不符
+        let err = verify(&cfg, &signed, b"tampered binary").unwrap_err();
+        assert_eq!(reason(err), "checksum_mismatch");
+
+        // 摘要同时被替换：签名不符
+        let mut forged = manifest(signed.signature.clone());
+        forged.sha256 = format!("{:x}", Sha256::digest(b"tampered binary"));
+        let err = verify(&cfg, &forged, b"tampered binary").unwrap_err();
+        assert_eq!(reason(err), "signature_invalid");
+
+        let err = verify(&cfg, &manifest(None), PACKAGE).unwrap_err();
+        assert_eq!(reason(err), "signature_invalid");
+    }
+
+    #[test]
+    fn test_verify_unsigned() {
+        let strict = UpgradeConfig::default();
+        let err = verify(&strict, &manifest(None), PACKAGE).unwrap_err();
+        assert_eq!(reason(err), "signature_invalid");
+
+        let lenient = UpgradeConfig {
+            allow_unsigned: true,
+            ..Default::default()
+        };
+        assert!(verify(&lenient, &manifest(None), PACKAGE).is_ok());
+        let err = verify(&lenient, &manifest(None), b"tampered binary").unwrap_err();
+        assert_eq!(reason(err), "checksum_mismatch");
+    }
+}
@@ -3,11 +3,14 @@
 /// 支持命令：DisableHttp / EnableHttp / Shutdown / Restart
 /// - Disable/Enable 切换全局 HTTP 可用标志
 /// - Shutdown 直接退出进程（容器内建议由编排系统重启）
-/// - Restart 返回请求状态，由外部系统决定重启策略
-use crate::{agent_logger, models::TaskDispatchItem, services::AppState};
+/// - Restart 回传结果后以相同参数重新执行自身
+use crate::{
+    agent_logger,
+    models::TaskDispatchItem,
+    services::{self_update, AppState},
+};
 use anyhow::Result;
 
-
 pub async fn execute(state: &AppState, item: &TaskDispatchItem) -> Result<serde_json::Value> {
     let t = item
         .task_content
@@ -34,7 +37,9 @@ pub async fn execute(state: &AppState, item: &TaskDispatchItem) -> Result<serde_
     }
     if t.eq_ignore_ascii_case("Restart") {
         agent_logger::info("收到命令：Restart");
-        return Ok(serde_json::json!({"restart":"requested"}));
+        let exe = std::env::current_exe()?;
+        self_update::schedule_restart(exe, std::time::Duration::from_secs(3));
+        return Ok(serde_json::json!({"restart":"scheduled"}));
     }
     Err(anyhow::anyhow!("unknown command"))
 }
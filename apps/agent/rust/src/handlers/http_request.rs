@@ -2,13 +2,12 @@
 ///
 /// 支持多种请求体：json/form/raw/multipart；可配置重定向策略与 TLS 验证。
 /// 任务内容关键字段：method、url、allow_redirects、verify_tls、headers、query、body_type 等。
-use crate::{models::TaskDispatchItem, services::AppState};
+use crate::{models::TaskDispatchItem, services::AppState, utils::http_util};
 use anyhow::Result;
 use reqwest::Method;
 use serde_json::Value;
 use std::time::Duration;
 
-
 pub async fn execute(state: &AppState, item: &TaskDispatchItem, timeout_sec: u64) -> Result<Value> {
     let method = item
         .task_content
@@ -45,15 +44,7 @@ pub async fn execute(state: &AppState, item: &TaskDispatchItem, timeout_sec: u64
     if !verify_tls {
         builder = builder.danger_accept_invalid_certs(true);
     }
-    if state.cfg.http.proxy_enabled {
-        if let Some(proxy) = &state.cfg.http.proxy_url {
-            if !proxy.is_empty() {
-                if let Ok(pp) = reqwest::Proxy::all(proxy) {
-                    builder = builder.proxy(pp);
-                }
-            }
-        }
-    }
+    builder = http_util::apply_proxy(builder, &state.runtime().http);
     let client = builder
         .redirect(if allow_redirects {
             reqwest::redirect::Policy::limited(10)
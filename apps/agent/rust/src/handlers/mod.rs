@@ -1,5 +1,33 @@
+pub mod agent_upgrade;
 pub mod custom_command;
+// 文件管理处理器沿用原有写法，暂不做 clippy 风格调整
+#[allow(
+    clippy::doc_lazy_continuation,
+    clippy::double_ended_iterator_last,
+    clippy::manual_div_ceil,
+    clippy::identity_op
+)]
 pub mod file_manager;
 pub mod http_request;
 pub mod run_code;
 pub mod shell_exec;
+pub mod terminal;
+
+/// 带诊断数据的任务失败
+///
+/// 处理器返回该错误时，任务结果的 `message` 为 `reason`，`data` 保留诊断信息（如编译输出），
+/// 便于在控制台区分编译失败、运行失败等情况。
+#[derive(Debug)]
+pub struct TaskFailure {
+    pub reason: &'static str,
+    pub detail: String,
+    pub data: serde_json::Value,
+}
+
+impl std::fmt::Display for TaskFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.detail)
+    }
+}
+
+impl std::error::Error for TaskFailure {}
@@ -1,76 +1,769 @@
-/// 动态运行 Java 代码处理器（兼容 Java Agent）
+/// 动态运行代码处理器（支持 Python、Node.js、Bash 与 Java）
 ///
 /// 任务内容示例：
 /// {
-///   "code": "public class Xxx { public static Map runner(Map m){ return m; } }"
+///   "language": "python",
+///   "code": "import json, sys\nargs = json.load(sys.stdin)\nprint(json.dumps({\"sum\": args[\"a\"] + args[\"b\"]}))",
+///   "args": {"a": 1, "b": 2}
 /// }
-/// 实现：将代码写入临时目录，使用 `javac` 编译，随后通过 `java -cp` 反射调用 `runner` 方法。
-/// 注意：容器必须安装 JDK，否则执行会失败。
+/// - `language`：`python` / `node` / `bash` / `java`，缺省为 `java`（兼容 Java Agent）
+/// - `args`：以 JSON 写入标准输入，缺省为 `{}`
+/// - 标准输出整体或最后一个非空行为 JSON 时作为 `result` 返回，其余输出放入 `output`
+///
+/// 每次运行使用独立的临时目录，结束（含超时）后删除。运行前先做语法检查或编译
+/// （`py_compile` / `node --check` / `bash -n` / `javac`），失败时以 `compile_error` 失败并附带诊断输出；
+/// 进程非零退出时以 `runtime_error` 失败。
+/// Java 代码需提供 `runner(Map)` 方法（静态或带无参构造的实例方法），由包装类完成参数解析与结果序列化。
+///
+/// 运行代码等同于执行任意命令，与脚本任务同受本机 `shell_policy` 约束：策略关闭时拒绝执行，
+/// 以默认运行用户运行并应用其资源限制，每个输出流最多保留 `max_output_bytes` 字节；
+/// Unix 下在独立进程组中运行，结束或超时后整个进程组被强制结束。
+/// 注意：运行环境需安装对应的解释器或 JDK。
+use crate::config::ShellPolicyConfig;
+use crate::handlers::shell_exec::{self, RunAs};
+use crate::handlers::TaskFailure;
 use crate::models::TaskDispatchItem;
-use anyhow::Result;
+use crate::services::task_output::OutputStream;
+use crate::services::AppState;
+#[cfg(unix)]
+use crate::utils::sandbox;
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
-use tokio::time::{timeout, Duration};
+use tokio::time::{timeout_at, Duration, Instant};
+
+#[cfg(windows)]
+const PYTHON: &str = "python";
+#[cfg(not(windows))]
+const PYTHON: &str = "python3";
+/// 失败信息中保留的诊断输出长度
+const MAX_DETAIL_CHARS: usize = 2000;
+const JAVA_WRAPPER_CLASS: &str = "__RunnerWrapper";
+
+static JAVA_PACKAGE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*package\s+([\w.]+)\s*;").unwrap());
+static JAVA_PUBLIC_CLASS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\bpublic\s+(?:(?:final|abstract|static)\s+)*class\s+([A-Za-z_$][\w$]*)").unwrap()
+});
+static JAVA_CLASS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bclass\s+([A-Za-z_$][\w$]*)").unwrap());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Language {
+    Python,
+    Node,
+    Bash,
+    Java,
+}
+
+impl Language {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "python" | "python3" | "py" => Some(Self::Python),
+            "node" | "nodejs" | "javascript" | "js" => Some(Self::Node),
+            "bash" | "shell" | "sh" => Some(Self::Bash),
+            "java" => Some(Self::Java),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::Node => "node",
+            Self::Bash => "bash",
+            Self::Java => "java",
+        }
+    }
+}
+
+/// 子进程的运行约束，来自本机 `shell_policy`
+struct Sandbox<'a> {
+    policy: &'a ShellPolicyConfig,
+    record_id: &'a str,
+    run_as: Option<&'a RunAs>,
+    deadline: Instant,
+}
 
+/// 子进程的退出状态与（按上限截断后的）输出
+struct RunOutput {
+    status: ExitStatus,
+    stdout: String,
+    stderr: String,
+    truncated: bool,
+}
+
+/// 单次运行的临时目录，离开作用域时删除
+struct RunDir(PathBuf);
+
+impl RunDir {
+    /// Unix 下独占创建随机名称的 0700 目录，指定运行用户时交给该用户
+    #[cfg(unix)]
+    fn create(_record_id: &str, run_as: Option<&RunAs>) -> Result<Self> {
+        let dir = Self(sandbox::make_private_dir("monihub-code-")?);
+        hand_over(&dir.0, run_as)?;
+        Ok(dir)
+    }
 
-pub async fn execute(item: &TaskDispatchItem, timeout_sec: u64) -> Result<serde_json::Value> {
+    #[cfg(not(unix))]
+    fn create(record_id: &str, _run_as: Option<&RunAs>) -> Result<Self> {
+        let dir = std::env::temp_dir()
+            .join("monihub")
+            .join("code")
+            .join(format!("{}-{}", record_id, uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir)?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for RunDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub async fn execute(
+    state: &AppState,
+    item: &TaskDispatchItem,
+    timeout_sec: u64,
+) -> Result<serde_json::Value> {
+    let policy = &state.cfg.shell_policy;
+    if !policy.enabled {
+        bail!("本机策略已禁用脚本任务");
+    }
     let code = item
         .task_content
         .get("code")
         .and_then(|v| v.as_str())
         .unwrap_or("");
-    let class_name = parse_class_name(code).unwrap_or("RunnerCode").to_string();
-    let base = temp_base();
-    let src_dir = base.join("src");
-    let cls_dir = base.join("classes");
-    fs::create_dir_all(&src_dir)?;
-    fs::create_dir_all(&cls_dir)?;
-    let src_file = src_dir.join(format!("{}.java", class_name));
-    fs::write(&src_file, code)?;
-    let wrapper = src_dir.join("__RunnerWrapper.java");
-    fs::write(&wrapper, wrapper_source(&class_name))?;
-    let fut = async move {
-        let _ = Command::new("javac")
-            .arg("-d")
-            .arg(&cls_dir)
-            .arg(&src_file)
-            .arg(&wrapper)
-            .output()
-            .await?;
-        let out = Command::new("java")
-            .arg("-cp")
-            .arg(&cls_dir)
-            .arg("__RunnerWrapper")
-            .output()
-            .await?;
-        let s = String::from_utf8_lossy(&out.stdout).to_string();
-        Ok(serde_json::from_str::<serde_json::Value>(&s)
-            .unwrap_or(serde_json::json!({"output": s})))
+    if code.trim().is_empty() {
+        return Err(anyhow!("代码为空"));
+    }
+    let language = match item
+        .task_content
+        .get("language")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        Some(name) => Language::parse(name).ok_or_else(|| anyhow!("不支持的语言: {}", name))?,
+        None => Language::Java,
     };
-    let r = timeout(Duration::from_secs(timeout_sec), fut).await;
-    match r {
-        Ok(v) => v,
-        Err(_) => Err(anyhow::anyhow!("timeout")),
+    let args = item
+        .task_content
+        .get("args")
+        .filter(|v| !v.is_null())
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let run_as = shell_exec::default_run_as(policy)?;
+    let dir = RunDir::create(&item.record_id, run_as.as_ref())?;
+    // 检查与运行共用同一截止时间，超时后结束进程组并删除临时目录
+    let sandbox = Sandbox {
+        policy,
+        record_id: &item.record_id,
+        run_as: run_as.as_ref(),
+        deadline: Instant::now() + Duration::from_secs(timeout_sec),
+    };
+    run(language, code, &args, &dir.0, &sandbox).await
+}
+
+async fn run(
+    language: Language,
+    code: &str,
+    args: &Value,
+    dir: &Path,
+    sandbox: &Sandbox<'_>,
+) -> Result<Value> {
+    let (mut check, mut program) = prepare(language, code, dir, sandbox.run_as)?;
+    let checked = output(&mut check, None, sandbox).await?;
+    if !checked.status.success() {
+        let diagnostics = join_output(&checked.stderr, &checked.stdout);
+        return Err(TaskFailure {
+            reason: "compile_error",
+            detail: format!("编译失败: {}", truncate(&diagnostics)),
+            data: json!({"language": language.name(), "diagnostics": diagnostics}),
+        }
+        .into());
     }
+
+    let input = serde_json::to_vec(args)?;
+    let out = output(&mut program, Some(&input), sandbox).await?;
+    let stderr = out.stderr.trim().to_string();
+    if !out.status.success() {
+        return Err(TaskFailure {
+            reason: "runtime_error",
+            detail: format!(
+                "运行失败，退出码 {:?}: {}",
+                out.status.code(),
+                truncate(&stderr)
+            ),
+            data: json!({
+                "language": language.name(),
+                "exit_code": out.status.code(),
+                "output": out.stdout.trim(),
+                "stderr": stderr,
+                "truncated": out.truncated,
+            }),
+        }
+        .into());
+    }
+    let (result, output) = split_result(&out.stdout);
+    Ok(json!({
+        "language": language.name(),
+        "result": result,
+        "output": output,
+        "stderr": stderr,
+        "exit_code": out.status.code(),
+        "truncated": out.truncated,
+    }))
 }
 
-fn parse_class_name(src: &str) -> Option<&str> {
-    let t = src.split_whitespace().collect::<Vec<_>>();
-    let mut i = 0;
-    while i + 1 < t.len() {
-        if t[i] == "class" {
-            return Some(t[i + 1]);
+/// 写入源文件，返回检查（编译）命令与运行命令
+fn prepare(
+    language: Language,
+    code: &str,
+    dir: &Path,
+    run_as: Option<&RunAs>,
+) -> Result<(Command, Command)> {
+    let commands = match language {
+        Language::Python => {
+            let file = dir.join("main.py");
+            fs::write(&file, code)?;
+            let mut check = command(PYTHON, dir);
+            check.arg("-m").arg("py_compile").arg(&file);
+            let mut run = command(PYTHON, dir);
+            run.arg(&file);
+            (check, run)
+        }
+        Language::Node => {
+            let file = dir.join("main.js");
+            fs::write(&file, code)?;
+            let mut check = command("node", dir);
+            check.arg("--check").arg(&file);
+            let mut run = command("node", dir);
+            run.arg(&file);
+            (check, run)
         }
-        i += 1;
+        Language::Bash => {
+            let file = dir.join("main.sh");
+            fs::write(&file, code)?;
+            let mut check = command("bash", dir);
+            check.arg("-n").arg(&file);
+            let mut run = command("bash", dir);
+            run.arg(&file);
+            (check, run)
+        }
+        Language::Java => {
+            let (simple_name, class_name) = java_class_name(code);
+            let src_dir = dir.join("src");
+            let cls_dir = dir.join("classes");
+            fs::create_dir_all(&src_dir)?;
+            fs::create_dir_all(&cls_dir)?;
+            hand_over(&cls_dir, run_as)?;
+            let src_file = src_dir.join(format!("{}.java", simple_name));
+            let wrapper = src_dir.join(format!("{}.java", JAVA_WRAPPER_CLASS));
+            fs::write(&src_file, code)?;
+            fs::write(&wrapper, JAVA_WRAPPER)?;
+            let mut check = command("javac", dir);
+            check
+                .arg("-encoding")
+                .arg("UTF-8")
+                .arg("-nowarn")
+                .arg("-d")
+                .arg(&cls_dir)
+                .arg(&src_file)
+                .arg(&wrapper);
+            let mut run = command("java", dir);
+            run.arg("-Dfile.encoding=UTF-8")
+                .arg("-cp")
+                .arg(&cls_dir)
+                .arg(JAVA_WRAPPER_CLASS)
+                .arg(class_name);
+            (check, run)
+        }
+    };
+    Ok(commands)
+}
+
+fn command(program: &str, dir: &Path) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    cmd
+}
+
+/// 指定运行用户时把运行目录下需要写入的目录交给该用户
+fn hand_over(path: &Path, run_as: Option<&RunAs>) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(user) = run_as {
+        sandbox::chown(path, user.uid, user.gid)?;
     }
-    None
+    #[cfg(not(unix))]
+    let _ = (path, run_as);
+    Ok(())
 }
 
-fn temp_base() -> PathBuf {
-    std::env::temp_dir().join("monihub").join("code")
+/// 在沙箱中运行命令并收集输出，`input` 写入标准输入
+///
+/// 每个输出流超过 `max_output_bytes` 的部分丢弃；结束或到达截止时间后强制结束整个进程组。
+async fn output(
+    cmd: &mut Command,
+    input: Option<&[u8]>,
+    sandbox: &Sandbox<'_>,
+) -> Result<RunOutput> {
+    if input.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+    if let Some(user) = sandbox.run_as {
+        cmd.env("HOME", &user.home)
+            .env("USER", &user.name)
+            .env("LOGNAME", &user.name);
+    }
+    #[cfg(unix)]
+    let cgroup = shell_exec::setup_sandbox(
+        cmd,
+        sandbox.policy,
+        sandbox.record_id,
+        sandbox.policy.limits,
+        sandbox.run_as,
+    );
+    let program = cmd.as_std().get_program().to_string_lossy().to_string();
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            #[cfg(unix)]
+            if let Some(cgroup) = cgroup {
+                cgroup.remove().await;
+            }
+            return Err(match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    anyhow!("未找到 {}，请确认已安装并在 PATH 中", program)
+                }
+                _ => anyhow!("启动 {} 失败: {}", program, e),
+            });
+        }
+    };
+    let pid = child.id();
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let write = async move {
+        if let (Some(mut stdin), Some(input)) = (stdin, input) {
+            // 程序不读取标准输入时写入可能失败，忽略
+            let _ = stdin.write_all(input).await;
+        }
+    };
+    let limit = sandbox.policy.max_output_bytes;
+    let fut = async {
+        let (_, (stdout, stdout_omitted), (stderr, stderr_omitted), status) = tokio::join!(
+            write,
+            shell_exec::read_stream(stdout, OutputStream::Stdout, None, limit),
+            shell_exec::read_stream(stderr, OutputStream::Stderr, None, limit),
+            child.wait()
+        );
+        anyhow::Ok(RunOutput {
+            status: status?,
+            stdout,
+            stderr,
+            truncated: stdout_omitted + stderr_omitted > 0,
+        })
+    };
+    let r = timeout_at(sandbox.deadline, fut).await;
+    let _ = child.start_kill();
+    #[cfg(unix)]
+    {
+        if let Some(pid) = pid {
+            sandbox::kill_group(pid);
+        }
+        if let Some(cgroup) = cgroup {
+            cgroup.remove().await;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+    r.unwrap_or_else(|_| Err(anyhow!("timeout")))
+}
+
+/// 解析 Java 主类：返回（源文件名用的类名，含包名的全限定类名）
+fn java_class_name(src: &str) -> (String, String) {
+    let simple = JAVA_PUBLIC_CLASS
+        .captures(src)
+        .or_else(|| JAVA_CLASS.captures(src))
+        .map(|c| c[1].to_string())
+        .unwrap_or_else(|| "RunnerCode".to_string());
+    let qualified = match JAVA_PACKAGE.captures(src) {
+        Some(c) => format!("{}.{}", &c[1], simple),
+        None => simple.clone(),
+    };
+    (simple, qualified)
 }
 
-fn wrapper_source(target: &str) -> String {
-    format!("public class __RunnerWrapper {{ public static void main(String[] args) throws Exception {{ Class<?> c = Class.forName(\"{}\"); java.lang.reflect.Method m = c.getMethod(\"runner\", java.util.Map.class); java.util.Map res = (java.util.Map) m.invoke(null, new java.util.HashMap()); System.out.print(String.valueOf(res)); }} }}", target)
+/// 拆分标准输出：整体或最后一个非空行为 JSON 时作为结果，其余为普通输出
+fn split_result(stdout: &str) -> (Value, String) {
+    let trimmed = stdout.trim();
+    if trimmed.is_empty() {
+        return (Value::Null, String::new());
+    }
+    if let Ok(v) = serde_json::from_str::<Value>(trimmed) {
+        return (v, String::new());
+    }
+    if let Some(pos) = trimmed.rfind('\n') {
+        if let Ok(v) = serde_json::from_str::<Value>(trimmed[pos + 1..].trim()) {
+            return (v, trimmed[..pos].trim_end().to_string());
+        }
+    }
+    (Value::Null, trimmed.to_string())
+}
+
+fn join_output(first: &str, second: &str) -> String {
+    match (first.trim(), second.trim()) {
+        (a, "") => a.to_string(),
+        ("", b) => b.to_string(),
+        (a, b) => format!("{}\n{}", a, b),
+    }
+}
+
+fn truncate(s: &str) -> String {
+    match s.char_indices().nth(MAX_DETAIL_CHARS) {
+        Some((idx, _)) => format!("{}...", &s[..idx]),
+        None => s.to_string(),
+    }
+}
+
+/// Java 包装类：从标准输入读取 JSON 参数，调用目标类的 `runner(Map)`，将返回值序列化为 JSON 输出
+const JAVA_WRAPPER: &str = r##"import java.io.ByteArrayOutputStream;
+import java.lang.reflect.Array;
+import java.lang.reflect.InvocationTargetException;
+import java.lang.reflect.Method;
+import java.lang.reflect.Modifier;
+import java.nio.charset.StandardCharsets;
+import java.util.ArrayList;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Map;
+
+public class __RunnerWrapper {
+    @SuppressWarnings("unchecked")
+    public static void main(String[] argv) throws Exception {
+        ByteArrayOutputStream buf = new ByteArrayOutputStream();
+        byte[] chunk = new byte[8192];
+        int n;
+        while ((n = System.in.read(chunk)) > 0) {
+            buf.write(chunk, 0, n);
+        }
+        String input = new String(buf.toByteArray(), StandardCharsets.UTF_8).trim();
+        Object parsed = input.isEmpty() ? null : new Parser(input).parse();
+        Map<String, Object> args = parsed instanceof Map
+                ? (Map<String, Object>) parsed
+                : new LinkedHashMap<String, Object>();
+
+        Class<?> c = Class.forName(argv[0]);
+        Method m = c.getMethod("runner", Map.class);
+        Object target = Modifier.isStatic(m.getModifiers()) ? null : c.getDeclaredConstructor().newInstance();
+        Object result;
+        try {
+            result = m.invoke(target, args);
+        } catch (InvocationTargetException e) {
+            e.getCause().printStackTrace();
+            System.exit(1);
+            return;
+        }
+        StringBuilder sb = new StringBuilder();
+        write(sb, result);
+        System.out.print("\n" + sb + "\n");
+        System.out.flush();
+    }
+
+    static void write(StringBuilder sb, Object o) {
+        if (o == null) {
+            sb.append("null");
+        } else if (o instanceof String || o instanceof Character) {
+            quote(sb, o.toString());
+        } else if (o instanceof Boolean) {
+            sb.append(o);
+        } else if (o instanceof Number) {
+            double d = ((Number) o).doubleValue();
+            sb.append(Double.isNaN(d) || Double.isInfinite(d) ? "null" : o.toString());
+        } else if (o instanceof Map) {
+            sb.append('{');
+            boolean first = true;
+            for (Map.Entry<?, ?> e : ((Map<?, ?>) o).entrySet()) {
+                if (!first) sb.append(',');
+                first = false;
+                quote(sb, String.valueOf(e.getKey()));
+                sb.append(':');
+                write(sb, e.getValue());
+            }
+            sb.append('}');
+        } else if (o instanceof Iterable) {
+            sb.append('[');
+            boolean first = true;
+            for (Object v : (Iterable<?>) o) {
+                if (!first) sb.append(',');
+                first = false;
+                write(sb, v);
+            }
+            sb.append(']');
+        } else if (o.getClass().isArray()) {
+            sb.append('[');
+            for (int i = 0; i < Array.getLength(o); i++) {
+                if (i > 0) sb.append(',');
+                write(sb, Array.get(o, i));
+            }
+            sb.append(']');
+        } else {
+            quote(sb, o.toString());
+        }
+    }
+
+    static void quote(StringBuilder sb, String s) {
+        sb.append('"');
+        for (int i = 0; i < s.length(); i++) {
+            char ch = s.charAt(i);
+            switch (ch) {
+                case '"': sb.append("\\\""); break;
+                case '\\': sb.append("\\\\"); break;
+                case '\n': sb.append("\\n"); break;
+                case '\r': sb.append("\\r"); break;
+                case '\t': sb.append("\\t"); break;
+                default:
+                    if (ch < 0x20) sb.append(String.format("\\u%04x", (int) ch));
+                    else sb.append(ch);
+            }
+        }
+        sb.append('"');
+    }
+
+    static final class Parser {
+        private final String s;
+        private int i;
+
+        Parser(String s) {
+            this.s = s;
+        }
+
+        Object parse() {
+            Object v = value();
+            ws();
+            if (i != s.length()) throw error();
+            return v;
+        }
+
+        private IllegalArgumentException error() {
+            return new IllegalArgumentException("invalid JSON at " + i);
+        }
+
+        private void ws() {
+            while (i < s.length() && Character.isWhitespace(s.charAt(i))) i++;
+        }
+
+        private boolean accept(char c) {
+            if (i < s.length() && s.charAt(i) == c) {
+                i++;
+                return true;
+            }
+            return false;
+        }
+
+        private void expect(char c) {
+            if (!accept(c)) throw error();
+        }
+
+        private Object value() {
+            ws();
+            if (i >= s.length()) throw error();
+            char c = s.charAt(i);
+            if (c == '{') {
+                i++;
+                Map<String, Object> m = new LinkedHashMap<String, Object>();
+                ws();
+                if (accept('}')) return m;
+                do {
+                    ws();
+                    String k = string();
+                    ws();
+                    expect(':');
+                    m.put(k, value());
+                    ws();
+                } while (accept(','));
+                expect('}');
+                return m;
+            }
+            if (c == '[') {
+                i++;
+                List<Object> l = new ArrayList<Object>();
+                ws();
+                if (accept(']')) return l;
+                do {
+                    l.add(value());
+                    ws();
+                } while (accept(','));
+                expect(']');
+                return l;
+            }
+            if (c == '"') return string();
+            if (s.startsWith("true", i)) { i += 4; return Boolean.TRUE; }
+            if (s.startsWith("false", i)) { i += 5; return Boolean.FALSE; }
+            if (s.startsWith("null", i)) { i += 4; return null; }
+            int start = i;
+            while (i < s.length() && "+-0123456789.eE".indexOf(s.charAt(i)) >= 0) i++;
+            if (start == i) throw error();
+            String num = s.substring(start, i);
+            if (num.contains(".") || num.contains("e") || num.contains("E")) return Double.valueOf(num);
+            try {
+                return Long.valueOf(num);
+            } catch (NumberFormatException e) {
+                return new java.math.BigInteger(num);
+            }
+        }
+
+        private String string() {
+            expect('"');
+            StringBuilder sb = new StringBuilder();
+            while (i < s.length()) {
+                char c = s.charAt(i++);
+                if (c == '"') return sb.toString();
+                if (c != '\\') {
+                    sb.append(c);
+                    continue;
+                }
+                if (i >= s.length()) break;
+                char e = s.charAt(i++);
+                switch (e) {
+                    case 'n': sb.append('\n'); break;
+                    case 't': sb.append('\t'); break;
+                    case 'r': sb.append('\r'); break;
+                    case 'b': sb.append('\b'); break;
+                    case 'f': sb.append('\f'); break;
+                    case 'u':
+                        if (i + 4 > s.length()) throw error();
+                        sb.append((char) Integer.parseInt(s.substring(i, i + 4), 16));
+                        i += 4;
+                        break;
+                    default: sb.append(e);
+                }
+            }
+            throw error();
+        }
+    }
+}
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_parse() {
+        assert_eq!(Language::parse("Python3"), Some(Language::Python));
+        assert_eq!(Language::parse("js"), Some(Language::Node));
+        assert_eq!(Language::parse("sh"), Some(Language::Bash));
+        assert_eq!(Language::parse("ruby"), None);
+    }
+
+    #[test]
+    fn test_split_result() {
+        assert_eq!(split_result("  \n"), (Value::Null, String::new()));
+        assert_eq!(
+            split_result("{\"a\": 1}\n"),
+            (json!({"a": 1}), String::new())
+        );
+        assert_eq!(
+            split_result("log 1\nlog 2\n[1, 2]\n"),
+            (json!([1, 2]), "log 1\nlog 2".to_string())
+        );
+        assert_eq!(
+            split_result("done\nnot json"),
+            (Value::Null, "done\nnot json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_java_class_name() {
+        let src = "package com.example.demo;\n\nclass Helper {}\npublic final class Job {}\n";
+        assert_eq!(
+            java_class_name(src),
+            ("Job".to_string(), "com.example.demo.Job".to_string())
+        );
+        assert_eq!(
+            java_class_name("class Task { }"),
+            ("Task".to_string(), "Task".to_string())
+        );
+        assert_eq!(
+            java_class_name("// no class"),
+            ("RunnerCode".to_string(), "RunnerCode".to_string())
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let short = "中".repeat(MAX_DETAIL_CHARS);
+        assert_eq!(truncate(&short), short);
+        let long = "中".repeat(MAX_DETAIL_CHARS + 1);
+        assert_eq!(truncate(&long), format!("{}...", short));
+    }
+
+    #[cfg(unix)]
+    fn sandbox(policy: &ShellPolicyConfig, timeout_ms: u64) -> Sandbox<'_> {
+        Sandbox {
+            policy,
+            record_id: "test",
+            run_as: None,
+            deadline: Instant::now() + Duration::from_millis(timeout_ms),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_capped_by_policy() {
+        let policy = ShellPolicyConfig {
+            max_output_bytes: 16,
+            ..Default::default()
+        };
+        let dir = RunDir::create("test", None).unwrap();
+        let code = "for i in $(seq 1 100); do echo line-$i; done\n";
+        let out = run(
+            Language::Bash,
+            code,
+            &json!({}),
+            &dir.0,
+            &sandbox(&policy, 10_000),
+        )
+        .await
+        .unwrap();
+        assert_eq!(out["truncated"], json!(true));
+        assert!(out["output"]
+            .as_str()
+            .unwrap()
+            .starts_with("line-1\nline-2\n"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let policy = ShellPolicyConfig::default();
+        let dir = RunDir::create("test", None).unwrap();
+        let marker = dir.0.join("marker");
+        let code = format!("(sleep 2; touch {}) &\nwait\n", marker.display());
+        let err = run(
+            Language::Bash,
+            &code,
+            &json!({}),
+            &dir.0,
+            &sandbox(&policy, 500),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "timeout");
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert!(!marker.exists());
+    }
 }
@@ -1,14 +1,91 @@
+/// 脚本任务执行
+///
+/// 任务内容除 `script`/`workdir` 外支持以下可选项，均受本机 `shell_policy` 约束：
+/// - `run_as_user` / `run_as_group`：运行用户与用户组（仅 Unix，须在允许列表内）
+/// - `env`：追加的环境变量，值为 null 时移除；`env_mode` 为 `clear` 时不继承 Agent 环境
+/// - `limits`：资源限制，与本机上限取较小值；Linux 下内存、CPU 配额与进程数优先使用 cgroup v2
+/// - `max_output_bytes`：结果中每个输出流保留的字节数，超出部分丢弃并附加截断提示；
+///   分段上报的完整输出不受此限制
+///
+/// Unix 下脚本在独立进程组中运行，超时后整个进程组（含残留的后台进程）被强制结束；
+/// 正常结束时保留脚本启动的后台进程（如 `nohup app &`）。
+use crate::agent_logger;
+use crate::config::{ResourceLimits, ShellPolicyConfig};
 use crate::models::TaskDispatchItem;
-use anyhow::Result;
+use crate::services::task_output::{OutputSender, OutputStream, OutputUploader};
+use crate::services::AppState;
+#[cfg(unix)]
+pub(crate) use crate::utils::sandbox::UserInfo as RunAs;
+#[cfg(unix)]
+use crate::utils::sandbox::{self, TaskCgroup};
+use anyhow::{anyhow, bail, Result};
 use encoding_rs::GBK;
+use serde::Deserialize;
+use serde_json::{Map, Value};
 use std::fs;
 #[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 
-pub async fn execute(item: &TaskDispatchItem, timeout_sec: u64) -> Result<serde_json::Value> {
+/// 清空环境变量时使用的默认 PATH
+#[cfg(unix)]
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+/// 单次读取的最大行长度，避免无换行的超长输出占满内存
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+#[cfg(not(unix))]
+#[allow(dead_code)]
+pub(crate) struct RunAs {
+    pub name: String,
+    pub home: String,
+}
+
+/// 任务内容中的执行选项，字段缺省或为 null 时使用默认值
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ShellOptions {
+    run_as_user: Option<String>,
+    run_as_group: Option<String>,
+    env: Option<Map<String, Value>>,
+    env_mode: Option<EnvMode>,
+    limits: Option<ResourceLimits>,
+    max_output_bytes: Option<usize>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum EnvMode {
+    /// 继承 Agent 环境变量
+    #[default]
+    Inherit,
+    /// 仅保留基础变量（PATH/HOME/USER 等）与任务指定的变量
+    Clear,
+}
+
+pub async fn execute(
+    state: &AppState,
+    item: &TaskDispatchItem,
+    timeout_sec: u64,
+) -> Result<serde_json::Value> {
+    let policy = &state.cfg.shell_policy;
+    if !policy.enabled {
+        bail!("本机策略已禁用脚本任务");
+    }
+    let opts: ShellOptions = serde_json::from_value(item.task_content.clone())
+        .map_err(|e| anyhow!("任务参数错误: {}", e))?;
+    let run_as = resolve_run_as(policy, &opts)?;
+    let limits = opts.limits.unwrap_or_default().clamp_by(policy.limits);
+    let env_mode = opts.env_mode.unwrap_or_default();
+    let env = opts.env.unwrap_or_default();
+    let max_output = opts
+        .max_output_bytes
+        .unwrap_or(policy.max_output_bytes)
+        .min(policy.max_output_bytes);
+
     let mut script = item
         .task_content
         .get("script")
@@ -27,8 +104,15 @@ pub async fn execute(item: &TaskDispatchItem, timeout_sec: u64) -> Result<serde_
         .and_then(|v| v.as_str())
         .map(|s| s.trim())
         .filter(|s| !s.is_empty());
+    if let Some(wd) = workdir_opt {
+        check_workdir(policy, Path::new(wd))?;
+    }
+    // 指定运行用户且未指定工作目录时，使用仅该用户可访问的临时目录
+    let private_dir = run_as.is_some() && workdir_opt.is_none();
     let base_dir: PathBuf = if let Some(wd) = workdir_opt {
         PathBuf::from(wd)
+    } else if private_dir {
+        private_task_dir(run_as.as_ref())?
     } else {
         task_dir
     };
@@ -44,17 +128,15 @@ pub async fn execute(item: &TaskDispatchItem, timeout_sec: u64) -> Result<serde_
         script = format!("@echo off\r\n{}", script);
     }
 
-    if cfg!(target_os = "windows") {
+    let written = if cfg!(target_os = "windows") {
         let (bytes, _, _) = GBK.encode(script.as_str());
-        fs::write(&script_path, bytes.as_ref())?;
+        write_script(&script_path, bytes.as_ref(), run_as.as_ref())
     } else {
-        fs::write(&script_path, script.as_bytes())?;
-        #[cfg(unix)]
-        {
-            let mut perms = fs::metadata(&script_path)?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&script_path, perms)?;
-        }
+        write_script(&script_path, script.as_bytes(), run_as.as_ref())
+    };
+    if let Err(e) = written {
+        cleanup(&script_path, private_dir.then_some(base_dir.as_path()));
+        return Err(e);
     }
 
     let mut cmd = if cfg!(target_os = "windows") {
@@ -66,31 +148,426 @@ pub async fn execute(item: &TaskDispatchItem, timeout_sec: u64) -> Result<serde_
         c.arg(&script_path);
         c
     };
-    cmd.current_dir(&base_dir);
+    cmd.current_dir(&base_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    apply_env(&mut cmd, env_mode, &env, run_as.as_ref());
+    #[cfg(unix)]
+    let cgroup = setup_sandbox(&mut cmd, policy, &item.record_id, limits, run_as.as_ref());
+    #[cfg(unix)]
+    let cgroup_used = cgroup.is_some();
+    #[cfg(not(unix))]
+    let cgroup_used = false;
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            #[cfg(unix)]
+            if let Some(cgroup) = cgroup {
+                cgroup.remove().await;
+            }
+            cleanup(&script_path, private_dir.then_some(base_dir.as_path()));
+            return Err(e.into());
+        }
+    };
+    let pid = child.id();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    // 执行过程中按行分段上报输出
+    let uploader = state
+        .cfg
+        .task
+        .stream_output
+        .then(|| OutputUploader::start(state, &item.record_id, item.retry_attempt));
+    let sender = uploader.as_ref().map(|u| u.sender());
+
+    let fut = async {
+        let (stdout_s, stderr_s, status) = tokio::join!(
+            read_stream(stdout, OutputStream::Stdout, sender.clone(), max_output),
+            read_stream(stderr, OutputStream::Stderr, sender.clone(), max_output),
+            child.wait()
+        );
+        anyhow::Ok((stdout_s, stderr_s, status?))
+    };
+    let r = timeout(Duration::from_secs(timeout_sec), fut).await;
+    let timed_out = r.is_err();
+    if timed_out {
+        // 超时时脚本进程尚未回收，进程组号不会被复用
+        #[cfg(unix)]
+        if let Some(pid) = pid {
+            sandbox::kill_group(pid);
+        }
+        let _ = child.start_kill();
+    }
+    #[cfg(unix)]
+    if let Some(cgroup) = cgroup {
+        if timed_out {
+            cgroup.remove().await;
+        } else {
+            cgroup.release();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+    drop(sender);
+    let summary = match uploader {
+        Some(uploader) => Some(uploader.finish().await),
+        None => None,
+    };
+    cleanup(&script_path, private_dir.then_some(base_dir.as_path()));
 
-    let fut = async move {
-        let out = cmd.output().await?;
-        let decode = |buf: &[u8]| -> String {
-            if cfg!(target_os = "windows") {
-                GBK.decode(buf).0.into_owned()
+    let ((stdout_s, stdout_omitted), (stderr_s, stderr_omitted), status) =
+        r.unwrap_or_else(|_| Err(anyhow::anyhow!("timeout")))?;
+    let sandbox_info = serde_json::json!({
+        "user": run_as.as_ref().map(|u| u.name.as_str()),
+        "env_mode": if env_mode == EnvMode::Clear { "clear" } else { "inherit" },
+        "cgroup": cgroup_used,
+        "limits": limits,
+    });
+    let mut result = match summary {
+        // 输出已完整上报，结果中只引用完整输出
+        Some(summary) if summary.complete && summary.last_seq.is_some() => serde_json::json!({
+            "status": status.code(),
+            "output_ref": {
+                "record_id": item.record_id,
+                "last_seq": summary.last_seq,
+                "bytes": summary.bytes,
+            },
+            "truncated": false,
+        }),
+        _ => {
+            let output = if stdout_s.trim().is_empty() {
+                stderr_s
             } else {
-                match String::from_utf8(buf.to_vec()) {
-                    Ok(txt) => txt,
-                    Err(_) => String::from_utf8_lossy(buf).to_string(),
+                stdout_s
+            };
+            serde_json::json!({
+                "output": output.trim(),
+                "status": status.code(),
+                "truncated": stdout_omitted + stderr_omitted > 0,
+            })
+        }
+    };
+    if let Some(obj) = result.as_object_mut() {
+        obj.insert("sandbox".into(), sandbox_info);
+    }
+    Ok(result)
+}
+
+/// 创建仅运行用户可访问的临时目录：随机名称独占创建（0700），再通过句柄修改属主
+#[cfg(unix)]
+fn private_task_dir(run_as: Option<&RunAs>) -> Result<PathBuf> {
+    let dir = sandbox::make_private_dir("monihub-task-")?;
+    if let Some(user) = run_as {
+        if let Err(e) = sandbox::chown(&dir, user.uid, user.gid) {
+            let _ = fs::remove_dir(&dir);
+            return Err(e.into());
+        }
+    }
+    Ok(dir)
+}
+
+#[cfg(not(unix))]
+fn private_task_dir(_run_as: Option<&RunAs>) -> Result<PathBuf> {
+    bail!("当前系统不支持指定运行用户");
+}
+
+/// 写入脚本：删除同名文件后独占创建，不跟随预先放置的符号链接，权限与属主通过句柄设置
+#[cfg(unix)]
+fn write_script(path: &Path, content: &[u8], run_as: Option<&RunAs>) -> Result<()> {
+    use std::io::Write;
+    let _ = fs::remove_file(path);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o755)
+        .open(path)?;
+    file.write_all(content)?;
+    file.set_permissions(fs::Permissions::from_mode(0o755))?;
+    if let Some(user) = run_as {
+        std::os::unix::fs::fchown(&file, Some(user.uid), Some(user.gid))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_script(path: &Path, content: &[u8], _run_as: Option<&RunAs>) -> Result<()> {
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn cleanup(script_path: &Path, private_dir: Option<&Path>) {
+    let _ = fs::remove_file(script_path);
+    if let Some(dir) = private_dir {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+/// 校验工作目录是否在允许范围内
+fn check_workdir(policy: &ShellPolicyConfig, workdir: &Path) -> Result<()> {
+    if policy.allowed_workdirs.is_empty() {
+        return Ok(());
+    }
+    let allowed = !workdir.components().any(|c| c == Component::ParentDir)
+        && policy
+            .allowed_workdirs
+            .iter()
+            .any(|prefix| workdir.starts_with(prefix));
+    if !allowed {
+        bail!("工作目录不在允许范围内: {}", workdir.display());
+    }
+    Ok(())
+}
+
+/// 本机策略的默认运行用户，供不能指定运行用户的任务（如 run_code）使用
+pub(crate) fn default_run_as(policy: &ShellPolicyConfig) -> Result<Option<RunAs>> {
+    resolve_run_as(policy, &ShellOptions::default())
+}
+
+/// 解析运行身份：任务指定的用户须在允许列表内，未指定时使用本机默认用户
+#[cfg(unix)]
+fn resolve_run_as(policy: &ShellPolicyConfig, opts: &ShellOptions) -> Result<Option<RunAs>> {
+    let requested = opts
+        .run_as_user
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    if let Some(user) = requested {
+        if !policy.allowed_users.iter().any(|u| u == user) {
+            bail!("运行用户不在允许列表内: {}", user);
+        }
+    }
+    let group = opts
+        .run_as_group
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let default_user = policy.default_user.as_deref().filter(|s| !s.is_empty());
+    let Some(name) = requested.or(default_user) else {
+        if group.is_some() {
+            bail!("指定运行用户组时必须同时指定运行用户");
+        }
+        return Ok(None);
+    };
+    let user = sandbox::lookup_user(name)?;
+    let gid = match group {
+        Some(group) => {
+            if !policy.allowed_groups.iter().any(|g| g == group) {
+                bail!("运行用户组不在允许列表内: {}", group);
+            }
+            sandbox::lookup_group(group)?
+        }
+        None => user.gid,
+    };
+    Ok(Some(RunAs { gid, ..user }))
+}
+
+#[cfg(not(unix))]
+fn resolve_run_as(policy: &ShellPolicyConfig, opts: &ShellOptions) -> Result<Option<RunAs>> {
+    let requested = [
+        opts.run_as_user.as_deref(),
+        opts.run_as_group.as_deref(),
+        policy.default_user.as_deref(),
+    ];
+    if requested.iter().flatten().any(|s| !s.trim().is_empty()) {
+        bail!("当前系统不支持指定运行用户");
+    }
+    Ok(None)
+}
+
+/// 设置子进程环境变量
+fn apply_env(cmd: &mut Command, mode: EnvMode, env: &Map<String, Value>, run_as: Option<&RunAs>) {
+    if mode == EnvMode::Clear {
+        cmd.env_clear();
+        #[cfg(unix)]
+        cmd.env("PATH", DEFAULT_PATH);
+        #[cfg(not(unix))]
+        for key in ["SystemRoot", "ComSpec", "PATH", "PATHEXT", "TEMP", "TMP"] {
+            if let Ok(v) = std::env::var(key) {
+                cmd.env(key, v);
+            }
+        }
+    }
+    if let Some(user) = run_as {
+        cmd.env("HOME", &user.home)
+            .env("USER", &user.name)
+            .env("LOGNAME", &user.name);
+    } else if mode == EnvMode::Clear {
+        for key in ["HOME", "USER", "LOGNAME"] {
+            if let Ok(v) = std::env::var(key) {
+                cmd.env(key, v);
+            }
+        }
+    }
+    for (key, value) in env {
+        match value {
+            Value::Null => {
+                cmd.env_remove(key);
+            }
+            Value::String(s) => {
+                cmd.env(key, s);
+            }
+            other => {
+                cmd.env(key, other.to_string());
+            }
+        }
+    }
+}
+
+/// 独立进程组、资源限制与降权；需要 cgroup 时返回任务控制组，创建失败回退为 rlimit
+#[cfg(unix)]
+pub(crate) fn setup_sandbox(
+    cmd: &mut Command,
+    policy: &ShellPolicyConfig,
+    record_id: &str,
+    limits: ResourceLimits,
+    run_as: Option<&RunAs>,
+) -> Option<TaskCgroup> {
+    cmd.process_group(0);
+    let cgroup = if cfg!(target_os = "linux") && policy.use_cgroup && TaskCgroup::wanted(&limits) {
+        match TaskCgroup::create(
+            &policy.cgroup_parent,
+            &format!("task-{}", record_id),
+            &limits,
+        ) {
+            Ok(cgroup) => Some(cgroup),
+            Err(e) => {
+                agent_logger::warn(&format!("创建任务 cgroup 失败，改用 rlimit 限制: {}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let procs_fd = cgroup.as_ref().map(|c| c.procs_fd());
+    let credentials = run_as.map(|u| (u.uid, u.gid));
+    // SAFETY: 闭包在 fork 后的子进程中执行，prepare_child 仅调用异步信号安全的系统调用
+    unsafe {
+        cmd.pre_exec(move || sandbox::prepare_child(procs_fd, &limits, credentials));
+    }
+    cgroup
+}
+
+fn decode(buf: &[u8]) -> String {
+    if cfg!(target_os = "windows") {
+        GBK.decode(buf).0.into_owned()
+    } else {
+        match String::from_utf8(buf.to_vec()) {
+            Ok(txt) => txt,
+            Err(_) => String::from_utf8_lossy(buf).to_string(),
+        }
+    }
+}
+
+/// 按行读取输出流，转发给上报器并返回内容与省略的字节数
+///
+/// 每行都转发给上报器，服务端保存完整输出；返回的内容仅保留前 `limit` 字节，
+/// 之后继续读取但不再缓存（避免子进程写满管道阻塞），末尾附加截断提示。
+pub(crate) async fn read_stream<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    stream: OutputStream,
+    sender: Option<OutputSender>,
+    limit: usize,
+) -> (String, u64) {
+    let Some(reader) = reader else {
+        return (String::new(), 0);
+    };
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    let mut all = String::new();
+    let mut kept = 0usize;
+    let mut omitted = 0u64;
+    loop {
+        buf.clear();
+        match (&mut reader)
+            .take(MAX_LINE_BYTES)
+            .read_until(b'\n', &mut buf)
+            .await
+        {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let keep = omitted == 0 && kept + n <= limit;
+                if !keep && sender.is_none() {
+                    omitted += n as u64;
+                    continue;
+                }
+                let line = decode(&buf);
+                if keep {
+                    kept += n;
+                    all.push_str(&line);
+                } else {
+                    omitted += n as u64;
+                }
+                if let Some(sender) = &sender {
+                    sender.send(stream, line).await;
                 }
             }
+        }
+    }
+    if omitted > 0 {
+        all.push_str(&format!("\n[输出已截断，省略 {} 字节]\n", omitted));
+    }
+    (all, omitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_clamped_by_policy() {
+        let policy = ShellPolicyConfig {
+            limits: ResourceLimits {
+                memory_mb: Some(512),
+                cpu_seconds: Some(60),
+                ..Default::default()
+            },
+            ..Default::default()
         };
-        let stdout_s = decode(&out.stdout);
-        let stderr_s = decode(&out.stderr);
-        let mut output = if stdout_s.trim().is_empty() {
-            stderr_s
-        } else {
-            stdout_s
+        let task = ResourceLimits {
+            memory_mb: Some(2048),
+            cpu_seconds: Some(10),
+            max_processes: Some(32),
+            ..Default::default()
         };
-        output = output.trim().to_string();
-        let _ = fs::remove_file(&script_path);
-        Ok(serde_json::json!({"output": output, "status": out.status.code()}))
-    };
-    let r = timeout(Duration::from_secs(timeout_sec), fut).await;
-    r.unwrap_or_else(|_| Err(anyhow::anyhow!("timeout")))
+        let limits = task.clamp_by(policy.limits);
+        assert_eq!(limits.memory_mb, Some(512));
+        assert_eq!(limits.cpu_seconds, Some(10));
+        assert_eq!(limits.max_processes, Some(32));
+        assert_eq!(limits.max_open_files, None);
+
+        // 任务未申请时使用策略默认值
+        let limits = ResourceLimits::default().clamp_by(policy.limits);
+        assert_eq!(limits.memory_mb, Some(512));
+        assert_eq!(limits.cpu_seconds, Some(60));
+    }
+
+    #[test]
+    fn test_check_workdir() {
+        let mut policy = ShellPolicyConfig::default();
+        assert!(check_workdir(&policy, Path::new("/etc")).is_ok());
+
+        policy.allowed_workdirs = vec!["/opt/app".to_string()];
+        assert!(check_workdir(&policy, Path::new("/opt/app")).is_ok());
+        assert!(check_workdir(&policy, Path::new("/opt/app/logs")).is_ok());
+        assert!(check_workdir(&policy, Path::new("/opt/application")).is_err());
+        assert!(check_workdir(&policy, Path::new("/etc")).is_err());
+        assert!(check_workdir(&policy, Path::new("/opt/app/../../etc")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_stream_truncates_buffer() {
+        let input: &[u8] = b"first\nsecond\nthird\n";
+        let (all, omitted) = read_stream(Some(input), OutputStream::Stdout, None, 8).await;
+        assert_eq!(omitted, 13);
+        assert_eq!(all, "first\n\n[输出已截断，省略 13 字节]\n");
+
+        let (all, omitted) = read_stream(Some(input), OutputStream::Stdout, None, 1024).await;
+        assert_eq!(omitted, 0);
+        assert_eq!(all, "first\nsecond\nthird\n");
+
+        let (all, omitted) = read_stream(None::<&[u8]>, OutputStream::Stderr, None, 8).await;
+        assert_eq!((all.as_str(), omitted), ("", 0));
+    }
 }
@@ -0,0 +1,160 @@
+/// 远程终端处理器
+///
+/// 在 PTY 中启动交互式 Shell，输出通过通道回传给终端服务，
+/// 支持写入输入、调整窗口大小与关闭会话。
+use anyhow::Result;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use std::io::{Read, Write};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// PTY 产生的事件
+pub enum PtyEvent {
+    Output { session_id: String, data: String },
+    Closed { session_id: String },
+}
+
+/// 单个终端会话句柄
+pub struct TerminalSession {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+}
+
+impl TerminalSession {
+    /// 在 PTY 中启动 Shell，并开启读取线程
+    pub fn spawn(
+        session_id: &str,
+        cols: u16,
+        rows: u16,
+        shell: Option<&str>,
+        events: UnboundedSender<PtyEvent>,
+    ) -> Result<Self> {
+        let pair = native_pty_system().openpty(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })?;
+
+        let mut cmd =
+            CommandBuilder::new(shell.map(|s| s.to_string()).unwrap_or_else(default_shell));
+        cmd.env("TERM", "xterm-256color");
+        if let Some(home) = dirs::home_dir() {
+            cmd.cwd(home);
+        }
+        let child = pair.slave.spawn_command(cmd)?;
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader()?;
+        let writer = pair.master.take_writer()?;
+        let sid = session_id.to_string();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            let mut pending: Vec<u8> = Vec::new();
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let data = take_utf8(&mut pending);
+                        if !data.is_empty()
+                            && events
+                                .send(PtyEvent::Output {
+                                    session_id: sid.clone(),
+                                    data,
+                                })
+                                .is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+            let _ = events.send(PtyEvent::Closed { session_id: sid });
+        });
+
+        Ok(Self {
+            master: pair.master,
+            writer,
+            child,
+        })
+    }
+
+    pub fn write(&mut self, data: &str) -> Result<()> {
+        self.writer.write_all(data.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn resize(&self, cols: u16, rows: u16) -> Result<()> {
+        self.master.resize(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })?;
+        Ok(())
+    }
+
+    /// 获取 Shell 退出码（已退出时）
+    pub fn exit_code(&mut self) -> Option<i32> {
+        match self.child.try_wait() {
+            Ok(Some(status)) => Some(status.exit_code() as i32),
+            _ => None,
+        }
+    }
+
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.try_wait();
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        if self.exit_code().is_none() {
+            self.kill();
+        }
+    }
+}
+
+fn default_shell() -> String {
+    if cfg!(target_os = "windows") {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+    } else {
+        std::env::var("SHELL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| {
+                if std::path::Path::new("/bin/bash").exists() {
+                    "/bin/bash".to_string()
+                } else {
+                    "/bin/sh".to_string()
+                }
+            })
+    }
+}
+
+/// 取出缓冲区中完整的 UTF-8 内容，末尾不完整的多字节字符留待下次拼接
+fn take_utf8(pending: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(pending) {
+        Ok(s) => {
+            let out = s.to_string();
+            pending.clear();
+            out
+        }
+        Err(e) => {
+            let valid = e.valid_up_to();
+            if e.error_len().is_none() && pending.len() - valid < 4 {
+                // 末尾为被截断的字符
+                let out = String::from_utf8_lossy(&pending[..valid]).to_string();
+                pending.drain(..valid);
+                out
+            } else {
+                let out = String::from_utf8_lossy(pending).to_string();
+                pending.clear();
+                out
+            }
+        }
+    }
+}
@@ -1,37 +1,273 @@
 /// Agent 内部日志缓冲区
 ///
-/// 采集运行过程中的轻量日志，并在实例上报时批量提交后清空。
+/// 运行过程中的日志先进入内存队列并追加写入本地缓冲文件，由日志上报服务按批发送，
+/// 服务端确认后才从队列移除，并在文件中追加确认记录，Agent 重启或服务端不可用期间日志不会丢失。
+/// 文件只追加，行数超过条数上限两倍时按当前队列重写。
+/// 队列有条数上限，超出时丢弃最早的日志并计数，丢弃数随下一批日志上报。
 use crate::models::AgentLogItem;
 use chrono::Utc;
+use serde::Deserialize;
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+/// 待上报的一批日志
+pub struct LogBatch {
+    /// 批次 ID，取队首日志的 ID，重试同一批时保持不变
+    pub batch_id: String,
+    pub items: Vec<AgentLogItem>,
+    /// 截至取批时累计丢弃的日志条数
+    pub dropped: u64,
+}
+
+/// 缓冲文件中的一行：日志或确认记录（该 ID 及之前的日志均已入库）
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpoolLine {
+    Ack { ack: Uuid },
+    Item(AgentLogItem),
+}
+
+struct Spool {
+    queue: VecDeque<AgentLogItem>,
+    dropped: u64,
+    /// 缓冲文件追加句柄，打开失败时仅保留内存队列
+    file: Option<File>,
+    /// 缓冲文件中的行数（含已确认、已被溢出丢弃以及确认记录等尚未压缩的行）
+    file_lines: usize,
+}
 
 #[derive(Clone)]
 pub struct AgentLogStore {
-    inner: Arc<Mutex<Vec<AgentLogItem>>>,
+    inner: Arc<Mutex<Spool>>,
+    path: Arc<PathBuf>,
+    max_entries: usize,
 }
 impl AgentLogStore {
-    /// 创建一个新的日志缓冲区
-    pub fn new() -> Self {
-        Self {
-            inner: Arc::new(Mutex::new(Vec::new())),
+    /// 打开日志缓冲区，加载上次未上报的日志
+    pub fn open(path: PathBuf, max_entries: usize) -> Self {
+        let max_entries = max_entries.max(1);
+        let mut queue = VecDeque::new();
+        if let Ok(f) = File::open(&path) {
+            for line in BufReader::new(f).lines().map_while(Result::ok) {
+                match serde_json::from_str(&line) {
+                    Ok(SpoolLine::Item(item)) => queue.push_back(item),
+                    Ok(SpoolLine::Ack { ack }) => remove_through(&mut queue, ack),
+                    Err(_) => {}
+                }
+            }
+        }
+        let mut dropped = 0;
+        while queue.len() > max_entries {
+            queue.pop_front();
+            dropped += 1;
         }
+        let store = Self {
+            inner: Arc::new(Mutex::new(Spool {
+                queue,
+                dropped,
+                file: None,
+                file_lines: 0,
+            })),
+            path: Arc::new(path),
+            max_entries,
+        };
+        store.compact(&mut store.inner.lock().unwrap());
+        store
     }
-    /// 推入一条日志
+    /// 推入一条 Agent 自身日志
     pub fn push(&self, level: &str, message: &str) {
-        self.inner.lock().unwrap().push(AgentLogItem {
+        self.push_item(AgentLogItem {
             id: Uuid::new_v4(),
             level: level.to_string(),
             message: message.to_string(),
             ts: Utc::now(),
+            log_type: None,
+            context: None,
         });
     }
-    /// 快照并清空当前缓冲区
-    pub fn snapshot_and_clear(&self) -> Vec<AgentLogItem> {
+    /// 推入一条完整日志（如采集的应用日志）
+    pub fn push_item(&self, item: AgentLogItem) {
+        let line = serde_json::to_string(&item).ok();
+        let mut g = self.inner.lock().unwrap();
+        g.queue.push_back(item);
+        if g.queue.len() > self.max_entries {
+            g.queue.pop_front();
+            g.dropped += 1;
+        }
+        // 先入队再写文件，写入触发压缩时新日志已在队列中
+        if let Some(line) = line {
+            self.append(&mut g, &line);
+        }
+    }
+    /// 取队首一批日志（不移除），服务端确认后调用 ack
+    pub fn peek_batch(&self, size: usize) -> LogBatch {
+        let g = self.inner.lock().unwrap();
+        let items: Vec<AgentLogItem> = g.queue.iter().take(size.max(1)).cloned().collect();
+        LogBatch {
+            batch_id: items
+                .first()
+                .map_or_else(Uuid::new_v4, |i| i.id)
+                .to_string(),
+            items,
+            dropped: g.dropped,
+        }
+    }
+    /// 确认一批日志已入库：从队列移除并在缓冲文件中追加确认记录，扣减已上报的丢弃数
+    pub fn ack(&self, batch: &LogBatch) {
+        let ids: HashSet<Uuid> = batch.items.iter().map(|i| i.id).collect();
         let mut g = self.inner.lock().unwrap();
-        let v = g.clone();
-        g.clear();
-        v
+        // 取批后队首可能已因溢出被丢弃，只移除仍在队首的部分
+        let mut last = None;
+        while g.queue.front().is_some_and(|i| ids.contains(&i.id)) {
+            last = g.queue.pop_front().map(|i| i.id);
+        }
+        g.dropped = g.dropped.saturating_sub(batch.dropped);
+        if let Some(id) = last {
+            self.append(&mut g, &serde_json::json!({ "ack": id }).to_string());
+        }
+    }
+    /// 追加一行到缓冲文件，超过上限两倍后压缩
+    fn append(&self, g: &mut Spool, line: &str) {
+        if let Some(f) = g.file.as_mut() {
+            let _ = writeln!(f, "{}", line);
+        }
+        g.file_lines += 1;
+        if g.file_lines > self.max_entries * 2 {
+            self.compact(g);
+        }
+    }
+    /// 用当前队列重写缓冲文件
+    fn compact(&self, g: &mut Spool) {
+        g.file = None;
+        g.file_lines = g.queue.len();
+        let tmp = self.path.with_extension("jsonl.tmp");
+        let written = (|| -> std::io::Result<()> {
+            if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            let mut f = File::create(&tmp)?;
+            for item in g.queue.iter() {
+                if let Ok(line) = serde_json::to_string(item) {
+                    writeln!(f, "{}", line)?;
+                }
+            }
+            f.sync_all()?;
+            fs::rename(&tmp, self.path.as_path())
+        })();
+        if written.is_ok() {
+            g.file = OpenOptions::new()
+                .append(true)
+                .open(self.path.as_path())
+                .ok();
+        }
+    }
+}
+
+/// 移除队列中指定 ID 及之前的日志，ID 不在队列中（已因溢出丢弃）时忽略
+fn remove_through(queue: &mut VecDeque<AgentLogItem>, id: Uuid) {
+    if let Some(pos) = queue.iter().position(|i| i.id == id) {
+        queue.drain(..=pos);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spool_path() -> PathBuf {
+        std::env::temp_dir().join(format!("monihub-spool-{}.jsonl", Uuid::new_v4()))
+    }
+
+    fn messages(batch: &LogBatch) -> Vec<&str> {
+        batch.items.iter().map(|i| i.message.as_str()).collect()
+    }
+
+    fn file_lines(path: &PathBuf) -> usize {
+        fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[test]
+    fn test_overflow_drops_oldest() {
+        let path = spool_path();
+        let store = AgentLogStore::open(path.clone(), 3);
+        for i in 0..5 {
+            store.push("info", &format!("m{}", i));
+        }
+        let batch = store.peek_batch(10);
+        assert_eq!(messages(&batch), ["m2", "m3", "m4"]);
+        assert_eq!(batch.dropped, 2);
+        assert_eq!(batch.batch_id, batch.items[0].id.to_string());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_ack_removes_batch_and_dropped_count() {
+        let path = spool_path();
+        let store = AgentLogStore::open(path.clone(), 2);
+        for i in 0..3 {
+            store.push("info", &format!("m{}", i));
+        }
+        let batch = store.peek_batch(1);
+        assert_eq!(messages(&batch), ["m1"]);
+        // 取批后又溢出一条，队首已变化
+        store.push("info", "m3");
+        store.ack(&batch);
+        let next = store.peek_batch(10);
+        assert_eq!(messages(&next), ["m2", "m3"]);
+        assert_eq!(next.dropped, 1);
+
+        // 同一批重试时批次 ID 不变
+        assert_eq!(store.peek_batch(10).batch_id, next.batch_id);
+        store.ack(&next);
+        let empty = store.peek_batch(10);
+        assert!(empty.items.is_empty());
+        assert_eq!(empty.dropped, 0);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_reload_skips_acked() {
+        let path = spool_path();
+        {
+            let store = AgentLogStore::open(path.clone(), 10);
+            for i in 0..4 {
+                store.push("warn", &format!("m{}", i));
+            }
+            let batch = store.peek_batch(2);
+            store.ack(&batch);
+            // 确认只追加记录，不重写文件
+            assert_eq!(file_lines(&path), 5);
+        }
+        let store = AgentLogStore::open(path.clone(), 10);
+        let batch = store.peek_batch(10);
+        assert_eq!(messages(&batch), ["m2", "m3"]);
+        assert_eq!(batch.items[0].level, "warn");
+        // 打开时按队列重写
+        assert_eq!(file_lines(&path), 2);
+
+        let store = AgentLogStore::open(path.clone(), 1);
+        assert_eq!(messages(&store.peek_batch(10)), ["m3"]);
+        assert_eq!(store.peek_batch(10).dropped, 1);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_compact_by_threshold() {
+        let path = spool_path();
+        let store = AgentLogStore::open(path.clone(), 3);
+        for i in 0..6 {
+            store.push("info", &format!("m{}", i));
+        }
+        assert_eq!(file_lines(&path), 6);
+        // 超过上限两倍后按队列重写
+        store.push("info", "m6");
+        assert_eq!(file_lines(&path), 3);
+        let reopened = AgentLogStore::open(path.clone(), 3);
+        assert_eq!(messages(&reopened.peek_batch(10)), ["m4", "m5", "m6"]);
+        let _ = fs::remove_file(path);
     }
 }
@@ -2,22 +2,23 @@
 ///
 /// 职责：
 /// - 解析命令行参数并加载配置文件
-/// - 启动实例上报服务与任务拉取执行服务
+/// - 获取实例令牌，启动实例上报、任务拉取执行与远程终端服务
 /// - 监听 Ctrl+C 信号，收到后优雅退出
 use clap::Parser;
 use tokio::signal;
 
-
 mod agent_logger;
 mod config;
 mod executor;
 mod handlers;
 mod log_store;
+mod metrics;
 mod models;
 mod services;
 mod utils;
 
 #[derive(Parser, Debug)]
+#[command(version)]
 /// 命令行参数结构体
 struct Cli {
     /// 配置文件路径
@@ -48,19 +49,41 @@ async fn main() {
     )
     .unwrap_or_default();
     agent_logger::init(&cfg);
+    // 未确认的升级已超时或反复重启时，先回滚到旧版本
+    services::self_update::check_on_startup(&cfg);
     let state = services::AppState::new(cfg.clone());
     agent_logger::set_state(state.clone());
+    utils::http_util::set_http_config(&cfg.http);
     agent_logger::info("Agent 启动");
     agent_logger::info(&format!(
         "配置加载完成 server={} debug={}",
         cfg.server_url, cfg.debug
     ));
+    // 加载或换取实例令牌，并开启令牌周期轮换
+    services::credentials::init(&state).await;
+    services::credentials::start(state.clone()).await;
+
+    // 检查待确认的升级：首次上报成功后确认，超时回滚到旧版本
+    services::self_update::start(state.clone()).await;
+
     // 开启实例信息上报服务（异步定时任务）
     services::report::start(state.clone()).await;
 
+    // 开启日志上报服务（落盘缓冲 + 批量确认）
+    services::log_shipper::start(state.clone()).await;
+
+    // 开启应用日志文件采集（写入日志缓冲，随日志上报服务发送）
+    services::log_tailer::start(state.clone()).await;
+
     // 开启任务拉取/执行服务（长轮询 + 并发执行）
     services::tasks::start(state.clone()).await;
 
+    // 开启远程终端通道（出站 WebSocket 长连接）
+    services::terminal::start(state.clone()).await;
+
+    // 开启配置中心订阅（配置变更后写入本地文件并执行重载命令）
+    services::config_watch::start(state.clone()).await;
+
     // 阻塞等待 Ctrl+C 信号，实现优雅退出
     let _ = signal::ctrl_c().await;
     agent_logger::info("收到退出信号，准备退出");
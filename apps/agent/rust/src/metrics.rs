@@ -0,0 +1,336 @@
+/// 指标采样器
+///
+/// 在上报周期之间保持 `System`/`Disks`/`Networks` 实例，CPU 使用率与网络吞吐
+/// 均按两次采样之间的差值计算（首个周期紧随基线，CPU 不准确，吞吐不输出）。
+///
+/// 除 `hardware_info`/`runtime_info` 固定字段外，其余指标以扁平数值写入
+/// `custom_metrics`，服务端按 `custom.<key>` 入库、降采样并绘制曲线：
+///
+/// | key | 含义 |
+/// | --- | --- |
+/// | `load_avg_1` / `load_avg_5` / `load_avg_15` | 系统负载（Windows 恒为 0） |
+/// | `disk.<mount>.total_gb` / `used_gb` / `usage_percent` | 各挂载点容量与使用率 |
+/// | `net.<iface>.rx_bytes_per_sec` / `tx_bytes_per_sec` | 各网卡收发速率 |
+/// | `process.up` | 被监控进程是否存在（1/0），仅配置了 `monitor` 时输出 |
+/// | `process.pid` / `process.rss_mb` / `process.cpu_percent` | 被监控进程 PID、常驻内存与 CPU |
+/// | `process.threads` / `process.open_fds` / `process.uptime_seconds` | 线程数、打开的文件描述符数（仅 Linux）、运行时长 |
+///
+/// `<mount>` 去掉首尾分隔符后将 `/`、`\`、`:`、`.` 替换为 `_`，根目录记为 `root`；
+/// 回环网卡不输出。进程 CPU 为单核百分比，多线程进程可超过 100。
+use crate::config::MonitorConfig;
+use serde_json::{json, Map, Value};
+use std::time::Instant;
+use sysinfo::{
+    CpuRefreshKind, Disks, MemoryRefreshKind, Networks, Pid, Process, ProcessRefreshKind,
+    ProcessesToUpdate, RefreshKind, System,
+};
+
+const MB: u64 = 1024 * 1024;
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// 单次采样结果
+pub struct MetricsSample {
+    pub cpu_model: Option<String>,
+    pub cpu_cores: Option<i32>,
+    pub cpu_usage_percent: f64,
+    pub memory_total_mb: i64,
+    pub memory_used_mb: i64,
+    pub disk_total_gb: i64,
+    pub disk_used_gb: i64,
+    /// Agent 自身进程的线程数与运行时长
+    pub thread_count: Option<i32>,
+    pub process_uptime_seconds: i64,
+    pub custom_metrics: Value,
+}
+
+pub struct MetricsSampler {
+    sys: System,
+    disks: Disks,
+    networks: Networks,
+    last_network_refresh: Instant,
+    network_primed: bool,
+    monitor: MonitorConfig,
+    /// 按名称匹配到的进程 PID，进程退出后重新查找
+    monitored_pid: Option<Pid>,
+    own_pid: Pid,
+}
+
+impl MetricsSampler {
+    /// 创建采样器并做首次刷新，作为下一次采样的基线
+    pub fn new(monitor: MonitorConfig) -> Self {
+        let mut sys = System::new_with_specifics(
+            RefreshKind::nothing()
+                .with_cpu(CpuRefreshKind::nothing().with_cpu_usage())
+                .with_memory(MemoryRefreshKind::nothing().with_ram()),
+        );
+        sys.refresh_cpu_usage();
+        let own_pid = Pid::from_u32(std::process::id());
+        let mut sampler = Self {
+            sys,
+            disks: Disks::new_with_refreshed_list(),
+            networks: Networks::new_with_refreshed_list(),
+            last_network_refresh: Instant::now(),
+            network_primed: false,
+            monitor,
+            monitored_pid: None,
+            own_pid,
+        };
+        sampler.refresh_processes();
+        sampler
+    }
+
+    /// 采集一次指标
+    pub fn sample(&mut self) -> MetricsSample {
+        self.sys.refresh_cpu_usage();
+        self.sys.refresh_memory();
+        self.disks.refresh(true);
+        self.refresh_processes();
+
+        let mut custom = Map::new();
+        let load = System::load_average();
+        custom.insert("load_avg_1".into(), json!(round2(load.one)));
+        custom.insert("load_avg_5".into(), json!(round2(load.five)));
+        custom.insert("load_avg_15".into(), json!(round2(load.fifteen)));
+
+        let (disk_total_gb, disk_used_gb) = self.collect_disks(&mut custom);
+        self.collect_networks(&mut custom);
+        self.collect_monitored_process(&mut custom);
+
+        let own = self.sys.process(self.own_pid);
+        let cpus = self.sys.cpus();
+        MetricsSample {
+            cpu_model: cpus.first().map(|c| c.brand().trim().to_string()),
+            cpu_cores: Some(cpus.len() as i32),
+            cpu_usage_percent: round2(self.sys.global_cpu_usage() as f64),
+            memory_total_mb: (self.sys.total_memory() / MB) as i64,
+            memory_used_mb: (self.sys.used_memory() / MB) as i64,
+            disk_total_gb,
+            disk_used_gb,
+            thread_count: own.and_then(thread_count),
+            process_uptime_seconds: own.map(|p| p.run_time() as i64).unwrap_or(0),
+            custom_metrics: Value::Object(custom),
+        }
+    }
+
+    /// 仅刷新 Agent 自身与被监控进程；按名称查找时才全量扫描
+    fn refresh_processes(&mut self) {
+        let kind = ProcessRefreshKind::nothing().with_cpu().with_memory();
+        let mut pids = vec![self.own_pid];
+        if let Some(pid) = self.monitor.process_pid.map(Pid::from_u32) {
+            pids.push(pid);
+        } else if let Some(pid) = self.monitored_pid {
+            pids.push(pid);
+        }
+        self.sys
+            .refresh_processes_specifics(ProcessesToUpdate::Some(&pids), true, kind);
+
+        if self.monitor.process_pid.is_some() {
+            return;
+        }
+        let Some(name) = self
+            .monitor
+            .process_name
+            .as_deref()
+            .filter(|n| !n.is_empty())
+        else {
+            return;
+        };
+        if self
+            .monitored_pid
+            .is_some_and(|pid| self.sys.process(pid).is_some())
+        {
+            return;
+        }
+        self.sys
+            .refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+        self.monitored_pid = self
+            .sys
+            .processes_by_exact_name(name.as_ref())
+            .filter(|p| p.pid() != self.own_pid && p.thread_kind().is_none())
+            .min_by_key(|p| p.start_time())
+            .map(|p| p.pid());
+    }
+
+    fn collect_disks(&self, custom: &mut Map<String, Value>) -> (i64, i64) {
+        let mut total_bytes: u64 = 0;
+        let mut used_bytes: u64 = 0;
+        for d in self.disks.list() {
+            let total = d.total_space();
+            if total == 0 {
+                continue;
+            }
+            let used = total.saturating_sub(d.available_space());
+            total_bytes += total;
+            used_bytes += used;
+            let key = mount_key(&d.mount_point().to_string_lossy());
+            custom.insert(
+                format!("disk.{key}.total_gb"),
+                json!(round2(total as f64 / GB as f64)),
+            );
+            custom.insert(
+                format!("disk.{key}.used_gb"),
+                json!(round2(used as f64 / GB as f64)),
+            );
+            custom.insert(
+                format!("disk.{key}.usage_percent"),
+                json!(round2(used as f64 * 100.0 / total as f64)),
+            );
+        }
+        ((total_bytes / GB) as i64, (used_bytes / GB) as i64)
+    }
+
+    fn collect_networks(&mut self, custom: &mut Map<String, Value>) {
+        self.networks.refresh(true);
+        let elapsed = self.last_network_refresh.elapsed().as_secs_f64();
+        self.last_network_refresh = Instant::now();
+        // 首个周期紧随基线刷新，间隔过短，速率不具备参考意义
+        if !std::mem::replace(&mut self.network_primed, true) || elapsed <= 0.0 {
+            return;
+        }
+        for (name, data) in self.networks.list() {
+            if is_loopback(name) {
+                continue;
+            }
+            custom.insert(
+                format!("net.{name}.rx_bytes_per_sec"),
+                json!(round2(data.received() as f64 / elapsed)),
+            );
+            custom.insert(
+                format!("net.{name}.tx_bytes_per_sec"),
+                json!(round2(data.transmitted() as f64 / elapsed)),
+            );
+        }
+    }
+
+    fn collect_monitored_process(&self, custom: &mut Map<String, Value>) {
+        let pid = match self.monitor.process_pid {
+            Some(pid) => Some(Pid::from_u32(pid)),
+            None if self
+                .monitor
+                .process_name
+                .as_deref()
+                .is_some_and(|n| !n.is_empty()) =>
+            {
+                self.monitored_pid
+            }
+            None => return,
+        };
+        let Some(process) = pid.and_then(|pid| self.sys.process(pid)) else {
+            custom.insert("process.up".into(), json!(0));
+            return;
+        };
+        custom.insert("process.up".into(), json!(1));
+        custom.insert("process.pid".into(), json!(process.pid().as_u32()));
+        custom.insert(
+            "process.rss_mb".into(),
+            json!(round2(process.memory() as f64 / MB as f64)),
+        );
+        custom.insert(
+            "process.cpu_percent".into(),
+            json!(round2(process.cpu_usage() as f64)),
+        );
+        custom.insert("process.uptime_seconds".into(), json!(process.run_time()));
+        if let Some(threads) = thread_count(process) {
+            custom.insert("process.threads".into(), json!(threads));
+        }
+        if let Some(fds) = open_fds(process.pid()) {
+            custom.insert("process.open_fds".into(), json!(fds));
+        }
+    }
+}
+
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+fn thread_count(process: &Process) -> Option<i32> {
+    process.tasks().map(|t| t.len().max(1) as i32)
+}
+
+#[cfg(target_os = "linux")]
+fn open_fds(pid: Pid) -> Option<usize> {
+    std::fs::read_dir(format!("/proc/{}/fd", pid.as_u32()))
+        .ok()
+        .map(|dir| dir.count())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_fds(_pid: Pid) -> Option<usize> {
+    None
+}
+
+fn is_loopback(name: &str) -> bool {
+    name == "lo" || name.starts_with("lo0") || name.to_ascii_lowercase().contains("loopback")
+}
+
+/// 挂载点转为指标名片段
+fn mount_key(mount: &str) -> String {
+    let trimmed = mount.trim_matches(|c| c == '/' || c == '\\');
+    if trimmed.is_empty() {
+        return "root".to_string();
+    }
+    trimmed
+        .trim_end_matches(':')
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '.' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mount_key() {
+        assert_eq!(mount_key("/"), "root");
+        assert_eq!(mount_key("/data"), "data");
+        assert_eq!(mount_key("/var/lib/docker/"), "var_lib_docker");
+        assert_eq!(mount_key("/mnt/disk.1"), "mnt_disk_1");
+        assert_eq!(mount_key("C:\\"), "C");
+        assert_eq!(mount_key("D:\\backup\\"), "D__backup");
+    }
+
+    #[test]
+    fn test_is_loopback() {
+        assert!(is_loopback("lo"));
+        assert!(is_loopback("lo0"));
+        assert!(is_loopback("Loopback Pseudo-Interface 1"));
+        assert!(!is_loopback("eth0"));
+        assert!(!is_loopback("local0"));
+    }
+
+    #[test]
+    fn test_sample_uses_deltas() {
+        let mut sampler = MetricsSampler::new(MonitorConfig::default());
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        let first = sampler.sample();
+        assert!((0.0..=100.0).contains(&first.cpu_usage_percent));
+        // 首个周期紧随基线，不输出网络速率
+        let custom = first.custom_metrics.as_object().unwrap();
+        assert!(!custom.keys().any(|k| k.starts_with("net.")));
+        assert!(custom.contains_key("load_avg_1"));
+        assert!(!custom.contains_key("process.up"));
+
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        let second = sampler.sample();
+        assert!((0.0..=100.0).contains(&second.cpu_usage_percent));
+        let custom = second.custom_metrics.as_object().unwrap();
+        for (key, value) in custom.iter().filter(|(k, _)| k.starts_with("net.")) {
+            assert!(!key.starts_with("net.lo."), "{}", key);
+            assert!(value.as_f64().unwrap() >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_monitored_process_missing() {
+        let mut sampler = MetricsSampler::new(MonitorConfig {
+            process_name: Some("monihub-no-such-process".to_string()),
+            ..Default::default()
+        });
+        let sample = sampler.sample();
+        assert_eq!(sample.custom_metrics["process.up"], 0);
+    }
+}
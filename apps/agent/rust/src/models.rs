@@ -6,7 +6,6 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-
 #[derive(Clone, Serialize, Deserialize)]
 /// 实例信息上报请求体
 pub struct InstanceReportRequest {
@@ -38,8 +37,23 @@ pub struct InstanceReportRequest {
     pub custom_metrics: serde_json::Value,
     #[serde(rename = "report_timestamp")]
     pub report_timestamp: String,
-    #[serde(rename = "agent_logs")]
+    /// 日志改由独立接口上报，保留字段兼容旧版服务端
+    #[serde(rename = "agent_logs", skip_serializing_if = "Vec::is_empty")]
     pub agent_logs: Vec<AgentLogWire>,
+    /// 已生效的服务端配置版本
+    #[serde(
+        rename = "config_applied_revision",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub config_applied_revision: Option<i32>,
+}
+
+#[derive(Clone, Deserialize, Default)]
+#[serde(default)]
+/// 实例上报响应体（仅解析配置下发相关字段）
+pub struct InstanceReportResponse {
+    pub config: Option<serde_json::Value>,
+    pub config_revision: Option<i32>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Default)]
@@ -89,15 +103,42 @@ pub struct AgentLogItem {
     pub level: String,
     pub message: String,
     pub ts: DateTime<Utc>,
+    /// 日志类型，None 表示 Agent 自身日志，采集的应用日志为 application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Serialize)]
+/// 日志批量上报请求体
+pub struct AgentLogBatchRequest {
+    pub agent_instance_id: String,
+    pub application_code: String,
+    pub batch_id: String,
+    pub logs: Vec<AgentLogWire>,
+    pub dropped_count: u64,
+}
+
+#[derive(Clone, Deserialize)]
+/// 日志批量上报响应体：整批写入成功或整批失败
+pub struct AgentLogBatchResponse {
+    pub log_success_count: u32,
+    pub log_failure_count: u32,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 /// 上报给服务端的日志项（与服务端模型对齐）
 pub struct AgentLogWire {
+    /// 缓冲中的日志 ID，服务端据此对重发的日志去重
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_id: Option<String>,
     #[serde(rename = "log_level")]
     pub log_level: String,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<serde_json::Value>,
     #[serde(rename = "timestamp", skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<String>,
@@ -120,6 +161,9 @@ pub struct TaskDispatchItem {
     pub timeout_seconds: u64,
     pub task_content: serde_json::Value,
     pub priority: i32,
+    /// 服务端重试次数，回传结果时原样带回
+    #[serde(default)]
+    pub retry_attempt: i32,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -131,6 +175,7 @@ pub enum TaskType {
     FileManager,
     CustomCommand,
     HttpRequest,
+    AgentUpgrade,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -155,6 +200,8 @@ pub struct TaskResultSubmitRequest {
     pub end_time: DateTime<Utc>,
     #[serde(rename = "duration_ms")]
     pub duration_ms: u128,
+    #[serde(rename = "retry_attempt")]
+    pub retry_attempt: i32,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
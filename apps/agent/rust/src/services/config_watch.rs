@@ -0,0 +1,297 @@
+/// 配置中心订阅服务
+///
+/// 按 `config_watch.files` 长轮询服务端 watch 接口，配置变化后按格式（JSON/YAML/properties/原文）
+/// 渲染并原子写入本地文件，文件内容实际变化时执行对应的重载命令。
+/// 以服务端返回的内容修订标识订阅，基础配置或变量修改后也会收到变更；
+/// 启动时尚未持有修订，服务端立即返回当前配置，文件内容与本地一致时不执行重载。
+use crate::{
+    agent_logger,
+    config::{ConfigFileConfig, ConfigFileFormat},
+    services::AppState,
+    utils::http_util,
+};
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::time::{sleep, timeout, Duration};
+
+/// 重载命令最长执行时间
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct WatchResponse {
+    changes: Vec<WatchChange>,
+}
+
+#[derive(Deserialize)]
+struct WatchChange {
+    code: String,
+    environment: String,
+    version: u32,
+    /// 内容修订标识，配置已删除时为空
+    #[serde(default)]
+    revision: Option<String>,
+    config: Option<WatchConfig>,
+}
+
+/// 已持有的配置版本与修订
+#[derive(Default)]
+struct Known {
+    version: u32,
+    revision: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WatchConfig {
+    content: String,
+}
+
+/// 环境名称归一，与服务端一致
+fn normalize_environment(environment: &str) -> String {
+    match environment.to_lowercase().as_str() {
+        "development" => "dev".to_string(),
+        "production" => "prod".to_string(),
+        other => other.to_string(),
+    }
+}
+
+pub async fn start(state: AppState) {
+    let cfg = state.cfg.config_watch.clone();
+    if !cfg.enabled || cfg.files.is_empty() {
+        return;
+    }
+    // (code, environment) -> 订阅该配置的文件
+    let mut subscriptions: HashMap<(String, String), Vec<ConfigFileConfig>> = HashMap::new();
+    for file in cfg
+        .files
+        .into_iter()
+        .filter(|f| !f.code.is_empty() && !f.path.is_empty())
+    {
+        let environment =
+            normalize_environment(file.environment.as_deref().unwrap_or(&cfg.environment));
+        subscriptions
+            .entry((file.code.clone(), environment))
+            .or_default()
+            .push(file);
+    }
+    if subscriptions.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut versions: HashMap<(String, String), Known> = subscriptions
+            .keys()
+            .map(|k| (k.clone(), Known::default()))
+            .collect();
+        let url = format!("{}/api/open/instances/configs/watch", state.cfg.server_url);
+        loop {
+            let configs: Vec<Value> = versions
+                .iter()
+                .map(|((code, environment), known)| {
+                    json!({
+                        "code": code,
+                        "environment": environment,
+                        "version": known.version,
+                        "revision": known.revision,
+                    })
+                })
+                .collect();
+            let body = json!({
+                "agent_instance_id": state.cfg.agent_instance_id.clone().unwrap_or_default(),
+                "configs": configs,
+                "timeout_seconds": cfg.timeout_seconds,
+            });
+
+            match http_util::post(url.clone(), &body).await {
+                Ok(resp) if resp.status().as_u16() == 401 => {
+                    crate::services::credentials::on_unauthorized(&state).await;
+                    sleep(Duration::from_secs(5)).await;
+                }
+                Ok(resp) if resp.status().is_success() => {
+                    match resp.json::<WatchResponse>().await {
+                        Ok(body) => {
+                            apply_changes(body.changes, &subscriptions, &mut versions).await
+                        }
+                        Err(e) => {
+                            agent_logger::warn(&format!("解析配置变更失败: {}", e));
+                            sleep(Duration::from_secs(5)).await;
+                        }
+                    }
+                }
+                Ok(resp) => {
+                    let status = resp.status().as_u16();
+                    let text = resp.text().await.unwrap_or_default();
+                    agent_logger::warn(&format!("订阅配置失败 http={} body={}", status, text));
+                    sleep(Duration::from_secs(10)).await;
+                }
+                Err(_) => {
+                    sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+}
+
+/// 写入变化的配置文件，并对内容实际变化的文件执行重载命令（同一命令只执行一次）
+async fn apply_changes(
+    changes: Vec<WatchChange>,
+    subscriptions: &HashMap<(String, String), Vec<ConfigFileConfig>>,
+    versions: &mut HashMap<(String, String), Known>,
+) {
+    let mut reload_commands: Vec<String> = Vec::new();
+    for change in changes {
+        let key = (change.code.clone(), change.environment.clone());
+        let Some(files) = subscriptions.get(&key) else {
+            continue;
+        };
+        versions.insert(
+            key,
+            Known {
+                version: change.version,
+                revision: change.revision.clone(),
+            },
+        );
+        let Some(config) = change.config else {
+            agent_logger::warn(&format!(
+                "配置 {}/{} 已在服务端删除，保留本地文件",
+                change.code, change.environment
+            ));
+            continue;
+        };
+        for file in files {
+            match write_config_file(file, &config.content) {
+                Ok(false) => {}
+                Ok(true) => {
+                    agent_logger::info(&format!(
+                        "配置 {}/{} 已更新到版本 {}，写入 {}",
+                        change.code, change.environment, change.version, file.path
+                    ));
+                    if let Some(cmd) = file.reload_command.as_ref().filter(|c| !c.is_empty()) {
+                        if !reload_commands.contains(cmd) {
+                            reload_commands.push(cmd.clone());
+                        }
+                    }
+                }
+                Err(e) => agent_logger::error(&format!(
+                    "写入配置文件 {} 失败（{}/{} 版本 {}）: {}",
+                    file.path, change.code, change.environment, change.version, e
+                )),
+            }
+        }
+    }
+    for cmd in reload_commands {
+        run_reload(&cmd).await;
+    }
+}
+
+/// 渲染并原子写入配置文件，返回文件内容是否发生变化
+fn write_config_file(file: &ConfigFileConfig, content: &str) -> Result<bool> {
+    let rendered = render(file.format, content)?;
+    let path = Path::new(&file.path);
+    if std::fs::read(path).ok().as_deref() == Some(rendered.as_bytes()) {
+        return Ok(false);
+    }
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = crate::services::self_update::with_suffix(path, ".tmp");
+    std::fs::write(&tmp, rendered)?;
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(true)
+}
+
+fn render(format: ConfigFileFormat, content: &str) -> Result<String> {
+    let parse = || -> Result<Value> {
+        serde_json::from_str(content)
+            .map_err(|e| anyhow::anyhow!("配置内容不是 JSON，无法按 {:?} 格式写入: {}", format, e))
+    };
+    Ok(match format {
+        ConfigFileFormat::Raw => content.to_string(),
+        ConfigFileFormat::Json => format!("{}\n", serde_json::to_string_pretty(&parse()?)?),
+        ConfigFileFormat::Yaml => serde_yaml::to_string(&parse()?)?,
+        ConfigFileFormat::Properties => {
+            let mut lines = Vec::new();
+            flatten_properties("", &parse()?, &mut lines);
+            lines.iter().map(|l| format!("{}\n", l)).collect()
+        }
+    })
+}
+
+/// 将 JSON 展开为 properties 行：对象字段以 `.` 连接，数组元素使用 `[i]`
+fn flatten_properties(prefix: &str, value: &Value, lines: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten_properties(&key, v, lines);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                flatten_properties(&format!("{}[{}]", prefix, i), v, lines);
+            }
+        }
+        Value::Null => lines.push(format!("{}=", escape_properties(prefix, true))),
+        Value::String(s) => lines.push(format!(
+            "{}={}",
+            escape_properties(prefix, true),
+            escape_properties(s, false)
+        )),
+        other => lines.push(format!("{}={}", escape_properties(prefix, true), other)),
+    }
+}
+
+fn escape_properties(s: &str, is_key: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '=' | ':' | '#' | '!' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// 执行重载命令，仅记录结果
+async fn run_reload(cmd: &str) {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = tokio::process::Command::new("cmd.exe");
+        c.arg("/c").arg(cmd);
+        c
+    } else {
+        let mut c = tokio::process::Command::new("sh");
+        c.arg("-c").arg(cmd);
+        c
+    };
+    command.kill_on_drop(true);
+    match timeout(RELOAD_TIMEOUT, command.output()).await {
+        Ok(Ok(output)) if output.status.success() => {
+            agent_logger::info(&format!("配置重载命令执行成功: {}", cmd));
+        }
+        Ok(Ok(output)) => agent_logger::error(&format!(
+            "配置重载命令执行失败: {} exit={:?} stderr={}",
+            cmd,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Ok(Err(e)) => agent_logger::error(&format!("配置重载命令无法执行: {} {}", cmd, e)),
+        Err(_) => agent_logger::error(&format!("配置重载命令执行超时: {}", cmd)),
+    }
+}
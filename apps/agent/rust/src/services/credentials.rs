@@ -0,0 +1,119 @@
+use crate::utils::http_util;
+/// 实例令牌服务
+///
+/// 启动时加载本地缓存的实例令牌，缺失时使用注册密钥向服务端换取；
+/// 服务端返回 401 时重新注册，并按 `auth.token_rotate_hours` 周期轮换令牌。
+use crate::{agent_logger, services::AppState};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::time::{sleep, Duration};
+
+static ENROLLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Deserialize)]
+struct AgentTokenResponse {
+    token: String,
+    token_prefix: String,
+}
+
+/// 初始化实例令牌：优先使用本地缓存，否则尝试注册
+pub async fn init(state: &AppState) {
+    http_util::init(&state.cfg.server_url);
+    if let Some(token) = state.cfg.load_agent_token() {
+        http_util::set_agent_token(Some(token));
+        return;
+    }
+    enroll(state).await;
+}
+
+/// 启动令牌周期轮换
+pub async fn start(state: AppState) {
+    let hours = state.cfg.auth.token_rotate_hours;
+    if hours == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            sleep(Duration::from_secs(hours * 3600)).await;
+            rotate(&state).await;
+        }
+    });
+}
+
+/// 服务端拒绝令牌（401）时调用：清除本地令牌并使用注册密钥重新注册
+pub async fn on_unauthorized(state: &AppState) {
+    if ENROLLING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    agent_logger::warn("实例令牌无效或已吊销，尝试重新注册");
+    http_util::set_agent_token(None);
+    let _ = state.cfg.persist_agent_token(None);
+    enroll(state).await;
+    ENROLLING.store(false, Ordering::SeqCst);
+}
+
+async fn enroll(state: &AppState) -> bool {
+    let secret = match state.cfg.auth.enrollment_secret.as_deref() {
+        Some(s) if !s.is_empty() => s.to_string(),
+        _ => return false,
+    };
+    let url = format!("{}/api/open/instances/enroll", state.cfg.server_url);
+    let body = json!({
+        "application_code": state.cfg.application_code,
+        "agent_instance_id": state.cfg.agent_instance_id.clone().unwrap_or_default(),
+        "enrollment_secret": secret,
+    });
+    match http_util::post(url, &body).await {
+        Ok(resp) if resp.status().is_success() => store_token(state, resp).await,
+        Ok(resp) => {
+            let code = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            agent_logger::warn(&format!("实例注册失败 http={} body={}", code, body));
+            false
+        }
+        Err(e) => {
+            agent_logger::warn(&format!("实例注册请求失败: {}", e));
+            false
+        }
+    }
+}
+
+async fn rotate(state: &AppState) {
+    if http_util::agent_token().is_none() {
+        return;
+    }
+    let url = format!("{}/api/open/instances/token/rotate", state.cfg.server_url);
+    let body = json!({
+        "agent_instance_id": state.cfg.agent_instance_id.clone().unwrap_or_default(),
+    });
+    match http_util::post(url, &body).await {
+        Ok(resp) if resp.status().is_success() => {
+            store_token(state, resp).await;
+        }
+        Ok(resp) if resp.status().as_u16() == 401 => on_unauthorized(state).await,
+        Ok(resp) => {
+            agent_logger::warn(&format!("实例令牌轮换失败 http={}", resp.status().as_u16()));
+        }
+        Err(e) => {
+            agent_logger::warn(&format!("实例令牌轮换请求失败: {}", e));
+        }
+    }
+}
+
+async fn store_token(state: &AppState, resp: reqwest::Response) -> bool {
+    match resp.json::<AgentTokenResponse>().await {
+        Ok(body) => {
+            if let Err(e) = state.cfg.persist_agent_token(Some(&body.token)) {
+                agent_logger::warn(&format!("持久化实例令牌失败: {}", e));
+            }
+            http_util::set_agent_token(Some(body.token));
+            agent_logger::info(&format!("已获取实例令牌 {}", body.token_prefix));
+            true
+        }
+        Err(e) => {
+            agent_logger::warn(&format!("解析实例令牌响应失败: {}", e));
+            false
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::utils::http_util;
+/// 日志上报服务
+///
+/// 周期性从落盘缓冲中取一批日志发送至 `/api/open/instances/logs`，
+/// 服务端确认整批入库后才从缓冲中移除；失败时保留该批并按指数退避重试。
+/// 每条日志携带缓冲中的持久化 ID，响应丢失后重发时服务端据此去重。
+/// 返回 401 时重新注册实例令牌；请求体被拒绝（400/413/422）时丢弃该批，避免阻塞后续日志。
+use crate::{
+    agent_logger,
+    log_store::{AgentLogStore, LogBatch},
+    models::{AgentLogBatchRequest, AgentLogBatchResponse, AgentLogWire},
+    services::AppState,
+};
+use tokio::time::{sleep, Duration};
+
+/// Agent 自身日志与采集的应用日志分别缓冲，各自独立上报，互不阻塞
+pub async fn start(state: AppState) {
+    for logs in [state.logs.clone(), state.app_logs.clone()] {
+        tokio::spawn(run(state.clone(), logs));
+    }
+}
+
+async fn run(state: AppState, logs: AgentLogStore) {
+    let cfg = state.cfg.log_spool.clone();
+    let interval = cfg.flush_interval_seconds.max(1);
+    let mut failures: u32 = 0;
+    loop {
+        let batch = logs.peek_batch(cfg.batch_size);
+        if batch.items.is_empty() && batch.dropped == 0 {
+            sleep(Duration::from_secs(interval)).await;
+            continue;
+        }
+        match ship(&state, &batch).await {
+            Ok(()) => {
+                logs.ack(&batch);
+                failures = 0;
+                // 缓冲中仍有积压时立即发送下一批
+                if batch.items.len() < cfg.batch_size {
+                    sleep(Duration::from_secs(interval)).await;
+                }
+            }
+            Err(ShipError::Rejected(reason)) => {
+                logs.ack(&batch);
+                agent_logger::warn(&format!(
+                    "日志批次被服务端拒绝，已丢弃 {} 条: {}",
+                    batch.items.len(),
+                    reason
+                ));
+            }
+            Err(ShipError::Retry(reason)) => {
+                failures = failures.saturating_add(1);
+                let backoff = interval
+                    .saturating_mul(1u64 << failures.min(16))
+                    .min(cfg.max_backoff_seconds.max(interval));
+                // 仅首次失败写入日志，避免服务端不可用期间日志自我膨胀
+                if failures == 1 {
+                    agent_logger::warn(&format!("日志上报失败，{}s 后重试: {}", backoff, reason));
+                } else {
+                    log::debug!("日志上报第 {} 次失败: {}", failures, reason);
+                }
+                sleep(Duration::from_secs(backoff)).await;
+            }
+        }
+    }
+}
+
+enum ShipError {
+    /// 暂时性失败，保留该批稍后重试
+    Retry(String),
+    /// 请求体不被接受，重试无意义
+    Rejected(String),
+}
+
+async fn ship(state: &AppState, batch: &LogBatch) -> Result<(), ShipError> {
+    let req = AgentLogBatchRequest {
+        agent_instance_id: state.cfg.agent_instance_id.clone().unwrap_or_default(),
+        application_code: state.cfg.application_code.clone(),
+        batch_id: batch.batch_id.clone(),
+        logs: batch
+            .items
+            .iter()
+            .map(|item| AgentLogWire {
+                log_id: Some(item.id.to_string()),
+                log_level: normalize_log_level(&item.level),
+                message: item.message.clone(),
+                log_type: item.log_type.clone(),
+                context: item.context.clone(),
+                timestamp: Some(item.ts.to_rfc3339()),
+            })
+            .collect(),
+        dropped_count: batch.dropped,
+    };
+    let url = format!("{}/api/open/instances/logs", state.cfg.server_url);
+    let resp = http_util::post(url, &req)
+        .await
+        .map_err(|e| ShipError::Retry(e.to_string()))?;
+    let code = resp.status().as_u16();
+    match code {
+        200..=299 => {}
+        401 => {
+            crate::services::credentials::on_unauthorized(state).await;
+            return Err(ShipError::Retry("http=401".to_string()));
+        }
+        400 | 413 | 422 => {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ShipError::Rejected(format!("http={} body={}", code, body)));
+        }
+        _ => {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ShipError::Retry(format!("http={} body={}", code, body)));
+        }
+    }
+    let body = resp
+        .json::<AgentLogBatchResponse>()
+        .await
+        .map_err(|e| ShipError::Retry(format!("解析响应失败: {}", e)))?;
+    if body.log_failure_count > 0 || body.log_success_count as usize != batch.items.len() {
+        return Err(ShipError::Retry(format!(
+            "服务端写入失败 success={} failure={}",
+            body.log_success_count, body.log_failure_count
+        )));
+    }
+    Ok(())
+}
+
+fn normalize_log_level(s: &str) -> String {
+    match s.to_ascii_lowercase().as_str() {
+        "trace" => "trace".to_string(),
+        "debug" => "debug".to_string(),
+        "info" => "info".to_string(),
+        "warn" | "warning" => "warn".to_string(),
+        "error" => "error".to_string(),
+        "fatal" => "fatal".to_string(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,651 @@
+/// 应用日志文件采集服务
+///
+/// 按 `log_tail.files` 配置的通配路径跟踪应用日志文件，新增内容解析为日志事件后写入日志缓冲，
+/// 由日志上报服务与 Agent 日志分批上报（log_type=application）；应用日志使用独立的缓冲与条数上限。
+/// - 轮转：文件标识（inode）变化时先读完旧文件再从头读取新文件；文件变小视为被截断，从头读取
+/// - 偏移：已写入缓冲的位置持久化到本地，重启后从上次位置继续
+/// - 多行：堆栈等续行合并到上一事件，空闲超过 `MULTILINE_FLUSH` 后输出
+/// - 级别：文本按正则提取，JSON 行按字段读取，经 `level_map` 映射后归一为服务端级别
+use crate::{
+    agent_logger,
+    config::{LogFileConfig, LogFileFormat},
+    log_store::AgentLogStore,
+    models::AgentLogItem,
+    services::AppState,
+    utils::glob,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// 多行事件在无新内容时的最长等待时间
+const MULTILINE_FLUSH: Duration = Duration::from_secs(2);
+/// 单个事件最大字节数，超出部分截断
+const MAX_EVENT_BYTES: usize = 64 * 1024;
+/// 每个文件每轮最多读取的字节数，避免积压时长时间占用
+const MAX_READ_PER_SCAN: u64 = 4 * 1024 * 1024;
+
+/// 未配置 multiline_start 时，除缩进行外视为续行的行：异常类型行、Caused by、Python Traceback
+static DEFAULT_CONTINUATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(Caused by:|Suppressed:|Traceback \(most recent call last\)|[\w$.]*(Exception|Error|Throwable)(:|$))",
+    )
+    .unwrap()
+});
+
+pub async fn start(state: AppState) {
+    let cfg = state.cfg.log_tail.clone();
+    if !cfg.enabled || cfg.files.is_empty() {
+        return;
+    }
+    let rules: Vec<Rule> = cfg.files.iter().filter_map(Rule::compile).collect();
+    if rules.is_empty() {
+        return;
+    }
+    let interval = Duration::from_secs(cfg.scan_interval_seconds.max(1));
+    let offsets_path = state.cfg.log_offsets_path();
+    // 文件读取均为阻塞 IO，放在独立线程中循环执行
+    tokio::task::spawn_blocking(move || {
+        let mut tailer = Tailer::new(state.app_logs.clone(), rules, offsets_path);
+        loop {
+            tailer.scan();
+            std::thread::sleep(interval);
+        }
+    });
+}
+
+/// 编译后的采集规则
+struct Rule {
+    cfg: LogFileConfig,
+    multiline_start: Option<Regex>,
+    level_pattern: Regex,
+    level_map: HashMap<String, String>,
+}
+
+impl Rule {
+    fn compile(cfg: &LogFileConfig) -> Option<Self> {
+        let compile = |name: &str, pattern: &str| match Regex::new(pattern) {
+            Ok(re) => Some(re),
+            Err(e) => {
+                agent_logger::warn(&format!(
+                    "日志采集规则 {:?} 的 {} 无效，已跳过: {}",
+                    cfg.paths, name, e
+                ));
+                None
+            }
+        };
+        let multiline_start = match cfg.multiline_start.as_deref().filter(|p| !p.is_empty()) {
+            Some(p) => Some(compile("multiline_start", p)?),
+            None => None,
+        };
+        let level_pattern = compile(
+            "level_pattern",
+            cfg.level_pattern
+                .as_deref()
+                .filter(|p| !p.is_empty())
+                .unwrap_or(
+                    r"(?i)\b(?P<level>trace|debug|info|warn|warning|error|fatal|severe|critical)\b",
+                ),
+        )?;
+        Some(Self {
+            level_map: cfg
+                .level_map
+                .iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v.to_ascii_lowercase()))
+                .collect(),
+            cfg: cfg.clone(),
+            multiline_start,
+            level_pattern,
+        })
+    }
+
+    /// 是否为上一事件的续行
+    fn is_continuation(&self, line: &str) -> bool {
+        match &self.multiline_start {
+            Some(re) => !re.is_match(line),
+            None => line.starts_with([' ', '\t']) || DEFAULT_CONTINUATION.is_match(line),
+        }
+    }
+
+    /// 归一为服务端日志级别，无法识别时为 info
+    fn normalize_level(&self, raw: &str) -> String {
+        let raw = raw.trim().to_ascii_lowercase();
+        let mapped = self.level_map.get(&raw).cloned().unwrap_or(raw);
+        let level = match mapped.as_str() {
+            "trace" | "finest" | "verbose" | "10" => "trace",
+            "debug" | "fine" | "finer" | "20" => "debug",
+            "info" | "information" | "notice" | "30" => "info",
+            "warn" | "warning" | "40" => "warn",
+            "error" | "err" | "severe" | "50" => "error",
+            "fatal" | "critical" | "crit" | "panic" | "60" => "fatal",
+            _ => "info",
+        };
+        level.to_string()
+    }
+
+    fn text_level(&self, line: &str) -> String {
+        let head: String = line.chars().take(256).collect();
+        let raw = self.level_pattern.captures(&head).and_then(|c| {
+            c.name("level")
+                .or_else(|| c.get(1))
+                .or_else(|| c.get(0))
+                .map(|m| m.as_str().to_string())
+        });
+        self.normalize_level(raw.as_deref().unwrap_or("info"))
+    }
+}
+
+/// 已解析的日志事件
+struct Event {
+    level: String,
+    message: String,
+    ts: DateTime<Utc>,
+    fields: Option<Value>,
+}
+
+/// 正在合并的多行事件
+struct Pending {
+    lines: Vec<String>,
+    bytes: usize,
+    /// 该事件占用的原始字节数（含换行），用于计算已提交偏移
+    raw_bytes: u64,
+    ts: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedOffset {
+    file_id: u64,
+    offset: u64,
+}
+
+struct TailedFile {
+    rule: usize,
+    file: File,
+    file_id: u64,
+    /// 已读取到的位置
+    offset: u64,
+    /// 末尾未以换行结束的内容
+    partial: Vec<u8>,
+    pending: Option<Pending>,
+    last_data_at: Instant,
+}
+
+impl TailedFile {
+    /// 已写入日志缓冲的位置（不含未完成的行与多行事件）
+    fn committed(&self) -> u64 {
+        let pending = self.pending.as_ref().map(|p| p.raw_bytes).unwrap_or(0);
+        self.offset - self.partial.len() as u64 - pending
+    }
+}
+
+struct Tailer {
+    logs: AgentLogStore,
+    rules: Vec<Rule>,
+    files: HashMap<PathBuf, TailedFile>,
+    saved: HashMap<String, SavedOffset>,
+    offsets_path: PathBuf,
+    /// 轮转时被删除、尚未重建的文件，重建后从头读取
+    rotated: HashSet<PathBuf>,
+    last_saved: String,
+}
+
+impl Tailer {
+    fn new(logs: AgentLogStore, rules: Vec<Rule>, offsets_path: PathBuf) -> Self {
+        let saved = fs::read_to_string(&offsets_path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default();
+        Self {
+            logs,
+            rules,
+            files: HashMap::new(),
+            saved,
+            offsets_path,
+            rotated: HashSet::new(),
+            last_saved: String::new(),
+        }
+    }
+
+    fn scan(&mut self) {
+        let mut matched: HashMap<PathBuf, usize> = HashMap::new();
+        for (idx, rule) in self.rules.iter().enumerate() {
+            for pattern in &rule.cfg.paths {
+                for path in glob::expand(pattern) {
+                    matched.entry(path).or_insert(idx);
+                }
+            }
+        }
+
+        // 已跟踪文件：检查轮转/截断并读取新增内容
+        let tracked: Vec<PathBuf> = self.files.keys().cloned().collect();
+        for path in tracked {
+            let meta = fs::metadata(&path).ok();
+            let rotated = match &meta {
+                Some(m) => file_id(m) != self.files[&path].file_id,
+                None => true,
+            };
+            if rotated {
+                // 旧句柄仍指向轮转前的文件，读完剩余内容后关闭
+                let mut tf = self.files.remove(&path).unwrap();
+                self.read_new(&path, &mut tf);
+                self.flush_pending(&path, &mut tf);
+                self.saved.remove(&path.to_string_lossy().to_string());
+                match matched.get(&path) {
+                    Some(&rule) if meta.is_some() => self.open(&path, rule, true),
+                    _ => {
+                        self.rotated.insert(path);
+                    }
+                }
+                continue;
+            }
+            let mut tf = self.files.remove(&path).unwrap();
+            if meta.as_ref().is_some_and(|m| m.len() < tf.offset) {
+                agent_logger::info(&format!("日志文件被截断，从头读取: {}", path.display()));
+                self.flush_pending(&path, &mut tf);
+                tf.partial.clear();
+                tf.offset = 0;
+                let _ = tf.file.seek(SeekFrom::Start(0));
+            }
+            self.read_new(&path, &mut tf);
+            if tf.last_data_at.elapsed() >= MULTILINE_FLUSH {
+                self.flush_pending(&path, &mut tf);
+            }
+            if matched.contains_key(&path) {
+                self.files.insert(path, tf);
+            } else {
+                self.flush_pending(&path, &mut tf);
+            }
+        }
+
+        // 新发现的文件
+        for (path, rule) in matched {
+            if !self.files.contains_key(&path) {
+                let rotated = self.rotated.remove(&path);
+                self.open(&path, rule, rotated);
+            }
+        }
+
+        self.save_offsets();
+    }
+
+    /// 开始跟踪文件；`rotated` 表示轮转后新建的文件，从头读取
+    fn open(&mut self, path: &Path, rule: usize, rotated: bool) {
+        let Ok(mut file) = File::open(path) else {
+            return;
+        };
+        let Ok(meta) = file.metadata() else {
+            return;
+        };
+        let id = file_id(&meta);
+        let key = path.to_string_lossy().to_string();
+        let offset = match self.saved.get(&key) {
+            _ if rotated => 0,
+            Some(s) if s.file_id == id && s.offset <= meta.len() => s.offset,
+            // 停机期间发生了轮转，新文件从头读取
+            Some(_) => 0,
+            None if self.rules[rule].cfg.from_beginning => 0,
+            None => meta.len(),
+        };
+        if file.seek(SeekFrom::Start(offset)).is_err() {
+            return;
+        }
+        agent_logger::info(&format!(
+            "开始采集日志文件: {} offset={}",
+            path.display(),
+            offset
+        ));
+        let mut tf = TailedFile {
+            rule,
+            file,
+            file_id: id,
+            offset,
+            partial: Vec::new(),
+            pending: None,
+            last_data_at: Instant::now(),
+        };
+        self.read_new(path, &mut tf);
+        self.files.insert(path.to_path_buf(), tf);
+    }
+
+    fn read_new(&self, path: &Path, tf: &mut TailedFile) {
+        let mut buf = Vec::new();
+        let read = (&mut tf.file)
+            .take(MAX_READ_PER_SCAN)
+            .read_to_end(&mut buf)
+            .unwrap_or(0);
+        if read == 0 {
+            return;
+        }
+        tf.offset += read as u64;
+        tf.last_data_at = Instant::now();
+        tf.partial.extend_from_slice(&buf);
+        let Some(last_nl) = tf.partial.iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        let rest = tf.partial.split_off(last_nl + 1);
+        let complete = std::mem::replace(&mut tf.partial, rest);
+        for raw in complete.split_inclusive(|b| *b == b'\n') {
+            let line = String::from_utf8_lossy(raw);
+            let line = line.trim_end_matches(['\r', '\n']);
+            self.handle_line(path, tf, line, raw.len() as u64);
+        }
+    }
+
+    fn handle_line(&self, path: &Path, tf: &mut TailedFile, line: &str, raw_len: u64) {
+        let rule = &self.rules[tf.rule];
+        if rule.cfg.format == LogFileFormat::Json {
+            if let Some(event) = parse_json_line(rule, line) {
+                self.flush_pending(path, tf);
+                self.emit(path, event);
+                return;
+            }
+        }
+        match tf.pending.as_mut() {
+            Some(p) if rule.is_continuation(line) || line.is_empty() => {
+                if p.bytes < MAX_EVENT_BYTES {
+                    p.bytes += line.len() + 1;
+                    p.lines.push(line.to_string());
+                }
+                p.raw_bytes += raw_len;
+            }
+            _ if line.is_empty() => {}
+            _ => {
+                self.flush_pending(path, tf);
+                tf.pending = Some(Pending {
+                    lines: vec![line.to_string()],
+                    bytes: line.len(),
+                    raw_bytes: raw_len,
+                    ts: Utc::now(),
+                });
+            }
+        }
+    }
+
+    fn flush_pending(&self, path: &Path, tf: &mut TailedFile) {
+        let Some(p) = tf.pending.take() else {
+            return;
+        };
+        let rule = &self.rules[tf.rule];
+        let mut message = p.lines.join("\n");
+        truncate_utf8(&mut message, MAX_EVENT_BYTES);
+        self.emit(
+            path,
+            Event {
+                level: rule.text_level(&p.lines[0]),
+                message,
+                ts: p.ts,
+                fields: None,
+            },
+        );
+    }
+
+    fn emit(&self, path: &Path, event: Event) {
+        let mut context = json!({ "file": path.to_string_lossy() });
+        if let Some(fields) = event.fields {
+            context["fields"] = fields;
+        }
+        self.logs.push_item(AgentLogItem {
+            id: Uuid::new_v4(),
+            level: event.level,
+            message: event.message,
+            ts: event.ts,
+            log_type: Some("application".to_string()),
+            context: Some(context),
+        });
+    }
+
+    fn save_offsets(&mut self) {
+        let live: HashSet<String> = self
+            .files
+            .keys()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        for (path, tf) in &self.files {
+            self.saved.insert(
+                path.to_string_lossy().to_string(),
+                SavedOffset {
+                    file_id: tf.file_id,
+                    offset: tf.committed(),
+                },
+            );
+        }
+        // 不再匹配的文件保留记录，文件已不存在的删除
+        self.saved
+            .retain(|k, _| live.contains(k) || Path::new(k).exists());
+        // 轮转后一直未重建的文件无需长期记录
+        if self.rotated.len() > 1024 {
+            self.rotated.clear();
+        }
+        let Ok(content) = serde_json::to_string(&self.saved) else {
+            return;
+        };
+        if content != self.last_saved {
+            let tmp = self.offsets_path.with_extension("json.tmp");
+            if fs::write(&tmp, &content).is_ok() {
+                let _ = fs::rename(&tmp, &self.offsets_path);
+                self.last_saved = content;
+            }
+        }
+    }
+}
+
+/// 解析 JSON 行，非对象时返回 None 按文本处理
+fn parse_json_line(rule: &Rule, line: &str) -> Option<Event> {
+    let Value::Object(mut obj) = serde_json::from_str::<Value>(line).ok()? else {
+        return None;
+    };
+    let cfg = &rule.cfg;
+    let level = match obj.remove(&cfg.level_field) {
+        Some(Value::String(s)) => rule.normalize_level(&s),
+        Some(Value::Number(n)) => rule.normalize_level(&n.to_string()),
+        _ => "info".to_string(),
+    };
+    let message = obj
+        .remove(&cfg.message_field)
+        .or_else(|| obj.remove("msg"))
+        .map(|v| match v {
+            Value::String(s) => s,
+            other => other.to_string(),
+        })
+        .unwrap_or_else(|| line.to_string());
+    let ts = [cfg.timestamp_field.as_str(), "time", "@timestamp"]
+        .iter()
+        .find_map(|k| obj.remove(*k))
+        .and_then(|v| parse_timestamp(&v))
+        .unwrap_or_else(Utc::now);
+    let mut message = message;
+    truncate_utf8(&mut message, MAX_EVENT_BYTES);
+    Some(Event {
+        level,
+        message,
+        ts,
+        fields: (!obj.is_empty()).then_some(Value::Object(obj)),
+    })
+}
+
+/// 支持 RFC3339 字符串与秒/毫秒时间戳
+fn parse_timestamp(v: &Value) -> Option<DateTime<Utc>> {
+    match v {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc)),
+        Value::Number(n) => {
+            let n = n.as_f64()?;
+            let ms = if n > 1e12 { n } else { n * 1000.0 };
+            Utc.timestamp_millis_opt(ms as i64).single()
+        }
+        _ => None,
+    }
+}
+
+fn truncate_utf8(s: &mut String, max: usize) {
+    if s.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s.truncate(end);
+}
+
+/// 文件标识：Unix 下为 inode，其他平台以创建时间近似
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(meta: &Metadata) -> u64 {
+    meta.created()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn new_tailer(dir: &Path, format: LogFileFormat, logs: AgentLogStore) -> Tailer {
+        let cfg = LogFileConfig {
+            paths: vec![dir.join("*.log").to_string_lossy().to_string()],
+            format,
+            from_beginning: true,
+            ..Default::default()
+        };
+        let rules = vec![Rule::compile(&cfg).unwrap()];
+        Tailer::new(logs, rules, dir.join("offsets.json"))
+    }
+
+    fn setup(format: LogFileFormat) -> (PathBuf, Tailer) {
+        let dir = std::env::temp_dir().join(format!("monihub-tail-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let logs = AgentLogStore::open(dir.join("spool.jsonl"), 100);
+        let tailer = new_tailer(&dir, format, logs);
+        (dir, tailer)
+    }
+
+    fn append(path: &Path, content: &str) {
+        let mut f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        f.write_all(content.as_bytes()).unwrap();
+    }
+
+    /// 取出并确认已写入缓冲的事件
+    fn drain(tailer: &Tailer) -> Vec<(String, String)> {
+        let batch = tailer.logs.peek_batch(100);
+        tailer.logs.ack(&batch);
+        batch
+            .items
+            .into_iter()
+            .map(|i| (i.level, i.message))
+            .collect()
+    }
+
+    fn messages(tailer: &Tailer) -> Vec<String> {
+        drain(tailer).into_iter().map(|(_, m)| m).collect()
+    }
+
+    #[test]
+    fn test_partial_line_waits_for_newline() {
+        let (dir, mut tailer) = setup(LogFileFormat::Json);
+        let path = dir.join("app.log");
+        let first = "{\"message\":\"one\",\"level\":\"ERROR\"}\n";
+        append(&path, &format!("{}{{\"message\":\"tw", first));
+        tailer.scan();
+        assert_eq!(drain(&tailer), [("error".into(), "one".into())]);
+        // 未完成的行不计入已提交偏移
+        let key = path.to_string_lossy().to_string();
+        assert_eq!(tailer.saved[&key].offset, first.len() as u64);
+
+        append(&path, "o\"}\n");
+        tailer.scan();
+        assert_eq!(messages(&tailer), ["two"]);
+
+        // 重启后从记录的偏移继续
+        append(&path, "{\"message\":\"three\"}\n");
+        let mut restarted = new_tailer(&dir, LogFileFormat::Json, tailer.logs.clone());
+        restarted.scan();
+        assert_eq!(messages(&restarted), ["three"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_multiline_merge() {
+        let (dir, mut tailer) = setup(LogFileFormat::Text);
+        let path = dir.join("app.log");
+        append(
+            &path,
+            "2024-01-01 ERROR boom\n\tat a.b(C.java:1)\nCaused by: java.io.IOException\n2024-01-01 INFO next\n",
+        );
+        tailer.scan();
+        assert_eq!(
+            drain(&tailer),
+            [(
+                "error".into(),
+                "2024-01-01 ERROR boom\n\tat a.b(C.java:1)\nCaused by: java.io.IOException".into()
+            )]
+        );
+        // 最后一个事件仍在合并中，偏移停在其起始处
+        let key = path.to_string_lossy().to_string();
+        let len = fs::metadata(&path).unwrap().len();
+        let pending = "2024-01-01 INFO next\n".len() as u64;
+        assert_eq!(tailer.saved[&key].offset, len - pending);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rotation_by_inode() {
+        let (dir, mut tailer) = setup(LogFileFormat::Json);
+        let path = dir.join("app.log");
+        append(&path, "{\"message\":\"1\"}\n");
+        tailer.scan();
+        assert_eq!(messages(&tailer), ["1"]);
+
+        // 轮转后旧文件仍有写入，新文件从头读取
+        let rotated = dir.join("app.log.1");
+        fs::rename(&path, &rotated).unwrap();
+        append(&rotated, "{\"message\":\"2\"}\n");
+        append(&path, "{\"message\":\"3\"}\n");
+        tailer.scan();
+        assert_eq!(messages(&tailer), ["2", "3"]);
+
+        // 删除后稍后重建的文件同样从头读取
+        fs::remove_file(&path).unwrap();
+        tailer.scan();
+        append(&path, "{\"message\":\"4\"}\n");
+        tailer.scan();
+        assert_eq!(messages(&tailer), ["4"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_truncate_reads_from_start() {
+        let (dir, mut tailer) = setup(LogFileFormat::Json);
+        let path = dir.join("app.log");
+        append(&path, "{\"message\":\"first\"}\n{\"message\":\"second\"}\n");
+        tailer.scan();
+        assert_eq!(messages(&tailer), ["first", "second"]);
+
+        fs::write(&path, "{\"message\":\"x\"}\n").unwrap();
+        tailer.scan();
+        assert_eq!(messages(&tailer), ["x"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
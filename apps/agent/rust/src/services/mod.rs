@@ -1,16 +1,26 @@
 /// 服务模块入口
 ///
-/// 提供应用状态与各类服务（上报、任务）的统一管理入口。
-use crate::{config::Config, log_store::AgentLogStore};
+/// 提供应用状态与各类服务（实例令牌、上报、任务、终端、配置订阅）的统一管理入口。
+use crate::{
+    config::{Config, RuntimeConfig},
+    log_store::AgentLogStore,
+};
 use once_cell::sync::OnceCell;
-use std::sync::{atomic::AtomicBool, Arc};
-
+use std::sync::{atomic::AtomicBool, Arc, RwLock};
 
 #[derive(Clone)]
 /// 应用全局状态：包含配置、日志存储与 HTTP 可用标志
+///
+/// `logs` 缓冲 Agent 自身日志，`app_logs` 缓冲采集的应用日志，两者分别限制条数，
+/// 应用日志量大时不会挤掉 Agent 自身日志。
+///
+/// `cfg` 为启动时加载的本地配置；上报间隔、任务拉取、代理与调试开关等
+/// 可由服务端下发的配置项从 `runtime` 读取。
 pub struct AppState {
     pub cfg: Config,
+    pub runtime: Arc<RwLock<RuntimeConfig>>,
     pub logs: AgentLogStore,
+    pub app_logs: AgentLogStore,
     pub http_enabled: Arc<AtomicBool>,
     pub public_ip: Arc<OnceCell<String>>,
 }
@@ -18,13 +28,31 @@ impl AppState {
     /// 构建新的应用状态实例
     pub fn new(cfg: Config) -> Self {
         Self {
+            runtime: Arc::new(RwLock::new(RuntimeConfig::from_config(&cfg))),
+            logs: AgentLogStore::open(cfg.log_spool_path(), cfg.log_spool.max_entries),
+            app_logs: AgentLogStore::open(cfg.app_log_spool_path(), cfg.log_tail.spool_max_entries),
             cfg,
-            logs: AgentLogStore::new(),
             http_enabled: Arc::new(AtomicBool::new(true)),
             public_ip: Arc::new(OnceCell::new()),
         }
     }
+    /// 当前生效的运行时配置快照
+    pub fn runtime(&self) -> RuntimeConfig {
+        self.runtime.read().unwrap().clone()
+    }
+    /// 是否开启调试日志
+    pub fn debug_enabled(&self) -> bool {
+        self.runtime.read().unwrap().debug
+    }
 }
 
+pub mod config_watch;
+pub mod credentials;
+pub mod log_shipper;
+pub mod log_tailer;
+pub mod remote_config;
 pub mod report;
+pub mod self_update;
+pub mod task_output;
 pub mod tasks;
+pub mod terminal;
@@ -0,0 +1,260 @@
+/// 服务端下发配置热加载
+///
+/// 上报响应携带实例配置及其版本号，版本变化时解析并应用到运行时配置：
+/// 上报间隔、任务拉取开关与长轮询超时、HTTP 代理以及调试日志级别。
+/// 生效的版本号在下一次上报中回传，服务端据此展示配置待生效/已生效。
+///
+/// 配置只能随上报响应下发，因此忽略服务端关闭上报的指令，否则 Agent 再也收不到后续配置；
+/// 代理仅在服务端显式设置 `proxy_enabled` 时覆盖，未设置时沿用本地代理配置。
+use crate::{
+    agent_logger,
+    config::{HttpConfig, RuntimeConfig},
+    models::InstanceReportResponse,
+    services::AppState,
+    utils::http_util,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+/// 服务端实例配置（与服务端 InstanceConfig 对齐，缺失字段保持本地值）
+struct RemoteConfig {
+    debug: Option<bool>,
+    report: RemoteReportConfig,
+    task: RemoteTaskConfig,
+    http: Option<RemoteHttpConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RemoteReportConfig {
+    enabled: Option<bool>,
+    interval_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RemoteTaskConfig {
+    enabled: Option<bool>,
+    long_poll_timeout_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RemoteHttpConfig {
+    proxy_enabled: Option<bool>,
+    proxy_host: Option<String>,
+    proxy_port: Option<u16>,
+    proxy_username: Option<String>,
+    proxy_password: Option<String>,
+}
+
+impl RemoteHttpConfig {
+    /// 由 host/port 拼接代理地址，未带协议时按 http 处理
+    fn proxy_url(&self) -> Option<String> {
+        let host = self
+            .proxy_host
+            .as_deref()
+            .map(str::trim)
+            .filter(|h| !h.is_empty())?;
+        let mut url = if host.contains("://") {
+            host.to_string()
+        } else {
+            format!("http://{}", host)
+        };
+        if let Some(port) = self.proxy_port {
+            url = format!("{}:{}", url.trim_end_matches('/'), port);
+        }
+        Some(url)
+    }
+
+    /// 服务端未设置 `proxy_enabled` 时保持当前代理配置不变
+    fn into_http_config(self, current: &HttpConfig) -> HttpConfig {
+        let Some(proxy_enabled) = self.proxy_enabled else {
+            return current.clone();
+        };
+        HttpConfig {
+            proxy_enabled,
+            proxy_url: self.proxy_url(),
+            proxy_username: self.proxy_username,
+            proxy_password: self.proxy_password,
+            verify_tls: current.verify_tls,
+        }
+    }
+}
+
+/// 处理上报响应中的配置下发，版本未变化时忽略
+pub fn on_report_response(state: &AppState, resp: InstanceReportResponse) {
+    let (Some(revision), Some(config)) = (resp.config_revision, resp.config) else {
+        return;
+    };
+    let applied = {
+        let mut guard = state.runtime.write().unwrap();
+        match apply_revision(&mut guard, revision, config) {
+            Ok(true) => guard.clone(),
+            Ok(false) => return,
+            Err(e) => {
+                agent_logger::warn(&format!("解析服务端配置失败 revision={}: {}", revision, e));
+                return;
+            }
+        }
+    };
+    http_util::set_http_config(&applied.http);
+    agent_logger::set_debug(applied.debug);
+    agent_logger::warn(&format!(
+        "已应用服务端配置 revision={} report={}/{}s task={}/{}s proxy={} debug={}",
+        revision,
+        applied.report.enabled,
+        applied.report.interval_seconds,
+        applied.task.enabled,
+        applied.task.long_poll_timeout_seconds,
+        applied.http.proxy_enabled,
+        applied.debug
+    ));
+}
+
+/// 解析并应用指定版本的配置，返回是否发生变更；解析失败时保持原配置与版本不变
+fn apply_revision(
+    rt: &mut RuntimeConfig,
+    revision: i32,
+    config: serde_json::Value,
+) -> Result<bool, serde_json::Error> {
+    if rt.revision == Some(revision) {
+        return Ok(false);
+    }
+    let remote: RemoteConfig = serde_json::from_value(config)?;
+    apply(rt, remote);
+    rt.revision = Some(revision);
+    Ok(true)
+}
+
+fn apply(rt: &mut RuntimeConfig, remote: RemoteConfig) {
+    if let Some(debug) = remote.debug {
+        rt.debug = debug;
+    }
+    // 上报是接收配置的唯一通道，关闭后无法再恢复，仅记录告警
+    if remote.report.enabled == Some(false) {
+        agent_logger::warn("忽略服务端下发的 report.enabled=false，上报保持开启以接收后续配置");
+    }
+    if let Some(secs) = remote.report.interval_seconds.filter(|s| *s > 0) {
+        rt.report.interval_seconds = secs;
+    }
+    if let Some(enabled) = remote.task.enabled {
+        rt.task.enabled = enabled;
+    }
+    if let Some(secs) = remote.task.long_poll_timeout_seconds.filter(|s| *s > 0) {
+        rt.task.long_poll_timeout_seconds = secs;
+    }
+    if let Some(http) = remote.http {
+        rt.http = http.into_http_config(&rt.http);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ReportConfig, TaskConfig};
+    use serde_json::json;
+
+    fn runtime() -> RuntimeConfig {
+        RuntimeConfig {
+            revision: None,
+            debug: false,
+            report: ReportConfig::default(),
+            task: TaskConfig::default(),
+            http: HttpConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_apply_keeps_missing_fields() {
+        let mut rt = runtime();
+        let remote: RemoteConfig = serde_json::from_value(json!({
+            "debug": true,
+            "report": { "enabled": false, "interval_seconds": 60 },
+            "task": { "enabled": false, "long_poll_timeout_seconds": 0 },
+        }))
+        .unwrap();
+        apply(&mut rt, remote);
+        assert!(rt.debug);
+        // 服务端不能关闭上报
+        assert!(rt.report.enabled);
+        assert_eq!(rt.report.interval_seconds, 60);
+        assert!(!rt.task.enabled);
+        // 0 视为未设置
+        assert_eq!(rt.task.long_poll_timeout_seconds, 30);
+        assert!(!rt.http.proxy_enabled);
+    }
+
+    #[test]
+    fn test_apply_http_proxy() {
+        let mut rt = runtime();
+        rt.http.verify_tls = false;
+        let remote: RemoteConfig = serde_json::from_value(json!({
+            "http": {
+                "proxy_enabled": true,
+                "proxy_host": " proxy.local/ ",
+                "proxy_port": 3128,
+                "proxy_username": "u",
+            },
+        }))
+        .unwrap();
+        apply(&mut rt, remote);
+        assert!(rt.http.proxy_enabled);
+        assert_eq!(
+            rt.http.proxy_url.as_deref(),
+            Some("http://proxy.local:3128")
+        );
+        assert_eq!(rt.http.proxy_username.as_deref(), Some("u"));
+        // TLS 校验只由本地配置决定
+        assert!(!rt.http.verify_tls);
+
+        let https = RemoteHttpConfig {
+            proxy_host: Some("https://proxy.local".into()),
+            ..Default::default()
+        };
+        assert_eq!(https.proxy_url().as_deref(), Some("https://proxy.local"));
+        assert_eq!(RemoteHttpConfig::default().proxy_url(), None);
+    }
+
+    #[test]
+    fn test_apply_keeps_local_proxy_when_unset() {
+        let mut rt = runtime();
+        rt.http.proxy_enabled = true;
+        rt.http.proxy_url = Some("http://local:8080".into());
+        let remote: RemoteConfig = serde_json::from_value(json!({
+            "http": { "proxy_host": "proxy.local" },
+        }))
+        .unwrap();
+        apply(&mut rt, remote);
+        assert!(rt.http.proxy_enabled);
+        assert_eq!(rt.http.proxy_url.as_deref(), Some("http://local:8080"));
+
+        // 显式关闭时覆盖本地代理
+        let remote: RemoteConfig = serde_json::from_value(json!({
+            "http": { "proxy_enabled": false },
+        }))
+        .unwrap();
+        apply(&mut rt, remote);
+        assert!(!rt.http.proxy_enabled);
+        assert_eq!(rt.http.proxy_url, None);
+    }
+
+    #[test]
+    fn test_apply_revision() {
+        let mut rt = runtime();
+        let config = json!({ "report": { "interval_seconds": 10 } });
+        assert!(apply_revision(&mut rt, 3, config.clone()).unwrap());
+        assert_eq!(rt.revision, Some(3));
+        assert_eq!(rt.report.interval_seconds, 10);
+
+        // 同一版本不重复应用
+        rt.report.interval_seconds = 99;
+        assert!(!apply_revision(&mut rt, 3, config).unwrap());
+        assert_eq!(rt.report.interval_seconds, 99);
+
+        // 解析失败时保留原版本，下次仍会重试
+        assert!(apply_revision(&mut rt, 4, json!({ "debug": "yes" })).is_err());
+        assert_eq!(rt.revision, Some(3));
+    }
+}
@@ -1,32 +1,37 @@
-use crate::utils::http_util;
+use crate::utils::{http_util, redaction};
 /// 实例信息上报服务
 ///
 /// 周期性采集系统/网络/硬件/运行时信息，构造上报请求并提交至服务端。
-/// 当服务端返回 403 时，标记 `http_enabled=false`，以便任务服务暂停轮询。
+/// 指标由常驻的 `MetricsSampler` 采集，扩展指标写入 `custom_metrics`（见 metrics 模块）。
+/// 当服务端返回 403 时，标记 `http_enabled=false`，以便任务服务暂停轮询；
+/// 返回 401 时重新注册实例令牌；成功响应中携带的配置交由 remote_config 热加载。
 use crate::{
     agent_logger,
+    metrics::MetricsSampler,
     models::{
-        AgentLogWire, HardwareInfo, InstanceReportRequest, NetworkInfo, RuntimeInfo, SystemInfo,
+        HardwareInfo, InstanceReportRequest, InstanceReportResponse, NetworkInfo, RuntimeInfo,
+        SystemInfo,
     },
-    services::AppState,
+    services::{remote_config, self_update, AppState},
 };
 use chrono::Utc;
 use gethostname::gethostname;
 use os_info::Type;
 use serde_json::json;
-use sysinfo::{Disks, System};
 use tokio::time::{sleep, Duration};
 
 pub async fn start(state: AppState) {
-    let interval = state.cfg.report.interval_seconds;
     if let Some(ip) = fetch_public_ip().await {
         let _ = state.public_ip.set(ip);
     }
     tokio::spawn(async move {
+        let mut sampler = MetricsSampler::new(state.cfg.monitor.clone());
         loop {
-            if state.cfg.report.enabled {
+            // 每轮读取运行时配置，服务端下发的开关与间隔在下一轮生效
+            let report_cfg = state.runtime().report;
+            if report_cfg.enabled {
                 agent_logger::info("准备进行实例信息上报");
-                let req = build_report(&state).await;
+                let req = build_report(&state, &mut sampler).await;
                 let url = format!("{}/api/open/instances/report", state.cfg.server_url);
                 let res = http_util::post(url, &req).await;
                 match res {
@@ -39,6 +44,13 @@ pub async fn start(state: AppState) {
                                 .store(true, std::sync::atomic::Ordering::SeqCst);
 
                             agent_logger::info(&format!("上报成功 http={}", code));
+                            self_update::mark_reported();
+                            match r.json::<InstanceReportResponse>().await {
+                                Ok(resp) => remote_config::on_report_response(&state, resp),
+                                Err(e) => agent_logger::warn(&format!("解析上报响应失败: {}", e)),
+                            }
+                        } else if code == 401 {
+                            crate::services::credentials::on_unauthorized(&state).await;
                         } else {
                             state
                                 .http_enabled
@@ -52,15 +64,13 @@ pub async fn start(state: AppState) {
                     }
                 }
             }
-            sleep(Duration::from_secs(interval)).await;
+            sleep(Duration::from_secs(report_cfg.interval_seconds)).await;
         }
     });
 }
 
 /// 构造上报请求体（字段命名与 Java Agent 对齐）
-async fn build_report(state: &AppState) -> InstanceReportRequest {
-    let mut sys = sysinfo::System::new_all();
-    sys.refresh_all();
+async fn build_report(state: &AppState, sampler: &mut MetricsSampler) -> InstanceReportRequest {
     let os = os_info::get();
     let system = SystemInfo {
         os_type: format_os(os.os_type()),
@@ -71,7 +81,7 @@ async fn build_report(state: &AppState) -> InstanceReportRequest {
     let ips = local_ips();
     let ip_primary = pick_primary_ip(&ips);
     let macs_list = macs();
-    let mac_primary = macs_list.get(0).cloned();
+    let mac_primary = macs_list.first().cloned();
     let public_ip = state.public_ip.get().cloned();
     let network = NetworkInfo {
         ip_address: ip_primary,
@@ -81,98 +91,55 @@ async fn build_report(state: &AppState) -> InstanceReportRequest {
         port: None,
     };
 
-    let cpu_usage = 0.0f64;
-    let total_mb = (sys.total_memory() / 1024) as i64;
-    let used_mb = (sys.used_memory() / 1024) as i64;
-    let mem_pct = if total_mb > 0 {
-        (used_mb as f64) * 100.0 / (total_mb as f64)
-    } else {
-        0.0
-    };
-    let disks = Disks::new_with_refreshed_list();
-    let mut disk_total_bytes: i128 = 0;
-    let mut disk_used_bytes: i128 = 0;
-    for d in disks.list().iter() {
-        let total = d.total_space() as i128;
-        let avail = d.available_space() as i128;
-        disk_total_bytes += total;
-        disk_used_bytes += (total - avail).max(0);
-    }
-    let gb_div = 1024_i128 * 1024_i128 * 1024_i128;
-    let disk_total_gb = (disk_total_bytes / gb_div) as i64;
-    let disk_used_gb = (disk_used_bytes / gb_div) as i64;
-    let disk_pct = if disk_total_gb > 0 {
-        (disk_used_gb as f64) * 100.0 / (disk_total_gb as f64)
-    } else {
-        0.0
+    let m = sampler.sample();
+    let pct = |used: i64, total: i64| {
+        if total > 0 {
+            (used as f64) * 100.0 / (total as f64)
+        } else {
+            0.0
+        }
     };
-
-    let cpu_model = Some(
-        sys.cpus()
-            .iter()
-            .map(|c| c.brand().to_string())
-            .collect::<Vec<_>>()
-            .join(","),
-    );
-    let cpu_cores = Some(sys.cpus().len() as i32);
     let hardware = HardwareInfo {
-        cpu_model,
-        cpu_cores,
-        cpu_usage_percent: cpu_usage,
-        memory_total_mb: total_mb,
-        memory_used_mb: used_mb,
-        memory_usage_percent: mem_pct,
-        disk_total_gb,
-        disk_used_gb,
-        disk_usage_percent: disk_pct,
+        cpu_model: m.cpu_model,
+        cpu_cores: m.cpu_cores,
+        cpu_usage_percent: m.cpu_usage_percent,
+        memory_total_mb: m.memory_total_mb,
+        memory_used_mb: m.memory_used_mb,
+        memory_usage_percent: pct(m.memory_used_mb, m.memory_total_mb),
+        disk_total_gb: m.disk_total_gb,
+        disk_used_gb: m.disk_used_gb,
+        disk_usage_percent: pct(m.disk_used_gb, m.disk_total_gb),
     };
 
     let runtime = RuntimeInfo {
         process_id: Some(std::process::id() as i32),
-        process_uptime_seconds: System::uptime() as i64,
-        thread_count: Some(0),
+        process_uptime_seconds: m.process_uptime_seconds,
+        thread_count: m.thread_count,
     };
     let program_path = std::env::current_exe()
         .ok()
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_default();
 
-    let environment_obj = {
-        let mut map = serde_json::Map::new();
-        for (k, v) in std::env::vars() {
-            map.insert(k, json!(v));
-        }
-        serde_json::Value::Object(map)
-    };
-
-    let agent_logs_wire: Vec<AgentLogWire> = state
-        .logs
-        .snapshot_and_clear()
-        .into_iter()
-        .map(|item| AgentLogWire {
-            log_level: normalize_log_level(&item.level),
-            message: item.message,
-            context: None,
-            timestamp: Some(item.ts.to_rfc3339()),
-        })
-        .collect();
+    let environment_obj = redaction::environment_snapshot(&state.cfg.environment);
 
     InstanceReportRequest {
         agent_instance_id: state.cfg.agent_instance_id.clone().unwrap_or_default(),
         application_code: state.cfg.application_code.clone(),
         agent_type: normalize_agent_type(&state.cfg.agent_type),
         agent_version: state.cfg.agent_version.clone(),
-        program_path: program_path,
+        program_path,
         profiles: None,
         environment: environment_obj,
         system_info: system,
         network_info: network,
         hardware_info: hardware,
         runtime_info: runtime,
-        custom_fields: serde_json::json!({}),
-        custom_metrics: serde_json::json!({}),
+        custom_fields: json!({ "tags": state.cfg.tags }),
+        custom_metrics: m.custom_metrics,
         report_timestamp: Utc::now().to_rfc3339(),
-        agent_logs: agent_logs_wire,
+        agent_logs: Vec::new(),
+        config_applied_revision: state.runtime.read().unwrap().revision,
     }
 }
 
@@ -273,14 +240,14 @@ fn macs() -> Vec<String> {
     }
 }
 
-fn pick_primary_ip(ips: &Vec<String>) -> Option<String> {
+fn pick_primary_ip(ips: &[String]) -> Option<String> {
     // 选取首个非回环 IPv4 地址；否则返回第一个
     for ip in ips {
         if ip != "127.0.0.1" && ip != "::1" && !ip.starts_with("169.254.") {
             return Some(ip.clone());
         }
     }
-    ips.get(0).cloned()
+    ips.first().cloned()
 }
 
 fn normalize_agent_type(s: &str) -> String {
@@ -294,15 +261,3 @@ fn normalize_agent_type(s: &str) -> String {
         lower.replace('-', "_")
     }
 }
-
-fn normalize_log_level(s: &str) -> String {
-    match s.to_ascii_lowercase().as_str() {
-        "trace" => "trace".to_string(),
-        "debug" => "debug".to_string(),
-        "info" => "info".to_string(),
-        "warn" | "warning" => "warn".to_string(),
-        "error" => "error".to_string(),
-        "fatal" => "fatal".to_string(),
-        other => other.to_string(),
-    }
-}
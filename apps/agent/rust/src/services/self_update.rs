@@ -0,0 +1,334 @@
+/// Agent 自升级：二进制替换、重新执行与升级后的健康确认
+///
+/// 升级任务校验通过后，将当前可执行文件改名为 `.bak`、新文件改名为原文件名，
+/// 写入升级状态文件后重新执行自身。新版本启动时读取状态文件：
+/// 在截止时间内首次上报成功则删除备份、确认升级；超时则恢复 `.bak` 并重新执行旧版本。
+///
+/// 新版本在确认前崩溃或卡住时进程内的确认流程无法执行回滚，因此每次启动时先由
+/// `check_on_startup` 累加未确认的启动次数：已过截止时间或启动次数超过上限时，
+/// 在其他初始化之前直接恢复旧版本（崩溃后依赖服务管理器重新拉起进程）。
+use crate::{agent_logger, config::Config, services::AppState};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+use tokio::time::{sleep, timeout, Duration};
+
+static REPORTED: AtomicBool = AtomicBool::new(false);
+static REPORTED_NOTIFY: Lazy<Notify> = Lazy::new(Notify::new);
+/// 未确认前允许的最大启动次数，超过后视为新版本反复崩溃并回滚
+const MAX_UNCONFIRMED_STARTS: u32 = 3;
+
+/// 待确认的升级
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UpgradeState {
+    pub from_version: String,
+    pub to_version: String,
+    /// 升级后的可执行文件路径
+    pub exe: PathBuf,
+    /// 旧版本备份路径
+    pub backup: PathBuf,
+    pub record_id: String,
+    /// 确认截止时间（Unix 秒）
+    pub deadline: i64,
+    /// 新版本未确认前的启动次数
+    #[serde(default)]
+    pub starts: u32,
+}
+
+/// 当前平台标识：<os>-<arch>，与服务端发布包的 platform 一致
+pub fn platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// 在路径后追加后缀，如 `agent` -> `agent.bak`
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut s: OsString = path.as_os_str().to_owned();
+    s.push(suffix);
+    PathBuf::from(s)
+}
+
+/// 上报成功后调用，通知升级确认流程
+pub fn mark_reported() {
+    if !REPORTED.swap(true, Ordering::SeqCst) {
+        REPORTED_NOTIFY.notify_waiters();
+    }
+}
+
+pub fn load_state(state: &AppState) -> Option<UpgradeState> {
+    read_state(&state.cfg.upgrade_state_path())
+}
+
+pub fn save_state(state: &AppState, upgrade: &UpgradeState) -> io::Result<()> {
+    write_state(&state.cfg.upgrade_state_path(), upgrade)
+}
+
+fn read_state(path: &Path) -> Option<UpgradeState> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_state(path: &Path, upgrade: &UpgradeState) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(upgrade).map_err(io::Error::other)?;
+    fs::write(path, content)
+}
+
+fn clear_state(state: &AppState) {
+    let _ = fs::remove_file(state.cfg.upgrade_state_path());
+}
+
+/// 替换可执行文件：当前文件改名为备份，新文件改名为当前文件名
+///
+/// 改名不影响正在运行的进程（Windows 也允许对运行中的可执行文件改名）。
+pub fn install(new: &Path, exe: &Path, backup: &Path) -> io::Result<()> {
+    let _ = fs::remove_file(backup);
+    fs::rename(exe, backup)?;
+    if let Err(e) = fs::rename(new, exe) {
+        let _ = fs::rename(backup, exe);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 恢复旧版本：升级后的文件改名为 `.failed`，备份改回原文件名
+fn restore(upgrade: &UpgradeState) -> io::Result<()> {
+    let failed = with_suffix(&upgrade.exe, ".failed");
+    let _ = fs::remove_file(&failed);
+    fs::rename(&upgrade.exe, &failed)?;
+    if let Err(e) = fs::rename(&upgrade.backup, &upgrade.exe) {
+        let _ = fs::rename(&failed, &upgrade.exe);
+        return Err(e);
+    }
+    // Unix 下可直接删除运行中的文件；Windows 下保留，下次升级前清理
+    let _ = fs::remove_file(&failed);
+    Ok(())
+}
+
+/// 延迟后以相同参数重新执行指定的可执行文件，留出时间回传任务结果
+pub fn schedule_restart(exe: PathBuf, delay: Duration) {
+    tokio::spawn(async move {
+        sleep(delay).await;
+        restart(&exe);
+    });
+}
+
+/// 以相同参数重新执行（Unix 下替换当前进程，PID 不变）
+#[cfg(unix)]
+pub fn restart(exe: &Path) -> ! {
+    use std::os::unix::process::CommandExt;
+    agent_logger::warn(&format!("重新启动 Agent: {}", exe.display()));
+    let err = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .exec();
+    agent_logger::error(&format!("重新启动失败: {}", err));
+    std::process::exit(1);
+}
+
+/// 以相同参数启动新进程后退出当前进程
+#[cfg(not(unix))]
+pub fn restart(exe: &Path) -> ! {
+    agent_logger::warn(&format!("重新启动 Agent: {}", exe.display()));
+    match std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .spawn()
+    {
+        Ok(_) => std::process::exit(0),
+        Err(e) => {
+            agent_logger::error(&format!("重新启动失败: {}", e));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 启动时的升级检查，需在加载配置后、其他初始化之前调用
+///
+/// 已过确认截止时间或未确认的启动次数超过上限时，恢复旧版本并重新执行。
+pub fn check_on_startup(cfg: &Config) {
+    let path = cfg.upgrade_state_path();
+    let Some(upgrade) = record_start(&path, &cfg.agent_version, chrono::Utc::now().timestamp())
+    else {
+        return;
+    };
+    agent_logger::error(&format!(
+        "升级到 {} 后未能确认（第 {} 次启动），回滚到 {}",
+        upgrade.to_version, upgrade.starts, upgrade.from_version
+    ));
+    let restored = restore(&upgrade);
+    let _ = fs::remove_file(&path);
+    match restored {
+        Ok(()) => restart(&upgrade.exe),
+        // 备份不可用时保留新版本继续运行，避免 Agent 失联
+        Err(e) => agent_logger::error(&format!("回滚失败，继续运行当前版本: {}", e)),
+    }
+}
+
+/// 累加未确认的启动次数，返回需要回滚的升级
+fn record_start(path: &Path, version: &str, now: i64) -> Option<UpgradeState> {
+    let mut upgrade = read_state(path)?;
+    if upgrade.to_version != version {
+        // 交由 start 记录并清理
+        return None;
+    }
+    upgrade.starts += 1;
+    if now <= upgrade.deadline && upgrade.starts <= MAX_UNCONFIRMED_STARTS {
+        if let Err(e) = write_state(path, &upgrade) {
+            agent_logger::warn(&format!("记录升级启动次数失败: {}", e));
+        }
+        return None;
+    }
+    Some(upgrade)
+}
+
+/// 启动时检查待确认的升级，需在上报服务之前调用
+pub async fn start(state: AppState) {
+    let Some(upgrade) = load_state(&state) else {
+        return;
+    };
+    if upgrade.to_version != state.cfg.agent_version {
+        // 运行的不是升级目标版本（已回滚或被外部替换），放弃确认
+        agent_logger::warn(&format!(
+            "升级到 {} 未生效，当前版本 {}，已放弃升级 record_id={}",
+            upgrade.to_version, state.cfg.agent_version, upgrade.record_id
+        ));
+        clear_state(&state);
+        return;
+    }
+
+    let remaining = upgrade.deadline - chrono::Utc::now().timestamp();
+    agent_logger::info(&format!(
+        "已升级到 {}，等待首次上报确认（剩余 {} 秒）",
+        upgrade.to_version,
+        remaining.max(0)
+    ));
+    tokio::spawn(async move {
+        let wait = Duration::from_secs(remaining.max(0) as u64);
+        let confirmed = timeout(wait, async {
+            while !REPORTED.load(Ordering::SeqCst) {
+                let notified = REPORTED_NOTIFY.notified();
+                if REPORTED.load(Ordering::SeqCst) {
+                    break;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .is_ok();
+
+        if confirmed {
+            let _ = fs::remove_file(&upgrade.backup);
+            clear_state(&state);
+            agent_logger::info(&format!(
+                "升级确认成功 {} -> {}",
+                upgrade.from_version, upgrade.to_version
+            ));
+            return;
+        }
+
+        agent_logger::error(&format!(
+            "升级到 {} 后未能在截止时间内上报，回滚到 {}",
+            upgrade.to_version, upgrade.from_version
+        ));
+        match restore(&upgrade) {
+            Ok(()) => {
+                clear_state(&state);
+                restart(&upgrade.exe);
+            }
+            Err(e) => {
+                // 备份不可用时保留新版本继续运行，避免 Agent 失联
+                agent_logger::error(&format!("回滚失败，继续运行当前版本: {}", e));
+                clear_state(&state);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monihub-upgrade-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn upgrade(dir: &Path, deadline: i64) -> UpgradeState {
+        let exe = dir.join("agent");
+        UpgradeState {
+            from_version: "1.0.0".to_string(),
+            to_version: "1.1.0".to_string(),
+            backup: with_suffix(&exe, ".bak"),
+            exe,
+            record_id: "r1".to_string(),
+            deadline,
+            starts: 0,
+        }
+    }
+
+    #[test]
+    fn test_with_suffix() {
+        assert_eq!(
+            with_suffix(Path::new("/opt/agent"), ".bak"),
+            PathBuf::from("/opt/agent.bak")
+        );
+        assert_eq!(
+            with_suffix(Path::new("agent.exe"), ".new"),
+            PathBuf::from("agent.exe.new")
+        );
+    }
+
+    #[test]
+    fn test_install_and_restore() {
+        let dir = temp_dir();
+        let state = upgrade(&dir, 0);
+        let new = with_suffix(&state.exe, ".new");
+        fs::write(&state.exe, "old").unwrap();
+        fs::write(&new, "new").unwrap();
+
+        install(&new, &state.exe, &state.backup).unwrap();
+        assert_eq!(fs::read_to_string(&state.exe).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&state.backup).unwrap(), "old");
+        assert!(!new.exists());
+
+        restore(&state).unwrap();
+        assert_eq!(fs::read_to_string(&state.exe).unwrap(), "old");
+        assert!(!state.backup.exists());
+        assert!(!with_suffix(&state.exe, ".failed").exists());
+
+        // 新文件缺失时保持原文件不变
+        assert!(install(&new, &state.exe, &state.backup).is_err());
+        assert_eq!(fs::read_to_string(&state.exe).unwrap(), "old");
+        // 备份缺失时回滚失败，保留当前文件
+        assert!(restore(&state).is_err());
+        assert_eq!(fs::read_to_string(&state.exe).unwrap(), "old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_start() {
+        let dir = temp_dir();
+        let path = dir.join("upgrade-state.json");
+        assert!(record_start(&path, "1.1.0", 100).is_none());
+
+        write_state(&path, &upgrade(&dir, 200)).unwrap();
+        // 运行的不是目标版本时不处理
+        assert!(record_start(&path, "1.0.0", 100).is_none());
+        assert_eq!(read_state(&path).unwrap().starts, 0);
+
+        for starts in 1..=MAX_UNCONFIRMED_STARTS {
+            assert!(record_start(&path, "1.1.0", 100).is_none());
+            assert_eq!(read_state(&path).unwrap().starts, starts);
+        }
+        let rollback = record_start(&path, "1.1.0", 100).unwrap();
+        assert_eq!(rollback.starts, MAX_UNCONFIRMED_STARTS + 1);
+
+        // 已过截止时间时首次启动即回滚
+        write_state(&path, &upgrade(&dir, 200)).unwrap();
+        assert!(record_start(&path, "1.1.0", 201).is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+use crate::utils::http_util;
+/// 任务输出分段上报
+///
+/// 脚本输出按行写入通道，后台按大小或时间合并为带序号的分段上报服务端，
+/// 服务端据此实时展示执行中的输出。分段携带下发时的重试次数，超时重试后旧执行的输出会被服务端拒绝。
+/// 上报连续失败后不再上报，由结果回传完整输出兜底。
+use crate::{agent_logger, services::AppState};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout_at, Duration, Instant};
+
+/// 缓冲达到该字节数立即上报
+const FLUSH_BYTES: usize = 16 * 1024;
+/// 单个分段最大字节数（服务端限制 64KB）
+const MAX_CHUNK_BYTES: usize = 60 * 1024;
+/// 缓冲最长停留时间
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// 单次上报最多尝试次数
+const MAX_ATTEMPTS: u32 = 3;
+/// 待上报行数上限，上报阻塞时读取端随之等待，输出不在内存中堆积
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+/// 输出流类型
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct OutputChunk {
+    seq: i64,
+    stream: &'static str,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct OutputSubmitRequest<'a> {
+    record_id: &'a str,
+    agent_instance_id: &'a str,
+    chunks: &'a [OutputChunk],
+    retry_attempt: i32,
+}
+
+/// 上报结果汇总
+pub struct OutputSummary {
+    /// 最后一个分段序号，没有输出时为 None
+    pub last_seq: Option<i64>,
+    /// 输出总字节数
+    pub bytes: u64,
+    /// 所有分段均已上报成功
+    pub complete: bool,
+}
+
+#[derive(Clone)]
+/// 输出写入端，可在多个读取任务间共享
+pub struct OutputSender(mpsc::Sender<(OutputStream, String)>);
+
+impl OutputSender {
+    pub async fn send(&self, stream: OutputStream, text: String) {
+        let _ = self.0.send((stream, text)).await;
+    }
+}
+
+/// 单个执行记录的输出上报器
+pub struct OutputUploader {
+    sender: OutputSender,
+    handle: JoinHandle<OutputSummary>,
+}
+
+impl OutputUploader {
+    pub fn start(state: &AppState, record_id: &str, retry_attempt: i32) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let target = Target {
+            record_id: record_id.to_string(),
+            retry_attempt,
+        };
+        let handle = tokio::spawn(run(state.clone(), target, rx));
+        Self {
+            sender: OutputSender(tx),
+            handle,
+        }
+    }
+
+    pub fn sender(&self) -> OutputSender {
+        self.sender.clone()
+    }
+
+    /// 上报剩余缓冲并返回汇总（需先释放所有写入端）
+    pub async fn finish(self) -> OutputSummary {
+        drop(self.sender);
+        self.handle.await.unwrap_or(OutputSummary {
+            last_seq: None,
+            bytes: 0,
+            complete: false,
+        })
+    }
+}
+
+/// 分段所属的执行记录与执行次数
+struct Target {
+    record_id: String,
+    retry_attempt: i32,
+}
+
+struct Batch {
+    chunks: Vec<OutputChunk>,
+    bytes: usize,
+    next_seq: i64,
+}
+
+impl Batch {
+    /// 追加输出：与上一分段同一输出流时合并，超过分段上限时拆分
+    fn push(&mut self, stream: OutputStream, mut text: String) {
+        self.bytes += text.len();
+        while !text.is_empty() {
+            if let Some(last) = self.chunks.last_mut() {
+                if last.stream == stream.as_str() && last.content.len() < MAX_CHUNK_BYTES {
+                    let room = MAX_CHUNK_BYTES - last.content.len();
+                    let split = floor_char_boundary(&text, room);
+                    last.content.push_str(&text[..split]);
+                    text.drain(..split);
+                    if text.is_empty() {
+                        break;
+                    }
+                    if split > 0 {
+                        continue;
+                    }
+                }
+            }
+            self.chunks.push(OutputChunk {
+                seq: self.next_seq,
+                stream: stream.as_str(),
+                content: String::new(),
+            });
+            self.next_seq += 1;
+        }
+    }
+}
+
+/// 不超过 max 的最大字符边界
+fn floor_char_boundary(text: &str, max: usize) -> usize {
+    if max >= text.len() {
+        return text.len();
+    }
+    let mut idx = max;
+    while !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+async fn run(
+    state: AppState,
+    target: Target,
+    mut rx: mpsc::Receiver<(OutputStream, String)>,
+) -> OutputSummary {
+    let mut batch = Batch {
+        chunks: Vec::new(),
+        bytes: 0,
+        next_seq: 0,
+    };
+    let mut total_bytes = 0u64;
+    let mut complete = true;
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let received = match deadline {
+            Some(at) => match timeout_at(at, rx.recv()).await {
+                Ok(received) => received,
+                Err(_) => {
+                    flush(&state, &target, &mut batch, &mut complete).await;
+                    deadline = None;
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        match received {
+            Some((stream, text)) => {
+                total_bytes += text.len() as u64;
+                batch.push(stream, text);
+                if batch.bytes >= FLUSH_BYTES {
+                    flush(&state, &target, &mut batch, &mut complete).await;
+                    deadline = None;
+                } else if deadline.is_none() {
+                    deadline = Some(Instant::now() + FLUSH_INTERVAL);
+                }
+            }
+            None => {
+                flush(&state, &target, &mut batch, &mut complete).await;
+                break;
+            }
+        }
+    }
+    OutputSummary {
+        last_seq: (batch.next_seq > 0).then_some(batch.next_seq - 1),
+        bytes: total_bytes,
+        complete,
+    }
+}
+
+async fn flush(state: &AppState, target: &Target, batch: &mut Batch, complete: &mut bool) {
+    let chunks = std::mem::take(&mut batch.chunks);
+    batch.bytes = 0;
+    if chunks.is_empty() || !*complete {
+        return;
+    }
+    let agent_instance_id = state.cfg.agent_instance_id.clone().unwrap_or_default();
+    let record_id = target.record_id.as_str();
+    let req = OutputSubmitRequest {
+        record_id,
+        agent_instance_id: &agent_instance_id,
+        chunks: &chunks,
+        retry_attempt: target.retry_attempt,
+    };
+    let url = format!("{}/api/open/instances/tasks/output", state.cfg.server_url);
+    for attempt in 1..=MAX_ATTEMPTS {
+        match http_util::post(url.clone(), &req).await {
+            Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) if resp.status().as_u16() == 401 => {
+                crate::services::credentials::on_unauthorized(state).await;
+            }
+            Ok(resp) if resp.status().is_client_error() => {
+                agent_logger::warn(&format!(
+                    "任务输出上报被拒绝 record_id={} status={}",
+                    record_id,
+                    resp.status()
+                ));
+                break;
+            }
+            _ => {}
+        }
+        if attempt < MAX_ATTEMPTS {
+            sleep(Duration::from_millis(500 * attempt as u64)).await;
+        }
+    }
+    agent_logger::warn(&format!(
+        "任务输出上报失败，停止分段上报 record_id={}",
+        record_id
+    ));
+    *complete = false;
+}
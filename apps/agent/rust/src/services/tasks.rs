@@ -2,9 +2,10 @@ use crate::utils::http_util;
 /// 任务拉取与执行服务
 ///
 /// 使用长轮询从服务端拉取任务（支持 wait/timeout），并发执行各类任务，
-/// 将执行结果回传后端。403 时通过全局标志暂停轮询并轻量退避。
+/// 将执行结果回传后端。403 时通过全局标志暂停轮询并轻量退避，401 时重新注册实例令牌。
 use crate::{
     agent_logger,
+    handlers::TaskFailure,
     models::{
         TaskDispatchItem, TaskDispatchResponse, TaskResultSubmitRequest, TaskStatus, TaskType,
     },
@@ -26,7 +27,8 @@ pub async fn start(state: AppState) {
             .build()
             .unwrap();
         loop {
-            if !st.cfg.task.enabled {
+            let task_cfg = st.runtime().task;
+            if !task_cfg.enabled {
                 sleep(Duration::from_secs(2)).await;
                 continue;
             }
@@ -35,28 +37,29 @@ pub async fn start(state: AppState) {
                 "{}/api/open/instances/tasks?agent_instance_id={}&wait=true&timeout={}",
                 st.cfg.server_url,
                 st.cfg.agent_instance_id.clone().unwrap_or_default(),
-                st.cfg.task.long_poll_timeout_seconds
+                task_cfg.long_poll_timeout_seconds
             );
 
             match http_util::get(url).await {
+                Ok(resp) if resp.status().as_u16() == 401 => {
+                    crate::services::credentials::on_unauthorized(&st).await;
+                    sleep(Duration::from_secs(5)).await;
+                }
                 Ok(resp) => {
                     let content = resp.text().await.unwrap();
-                    match serde_json::from_str::<TaskDispatchResponse>(&content) {
-                        Ok(body) => {
-                            agent_logger::info(&format!("拉取到任务数量: {}", body.tasks.len()));
-                            for item in body.tasks {
-                                let permit = sem.clone().acquire_owned().await.unwrap();
-                                let s2 = st.clone();
-                                tokio::spawn(async move {
-                                    handle_task(s2, item).await;
-                                    drop(permit);
-                                });
-                            }
+                    if let Ok(body) = serde_json::from_str::<TaskDispatchResponse>(&content) {
+                        agent_logger::info(&format!("拉取到任务数量: {}", body.tasks.len()));
+                        for item in body.tasks {
+                            let permit = sem.clone().acquire_owned().await.unwrap();
+                            let s2 = st.clone();
+                            tokio::spawn(async move {
+                                handle_task(s2, item).await;
+                                drop(permit);
+                            });
                         }
-                        Err(e) => {}
                     }
                 }
-                Err(e) => {
+                Err(_) => {
                     // 非超时错误轻微退避
                     sleep(Duration::from_millis(800)).await;
                 }
@@ -74,6 +77,7 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
         TaskType::FileManager => "file_manager",
         TaskType::CustomCommand => "custom_command",
         TaskType::HttpRequest => "http_request",
+        TaskType::AgentUpgrade => "agent_upgrade",
     };
     agent_logger::info(&format!(
         "开始执行任务 record_id={} type={}",
@@ -84,10 +88,10 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
     let mut message = String::new();
     let mut data = serde_json::json!({});
     let mut err = None;
-    let timeout = item.timeout_seconds.clone();
+    let timeout = item.timeout_seconds;
     let r = match item.task_type {
-        TaskType::ShellExec => crate::handlers::shell_exec::execute(&item, timeout).await,
-        TaskType::RunCode => crate::handlers::run_code::execute(&item, timeout).await,
+        TaskType::ShellExec => crate::handlers::shell_exec::execute(&state, &item, timeout).await,
+        TaskType::RunCode => crate::handlers::run_code::execute(&state, &item, timeout).await,
         TaskType::FileManager => {
             crate::handlers::file_manager::execute(&state, &item, timeout).await
         }
@@ -95,6 +99,9 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
         TaskType::HttpRequest => {
             crate::handlers::http_request::execute(&state, &item, timeout).await
         }
+        TaskType::AgentUpgrade => {
+            crate::handlers::agent_upgrade::execute(&state, &item, timeout).await
+        }
     };
     match r {
         Ok(v) => {
@@ -103,7 +110,7 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
             agent_logger::info(&format!("任务成功 record_id={}", item.record_id));
         }
         Err(e) => {
-            status = if message.contains("timeout") {
+            status = if e.to_string().contains("timeout") {
                 TaskStatus::Timeout
             } else {
                 TaskStatus::Failed
@@ -111,6 +118,10 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
             code = -1;
             err = Some(e.to_string());
             message = String::from("failed");
+            if let Some(failure) = e.downcast_ref::<TaskFailure>() {
+                message = failure.reason.to_string();
+                data = failure.data.clone();
+            }
             agent_logger::error(&format!(
                 "任务失败 record_id={} error={}",
                 item.record_id, e
@@ -120,7 +131,7 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
     let end = Utc::now();
     let req = TaskResultSubmitRequest {
         record_id: item.record_id.clone(),
-        instance_id: state.cfg.instance_id.clone().unwrap_or_default(),
+        instance_id: state.cfg.agent_instance_id.clone().unwrap_or_default(),
         status,
         code,
         message,
@@ -129,8 +140,8 @@ async fn handle_task(state: AppState, item: TaskDispatchItem) {
         start_time: start,
         end_time: end,
         duration_ms: (end - start).num_milliseconds() as u128,
+        retry_attempt: item.retry_attempt,
     };
     let url = format!("{}/api/open/instances/tasks/result", state.cfg.server_url);
-    let client = reqwest::Client::new();
-    let _ = client.post(url).json(&req).send().await;
+    let _ = http_util::post(url, &req).await;
 }
@@ -0,0 +1,229 @@
+use crate::handlers::terminal::{PtyEvent, TerminalSession};
+use crate::utils::http_util;
+/// 远程终端服务
+///
+/// Agent 主动与服务端建立 WebSocket 长连接（适用于 NAT 后的主机），
+/// 按服务端下发的 open/input/resize/close 帧管理 PTY 会话，并回传 output/exit/error 帧。
+/// 连接断开后自动重连，握手返回 401 时重新注册实例令牌。
+use crate::{agent_logger, services::AppState};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+/// 终端协议帧（与服务端保持一致）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TerminalFrame {
+    Open {
+        session_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    Input {
+        session_id: String,
+        data: String,
+    },
+    Resize {
+        session_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    Output {
+        session_id: String,
+        data: String,
+    },
+    Exit {
+        session_id: String,
+        code: Option<i32>,
+    },
+    Close {
+        session_id: String,
+    },
+    Error {
+        session_id: String,
+        message: String,
+    },
+}
+
+pub async fn start(state: AppState) {
+    if !state.cfg.terminal.enabled {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            match run(&state).await {
+                Ok(()) => agent_logger::info("终端通道已断开，准备重连"),
+                Err(WsError::Http(resp)) if resp.status().as_u16() == 401 => {
+                    crate::services::credentials::on_unauthorized(&state).await;
+                }
+                Err(e) => agent_logger::warn(&format!("终端通道连接失败: {}", e)),
+            }
+            sleep(Duration::from_secs(
+                state.cfg.terminal.reconnect_seconds.max(1),
+            ))
+            .await;
+        }
+    });
+}
+
+fn channel_url(state: &AppState) -> String {
+    let base = state.cfg.server_url.trim_end_matches('/');
+    let base = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        base.to_string()
+    };
+    format!(
+        "{}/api/open/instances/terminal/ws?agent_instance_id={}",
+        base,
+        state.cfg.agent_instance_id.clone().unwrap_or_default()
+    )
+}
+
+/// 建立一次终端通道并处理直到断开
+async fn run(state: &AppState) -> Result<(), WsError> {
+    let mut request = channel_url(state).into_client_request()?;
+    if let Some(token) = http_util::agent_token() {
+        if let Ok(v) = HeaderValue::from_str(&token) {
+            request
+                .headers_mut()
+                .insert(http_util::AGENT_TOKEN_HEADER, v);
+        }
+    }
+    let (ws, _) = tokio_tungstenite::connect_async(request).await?;
+    agent_logger::info("终端通道已连接");
+    let (mut sink, mut stream) = ws.split();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<PtyEvent>();
+    let mut sessions: HashMap<String, TerminalSession> = HashMap::new();
+
+    let result = loop {
+        tokio::select! {
+            msg = stream.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Ping(payload))) => {
+                        if let Err(e) = sink.send(Message::Pong(payload)).await {
+                            break Err(e);
+                        }
+                        continue;
+                    }
+                    Some(Ok(Message::Close(_))) | None => break Ok(()),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => break Err(e),
+                };
+                let frame = match serde_json::from_str::<TerminalFrame>(&text) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        agent_logger::warn(&format!("无法解析终端帧: {}", e));
+                        continue;
+                    }
+                };
+                if let Some(reply) = handle_frame(state, frame, &mut sessions, &tx) {
+                    if let Err(e) = sink.send(Message::Text(serde_json::to_string(&reply).unwrap_or_default())).await {
+                        break Err(e);
+                    }
+                }
+            }
+            Some(event) = rx.recv() => {
+                let frame = match event {
+                    PtyEvent::Output { session_id, data } => TerminalFrame::Output { session_id, data },
+                    PtyEvent::Closed { session_id } => {
+                        // 已被服务端关闭的会话无需再回传
+                        let Some(mut session) = sessions.remove(&session_id) else {
+                            continue;
+                        };
+                        let code = session.exit_code();
+                        agent_logger::info(&format!("终端会话结束 session={} code={:?}", session_id, code));
+                        TerminalFrame::Exit { session_id, code }
+                    }
+                };
+                if let Err(e) = sink.send(Message::Text(serde_json::to_string(&frame).unwrap_or_default())).await {
+                    break Err(e);
+                }
+            }
+        }
+    };
+
+    // 通道断开时结束所有会话
+    for (_, mut session) in sessions.drain() {
+        session.kill();
+    }
+    result
+}
+
+/// 处理服务端下发的帧，必要时返回需要回传的帧
+fn handle_frame(
+    state: &AppState,
+    frame: TerminalFrame,
+    sessions: &mut HashMap<String, TerminalSession>,
+    tx: &mpsc::UnboundedSender<PtyEvent>,
+) -> Option<TerminalFrame> {
+    match frame {
+        TerminalFrame::Open {
+            session_id,
+            cols,
+            rows,
+        } => {
+            if sessions.len() >= state.cfg.terminal.max_sessions {
+                return Some(TerminalFrame::Error {
+                    session_id,
+                    message: "终端会话数已达上限".to_string(),
+                });
+            }
+            match TerminalSession::spawn(
+                &session_id,
+                cols,
+                rows,
+                state.cfg.terminal.shell.as_deref(),
+                tx.clone(),
+            ) {
+                Ok(session) => {
+                    agent_logger::info(&format!("终端会话已打开 session={}", session_id));
+                    sessions.insert(session_id, session);
+                    None
+                }
+                Err(e) => {
+                    agent_logger::error(&format!("启动终端失败: {}", e));
+                    Some(TerminalFrame::Error {
+                        session_id,
+                        message: format!("启动终端失败: {}", e),
+                    })
+                }
+            }
+        }
+        TerminalFrame::Input { session_id, data } => {
+            let session = sessions.get_mut(&session_id)?;
+            if let Err(e) = session.write(&data) {
+                agent_logger::warn(&format!("写入终端失败 session={}: {}", session_id, e));
+            }
+            None
+        }
+        TerminalFrame::Resize {
+            session_id,
+            cols,
+            rows,
+        } => {
+            let session = sessions.get(&session_id)?;
+            if let Err(e) = session.resize(cols, rows) {
+                agent_logger::warn(&format!("调整终端大小失败 session={}: {}", session_id, e));
+            }
+            None
+        }
+        TerminalFrame::Close { session_id } => {
+            if let Some(mut session) = sessions.remove(&session_id) {
+                session.kill();
+                agent_logger::info(&format!("终端会话已关闭 session={}", session_id));
+            }
+            None
+        }
+        _ => None,
+    }
+}
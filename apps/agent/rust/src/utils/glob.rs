@@ -0,0 +1,157 @@
+/// 文件路径通配展开
+///
+/// 支持 `*`、`?` 匹配单级路径中的任意字符，`**` 匹配任意层级目录，
+/// 如 `/var/log/app/*.log`、`/opt/apps/**/logs/*.log`。
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 展开通配路径，返回存在的普通文件（按路径排序）
+pub fn expand(pattern: &str) -> Vec<PathBuf> {
+    let path = Path::new(pattern);
+    if !has_wildcard(pattern) {
+        return if path.is_file() {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        };
+    }
+    // 拆出不含通配符的前缀作为起始目录
+    let mut base = PathBuf::new();
+    let mut rest: Vec<String> = Vec::new();
+    for comp in path.components() {
+        let s = comp.as_os_str().to_string_lossy().to_string();
+        if rest.is_empty() && !has_wildcard(&s) {
+            base.push(comp);
+        } else if !matches!(comp, Component::CurDir) {
+            rest.push(s);
+        }
+    }
+    if base.as_os_str().is_empty() {
+        base.push(".");
+    }
+    let mut out = Vec::new();
+    walk(&base, &rest, &mut out);
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn walk(dir: &Path, parts: &[String], out: &mut Vec<PathBuf>) {
+    let Some((head, tail)) = parts.split_first() else {
+        if dir.is_file() {
+            out.push(dir.to_path_buf());
+        }
+        return;
+    };
+    if head == "**" {
+        // `**` 可匹配零级目录
+        walk(dir, tail, out);
+        for entry in walkdir::WalkDir::new(dir)
+            .min_depth(1)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_dir())
+        {
+            walk(entry.path(), tail, out);
+        }
+        return;
+    }
+    if !has_wildcard(head) {
+        walk(&dir.join(head), tail, out);
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if matches(head, &name) {
+            walk(&entry.path(), tail, out);
+        }
+    }
+}
+
+fn has_wildcard(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// 单级名称匹配，`*` 匹配任意长度字符，`?` 匹配单个字符
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = name.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || (p[pi] != '*' && p[pi] == t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*.log", "app.log"));
+        assert!(matches("*.log", ".log"));
+        assert!(!matches("*.log", "app.log.1"));
+        assert!(matches("app-*.log*", "app-2024.log.1"));
+        assert!(matches("?.log", "a.log"));
+        assert!(!matches("?.log", "ab.log"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXbYbZ"));
+        assert!(matches("*", ""));
+        assert!(matches("日志-?.txt", "日志-1.txt"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let root = std::env::temp_dir().join(format!("monihub-glob-{}", uuid::Uuid::new_v4()));
+        for file in ["a/x.log", "a/b/y.log", "a/b/c/z.txt", "d/w.log"] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let base = root.to_string_lossy();
+        let rel = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            rel(expand(&format!("{}/**/*.log", base))),
+            ["a/b/y.log", "a/x.log", "d/w.log"]
+        );
+        assert_eq!(
+            rel(expand(&format!("{}/*/?.log", base))),
+            ["a/x.log", "d/w.log"]
+        );
+        assert_eq!(rel(expand(&format!("{}/a/**/z.*", base))), ["a/b/c/z.txt"]);
+        assert_eq!(rel(expand(&format!("{}/a/x.log", base))), ["a/x.log"]);
+        // 目录与不存在的路径不返回
+        assert!(expand(&format!("{}/a", base)).is_empty());
+        assert!(expand(&format!("{}/none/*.log", base)).is_empty());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
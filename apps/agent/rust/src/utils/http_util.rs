@@ -1,28 +1,90 @@
-use reqwest::{Error, Response};
+use crate::{agent_logger, config::HttpConfig};
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::{ClientBuilder, Error, RequestBuilder, Response};
 use serde::Serialize;
+use std::sync::RwLock;
+
+/// 实例令牌请求头（与服务端保持一致）
+pub const AGENT_TOKEN_HEADER: &str = "X-Agent-Token";
+
+static SERVER_URL: OnceCell<String> = OnceCell::new();
+static AGENT_TOKEN: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+static HTTP_CONFIG: Lazy<RwLock<HttpConfig>> = Lazy::new(|| RwLock::new(HttpConfig::default()));
+
+/// 记录服务端地址，仅发往该地址的请求才会附带实例令牌
+pub fn init(server_url: &str) {
+    let _ = SERVER_URL.set(server_url.trim_end_matches('/').to_string());
+}
+
+/// 更新当前使用的实例令牌
+pub fn set_agent_token(token: Option<String>) {
+    if let Ok(mut guard) = AGENT_TOKEN.write() {
+        *guard = token;
+    }
+}
+
+pub fn agent_token() -> Option<String> {
+    AGENT_TOKEN.read().ok().and_then(|g| g.clone())
+}
+
+/// 更新出站请求使用的代理配置（启动时加载本地配置，服务端下发后热更新）
+pub fn set_http_config(cfg: &HttpConfig) {
+    if let Ok(mut guard) = HTTP_CONFIG.write() {
+        *guard = cfg.clone();
+    }
+}
+
+/// 按代理配置设置客户端构建器，未启用或地址为空时保持直连
+pub fn apply_proxy(builder: ClientBuilder, cfg: &HttpConfig) -> ClientBuilder {
+    if !cfg.proxy_enabled {
+        return builder;
+    }
+    let Some(url) = cfg.proxy_url.as_deref().filter(|u| !u.is_empty()) else {
+        return builder;
+    };
+    match reqwest::Proxy::all(url) {
+        Ok(mut proxy) => {
+            if let Some(user) = cfg.proxy_username.as_deref().filter(|u| !u.is_empty()) {
+                proxy = proxy.basic_auth(user, cfg.proxy_password.as_deref().unwrap_or(""));
+            }
+            builder.proxy(proxy)
+        }
+        Err(e) => {
+            agent_logger::warn(&format!("初始化代理失败: {}", e));
+            builder
+        }
+    }
+}
 
 pub fn get_client() -> reqwest::Client {
-    let client = reqwest::Client::new();
-    // let client = match reqwest::Proxy::all("http://127.0.0.1:9000") {
-    //     Ok(p) => reqwest::Client::builder()
-    //         .proxy(p)
-    //         .build()
-    //         .unwrap_or_else(|e| {
-    //             agent_logger::warn(&format!("初始化HTTP客户端失败: {}", e));
-    //             reqwest::Client::new()
-    //         }),
-    //     Err(e) => {
-    //         agent_logger::warn(&format!("初始化代理失败: {}", e));
-    //         reqwest::Client::new()
-    //     }
-    // };
-    client
+    let cfg = HTTP_CONFIG.read().map(|g| g.clone()).unwrap_or_default();
+    apply_proxy(reqwest::Client::builder(), &cfg)
+        .build()
+        .unwrap_or_else(|e| {
+            agent_logger::warn(&format!("初始化HTTP客户端失败: {}", e));
+            reqwest::Client::new()
+        })
+}
+
+/// 请求服务端时自动附带实例令牌，第三方地址不附带
+pub fn with_agent_token(builder: RequestBuilder, url: &str) -> RequestBuilder {
+    let to_server = SERVER_URL
+        .get()
+        .map(|s| !s.is_empty() && url.starts_with(s.as_str()))
+        .unwrap_or(false);
+    match agent_token() {
+        Some(token) if to_server => builder.header(AGENT_TOKEN_HEADER, token),
+        _ => builder,
+    }
 }
 
 pub async fn post<T: Serialize + ?Sized>(url: String, json: &T) -> Result<Response, Error> {
-    get_client().post(url).json(json).send().await
+    with_agent_token(get_client().post(&url), &url)
+        .json(json)
+        .send()
+        .await
 }
 
 pub(crate) async fn get(url: String) -> Result<Response, Error> {
-    get_client().get(url).send().await
+    with_agent_token(get_client().get(&url), &url).send().await
 }
@@ -1 +1,5 @@
+pub mod glob;
 pub mod http_util;
+pub mod redaction;
+#[cfg(unix)]
+pub mod sandbox;
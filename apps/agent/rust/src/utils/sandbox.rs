@@ -0,0 +1,296 @@
+/// 脚本任务进程隔离（仅 Unix）
+///
+/// 提供运行用户查询、fork 后 exec 前的子进程准备（加入 cgroup、设置 rlimit、
+/// 降权）、Linux cgroup v2 任务组的创建与回收，以及整个进程组的强制结束。
+/// `prepare_child` 运行在 fork 后的子进程中，只能调用异步信号安全的系统调用。
+use crate::config::ResourceLimits;
+use std::ffi::{CStr, CString, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const MB: u64 = 1024 * 1024;
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// cpu.max 的调度周期（微秒）
+const CPU_PERIOD_US: u64 = 100_000;
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type Resource = libc::c_int;
+
+/// 系统用户信息
+#[derive(Clone, Debug)]
+pub struct UserInfo {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: String,
+}
+
+/// 按用户名查询系统用户
+pub fn lookup_user(name: &str) -> io::Result<UserInfo> {
+    let cname = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getpwnam_r(
+            cname.as_ptr(),
+            &mut pwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 || result.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("用户不存在: {}", name),
+        ));
+    }
+    let home = unsafe { CStr::from_ptr(pwd.pw_dir) }
+        .to_string_lossy()
+        .into_owned();
+    Ok(UserInfo {
+        name: name.to_string(),
+        uid: pwd.pw_uid,
+        gid: pwd.pw_gid,
+        home,
+    })
+}
+
+/// 按组名查询 gid
+pub fn lookup_group(name: &str) -> io::Result<u32> {
+    let cname = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getgrnam_r(
+            cname.as_ptr(),
+            &mut grp,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 || result.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("用户组不存在: {}", name),
+        ));
+    }
+    Ok(grp.gr_gid)
+}
+
+/// 修改文件或目录属主，使降权后的进程可以访问；路径为符号链接时失败而不跟随
+pub fn chown(path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)?;
+    std::os::unix::fs::fchown(&file, Some(uid), Some(gid))
+}
+
+/// 在系统临时目录下独占创建随机名称、仅属主可访问（0700）的目录
+pub fn make_private_dir(prefix: &str) -> io::Result<PathBuf> {
+    let template = std::env::temp_dir().join(format!("{}XXXXXX", prefix));
+    let mut buf = CString::new(template.as_os_str().as_bytes())?.into_bytes_with_nul();
+    if unsafe { libc::mkdtemp(buf.as_mut_ptr() as *mut libc::c_char) }.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    buf.pop();
+    Ok(PathBuf::from(OsString::from_vec(buf)))
+}
+
+/// 子进程 exec 前的准备：加入 cgroup、设置 rlimit、切换用户
+///
+/// 顺序不可调换：写 cgroup.procs 与提高 rlimit 需要在降权之前完成。
+/// `cgroup_active` 时内存与进程数由 cgroup 限制，不再设置 RLIMIT_AS/RLIMIT_NPROC，
+/// 避免虚拟内存上限误伤 JVM 等预留大地址空间的程序。
+pub fn prepare_child(
+    cgroup_procs: Option<RawFd>,
+    limits: &ResourceLimits,
+    credentials: Option<(u32, u32)>,
+) -> io::Result<()> {
+    if let Some(fd) = cgroup_procs {
+        if unsafe { libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    let cgroup_active = cgroup_procs.is_some();
+    if !cgroup_active {
+        set_rlimit(
+            libc::RLIMIT_AS as Resource,
+            limits.memory_mb.map(|v| v * MB),
+        )?;
+        set_rlimit(libc::RLIMIT_NPROC as Resource, limits.max_processes)?;
+    }
+    set_rlimit(libc::RLIMIT_CPU as Resource, limits.cpu_seconds)?;
+    set_rlimit(libc::RLIMIT_NOFILE as Resource, limits.max_open_files)?;
+    set_rlimit(
+        libc::RLIMIT_FSIZE as Resource,
+        limits.max_file_size_mb.map(|v| v * MB),
+    )?;
+    if let Some((uid, gid)) = credentials {
+        unsafe {
+            if libc::setgroups(1, &gid) != 0 || libc::setgid(gid) != 0 || libc::setuid(uid) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_rlimit(resource: Resource, value: Option<u64>) -> io::Result<()> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let rl = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource, &rl) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// 强制结束整个进程组（子进程以自身 PID 为组号启动）
+pub fn kill_group(pgid: u32) {
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+/// 单个任务的 cgroup v2 控制组
+pub struct TaskCgroup {
+    path: PathBuf,
+    procs: File,
+}
+
+impl TaskCgroup {
+    /// 是否需要 cgroup：仅内存、CPU 配额与进程数由 cgroup 限制
+    pub fn wanted(limits: &ResourceLimits) -> bool {
+        limits.memory_mb.is_some() || limits.cpu_percent.is_some() || limits.max_processes.is_some()
+    }
+
+    /// 在 `<cgroup 根>/<parent>/<name>` 创建控制组并写入限制
+    pub fn create(parent: &str, name: &str, limits: &ResourceLimits) -> io::Result<Self> {
+        let root = Path::new(CGROUP_ROOT);
+        if !root.join("cgroup.controllers").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "未挂载 cgroup v2",
+            ));
+        }
+        let parent_dir = root.join(parent.trim_matches('/'));
+        fs::create_dir_all(&parent_dir)?;
+        // 逐级开启控制器，已开启或不可用时忽略，缺失的限制文件在下方写入时报错
+        for dir in [root, parent_dir.as_path()] {
+            for controller in ["+memory", "+cpu", "+pids"] {
+                let _ = fs::write(dir.join("cgroup.subtree_control"), controller);
+            }
+        }
+        let path = parent_dir.join(name);
+        fs::create_dir(&path)?;
+        let written = (|| -> io::Result<File> {
+            if let Some(mb) = limits.memory_mb {
+                fs::write(path.join("memory.max"), (mb * MB).to_string())?;
+                let _ = fs::write(path.join("memory.swap.max"), "0");
+            }
+            if let Some(percent) = limits.cpu_percent {
+                let quota = (percent * CPU_PERIOD_US / 100).max(1000);
+                fs::write(path.join("cpu.max"), format!("{} {}", quota, CPU_PERIOD_US))?;
+            }
+            if let Some(max) = limits.max_processes {
+                fs::write(path.join("pids.max"), max.to_string())?;
+            }
+            OpenOptions::new()
+                .write(true)
+                .open(path.join("cgroup.procs"))
+        })();
+        match written {
+            Ok(procs) => Ok(Self { path, procs }),
+            Err(e) => {
+                let _ = fs::remove_dir(&path);
+                Err(e)
+            }
+        }
+    }
+
+    /// 供子进程写入自身 PID 的 cgroup.procs 句柄
+    pub fn procs_fd(&self) -> RawFd {
+        self.procs.as_raw_fd()
+    }
+
+    /// 任务正常结束：控制组为空时删除，仍有后台进程时保留（进程继续受任务限制）
+    pub fn release(self) {
+        drop(self.procs);
+        if fs::remove_dir(&self.path).is_err() {
+            crate::agent_logger::info(&format!(
+                "任务后台进程仍在运行，保留 cgroup: {}",
+                self.path.display()
+            ));
+        }
+    }
+
+    /// 结束组内所有进程并删除控制组
+    pub async fn remove(self) {
+        if fs::write(self.path.join("cgroup.kill"), "1").is_err() {
+            // 5.14 以前的内核没有 cgroup.kill，逐个结束
+            if let Ok(pids) = fs::read_to_string(self.path.join("cgroup.procs")) {
+                for pid in pids.lines().filter_map(|l| l.trim().parse::<i32>().ok()) {
+                    unsafe {
+                        libc::kill(pid, libc::SIGKILL);
+                    }
+                }
+            }
+        }
+        drop(self.procs);
+        for _ in 0..20 {
+            if fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        crate::agent_logger::warn(&format!("删除任务 cgroup 失败: {}", self.path.display()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_make_private_dir() {
+        let a = make_private_dir("monihub-test-").unwrap();
+        let b = make_private_dir("monihub-test-").unwrap();
+        assert_ne!(a, b);
+        let meta = fs::symlink_metadata(&a).unwrap();
+        assert!(meta.is_dir());
+        assert_eq!(meta.permissions().mode() & 0o777, 0o700);
+        fs::remove_dir(&a).unwrap();
+        fs::remove_dir(&b).unwrap();
+    }
+
+    #[test]
+    fn test_chown_rejects_symlink() {
+        let dir = make_private_dir("monihub-test-").unwrap();
+        let target = dir.join("target");
+        let link = dir.join("link");
+        fs::write(&target, b"x").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        assert!(chown(&link, uid, gid).is_err());
+        assert!(chown(&target, uid, gid).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
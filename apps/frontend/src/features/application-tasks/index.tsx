@@ -52,6 +52,14 @@ import { ExecutionResultPanel } from './execution-result'
 
 const route = getRouteApi('/_authenticated/application-tasks')
 
+// run_code 语言与编辑器高亮语言的对应关系
+const CODE_EDITOR_LANGUAGES: Record<string, string> = {
+  java: 'java',
+  python: 'python',
+  node: 'javascript',
+  bash: 'shell',
+}
+
 export function ApplicationTasks() {
   const search = route.useSearch()
   const queryClient = useQueryClient()
@@ -94,6 +102,8 @@ export function ApplicationTasks() {
   // 不同任务类型的参数状态
   const [shellScript, setShellScript] = useState('')
   const [codeContent, setCodeContent] = useState('')
+  const [codeLanguage, setCodeLanguage] = useState('java')
+  const [codeArgs, setCodeArgs] = useState('')
   const [fileManagerOperation, setFileManagerOperation] =
     useState('upload_file')
   const [filePath, setFilePath] = useState('')
@@ -176,6 +186,7 @@ export function ApplicationTasks() {
     file_manager: '文件管理',
     custom_command: '自定义命令',
     run_code: '运行Code',
+    agent_upgrade: 'Agent升级',
   }
 
   // 任务类型中文映射
@@ -250,8 +261,27 @@ export function ApplicationTasks() {
       case 'run_code':
         return (
           <div className='space-y-3'>
+            <div className='flex gap-2'>
+              <select
+                value={codeLanguage}
+                onChange={(e) => setCodeLanguage(e.target.value)}
+                className='border-input bg-background min-w-[126px] flex-1 rounded-md border p-2 text-sm'
+              >
+                <option value='java'>Java</option>
+                <option value='python'>Python</option>
+                <option value='node'>Node.js</option>
+                <option value='bash'>Bash</option>
+              </select>
+              <input
+                type='text'
+                placeholder='运行参数（JSON，经标准输入传入），如：{"name": "test"}'
+                value={codeArgs}
+                onChange={(e) => setCodeArgs(e.target.value)}
+                className='border-input bg-background flex-5 rounded-md border p-2 text-sm'
+              />
+            </div>
             <CodeEditor
-              language='java'
+              language={CODE_EDITOR_LANGUAGES[codeLanguage] ?? codeLanguage}
               value={codeContent}
               onChange={(v: string) => setCodeContent(v)}
               minHeight={130}
@@ -398,7 +428,16 @@ export function ApplicationTasks() {
 
       case 'run_code':
         isValid = codeContent.trim() !== ''
+        taskContent.language = codeLanguage
         taskContent.code = codeContent
+        if (codeArgs.trim() !== '') {
+          try {
+            taskContent.args = JSON.parse(codeArgs)
+          } catch {
+            toast.error('运行参数不是合法的 JSON')
+            return
+          }
+        }
         break
 
       case 'file_manager':
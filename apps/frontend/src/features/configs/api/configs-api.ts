@@ -2,6 +2,16 @@ import { apiClient } from '@/lib/api-client'
 
 export type ConfigType = 'object' | 'array' | 'html' | 'text'
 
+/** 密钥配置内容在列表/详情中的打码值，修改时原样提交表示沿用原内容 */
+export const SECRET_MASK = '******'
+
+export type ConfigStatus =
+  | 'draft'
+  | 'pending'
+  | 'approved'
+  | 'rejected'
+  | 'published'
+
 export type ConfigResponse = {
   id: string
   code: string
@@ -15,6 +25,24 @@ export type ConfigResponse = {
   updated_at: string
   generate_values: boolean
   schema?: string | null
+  status: ConfigStatus
+  submitted_by?: string | null
+  reviewed_by?: string | null
+  review_comment?: string | null
+  source_config_id?: string | null
+  is_secret: boolean
+  base_code?: string | null
+}
+
+export type ConfigReleaseResponse = {
+  code: string
+  environment: string
+  application_id?: string | null
+  config_id?: string | null
+  version?: number | null
+  require_approval: boolean
+  published_by?: string | null
+  published_at?: string | null
 }
 
 export type ConfigListResponse = {
@@ -33,6 +61,8 @@ export type ConfigCreateRequest = {
   description: string
   generate_values?: boolean
   schema?: string | null
+  is_secret?: boolean
+  base_code?: string | null
 }
 
 export type ConfigUpdateRequest = ConfigCreateRequest
@@ -61,3 +91,46 @@ export function updateConfig(configId: string, data: ConfigUpdateRequest) {
 export function deleteConfig(configId: string) {
   return apiClient.delete<void>(`/api/configs/${configId}`)
 }
+
+export function revealConfig(configId: string) {
+  return apiClient.get<ConfigResponse>(`/api/configs/${configId}/reveal`)
+}
+
+export function submitConfig(configId: string) {
+  return apiClient.post<ConfigResponse>(`/api/configs/${configId}/submit`)
+}
+
+export function approveConfig(configId: string, comment?: string) {
+  return apiClient.post<ConfigResponse>(`/api/configs/${configId}/approve`, {
+    comment,
+  })
+}
+
+export function rejectConfig(configId: string, comment?: string) {
+  return apiClient.post<ConfigResponse>(`/api/configs/${configId}/reject`, {
+    comment,
+  })
+}
+
+export function publishConfig(configId: string) {
+  return apiClient.post<ConfigReleaseResponse>(
+    `/api/configs/${configId}/publish`
+  )
+}
+
+export function promoteConfig(configId: string, targetEnvironment?: string) {
+  return apiClient.post<ConfigResponse>(`/api/configs/${configId}/promote`, {
+    target_environment: targetEnvironment,
+  })
+}
+
+export function rollbackConfig(
+  code: string,
+  environment: string,
+  version?: number
+) {
+  return apiClient.post<ConfigReleaseResponse>(
+    `/api/configs/code/${code}/environment/${environment}/rollback`,
+    { version }
+  )
+}
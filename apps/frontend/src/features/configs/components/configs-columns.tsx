@@ -2,9 +2,17 @@ import { ColumnDef } from '@tanstack/react-table'
 import { formatDateTime } from '@/lib/datetime'
 import { Badge } from '@/components/ui/badge'
 import { DataTableColumnHeader } from '@/components/data-table/column-header'
-import type { ConfigResponse } from '../api/configs-api'
+import type { ConfigResponse, ConfigStatus } from '../api/configs-api'
 import { ConfigsDataTableRowActions } from './configs-data-table-row-actions'
 
+const STATUS_LABELS: Record<ConfigStatus, string> = {
+  draft: '草稿',
+  pending: '待审核',
+  approved: '已审批',
+  rejected: '已驳回',
+  published: '已发布',
+}
+
 export const columns: ColumnDef<ConfigResponse>[] = [
   {
     accessorKey: 'code',
@@ -40,6 +48,19 @@ export const columns: ColumnDef<ConfigResponse>[] = [
       <DataTableColumnHeader column={column} title='版本' />
     ),
   },
+  {
+    accessorKey: 'status',
+    header: ({ column }) => (
+      <DataTableColumnHeader column={column} title='状态' />
+    ),
+    cell: ({ row }) => (
+      <Badge
+        variant={row.original.status === 'published' ? 'default' : 'secondary'}
+      >
+        {STATUS_LABELS[row.original.status] ?? row.original.status}
+      </Badge>
+    ),
+  },
   {
     accessorKey: 'updated_at',
     header: ({ column }) => (
@@ -8,8 +8,11 @@ import {
   DropdownMenuItem,
   DropdownMenuTrigger,
 } from '@/components/ui/dropdown-menu'
-import type { ConfigResponse } from '../api/configs-api'
-import { useDeleteConfig } from '../hooks/use-configs-query'
+import { revealConfig, type ConfigResponse } from '../api/configs-api'
+import {
+  useConfigWorkflow,
+  useDeleteConfig,
+} from '../hooks/use-configs-query'
 import { useConfigsProvider } from './configs-provider'
 
 export function ConfigsDataTableRowActions({
@@ -20,6 +23,15 @@ export function ConfigsDataTableRowActions({
   const { setIsSheetOpen, setSheetMode, setSelectedConfigId } =
     useConfigsProvider()
   const deleteMutation = useDeleteConfig()
+  const workflowMutation = useConfigWorkflow()
+  const { status } = row.original
+  const runWorkflow = async (
+    action: 'submit' | 'approve' | 'reject' | 'publish' | 'promote',
+    message: string
+  ) => {
+    await workflowMutation.mutateAsync({ configId: row.original.id, action })
+    toast.success(message)
+  }
   return (
     <DropdownMenu>
       <DropdownMenuTrigger asChild>
@@ -46,6 +58,52 @@ export function ConfigsDataTableRowActions({
         >
           编辑
         </DropdownMenuItem>
+        {row.original.is_secret && (
+          <DropdownMenuItem
+            onClick={async () => {
+              const { data } = await revealConfig(row.original.id)
+              await navigator.clipboard.writeText(data.content)
+              toast.success('明文已复制到剪贴板')
+            }}
+          >
+            复制明文
+          </DropdownMenuItem>
+        )}
+        {(status === 'draft' || status === 'rejected') && (
+          <DropdownMenuItem
+            onClick={() => runWorkflow('submit', '已提交审核')}
+          >
+            提交审核
+          </DropdownMenuItem>
+        )}
+        {status === 'pending' && (
+          <>
+            <DropdownMenuItem
+              onClick={() => runWorkflow('approve', '审批通过')}
+            >
+              审批通过
+            </DropdownMenuItem>
+            <DropdownMenuItem
+              onClick={() => runWorkflow('reject', '已驳回')}
+            >
+              驳回
+            </DropdownMenuItem>
+          </>
+        )}
+        {status !== 'rejected' && (
+          <DropdownMenuItem
+            onClick={() => runWorkflow('publish', '发布成功')}
+          >
+            发布
+          </DropdownMenuItem>
+        )}
+        {row.original.environment !== 'prod' && (
+          <DropdownMenuItem
+            onClick={() => runWorkflow('promote', '已复制到下一环境')}
+          >
+            晋级
+          </DropdownMenuItem>
+        )}
         <DropdownMenuItem
           onClick={async () => {
             await deleteMutation.mutateAsync(row.original.id)
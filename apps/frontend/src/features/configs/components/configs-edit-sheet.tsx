@@ -29,6 +29,7 @@ import {
   SheetFooter,
 } from '@/components/ui/sheet'
 import { Textarea } from '@/components/ui/textarea'
+import { SECRET_MASK } from '../api/configs-api'
 import {
   createConfigRequestSchema,
   type CreateConfigRequest,
@@ -63,6 +64,8 @@ export function ConfigsEditSheet() {
       description: '',
       generate_values: false,
       schema: '',
+      is_secret: false,
+      base_code: '',
     },
     mode: 'onChange',
     reValidateMode: 'onBlur',
@@ -81,6 +84,8 @@ export function ConfigsEditSheet() {
           description: cur.description,
           generate_values: cur.generate_values,
           schema: cur.schema ?? '',
+          is_secret: cur.is_secret,
+          base_code: cur.base_code ?? '',
         }
         const needReset =
           JSON.stringify(form.getValues()) !== JSON.stringify(target)
@@ -96,6 +101,8 @@ export function ConfigsEditSheet() {
         description: '',
         generate_values: false,
         schema: '',
+        is_secret: false,
+        base_code: '',
       }
       const needReset =
         JSON.stringify(form.getValues()) !== JSON.stringify(defaults)
@@ -105,7 +112,15 @@ export function ConfigsEditSheet() {
 
   const onSubmit = async (payload: CreateConfigRequest) => {
     try {
-      if (payload.config_type === 'array' && payload.generate_values) {
+      // 密钥配置未修改内容时提交打码值，由服务端沿用原内容
+      const contentMasked = payload.content === SECRET_MASK
+      // 覆盖层的选项可只包含 code 与覆盖字段，由服务端按合并结果校验
+      if (
+        !contentMasked &&
+        !payload.base_code &&
+        payload.config_type === 'array' &&
+        payload.generate_values
+      ) {
         try {
           const v = JSON.parse(payload.content)
           if (!Array.isArray(v)) throw new Error('内容需为数组')
@@ -123,7 +138,7 @@ export function ConfigsEditSheet() {
           return
         }
       }
-      if (payload.config_type === 'object') {
+      if (!contentMasked && payload.config_type === 'object') {
         try {
           const v = JSON.parse(payload.content)
           if (typeof v !== 'object' || Array.isArray(v) || v === null)
@@ -133,7 +148,11 @@ export function ConfigsEditSheet() {
           return
         }
       }
-      if (payload.config_type === 'array' && !payload.generate_values) {
+      if (
+        !contentMasked &&
+        payload.config_type === 'array' &&
+        !payload.generate_values
+      ) {
         try {
           const v = JSON.parse(payload.content)
           if (!Array.isArray(v)) throw new Error('')
@@ -300,6 +319,24 @@ export function ConfigsEditSheet() {
                   </FormItem>
                 )}
               />
+              <FormField
+                control={form.control}
+                name='base_code'
+                render={({ field }) => (
+                  <FormItem>
+                    <FormLabel>基础配置代码</FormLabel>
+                    <FormControl>
+                      <Input
+                        placeholder='可选，填写后本配置作为覆盖层与基础配置合并'
+                        {...field}
+                        value={field.value ?? ''}
+                        disabled={isViewMode}
+                      />
+                    </FormControl>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
               <FormField
                 control={form.control}
                 name='content'
@@ -357,6 +394,23 @@ export function ConfigsEditSheet() {
                   )}
                 />
               )}
+              <FormField
+                control={form.control}
+                name='is_secret'
+                render={({ field }) => (
+                  <FormItem className='flex flex-row items-center gap-2'>
+                    <FormControl>
+                      <Checkbox
+                        checked={field.value}
+                        onCheckedChange={field.onChange}
+                        disabled={isViewMode}
+                      />
+                    </FormControl>
+                    <FormLabel>密钥配置（加密存储，内容打码显示）</FormLabel>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
             </form>
           </Form>
         </div>
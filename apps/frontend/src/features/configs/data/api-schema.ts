@@ -21,6 +21,8 @@ export const createConfigRequestSchema = z.object({
   description: z.string().optional().default(''),
   generate_values: z.boolean().optional().default(false),
   schema: z.string().optional().nullable(),
+  is_secret: z.boolean().optional().default(false),
+  base_code: z.string().optional().nullable(),
 })
 
 export type CreateConfigRequest = z.infer<typeof createConfigRequestSchema>
@@ -6,6 +6,11 @@ import {
   createConfig,
   updateConfig,
   deleteConfig,
+  submitConfig,
+  approveConfig,
+  rejectConfig,
+  publishConfig,
+  promoteConfig,
   type ConfigListQuery,
   type ConfigCreateRequest,
   type ConfigUpdateRequest,
@@ -74,3 +79,34 @@ export function useDeleteConfig() {
     },
   })
 }
+
+/** 发布流程操作：提交审核、审批、驳回、发布、晋级 */
+export function useConfigWorkflow() {
+  const qc = useQueryClient()
+  return useMutation({
+    mutationFn: async (payload: {
+      configId: string
+      action: 'submit' | 'approve' | 'reject' | 'publish' | 'promote'
+    }) => {
+      switch (payload.action) {
+        case 'submit':
+          return (await submitConfig(payload.configId)).data
+        case 'approve':
+          return (await approveConfig(payload.configId)).data
+        case 'reject':
+          return (await rejectConfig(payload.configId)).data
+        case 'publish':
+          return (await publishConfig(payload.configId)).data
+        case 'promote':
+          return (await promoteConfig(payload.configId)).data
+      }
+    },
+    onSuccess: () => {
+      qc.invalidateQueries({ queryKey: ['configs'] })
+    },
+    onError: (e: unknown) => {
+      toast.error('操作失败')
+      console.error(e)
+    },
+  })
+}
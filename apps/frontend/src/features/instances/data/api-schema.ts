@@ -67,7 +67,8 @@ export type InstanceDetailResponse = z.infer<
 // ===================================================================
 
 export const httpConfigSchema = z.object({
-  proxy_enabled: z.boolean().default(false),
+  // 未设置时不下发，Agent 沿用本地代理配置
+  proxy_enabled: z.boolean().optional(),
   proxy_host: z.string().optional(),
   proxy_port: z.number().optional(),
   proxy_username: z.string().optional(),
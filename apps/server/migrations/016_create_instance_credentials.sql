@@ -0,0 +1,45 @@
+-- ===================================================================
+-- Agent 实例凭证
+-- 说明: 应用通过 applications.auth_config 下发注册密钥（enrollment secret），
+--       Agent 使用注册密钥换取与 agent_instance_id 绑定的实例令牌，
+--       开放接口（/api/open/instances/*）据此校验调用方身份。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."instance_credentials"
+(
+    "id"                   varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "application_id"       varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "agent_instance_id"    varchar(255) COLLATE "pg_catalog"."default" NOT NULL,
+    "token_hash"           varchar(128) COLLATE "pg_catalog"."default" NOT NULL,
+    "token_prefix"         varchar(32) COLLATE "pg_catalog"."default"  NOT NULL,
+    "enrollment_secret_id" varchar(64) COLLATE "pg_catalog"."default",
+    "issued_at"            timestamptz(3)                              NOT NULL DEFAULT now(),
+    "last_used_at"         timestamptz(3),
+    "revoked_at"           timestamptz(3),
+    "revoked_reason"       varchar(100) COLLATE "pg_catalog"."default",
+    "created_at"           timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"           timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_instance_credentials" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."instance_credentials" IS 'Agent 实例令牌表，令牌仅保存 SHA-256 摘要';
+COMMENT ON COLUMN "public"."instance_credentials"."application_id" IS '签发令牌的应用ID';
+COMMENT ON COLUMN "public"."instance_credentials"."agent_instance_id" IS '令牌绑定的 Agent 实例ID';
+COMMENT ON COLUMN "public"."instance_credentials"."token_hash" IS '令牌 SHA-256 摘要（十六进制）';
+COMMENT ON COLUMN "public"."instance_credentials"."token_prefix" IS '令牌前缀，便于界面识别';
+COMMENT ON COLUMN "public"."instance_credentials"."enrollment_secret_id" IS '换取令牌时使用的注册密钥ID';
+COMMENT ON COLUMN "public"."instance_credentials"."revoked_at" IS '吊销时间；NULL 表示有效';
+COMMENT ON COLUMN "public"."instance_credentials"."revoked_reason" IS '吊销原因：rotated, revoked, re_enrolled';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uniq_instance_credentials_token_hash"
+    ON "public"."instance_credentials" ("token_hash");
+CREATE INDEX IF NOT EXISTS "idx_instance_credentials_agent_instance_id"
+    ON "public"."instance_credentials" ("agent_instance_id");
+CREATE INDEX IF NOT EXISTS "idx_instance_credentials_application_id"
+    ON "public"."instance_credentials" ("application_id");
+
+CREATE TRIGGER "update_instance_credentials_updated_at"
+    BEFORE UPDATE
+    ON "public"."instance_credentials"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
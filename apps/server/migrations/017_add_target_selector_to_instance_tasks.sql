@@ -0,0 +1,16 @@
+-- ===================================================================
+-- 为 instance_tasks 表添加目标选择器
+-- 说明: 任务可按应用、标签、操作系统、在线状态与比例选择目标实例，
+--       target_instances 保存解析后的实例集合，target_selector 保存原始选择器。
+--       resolve_at = dispatch 的任务在有效期内会将后续上线且匹配的实例加入执行。
+-- ===================================================================
+
+ALTER TABLE "public"."instance_tasks"
+    ADD COLUMN IF NOT EXISTS "target_selector" jsonb;
+
+CREATE INDEX IF NOT EXISTS "idx_instance_tasks_selector_resolve_at" ON "public"."instance_tasks" USING btree (
+    ("target_selector" ->> 'resolve_at')
+    ) WHERE "target_selector" IS NOT NULL AND "deleted_at" IS NULL;
+
+COMMENT
+ON COLUMN "public"."instance_tasks"."target_selector" IS '目标实例选择器（JSON），为空表示仅使用显式指定的实例';
@@ -0,0 +1,30 @@
+-- ===================================================================
+-- 任务分批执行（滚动发布）
+-- 说明: instance_tasks.rollout_strategy 保存分批策略（批大小/比例、批间暂停、失败率阈值），
+--       instance_tasks.rollout_state 保存执行进度；
+--       instance_task_records.batch_index 标识记录所属批次，未放行批次的记录状态为 queued。
+-- ===================================================================
+
+ALTER TABLE "public"."instance_tasks"
+    ADD COLUMN IF NOT EXISTS "rollout_strategy" jsonb,
+    ADD COLUMN IF NOT EXISTS "rollout_state" jsonb;
+
+ALTER TABLE "public"."instance_task_records"
+    ADD COLUMN IF NOT EXISTS "batch_index" int4;
+
+CREATE INDEX IF NOT EXISTS "idx_instance_tasks_rollout_status" ON "public"."instance_tasks" USING btree (
+    ("rollout_state" ->> 'status')
+    ) WHERE "rollout_state" IS NOT NULL AND "deleted_at" IS NULL;
+
+CREATE INDEX IF NOT EXISTS "idx_instance_task_records_task_batch" ON "public"."instance_task_records" USING btree (
+    "task_id", "batch_index"
+    );
+
+COMMENT
+ON COLUMN "public"."instance_tasks"."rollout_strategy" IS '分批执行策略（JSON），为空表示所有实例同时执行';
+COMMENT
+ON COLUMN "public"."instance_tasks"."rollout_state" IS '分批执行进度（JSON）：当前批次、总批次、状态、下一批放行时间';
+COMMENT
+ON COLUMN "public"."instance_task_records"."batch_index" IS '所属批次（从0开始），为空表示未分批';
+COMMENT
+ON COLUMN "public"."instance_task_records"."status" IS '执行状态：queued, pending, dispatched, running, success, failed, timeout, cancelled';
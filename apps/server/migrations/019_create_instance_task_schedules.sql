@@ -0,0 +1,68 @@
+-- ===================================================================
+-- 定时任务
+-- 说明: instance_task_schedules 保存任务模板与 cron 表达式（含时区），
+--       后台调度器到期后按模板生成普通的 instance_tasks 与 instance_task_records；
+--       instance_task_schedule_runs 记录每次触发结果及生成的任务ID。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."instance_task_schedules"
+(
+    "id"                varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "name"              varchar(255) COLLATE "pg_catalog"."default" NOT NULL,
+    "description"       text COLLATE "pg_catalog"."default",
+    "application_id"    varchar(64) COLLATE "pg_catalog"."default",
+    "enabled"           bool                                        NOT NULL DEFAULT true,
+    "cron_expression"   varchar(255) COLLATE "pg_catalog"."default" NOT NULL,
+    "timezone"          varchar(64) COLLATE "pg_catalog"."default"  NOT NULL DEFAULT 'UTC',
+    "misfire_policy"    varchar(32) COLLATE "pg_catalog"."default"  NOT NULL DEFAULT 'fire_once',
+    "allow_overlap"     bool                                        NOT NULL DEFAULT false,
+    "task_template"     jsonb                                       NOT NULL,
+    "next_run_at"       timestamptz(3),
+    "last_run_at"       timestamptz(3),
+    "created_by"        varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "updated_by"        varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "deleted_at"        timestamptz(3),
+    "created_at"        timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"        timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_instance_task_schedules" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."instance_task_schedules" IS '定时任务表';
+COMMENT ON COLUMN "public"."instance_task_schedules"."cron_expression" IS 'cron 表达式，支持 5 段（分 时 日 月 周）或带秒的 6 段';
+COMMENT ON COLUMN "public"."instance_task_schedules"."timezone" IS 'IANA 时区，如 Asia/Shanghai';
+COMMENT ON COLUMN "public"."instance_task_schedules"."misfire_policy" IS '错过触发时间的处理：fire_once 补触发一次，skip 跳过';
+COMMENT ON COLUMN "public"."instance_task_schedules"."allow_overlap" IS '上次生成的任务未结束时是否仍然触发';
+COMMENT ON COLUMN "public"."instance_task_schedules"."task_template" IS '任务模板（与创建任务请求结构一致）';
+COMMENT ON COLUMN "public"."instance_task_schedules"."next_run_at" IS '下次触发时间，停用时为 NULL';
+
+CREATE INDEX IF NOT EXISTS "idx_instance_task_schedules_next_run_at"
+    ON "public"."instance_task_schedules" ("next_run_at") WHERE "enabled" = true AND "deleted_at" IS NULL;
+CREATE INDEX IF NOT EXISTS "idx_instance_task_schedules_application_id"
+    ON "public"."instance_task_schedules" ("application_id");
+
+CREATE TRIGGER "update_instance_task_schedules_updated_at"
+    BEFORE UPDATE
+    ON "public"."instance_task_schedules"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
+
+CREATE TABLE IF NOT EXISTS "public"."instance_task_schedule_runs"
+(
+    "id"           varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "schedule_id"  varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "scheduled_at" timestamptz(3)                             NOT NULL,
+    "triggered_at" timestamptz(3)                             NOT NULL DEFAULT now(),
+    "status"       varchar(32) COLLATE "pg_catalog"."default" NOT NULL,
+    "task_id"      varchar(64) COLLATE "pg_catalog"."default",
+    "message"      text COLLATE "pg_catalog"."default",
+    "created_at"   timestamptz(3)                             NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_instance_task_schedule_runs" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."instance_task_schedule_runs" IS '定时任务触发历史';
+COMMENT ON COLUMN "public"."instance_task_schedule_runs"."scheduled_at" IS '计划触发时间';
+COMMENT ON COLUMN "public"."instance_task_schedule_runs"."status" IS '触发结果：created, skipped, failed';
+COMMENT ON COLUMN "public"."instance_task_schedule_runs"."task_id" IS '生成的任务ID';
+
+CREATE INDEX IF NOT EXISTS "idx_instance_task_schedule_runs_schedule_id"
+    ON "public"."instance_task_schedule_runs" ("schedule_id", "scheduled_at" DESC);
@@ -0,0 +1,27 @@
+-- ===================================================================
+-- 任务输出流
+-- 说明: Agent 在任务执行过程中按序号分段上报 stdout/stderr，
+--       服务端逐段保存并推送给正在查看该执行记录的用户；
+--       执行记录重新下发时清空旧输出，(record_id, seq) 唯一保证重复上报幂等。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."instance_task_outputs"
+(
+    "id"         varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "record_id"  varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "seq"        int8                                       NOT NULL,
+    "stream"     varchar(16) COLLATE "pg_catalog"."default" NOT NULL DEFAULT 'stdout',
+    "content"    text COLLATE "pg_catalog"."default"        NOT NULL,
+    "created_at" timestamptz(3)                             NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_instance_task_outputs" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."instance_task_outputs" IS '任务执行输出分段表';
+COMMENT ON COLUMN "public"."instance_task_outputs"."record_id" IS '任务执行记录ID';
+COMMENT ON COLUMN "public"."instance_task_outputs"."seq" IS '分段序号，从 0 开始递增';
+COMMENT ON COLUMN "public"."instance_task_outputs"."stream" IS '输出流：stdout, stderr';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uk_instance_task_outputs_record_seq"
+    ON "public"."instance_task_outputs" ("record_id", "seq");
+CREATE INDEX IF NOT EXISTS "idx_instance_task_outputs_created_at"
+    ON "public"."instance_task_outputs" ("created_at");
@@ -0,0 +1,31 @@
+-- ===================================================================
+-- 实例指标降采样
+-- 说明: instance_records 保存 Agent 每次上报的原始快照（短期保留），
+--       后台任务按 1m / 1h / 1d 粒度逐级汇总到 instance_metric_rollups，
+--       各粒度独立保留，长时间范围的监控图表查询汇总数据而不扫描原始记录。
+--       metric 为指标名：固定列（如 cpu_usage_percent）或 custom.<key>（custom_metrics 中的数值项）。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."instance_metric_rollups"
+(
+    "instance_id"  varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "resolution"   varchar(8) COLLATE "pg_catalog"."default"   NOT NULL,
+    "metric"       varchar(128) COLLATE "pg_catalog"."default" NOT NULL,
+    "bucket_start" timestamptz(3)                              NOT NULL,
+    "sample_count" int8                                        NOT NULL,
+    "sum_value"    float8                                      NOT NULL,
+    "min_value"    float8                                      NOT NULL,
+    "max_value"    float8                                      NOT NULL,
+    "p95_value"    float8                                      NOT NULL,
+    "updated_at"   timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_instance_metric_rollups" PRIMARY KEY ("instance_id", "resolution", "metric", "bucket_start")
+);
+
+COMMENT ON TABLE "public"."instance_metric_rollups" IS '实例指标降采样表';
+COMMENT ON COLUMN "public"."instance_metric_rollups"."resolution" IS '汇总粒度：1m, 1h, 1d';
+COMMENT ON COLUMN "public"."instance_metric_rollups"."metric" IS '指标名，自定义指标以 custom. 为前缀';
+COMMENT ON COLUMN "public"."instance_metric_rollups"."bucket_start" IS '分桶起始时间（UTC 对齐）';
+COMMENT ON COLUMN "public"."instance_metric_rollups"."p95_value" IS '95 分位值：1m 由原始样本计算，1h/1d 由下级分桶的 p95 近似';
+
+CREATE INDEX IF NOT EXISTS "idx_instance_metric_rollups_resolution_bucket"
+    ON "public"."instance_metric_rollups" ("resolution", "bucket_start");
@@ -0,0 +1,138 @@
+-- ===================================================================
+-- 告警规则与通知渠道
+-- 说明: alert_rules 定义告警条件（指标阈值持续、实例离线、任务失败率），
+--       后台评估器周期性计算并在 alert_events 中维护 pending/firing/resolved 状态；
+--       alert_channels 保存 webhook、邮件、钉钉/企业微信机器人等通知渠道；
+--       alert_silences 在指定时间段内屏蔽匹配告警的通知。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."alert_channels"
+(
+    "id"           varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "name"         varchar(255) COLLATE "pg_catalog"."default" NOT NULL,
+    "channel_type" varchar(32) COLLATE "pg_catalog"."default"  NOT NULL,
+    "config"       jsonb                                       NOT NULL DEFAULT '{}'::jsonb,
+    "enabled"      bool                                        NOT NULL DEFAULT true,
+    "created_by"   varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "updated_by"   varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "deleted_at"   timestamptz(3),
+    "created_at"   timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"   timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_alert_channels" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."alert_channels" IS '告警通知渠道';
+COMMENT ON COLUMN "public"."alert_channels"."channel_type" IS '渠道类型：webhook, email, dingtalk, wecom';
+COMMENT ON COLUMN "public"."alert_channels"."config" IS '渠道配置（地址、SMTP 参数、签名密钥等）';
+
+CREATE TRIGGER "update_alert_channels_updated_at"
+    BEFORE UPDATE
+    ON "public"."alert_channels"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
+
+CREATE TABLE IF NOT EXISTS "public"."alert_rules"
+(
+    "id"                      varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "name"                    varchar(255) COLLATE "pg_catalog"."default" NOT NULL,
+    "description"             text COLLATE "pg_catalog"."default",
+    "rule_type"               varchar(32) COLLATE "pg_catalog"."default"  NOT NULL,
+    "severity"                varchar(16) COLLATE "pg_catalog"."default"  NOT NULL DEFAULT 'warning',
+    "application_id"          varchar(64) COLLATE "pg_catalog"."default",
+    "instance_ids"            jsonb                                       NOT NULL DEFAULT '[]'::jsonb,
+    "condition"               jsonb                                       NOT NULL,
+    "channel_ids"             jsonb                                       NOT NULL DEFAULT '[]'::jsonb,
+    "repeat_interval_minutes" int4                                        NOT NULL DEFAULT 0,
+    "notify_resolved"         bool                                        NOT NULL DEFAULT true,
+    "enabled"                 bool                                        NOT NULL DEFAULT true,
+    "last_evaluated_at"       timestamptz(3),
+    "created_by"              varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "updated_by"              varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "deleted_at"              timestamptz(3),
+    "created_at"              timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"              timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_alert_rules" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."alert_rules" IS '告警规则';
+COMMENT ON COLUMN "public"."alert_rules"."rule_type" IS '规则类型：metric_threshold, instance_offline, task_failure_rate';
+COMMENT ON COLUMN "public"."alert_rules"."severity" IS '级别：info, warning, critical';
+COMMENT ON COLUMN "public"."alert_rules"."application_id" IS '限定应用，为空表示全部应用';
+COMMENT ON COLUMN "public"."alert_rules"."instance_ids" IS '限定实例ID列表，为空表示应用下全部实例';
+COMMENT ON COLUMN "public"."alert_rules"."condition" IS '告警条件（结构随 rule_type 变化）';
+COMMENT ON COLUMN "public"."alert_rules"."channel_ids" IS '通知渠道ID列表';
+COMMENT ON COLUMN "public"."alert_rules"."repeat_interval_minutes" IS '持续告警的重复通知间隔（分钟），0 表示不重复';
+
+CREATE INDEX IF NOT EXISTS "idx_alert_rules_enabled"
+    ON "public"."alert_rules" ("enabled") WHERE "deleted_at" IS NULL;
+
+CREATE TRIGGER "update_alert_rules_updated_at"
+    BEFORE UPDATE
+    ON "public"."alert_rules"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
+
+CREATE TABLE IF NOT EXISTS "public"."alert_events"
+(
+    "id"               varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "rule_id"          varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "fingerprint"      varchar(255) COLLATE "pg_catalog"."default" NOT NULL,
+    "instance_id"      varchar(64) COLLATE "pg_catalog"."default",
+    "application_id"   varchar(64) COLLATE "pg_catalog"."default",
+    "status"           varchar(16) COLLATE "pg_catalog"."default"  NOT NULL,
+    "severity"         varchar(16) COLLATE "pg_catalog"."default"  NOT NULL,
+    "summary"          text COLLATE "pg_catalog"."default"         NOT NULL,
+    "value"            float8,
+    "started_at"       timestamptz(3)                              NOT NULL,
+    "fired_at"         timestamptz(3),
+    "resolved_at"      timestamptz(3),
+    "last_value_at"    timestamptz(3)                              NOT NULL,
+    "acknowledged_by"  varchar(64) COLLATE "pg_catalog"."default",
+    "acknowledged_at"  timestamptz(3),
+    "last_notified_at" timestamptz(3),
+    "notify_count"     int4                                        NOT NULL DEFAULT 0,
+    "notify_error"     text COLLATE "pg_catalog"."default",
+    "created_at"       timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"       timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_alert_events" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."alert_events" IS '告警事件';
+COMMENT ON COLUMN "public"."alert_events"."fingerprint" IS '告警对象标识（实例ID或规则范围），同一规则同一对象同时只有一条未恢复事件';
+COMMENT ON COLUMN "public"."alert_events"."status" IS '状态：pending（等待持续时间）, firing, resolved';
+COMMENT ON COLUMN "public"."alert_events"."started_at" IS '条件首次满足时间';
+COMMENT ON COLUMN "public"."alert_events"."fired_at" IS '进入 firing 的时间';
+COMMENT ON COLUMN "public"."alert_events"."notify_error" IS '最近一次通知失败原因';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uk_alert_events_active"
+    ON "public"."alert_events" ("rule_id", "fingerprint") WHERE "status" <> 'resolved';
+CREATE INDEX IF NOT EXISTS "idx_alert_events_status"
+    ON "public"."alert_events" ("status", "started_at" DESC);
+CREATE INDEX IF NOT EXISTS "idx_alert_events_instance_id"
+    ON "public"."alert_events" ("instance_id");
+
+CREATE TRIGGER "update_alert_events_updated_at"
+    BEFORE UPDATE
+    ON "public"."alert_events"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
+
+CREATE TABLE IF NOT EXISTS "public"."alert_silences"
+(
+    "id"          varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "rule_id"     varchar(64) COLLATE "pg_catalog"."default",
+    "instance_id" varchar(64) COLLATE "pg_catalog"."default",
+    "comment"     text COLLATE "pg_catalog"."default",
+    "starts_at"   timestamptz(3)                             NOT NULL,
+    "ends_at"     timestamptz(3)                             NOT NULL,
+    "created_by"  varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "created_at"  timestamptz(3)                             NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_alert_silences" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."alert_silences" IS '告警静默';
+COMMENT ON COLUMN "public"."alert_silences"."rule_id" IS '匹配的规则ID，为空表示全部规则';
+COMMENT ON COLUMN "public"."alert_silences"."instance_id" IS '匹配的实例ID，为空表示全部实例';
+
+CREATE INDEX IF NOT EXISTS "idx_alert_silences_ends_at"
+    ON "public"."alert_silences" ("ends_at");
@@ -0,0 +1,62 @@
+-- ===================================================================
+-- 路由级权限
+-- 说明: 服务端按路由校验操作类权限（permission_type = 'action'），名称格式为 <资源>.<操作>，
+--       <资源>.manage 涵盖该资源的全部操作；admin 角色拥有全部权限。
+--       这里补齐任务、定时任务、告警、配置、系统管理（用户/角色/权限）、审计的操作权限，
+--       并为内置的 user 角色授予业务资源的查看权限，保持原有的只读访问。
+-- ===================================================================
+
+INSERT INTO "public"."permissions"
+("id", "name", "permission_action", "description", "permission_type", "created_by", "updated_by")
+VALUES ('1734095616123456701', 'projects.manage', 'manage', '管理项目', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456702', 'applications.manage', 'manage', '管理应用', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456703', 'instances.manage', 'manage', '管理实例', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456711', 'tasks.create', 'create', '下发任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456712', 'tasks.edit', 'update', '取消/重试任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456713', 'tasks.delete', 'delete', '删除任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456714', 'tasks.view', 'read', '查看任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456715', 'tasks.manage', 'manage', '管理任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456721', 'task_schedules.create', 'create', '创建定时任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456722', 'task_schedules.edit', 'update', '编辑/启停定时任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456723', 'task_schedules.delete', 'delete', '删除定时任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456724', 'task_schedules.view', 'read', '查看定时任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456725', 'task_schedules.manage', 'manage', '管理定时任务', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456731', 'configs.create', 'create', '创建配置', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456732', 'configs.edit', 'update', '编辑配置', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456733', 'configs.delete', 'delete', '删除配置', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456734', 'configs.view', 'read', '查看配置', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456735', 'configs.manage', 'manage', '管理配置', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456741', 'alerts.create', 'create', '创建告警规则/渠道/静默', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456742', 'alerts.edit', 'update', '编辑告警与确认告警事件', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456743', 'alerts.delete', 'delete', '删除告警规则/渠道/静默', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456744', 'alerts.view', 'read', '查看告警', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456745', 'alerts.manage', 'manage', '管理告警', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456751', 'system.users.create', 'create', '创建用户', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456752', 'system.users.edit', 'update', '编辑/启用/禁用用户', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456753', 'system.users.delete', 'delete', '删除用户', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456754', 'system.users.view', 'read', '查看用户', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456761', 'system.roles.create', 'create', '创建角色', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456762', 'system.roles.edit', 'update', '编辑角色', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456763', 'system.roles.delete', 'delete', '删除角色', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456764', 'system.roles.view', 'read', '查看角色', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456765', 'system.roles.assign', 'update', '为用户分配角色', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456771', 'system.permissions.create', 'create', '创建权限', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456772', 'system.permissions.edit', 'update', '编辑权限', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456773', 'system.permissions.delete', 'delete', '删除权限', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456774', 'system.permissions.view', 'read', '查看权限', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456775', 'system.permissions.assign', 'update', '为角色分配权限', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456781', 'audit.view', 'read', '查看审计日志', 'action', '1734095616123456001', '1734095616123456001')
+ON CONFLICT ("name") DO NOTHING;
+
+-- 内置 user 角色：授予业务资源的查看权限（系统管理与审计除外）
+INSERT INTO "public"."role_permissions" ("id", "role_id", "permission_id")
+SELECT '1734095616123459' || RIGHT(p."id", 3), r."id", p."id"
+FROM "public"."roles" r
+         CROSS JOIN "public"."permissions" p
+WHERE r."name" = 'user'
+  AND r."deleted_at" IS NULL
+  AND p."deleted_at" IS NULL
+  AND p."permission_type" = 'action'
+  AND p."name" IN ('projects.view', 'applications.view', 'instances.view', 'tasks.view',
+                   'task_schedules.view', 'configs.view', 'alerts.view', 'logs.view')
+ON CONFLICT ("role_id", "permission_id") DO NOTHING;
@@ -0,0 +1,47 @@
+-- ===================================================================
+-- 项目/应用成员与数据范围
+-- 说明: resource_members 记录用户或角色在项目、应用上的成员关系。
+--       非管理员只能看到并操作所属项目（含其下全部应用）与所属应用的实例、任务、配置、日志和文件；
+--       configs 新增 application_id，为空表示全局共享配置。
+--       为保持升级前的可见范围，内置 user 角色加入所有现有项目。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."resource_members"
+(
+    "id"            varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "resource_type" varchar(32) COLLATE "pg_catalog"."default" NOT NULL,
+    "resource_id"   varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "member_type"   varchar(32) COLLATE "pg_catalog"."default" NOT NULL,
+    "member_id"     varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "created_by"    varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "created_at"    timestamptz(3)                             NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_resource_members" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."resource_members" IS '项目/应用成员';
+COMMENT ON COLUMN "public"."resource_members"."resource_type" IS '资源类型：project, application';
+COMMENT ON COLUMN "public"."resource_members"."resource_id" IS '项目ID或应用ID';
+COMMENT ON COLUMN "public"."resource_members"."member_type" IS '成员类型：user, role';
+COMMENT ON COLUMN "public"."resource_members"."member_id" IS '用户ID或角色ID';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uk_resource_members"
+    ON "public"."resource_members" ("resource_type", "resource_id", "member_type", "member_id");
+CREATE INDEX IF NOT EXISTS "idx_resource_members_member"
+    ON "public"."resource_members" ("member_type", "member_id");
+
+ALTER TABLE "public"."configs"
+    ADD COLUMN IF NOT EXISTS "application_id" varchar(64) COLLATE "pg_catalog"."default";
+
+COMMENT ON COLUMN "public"."configs"."application_id" IS '所属应用ID，为空表示全局共享配置';
+
+CREATE INDEX IF NOT EXISTS "idx_configs_application_id"
+    ON "public"."configs" ("application_id") WHERE "application_id" IS NOT NULL;
+
+INSERT INTO "public"."resource_members" ("id", "resource_type", "resource_id", "member_type", "member_id", "created_by")
+SELECT p."id" || '-' || r."id", 'project', p."id", 'role', r."id", '1734095616123456001'
+FROM "public"."projects" p
+         CROSS JOIN "public"."roles" r
+WHERE r."name" = 'user'
+  AND r."deleted_at" IS NULL
+  AND p."deleted_at" IS NULL
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,15 @@
+-- ===================================================================
+-- 实例配置下发版本
+-- 说明: config_revision 在每次修改实例配置时递增，随上报响应下发给 Agent；
+--       Agent 热加载成功后在下一次上报中回传 config_applied_revision，
+--       两者一致表示配置已生效，否则为待生效。
+-- ===================================================================
+
+ALTER TABLE "public"."instances"
+    ADD COLUMN IF NOT EXISTS "config_revision" int4 NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "config_applied_revision" int4,
+    ADD COLUMN IF NOT EXISTS "config_applied_at" timestamptz(3);
+
+COMMENT ON COLUMN "public"."instances"."config_revision" IS '实例配置版本，每次修改配置递增';
+COMMENT ON COLUMN "public"."instances"."config_applied_revision" IS 'Agent 已生效的配置版本';
+COMMENT ON COLUMN "public"."instances"."config_applied_at" IS 'Agent 确认配置生效的时间';
@@ -0,0 +1,62 @@
+-- ===================================================================
+-- Agent 发布包与版本管理
+-- 说明: agent_releases 登记 Rust Agent 各版本、各平台的发布包，安装包本身存放在 files 表，
+--       这里记录 SHA-256 与 Ed25519 签名供 Agent 下载后校验；
+--       applications.agent_target_version 为应用的 Agent 目标版本，为空表示跟随最新版本。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."agent_releases"
+(
+    "id"          varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "version"     varchar(50) COLLATE "pg_catalog"."default"  NOT NULL,
+    "platform"    varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "file_id"     varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "file_size"   int8                                        NOT NULL,
+    "sha256"      varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "signature"   varchar(255) COLLATE "pg_catalog"."default",
+    "notes"       text COLLATE "pg_catalog"."default",
+    "created_by"  varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "created_at"  timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"  timestamptz(3)                              NOT NULL DEFAULT now(),
+    "deleted_at"  timestamptz(3),
+    CONSTRAINT "pk_agent_releases" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."agent_releases" IS 'Agent 发布包';
+COMMENT ON COLUMN "public"."agent_releases"."version" IS 'Agent 版本号，如 1.2.0';
+COMMENT ON COLUMN "public"."agent_releases"."platform" IS '目标平台：<os>-<arch>，如 linux-x86_64';
+COMMENT ON COLUMN "public"."agent_releases"."file_id" IS '发布包文件ID（files.id）';
+COMMENT ON COLUMN "public"."agent_releases"."sha256" IS '发布包 SHA-256 摘要（十六进制）';
+COMMENT ON COLUMN "public"."agent_releases"."signature" IS '发布包 Ed25519 签名（Base64），为空表示未签名';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uniq_agent_releases_version_platform"
+    ON "public"."agent_releases" ("version", "platform") WHERE "deleted_at" IS NULL;
+
+CREATE TRIGGER "update_agent_releases_updated_at"
+    BEFORE UPDATE
+    ON "public"."agent_releases"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
+
+ALTER TABLE "public"."applications"
+    ADD COLUMN IF NOT EXISTS "agent_target_version" varchar(50) COLLATE "pg_catalog"."default";
+
+COMMENT ON COLUMN "public"."applications"."agent_target_version" IS 'Agent 目标版本，为空表示跟随最新发布版本';
+
+INSERT INTO "public"."permissions"
+("id", "name", "permission_action", "description", "permission_type", "created_by", "updated_by")
+VALUES ('1734095616123456791', 'agent_releases.create', 'create', '上传 Agent 发布包', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456792', 'agent_releases.delete', 'delete', '删除 Agent 发布包', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456793', 'agent_releases.view', 'read', '查看 Agent 发布包', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456794', 'agent_releases.manage', 'manage', '管理 Agent 发布包', 'action', '1734095616123456001', '1734095616123456001')
+ON CONFLICT ("name") DO NOTHING;
+
+INSERT INTO "public"."role_permissions" ("id", "role_id", "permission_id")
+SELECT '1734095616123459' || RIGHT(p."id", 3), r."id", p."id"
+FROM "public"."roles" r
+         CROSS JOIN "public"."permissions" p
+WHERE r."name" = 'user'
+  AND r."deleted_at" IS NULL
+  AND p."deleted_at" IS NULL
+  AND p."name" = 'agent_releases.view'
+ON CONFLICT ("role_id", "permission_id") DO NOTHING;
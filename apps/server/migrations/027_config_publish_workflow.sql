@@ -0,0 +1,92 @@
+-- ===================================================================
+-- 配置发布流程
+-- 说明: 配置的每次新增/修改生成草稿版本（status = draft），发布后才对应用客户端生效；
+--       config_releases 记录每个 code + environment 当前发布的版本，回滚即重新指向旧版本。
+--       require_approval 开启时，版本需提交审核并由拥有 configs.approve 权限的其他用户审批后才能发布。
+--       迁移前的配置版本均视为已发布，发布指针指向各 code + environment 的最新版本。
+-- ===================================================================
+
+ALTER TABLE "public"."configs"
+    ADD COLUMN IF NOT EXISTS "status"           varchar(20) COLLATE "pg_catalog"."default" NOT NULL DEFAULT 'draft',
+    ADD COLUMN IF NOT EXISTS "submitted_by"     varchar(64) COLLATE "pg_catalog"."default",
+    ADD COLUMN IF NOT EXISTS "submitted_at"     timestamptz(3),
+    ADD COLUMN IF NOT EXISTS "reviewed_by"      varchar(64) COLLATE "pg_catalog"."default",
+    ADD COLUMN IF NOT EXISTS "reviewed_at"      timestamptz(3),
+    ADD COLUMN IF NOT EXISTS "review_comment"   text COLLATE "pg_catalog"."default",
+    ADD COLUMN IF NOT EXISTS "source_config_id" varchar(64) COLLATE "pg_catalog"."default";
+
+COMMENT ON COLUMN "public"."configs"."status" IS '版本状态：draft(草稿), pending(待审核), approved(已审批), rejected(已驳回), published(已发布过)';
+COMMENT ON COLUMN "public"."configs"."submitted_by" IS '提交审核的用户ID';
+COMMENT ON COLUMN "public"."configs"."reviewed_by" IS '审批/驳回的用户ID';
+COMMENT ON COLUMN "public"."configs"."source_config_id" IS '晋级来源版本ID（从上一环境复制而来）';
+
+UPDATE "public"."configs"
+SET "status" = 'published'
+WHERE "status" = 'draft';
+
+CREATE TABLE IF NOT EXISTS "public"."config_releases"
+(
+    "id"               varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "code"             varchar(100) COLLATE "pg_catalog"."default" NOT NULL,
+    "environment"      varchar(50) COLLATE "pg_catalog"."default" NOT NULL,
+    "application_id"   varchar(64) COLLATE "pg_catalog"."default",
+    "config_id"        varchar(64) COLLATE "pg_catalog"."default",
+    "version"          int4,
+    "require_approval" bool                                       NOT NULL DEFAULT false,
+    "published_by"     varchar(64) COLLATE "pg_catalog"."default",
+    "published_at"     timestamptz(3),
+    "revision"         int4                                       NOT NULL DEFAULT 1,
+    "created_at"       timestamptz(3)                             NOT NULL DEFAULT now(),
+    "updated_at"       timestamptz(3)                             NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_config_releases" PRIMARY KEY ("id")
+);
+
+COMMENT ON TABLE "public"."config_releases" IS '配置发布指针';
+COMMENT ON COLUMN "public"."config_releases"."config_id" IS '当前发布的配置版本ID（configs.id），为空表示尚未发布';
+COMMENT ON COLUMN "public"."config_releases"."version" IS '当前发布的版本号';
+COMMENT ON COLUMN "public"."config_releases"."require_approval" IS '发布前是否需要其他用户审批';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uniq_config_releases_code_environment"
+    ON "public"."config_releases" ("code", "environment");
+
+CREATE TRIGGER "update_config_releases_updated_at"
+    BEFORE UPDATE
+    ON "public"."config_releases"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
+
+INSERT INTO "public"."config_releases"
+("id", "code", "environment", "application_id", "config_id", "version", "published_by", "published_at")
+SELECT DISTINCT ON (c."code", c."environment") c."id",
+                                               c."code",
+                                               c."environment",
+                                               c."application_id",
+                                               c."id",
+                                               c."version",
+                                               c."updated_by",
+                                               c."updated_at"
+FROM "public"."configs" c
+WHERE c."deleted_at" IS NULL
+ORDER BY c."code", c."environment", c."version" DESC
+ON CONFLICT ("code", "environment") DO NOTHING;
+
+INSERT INTO "public"."permissions"
+("id", "name", "permission_action", "description", "permission_type", "created_by", "updated_by")
+VALUES ('1734095616123456736', 'configs.publish', 'update', '发布/回滚配置', 'action', '1734095616123456001', '1734095616123456001'),
+       ('1734095616123456737', 'configs.approve', 'update', '审批配置发布', 'action', '1734095616123456001', '1734095616123456001')
+ON CONFLICT ("name") DO NOTHING;
+
+-- 内置配置审批角色：查看配置并审批发布
+INSERT INTO "public"."roles" ("id", "name", "description", "created_by", "updated_by")
+VALUES ('1734095616123456103', 'config_approver', '配置审批角色', '1734095616123456001', '1734095616123456001')
+ON CONFLICT ("id") DO NOTHING;
+
+INSERT INTO "public"."role_permissions" ("id", "role_id", "permission_id")
+SELECT '1734095616123457' || RIGHT(p."id", 3), r."id", p."id"
+FROM "public"."roles" r
+         CROSS JOIN "public"."permissions" p
+WHERE r."name" = 'config_approver'
+  AND r."deleted_at" IS NULL
+  AND p."deleted_at" IS NULL
+  AND p."name" IN ('configs.view', 'configs.approve')
+ON CONFLICT ("role_id", "permission_id") DO NOTHING;
@@ -0,0 +1,16 @@
+-- ===================================================================
+-- 密钥配置
+-- 说明: is_secret 为 true 的配置版本内容以信封加密保存（enc:v1:<主密钥ID>:<加密的数据密钥>:<密文>），
+--       生成的选项值（config_values.value_data）同样加密为字符串。主密钥由环境变量 CONFIG_MASTER_KEYS 提供。
+--       管理接口返回打码内容，明文仅能通过 configs.reveal 权限（记录审计）或应用客户端接口获取。
+-- ===================================================================
+
+ALTER TABLE "public"."configs"
+    ADD COLUMN IF NOT EXISTS "is_secret" bool NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN "public"."configs"."is_secret" IS '是否为密钥配置（内容加密存储）';
+
+INSERT INTO "public"."permissions"
+("id", "name", "permission_action", "description", "permission_type", "created_by", "updated_by")
+VALUES ('1734095616123456738', 'configs.reveal', 'read', '查看密钥配置明文', 'action', '1734095616123456001', '1734095616123456001')
+ON CONFLICT ("name") DO NOTHING;
@@ -0,0 +1,43 @@
+-- ===================================================================
+-- 配置模板与变量
+-- 说明: base_code 不为空的配置作为覆盖层，与基础配置合并得到生效配置：
+--       object 深度合并，array 按项的 code 合并；基础配置优先取同环境的发布版本。
+--       配置内容中的 ${name} 占位符按 config_variables 解析，
+--       优先级：应用+环境 > 应用 > 项目+环境 > 项目。
+-- ===================================================================
+
+ALTER TABLE "public"."configs"
+    ADD COLUMN IF NOT EXISTS "base_code" varchar(100) COLLATE "pg_catalog"."default";
+
+COMMENT ON COLUMN "public"."configs"."base_code" IS '基础配置代码，设置后本配置作为覆盖层与基础配置合并';
+
+CREATE TABLE IF NOT EXISTS "public"."config_variables"
+(
+    "id"             varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "project_id"     varchar(64) COLLATE "pg_catalog"."default",
+    "application_id" varchar(64) COLLATE "pg_catalog"."default",
+    "environment"    varchar(50) COLLATE "pg_catalog"."default",
+    "name"           varchar(100) COLLATE "pg_catalog"."default" NOT NULL,
+    "value"          text COLLATE "pg_catalog"."default"         NOT NULL,
+    "description"    text COLLATE "pg_catalog"."default",
+    "created_by"     varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "updated_by"     varchar(64) COLLATE "pg_catalog"."default"  NOT NULL,
+    "revision"       int4                                        NOT NULL DEFAULT 1,
+    "created_at"     timestamptz(3)                              NOT NULL DEFAULT now(),
+    "updated_at"     timestamptz(3)                              NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_config_variables" PRIMARY KEY ("id"),
+    CONSTRAINT "chk_config_variables_owner" CHECK (("project_id" IS NULL) <> ("application_id" IS NULL))
+);
+
+COMMENT ON TABLE "public"."config_variables" IS '配置变量（项目级或应用级）';
+COMMENT ON COLUMN "public"."config_variables"."environment" IS '生效环境，为空表示所有环境';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uniq_config_variables_owner_environment_name"
+    ON "public"."config_variables" (COALESCE("project_id", ''), COALESCE("application_id", ''),
+                                    COALESCE("environment", ''), "name");
+
+CREATE TRIGGER "update_config_variables_updated_at"
+    BEFORE UPDATE
+    ON "public"."config_variables"
+    FOR EACH ROW
+EXECUTE PROCEDURE "public"."update_updated_at_column"();
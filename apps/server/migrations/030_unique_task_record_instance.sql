@@ -0,0 +1,35 @@
+-- ===================================================================
+-- 任务执行记录唯一约束
+-- 说明: 每个任务在每个实例上只有一条执行记录（重试复用同一记录）。
+--       下发时解析的任务在多个并发拉取中补充记录时依赖该约束去重。
+--       建立约束前每组 (task_id, instance_id) 保留一条记录：优先已有执行结果的，其次 id 最小的；
+--       其余重复记录移入归档表，不直接删除。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."instance_task_records_archive"
+(
+    LIKE "public"."instance_task_records",
+    "archived_at" timestamptz(3) NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE "public"."instance_task_records_archive" IS '建立唯一约束时归档的重复任务执行记录';
+
+WITH ranked AS (SELECT "id",
+                       row_number() OVER (
+                           PARTITION BY "task_id", "instance_id"
+                           ORDER BY ("end_time" IS NULL), "id"
+                           ) AS rn
+                FROM "public"."instance_task_records"),
+     moved AS (
+         DELETE FROM "public"."instance_task_records" r
+             USING ranked
+             WHERE r."id" = ranked."id"
+                 AND ranked.rn > 1
+             RETURNING r.*)
+INSERT
+INTO "public"."instance_task_records_archive"
+SELECT *
+FROM moved;
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uniq_instance_task_records_task_instance"
+    ON "public"."instance_task_records" ("task_id", "instance_id");
@@ -0,0 +1,17 @@
+-- ===================================================================
+-- 后台任务进度
+-- 说明: 记录后台任务已处理到的位置，任务中断或服务停机后从该位置继续，
+--       而不是只回看固定时长。name 为任务标识，如 metrics_rollup:1m。
+-- ===================================================================
+
+CREATE TABLE IF NOT EXISTS "public"."maintenance_checkpoints"
+(
+    "name"       varchar(64) COLLATE "pg_catalog"."default" NOT NULL,
+    "position"   timestamptz(3)                             NOT NULL,
+    "updated_at" timestamptz(3)                             NOT NULL DEFAULT now(),
+    CONSTRAINT "pk_maintenance_checkpoints" PRIMARY KEY ("name")
+);
+
+COMMENT ON TABLE "public"."maintenance_checkpoints" IS '后台任务进度表';
+COMMENT ON COLUMN "public"."maintenance_checkpoints"."name" IS '任务标识';
+COMMENT ON COLUMN "public"."maintenance_checkpoints"."position" IS '已处理到的时间位置';
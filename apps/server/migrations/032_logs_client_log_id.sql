@@ -0,0 +1,14 @@
+-- ===================================================================
+-- Agent 日志去重
+-- 说明: Agent 为每条缓冲日志生成持久化的 ID 并随上报发送，响应丢失后重发同一批时
+--       服务端按 (instance_id, client_log_id) 忽略已入库的日志。
+--       服务端自身产生的日志 client_log_id 为空，不参与唯一约束。
+-- ===================================================================
+
+ALTER TABLE "public"."logs"
+    ADD COLUMN IF NOT EXISTS "client_log_id" varchar(64) COLLATE "pg_catalog"."default";
+
+COMMENT ON COLUMN "public"."logs"."client_log_id" IS 'Agent 侧日志ID，用于重发去重';
+
+CREATE UNIQUE INDEX IF NOT EXISTS "uniq_logs_instance_client_log_id"
+    ON "public"."logs" ("instance_id", "client_log_id");
@@ -0,0 +1,15 @@
+-- ===================================================================
+-- 任务输出按执行次数区分
+-- 说明: 超时重试后同一执行记录会再次下发，上一次执行迟到的输出分段与新一次执行的序号重叠。
+--       分段记录所属的 retry_attempt，唯一约束改为 (record_id, attempt, seq)，
+--       查询只返回记录当前执行次数的输出。
+-- ===================================================================
+
+ALTER TABLE "public"."instance_task_outputs"
+    ADD COLUMN IF NOT EXISTS "attempt" int4 NOT NULL DEFAULT 0;
+
+COMMENT ON COLUMN "public"."instance_task_outputs"."attempt" IS '所属执行次数，对应执行记录的 retry_attempt';
+
+DROP INDEX IF EXISTS "public"."uk_instance_task_outputs_record_seq";
+CREATE UNIQUE INDEX IF NOT EXISTS "uk_instance_task_outputs_record_attempt_seq"
+    ON "public"."instance_task_outputs" ("record_id", "attempt", "seq");
@@ -0,0 +1,269 @@
+use crate::agent_auth::models::AgentAuthConfig;
+use crate::entities::{applications, instance_credentials, instances};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::HttpRequest;
+use chrono::Utc;
+use rand::RngCore;
+use sea_orm::prelude::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sha2::{Digest, Sha256};
+
+/// Agent 携带实例令牌的请求头
+pub const AGENT_TOKEN_HEADER: &str = "X-Agent-Token";
+
+/// 实例令牌前缀
+pub const TOKEN_PREFIX: &str = "mht_";
+
+/// 注册密钥前缀
+pub const ENROLLMENT_SECRET_PREFIX: &str = "mhe_";
+
+/// 生成带前缀的随机密钥（32 字节随机数，十六进制编码）
+pub fn generate_secret(prefix: &str) -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", prefix, to_hex(&bytes))
+}
+
+/// 计算密钥的 SHA-256 摘要（十六进制）
+pub fn hash_secret(secret: &str) -> String {
+    to_hex(&Sha256::digest(secret.as_bytes()))
+}
+
+/// 截取密钥前缀用于界面展示
+pub fn display_prefix(secret: &str) -> String {
+    secret.chars().take(12).collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 解析应用的 auth_config，格式不合法时视为空配置
+pub fn parse_auth_config(value: &serde_json::Value) -> AgentAuthConfig {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+/// 为实例签发新令牌，同时吊销该实例在此应用下的所有有效令牌
+///
+/// 返回 (明文令牌, 凭证记录)，明文令牌只在此处出现一次。
+pub async fn issue_credential(
+    db: &DatabaseConnection,
+    application_id: &str,
+    agent_instance_id: &str,
+    enrollment_secret_id: Option<String>,
+    revoked_reason: &str,
+) -> Result<(String, instance_credentials::Model), ApiError> {
+    revoke_active_credentials(db, Some(application_id), agent_instance_id, revoked_reason).await?;
+
+    let token = generate_secret(TOKEN_PREFIX);
+    let now = Utc::now();
+    let credential = instance_credentials::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        application_id: Set(application_id.to_string()),
+        agent_instance_id: Set(agent_instance_id.to_string()),
+        token_hash: Set(hash_secret(&token)),
+        token_prefix: Set(display_prefix(&token)),
+        enrollment_secret_id: Set(enrollment_secret_id),
+        issued_at: Set(now.into()),
+        last_used_at: Set(None),
+        revoked_at: Set(None),
+        revoked_reason: Set(None),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    }
+    .insert(db)
+    .await?;
+
+    Ok((token, credential))
+}
+
+/// 吊销实例的所有有效令牌，返回吊销数量
+pub async fn revoke_active_credentials(
+    db: &DatabaseConnection,
+    application_id: Option<&str>,
+    agent_instance_id: &str,
+    revoked_reason: &str,
+) -> Result<u64, ApiError> {
+    let mut update = instance_credentials::Entity::update_many()
+        .col_expr(
+            instance_credentials::Column::RevokedAt,
+            Expr::value(Some(chrono::DateTime::<chrono::FixedOffset>::from(
+                Utc::now(),
+            ))),
+        )
+        .col_expr(
+            instance_credentials::Column::RevokedReason,
+            Expr::value(Some(revoked_reason.to_string())),
+        )
+        .filter(instance_credentials::Column::AgentInstanceId.eq(agent_instance_id))
+        .filter(instance_credentials::Column::RevokedAt.is_null());
+
+    if let Some(application_id) = application_id {
+        update = update.filter(instance_credentials::Column::ApplicationId.eq(application_id));
+    }
+
+    let result = update.exec(db).await?;
+    Ok(result.rows_affected)
+}
+
+/// 从请求头中读取实例令牌
+pub fn get_agent_token_from_request(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(AGENT_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// 未携带令牌的请求是否必须拒绝：应用开启强制校验，或实例已签发过令牌
+pub fn token_required(config: &AgentAuthConfig, credential_issued: bool) -> bool {
+    config.require_agent_token || credential_issued
+}
+
+/// 实例在应用下是否签发过令牌（含已吊销的令牌）
+async fn credential_issued(
+    db: &DatabaseConnection,
+    application_id: &str,
+    agent_instance_id: &str,
+) -> Result<bool, ApiError> {
+    let credential = instance_credentials::Entity::find()
+        .filter(instance_credentials::Column::ApplicationId.eq(application_id))
+        .filter(instance_credentials::Column::AgentInstanceId.eq(agent_instance_id))
+        .one(db)
+        .await?;
+    Ok(credential.is_some())
+}
+
+/// 校验开放接口的实例令牌
+///
+/// - 请求携带令牌时必须有效，且绑定的应用与实例均需与本次操作一致；
+/// - 未携带令牌时，应用开启 require_agent_token 或实例已签发过令牌则拒绝请求，
+///   仅尚未注册凭证的实例在未强制的应用下可匿名访问（返回 None）。
+pub async fn verify_agent_token(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    application: &applications::Model,
+    agent_instance_id: &str,
+) -> Result<Option<instance_credentials::Model>, ApiError> {
+    let token = match get_agent_token_from_request(req) {
+        Some(token) => token,
+        None => {
+            let issued = credential_issued(db, &application.id, agent_instance_id).await?;
+            if token_required(&parse_auth_config(&application.auth_config), issued) {
+                return Err(ApiError::Unauthorized("Missing agent token".to_string()));
+            }
+            return Ok(None);
+        }
+    };
+
+    let credential = instance_credentials::Entity::find()
+        .filter(instance_credentials::Column::TokenHash.eq(hash_secret(&token)))
+        .filter(instance_credentials::Column::RevokedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid or revoked agent token".to_string()))?;
+
+    if credential.application_id != application.id
+        || credential.agent_instance_id != agent_instance_id
+    {
+        return Err(ApiError::Forbidden(
+            "Agent token does not match the target instance".to_string(),
+        ));
+    }
+
+    let mut active: instance_credentials::ActiveModel = credential.clone().into();
+    active.last_used_at = Set(Some(Utc::now().into()));
+    active.updated_at = Set(Utc::now().into());
+    active.update(db).await?;
+
+    Ok(Some(credential))
+}
+
+/// 按实例记录校验实例令牌（实例所属应用决定是否强制）
+pub async fn verify_agent_token_for_instance(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    instance: &instances::Model,
+) -> Result<Option<instance_credentials::Model>, ApiError> {
+    let application = applications::Entity::find_by_id(&instance.application_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Application not found".to_string()))?;
+
+    let agent_instance_id = instance.agent_instance_id.clone().unwrap_or_default();
+    verify_agent_token(db, req, &application, &agent_instance_id).await
+}
+
+/// 将认证配置写回 auth_config，保留其中与 Agent 认证无关的字段
+pub fn merge_auth_config(
+    original: &serde_json::Value,
+    config: &AgentAuthConfig,
+) -> serde_json::Value {
+    let mut object = original.as_object().cloned().unwrap_or_default();
+    object.insert(
+        "require_agent_token".to_string(),
+        serde_json::json!(config.require_agent_token),
+    );
+    object.insert(
+        "enrollment_secrets".to_string(),
+        serde_json::json!(config.enrollment_secrets),
+    );
+    serde_json::Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_hash_secret() {
+        let token = generate_secret(TOKEN_PREFIX);
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(token.len(), TOKEN_PREFIX.len() + 64);
+        assert_ne!(token, generate_secret(TOKEN_PREFIX));
+
+        assert_eq!(
+            hash_secret("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(hash_secret(&token), hash_secret(&token));
+        assert_ne!(hash_secret(&token), hash_secret(&format!("{}x", token)));
+        assert_eq!(display_prefix(&token), &token[..12]);
+    }
+
+    #[test]
+    fn test_token_required() {
+        let optional = AgentAuthConfig::default();
+        let enforced = AgentAuthConfig {
+            require_agent_token: true,
+            ..Default::default()
+        };
+        assert!(!token_required(&optional, false));
+        assert!(token_required(&optional, true));
+        assert!(token_required(&enforced, false));
+        assert!(token_required(&enforced, true));
+    }
+
+    #[test]
+    fn test_get_agent_token_from_request() {
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((AGENT_TOKEN_HEADER, "  mht_abc  "))
+            .to_http_request();
+        assert_eq!(
+            get_agent_token_from_request(&req),
+            Some("mht_abc".to_string())
+        );
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((AGENT_TOKEN_HEADER, " "))
+            .to_http_request();
+        assert_eq!(get_agent_token_from_request(&req), None);
+        assert_eq!(
+            get_agent_token_from_request(
+                &actix_web::test::TestRequest::default().to_http_request()
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,609 @@
+use crate::agent_auth::credentials::{
+    display_prefix, generate_secret, get_agent_token_from_request, hash_secret, issue_credential,
+    merge_auth_config, parse_auth_config, revoke_active_credentials, ENROLLMENT_SECRET_PREFIX,
+};
+use crate::agent_auth::models::{
+    AgentAuthPolicyUpdateRequest, AgentEnrollRequest, AgentTokenResponse, AgentTokenRotateRequest,
+    EnrollmentSecret, EnrollmentSecretCreateRequest, EnrollmentSecretCreateResponse,
+    EnrollmentSecretListResponse, EnrollmentSecretResponse, InstanceCredentialListResponse,
+    InstanceCredentialResponse, InstanceCredentialRevokeResponse,
+};
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::{applications, instance_credentials, instances};
+use crate::members::request_scope;
+use crate::permissions::handlers::get_user_permission_by_name;
+use crate::shared::enums::Status;
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn to_secret_response(secret: &EnrollmentSecret) -> EnrollmentSecretResponse {
+    EnrollmentSecretResponse {
+        id: secret.id.clone(),
+        name: secret.name.clone(),
+        secret_prefix: secret.secret_prefix.clone(),
+        created_by: secret.created_by.clone(),
+        created_at: secret.created_at.clone(),
+        revoked_at: secret.revoked_at.clone(),
+    }
+}
+
+fn to_credential_response(model: instance_credentials::Model) -> InstanceCredentialResponse {
+    InstanceCredentialResponse {
+        id: model.id,
+        application_id: model.application_id,
+        agent_instance_id: model.agent_instance_id,
+        token_prefix: model.token_prefix,
+        enrollment_secret_id: model.enrollment_secret_id,
+        issued_at: model.issued_at.to_rfc3339(),
+        last_used_at: model.last_used_at.map(|t| t.to_rfc3339()),
+        revoked_at: model.revoked_at.map(|t| t.to_rfc3339()),
+        revoked_reason: model.revoked_reason,
+    }
+}
+
+async fn find_application(
+    db: &DatabaseConnection,
+    application_id: &str,
+) -> Result<applications::Model, ApiError> {
+    applications::Entity::find_by_id(application_id)
+        .filter(applications::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Application not found".to_string()))
+}
+
+async fn require_permission(
+    db: &DatabaseConnection,
+    user_id: &str,
+    permission: &str,
+    message: &str,
+) -> Result<(), ApiError> {
+    if get_user_permission_by_name(user_id, permission, db)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::Forbidden(message.to_string()));
+    }
+    Ok(())
+}
+
+/// 保存应用的 Agent 认证配置
+async fn save_auth_config(
+    db: &DatabaseConnection,
+    application: applications::Model,
+    config: &crate::agent_auth::models::AgentAuthConfig,
+    user_id: &str,
+) -> Result<applications::Model, ApiError> {
+    let auth_config = merge_auth_config(&application.auth_config, config);
+    let revision = application.revision;
+    let mut active: applications::ActiveModel = application.into();
+    active.auth_config = Set(auth_config);
+    active.updated_by = Set(user_id.to_string());
+    active.revision = Set(revision + 1);
+    active.updated_at = Set(Utc::now().into());
+    Ok(active.update(db).await?)
+}
+
+// ===================================================================
+// 管理接口（需要认证）
+// ===================================================================
+
+/// GET /api/applications/{id}/enrollment-secrets
+/// 查询应用的注册密钥
+#[utoipa::path(
+    get,
+    path = "/api/applications/{id}/enrollment-secrets",
+    params(("id" = String, Path, description = "Application ID")),
+    responses(
+        (status = 200, description = "List enrollment secrets successfully", body = EnrollmentSecretListResponse),
+        (status = 404, description = "Application not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Auth"
+)]
+pub async fn get_enrollment_secrets(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let _user_id = get_user_id_from_request(&req)?;
+    let application = find_application(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+    let config = parse_auth_config(&application.auth_config);
+
+    let response = EnrollmentSecretListResponse {
+        require_agent_token: config.require_agent_token,
+        data: config
+            .enrollment_secrets
+            .iter()
+            .map(to_secret_response)
+            .collect(),
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/applications/{id}/enrollment-secrets
+/// 创建注册密钥，明文只在响应中返回一次
+#[utoipa::path(
+    post,
+    path = "/api/applications/{id}/enrollment-secrets",
+    params(("id" = String, Path, description = "Application ID")),
+    request_body = EnrollmentSecretCreateRequest,
+    responses(
+        (status = 200, description = "Enrollment secret created", body = EnrollmentSecretCreateResponse),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Application not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Auth"
+)]
+pub async fn create_enrollment_secret(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<EnrollmentSecretCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    require_permission(
+        &db,
+        &user_id,
+        "applications.edit",
+        "没有权限管理应用注册密钥",
+    )
+    .await?;
+
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::ValidationError("密钥名称不能为空".to_string()));
+    }
+
+    let application = find_application(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+    let mut config = parse_auth_config(&application.auth_config);
+
+    let secret = generate_secret(ENROLLMENT_SECRET_PREFIX);
+    let entry = EnrollmentSecret {
+        id: generate_snowflake_id(),
+        name: name.to_string(),
+        secret_hash: hash_secret(&secret),
+        secret_prefix: display_prefix(&secret),
+        created_by: user_id.clone(),
+        created_at: Utc::now().to_rfc3339(),
+        revoked_at: None,
+    };
+    config.enrollment_secrets.push(entry.clone());
+    save_auth_config(&db, application, &config, &user_id).await?;
+
+    // 审计记录：创建注册密钥（不记录明文与摘要）
+    let after = serde_json::json!({
+        "enrollment_secret_id": entry.id,
+        "name": entry.name,
+        "secret_prefix": entry.secret_prefix,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "applications",
+        "update",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+
+    let response = EnrollmentSecretCreateResponse {
+        secret,
+        data: to_secret_response(&entry),
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// DELETE /api/applications/{id}/enrollment-secrets/{secret_id}
+/// 吊销注册密钥；已签发的实例令牌不受影响，需单独吊销
+#[utoipa::path(
+    delete,
+    path = "/api/applications/{id}/enrollment-secrets/{secret_id}",
+    params(
+        ("id" = String, Path, description = "Application ID"),
+        ("secret_id" = String, Path, description = "Enrollment secret ID")
+    ),
+    responses(
+        (status = 200, description = "Enrollment secret revoked", body = EnrollmentSecretResponse),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Enrollment secret not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Auth"
+)]
+pub async fn revoke_enrollment_secret(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    require_permission(
+        &db,
+        &user_id,
+        "applications.edit",
+        "没有权限管理应用注册密钥",
+    )
+    .await?;
+
+    let (application_id, secret_id) = path.into_inner();
+    let application = find_application(&db, &application_id).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+    let mut config = parse_auth_config(&application.auth_config);
+
+    let entry = config
+        .enrollment_secrets
+        .iter_mut()
+        .find(|s| s.id == secret_id)
+        .ok_or_else(|| ApiError::NotFound("Enrollment secret not found".to_string()))?;
+    if entry.revoked_at.is_none() {
+        entry.revoked_at = Some(Utc::now().to_rfc3339());
+    }
+    let response = to_secret_response(entry);
+    save_auth_config(&db, application, &config, &user_id).await?;
+
+    let after = serde_json::json!({
+        "enrollment_secret_id": response.id,
+        "revoked_at": response.revoked_at,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "applications",
+        "update",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// PUT /api/applications/{id}/agent-auth
+/// 设置应用是否强制开放接口携带实例令牌
+#[utoipa::path(
+    put,
+    path = "/api/applications/{id}/agent-auth",
+    params(("id" = String, Path, description = "Application ID")),
+    request_body = AgentAuthPolicyUpdateRequest,
+    responses(
+        (status = 200, description = "Agent auth policy updated", body = EnrollmentSecretListResponse),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Application not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Auth"
+)]
+pub async fn update_agent_auth_policy(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<AgentAuthPolicyUpdateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    require_permission(
+        &db,
+        &user_id,
+        "applications.edit",
+        "没有权限修改应用认证策略",
+    )
+    .await?;
+
+    let application = find_application(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+    let mut config = parse_auth_config(&application.auth_config);
+    let before = serde_json::json!({ "require_agent_token": config.require_agent_token });
+    config.require_agent_token = body.require_agent_token;
+    save_auth_config(&db, application, &config, &user_id).await?;
+
+    let after = serde_json::json!({ "require_agent_token": config.require_agent_token });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "applications",
+        "update",
+        &req,
+        Some(before),
+        Some(after),
+    )
+    .await;
+
+    let response = EnrollmentSecretListResponse {
+        require_agent_token: config.require_agent_token,
+        data: config
+            .enrollment_secrets
+            .iter()
+            .map(to_secret_response)
+            .collect(),
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// GET /api/instances/{id}/credentials
+/// 查询实例的令牌签发记录
+#[utoipa::path(
+    get,
+    path = "/api/instances/{id}/credentials",
+    params(("id" = String, Path, description = "Instance ID")),
+    responses(
+        (status = 200, description = "List instance credentials successfully", body = InstanceCredentialListResponse),
+        (status = 404, description = "Instance not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Auth"
+)]
+pub async fn get_instance_credentials(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let _user_id = get_user_id_from_request(&req)?;
+    let instance = find_instance(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&instance.application_id)?;
+    let agent_instance_id = instance.agent_instance_id.unwrap_or_default();
+
+    let credentials = instance_credentials::Entity::find()
+        .filter(instance_credentials::Column::ApplicationId.eq(&instance.application_id))
+        .filter(instance_credentials::Column::AgentInstanceId.eq(&agent_instance_id))
+        .order_by_desc(instance_credentials::Column::IssuedAt)
+        .all(&**db)
+        .await?;
+
+    let response = InstanceCredentialListResponse {
+        data: credentials
+            .into_iter()
+            .map(to_credential_response)
+            .collect(),
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/instances/{id}/credentials/revoke
+/// 吊销实例的全部有效令牌，Agent 需使用注册密钥重新注册
+#[utoipa::path(
+    post,
+    path = "/api/instances/{id}/credentials/revoke",
+    params(("id" = String, Path, description = "Instance ID")),
+    responses(
+        (status = 200, description = "Instance credentials revoked", body = InstanceCredentialRevokeResponse),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Instance not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Auth"
+)]
+pub async fn revoke_instance_credentials(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    require_permission(&db, &user_id, "instances.edit", "没有权限吊销实例令牌").await?;
+
+    let instance = find_instance(&db, &path.into_inner()).await?;
+    let agent_instance_id = instance.agent_instance_id.clone().unwrap_or_default();
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&instance.application_id)?;
+    let revoked_count = revoke_active_credentials(
+        &db,
+        Some(&instance.application_id),
+        &agent_instance_id,
+        "revoked",
+    )
+    .await?;
+
+    let after = serde_json::json!({
+        "instance_id": instance.id,
+        "agent_instance_id": agent_instance_id,
+        "revoked_count": revoked_count,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "instance_credentials",
+        "update",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+
+    let response = InstanceCredentialRevokeResponse {
+        revoked_count,
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+async fn find_instance(
+    db: &DatabaseConnection,
+    instance_id: &str,
+) -> Result<instances::Model, ApiError> {
+    instances::Entity::find_by_id(instance_id)
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("实例不存在".to_string()))
+}
+
+// ===================================================================
+// 开放接口（使用注册密钥或实例令牌认证）
+// ===================================================================
+
+/// POST /api/open/instances/enroll
+/// Agent 使用注册密钥换取实例令牌；重复注册会吊销该实例之前的令牌
+#[utoipa::path(
+    post,
+    path = "/api/open/instances/enroll",
+    request_body = AgentEnrollRequest,
+    responses(
+        (status = 200, description = "Agent enrolled", body = AgentTokenResponse),
+        (status = 401, description = "Invalid enrollment secret"),
+        (status = 403, description = "Instance disabled or belongs to another application"),
+        (status = 404, description = "Application not found")
+    ),
+    tag = "Agent Auth"
+)]
+pub async fn enroll_agent(
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<AgentEnrollRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if request.application_code.trim().is_empty() || request.agent_instance_id.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "application_code and agent_instance_id cannot be empty".to_string(),
+        ));
+    }
+
+    let application = applications::Entity::find()
+        .filter(applications::Column::Code.eq(&request.application_code))
+        .filter(applications::Column::DeletedAt.is_null())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Application with code '{}' not found",
+                request.application_code
+            ))
+        })?;
+    if application.status != Status::Active {
+        return Err(ApiError::Forbidden("Application is disabled".to_string()));
+    }
+
+    // 校验注册密钥
+    let config = parse_auth_config(&application.auth_config);
+    let secret_hash = hash_secret(request.enrollment_secret.trim());
+    let secret = config
+        .enrollment_secrets
+        .iter()
+        .find(|s| s.revoked_at.is_none() && s.secret_hash == secret_hash)
+        .ok_or_else(|| ApiError::Unauthorized("Invalid enrollment secret".to_string()))?;
+
+    // 已存在的实例必须属于同一应用且处于启用状态
+    let instance = instances::Entity::find()
+        .filter(instances::Column::AgentInstanceId.eq(&request.agent_instance_id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(&**db)
+        .await?;
+    if let Some(instance) = instance {
+        if instance.application_id != application.id {
+            return Err(ApiError::Forbidden(
+                "Instance belongs to another application".to_string(),
+            ));
+        }
+        if instance.status != Status::Active {
+            return Err(ApiError::Forbidden("Instance is disabled".to_string()));
+        }
+    }
+
+    let (token, credential) = issue_credential(
+        &db,
+        &application.id,
+        &request.agent_instance_id,
+        Some(secret.id.clone()),
+        "re_enrolled",
+    )
+    .await?;
+
+    log::info!(
+        "[enroll_agent] Issued token {} for instance {} of application {}",
+        credential.token_prefix,
+        credential.agent_instance_id,
+        application.code
+    );
+
+    let response = AgentTokenResponse {
+        status: "success".to_string(),
+        token,
+        token_prefix: credential.token_prefix,
+        agent_instance_id: credential.agent_instance_id,
+        issued_at: credential.issued_at.to_rfc3339(),
+        timestamp: now_secs(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/open/instances/token/rotate
+/// Agent 使用当前令牌换取新令牌，旧令牌立即失效
+#[utoipa::path(
+    post,
+    path = "/api/open/instances/token/rotate",
+    request_body = AgentTokenRotateRequest,
+    responses(
+        (status = 200, description = "Token rotated", body = AgentTokenResponse),
+        (status = 401, description = "Invalid or revoked agent token"),
+        (status = 403, description = "Token does not match the instance")
+    ),
+    tag = "Agent Auth"
+)]
+pub async fn rotate_agent_token(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<AgentTokenRotateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let token = get_agent_token_from_request(&req)
+        .ok_or_else(|| ApiError::Unauthorized("Missing agent token".to_string()))?;
+
+    let current = instance_credentials::Entity::find()
+        .filter(instance_credentials::Column::TokenHash.eq(hash_secret(&token)))
+        .filter(instance_credentials::Column::RevokedAt.is_null())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid or revoked agent token".to_string()))?;
+
+    if current.agent_instance_id != request.agent_instance_id {
+        return Err(ApiError::Forbidden(
+            "Agent token does not match the target instance".to_string(),
+        ));
+    }
+
+    let (token, credential) = issue_credential(
+        &db,
+        &current.application_id,
+        &current.agent_instance_id,
+        current.enrollment_secret_id.clone(),
+        "rotated",
+    )
+    .await?;
+
+    let response = AgentTokenResponse {
+        status: "success".to_string(),
+        token,
+        token_prefix: credential.token_prefix,
+        agent_instance_id: credential.agent_instance_id,
+        issued_at: credential.issued_at.to_rfc3339(),
+        timestamp: now_secs(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
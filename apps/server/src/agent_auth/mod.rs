@@ -0,0 +1,7 @@
+pub mod credentials;
+pub mod handlers;
+pub mod models;
+pub mod routes;
+
+pub use credentials::{verify_agent_token, verify_agent_token_for_instance, AGENT_TOKEN_HEADER};
+pub use routes::{agent_auth_routes, open_agent_auth_routes};
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// ===================================================================
+// 应用 auth_config 结构
+// ===================================================================
+
+/// 应用认证配置（存储于 applications.auth_config）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentAuthConfig {
+    /// 是否强制要求开放接口携带实例令牌
+    #[serde(default)]
+    pub require_agent_token: bool,
+    /// 注册密钥列表（仅保存摘要）
+    #[serde(default)]
+    pub enrollment_secrets: Vec<EnrollmentSecret>,
+}
+
+/// 注册密钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrollmentSecret {
+    pub id: String,
+    pub name: String,
+    pub secret_hash: String,
+    pub secret_prefix: String,
+    pub created_by: String,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<String>,
+}
+
+// ===================================================================
+// 管理接口请求/响应模型
+// ===================================================================
+
+/// 创建注册密钥请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnrollmentSecretCreateRequest {
+    pub name: String,
+}
+
+/// 注册密钥响应（不包含摘要）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnrollmentSecretResponse {
+    pub id: String,
+    pub name: String,
+    pub secret_prefix: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+/// 创建注册密钥响应，明文密钥仅返回一次
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnrollmentSecretCreateResponse {
+    pub secret: String,
+    pub data: EnrollmentSecretResponse,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 注册密钥列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EnrollmentSecretListResponse {
+    pub require_agent_token: bool,
+    pub data: Vec<EnrollmentSecretResponse>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 更新应用 Agent 认证策略请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentAuthPolicyUpdateRequest {
+    pub require_agent_token: bool,
+}
+
+/// 实例凭证响应（不包含令牌摘要）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InstanceCredentialResponse {
+    pub id: String,
+    pub application_id: String,
+    pub agent_instance_id: String,
+    pub token_prefix: String,
+    pub enrollment_secret_id: Option<String>,
+    pub issued_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub revoked_reason: Option<String>,
+}
+
+/// 实例凭证列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InstanceCredentialListResponse {
+    pub data: Vec<InstanceCredentialResponse>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 吊销实例凭证响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InstanceCredentialRevokeResponse {
+    pub revoked_count: u64,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+// ===================================================================
+// 开放接口请求/响应模型
+// ===================================================================
+
+/// Agent 注册请求：使用注册密钥换取实例令牌
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentEnrollRequest {
+    pub application_code: String,
+    pub agent_instance_id: String,
+    pub enrollment_secret: String,
+}
+
+/// Agent 令牌轮换请求（旧令牌通过 X-Agent-Token 头传递）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentTokenRotateRequest {
+    pub agent_instance_id: String,
+}
+
+/// Agent 令牌响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentTokenResponse {
+    pub status: String,
+    pub token: String,
+    pub token_prefix: String,
+    pub agent_instance_id: String,
+    pub issued_at: String,
+    pub timestamp: u64,
+}
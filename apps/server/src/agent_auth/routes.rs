@@ -0,0 +1,39 @@
+use super::handlers;
+use actix_web::web;
+
+pub fn agent_auth_routes(cfg: &mut web::ServiceConfig) {
+    // 注册密钥与实例令牌管理（需要认证）
+    cfg.route(
+        "/applications/{id}/enrollment-secrets",
+        web::get().to(handlers::get_enrollment_secrets),
+    )
+    .route(
+        "/applications/{id}/enrollment-secrets",
+        web::post().to(handlers::create_enrollment_secret),
+    )
+    .route(
+        "/applications/{id}/enrollment-secrets/{secret_id}",
+        web::delete().to(handlers::revoke_enrollment_secret),
+    )
+    .route(
+        "/applications/{id}/agent-auth",
+        web::put().to(handlers::update_agent_auth_policy),
+    )
+    .route(
+        "/instances/{id}/credentials",
+        web::get().to(handlers::get_instance_credentials),
+    )
+    .route(
+        "/instances/{id}/credentials/revoke",
+        web::post().to(handlers::revoke_instance_credentials),
+    );
+}
+
+pub fn open_agent_auth_routes(cfg: &mut web::ServiceConfig) {
+    // Agent 注册与令牌轮换（开放接口，使用注册密钥/实例令牌认证）
+    cfg.route("/enroll", web::post().to(handlers::enroll_agent))
+        .route(
+            "/token/rotate",
+            web::post().to(handlers::rotate_agent_token),
+        );
+}
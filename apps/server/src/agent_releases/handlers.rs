@@ -0,0 +1,663 @@
+use crate::agent_auth::verify_agent_token_for_instance;
+use crate::agent_releases::models::{
+    compare_versions, normalize_platform, validate_signature, validate_version,
+    AgentReleaseCreateRequest, AgentReleaseDownloadQuery, AgentReleaseListQuery,
+    AgentReleaseListResponse, AgentReleaseManifest, AgentReleaseResolveQuery, AgentReleaseResponse,
+    AgentTargetVersionUpdateRequest, AgentUpgradeRequest, AgentVersionCount,
+    AgentVersionDistributionResponse, DEFAULT_HEALTH_TIMEOUT_SECONDS,
+};
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::{agent_releases, applications, files, instances};
+use crate::instance_tasks::dispatch::TaskDispatchHub;
+use crate::instance_tasks::handlers::insert_task;
+use crate::instance_tasks::models::{TaskCreateRequest, TaskResponse};
+use crate::members::request_scope;
+use crate::shared::enums::{AgentType, OnlineStatus, TaskType};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+async fn find_application(
+    db: &DatabaseConnection,
+    application_id: &str,
+) -> Result<applications::Model, ApiError> {
+    applications::Entity::find_by_id(application_id)
+        .filter(applications::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Application not found".to_string()))
+}
+
+/// 查询未删除的发布包，按版本从新到旧排序
+async fn list_releases(
+    db: &DatabaseConnection,
+    platform: Option<&str>,
+    version: Option<&str>,
+) -> Result<Vec<agent_releases::Model>, ApiError> {
+    let mut query =
+        agent_releases::Entity::find().filter(agent_releases::Column::DeletedAt.is_null());
+    if let Some(platform) = platform {
+        query = query.filter(agent_releases::Column::Platform.eq(platform));
+    }
+    if let Some(version) = version {
+        query = query.filter(agent_releases::Column::Version.eq(version));
+    }
+    let mut releases = query.all(db).await?;
+    releases.sort_by(|a, b| {
+        compare_versions(&b.version, &a.version).then_with(|| a.platform.cmp(&b.platform))
+    });
+    Ok(releases)
+}
+
+/// 应用当前生效的目标版本：显式配置的目标版本，否则为最新发布版本
+async fn effective_target_version(
+    db: &DatabaseConnection,
+    application: &applications::Model,
+    platform: Option<&str>,
+) -> Result<Option<String>, ApiError> {
+    if let Some(version) = application.agent_target_version.clone() {
+        return Ok(Some(version));
+    }
+    Ok(list_releases(db, platform, None)
+        .await?
+        .into_iter()
+        .next()
+        .map(|r| r.version))
+}
+
+/// 流式计算文件 SHA-256（十六进制）
+async fn file_sha256(path: &Path) -> Result<String, ApiError> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("发布包文件不可读: {}", e)))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .map_err(|e| ApiError::InternalServerError(format!("读取发布包失败: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// ===================================================================
+// 发布包管理（需要认证）
+// ===================================================================
+
+/// GET /api/agent-releases
+/// 查询 Agent 发布包
+#[utoipa::path(
+    get,
+    path = "/api/agent-releases",
+    params(AgentReleaseListQuery),
+    responses(
+        (status = 200, description = "List agent releases successfully", body = AgentReleaseListResponse)
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Releases"
+)]
+pub async fn get_agent_releases(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<AgentReleaseListQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let _user_id = get_user_id_from_request(&req)?;
+    let platform = query
+        .platform
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+        .map(normalize_platform)
+        .transpose()?;
+    let version = query
+        .version
+        .as_deref()
+        .filter(|v| !v.trim().is_empty())
+        .map(validate_version)
+        .transpose()?;
+
+    let releases = list_releases(&db, platform.as_deref(), version.as_deref()).await?;
+    let response = AgentReleaseListResponse {
+        data: releases
+            .into_iter()
+            .map(AgentReleaseResponse::from_entity)
+            .collect(),
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/agent-releases
+/// 登记发布包：引用已上传的文件，服务端计算 SHA-256 与大小
+#[utoipa::path(
+    post,
+    path = "/api/agent-releases",
+    request_body = AgentReleaseCreateRequest,
+    responses(
+        (status = 200, description = "Agent release created", body = AgentReleaseResponse),
+        (status = 400, description = "Bad request or release already exists")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Releases"
+)]
+pub async fn create_agent_release(
+    db: web::Data<DatabaseConnection>,
+    body: web::Json<AgentReleaseCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    request_scope(&db, &req).await?.ensure_unrestricted()?;
+
+    let version = validate_version(&body.version)?;
+    let platform = normalize_platform(&body.platform)?;
+    let signature = body
+        .signature
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(validate_signature)
+        .transpose()?;
+
+    if !list_releases(&db, Some(&platform), Some(&version))
+        .await?
+        .is_empty()
+    {
+        return Err(ApiError::BadRequest(format!(
+            "发布包已存在: {} {}",
+            version, platform
+        )));
+    }
+
+    let file = files::Entity::find_by_id(body.file_id.clone())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+    let file_path = Path::new(&file.file_path);
+    let file_size = tokio::fs::metadata(file_path)
+        .await
+        .map_err(|_| ApiError::BadRequest("发布包文件尚未上传完成".to_string()))?
+        .len() as i64;
+    let sha256 = file_sha256(file_path).await?;
+
+    let release = agent_releases::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        version: Set(version),
+        platform: Set(platform),
+        file_id: Set(file.id),
+        file_size: Set(file_size),
+        sha256: Set(sha256),
+        signature: Set(signature),
+        notes: Set(body.notes.clone().filter(|n| !n.trim().is_empty())),
+        created_by: Set(user_id),
+        created_at: Set(Utc::now().into()),
+        updated_at: Set(Utc::now().into()),
+        deleted_at: Set(None),
+    }
+    .insert(&**db)
+    .await?;
+
+    let response = AgentReleaseResponse::from_entity(release);
+    // 审计记录：登记发布包
+    let after = serde_json::json!({
+        "id": response.id,
+        "version": response.version,
+        "platform": response.platform,
+        "file_id": response.file_id,
+        "sha256": response.sha256,
+        "signed": response.signature.is_some(),
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "agent_releases",
+        "create",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// DELETE /api/agent-releases/{id}
+/// 删除发布包（软删除，安装包文件保留）
+#[utoipa::path(
+    delete,
+    path = "/api/agent-releases/{id}",
+    params(("id" = String, Path, description = "Agent release ID")),
+    responses(
+        (status = 200, description = "Agent release deleted", body = AgentReleaseResponse),
+        (status = 404, description = "Agent release not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Releases"
+)]
+pub async fn delete_agent_release(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let _user_id = get_user_id_from_request(&req)?;
+    request_scope(&db, &req).await?.ensure_unrestricted()?;
+
+    let release = agent_releases::Entity::find_by_id(path.into_inner())
+        .filter(agent_releases::Column::DeletedAt.is_null())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Agent release not found".to_string()))?;
+    let before = serde_json::to_value(&release).ok();
+
+    let mut active: agent_releases::ActiveModel = release.into();
+    active.deleted_at = Set(Some(Utc::now().into()));
+    active.updated_at = Set(Utc::now().into());
+    let deleted = active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "agent_releases",
+        "delete",
+        &req,
+        before,
+        None,
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AgentReleaseResponse::from_entity(deleted)))
+}
+
+// ===================================================================
+// 应用 Agent 版本管理（需要认证）
+// ===================================================================
+
+/// GET /api/applications/{id}/agent-versions
+/// 查询应用下 Agent 的版本分布
+#[utoipa::path(
+    get,
+    path = "/api/applications/{id}/agent-versions",
+    params(("id" = String, Path, description = "Application ID")),
+    responses(
+        (status = 200, description = "Agent version distribution", body = AgentVersionDistributionResponse),
+        (status = 404, description = "Application not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Releases"
+)]
+pub async fn get_agent_versions(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let _user_id = get_user_id_from_request(&req)?;
+    let application = find_application(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+
+    let latest_version = list_releases(&db, None, None)
+        .await?
+        .into_iter()
+        .next()
+        .map(|r| r.version);
+    let target = application
+        .agent_target_version
+        .clone()
+        .or_else(|| latest_version.clone());
+
+    let instances = instances::Entity::find()
+        .filter(instances::Column::ApplicationId.eq(&application.id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .all(&**db)
+        .await?;
+
+    let mut counts: BTreeMap<(Option<String>, Option<String>), (u64, u64)> = BTreeMap::new();
+    for instance in instances {
+        let agent_type = instance
+            .agent_type
+            .as_ref()
+            .and_then(|t| serde_json::to_value(t).ok())
+            .and_then(|v| v.as_str().map(String::from));
+        let entry = counts
+            .entry((agent_type, instance.agent_version.clone()))
+            .or_default();
+        entry.0 += 1;
+        if instance.online_status == OnlineStatus::Online {
+            entry.1 += 1;
+        }
+    }
+    let mut data: Vec<AgentVersionCount> = counts
+        .into_iter()
+        .map(
+            |((agent_type, agent_version), (total, online))| AgentVersionCount {
+                is_target: agent_type.as_deref() == Some("rust_agent")
+                    && agent_version.is_some()
+                    && agent_version == target,
+                agent_type,
+                agent_version,
+                total,
+                online,
+            },
+        )
+        .collect();
+    data.sort_by(|a, b| {
+        a.agent_type
+            .cmp(&b.agent_type)
+            .then_with(|| match (&a.agent_version, &b.agent_version) {
+                (Some(x), Some(y)) => compare_versions(y, x),
+                (x, y) => y.is_some().cmp(&x.is_some()),
+            })
+    });
+
+    let response = AgentVersionDistributionResponse {
+        application_id: application.id,
+        target_version: application.agent_target_version,
+        latest_version,
+        data,
+        timestamp: now_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// PUT /api/applications/{id}/agent-target-version
+/// 设置应用的 Agent 目标版本，为空表示跟随最新版本
+#[utoipa::path(
+    put,
+    path = "/api/applications/{id}/agent-target-version",
+    params(("id" = String, Path, description = "Application ID")),
+    request_body = AgentTargetVersionUpdateRequest,
+    responses(
+        (status = 200, description = "Agent target version updated", body = AgentVersionDistributionResponse),
+        (status = 400, description = "Release not found for version"),
+        (status = 404, description = "Application not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Releases"
+)]
+pub async fn update_agent_target_version(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<AgentTargetVersionUpdateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let application = find_application(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+
+    let target_version = body
+        .target_version
+        .as_deref()
+        .filter(|v| !v.trim().is_empty())
+        .map(validate_version)
+        .transpose()?;
+    if let Some(version) = &target_version {
+        if list_releases(&db, None, Some(version)).await?.is_empty() {
+            return Err(ApiError::BadRequest(format!(
+                "版本 {} 没有登记发布包",
+                version
+            )));
+        }
+    }
+
+    let before = serde_json::json!({
+        "id": application.id,
+        "agent_target_version": application.agent_target_version,
+    });
+    let application_id = application.id.clone();
+    let revision = application.revision;
+    let mut active: applications::ActiveModel = application.into();
+    active.agent_target_version = Set(target_version.clone());
+    active.updated_by = Set(user_id);
+    active.revision = Set(revision + 1);
+    active.updated_at = Set(Utc::now().into());
+    active.update(&**db).await?;
+
+    let after = serde_json::json!({
+        "id": application_id,
+        "agent_target_version": target_version,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "applications",
+        "update",
+        &req,
+        Some(before),
+        Some(after),
+    )
+    .await;
+
+    get_agent_versions(db, web::Path::from(application_id), req).await
+}
+
+/// POST /api/applications/{id}/agent-upgrade
+/// 向应用下版本不一致的 Rust Agent 下发升级任务
+#[utoipa::path(
+    post,
+    path = "/api/applications/{id}/agent-upgrade",
+    params(("id" = String, Path, description = "Application ID")),
+    request_body = AgentUpgradeRequest,
+    responses(
+        (status = 200, description = "Agent upgrade task created", body = TaskResponse),
+        (status = 400, description = "No release or no instance to upgrade"),
+        (status = 404, description = "Application not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Agent Releases"
+)]
+pub async fn create_agent_upgrade(
+    db: web::Data<DatabaseConnection>,
+    hub: web::Data<TaskDispatchHub>,
+    path: web::Path<String>,
+    body: web::Json<AgentUpgradeRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let application = find_application(&db, &path.into_inner()).await?;
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&application.id)?;
+
+    let version = match body.version.as_deref().filter(|v| !v.trim().is_empty()) {
+        Some(version) => Some(validate_version(version)?),
+        None => effective_target_version(&db, &application, None).await?,
+    }
+    .ok_or_else(|| ApiError::BadRequest("尚未登记任何 Agent 发布包".to_string()))?;
+    let platforms: Vec<String> = list_releases(&db, None, Some(&version))
+        .await?
+        .into_iter()
+        .map(|r| r.platform)
+        .collect();
+    if platforms.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "版本 {} 没有登记发布包",
+            version
+        )));
+    }
+
+    let mut query = instances::Entity::find()
+        .filter(instances::Column::ApplicationId.eq(&application.id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .filter(instances::Column::AgentType.eq(AgentType::RustAgent));
+    if !body.instance_ids.is_empty() {
+        query = query.filter(instances::Column::Id.is_in(body.instance_ids.clone()));
+    }
+    let targets: Vec<String> = query
+        .all(&**db)
+        .await?
+        .into_iter()
+        .filter(|i| i.agent_version.as_deref() != Some(version.as_str()))
+        .map(|i| i.id)
+        .collect();
+    if targets.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "没有需要升级到 {} 的 Rust Agent 实例",
+            version
+        )));
+    }
+
+    let health_timeout_seconds = body
+        .health_timeout_seconds
+        .unwrap_or(DEFAULT_HEALTH_TIMEOUT_SECONDS)
+        .clamp(10, 3600);
+    let request = TaskCreateRequest {
+        task_name: format!("Agent 升级到 {}", version),
+        task_type: TaskType::AgentUpgrade,
+        target_instances: targets,
+        target_selector: None,
+        rollout_strategy: body.rollout_strategy.clone(),
+        task_content: serde_json::json!({
+            "version": version,
+            "health_timeout_seconds": health_timeout_seconds,
+        }),
+        priority: None,
+        timeout_seconds: body.timeout_seconds,
+        retry_count: Some(0),
+        application_id: Some(application.id.clone()),
+    };
+    let saved_task = insert_task(&db, &hub, &request, &user_id).await?;
+
+    let response = TaskResponse::from_entity(saved_task);
+    // 审计记录：创建升级任务
+    let after = serde_json::json!({
+        "id": response.id,
+        "task_name": response.task_name,
+        "task_type": response.task_type,
+        "application_id": response.application_id,
+        "version": version,
+        "created_at": response.created_at,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "instance_tasks",
+        "create",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// ===================================================================
+// 开放接口（Agent 使用实例令牌认证）
+// ===================================================================
+
+async fn find_agent_instance(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    agent_instance_id: &str,
+) -> Result<instances::Model, ApiError> {
+    let instance = instances::Entity::find()
+        .filter(instances::Column::AgentInstanceId.eq(agent_instance_id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Instance {} not found", agent_instance_id)))?;
+    verify_agent_token_for_instance(db, req, &instance).await?;
+    Ok(instance)
+}
+
+/// GET /api/open/instances/agent-release
+/// Agent 查询适用于自身平台的发布包
+#[utoipa::path(
+    get,
+    path = "/api/open/instances/agent-release",
+    params(AgentReleaseResolveQuery),
+    responses(
+        (status = 200, description = "Agent release manifest", body = AgentReleaseManifest),
+        (status = 404, description = "No release for platform")
+    ),
+    tag = "Agent Releases"
+)]
+pub async fn resolve_agent_release(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<AgentReleaseResolveQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let instance = find_agent_instance(&db, &req, &query.agent_instance_id).await?;
+    let platform = normalize_platform(&query.platform)?;
+
+    let version = match query.version.as_deref().filter(|v| !v.trim().is_empty()) {
+        Some(version) => Some(validate_version(version)?),
+        None => {
+            let application = find_application(&db, &instance.application_id).await?;
+            effective_target_version(&db, &application, Some(&platform)).await?
+        }
+    };
+    let release = match version {
+        Some(version) => list_releases(&db, Some(&platform), Some(&version)).await?,
+        None => Vec::new(),
+    }
+    .into_iter()
+    .next()
+    .ok_or_else(|| ApiError::NotFound(format!("平台 {} 没有可用的发布包", platform)))?;
+
+    let manifest = AgentReleaseManifest {
+        download_url: format!("/api/open/instances/agent-release/{}/download", release.id),
+        id: release.id,
+        version: release.version,
+        platform: release.platform,
+        file_size: release.file_size,
+        sha256: release.sha256,
+        signature: release.signature,
+    };
+    Ok(HttpResponse::Ok().json(manifest))
+}
+
+/// GET /api/open/instances/agent-release/{id}/download
+/// Agent 下载发布包
+#[utoipa::path(
+    get,
+    path = "/api/open/instances/agent-release/{id}/download",
+    params(
+        ("id" = String, Path, description = "Agent release ID"),
+        AgentReleaseDownloadQuery
+    ),
+    responses(
+        (status = 200, description = "Agent release binary"),
+        (status = 404, description = "Agent release not found")
+    ),
+    tag = "Agent Releases"
+)]
+pub async fn download_agent_release(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    query: web::Query<AgentReleaseDownloadQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    find_agent_instance(&db, &req, &query.agent_instance_id).await?;
+
+    let release = agent_releases::Entity::find_by_id(path.into_inner())
+        .filter(agent_releases::Column::DeletedAt.is_null())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Agent release not found".to_string()))?;
+    let file = files::Entity::find_by_id(release.file_id)
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+    let file_path = Path::new(&file.file_path);
+    if !file_path.exists() {
+        return Err(ApiError::NotFound("File not found on disk".to_string()));
+    }
+
+    Ok(actix_files::NamedFile::open(file_path)
+        .map_err(|e| ApiError::InternalServerError(format!("Failed to open file: {}", e)))?
+        .into_response(&req))
+}
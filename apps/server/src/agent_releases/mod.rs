@@ -0,0 +1,5 @@
+pub mod handlers;
+pub mod models;
+pub mod routes;
+
+pub use routes::{agent_release_routes, open_agent_release_routes};
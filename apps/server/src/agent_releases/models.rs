@@ -0,0 +1,293 @@
+use crate::entities::agent_releases;
+use crate::instance_tasks::models::TaskRolloutStrategy;
+use crate::shared::error::ApiError;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use utoipa::{IntoParams, ToSchema};
+
+/// 升级后等待新版本首次上报的默认时长（秒），超时 Agent 自动回滚
+pub const DEFAULT_HEALTH_TIMEOUT_SECONDS: u64 = 120;
+
+// ===================================================================
+// 版本号与平台
+// ===================================================================
+
+/// 校验版本号：以数字开头，由字母、数字、`.`、`-`、`+` 组成
+pub fn validate_version(version: &str) -> Result<String, ApiError> {
+    let version = version.trim().trim_start_matches('v');
+    let valid = !version.is_empty()
+        && version.len() <= 50
+        && version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+    if !valid {
+        return Err(ApiError::ValidationError(format!(
+            "版本号格式不正确: {}",
+            version
+        )));
+    }
+    Ok(version.to_string())
+}
+
+/// 规范化平台标识为 `<os>-<arch>`，兼容常见的架构别名（amd64、arm64）
+pub fn normalize_platform(platform: &str) -> Result<String, ApiError> {
+    let platform = platform.trim().to_ascii_lowercase();
+    let (os, arch) = platform
+        .split_once('-')
+        .filter(|(os, arch)| {
+            let valid =
+                |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            valid(os) && valid(arch)
+        })
+        .ok_or_else(|| {
+            ApiError::ValidationError(format!(
+                "平台标识格式不正确（应为 <os>-<arch>，如 linux-x86_64）: {}",
+                platform
+            ))
+        })?;
+    let os = match os {
+        "darwin" => "macos",
+        other => other,
+    };
+    let arch = match arch {
+        "amd64" | "x64" => "x86_64",
+        "arm64" => "aarch64",
+        other => other,
+    };
+    Ok(format!("{}-{}", os, arch))
+}
+
+/// 比较版本号：按 `.` 分段逐段比较（数字段按数值），带预发布后缀的版本低于正式版本
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (&str, Option<&str>) {
+        let version = version.split('+').next().unwrap_or(version);
+        match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        }
+    }
+    fn compare_segments(a: &str, b: &str) -> Ordering {
+        let mut left = a.split('.');
+        let mut right = b.split('.');
+        loop {
+            match (left.next(), right.next()) {
+                (None, None) => return Ordering::Equal,
+                (l, r) => {
+                    let l = l.unwrap_or("0");
+                    let r = r.unwrap_or("0");
+                    let ordering = match (l.parse::<u64>(), r.parse::<u64>()) {
+                        (Ok(l), Ok(r)) => l.cmp(&r),
+                        (Ok(_), Err(_)) => Ordering::Less,
+                        (Err(_), Ok(_)) => Ordering::Greater,
+                        (Err(_), Err(_)) => l.cmp(r),
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+            }
+        }
+    }
+
+    let (core_a, pre_a) = split(a);
+    let (core_b, pre_b) = split(b);
+    compare_segments(core_a, core_b).then_with(|| match (pre_a, pre_b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => compare_segments(a, b),
+    })
+}
+
+/// 校验签名为 Base64 编码的 64 字节 Ed25519 签名
+pub fn validate_signature(signature: &str) -> Result<String, ApiError> {
+    let signature = signature.trim();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(signature)
+        .map_err(|_| ApiError::ValidationError("签名不是有效的 Base64 编码".to_string()))?;
+    if bytes.len() != 64 {
+        return Err(ApiError::ValidationError(
+            "签名长度不正确，应为 64 字节的 Ed25519 签名".to_string(),
+        ));
+    }
+    Ok(signature.to_string())
+}
+
+// ===================================================================
+// 发布包管理
+// ===================================================================
+
+/// 登记发布包请求：安装包先通过文件接口上传，这里引用其文件ID
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentReleaseCreateRequest {
+    pub version: String,
+    /// 目标平台，如 linux-x86_64、linux-aarch64、windows-x86_64
+    pub platform: String,
+    pub file_id: String,
+    /// 对安装包原始字节的 Ed25519 签名（Base64）
+    pub signature: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// 发布包查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AgentReleaseListQuery {
+    pub platform: Option<String>,
+    pub version: Option<String>,
+}
+
+/// 发布包响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentReleaseResponse {
+    pub id: String,
+    pub version: String,
+    pub platform: String,
+    pub file_id: String,
+    pub file_size: i64,
+    pub sha256: String,
+    pub signature: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+impl AgentReleaseResponse {
+    pub fn from_entity(model: agent_releases::Model) -> Self {
+        Self {
+            id: model.id,
+            version: model.version,
+            platform: model.platform,
+            file_id: model.file_id,
+            file_size: model.file_size,
+            sha256: model.sha256,
+            signature: model.signature,
+            notes: model.notes,
+            created_by: model.created_by,
+            created_at: model.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 发布包列表响应（按版本从新到旧）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentReleaseListResponse {
+    pub data: Vec<AgentReleaseResponse>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+// ===================================================================
+// 应用版本管理
+// ===================================================================
+
+/// 单个版本的实例数量
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentVersionCount {
+    pub agent_type: Option<String>,
+    pub agent_version: Option<String>,
+    pub total: u64,
+    pub online: u64,
+    /// 是否为应用当前生效的目标版本（仅 Rust Agent）
+    pub is_target: bool,
+}
+
+/// 应用 Agent 版本分布响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentVersionDistributionResponse {
+    pub application_id: String,
+    /// 应用配置的目标版本，为空表示跟随最新版本
+    pub target_version: Option<String>,
+    /// 已登记的最新发布版本
+    pub latest_version: Option<String>,
+    pub data: Vec<AgentVersionCount>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 设置应用 Agent 目标版本请求，`target_version` 为空表示跟随最新版本
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentTargetVersionUpdateRequest {
+    pub target_version: Option<String>,
+}
+
+/// 发起 Agent 升级请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentUpgradeRequest {
+    /// 升级到的版本，为空时使用应用目标版本或最新版本
+    pub version: Option<String>,
+    /// 指定实例，为空表示应用下所有版本不一致的 Rust Agent
+    #[serde(default)]
+    pub instance_ids: Vec<String>,
+    /// 等待新版本首次上报的时长（秒），超时自动回滚
+    pub health_timeout_seconds: Option<u64>,
+    pub timeout_seconds: Option<i32>,
+    pub rollout_strategy: Option<TaskRolloutStrategy>,
+}
+
+// ===================================================================
+// 开放接口
+// ===================================================================
+
+/// Agent 查询发布包参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AgentReleaseResolveQuery {
+    pub agent_instance_id: String,
+    pub platform: String,
+    /// 指定版本，为空时使用应用目标版本或该平台的最新版本
+    pub version: Option<String>,
+}
+
+/// Agent 下载发布包参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AgentReleaseDownloadQuery {
+    pub agent_instance_id: String,
+}
+
+/// 下发给 Agent 的发布包信息
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentReleaseManifest {
+    pub id: String,
+    pub version: String,
+    pub platform: String,
+    pub file_size: i64,
+    pub sha256: String,
+    pub signature: Option<String>,
+    /// 相对服务端地址的下载路径，下载时需携带 agent_instance_id 查询参数与实例令牌
+    pub download_url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.2.0", "1.10.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.0", "1.99.99"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2.0-rc.1", "1.2.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("1.2.0-rc.2", "1.2.0-rc.10"),
+            Ordering::Less
+        );
+        assert_eq!(compare_versions("1.2.0+build.5", "1.2.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_normalize_platform_and_version() {
+        assert_eq!(normalize_platform("Linux-AMD64").unwrap(), "linux-x86_64");
+        assert_eq!(normalize_platform("darwin-arm64").unwrap(), "macos-aarch64");
+        assert_eq!(
+            normalize_platform("windows-x86_64").unwrap(),
+            "windows-x86_64"
+        );
+        assert!(normalize_platform("linux").is_err());
+        assert!(normalize_platform("linux-../x").is_err());
+
+        assert_eq!(validate_version(" v1.2.0 ").unwrap(), "1.2.0");
+        assert!(validate_version("latest").is_err());
+        assert!(validate_version("1.0/../x").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use super::handlers;
+use actix_web::web;
+
+pub fn agent_release_routes(cfg: &mut web::ServiceConfig) {
+    // 发布包管理与应用 Agent 版本管理（需要认证）
+    cfg.route(
+        "/agent-releases",
+        web::get().to(handlers::get_agent_releases),
+    )
+    .route(
+        "/agent-releases",
+        web::post().to(handlers::create_agent_release),
+    )
+    .route(
+        "/agent-releases/{id}",
+        web::delete().to(handlers::delete_agent_release),
+    )
+    .route(
+        "/applications/{id}/agent-versions",
+        web::get().to(handlers::get_agent_versions),
+    )
+    .route(
+        "/applications/{id}/agent-target-version",
+        web::put().to(handlers::update_agent_target_version),
+    )
+    .route(
+        "/applications/{id}/agent-upgrade",
+        web::post().to(handlers::create_agent_upgrade),
+    );
+}
+
+pub fn open_agent_release_routes(cfg: &mut web::ServiceConfig) {
+    // Agent 查询与下载发布包（开放接口，使用实例令牌认证）
+    cfg.route(
+        "/agent-release",
+        web::get().to(handlers::resolve_agent_release),
+    )
+    .route(
+        "/agent-release/{id}/download",
+        web::get().to(handlers::download_agent_release),
+    );
+}
@@ -0,0 +1,532 @@
+/// 告警评估
+///
+/// 每轮加载启用的规则，计算观测值并推进告警事件状态，然后向规则配置的渠道发送通知。
+/// 状态变更与通知认领均使用带原状态条件的更新，多个服务端实例同时评估时同一变更只会通知一次。
+use crate::alerts::models::AlertCondition;
+use crate::alerts::notifier::{self, AlertNotification};
+use crate::alerts::rules::{self, Observation, Transition};
+use crate::entities::{
+    alert_channels, alert_events, alert_rules, alert_silences, instance_records,
+    instance_task_records, instances,
+};
+use crate::instances::metrics;
+use crate::shared::enums::{AlertStatus, Status, TaskStatus};
+use crate::shared::generate_snowflake_id;
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use std::collections::{HashMap, HashSet};
+
+/// 指标数据超过该时长视为无数据（由离线规则负责）
+const METRIC_STALE_MINUTES: i64 = 5;
+
+/// 本轮评估结果统计
+#[derive(Debug, Default)]
+pub struct EvaluationSummary {
+    pub rules: usize,
+    pub opened: usize,
+    pub fired: usize,
+    pub resolved: usize,
+    pub notified: usize,
+}
+
+/// 评估所有启用的规则
+pub async fn evaluate_rules(
+    db: &DatabaseConnection,
+    now: DateTime<Utc>,
+) -> Result<EvaluationSummary, DbErr> {
+    let rules = alert_rules::Entity::find()
+        .filter(alert_rules::Column::Enabled.eq(true))
+        .filter(alert_rules::Column::DeletedAt.is_null())
+        .all(db)
+        .await?;
+    let mut summary = EvaluationSummary::default();
+    if rules.is_empty() {
+        return Ok(summary);
+    }
+    let silences = alert_silences::Entity::find()
+        .filter(alert_silences::Column::StartsAt.lte(now))
+        .filter(alert_silences::Column::EndsAt.gt(now))
+        .all(db)
+        .await?;
+    let channels: HashMap<String, alert_channels::Model> = alert_channels::Entity::find()
+        .filter(alert_channels::Column::Enabled.eq(true))
+        .filter(alert_channels::Column::DeletedAt.is_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|c| (c.id.clone(), c))
+        .collect();
+
+    for rule in rules {
+        let condition: AlertCondition = match serde_json::from_value(rule.condition.clone()) {
+            Ok(condition) => condition,
+            Err(e) => {
+                warn!("[alert_evaluator] 规则条件无效 rule_id={}: {}", rule.id, e);
+                continue;
+            }
+        };
+        let ctx = RuleContext {
+            rule: &rule,
+            silences: &silences,
+            channels: &channels,
+            now,
+        };
+        if let Err(e) = evaluate_rule(db, &ctx, &condition, &mut summary).await {
+            warn!("[alert_evaluator] 规则评估失败 rule_id={}: {}", rule.id, e);
+            continue;
+        }
+        alert_rules::Entity::update_many()
+            .col_expr(alert_rules::Column::LastEvaluatedAt, Expr::value(now))
+            .filter(alert_rules::Column::Id.eq(&rule.id))
+            .exec(db)
+            .await?;
+        summary.rules += 1;
+    }
+    Ok(summary)
+}
+
+struct RuleContext<'a> {
+    rule: &'a alert_rules::Model,
+    silences: &'a [alert_silences::Model],
+    channels: &'a HashMap<String, alert_channels::Model>,
+    now: DateTime<Utc>,
+}
+
+impl RuleContext<'_> {
+    fn silenced(&self, event: &alert_events::Model) -> bool {
+        self.silences.iter().any(|s| {
+            rules::silence_matches(s, &self.rule.id, event.instance_id.as_deref(), self.now)
+        })
+    }
+}
+
+async fn evaluate_rule(
+    db: &DatabaseConnection,
+    ctx: &RuleContext<'_>,
+    condition: &AlertCondition,
+    summary: &mut EvaluationSummary,
+) -> Result<(), DbErr> {
+    let observations = observe(db, ctx.rule, condition, ctx.now).await?;
+    let mut active: HashMap<String, alert_events::Model> = alert_events::Entity::find()
+        .filter(alert_events::Column::RuleId.eq(&ctx.rule.id))
+        .filter(alert_events::Column::Status.ne(AlertStatus::Resolved))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|e| (e.fingerprint.clone(), e))
+        .collect();
+    let pending_for = condition.pending_duration();
+
+    let mut seen = HashSet::new();
+    for observation in observations {
+        seen.insert(observation.fingerprint.clone());
+        let current = active.remove(&observation.fingerprint);
+        let transition = rules::transition(
+            current.as_ref(),
+            observation.breaching,
+            ctx.now,
+            pending_for,
+        );
+        apply(db, ctx, current, &observation, transition, summary).await?;
+    }
+    // 不再属于规则范围的对象（实例删除或规则范围调整）按恢复处理
+    for (fingerprint, current) in active {
+        if seen.contains(&fingerprint) {
+            continue;
+        }
+        let observation = Observation {
+            fingerprint,
+            instance_id: current.instance_id.clone(),
+            application_id: current.application_id.clone(),
+            value: None,
+            breaching: Some(false),
+            summary: current.summary.clone(),
+        };
+        let transition = rules::transition(Some(&current), Some(false), ctx.now, pending_for);
+        apply(db, ctx, Some(current), &observation, transition, summary).await?;
+    }
+    Ok(())
+}
+
+async fn apply(
+    db: &DatabaseConnection,
+    ctx: &RuleContext<'_>,
+    current: Option<alert_events::Model>,
+    observation: &Observation,
+    transition: Transition,
+    summary: &mut EvaluationSummary,
+) -> Result<(), DbErr> {
+    let now = ctx.now;
+    match (transition, current) {
+        (Transition::Open(status), _) => {
+            let firing = status == AlertStatus::Firing;
+            let event = alert_events::ActiveModel {
+                id: Set(generate_snowflake_id()),
+                rule_id: Set(ctx.rule.id.clone()),
+                fingerprint: Set(observation.fingerprint.clone()),
+                instance_id: Set(observation.instance_id.clone()),
+                application_id: Set(observation.application_id.clone()),
+                status: Set(status),
+                severity: Set(ctx.rule.severity.clone()),
+                summary: Set(observation.summary.clone()),
+                value: Set(observation.value),
+                started_at: Set(now.into()),
+                fired_at: Set(firing.then_some(now.into())),
+                resolved_at: Set(None),
+                last_value_at: Set(now.into()),
+                acknowledged_by: Set(None),
+                acknowledged_at: Set(None),
+                last_notified_at: Set(None),
+                notify_count: Set(0),
+                notify_error: Set(None),
+                created_at: Set(now.into()),
+                updated_at: Set(now.into()),
+            };
+            // 其他服务端实例已创建时唯一索引冲突，忽略即可
+            let Ok(event) = event.insert(db).await else {
+                return Ok(());
+            };
+            summary.opened += 1;
+            if firing {
+                summary.fired += 1;
+                notify_firing(db, ctx, &event, summary).await?;
+            }
+        }
+        (Transition::Fire, Some(current)) => {
+            let claimed = alert_events::Entity::update_many()
+                .col_expr(
+                    alert_events::Column::Status,
+                    Expr::value(AlertStatus::Firing),
+                )
+                .col_expr(alert_events::Column::FiredAt, Expr::value(now))
+                .col_expr(
+                    alert_events::Column::Summary,
+                    Expr::value(&observation.summary),
+                )
+                .col_expr(alert_events::Column::Value, Expr::value(observation.value))
+                .col_expr(alert_events::Column::LastValueAt, Expr::value(now))
+                .filter(alert_events::Column::Id.eq(&current.id))
+                .filter(alert_events::Column::Status.eq(AlertStatus::Pending))
+                .exec(db)
+                .await?
+                .rows_affected;
+            if claimed == 1 {
+                summary.fired += 1;
+                let event = alert_events::Model {
+                    status: AlertStatus::Firing,
+                    fired_at: Some(now.into()),
+                    summary: observation.summary.clone(),
+                    value: observation.value,
+                    ..current
+                };
+                notify_firing(db, ctx, &event, summary).await?;
+            }
+        }
+        (Transition::Resolve, Some(current)) => {
+            let claimed = alert_events::Entity::update_many()
+                .col_expr(
+                    alert_events::Column::Status,
+                    Expr::value(AlertStatus::Resolved),
+                )
+                .col_expr(alert_events::Column::ResolvedAt, Expr::value(now))
+                .filter(alert_events::Column::Id.eq(&current.id))
+                .filter(alert_events::Column::Status.eq(AlertStatus::Firing))
+                .exec(db)
+                .await?
+                .rows_affected;
+            if claimed == 1 {
+                summary.resolved += 1;
+                let event = alert_events::Model {
+                    status: AlertStatus::Resolved,
+                    resolved_at: Some(now.into()),
+                    ..current
+                };
+                // 仅通知过的告警发送恢复通知
+                if ctx.rule.notify_resolved
+                    && event.last_notified_at.is_some()
+                    && !ctx.silenced(&event)
+                {
+                    send_to_channels(db, ctx, &event).await?;
+                    summary.notified += 1;
+                }
+            }
+        }
+        (Transition::Discard, Some(current)) => {
+            alert_events::Entity::delete_many()
+                .filter(alert_events::Column::Id.eq(&current.id))
+                .filter(alert_events::Column::Status.eq(AlertStatus::Pending))
+                .exec(db)
+                .await?;
+        }
+        (Transition::Keep, Some(current)) => {
+            if observation.breaching == Some(true) {
+                alert_events::Entity::update_many()
+                    .col_expr(
+                        alert_events::Column::Summary,
+                        Expr::value(&observation.summary),
+                    )
+                    .col_expr(alert_events::Column::Value, Expr::value(observation.value))
+                    .col_expr(alert_events::Column::LastValueAt, Expr::value(now))
+                    .filter(alert_events::Column::Id.eq(&current.id))
+                    .exec(db)
+                    .await?;
+            }
+            if rules::notify_due(&current, ctx.rule.repeat_interval_minutes, now) {
+                notify_firing(db, ctx, &current, summary).await?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 认领并发送 firing 通知（静默中的告警不通知，静默结束后补发）
+async fn notify_firing(
+    db: &DatabaseConnection,
+    ctx: &RuleContext<'_>,
+    event: &alert_events::Model,
+    summary: &mut EvaluationSummary,
+) -> Result<(), DbErr> {
+    if ctx.silenced(event) || channels_of(ctx).is_empty() {
+        return Ok(());
+    }
+    let mut claim = alert_events::Entity::update_many()
+        .col_expr(alert_events::Column::LastNotifiedAt, Expr::value(ctx.now))
+        .col_expr(
+            alert_events::Column::NotifyCount,
+            Expr::col(alert_events::Column::NotifyCount).add(1),
+        )
+        .filter(alert_events::Column::Id.eq(&event.id));
+    claim = match event.last_notified_at {
+        Some(last) => claim.filter(alert_events::Column::LastNotifiedAt.eq(last)),
+        None => claim.filter(alert_events::Column::LastNotifiedAt.is_null()),
+    };
+    if claim.exec(db).await?.rows_affected != 1 {
+        return Ok(());
+    }
+    send_to_channels(db, ctx, event).await?;
+    summary.notified += 1;
+    Ok(())
+}
+
+fn channels_of<'a>(ctx: &RuleContext<'a>) -> Vec<&'a alert_channels::Model> {
+    ctx.rule
+        .channel_ids
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .filter_map(|id| ctx.channels.get(id))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 向规则的所有渠道发送通知，并记录失败原因
+async fn send_to_channels(
+    db: &DatabaseConnection,
+    ctx: &RuleContext<'_>,
+    event: &alert_events::Model,
+) -> Result<(), DbErr> {
+    let notification = AlertNotification::for_event(ctx.rule, event);
+    let mut errors = Vec::new();
+    for channel in channels_of(ctx) {
+        if let Err(e) = notifier::send(channel, &notification).await {
+            warn!(
+                "[alert_evaluator] 告警通知发送失败 event_id={} channel={}: {}",
+                event.id, channel.name, e
+            );
+            errors.push(format!("{}: {}", channel.name, e));
+        }
+    }
+    let error = (!errors.is_empty()).then(|| errors.join("; "));
+    if error != event.notify_error {
+        alert_events::Entity::update_many()
+            .col_expr(alert_events::Column::NotifyError, Expr::value(error))
+            .filter(alert_events::Column::Id.eq(&event.id))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// 规则范围内的实例
+async fn target_instances(
+    db: &DatabaseConnection,
+    rule: &alert_rules::Model,
+) -> Result<Vec<instances::Model>, DbErr> {
+    let mut select = instances::Entity::find()
+        .filter(instances::Column::DeletedAt.is_null())
+        .filter(instances::Column::Status.eq(Status::Active));
+    if let Some(application_id) = &rule.application_id {
+        select = select.filter(instances::Column::ApplicationId.eq(application_id));
+    }
+    let ids = instance_ids_of(rule);
+    if !ids.is_empty() {
+        select = select.filter(instances::Column::Id.is_in(ids));
+    }
+    select.all(db).await
+}
+
+fn instance_ids_of(rule: &alert_rules::Model) -> Vec<String> {
+    rule.instance_ids
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn instance_label(instance: &instances::Model) -> String {
+    format!("{}({})", instance.hostname, instance.ip_address)
+}
+
+/// 计算规则范围内各对象的观测值
+async fn observe(
+    db: &DatabaseConnection,
+    rule: &alert_rules::Model,
+    condition: &AlertCondition,
+    now: DateTime<Utc>,
+) -> Result<Vec<Observation>, DbErr> {
+    match condition {
+        AlertCondition::MetricThreshold {
+            metric,
+            operator,
+            threshold,
+            ..
+        } => {
+            let targets = target_instances(db, rule).await?;
+            if targets.is_empty() {
+                return Ok(Vec::new());
+            }
+            let records = instance_records::Entity::find()
+                .filter(
+                    instance_records::Column::InstanceId
+                        .is_in(targets.iter().map(|i| i.id.clone())),
+                )
+                .filter(
+                    instance_records::Column::ReportTimestamp
+                        .gte(now - Duration::minutes(METRIC_STALE_MINUTES)),
+                )
+                .order_by_desc(instance_records::Column::ReportTimestamp)
+                .all(db)
+                .await?;
+            let mut latest: HashMap<&str, f64> = HashMap::new();
+            for record in &records {
+                if latest.contains_key(record.instance_id.as_str()) {
+                    continue;
+                }
+                if let Some(value) = metrics::record_value(record, metric) {
+                    latest.insert(record.instance_id.as_str(), value);
+                }
+            }
+            Ok(targets
+                .iter()
+                .map(|instance| {
+                    let value = latest.get(instance.id.as_str()).copied();
+                    Observation {
+                        fingerprint: instance.id.clone(),
+                        instance_id: Some(instance.id.clone()),
+                        application_id: Some(instance.application_id.clone()),
+                        value,
+                        breaching: value.map(|v| operator.compare(v, *threshold)),
+                        summary: format!(
+                            "实例 {} 指标 {} 当前值 {:.2}，阈值 {} {}",
+                            instance_label(instance),
+                            metric,
+                            value.unwrap_or_default(),
+                            operator.symbol(),
+                            threshold
+                        ),
+                    }
+                })
+                .collect())
+        }
+        AlertCondition::InstanceOffline { for_minutes } => {
+            let targets = target_instances(db, rule).await?;
+            Ok(targets
+                .iter()
+                .map(|instance| {
+                    let last_seen = instance
+                        .last_report_at
+                        .or(instance.first_report_at)
+                        .unwrap_or(instance.created_at)
+                        .with_timezone(&Utc);
+                    let minutes = (now - last_seen).num_minutes();
+                    Observation {
+                        fingerprint: instance.id.clone(),
+                        instance_id: Some(instance.id.clone()),
+                        application_id: Some(instance.application_id.clone()),
+                        value: Some(minutes as f64),
+                        breaching: Some(minutes >= *for_minutes),
+                        summary: format!(
+                            "实例 {} 已 {} 分钟未上报（最后上报 {}）",
+                            instance_label(instance),
+                            minutes,
+                            last_seen.to_rfc3339()
+                        ),
+                    }
+                })
+                .collect())
+        }
+        AlertCondition::TaskFailureRate {
+            window_minutes,
+            threshold_percent,
+            min_records,
+        } => {
+            let mut select = instance_task_records::Entity::find()
+                .filter(
+                    instance_task_records::Column::EndTime
+                        .gte(now - Duration::minutes(*window_minutes)),
+                )
+                .filter(instance_task_records::Column::Status.is_in([
+                    TaskStatus::Success,
+                    TaskStatus::Failed,
+                    TaskStatus::Timeout,
+                ]));
+            if rule.application_id.is_some() || !instance_ids_of(rule).is_empty() {
+                let ids: Vec<String> = target_instances(db, rule)
+                    .await?
+                    .into_iter()
+                    .map(|i| i.id)
+                    .collect();
+                select = select.filter(instance_task_records::Column::InstanceId.is_in(ids));
+            }
+            let total = select.clone().count(db).await? as i64;
+            let failed = select
+                .filter(
+                    Condition::any()
+                        .add(instance_task_records::Column::Status.eq(TaskStatus::Failed))
+                        .add(instance_task_records::Column::Status.eq(TaskStatus::Timeout)),
+                )
+                .count(db)
+                .await? as i64;
+            let rate = if total > 0 {
+                failed as f64 * 100.0 / total as f64
+            } else {
+                0.0
+            };
+            Ok(vec![Observation {
+                fingerprint: rule
+                    .application_id
+                    .clone()
+                    .unwrap_or_else(|| "all".to_string()),
+                instance_id: None,
+                application_id: rule.application_id.clone(),
+                value: Some(rate),
+                breaching: Some(total >= *min_records && rate >= *threshold_percent),
+                summary: format!(
+                    "最近 {} 分钟任务失败率 {:.1}%（{}/{}），阈值 {}%",
+                    window_minutes, rate, failed, total, threshold_percent
+                ),
+            }])
+        }
+    }
+}
@@ -0,0 +1,1044 @@
+use crate::alerts::models::*;
+use crate::alerts::notifier::{self, AlertNotification, ChannelConfig};
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::{alert_channels, alert_events, alert_rules, alert_silences};
+use crate::instance_tasks::models::Pagination;
+use crate::shared::enums::{AlertChannelType, AlertSeverity, AlertStatus};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use serde_json::json;
+
+/// 静默最长时长（天）
+const MAX_SILENCE_DAYS: i64 = 30;
+
+fn timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn parse_time(field: &str, value: &str) -> Result<DateTime<Utc>, ApiError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| ApiError::ValidationError(format!("{} must be an RFC3339 time", field)))
+}
+
+fn channel_snapshot(channel: &alert_channels::Model) -> serde_json::Value {
+    json!({
+        "id": channel.id,
+        "name": channel.name,
+        "channel_type": channel.channel_type,
+        "config": ChannelConfig::mask(&channel.config),
+        "enabled": channel.enabled,
+        "updated_at": channel.updated_at.to_rfc3339(),
+    })
+}
+
+fn rule_snapshot(rule: &alert_rules::Model) -> serde_json::Value {
+    json!({
+        "id": rule.id,
+        "name": rule.name,
+        "rule_type": rule.rule_type,
+        "severity": rule.severity,
+        "application_id": rule.application_id,
+        "instance_ids": rule.instance_ids,
+        "condition": rule.condition,
+        "channel_ids": rule.channel_ids,
+        "repeat_interval_minutes": rule.repeat_interval_minutes,
+        "notify_resolved": rule.notify_resolved,
+        "enabled": rule.enabled,
+        "updated_at": rule.updated_at.to_rfc3339(),
+    })
+}
+
+async fn find_channel(
+    db: &DatabaseConnection,
+    channel_id: &str,
+) -> Result<alert_channels::Model, ApiError> {
+    alert_channels::Entity::find_by_id(channel_id)
+        .filter(alert_channels::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Alert channel not found".to_string()))
+}
+
+async fn find_rule(db: &DatabaseConnection, rule_id: &str) -> Result<alert_rules::Model, ApiError> {
+    alert_rules::Entity::find_by_id(rule_id)
+        .filter(alert_rules::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Alert rule not found".to_string()))
+}
+
+fn validate_channel_config(
+    channel_type: &AlertChannelType,
+    config: &serde_json::Value,
+) -> Result<(), ApiError> {
+    ChannelConfig::parse(channel_type, config)
+        .map(|_| ())
+        .map_err(ApiError::ValidationError)
+}
+
+/// 校验规则引用的通知渠道均存在
+async fn validate_channel_ids(db: &DatabaseConnection, ids: &[String]) -> Result<(), ApiError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let found = alert_channels::Entity::find()
+        .filter(alert_channels::Column::Id.is_in(ids.to_vec()))
+        .filter(alert_channels::Column::DeletedAt.is_null())
+        .count(db)
+        .await?;
+    let unique: std::collections::HashSet<&String> = ids.iter().collect();
+    if found as usize != unique.len() {
+        return Err(ApiError::ValidationError(
+            "channel_ids contains unknown channel".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_repeat_interval(minutes: i32) -> Result<(), ApiError> {
+    if !(0..=7 * 24 * 60).contains(&minutes) {
+        return Err(ApiError::ValidationError(
+            "repeat_interval_minutes must be between 0 and 10080".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// ===================================================================
+// 通知渠道
+// ===================================================================
+
+/// GET /api/alerts/channels
+/// 获取通知渠道列表
+#[utoipa::path(
+    get,
+    path = "/api/alerts/channels",
+    responses(
+        (status = 200, description = "返回通知渠道列表", body = AlertChannelListResponse),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_channels(
+    db: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, ApiError> {
+    let channels = alert_channels::Entity::find()
+        .filter(alert_channels::Column::DeletedAt.is_null())
+        .order_by_desc(alert_channels::Column::CreatedAt)
+        .all(&**db)
+        .await?;
+    Ok(HttpResponse::Ok().json(AlertChannelListResponse {
+        data: channels
+            .into_iter()
+            .map(AlertChannelResponse::from_entity)
+            .collect(),
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    }))
+}
+
+/// POST /api/alerts/channels
+/// 创建通知渠道
+#[utoipa::path(
+    post,
+    path = "/api/alerts/channels",
+    request_body = AlertChannelCreateRequest,
+    responses(
+        (status = 200, description = "创建成功", body = AlertChannelResponse),
+        (status = 400, description = "渠道配置无效"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn create_alert_channel(
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<AlertChannelCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+    if request.name.trim().is_empty() {
+        return Err(ApiError::ValidationError("name is required".to_string()));
+    }
+    validate_channel_config(&request.channel_type, &request.config)?;
+
+    let now = Utc::now();
+    let channel = alert_channels::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        name: Set(request.name),
+        channel_type: Set(request.channel_type),
+        config: Set(request.config),
+        enabled: Set(request.enabled.unwrap_or(true)),
+        created_by: Set(user_id.clone()),
+        updated_by: Set(user_id),
+        deleted_at: Set(None),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    }
+    .insert(&**db)
+    .await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_channels",
+        "create",
+        &req,
+        None,
+        Some(channel_snapshot(&channel)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertChannelResponse::from_entity(channel)))
+}
+
+/// GET /api/alerts/channels/{channel_id}
+/// 获取通知渠道详情
+#[utoipa::path(
+    get,
+    path = "/api/alerts/channels/{channel_id}",
+    params(("channel_id" = String, Path, description = "通知渠道ID")),
+    responses(
+        (status = 200, description = "返回通知渠道详情", body = AlertChannelResponse),
+        (status = 404, description = "通知渠道不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_channel(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let channel = find_channel(&db, &path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(AlertChannelResponse::from_entity(channel)))
+}
+
+/// PUT /api/alerts/channels/{channel_id}
+/// 更新通知渠道
+#[utoipa::path(
+    put,
+    path = "/api/alerts/channels/{channel_id}",
+    params(("channel_id" = String, Path, description = "通知渠道ID")),
+    request_body = AlertChannelUpdateRequest,
+    responses(
+        (status = 200, description = "更新成功", body = AlertChannelResponse),
+        (status = 400, description = "渠道配置无效"),
+        (status = 404, description = "通知渠道不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn update_alert_channel(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    request: web::Json<AlertChannelUpdateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+    let channel = find_channel(&db, &path.into_inner()).await?;
+    let before = channel_snapshot(&channel);
+
+    let config = match request.config {
+        Some(mut config) => {
+            ChannelConfig::restore_secrets(&mut config, &channel.config);
+            validate_channel_config(&channel.channel_type, &config)?;
+            Some(config)
+        }
+        None => None,
+    };
+    let mut active: alert_channels::ActiveModel = channel.into();
+    if let Some(name) = request.name {
+        if name.trim().is_empty() {
+            return Err(ApiError::ValidationError("name is required".to_string()));
+        }
+        active.name = Set(name);
+    }
+    if let Some(config) = config {
+        active.config = Set(config);
+    }
+    if let Some(enabled) = request.enabled {
+        active.enabled = Set(enabled);
+    }
+    active.updated_by = Set(user_id);
+    active.updated_at = Set(Utc::now().into());
+    let channel = active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_channels",
+        "update",
+        &req,
+        Some(before),
+        Some(channel_snapshot(&channel)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertChannelResponse::from_entity(channel)))
+}
+
+/// DELETE /api/alerts/channels/{channel_id}
+/// 删除通知渠道（软删除，引用该渠道的规则不再向其发送）
+#[utoipa::path(
+    delete,
+    path = "/api/alerts/channels/{channel_id}",
+    params(("channel_id" = String, Path, description = "通知渠道ID")),
+    responses(
+        (status = 200, description = "删除成功"),
+        (status = 404, description = "通知渠道不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn delete_alert_channel(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let channel = find_channel(&db, &path.into_inner()).await?;
+    let before = channel_snapshot(&channel);
+
+    let mut active: alert_channels::ActiveModel = channel.into();
+    active.enabled = Set(false);
+    active.deleted_at = Set(Some(Utc::now().into()));
+    active.updated_at = Set(Utc::now().into());
+    active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_channels",
+        "delete",
+        &req,
+        Some(before),
+        None,
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "message": "Alert channel deleted successfully"
+    })))
+}
+
+/// POST /api/alerts/channels/{channel_id}/test
+/// 发送测试通知
+#[utoipa::path(
+    post,
+    path = "/api/alerts/channels/{channel_id}/test",
+    params(("channel_id" = String, Path, description = "通知渠道ID")),
+    responses(
+        (status = 200, description = "返回发送结果", body = AlertChannelTestResponse),
+        (status = 404, description = "通知渠道不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn test_alert_channel(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let channel = find_channel(&db, &path.into_inner()).await?;
+    let response = match notifier::send(&channel, &AlertNotification::test(&channel.name)).await {
+        Ok(()) => AlertChannelTestResponse {
+            success: true,
+            message: "Test notification sent".to_string(),
+        },
+        Err(e) => AlertChannelTestResponse {
+            success: false,
+            message: e,
+        },
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// ===================================================================
+// 告警规则
+// ===================================================================
+
+/// GET /api/alerts/rules
+/// 获取告警规则列表
+#[utoipa::path(
+    get,
+    path = "/api/alerts/rules",
+    params(AlertRuleListQuery),
+    responses(
+        (status = 200, description = "返回告警规则列表", body = AlertRuleListResponse),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_rules(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<AlertRuleListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = (page - 1) * limit;
+
+    let mut select = alert_rules::Entity::find().filter(alert_rules::Column::DeletedAt.is_null());
+    if let Some(rule_type) = &query.rule_type {
+        select = select.filter(alert_rules::Column::RuleType.eq(rule_type.clone()));
+    }
+    if let Some(application_id) = &query.application_id {
+        select = select.filter(alert_rules::Column::ApplicationId.eq(application_id));
+    }
+    if let Some(enabled) = query.enabled {
+        select = select.filter(alert_rules::Column::Enabled.eq(enabled));
+    }
+    if let Some(search) = query.search.as_deref().filter(|s| !s.is_empty()) {
+        select = select.filter(alert_rules::Column::Name.contains(search));
+    }
+
+    let total = select.clone().count(&**db).await?;
+    let rules = select
+        .order_by_desc(alert_rules::Column::CreatedAt)
+        .offset(offset as u64)
+        .limit(limit as u64)
+        .all(&**db)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AlertRuleListResponse {
+        data: rules
+            .into_iter()
+            .map(AlertRuleResponse::from_entity)
+            .collect(),
+        pagination: Pagination {
+            page,
+            limit,
+            total: total as u32,
+        },
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    }))
+}
+
+/// POST /api/alerts/rules
+/// 创建告警规则
+#[utoipa::path(
+    post,
+    path = "/api/alerts/rules",
+    request_body = AlertRuleCreateRequest,
+    responses(
+        (status = 200, description = "创建成功", body = AlertRuleResponse),
+        (status = 400, description = "告警条件或通知渠道无效"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn create_alert_rule(
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<AlertRuleCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+    if request.name.trim().is_empty() {
+        return Err(ApiError::ValidationError("name is required".to_string()));
+    }
+    request.condition.validate()?;
+    validate_channel_ids(&db, &request.channel_ids).await?;
+    let repeat_interval_minutes = request.repeat_interval_minutes.unwrap_or(0);
+    validate_repeat_interval(repeat_interval_minutes)?;
+
+    let now = Utc::now();
+    let rule = alert_rules::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        name: Set(request.name),
+        description: Set(request.description),
+        rule_type: Set(request.condition.rule_type()),
+        severity: Set(request.severity.unwrap_or(AlertSeverity::Warning)),
+        application_id: Set(request.application_id.filter(|id| !id.is_empty())),
+        instance_ids: Set(json!(request.instance_ids)),
+        condition: Set(json!(request.condition)),
+        channel_ids: Set(json!(request.channel_ids)),
+        repeat_interval_minutes: Set(repeat_interval_minutes),
+        notify_resolved: Set(request.notify_resolved.unwrap_or(true)),
+        enabled: Set(request.enabled.unwrap_or(true)),
+        last_evaluated_at: Set(None),
+        created_by: Set(user_id.clone()),
+        updated_by: Set(user_id),
+        deleted_at: Set(None),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    }
+    .insert(&**db)
+    .await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_rules",
+        "create",
+        &req,
+        None,
+        Some(rule_snapshot(&rule)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertRuleResponse::from_entity(rule)))
+}
+
+/// GET /api/alerts/rules/{rule_id}
+/// 获取告警规则详情
+#[utoipa::path(
+    get,
+    path = "/api/alerts/rules/{rule_id}",
+    params(("rule_id" = String, Path, description = "告警规则ID")),
+    responses(
+        (status = 200, description = "返回告警规则详情", body = AlertRuleResponse),
+        (status = 404, description = "告警规则不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_rule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let rule = find_rule(&db, &path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(AlertRuleResponse::from_entity(rule)))
+}
+
+/// PUT /api/alerts/rules/{rule_id}
+/// 更新告警规则（条件类型变化时关闭原有未恢复事件）
+#[utoipa::path(
+    put,
+    path = "/api/alerts/rules/{rule_id}",
+    params(("rule_id" = String, Path, description = "告警规则ID")),
+    request_body = AlertRuleUpdateRequest,
+    responses(
+        (status = 200, description = "更新成功", body = AlertRuleResponse),
+        (status = 400, description = "告警条件或通知渠道无效"),
+        (status = 404, description = "告警规则不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn update_alert_rule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    request: web::Json<AlertRuleUpdateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+    let rule = find_rule(&db, &path.into_inner()).await?;
+    let before = rule_snapshot(&rule);
+
+    if let Some(condition) = &request.condition {
+        condition.validate()?;
+    }
+    if let Some(channel_ids) = &request.channel_ids {
+        validate_channel_ids(&db, channel_ids).await?;
+    }
+    if let Some(minutes) = request.repeat_interval_minutes {
+        validate_repeat_interval(minutes)?;
+    }
+    let type_changed = request
+        .condition
+        .as_ref()
+        .is_some_and(|c| c.rule_type() != rule.rule_type);
+
+    let rule_id = rule.id.clone();
+    let mut active: alert_rules::ActiveModel = rule.into();
+    if let Some(name) = request.name {
+        if name.trim().is_empty() {
+            return Err(ApiError::ValidationError("name is required".to_string()));
+        }
+        active.name = Set(name);
+    }
+    if let Some(description) = request.description {
+        active.description = Set(Some(description));
+    }
+    if let Some(severity) = request.severity {
+        active.severity = Set(severity);
+    }
+    if let Some(application_id) = request.application_id {
+        active.application_id = Set(Some(application_id).filter(|id| !id.is_empty()));
+    }
+    if let Some(instance_ids) = request.instance_ids {
+        active.instance_ids = Set(json!(instance_ids));
+    }
+    if let Some(condition) = request.condition {
+        active.rule_type = Set(condition.rule_type());
+        active.condition = Set(json!(condition));
+    }
+    if let Some(channel_ids) = request.channel_ids {
+        active.channel_ids = Set(json!(channel_ids));
+    }
+    if let Some(minutes) = request.repeat_interval_minutes {
+        active.repeat_interval_minutes = Set(minutes);
+    }
+    if let Some(notify_resolved) = request.notify_resolved {
+        active.notify_resolved = Set(notify_resolved);
+    }
+    active.updated_by = Set(user_id);
+    active.updated_at = Set(Utc::now().into());
+    let rule = active.update(&**db).await?;
+
+    if type_changed {
+        close_active_events(&db, &rule_id).await?;
+    }
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_rules",
+        "update",
+        &req,
+        Some(before),
+        Some(rule_snapshot(&rule)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertRuleResponse::from_entity(rule)))
+}
+
+/// 关闭规则的未恢复事件（不发送恢复通知）
+async fn close_active_events(db: &DatabaseConnection, rule_id: &str) -> Result<(), ApiError> {
+    let now = Utc::now();
+    alert_events::Entity::delete_many()
+        .filter(alert_events::Column::RuleId.eq(rule_id))
+        .filter(alert_events::Column::Status.eq(AlertStatus::Pending))
+        .exec(db)
+        .await?;
+    alert_events::Entity::update_many()
+        .col_expr(
+            alert_events::Column::Status,
+            sea_orm::sea_query::Expr::value(AlertStatus::Resolved),
+        )
+        .col_expr(
+            alert_events::Column::ResolvedAt,
+            sea_orm::sea_query::Expr::value(now),
+        )
+        .filter(alert_events::Column::RuleId.eq(rule_id))
+        .filter(alert_events::Column::Status.eq(AlertStatus::Firing))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// DELETE /api/alerts/rules/{rule_id}
+/// 删除告警规则（软删除，未恢复事件一并关闭）
+#[utoipa::path(
+    delete,
+    path = "/api/alerts/rules/{rule_id}",
+    params(("rule_id" = String, Path, description = "告警规则ID")),
+    responses(
+        (status = 200, description = "删除成功"),
+        (status = 404, description = "告警规则不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn delete_alert_rule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let rule = find_rule(&db, &path.into_inner()).await?;
+    let before = rule_snapshot(&rule);
+    let rule_id = rule.id.clone();
+
+    let mut active: alert_rules::ActiveModel = rule.into();
+    active.enabled = Set(false);
+    active.deleted_at = Set(Some(Utc::now().into()));
+    active.updated_at = Set(Utc::now().into());
+    active.update(&**db).await?;
+    close_active_events(&db, &rule_id).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_rules",
+        "delete",
+        &req,
+        Some(before),
+        None,
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "message": "Alert rule deleted successfully"
+    })))
+}
+
+/// 启用或停用告警规则
+async fn set_rule_enabled(
+    db: &DatabaseConnection,
+    rule_id: &str,
+    enabled: bool,
+    req: &HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    let rule = find_rule(db, rule_id).await?;
+    let before = rule_snapshot(&rule);
+
+    let mut active: alert_rules::ActiveModel = rule.into();
+    active.enabled = Set(enabled);
+    active.updated_by = Set(user_id);
+    active.updated_at = Set(Utc::now().into());
+    let rule = active.update(db).await?;
+    // 停用后不再评估，未恢复事件直接关闭
+    if !enabled {
+        close_active_events(db, rule_id).await?;
+    }
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db,
+        "alert_rules",
+        if enabled { "enable" } else { "disable" },
+        req,
+        Some(before),
+        Some(rule_snapshot(&rule)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertRuleResponse::from_entity(rule)))
+}
+
+/// POST /api/alerts/rules/{rule_id}/enable
+/// 启用告警规则
+#[utoipa::path(
+    post,
+    path = "/api/alerts/rules/{rule_id}/enable",
+    params(("rule_id" = String, Path, description = "告警规则ID")),
+    responses(
+        (status = 200, description = "启用成功", body = AlertRuleResponse),
+        (status = 404, description = "告警规则不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn enable_alert_rule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    set_rule_enabled(&db, &path.into_inner(), true, &req).await
+}
+
+/// POST /api/alerts/rules/{rule_id}/disable
+/// 停用告警规则
+#[utoipa::path(
+    post,
+    path = "/api/alerts/rules/{rule_id}/disable",
+    params(("rule_id" = String, Path, description = "告警规则ID")),
+    responses(
+        (status = 200, description = "停用成功", body = AlertRuleResponse),
+        (status = 404, description = "告警规则不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn disable_alert_rule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    set_rule_enabled(&db, &path.into_inner(), false, &req).await
+}
+
+// ===================================================================
+// 告警事件
+// ===================================================================
+
+/// GET /api/alerts/events
+/// 获取告警事件列表
+#[utoipa::path(
+    get,
+    path = "/api/alerts/events",
+    params(AlertEventListQuery),
+    responses(
+        (status = 200, description = "返回告警事件列表", body = AlertEventListResponse),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_events(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<AlertEventListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = (page - 1) * limit;
+
+    let mut select = alert_events::Entity::find();
+    if let Some(status) = &query.status {
+        select = select.filter(alert_events::Column::Status.eq(status.clone()));
+    }
+    if let Some(severity) = &query.severity {
+        select = select.filter(alert_events::Column::Severity.eq(severity.clone()));
+    }
+    if let Some(rule_id) = &query.rule_id {
+        select = select.filter(alert_events::Column::RuleId.eq(rule_id));
+    }
+    if let Some(instance_id) = &query.instance_id {
+        select = select.filter(alert_events::Column::InstanceId.eq(instance_id));
+    }
+    if let Some(application_id) = &query.application_id {
+        select = select.filter(alert_events::Column::ApplicationId.eq(application_id));
+    }
+
+    let total = select.clone().count(&**db).await?;
+    let events = select
+        .order_by_desc(alert_events::Column::StartedAt)
+        .offset(offset as u64)
+        .limit(limit as u64)
+        .all(&**db)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AlertEventListResponse {
+        data: events
+            .into_iter()
+            .map(AlertEventResponse::from_entity)
+            .collect(),
+        pagination: Pagination {
+            page,
+            limit,
+            total: total as u32,
+        },
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    }))
+}
+
+/// GET /api/alerts/events/{event_id}
+/// 获取告警事件详情
+#[utoipa::path(
+    get,
+    path = "/api/alerts/events/{event_id}",
+    params(("event_id" = String, Path, description = "告警事件ID")),
+    responses(
+        (status = 200, description = "返回告警事件详情", body = AlertEventResponse),
+        (status = 404, description = "告警事件不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_event(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let event = alert_events::Entity::find_by_id(path.into_inner())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Alert event not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(AlertEventResponse::from_entity(event)))
+}
+
+/// POST /api/alerts/events/{event_id}/ack
+/// 确认告警（确认后不再重复通知，恢复通知不受影响）
+#[utoipa::path(
+    post,
+    path = "/api/alerts/events/{event_id}/ack",
+    params(("event_id" = String, Path, description = "告警事件ID")),
+    responses(
+        (status = 200, description = "确认成功", body = AlertEventResponse),
+        (status = 400, description = "告警不处于 firing 状态"),
+        (status = 404, description = "告警事件不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn acknowledge_alert_event(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let event = alert_events::Entity::find_by_id(path.into_inner())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Alert event not found".to_string()))?;
+    if event.status != AlertStatus::Firing {
+        return Err(ApiError::BadRequest(
+            "Only firing alerts can be acknowledged".to_string(),
+        ));
+    }
+    if event.acknowledged_at.is_some() {
+        return Ok(HttpResponse::Ok().json(AlertEventResponse::from_entity(event)));
+    }
+
+    let mut active: alert_events::ActiveModel = event.into();
+    active.acknowledged_by = Set(Some(user_id));
+    active.acknowledged_at = Set(Some(Utc::now().into()));
+    active.updated_at = Set(Utc::now().into());
+    let event = active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_events",
+        "acknowledge",
+        &req,
+        None,
+        Some(json!({
+            "id": event.id,
+            "rule_id": event.rule_id,
+            "instance_id": event.instance_id,
+            "acknowledged_by": event.acknowledged_by,
+        })),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertEventResponse::from_entity(event)))
+}
+
+// ===================================================================
+// 静默
+// ===================================================================
+
+/// GET /api/alerts/silences
+/// 获取静默列表
+#[utoipa::path(
+    get,
+    path = "/api/alerts/silences",
+    params(AlertSilenceListQuery),
+    responses(
+        (status = 200, description = "返回静默列表", body = AlertSilenceListResponse),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn get_alert_silences(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<AlertSilenceListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let mut select = alert_silences::Entity::find();
+    if query.active.unwrap_or(true) {
+        select = select.filter(alert_silences::Column::EndsAt.gt(Utc::now()));
+    }
+    let silences = select
+        .order_by_desc(alert_silences::Column::CreatedAt)
+        .limit(500)
+        .all(&**db)
+        .await?;
+    Ok(HttpResponse::Ok().json(AlertSilenceListResponse {
+        data: silences
+            .into_iter()
+            .map(AlertSilenceResponse::from_entity)
+            .collect(),
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    }))
+}
+
+/// POST /api/alerts/silences
+/// 创建静默
+#[utoipa::path(
+    post,
+    path = "/api/alerts/silences",
+    request_body = AlertSilenceCreateRequest,
+    responses(
+        (status = 200, description = "创建成功", body = AlertSilenceResponse),
+        (status = 400, description = "时间范围无效"),
+        (status = 404, description = "告警规则不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn create_alert_silence(
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<AlertSilenceCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+    let rule_id = request.rule_id.filter(|id| !id.is_empty());
+    if let Some(rule_id) = &rule_id {
+        find_rule(&db, rule_id).await?;
+    }
+
+    let now = Utc::now();
+    let starts_at = match request.starts_at.as_deref() {
+        Some(value) => parse_time("starts_at", value)?,
+        None => now,
+    };
+    let ends_at = match (request.ends_at.as_deref(), request.duration_minutes) {
+        (Some(value), _) => parse_time("ends_at", value)?,
+        (None, Some(minutes)) if minutes > 0 => starts_at + Duration::minutes(minutes),
+        _ => {
+            return Err(ApiError::ValidationError(
+                "ends_at or a positive duration_minutes is required".to_string(),
+            ))
+        }
+    };
+    if ends_at <= starts_at || ends_at <= now {
+        return Err(ApiError::ValidationError(
+            "ends_at must be later than starts_at and now".to_string(),
+        ));
+    }
+    if ends_at - starts_at > Duration::days(MAX_SILENCE_DAYS) {
+        return Err(ApiError::ValidationError(format!(
+            "Silence cannot exceed {} days",
+            MAX_SILENCE_DAYS
+        )));
+    }
+
+    let silence = alert_silences::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        rule_id: Set(rule_id),
+        instance_id: Set(request.instance_id.filter(|id| !id.is_empty())),
+        comment: Set(request.comment),
+        starts_at: Set(starts_at.into()),
+        ends_at: Set(ends_at.into()),
+        created_by: Set(user_id),
+        created_at: Set(now.into()),
+    }
+    .insert(&**db)
+    .await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_silences",
+        "create",
+        &req,
+        None,
+        Some(json!(AlertSilenceResponse::from_entity(silence.clone()))),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertSilenceResponse::from_entity(silence)))
+}
+
+/// DELETE /api/alerts/silences/{silence_id}
+/// 提前结束静默
+#[utoipa::path(
+    delete,
+    path = "/api/alerts/silences/{silence_id}",
+    params(("silence_id" = String, Path, description = "静默ID")),
+    responses(
+        (status = 200, description = "已结束", body = AlertSilenceResponse),
+        (status = 404, description = "静默不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Alerts"
+)]
+pub async fn expire_alert_silence(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let silence = alert_silences::Entity::find_by_id(path.into_inner())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Alert silence not found".to_string()))?;
+    let before = json!(AlertSilenceResponse::from_entity(silence.clone()));
+    let now = Utc::now();
+    if silence.ends_at.with_timezone(&Utc) <= now {
+        return Ok(HttpResponse::Ok().json(AlertSilenceResponse::from_entity(silence)));
+    }
+
+    let mut active: alert_silences::ActiveModel = silence.into();
+    active.ends_at = Set(now.into());
+    let silence = active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "alert_silences",
+        "expire",
+        &req,
+        Some(before),
+        Some(json!(AlertSilenceResponse::from_entity(silence.clone()))),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(AlertSilenceResponse::from_entity(silence)))
+}
@@ -0,0 +1,8 @@
+pub mod evaluator;
+pub mod handlers;
+pub mod models;
+pub mod notifier;
+pub mod routes;
+pub mod rules;
+
+pub use routes::alert_routes;
@@ -0,0 +1,392 @@
+use crate::alerts::notifier::ChannelConfig;
+use crate::entities::{alert_channels, alert_events, alert_rules, alert_silences};
+use crate::instance_tasks::models::Pagination;
+use crate::shared::enums::{AlertChannelType, AlertRuleType, AlertSeverity, AlertStatus};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use utoipa::{IntoParams, ToSchema};
+
+// ===================================================================
+// 告警条件
+// ===================================================================
+
+/// 阈值比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdOperator {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+/// 告警条件，type 与规则类型一致
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// 实例最新指标满足阈值并持续 for_seconds 秒
+    MetricThreshold {
+        /// 指标名，与监控数据查询一致（固定列或 custom.xxx）
+        metric: String,
+        operator: ThresholdOperator,
+        threshold: f64,
+        #[serde(default)]
+        for_seconds: i64,
+    },
+    /// 实例离线（超过 for_minutes 分钟未上报）
+    InstanceOffline { for_minutes: i64 },
+    /// 最近 window_minutes 分钟内结束的任务执行记录失败率（失败与超时）达到阈值
+    TaskFailureRate {
+        window_minutes: i64,
+        threshold_percent: f64,
+        /// 样本不足时不告警
+        #[serde(default = "default_min_records")]
+        min_records: i64,
+    },
+}
+
+fn default_min_records() -> i64 {
+    5
+}
+
+// ===================================================================
+// 通知渠道
+// ===================================================================
+
+/// 创建通知渠道请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertChannelCreateRequest {
+    pub name: String,
+    pub channel_type: AlertChannelType,
+    /// 渠道配置：
+    /// webhook `{url, headers?, secret?}`；
+    /// email `{smtp_host, smtp_port?, security?(none|starttls|tls), username?, password?, from, to[]}`；
+    /// dingtalk / wecom `{webhook_url, secret?}`
+    pub config: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+/// 更新通知渠道请求（敏感字段与敏感请求头传掩码值时保留原值）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertChannelUpdateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+/// 通知渠道响应（密码、签名密钥等已掩码）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertChannelResponse {
+    pub id: String,
+    pub name: String,
+    pub channel_type: AlertChannelType,
+    pub config: JsonValue,
+    pub enabled: bool,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 通知渠道列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertChannelListResponse {
+    pub data: Vec<AlertChannelResponse>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 测试通知结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertChannelTestResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+impl AlertChannelResponse {
+    pub fn from_entity(entity: alert_channels::Model) -> Self {
+        Self {
+            config: ChannelConfig::mask(&entity.config),
+            id: entity.id,
+            name: entity.name,
+            channel_type: entity.channel_type,
+            enabled: entity.enabled,
+            created_by: entity.created_by,
+            created_at: entity.created_at.to_rfc3339(),
+            updated_at: entity.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+// ===================================================================
+// 告警规则
+// ===================================================================
+
+/// 创建告警规则请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertRuleCreateRequest {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<AlertSeverity>,
+    /// 限定应用，为空表示全部应用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    /// 限定实例，为空表示应用下全部实例
+    #[serde(default)]
+    pub instance_ids: Vec<String>,
+    pub condition: AlertCondition,
+    #[serde(default)]
+    pub channel_ids: Vec<String>,
+    /// 持续告警的重复通知间隔（分钟），默认 0 不重复
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_interval_minutes: Option<i32>,
+    /// 恢复时是否通知，默认 true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_resolved: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+/// 更新告警规则请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertRuleUpdateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<AlertSeverity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<AlertCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_interval_minutes: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_resolved: Option<bool>,
+}
+
+/// 告警规则响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertRuleResponse {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub rule_type: AlertRuleType,
+    pub severity: AlertSeverity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    pub instance_ids: JsonValue,
+    pub condition: JsonValue,
+    pub channel_ids: JsonValue,
+    pub repeat_interval_minutes: i32,
+    pub notify_resolved: bool,
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_evaluated_at: Option<String>,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 告警规则列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertRuleListResponse {
+    pub data: Vec<AlertRuleResponse>,
+    pub pagination: Pagination,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 告警规则列表查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlertRuleListQuery {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub rule_type: Option<AlertRuleType>,
+    pub application_id: Option<String>,
+    pub enabled: Option<bool>,
+    pub search: Option<String>,
+}
+
+impl AlertRuleResponse {
+    pub fn from_entity(entity: alert_rules::Model) -> Self {
+        Self {
+            id: entity.id,
+            name: entity.name,
+            description: entity.description,
+            rule_type: entity.rule_type,
+            severity: entity.severity,
+            application_id: entity.application_id,
+            instance_ids: entity.instance_ids,
+            condition: entity.condition,
+            channel_ids: entity.channel_ids,
+            repeat_interval_minutes: entity.repeat_interval_minutes,
+            notify_resolved: entity.notify_resolved,
+            enabled: entity.enabled,
+            last_evaluated_at: entity.last_evaluated_at.map(|t| t.to_rfc3339()),
+            created_by: entity.created_by,
+            created_at: entity.created_at.to_rfc3339(),
+            updated_at: entity.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+// ===================================================================
+// 告警事件
+// ===================================================================
+
+/// 告警事件响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertEventResponse {
+    pub id: String,
+    pub rule_id: String,
+    pub fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    pub status: AlertStatus,
+    pub severity: AlertSeverity,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fired_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acknowledged_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acknowledged_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_notified_at: Option<String>,
+    pub notify_count: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_error: Option<String>,
+}
+
+/// 告警事件列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertEventListResponse {
+    pub data: Vec<AlertEventResponse>,
+    pub pagination: Pagination,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 告警事件列表查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlertEventListQuery {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub status: Option<AlertStatus>,
+    pub severity: Option<AlertSeverity>,
+    pub rule_id: Option<String>,
+    pub instance_id: Option<String>,
+    pub application_id: Option<String>,
+}
+
+impl AlertEventResponse {
+    pub fn from_entity(entity: alert_events::Model) -> Self {
+        Self {
+            id: entity.id,
+            rule_id: entity.rule_id,
+            fingerprint: entity.fingerprint,
+            instance_id: entity.instance_id,
+            application_id: entity.application_id,
+            status: entity.status,
+            severity: entity.severity,
+            summary: entity.summary,
+            value: entity.value,
+            started_at: entity.started_at.to_rfc3339(),
+            fired_at: entity.fired_at.map(|t| t.to_rfc3339()),
+            resolved_at: entity.resolved_at.map(|t| t.to_rfc3339()),
+            acknowledged_by: entity.acknowledged_by,
+            acknowledged_at: entity.acknowledged_at.map(|t| t.to_rfc3339()),
+            last_notified_at: entity.last_notified_at.map(|t| t.to_rfc3339()),
+            notify_count: entity.notify_count,
+            notify_error: entity.notify_error,
+        }
+    }
+}
+
+// ===================================================================
+// 静默
+// ===================================================================
+
+/// 创建静默请求，rule_id 与 instance_id 为空时匹配全部
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertSilenceCreateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// 开始时间（RFC3339），默认当前时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starts_at: Option<String>,
+    /// 结束时间（RFC3339），与 duration_minutes 二选一
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<i64>,
+}
+
+/// 静默响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertSilenceResponse {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+/// 静默列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertSilenceListResponse {
+    pub data: Vec<AlertSilenceResponse>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 静默列表查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AlertSilenceListQuery {
+    /// 仅返回未过期的静默，默认 true
+    pub active: Option<bool>,
+}
+
+impl AlertSilenceResponse {
+    pub fn from_entity(entity: alert_silences::Model) -> Self {
+        Self {
+            id: entity.id,
+            rule_id: entity.rule_id,
+            instance_id: entity.instance_id,
+            comment: entity.comment,
+            starts_at: entity.starts_at.to_rfc3339(),
+            ends_at: entity.ends_at.to_rfc3339(),
+            created_by: entity.created_by,
+            created_at: entity.created_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,639 @@
+/// 告警通知渠道
+///
+/// 支持通用 webhook（可选 HMAC 签名）、SMTP 邮件、钉钉与企业微信群机器人。
+/// 渠道地址均可配置为任意 http(s)/SMTP 服务器，便于对接内网网关或本地模拟服务。
+use crate::entities::{alert_channels, alert_events, alert_rules};
+use crate::shared::enums::{AlertChannelType, AlertStatus};
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// 响应中敏感字段的掩码值，更新时传回该值表示保留原值
+pub const SECRET_MASK: &str = "******";
+/// 渠道配置中的敏感字段
+const SECRET_FIELDS: &[&str] = &["password", "secret"];
+/// webhook 请求头名称包含这些词（不区分大小写）时视为敏感，如 Authorization、X-Api-Token
+const SECRET_HEADER_WORDS: &[&str] = &["auth", "token", "secret", "key", "password", "cookie"];
+/// 单次发送超时
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(SEND_TIMEOUT)
+        .build()
+        .expect("failed to build alert http client")
+});
+
+/// 通用 webhook 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 配置后附加 X-Monihub-Timestamp 与 X-Monihub-Signature（HMAC-SHA256）请求头
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    None,
+    #[default]
+    Starttls,
+    Tls,
+}
+
+/// 邮件配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// 钉钉/企业微信群机器人配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobotConfig {
+    pub webhook_url: String,
+    /// 钉钉加签密钥（企业微信不使用）
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// 已校验的渠道配置
+#[derive(Debug, Clone)]
+pub enum ChannelConfig {
+    Webhook(WebhookConfig),
+    Email(EmailConfig),
+    Dingtalk(RobotConfig),
+    Wecom(RobotConfig),
+}
+
+fn check_http_url(field: &str, url: &str) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(u) if u.scheme() == "http" || u.scheme() == "https" => Ok(()),
+        _ => Err(format!("config.{} must be an http(s) url", field)),
+    }
+}
+
+impl ChannelConfig {
+    /// 解析并校验渠道配置
+    pub fn parse(channel_type: &AlertChannelType, config: &JsonValue) -> Result<Self, String> {
+        let invalid = |e: serde_json::Error| format!("Invalid channel config: {}", e);
+        let parsed = match channel_type {
+            AlertChannelType::Webhook => {
+                ChannelConfig::Webhook(serde_json::from_value(config.clone()).map_err(invalid)?)
+            }
+            AlertChannelType::Email => {
+                ChannelConfig::Email(serde_json::from_value(config.clone()).map_err(invalid)?)
+            }
+            AlertChannelType::Dingtalk => {
+                ChannelConfig::Dingtalk(serde_json::from_value(config.clone()).map_err(invalid)?)
+            }
+            AlertChannelType::Wecom => {
+                ChannelConfig::Wecom(serde_json::from_value(config.clone()).map_err(invalid)?)
+            }
+        };
+        match &parsed {
+            ChannelConfig::Webhook(cfg) => check_http_url("url", &cfg.url)?,
+            ChannelConfig::Dingtalk(cfg) | ChannelConfig::Wecom(cfg) => {
+                check_http_url("webhook_url", &cfg.webhook_url)?
+            }
+            ChannelConfig::Email(cfg) => {
+                if cfg.smtp_host.trim().is_empty() {
+                    return Err("config.smtp_host is required".to_string());
+                }
+                cfg.from
+                    .parse::<Mailbox>()
+                    .map_err(|e| format!("config.from is invalid: {}", e))?;
+                if cfg.to.is_empty() {
+                    return Err("config.to requires at least one recipient".to_string());
+                }
+                for to in &cfg.to {
+                    to.parse::<Mailbox>()
+                        .map_err(|e| format!("config.to '{}' is invalid: {}", to, e))?;
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// 掩码敏感字段与敏感请求头，用于接口返回
+    pub fn mask(config: &JsonValue) -> JsonValue {
+        let mut masked = config.clone();
+        if let Some(obj) = masked.as_object_mut() {
+            for field in SECRET_FIELDS {
+                if let Some(v) = obj.get_mut(*field) {
+                    if v.as_str().is_some_and(|s| !s.is_empty()) {
+                        *v = json!(SECRET_MASK);
+                    }
+                }
+            }
+            if let Some(headers) = obj.get_mut("headers").and_then(|h| h.as_object_mut()) {
+                for (name, v) in headers.iter_mut() {
+                    if is_secret_header(name) && v.as_str().is_some_and(|s| !s.is_empty()) {
+                        *v = json!(SECRET_MASK);
+                    }
+                }
+            }
+        }
+        masked
+    }
+
+    /// 更新时将仍为掩码值的敏感字段与请求头恢复为原值
+    pub fn restore_secrets(config: &mut JsonValue, previous: &JsonValue) {
+        let Some(obj) = config.as_object_mut() else {
+            return;
+        };
+        for field in SECRET_FIELDS {
+            if obj.get(*field).and_then(|v| v.as_str()) == Some(SECRET_MASK) {
+                match previous.get(*field) {
+                    Some(old) => obj.insert(field.to_string(), old.clone()),
+                    None => obj.remove(*field),
+                };
+            }
+        }
+        if let Some(headers) = obj.get_mut("headers").and_then(|h| h.as_object_mut()) {
+            let masked: Vec<String> = headers
+                .iter()
+                .filter(|(_, v)| v.as_str() == Some(SECRET_MASK))
+                .map(|(name, _)| name.clone())
+                .collect();
+            for name in masked {
+                match previous.get("headers").and_then(|h| h.get(&name)) {
+                    Some(old) => headers.insert(name, old.clone()),
+                    None => headers.remove(&name),
+                };
+            }
+        }
+    }
+}
+
+/// 请求头是否携带凭据
+fn is_secret_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_HEADER_WORDS.iter().any(|w| name.contains(w))
+}
+
+/// 待发送的通知内容
+#[derive(Debug, Clone)]
+pub struct AlertNotification {
+    pub title: String,
+    /// Markdown 正文（邮件按纯文本发送）
+    pub text: String,
+    /// webhook 请求体
+    pub payload: JsonValue,
+}
+
+impl AlertNotification {
+    /// 告警触发或恢复通知
+    pub fn for_event(rule: &alert_rules::Model, event: &alert_events::Model) -> Self {
+        let resolved = event.status == AlertStatus::Resolved;
+        let state = if resolved { "RESOLVED" } else { "FIRING" };
+        let severity = json!(event.severity);
+        let severity = severity.as_str().unwrap_or_default();
+        let title = format!("[{}][{}] {}", state, severity, rule.name);
+
+        let mut lines = vec![
+            format!("### {}", title),
+            String::new(),
+            event.summary.clone(),
+        ];
+        lines.push(String::new());
+        if let Some(instance_id) = &event.instance_id {
+            lines.push(format!("- 实例: {}", instance_id));
+        }
+        if let Some(application_id) = &event.application_id {
+            lines.push(format!("- 应用: {}", application_id));
+        }
+        lines.push(format!("- 开始时间: {}", event.started_at.to_rfc3339()));
+        if let Some(resolved_at) = event.resolved_at {
+            lines.push(format!("- 恢复时间: {}", resolved_at.to_rfc3339()));
+        }
+        lines.push(format!("- 事件ID: {}", event.id));
+
+        Self {
+            title,
+            text: lines.join("\n"),
+            payload: json!({
+                "status": event.status,
+                "event_id": event.id,
+                "rule_id": rule.id,
+                "rule_name": rule.name,
+                "rule_type": rule.rule_type,
+                "severity": event.severity,
+                "instance_id": event.instance_id,
+                "application_id": event.application_id,
+                "summary": event.summary,
+                "value": event.value,
+                "started_at": event.started_at.to_rfc3339(),
+                "fired_at": event.fired_at.map(|t| t.to_rfc3339()),
+                "resolved_at": event.resolved_at.map(|t| t.to_rfc3339()),
+            }),
+        }
+    }
+
+    /// 渠道测试通知
+    pub fn test(channel_name: &str) -> Self {
+        let title = "[TEST] MoniHub 告警通知测试".to_string();
+        let text = format!(
+            "### {}\n\n通知渠道「{}」配置正确。\n\n- 发送时间: {}",
+            title,
+            channel_name,
+            Utc::now().to_rfc3339()
+        );
+        Self {
+            payload: json!({
+                "status": "test",
+                "summary": format!("通知渠道「{}」测试", channel_name),
+                "sent_at": Utc::now().to_rfc3339(),
+            }),
+            title,
+            text,
+        }
+    }
+}
+
+/// 通过渠道发送通知
+pub async fn send(
+    channel: &alert_channels::Model,
+    notification: &AlertNotification,
+) -> Result<(), String> {
+    let config = ChannelConfig::parse(&channel.channel_type, &channel.config)?;
+    deliver(&config, notification).await
+}
+
+/// 按渠道配置发送
+pub async fn deliver(
+    config: &ChannelConfig,
+    notification: &AlertNotification,
+) -> Result<(), String> {
+    match config {
+        ChannelConfig::Webhook(cfg) => send_webhook(cfg, notification).await,
+        ChannelConfig::Email(cfg) => send_email(cfg, notification).await,
+        ChannelConfig::Dingtalk(cfg) => {
+            let url = match cfg.secret.as_deref().filter(|s| !s.is_empty()) {
+                Some(secret) => {
+                    dingtalk_signed_url(&cfg.webhook_url, secret, Utc::now().timestamp_millis())
+                }
+                None => cfg.webhook_url.clone(),
+            };
+            let body = json!({
+                "msgtype": "markdown",
+                "markdown": { "title": notification.title, "text": notification.text },
+            });
+            send_robot(&url, &body).await
+        }
+        ChannelConfig::Wecom(cfg) => {
+            let body = json!({
+                "msgtype": "markdown",
+                "markdown": { "content": notification.text },
+            });
+            send_robot(&cfg.webhook_url, &body).await
+        }
+    }
+}
+
+fn hmac_sha256(secret: &str, message: &str) -> Vec<u8> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+async fn send_webhook(cfg: &WebhookConfig, notification: &AlertNotification) -> Result<(), String> {
+    let body = serde_json::to_string(&notification.payload).map_err(|e| e.to_string())?;
+    let mut request = HTTP_CLIENT
+        .post(&cfg.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    for (name, value) in &cfg.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    if let Some(secret) = cfg.secret.as_deref().filter(|s| !s.is_empty()) {
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature: String = hmac_sha256(secret, &format!("{}.{}", timestamp, body))
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        request = request
+            .header("X-Monihub-Timestamp", timestamp)
+            .header("X-Monihub-Signature", format!("sha256={}", signature));
+    }
+    let resp = request.body(body).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("webhook responded with status {}", resp.status()));
+    }
+    Ok(())
+}
+
+/// 钉钉加签：timestamp + "\n" + secret 的 HMAC-SHA256，Base64 后 URL 编码
+fn dingtalk_signed_url(webhook_url: &str, secret: &str, timestamp_ms: i64) -> String {
+    let sign = base64::engine::general_purpose::STANDARD.encode(hmac_sha256(
+        secret,
+        &format!("{}\n{}", timestamp_ms, secret),
+    ));
+    let sign = sign
+        .replace('+', "%2B")
+        .replace('/', "%2F")
+        .replace('=', "%3D");
+    let sep = if webhook_url.contains('?') { '&' } else { '?' };
+    format!(
+        "{}{}timestamp={}&sign={}",
+        webhook_url, sep, timestamp_ms, sign
+    )
+}
+
+/// 机器人接口返回 HTTP 200，通过 errcode 表示失败
+async fn send_robot(url: &str, body: &JsonValue) -> Result<(), String> {
+    let resp = HTTP_CLIENT
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("robot responded with status {}", status));
+    }
+    let result: JsonValue = resp.json().await.map_err(|e| e.to_string())?;
+    match result.get("errcode").and_then(|c| c.as_i64()) {
+        Some(0) | None => Ok(()),
+        Some(code) => Err(format!(
+            "robot responded with errcode {}: {}",
+            code,
+            result.get("errmsg").and_then(|m| m.as_str()).unwrap_or("")
+        )),
+    }
+}
+
+async fn send_email(cfg: &EmailConfig, notification: &AlertNotification) -> Result<(), String> {
+    let builder = match cfg.security {
+        SmtpSecurity::None => {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.smtp_host)
+        }
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.smtp_host)
+                .map_err(|e| e.to_string())?
+        }
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&cfg.smtp_host)
+            .map_err(|e| e.to_string())?,
+    };
+    let default_port = match cfg.security {
+        SmtpSecurity::None => 25,
+        SmtpSecurity::Starttls => 587,
+        SmtpSecurity::Tls => 465,
+    };
+    let mut builder = builder
+        .port(cfg.smtp_port.unwrap_or(default_port))
+        .timeout(Some(SEND_TIMEOUT));
+    if let (Some(username), Some(password)) = (&cfg.username, &cfg.password) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    let mailer = builder.build();
+
+    let mut message = Message::builder()
+        .from(cfg.from.parse::<Mailbox>().map_err(|e| e.to_string())?)
+        .subject(notification.title.clone());
+    for to in &cfg.to {
+        message = message.to(to.parse::<Mailbox>().map_err(|e| e.to_string())?);
+    }
+    let email = message
+        .header(ContentType::TEXT_PLAIN)
+        .body(notification.text.clone())
+        .map_err(|e| e.to_string())?;
+    mailer.send(email).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// 本地模拟 HTTP 服务：接收一个请求并返回指定响应体，输出原始请求
+    async fn mock_http(response_body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = v.trim().parse().unwrap();
+                }
+                head.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response_body.len(),
+                response_body
+            );
+            reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+            head + &String::from_utf8(body).unwrap()
+        });
+        (format!("http://{}", addr), handle)
+    }
+
+    /// 本地模拟 SMTP 服务：接收一封邮件，输出 DATA 内容
+    async fn mock_smtp() -> (u16, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            reader
+                .get_mut()
+                .write_all(b"220 mock ESMTP\r\n")
+                .await
+                .unwrap();
+            let mut data = String::new();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                if in_data {
+                    if line == ".\r\n" {
+                        in_data = false;
+                        reader.get_mut().write_all(b"250 queued\r\n").await.unwrap();
+                    } else {
+                        data.push_str(&line);
+                    }
+                    continue;
+                }
+                let reply: &[u8] = match line.get(..4).unwrap_or("").to_ascii_uppercase().as_str() {
+                    "EHLO" => b"250 mock\r\n",
+                    "DATA" => {
+                        in_data = true;
+                        b"354 end with .\r\n"
+                    }
+                    "QUIT" => {
+                        reader.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => b"250 OK\r\n",
+                };
+                reader.get_mut().write_all(reply).await.unwrap();
+            }
+            data
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_parse_and_mask_config() {
+        let config = json!({"webhook_url": "https://oapi.dingtalk.com/robot/send?access_token=x", "secret": "SEC1"});
+        assert!(ChannelConfig::parse(&AlertChannelType::Dingtalk, &config).is_ok());
+        assert!(ChannelConfig::parse(&AlertChannelType::Webhook, &config).is_err());
+        assert!(ChannelConfig::parse(
+            &AlertChannelType::Webhook,
+            &json!({"url": "ftp://example.com"})
+        )
+        .is_err());
+        assert!(ChannelConfig::parse(
+            &AlertChannelType::Email,
+            &json!({"smtp_host": "smtp.example.com", "from": "bad", "to": ["ops@example.com"]})
+        )
+        .is_err());
+
+        let masked = ChannelConfig::mask(&config);
+        assert_eq!(masked["secret"], SECRET_MASK);
+        let mut updated = masked.clone();
+        ChannelConfig::restore_secrets(&mut updated, &config);
+        assert_eq!(updated, config);
+    }
+
+    #[test]
+    fn test_mask_webhook_headers() {
+        let config = json!({
+            "url": "http://hook",
+            "headers": {
+                "Authorization": "Bearer abc",
+                "X-Api-Token": "t",
+                "X-Env": "prod",
+            },
+        });
+        let masked = ChannelConfig::mask(&config);
+        assert_eq!(masked["headers"]["Authorization"], SECRET_MASK);
+        assert_eq!(masked["headers"]["X-Api-Token"], SECRET_MASK);
+        assert_eq!(masked["headers"]["X-Env"], "prod");
+
+        // 传回掩码值的请求头保留原值，新值与新增请求头照常保存
+        let mut updated = masked.clone();
+        updated["headers"]["X-Api-Token"] = json!("t2");
+        updated["headers"]["X-Trace-Key"] = json!(SECRET_MASK);
+        ChannelConfig::restore_secrets(&mut updated, &config);
+        assert_eq!(
+            updated["headers"],
+            json!({"Authorization": "Bearer abc", "X-Api-Token": "t2", "X-Env": "prod"})
+        );
+    }
+
+    #[test]
+    fn test_dingtalk_signed_url() {
+        let url = dingtalk_signed_url("http://robot/send?access_token=t", "secret", 1700000000000);
+        assert!(url.starts_with("http://robot/send?access_token=t&timestamp=1700000000000&sign="));
+        let sign = url.rsplit("sign=").next().unwrap();
+        assert!(!sign.contains('+') && !sign.contains('/') && !sign.contains('='));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_with_signature() {
+        let (url, server) = mock_http("{}").await;
+        let config = ChannelConfig::Webhook(WebhookConfig {
+            url: format!("{}/hook", url),
+            headers: BTreeMap::from([("X-Env".to_string(), "test".to_string())]),
+            secret: Some("s3cret".to_string()),
+        });
+        deliver(&config, &AlertNotification::test("webhook"))
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        let lower = request.to_ascii_lowercase();
+        assert!(lower.contains("x-env: test"));
+        assert!(lower.contains("x-monihub-signature: sha256="));
+        assert!(request.contains("\"status\":\"test\""));
+    }
+
+    #[tokio::test]
+    async fn test_robot_errcode() {
+        let (url, server) = mock_http(r#"{"errcode":0,"errmsg":"ok"}"#).await;
+        let config = ChannelConfig::Dingtalk(RobotConfig {
+            webhook_url: format!("{}/robot/send?access_token=t", url),
+            secret: Some("SEC".to_string()),
+        });
+        deliver(&config, &AlertNotification::test("dingtalk"))
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.contains("&timestamp="));
+        assert!(request.contains("\"msgtype\":\"markdown\""));
+
+        let (url, server) = mock_http(r#"{"errcode":93000,"errmsg":"invalid webhook url"}"#).await;
+        let config = ChannelConfig::Wecom(RobotConfig {
+            webhook_url: format!("{}/cgi-bin/webhook/send?key=k", url),
+            secret: None,
+        });
+        let err = deliver(&config, &AlertNotification::test("wecom"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("93000"));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_email_via_mock_smtp() {
+        let (port, server) = mock_smtp().await;
+        let config = ChannelConfig::Email(EmailConfig {
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: Some(port),
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "MoniHub <alert@example.com>".to_string(),
+            to: vec!["ops@example.com".to_string()],
+        });
+        deliver(&config, &AlertNotification::test("email"))
+            .await
+            .unwrap();
+        let data = server.await.unwrap();
+        assert!(data.contains("To: ops@example.com"));
+        assert!(data.contains("Subject:"));
+    }
+}
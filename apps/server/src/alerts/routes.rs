@@ -0,0 +1,73 @@
+use super::handlers;
+use actix_web::web;
+
+pub fn alert_routes(cfg: &mut web::ServiceConfig) {
+    // 告警管理（需要认证）
+    cfg.route(
+        "/alerts/channels",
+        web::get().to(handlers::get_alert_channels),
+    )
+    .route(
+        "/alerts/channels",
+        web::post().to(handlers::create_alert_channel),
+    )
+    .route(
+        "/alerts/channels/{channel_id}",
+        web::get().to(handlers::get_alert_channel),
+    )
+    .route(
+        "/alerts/channels/{channel_id}",
+        web::put().to(handlers::update_alert_channel),
+    )
+    .route(
+        "/alerts/channels/{channel_id}",
+        web::delete().to(handlers::delete_alert_channel),
+    )
+    .route(
+        "/alerts/channels/{channel_id}/test",
+        web::post().to(handlers::test_alert_channel),
+    )
+    .route("/alerts/rules", web::get().to(handlers::get_alert_rules))
+    .route("/alerts/rules", web::post().to(handlers::create_alert_rule))
+    .route(
+        "/alerts/rules/{rule_id}",
+        web::get().to(handlers::get_alert_rule),
+    )
+    .route(
+        "/alerts/rules/{rule_id}",
+        web::put().to(handlers::update_alert_rule),
+    )
+    .route(
+        "/alerts/rules/{rule_id}",
+        web::delete().to(handlers::delete_alert_rule),
+    )
+    .route(
+        "/alerts/rules/{rule_id}/enable",
+        web::post().to(handlers::enable_alert_rule),
+    )
+    .route(
+        "/alerts/rules/{rule_id}/disable",
+        web::post().to(handlers::disable_alert_rule),
+    )
+    .route("/alerts/events", web::get().to(handlers::get_alert_events))
+    .route(
+        "/alerts/events/{event_id}",
+        web::get().to(handlers::get_alert_event),
+    )
+    .route(
+        "/alerts/events/{event_id}/ack",
+        web::post().to(handlers::acknowledge_alert_event),
+    )
+    .route(
+        "/alerts/silences",
+        web::get().to(handlers::get_alert_silences),
+    )
+    .route(
+        "/alerts/silences",
+        web::post().to(handlers::create_alert_silence),
+    )
+    .route(
+        "/alerts/silences/{silence_id}",
+        web::delete().to(handlers::expire_alert_silence),
+    );
+}
@@ -0,0 +1,356 @@
+/// 告警条件校验与状态流转
+///
+/// 评估器每轮为规则范围内的每个对象（实例或规则整体）产出一个观测值，
+/// 再按 无事件 → pending → firing → resolved 的状态机推进；pending 恢复时直接丢弃，不产生通知。
+use crate::alerts::models::{AlertCondition, ThresholdOperator};
+use crate::entities::{alert_events, alert_silences};
+use crate::instances::metrics;
+use crate::shared::enums::{AlertRuleType, AlertStatus};
+use crate::shared::error::ApiError;
+use chrono::{DateTime, Duration, Utc};
+
+/// 指标阈值最长持续时间（秒）
+const MAX_FOR_SECONDS: i64 = 24 * 3600;
+/// 离线判定最长时长（分钟）
+const MAX_OFFLINE_MINUTES: i64 = 7 * 24 * 60;
+/// 失败率统计最长窗口（分钟）
+const MAX_WINDOW_MINUTES: i64 = 24 * 60;
+
+impl ThresholdOperator {
+    pub fn compare(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            ThresholdOperator::Gt => value > threshold,
+            ThresholdOperator::Gte => value >= threshold,
+            ThresholdOperator::Lt => value < threshold,
+            ThresholdOperator::Lte => value <= threshold,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ThresholdOperator::Gt => ">",
+            ThresholdOperator::Gte => ">=",
+            ThresholdOperator::Lt => "<",
+            ThresholdOperator::Lte => "<=",
+        }
+    }
+}
+
+impl AlertCondition {
+    pub fn rule_type(&self) -> AlertRuleType {
+        match self {
+            AlertCondition::MetricThreshold { .. } => AlertRuleType::MetricThreshold,
+            AlertCondition::InstanceOffline { .. } => AlertRuleType::InstanceOffline,
+            AlertCondition::TaskFailureRate { .. } => AlertRuleType::TaskFailureRate,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ApiError> {
+        match self {
+            AlertCondition::MetricThreshold {
+                metric,
+                threshold,
+                for_seconds,
+                ..
+            } => {
+                if metric.contains(',') {
+                    return Err(ApiError::ValidationError(
+                        "condition.metric must be a single metric".to_string(),
+                    ));
+                }
+                metrics::parse_metrics(Some(metric))?;
+                if !threshold.is_finite() {
+                    return Err(ApiError::ValidationError(
+                        "condition.threshold must be a finite number".to_string(),
+                    ));
+                }
+                if !(0..=MAX_FOR_SECONDS).contains(for_seconds) {
+                    return Err(ApiError::ValidationError(format!(
+                        "condition.for_seconds must be between 0 and {}",
+                        MAX_FOR_SECONDS
+                    )));
+                }
+            }
+            AlertCondition::InstanceOffline { for_minutes } => {
+                if !(1..=MAX_OFFLINE_MINUTES).contains(for_minutes) {
+                    return Err(ApiError::ValidationError(format!(
+                        "condition.for_minutes must be between 1 and {}",
+                        MAX_OFFLINE_MINUTES
+                    )));
+                }
+            }
+            AlertCondition::TaskFailureRate {
+                window_minutes,
+                threshold_percent,
+                min_records,
+            } => {
+                if !(1..=MAX_WINDOW_MINUTES).contains(window_minutes) {
+                    return Err(ApiError::ValidationError(format!(
+                        "condition.window_minutes must be between 1 and {}",
+                        MAX_WINDOW_MINUTES
+                    )));
+                }
+                if !(*threshold_percent > 0.0 && *threshold_percent <= 100.0) {
+                    return Err(ApiError::ValidationError(
+                        "condition.threshold_percent must be between 0 and 100".to_string(),
+                    ));
+                }
+                if *min_records < 1 {
+                    return Err(ApiError::ValidationError(
+                        "condition.min_records must be at least 1".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 条件满足后进入 firing 前需持续的时间
+    pub fn pending_duration(&self) -> Duration {
+        match self {
+            AlertCondition::MetricThreshold { for_seconds, .. } => Duration::seconds(*for_seconds),
+            _ => Duration::zero(),
+        }
+    }
+}
+
+/// 单个告警对象的本轮观测结果
+#[derive(Clone, Debug)]
+pub struct Observation {
+    /// 告警对象标识：实例ID，或规则整体范围
+    pub fingerprint: String,
+    pub instance_id: Option<String>,
+    pub application_id: Option<String>,
+    pub value: Option<f64>,
+    /// 是否满足告警条件，None 表示无数据（保持原状态）
+    pub breaching: Option<bool>,
+    pub summary: String,
+}
+
+/// 状态流转结果
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transition {
+    /// 状态不变
+    Keep,
+    /// 新建事件
+    Open(AlertStatus),
+    /// pending 持续足够时间，进入 firing
+    Fire,
+    /// firing 条件不再满足
+    Resolve,
+    /// pending 期间恢复，丢弃事件
+    Discard,
+}
+
+/// 根据当前未恢复事件与观测结果计算状态流转
+pub fn transition(
+    current: Option<&alert_events::Model>,
+    breaching: Option<bool>,
+    now: DateTime<Utc>,
+    pending_for: Duration,
+) -> Transition {
+    let Some(breaching) = breaching else {
+        return Transition::Keep;
+    };
+    match (current.map(|e| &e.status), breaching) {
+        (None, false) => Transition::Keep,
+        (None, true) if pending_for > Duration::zero() => Transition::Open(AlertStatus::Pending),
+        (None, true) => Transition::Open(AlertStatus::Firing),
+        (Some(AlertStatus::Pending), true) => {
+            let started = current.map(|e| e.started_at.with_timezone(&Utc));
+            if started.is_some_and(|s| now - s >= pending_for) {
+                Transition::Fire
+            } else {
+                Transition::Keep
+            }
+        }
+        (Some(AlertStatus::Pending), false) => Transition::Discard,
+        (Some(AlertStatus::Firing), false) => Transition::Resolve,
+        (Some(_), _) => Transition::Keep,
+    }
+}
+
+/// 持续告警是否需要（再次）通知：未确认，且从未通知或已超过重复间隔
+pub fn notify_due(
+    event: &alert_events::Model,
+    repeat_interval_minutes: i32,
+    now: DateTime<Utc>,
+) -> bool {
+    if event.status != AlertStatus::Firing || event.acknowledged_at.is_some() {
+        return false;
+    }
+    match event.last_notified_at {
+        None => true,
+        Some(last) => {
+            repeat_interval_minutes > 0
+                && now - last.with_timezone(&Utc)
+                    >= Duration::minutes(repeat_interval_minutes as i64)
+        }
+    }
+}
+
+/// 静默是否作用于指定规则与实例
+pub fn silence_matches(
+    silence: &alert_silences::Model,
+    rule_id: &str,
+    instance_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> bool {
+    silence.starts_at.with_timezone(&Utc) <= now
+        && now < silence.ends_at.with_timezone(&Utc)
+        && silence.rule_id.as_deref().is_none_or(|id| id == rule_id)
+        && silence
+            .instance_id
+            .as_deref()
+            .is_none_or(|id| Some(id) == instance_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::enums::AlertSeverity;
+
+    fn event(status: AlertStatus, started_at: DateTime<Utc>) -> alert_events::Model {
+        alert_events::Model {
+            id: "e1".to_string(),
+            rule_id: "r1".to_string(),
+            fingerprint: "i1".to_string(),
+            instance_id: Some("i1".to_string()),
+            application_id: None,
+            status,
+            severity: AlertSeverity::Warning,
+            summary: String::new(),
+            value: None,
+            started_at: started_at.into(),
+            fired_at: None,
+            resolved_at: None,
+            last_value_at: started_at.into(),
+            acknowledged_by: None,
+            acknowledged_at: None,
+            last_notified_at: None,
+            notify_count: 0,
+            notify_error: None,
+            created_at: started_at.into(),
+            updated_at: started_at.into(),
+        }
+    }
+
+    #[test]
+    fn test_transition_with_pending_duration() {
+        let now = Utc::now();
+        let wait = Duration::seconds(300);
+        assert_eq!(transition(None, Some(false), now, wait), Transition::Keep);
+        assert_eq!(
+            transition(None, Some(true), now, wait),
+            Transition::Open(AlertStatus::Pending)
+        );
+        assert_eq!(
+            transition(None, Some(true), now, Duration::zero()),
+            Transition::Open(AlertStatus::Firing)
+        );
+
+        let pending = event(AlertStatus::Pending, now - Duration::seconds(120));
+        assert_eq!(
+            transition(Some(&pending), Some(true), now, wait),
+            Transition::Keep
+        );
+        assert_eq!(
+            transition(Some(&pending), Some(false), now, wait),
+            Transition::Discard
+        );
+        let pending = event(AlertStatus::Pending, now - Duration::seconds(300));
+        assert_eq!(
+            transition(Some(&pending), Some(true), now, wait),
+            Transition::Fire
+        );
+
+        let firing = event(AlertStatus::Firing, now - Duration::hours(1));
+        assert_eq!(
+            transition(Some(&firing), Some(true), now, wait),
+            Transition::Keep
+        );
+        assert_eq!(transition(Some(&firing), None, now, wait), Transition::Keep);
+        assert_eq!(
+            transition(Some(&firing), Some(false), now, wait),
+            Transition::Resolve
+        );
+    }
+
+    #[test]
+    fn test_notify_due() {
+        let now = Utc::now();
+        let mut firing = event(AlertStatus::Firing, now - Duration::hours(1));
+        assert!(notify_due(&firing, 0, now));
+        firing.last_notified_at = Some((now - Duration::minutes(10)).into());
+        assert!(!notify_due(&firing, 0, now));
+        assert!(!notify_due(&firing, 30, now));
+        assert!(notify_due(&firing, 10, now));
+        firing.acknowledged_at = Some(now.into());
+        assert!(!notify_due(&firing, 10, now));
+    }
+
+    #[test]
+    fn test_silence_matches() {
+        let now = Utc::now();
+        let mut silence = alert_silences::Model {
+            id: "s1".to_string(),
+            rule_id: None,
+            instance_id: Some("i1".to_string()),
+            comment: None,
+            starts_at: (now - Duration::minutes(5)).into(),
+            ends_at: (now + Duration::minutes(5)).into(),
+            created_by: "u1".to_string(),
+            created_at: now.into(),
+        };
+        assert!(silence_matches(&silence, "r1", Some("i1"), now));
+        assert!(!silence_matches(&silence, "r1", Some("i2"), now));
+        assert!(!silence_matches(&silence, "r1", None, now));
+        silence.rule_id = Some("r2".to_string());
+        assert!(!silence_matches(&silence, "r1", Some("i1"), now));
+        silence.rule_id = None;
+        assert!(!silence_matches(
+            &silence,
+            "r1",
+            Some("i1"),
+            now + Duration::minutes(6)
+        ));
+    }
+
+    #[test]
+    fn test_condition_validation() {
+        let condition: AlertCondition = serde_json::from_value(serde_json::json!({
+            "type": "metric_threshold",
+            "metric": "disk_usage_percent",
+            "operator": "gte",
+            "threshold": 90,
+            "for_seconds": 300
+        }))
+        .unwrap();
+        assert_eq!(condition.rule_type(), AlertRuleType::MetricThreshold);
+        assert!(condition.validate().is_ok());
+        assert_eq!(condition.pending_duration(), Duration::seconds(300));
+
+        let condition = AlertCondition::MetricThreshold {
+            metric: "unknown".to_string(),
+            operator: ThresholdOperator::Gt,
+            threshold: 1.0,
+            for_seconds: 0,
+        };
+        assert!(condition.validate().is_err());
+
+        let condition: AlertCondition = serde_json::from_value(serde_json::json!({
+            "type": "task_failure_rate",
+            "window_minutes": 30,
+            "threshold_percent": 20
+        }))
+        .unwrap();
+        assert!(condition.validate().is_ok());
+        assert!(matches!(
+            condition,
+            AlertCondition::TaskFailureRate { min_records: 5, .. }
+        ));
+        assert!(AlertCondition::InstanceOffline { for_minutes: 0 }
+            .validate()
+            .is_err());
+    }
+}
@@ -5,8 +5,10 @@ use crate::applications::models::{
 use crate::applications::ApplicationsModule;
 use crate::auth::middleware::get_user_id_from_request;
 use crate::entities::applications::Entity as Applications;
+use crate::members::handlers::add_creator_membership;
+use crate::members::request_scope;
 use crate::permissions::handlers::get_user_permission_by_name;
-use crate::shared::enums::Status;
+use crate::shared::enums::{MemberResourceType, Status};
 use crate::shared::error::db_error_here_with_context;
 use crate::shared::error::ApiError;
 use crate::shared::snowflake::generate_snowflake_id;
@@ -40,6 +42,7 @@ pub async fn get_applications(
 ) -> Result<HttpResponse, ApiError> {
     // 从JWT中获取用户ID
     let _user_id = get_user_id_from_request(&req)?;
+    let scope = request_scope(&db, &req).await?;
 
     // 分页参数处理
     let page = query.page.unwrap_or(1);
@@ -49,6 +52,11 @@ pub async fn get_applications(
     let mut select =
         Applications::find().filter(crate::entities::applications::Column::DeletedAt.is_null());
 
+    // 数据范围：仅返回可访问的应用
+    if let Some(application_ids) = scope.application_filter() {
+        select = select.filter(crate::entities::applications::Column::Id.is_in(application_ids));
+    }
+
     // 搜索过滤：名称、编码、描述（任意匹配）
     if let Some(search) = &query.search {
         let search_pattern = format!("%{}%", search);
@@ -164,6 +172,11 @@ pub async fn create_application(
         ));
     }
 
+    // 只能在可访问的项目下创建应用
+    request_scope(&db, &req)
+        .await?
+        .ensure_project(&app.project_id)?;
+
     // 创建ApplicationsModule实例
     let applications_module = ApplicationsModule::new(db.get_ref().clone());
 
@@ -207,6 +220,7 @@ pub async fn create_application(
                 .unwrap_or(serde_json::Value::Null),
         ),
         revision: ActiveValue::Set(1),
+        agent_target_version: ActiveValue::Set(None),
         deleted_at: ActiveValue::Set(None),
         created_by: ActiveValue::Set(user_id.to_string()),
         updated_by: ActiveValue::Set(user_id.to_string()),
@@ -215,6 +229,14 @@ pub async fn create_application(
     };
 
     let created_app = applications_module.create_application(new_app).await?;
+    // 创建者自动成为应用成员
+    add_creator_membership(
+        db.get_ref(),
+        MemberResourceType::Application,
+        &created_app.id,
+        &user_id,
+    )
+    .await?;
 
     // 转换为响应格式
     let response = ApplicationResponse {
@@ -286,7 +308,11 @@ pub async fn get_application(
     // 查询数据库获取应用信息
     if let Some(application) = applications_module.find_application_by_id(&app_id).await? {
         // 检查用户是否有访问权限
-        if application.created_by != user_id.to_string() {
+        if application.created_by != user_id.to_string()
+            && !request_scope(&db, &req)
+                .await?
+                .allows_application(&application.id)
+        {
             return Err(ApiError::Unauthorized(
                 "You do not have permission to access this application".to_string(),
             ));
@@ -353,7 +379,11 @@ pub async fn update_application(
     // 查询数据库获取应用信息
     if let Some(application) = applications_module.find_application_by_id(&app_id).await? {
         // 检查用户是否有更新权限
-        if application.created_by != user_id.to_string() {
+        if application.created_by != user_id.to_string()
+            && !request_scope(&db, &req)
+                .await?
+                .allows_application(&application.id)
+        {
             return Err(ApiError::Unauthorized(
                 "You do not have permission to update this application".to_string(),
             ));
@@ -477,7 +507,11 @@ pub async fn delete_application(
     // 查询数据库获取应用信息
     if let Some(application) = applications_module.find_application_by_id(&app_id).await? {
         // 检查用户是否有删除权限
-        if application.created_by != user_id.to_string() {
+        if application.created_by != user_id.to_string()
+            && !request_scope(&db, &req)
+                .await?
+                .allows_application(&application.id)
+        {
             return Err(ApiError::Unauthorized(
                 "You do not have permission to delete this application".to_string(),
             ));
@@ -537,6 +571,9 @@ pub async fn enable_application(
     let module = ApplicationsModule::new(db.get_ref().clone());
 
     if let Some(app) = module.find_application_by_id(&app_id).await? {
+        request_scope(&db, &req)
+            .await?
+            .ensure_application(&app.id)?;
         let mut active: crate::entities::applications::ActiveModel = app.into();
         active.status = ActiveValue::Set(Status::Active);
         active.updated_by = ActiveValue::Set(user_id.to_string());
@@ -595,6 +632,9 @@ pub async fn disable_application(
     let module = ApplicationsModule::new(db.get_ref().clone());
 
     if let Some(app) = module.find_application_by_id(&app_id).await? {
+        request_scope(&db, &req)
+            .await?
+            .ensure_application(&app.id)?;
         let mut active: crate::entities::applications::ActiveModel = app.into();
         active.status = ActiveValue::Set(Status::Disabled);
         active.updated_by = ActiveValue::Set(user_id.to_string());
@@ -266,6 +266,7 @@ pub async fn record_audit_log(
         log_type: Set(crate::shared::enums::LogType::Operation),
         timestamp: Set(Utc::now().into()),
         created_at: Set(Utc::now().into()),
+        client_log_id: Set(None),
     };
 
     active.insert(db).await.map(|_| ())
@@ -2,10 +2,11 @@ use actix_web::http::Method;
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
     error::ErrorUnauthorized,
-    Error, HttpMessage, HttpRequest,
+    web, Error, HttpMessage, HttpRequest,
 };
 use futures_util::future::{ready, LocalBoxFuture, Ready};
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use std::collections::HashMap;
 use std::env;
 use std::rc::Rc;
 
@@ -17,6 +18,25 @@ fn get_jwt_secret() -> String {
     env::var("JWT_SECRET").unwrap_or_else(|_| "aione_monihub_secret_key".to_string())
 }
 
+// 允许的公开路径（不需要认证）
+const PUBLIC_PATHS: [&str; 8] = [
+    "/api/auth/login",
+    "/api/auth/forgot-password",
+    "/api/auth/reset-password",
+    "/api/open",  // 所有开放 API
+    "/api/files", // 文件管理相关api 路由这里放开权限，但是api内部需要根据参数进行鉴权
+    "/health",
+    "/swagger-ui",
+    "/api-docs",
+];
+
+/// 是否是无需登录即可访问的公开路径
+pub fn is_public_path(path: &str) -> bool {
+    PUBLIC_PATHS
+        .iter()
+        .any(|public_path| path.starts_with(public_path))
+}
+
 pub struct AuthMiddleware;
 
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
@@ -64,52 +84,42 @@ where
             }
 
             let path = req.path();
-
-            // 允许的公开路径（不需要认证）
-            let public_paths = vec![
-                "/api/auth/login",
-                "/api/auth/forgot-password",
-                "/api/auth/reset-password",
-                "/api/open",  // 所有开放 API
-                "/api/files", // 文件管理相关api 路由这里放开权限，但是api内部需要根据参数进行鉴权
-                "/health",
-                "/swagger-ui",
-                "/api-docs",
-            ];
+            let header_token = req
+                .headers()
+                .get("Authorization")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|s| s.strip_prefix("Bearer ")) // Remove "Bearer " prefix
+                .map(|s| s.to_string());
 
             // 检查是否是公开路径
-            let is_public = public_paths
-                .iter()
-                .any(|public_path| path.starts_with(public_path));
-
-            if is_public {
-                // 公开路径，直接放行
+            if is_public_path(path) {
+                // 公开路径直接放行；携带有效token时附加用户信息，供接口内部按数据范围鉴权
+                if let Some(claims) = header_token.as_deref().and_then(|t| decode_claims(t).ok()) {
+                    req.extensions_mut().insert(claims);
+                }
                 return service.call(req).await;
             }
 
             // 需要认证的路径，检查JWT token
-            if let Some(auth_header) = req.headers().get("Authorization") {
-                if let Ok(auth_str) = auth_header.to_str() {
-                    if auth_str.starts_with("Bearer ") {
-                        let token = &auth_str[7..]; // Remove "Bearer " prefix
-
-                        // 验证token
-                        let validation = Validation::new(Algorithm::HS256);
-                        let jwt_secret = get_jwt_secret();
-                        match decode::<Claims>(
-                            token,
-                            &DecodingKey::from_secret(jwt_secret.as_ref()),
-                            &validation,
-                        ) {
-                            Ok(token_data) => {
-                                // Token有效，将用户信息添加到请求扩展中
-                                req.extensions_mut().insert(token_data.claims);
-                                return service.call(req).await;
-                            }
-                            Err(err) => {
-                                log::warn!("Invalid JWT token: {:?}", err);
-                            }
-                        }
+            // 浏览器 WebSocket 无法设置请求头，允许通过 ?token= 传递
+            let token = header_token.or_else(|| {
+                if path.starts_with("/api/websocket/") {
+                    get_query_token(req.query_string())
+                } else {
+                    None
+                }
+            });
+
+            if let Some(token) = token {
+                // 验证token
+                match decode_claims(&token) {
+                    Ok(claims) => {
+                        // Token有效，将用户信息添加到请求扩展中
+                        req.extensions_mut().insert(claims);
+                        return service.call(req).await;
+                    }
+                    Err(err) => {
+                        log::warn!("Invalid JWT token: {:?}", err);
                     }
                 }
             }
@@ -120,6 +130,26 @@ where
     }
 }
 
+/// 校验并解析JWT token
+fn decode_claims(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let validation = Validation::new(Algorithm::HS256);
+    let jwt_secret = get_jwt_secret();
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_ref()),
+        &validation,
+    )
+    .map(|token_data| token_data.claims)
+}
+
+/// 从查询字符串中读取 token 参数
+fn get_query_token(query: &str) -> Option<String> {
+    web::Query::<HashMap<String, String>>::from_query(query)
+        .ok()
+        .and_then(|q| q.get("token").cloned())
+        .filter(|t| !t.is_empty())
+}
+
 /// 从HTTP请求中获取当前登录用户的ID
 pub fn get_user_id_from_request(req: &HttpRequest) -> Result<String, ApiError> {
     let extensions = req.extensions();
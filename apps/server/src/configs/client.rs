@@ -0,0 +1,514 @@
+/// 配置中心客户端接口（开放接口，使用实例令牌认证）
+///
+/// 应用通过 Agent 实例读取所属应用及全局共享配置的发布版本（草稿与待审核版本不可见）：
+/// - 读取接口返回 ETag（按版本与渲染后内容生成），携带 If-None-Match 或 revision 参数且未变化时返回 304；
+/// - watch 接口长轮询等待所关注配置的变化，超时仍无变化时返回空列表。
+///   覆盖层、基础配置与变量在读取时渲染，客户端提供 revision 时其中任一变化都会下发；
+///   只提供 version 的旧客户端仅感知覆盖层版本变化。
+///   渲染失败（如基础配置在该环境未发布）时不下发原始覆盖层：读取返回错误，watch 不报告该配置的变化。
+/// - 密钥配置仅对携带有效实例令牌的请求返回，匿名请求（应用未强制令牌时）读取返回 403，watch 不下发。
+use crate::agent_auth::verify_agent_token_for_instance;
+use crate::configs::models::{
+    ConfigClientQuery, ConfigClientResponse, ConfigWatchChange, ConfigWatchItem,
+    ConfigWatchRequest, ConfigWatchResponse,
+};
+use crate::configs::template::{load_variables, render};
+use crate::configs::watch::ConfigWatchHub;
+use crate::entities::configs::{Column, Entity as Configs, Model};
+use crate::entities::{config_releases, instances, ConfigReleases};
+use crate::shared::enums::Environment;
+use crate::shared::error::ApiError;
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// 单次 watch 最多关注的配置数
+const MAX_WATCH_CONFIGS: usize = 100;
+/// watch 默认与最大等待秒数
+const DEFAULT_WATCH_TIMEOUT_SECONDS: u64 = 30;
+const MAX_WATCH_TIMEOUT_SECONDS: u64 = 60;
+
+/// watch 的单个配置：(code, 环境, 客户端持有的版本, 客户端持有的修订)
+type WatchedConfig = (String, Environment, u32, Option<String>);
+
+/// 解析环境名称，兼容 development / production 写法
+pub fn parse_environment(environment: &str) -> Option<Environment> {
+    match environment.to_lowercase().as_str() {
+        "dev" | "development" => Some(Environment::Dev),
+        "test" => Some(Environment::Test),
+        "staging" => Some(Environment::Staging),
+        "prod" | "production" => Some(Environment::Prod),
+        _ => None,
+    }
+}
+
+pub fn environment_str(environment: &Environment) -> &'static str {
+    match environment {
+        Environment::Dev => "dev",
+        Environment::Test => "test",
+        Environment::Staging => "staging",
+        Environment::Prod => "prod",
+    }
+}
+
+/// 内容修订标识：版本与渲染后内容的摘要，基础配置或变量变化时随之变化
+pub fn config_revision(version: i32, content: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", version, content).as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+/// 内容修订标识对应的 ETag
+pub fn config_etag(revision: &str) -> String {
+    format!("\"{}\"", revision)
+}
+
+/// If-None-Match 是否命中当前 ETag（支持多个值、弱校验与 `*`）
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+fn client_response(config: Model) -> ConfigClientResponse {
+    ConfigClientResponse {
+        revision: config_revision(config.version, &config.content),
+        code: config.code,
+        environment: config.environment,
+        name: config.name,
+        config_type: config.config_type,
+        content: config.content,
+        version: config.version as u32,
+        application_id: config.application_id,
+        updated_at: config.updated_at.to_rfc3339(),
+    }
+}
+
+/// 查找实例并校验实例令牌，返回实例与是否携带了有效令牌
+///
+/// 应用未强制令牌时允许匿名访问，但匿名请求不能读取密钥配置明文。
+async fn find_agent_instance(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    agent_instance_id: &str,
+) -> Result<(instances::Model, bool), ApiError> {
+    let instance = instances::Entity::find()
+        .filter(instances::Column::AgentInstanceId.eq(agent_instance_id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Instance {} not found", agent_instance_id)))?;
+    let authenticated = verify_agent_token_for_instance(db, req, &instance)
+        .await?
+        .is_some();
+    Ok((instance, authenticated))
+}
+
+/// 查询应用可见的配置发布版本，按 (code, environment) 返回
+///
+/// 应用自身配置与全局共享配置共用发布指针，仅返回未删除且归属可见的发布版本。
+/// 本身或任一基础配置为密钥配置时 `is_secret` 置为 true，由调用方决定是否返回；
+/// 渲染失败的配置保留错误，由调用方决定如何处理。
+async fn find_current_configs(
+    db: &DatabaseConnection,
+    application_id: &str,
+    codes: Vec<String>,
+) -> Result<HashMap<(String, &'static str), Result<Model, ApiError>>, ApiError> {
+    let config_ids: Vec<String> = ConfigReleases::find()
+        .filter(config_releases::Column::Code.is_in(codes))
+        .filter(config_releases::Column::ConfigId.is_not_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|release| release.config_id)
+        .collect();
+    if config_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let configs = Configs::find()
+        .filter(Column::Id.is_in(config_ids))
+        .filter(Column::DeletedAt.is_null())
+        .filter(
+            Condition::any()
+                .add(Column::ApplicationId.eq(application_id))
+                .add(Column::ApplicationId.is_null()),
+        )
+        .all(db)
+        .await?;
+
+    // 覆盖层与变量在读取时渲染，修订标识由渲染结果计算
+    let variables = load_variables(db, Some(application_id)).await?;
+    let mut current = HashMap::new();
+    for mut config in configs {
+        let key = (config.code.clone(), environment_str(&config.environment));
+        let rendered = match render(db, config.clone(), &variables).await {
+            Ok(rendered) => {
+                config.is_secret |= rendered.layers.iter().any(|l| l.is_secret);
+                config.content = rendered.content;
+                Ok(config)
+            }
+            Err(e) => {
+                log::warn!("渲染配置 {} 失败: {:?}", config.code, e);
+                Err(e)
+            }
+        };
+        current.insert(key, rendered);
+    }
+    Ok(current)
+}
+
+/// 比对客户端持有的修订（未提供时比对版本），返回发生变化的配置；渲染失败的配置不报告变化
+fn collect_changes(
+    items: &[WatchedConfig],
+    mut current: HashMap<(String, &'static str), Model>,
+    failed: &HashSet<(String, &'static str)>,
+) -> Vec<ConfigWatchChange> {
+    let mut changes = Vec::new();
+    for (code, environment, known_version, known_revision) in items {
+        let environment = environment_str(environment);
+        if failed.contains(&(code.clone(), environment)) {
+            continue;
+        }
+        let config = current
+            .remove(&(code.clone(), environment))
+            .map(client_response);
+        let version = config.as_ref().map(|c| c.version).unwrap_or(0);
+        let revision = config.as_ref().map(|c| c.revision.clone());
+        let changed = match known_revision {
+            Some(known) => revision.as_ref() != Some(known),
+            None => version != *known_version,
+        };
+        if changed {
+            changes.push(ConfigWatchChange {
+                code: code.clone(),
+                environment: environment.to_string(),
+                version,
+                revision,
+                config,
+            });
+        }
+    }
+    changes
+}
+
+fn parse_watch_items(items: &[ConfigWatchItem]) -> Result<Vec<WatchedConfig>, ApiError> {
+    if items.is_empty() {
+        return Err(ApiError::ValidationError(
+            "configs must not be empty".to_string(),
+        ));
+    }
+    if items.len() > MAX_WATCH_CONFIGS {
+        return Err(ApiError::ValidationError(format!(
+            "Too many configs in one watch (max {})",
+            MAX_WATCH_CONFIGS
+        )));
+    }
+    let mut parsed: Vec<WatchedConfig> = Vec::with_capacity(items.len());
+    for item in items {
+        if item.code.is_empty() {
+            return Err(ApiError::ValidationError("Code is required".to_string()));
+        }
+        let environment = parse_environment(&item.environment).ok_or_else(|| {
+            ApiError::ValidationError(format!("Invalid environment: {}", item.environment))
+        })?;
+        if parsed
+            .iter()
+            .any(|(code, env, _, _)| code == &item.code && env == &environment)
+        {
+            continue;
+        }
+        let revision = item.revision.clone().filter(|r| !r.is_empty());
+        parsed.push((
+            item.code.clone(),
+            environment,
+            item.version.unwrap_or(0),
+            revision,
+        ));
+    }
+    Ok(parsed)
+}
+
+/// GET /api/open/instances/configs/{code}/{environment}
+/// 读取应用可见的配置发布版本
+#[utoipa::path(
+    get,
+    path = "/api/open/instances/configs/{code}/{environment}",
+    params(
+        ("code" = String, Path, description = "Config code"),
+        ("environment" = String, Path, description = "Environment name"),
+        ConfigClientQuery
+    ),
+    responses(
+        (status = 200, description = "Config found successfully", body = ConfigClientResponse),
+        (status = 304, description = "Config not modified"),
+        (status = 404, description = "Config not found")
+    ),
+    tag = "Configs"
+)]
+pub async fn get_client_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    query: web::Query<ConfigClientQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let (code, environment) = path.into_inner();
+    let environment = parse_environment(&environment)
+        .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))?;
+    let (instance, authenticated) =
+        find_agent_instance(&db, &req, &query.agent_instance_id).await?;
+
+    let config = find_current_configs(&db, &instance.application_id, vec![code.clone()])
+        .await?
+        .remove(&(code, environment_str(&environment)))
+        .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))??;
+    if config.is_secret && !authenticated {
+        return Err(ApiError::Forbidden(
+            "Secret config requires an agent token".to_string(),
+        ));
+    }
+
+    let response = client_response(config);
+    let etag = config_etag(&response.revision);
+    let not_modified = query.revision.as_deref() == Some(response.revision.as_str())
+        || req
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| etag_matches(v, &etag));
+    if not_modified {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish());
+    }
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .json(response))
+}
+
+/// POST /api/open/instances/configs/watch
+/// 长轮询等待所关注配置的版本变化
+#[utoipa::path(
+    post,
+    path = "/api/open/instances/configs/watch",
+    request_body = ConfigWatchRequest,
+    responses(
+        (status = 200, description = "Changed configs (empty when timed out)", body = ConfigWatchResponse),
+        (status = 400, description = "Bad request")
+    ),
+    tag = "Configs"
+)]
+pub async fn watch_client_configs(
+    db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
+    body: web::Json<ConfigWatchRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let items = parse_watch_items(&body.configs)?;
+    let (instance, authenticated) = find_agent_instance(&db, &req, &body.agent_instance_id).await?;
+    let timeout_secs = body
+        .timeout_seconds
+        .unwrap_or(DEFAULT_WATCH_TIMEOUT_SECONDS)
+        .min(MAX_WATCH_TIMEOUT_SECONDS);
+
+    let codes: Vec<String> = items.iter().map(|(code, _, _, _)| code.clone()).collect();
+    let changes = hub
+        .wait_for(Duration::from_secs(timeout_secs), || {
+            let codes = codes.clone();
+            let items = &items;
+            let db = &db;
+            let application_id = &instance.application_id;
+            async move {
+                let mut current = HashMap::new();
+                let mut failed = HashSet::new();
+                for (key, config) in find_current_configs(db, application_id, codes).await? {
+                    match config {
+                        // 未携带令牌时不下发密钥配置，客户端视为尚未发布
+                        Ok(config) if authenticated || !config.is_secret => {
+                            current.insert(key, config);
+                        }
+                        Ok(_) => {}
+                        Err(_) => {
+                            failed.insert(key);
+                        }
+                    }
+                }
+                Ok::<_, ApiError>(collect_changes(items, current, &failed))
+            }
+        })
+        .await?;
+
+    let response = ConfigWatchResponse {
+        changes,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(version: i32, content: &str) -> Model {
+        let now = chrono::Utc::now().into();
+        Model {
+            id: "1".to_string(),
+            code: "app".to_string(),
+            environment: Environment::Prod,
+            name: "app".to_string(),
+            config_type: crate::shared::enums::ConfigType::Text,
+            content: content.to_string(),
+            description: None,
+            version,
+            created_by: "u".to_string(),
+            updated_by: "u".to_string(),
+            deleted_at: None,
+            revision: 1,
+            created_at: now,
+            updated_at: now,
+            generate_values: false,
+            schema: None,
+            application_id: None,
+            status: crate::shared::enums::ConfigStatus::Published,
+            submitted_by: None,
+            submitted_at: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            review_comment: None,
+            source_config_id: None,
+            is_secret: false,
+            base_code: Some("base".to_string()),
+        }
+    }
+
+    fn current(config: Model) -> HashMap<(String, &'static str), Model> {
+        HashMap::from([((config.code.clone(), "prod"), config)])
+    }
+
+    #[test]
+    fn test_config_revision() {
+        let revision = config_revision(3, "a=1");
+        assert_eq!(revision.len(), 16);
+        assert_eq!(revision, config_revision(3, "a=1"));
+        // 覆盖层版本不变、渲染内容变化（基础配置或变量修改）时修订变化
+        assert_ne!(revision, config_revision(3, "a=2"));
+        assert_ne!(revision, config_revision(4, "a=1"));
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = config_etag("abc");
+        assert_eq!(etag, "\"abc\"");
+        assert!(etag_matches("\"abc\"", &etag));
+        assert!(etag_matches("W/\"abc\"", &etag));
+        assert!(etag_matches("\"v1\", \"abc\"", &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"abd\"", &etag));
+    }
+
+    #[test]
+    fn test_parse_watch_items() {
+        let items = vec![
+            ConfigWatchItem {
+                code: "app".to_string(),
+                environment: "production".to_string(),
+                version: Some(2),
+                revision: Some("r2".to_string()),
+            },
+            ConfigWatchItem {
+                code: "app".to_string(),
+                environment: "prod".to_string(),
+                version: Some(1),
+                revision: None,
+            },
+            ConfigWatchItem {
+                code: "feature".to_string(),
+                environment: "dev".to_string(),
+                version: None,
+                revision: Some(String::new()),
+            },
+        ];
+        let parsed = parse_watch_items(&items).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(
+            parsed[0],
+            (
+                "app".to_string(),
+                Environment::Prod,
+                2,
+                Some("r2".to_string())
+            )
+        );
+        assert_eq!(
+            parsed[1],
+            ("feature".to_string(), Environment::Dev, 0, None)
+        );
+
+        let invalid = vec![ConfigWatchItem {
+            code: "app".to_string(),
+            environment: "qa".to_string(),
+            version: None,
+            revision: None,
+        }];
+        assert!(parse_watch_items(&invalid).is_err());
+        assert!(parse_watch_items(&[]).is_err());
+    }
+
+    #[test]
+    fn test_collect_changes_reports_removed_config() {
+        let items = vec![
+            ("app".to_string(), Environment::Prod, 2, None),
+            ("new".to_string(), Environment::Prod, 0, None),
+        ];
+        let changes = collect_changes(&items, HashMap::new(), &HashSet::new());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].code, "app");
+        assert_eq!(changes[0].version, 0);
+        assert!(changes[0].revision.is_none());
+        assert!(changes[0].config.is_none());
+
+        let items = vec![(
+            "app".to_string(),
+            Environment::Prod,
+            2,
+            Some("r".to_string()),
+        )];
+        let changes = collect_changes(&items, HashMap::new(), &HashSet::new());
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].revision.is_none());
+    }
+
+    #[test]
+    fn test_collect_changes_by_revision() {
+        let known = config_revision(2, "base=1");
+        let items = vec![("app".to_string(), Environment::Prod, 2, Some(known.clone()))];
+        assert!(collect_changes(&items, current(config(2, "base=1")), &HashSet::new()).is_empty());
+
+        // 覆盖层版本未变，基础配置修改后渲染内容变化
+        let changes = collect_changes(&items, current(config(2, "base=2")), &HashSet::new());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].version, 2);
+        assert_eq!(
+            changes[0].revision.as_deref(),
+            Some(config_revision(2, "base=2").as_str())
+        );
+        assert_eq!(changes[0].config.as_ref().unwrap().content, "base=2");
+
+        // 未提供修订的旧客户端按版本比对
+        let legacy = vec![("app".to_string(), Environment::Prod, 2, None)];
+        assert!(collect_changes(&legacy, current(config(2, "base=2")), &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_collect_changes_skips_failed_render() {
+        let items = vec![("app".to_string(), Environment::Prod, 2, None)];
+        let failed = HashSet::from([("app".to_string(), "prod")]);
+        // 渲染失败不视为删除，客户端保留已有内容
+        assert!(collect_changes(&items, HashMap::new(), &failed).is_empty());
+    }
+}
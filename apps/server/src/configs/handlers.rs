@@ -1,20 +1,127 @@
+use crate::auth::middleware::get_user_id_from_request;
+use crate::configs::client::parse_environment;
 use crate::configs::models::{
     ConfigCreateRequest, ConfigListQuery, ConfigListResponse, ConfigResponse, ConfigUpdateRequest,
     Pagination,
 };
+use crate::configs::schema::{ensure_conforms, parse_schema};
+use crate::configs::secrets::{plain_content, seal_content, SECRET_MASK};
+use crate::configs::template::merge_with_base;
 use crate::configs::value_sync::sync_config_values;
+use crate::configs::watch::ConfigWatchHub;
 use crate::entities::configs::{ActiveModel, Column, Entity as Configs};
-use crate::shared::enums::{ConfigType, Environment};
+use crate::entities::{config_releases, ConfigReleases};
+use crate::members::{request_scope, DataScope};
+use crate::permissions::cache::get_user_permissions;
+use crate::shared::enums::{ConfigStatus, ConfigType, Environment};
 use crate::shared::error::ApiError;
 use crate::shared::generate_snowflake_id;
 use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, Order, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, Set,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, Order,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, Set,
 };
 use serde_json::Value;
 
+/// 数据范围过滤：可访问应用的配置与全局共享配置
+pub(crate) fn scoped(select: Select<Configs>, scope: &DataScope) -> Select<Configs> {
+    match scope.application_filter() {
+        Some(application_ids) => select.filter(
+            Condition::any()
+                .add(Column::ApplicationId.is_in(application_ids))
+                .add(Column::ApplicationId.is_null()),
+        ),
+        None => select,
+    }
+}
+
+/// 修改时请求未携带 schema 则沿用 (code, environment) 最新版本保存的 schema，省略字段不能绕过校验
+async fn update_schema(
+    db: &DatabaseConnection,
+    requested: Option<&str>,
+    code: &str,
+    environment: &str,
+) -> Result<Option<Value>, ApiError> {
+    if requested.is_some() {
+        return parse_schema(requested);
+    }
+    let environment = parse_environment(environment)
+        .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))?;
+    let latest = Configs::find()
+        .filter(Column::Code.eq(code))
+        .filter(Column::Environment.eq(environment))
+        .filter(Column::DeletedAt.is_null())
+        .order_by(Column::Version, Order::Desc)
+        .one(db)
+        .await?;
+    Ok(latest.and_then(|c| c.schema))
+}
+
+/// generate_values 的 array 配置每项必须是包含字符串字段 code 与 name 的对象
+fn ensure_value_items(items: &[Value]) -> Result<(), ApiError> {
+    for item in items {
+        let obj = item
+            .as_object()
+            .ok_or_else(|| ApiError::ValidationError("array 项必须是对象".to_string()))?;
+        if obj.get("code").and_then(|x| x.as_str()).is_none() {
+            return Err(ApiError::ValidationError(
+                "array 每项必须包含字符串字段 code".to_string(),
+            ));
+        }
+        if obj.get("name").and_then(|x| x.as_str()).is_none() {
+            return Err(ApiError::ValidationError(
+                "array 每项必须包含字符串字段 name".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 设置了基础配置时返回与基础配置合并后的内容，否则原样返回
+async fn merged_content(
+    db: &DatabaseConnection,
+    code: &str,
+    base_code: Option<&str>,
+    application_id: Option<&str>,
+    environment: &str,
+    config_type: &str,
+    content: Value,
+) -> Result<Value, ApiError> {
+    let Some(base_code) = base_code else {
+        return Ok(content);
+    };
+    let environment = parse_environment(environment)
+        .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))?;
+    let config_type = match config_type {
+        "object" => ConfigType::Object,
+        "array" => ConfigType::Array,
+        "html" => ConfigType::Html,
+        _ => ConfigType::Text,
+    };
+    merge_with_base(
+        db,
+        code,
+        base_code,
+        application_id,
+        &environment,
+        &config_type,
+        content,
+    )
+    .await
+}
+
+/// 写入校验：应用配置需应用在范围内，全局配置仅不受限用户可修改
+pub(crate) fn ensure_config_writable(
+    scope: &DataScope,
+    application_id: Option<&str>,
+) -> Result<(), ApiError> {
+    match application_id {
+        Some(application_id) => scope.ensure_application(application_id),
+        None => scope.ensure_unrestricted(),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/api/configs",
@@ -29,14 +136,21 @@ use serde_json::Value;
 pub async fn get_configs(
     query: web::Query<ConfigListQuery>,
     db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
+
     // 获取分页参数
     let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(10).max(1).min(100);
-    let _offset = (page - 1) * limit;
 
     // 构建查询
-    let mut query_builder = Configs::find();
+    let mut query_builder = scoped(Configs::find().filter(Column::DeletedAt.is_null()), &scope);
+
+    // 添加应用过滤
+    if let Some(application_id) = query.application_id.as_deref().filter(|id| !id.is_empty()) {
+        query_builder = query_builder.filter(Column::ApplicationId.eq(application_id));
+    }
 
     // 添加搜索条件
     if let Some(search) = &query.search {
@@ -64,17 +178,8 @@ pub async fn get_configs(
         query_builder = query_builder.order_by(Column::Version, Order::Desc);
     }
 
-    // 获取总数
-    let _total = query_builder
-        .clone()
-        .count(db.get_ref())
-        .await
-        .map_err(|e: sea_orm::DbErr| ApiError::DatabaseError(e.to_string()))?;
-
     // 获取数据
-    let paginator = Configs::find()
-        .filter(Column::DeletedAt.is_null())
-        .paginate(db.get_ref(), limit as u64);
+    let paginator = query_builder.paginate(db.get_ref(), limit as u64);
 
     let total = paginator
         .num_items()
@@ -86,26 +191,8 @@ pub async fn get_configs(
         .map_err(|e: sea_orm::DbErr| ApiError::DatabaseError(e.to_string()))?;
 
     // 转换为响应格式
-    let config_responses: Vec<ConfigResponse> = configs
-        .into_iter()
-        .map(|config| ConfigResponse {
-            id: config.id,
-            code: config.code,
-            environment: config.environment,
-            name: config.name,
-            config_type: config.config_type,
-            content: config.content,
-            description: config.description.unwrap_or_default(),
-            version: config.version as u32,
-            created_at: config.created_at.to_rfc3339(),
-            updated_at: config.updated_at.to_rfc3339(),
-            generate_values: config.generate_values,
-            schema: config
-                .schema
-                .as_ref()
-                .map(|v| serde_json::to_string(v).unwrap_or_default()),
-        })
-        .collect();
+    let config_responses: Vec<ConfigResponse> =
+        configs.into_iter().map(ConfigResponse::from).collect();
 
     let response = ConfigListResponse {
         data: config_responses,
@@ -138,6 +225,7 @@ pub async fn get_configs(
 pub async fn create_config(
     config: web::Json<ConfigCreateRequest>,
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
     req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     // 验证请求数据
@@ -163,7 +251,7 @@ pub async fn create_config(
 
     // 按类型校验内容
     let ct = config.config_type.to_lowercase();
-    match ct.as_str() {
+    let content = match ct.as_str() {
         "object" => {
             let v: Value = serde_json::from_str(&config.content).map_err(|e| {
                 ApiError::ValidationError(format!("Invalid JSON object content: {}", e))
@@ -173,6 +261,7 @@ pub async fn create_config(
                     "content 必须是 JSON 对象".to_string(),
                 ));
             }
+            v
         }
         "array" => {
             let v: Value = serde_json::from_str(&config.content).map_err(|e| {
@@ -183,29 +272,45 @@ pub async fn create_config(
                     "content 必须是 JSON 数组".to_string(),
                 ));
             }
-            if config.generate_values.unwrap_or(false) {
-                for item in v.as_array().unwrap() {
-                    let obj = item.as_object().ok_or_else(|| {
-                        ApiError::ValidationError("array 项必须是对象".to_string())
-                    })?;
-                    if obj.get("code").and_then(|x| x.as_str()).is_none() {
-                        return Err(ApiError::ValidationError(
-                            "array 每项必须包含字符串字段 code".to_string(),
-                        ));
-                    }
-                    if obj.get("name").and_then(|x| x.as_str()).is_none() {
-                        return Err(ApiError::ValidationError(
-                            "array 每项必须包含字符串字段 name".to_string(),
-                        ));
-                    }
-                }
-            }
+            v
         }
-        "html" | "text" => { /* 无结构校验 */ }
+        "html" | "text" => Value::String(config.content.clone()),
         _ => {
             return Err(ApiError::ValidationError("Invalid config_type".to_string()));
         }
+    };
+
+    // 覆盖层先与基础配置合并，再校验生效内容
+    let base_code = config.base_code.clone().filter(|c| !c.is_empty());
+    let merged = merged_content(
+        db.get_ref(),
+        &config.code,
+        base_code.as_deref(),
+        config.application_id.as_deref().filter(|id| !id.is_empty()),
+        &config.environment,
+        &ct,
+        content,
+    )
+    .await?;
+    let items = match &merged {
+        Value::Array(items) if config.generate_values.unwrap_or(false) => {
+            ensure_value_items(items)?;
+            Some(items.clone())
+        }
+        _ => None,
+    };
+
+    // 按 JSON Schema 校验内容
+    let schema = parse_schema(config.schema.as_deref())?;
+    if let Some(schema) = &schema {
+        ensure_conforms(schema, &merged)?;
     }
+    let is_secret = config.is_secret.unwrap_or(false);
+
+    // 数据范围校验
+    let application_id = config.application_id.clone().filter(|id| !id.is_empty());
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, application_id.as_deref())?;
 
     // 生成雪花ID
     let id = generate_snowflake_id();
@@ -250,7 +355,7 @@ pub async fn create_config(
                 return Err(ApiError::ValidationError("Invalid config_type".to_string()));
             }
         }),
-        content: Set(config.content.clone()),
+        content: Set(seal_content(is_secret, &config.content)?),
         description: Set(Some(config.description.clone())),
         version: Set(version),
         created_by: Set("system".to_string()), // 实际应该从认证信息中获取
@@ -260,15 +365,17 @@ pub async fn create_config(
         created_at: Set(Utc::now().into()),
         updated_at: Set(Utc::now().into()),
         generate_values: Set(config.generate_values.unwrap_or(false)),
-        schema: Set(match &config.schema {
-            Some(s) if !s.is_empty() => {
-                let v: Value = serde_json::from_str(s).map_err(|e| {
-                    ApiError::ValidationError(format!("Invalid schema JSON: {}", e))
-                })?;
-                Some(v)
-            }
-            _ => None,
-        }),
+        application_id: Set(application_id.clone()),
+        schema: Set(schema),
+        status: Set(ConfigStatus::Draft),
+        submitted_by: Set(None),
+        submitted_at: Set(None),
+        reviewed_by: Set(None),
+        reviewed_at: Set(None),
+        review_comment: Set(None),
+        source_config_id: Set(None),
+        is_secret: Set(is_secret),
+        base_code: Set(base_code),
     };
 
     // 保存到数据库
@@ -278,23 +385,7 @@ pub async fn create_config(
         .map_err(|e: sea_orm::DbErr| ApiError::DatabaseError(e.to_string()))?;
 
     // 转换为响应格式
-    let response = ConfigResponse {
-        id: saved_config.id,
-        code: saved_config.code,
-        environment: saved_config.environment,
-        name: saved_config.name,
-        config_type: saved_config.config_type,
-        content: saved_config.content,
-        description: saved_config.description.unwrap_or_default(),
-        version: saved_config.version as u32,
-        created_at: saved_config.created_at.to_rfc3339(),
-        updated_at: saved_config.updated_at.to_rfc3339(),
-        generate_values: saved_config.generate_values,
-        schema: saved_config
-            .schema
-            .as_ref()
-            .map(|v| serde_json::to_string(v).unwrap_or_default()),
-    };
+    let response = ConfigResponse::from(saved_config);
 
     // 审计记录：新增配置
     let after = serde_json::json!({
@@ -304,6 +395,8 @@ pub async fn create_config(
         "name": response.name,
         "config_type": response.config_type,
         "version": response.version,
+        "is_secret": response.is_secret,
+        "base_code": response.base_code,
         "created_at": response.created_at,
         "updated_at": response.updated_at,
     });
@@ -317,11 +410,8 @@ pub async fn create_config(
     )
     .await;
 
-    // 若需要生成选项值，执行同步
-    if ct == "array" && config.generate_values.unwrap_or(false) {
-        let parsed: Value = serde_json::from_str(&config.content)
-            .map_err(|e| ApiError::ValidationError(format!("Invalid JSON array content: {}", e)))?;
-        let items = parsed.as_array().unwrap().clone();
+    // 若需要生成选项值，按合并后的选项同步
+    if let Some(items) = items {
         sync_config_values(
             db.get_ref(),
             &response.id,
@@ -334,10 +424,13 @@ pub async fn create_config(
             },
             response.version as i32,
             &items,
+            is_secret,
         )
         .await?;
     }
 
+    // 可能作为其他配置的基础配置，唤醒 watch 重新比对渲染结果
+    hub.notify();
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -356,6 +449,7 @@ pub async fn update_config(
     id: web::Path<String>,
     config: web::Json<ConfigUpdateRequest>,
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
     req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     if config.code.is_empty()
@@ -366,8 +460,38 @@ pub async fn update_config(
         return Err(ApiError::ValidationError("必填字段缺失".to_string()));
     }
 
+    let existing = Configs::find_by_id(id.as_str())
+        .filter(Column::DeletedAt.is_null())
+        .one(db.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))?;
+
+    // 密钥配置以打码内容提交时沿用原内容，非密钥配置不接受打码占位内容
+    let mut config = config.into_inner();
+    if config.content == SECRET_MASK {
+        if !existing.is_secret {
+            return Err(ApiError::ValidationError(
+                "非密钥配置不能提交打码内容".to_string(),
+            ));
+        }
+        config.content = plain_content(&existing)?;
+    }
+
+    // 新版本默认沿用原版本的密钥属性；取消密钥等同于查看明文，需要 configs.reveal 权限
+    let is_secret = config.is_secret.unwrap_or(existing.is_secret);
+    let unsealed = existing.is_secret && !is_secret;
+    if unsealed {
+        let user_id = get_user_id_from_request(&req)?;
+        let permissions = get_user_permissions(db.get_ref(), &user_id).await?;
+        if !permissions.allows("configs.reveal") {
+            return Err(ApiError::Forbidden(
+                "取消密钥配置需要 configs.reveal 权限".to_string(),
+            ));
+        }
+    }
+
     let ct = config.config_type.to_lowercase();
-    match ct.as_str() {
+    let content = match ct.as_str() {
         "object" => {
             let v: Value = serde_json::from_str(&config.content).map_err(|e| {
                 ApiError::ValidationError(format!("Invalid JSON object content: {}", e))
@@ -377,6 +501,7 @@ pub async fn update_config(
                     "content 必须是 JSON 对象".to_string(),
                 ));
             }
+            v
         }
         "array" => {
             let v: Value = serde_json::from_str(&config.content).map_err(|e| {
@@ -387,30 +512,52 @@ pub async fn update_config(
                     "content 必须是 JSON 数组".to_string(),
                 ));
             }
-            if config.generate_values.unwrap_or(false) {
-                for item in v.as_array().unwrap() {
-                    let obj = item.as_object().ok_or_else(|| {
-                        ApiError::ValidationError("array 项必须是对象".to_string())
-                    })?;
-                    if obj.get("code").and_then(|x| x.as_str()).is_none() {
-                        return Err(ApiError::ValidationError(
-                            "array 每项必须包含字符串字段 code".to_string(),
-                        ));
-                    }
-                    if obj.get("name").and_then(|x| x.as_str()).is_none() {
-                        return Err(ApiError::ValidationError(
-                            "array 每项必须包含字符串字段 name".to_string(),
-                        ));
-                    }
-                }
-            }
+            v
         }
-        "html" | "text" => {}
+        "html" | "text" => Value::String(config.content.clone()),
         _ => {
             return Err(ApiError::ValidationError("Invalid config_type".to_string()));
         }
+    };
+
+    // 覆盖层先与基础配置合并，再校验生效内容
+    let base_code = config.base_code.clone().filter(|c| !c.is_empty());
+    let merged = merged_content(
+        db.get_ref(),
+        &config.code,
+        base_code.as_deref(),
+        config.application_id.as_deref().filter(|id| !id.is_empty()),
+        &config.environment,
+        &ct,
+        content,
+    )
+    .await?;
+    let items = match &merged {
+        Value::Array(items) if config.generate_values.unwrap_or(false) => {
+            ensure_value_items(items)?;
+            Some(items.clone())
+        }
+        _ => None,
+    };
+
+    // 按 JSON Schema 校验内容
+    let schema = update_schema(
+        db.get_ref(),
+        config.schema.as_deref(),
+        &config.code,
+        &config.environment,
+    )
+    .await?;
+    if let Some(schema) = &schema {
+        ensure_conforms(schema, &merged)?;
     }
 
+    // 数据范围校验：原配置与新版本的归属应用均需可写
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, existing.application_id.as_deref())?;
+    let application_id = config.application_id.clone().filter(|id| !id.is_empty());
+    ensure_config_writable(&scope, application_id.as_deref())?;
+
     // 计算新版本号
     let max_version_result = Configs::find()
         .filter(Column::Code.eq(&config.code))
@@ -451,7 +598,7 @@ pub async fn update_config(
                 return Err(ApiError::ValidationError("Invalid config_type".to_string()));
             }
         }),
-        content: Set(config.content.clone()),
+        content: Set(seal_content(is_secret, &config.content)?),
         description: Set(Some(config.description.clone())),
         version: Set(version),
         created_by: Set("system".to_string()),
@@ -461,15 +608,17 @@ pub async fn update_config(
         created_at: Set(Utc::now().into()),
         updated_at: Set(Utc::now().into()),
         generate_values: Set(config.generate_values.unwrap_or(false)),
-        schema: Set(match &config.schema {
-            Some(s) if !s.is_empty() => {
-                let v: Value = serde_json::from_str(s).map_err(|e| {
-                    ApiError::ValidationError(format!("Invalid schema JSON: {}", e))
-                })?;
-                Some(v)
-            }
-            _ => None,
-        }),
+        application_id: Set(application_id.clone()),
+        schema: Set(schema),
+        status: Set(ConfigStatus::Draft),
+        submitted_by: Set(None),
+        submitted_at: Set(None),
+        reviewed_by: Set(None),
+        reviewed_at: Set(None),
+        review_comment: Set(None),
+        source_config_id: Set(None),
+        is_secret: Set(is_secret),
+        base_code: Set(base_code),
     };
 
     let saved_config = config_model
@@ -477,23 +626,7 @@ pub async fn update_config(
         .await
         .map_err(|e: sea_orm::DbErr| ApiError::DatabaseError(e.to_string()))?;
 
-    let response = ConfigResponse {
-        id: saved_config.id.clone(),
-        code: saved_config.code.clone(),
-        environment: saved_config.environment,
-        name: saved_config.name.clone(),
-        config_type: saved_config.config_type,
-        content: saved_config.content.clone(),
-        description: saved_config.description.clone().unwrap_or_default(),
-        version: saved_config.version as u32,
-        created_at: saved_config.created_at.to_rfc3339(),
-        updated_at: saved_config.updated_at.to_rfc3339(),
-        generate_values: saved_config.generate_values,
-        schema: saved_config
-            .schema
-            .as_ref()
-            .map(|v| serde_json::to_string(v).unwrap_or_default()),
-    };
+    let response = ConfigResponse::from(saved_config);
 
     // 审计记录：更新配置（新版本）
     let after = serde_json::json!({
@@ -503,6 +636,8 @@ pub async fn update_config(
         "name": response.name,
         "config_type": response.config_type,
         "version": response.version,
+        "is_secret": response.is_secret,
+        "base_code": response.base_code,
         "created_at": response.created_at,
         "updated_at": response.updated_at,
     });
@@ -515,11 +650,28 @@ pub async fn update_config(
         Some(after),
     )
     .await;
+    if unsealed {
+        // 与查看明文相同，只记录行为不记录内容
+        let revealed = serde_json::json!({
+            "id": existing.id,
+            "code": existing.code,
+            "environment": existing.environment,
+            "version": existing.version,
+            "new_version": response.version,
+        });
+        let _ = crate::shared::request_context::record_audit_log_simple(
+            db.get_ref(),
+            "configs",
+            "reveal",
+            &req,
+            None,
+            Some(revealed),
+        )
+        .await;
+    }
 
-    if ct == "array" && config.generate_values.unwrap_or(false) {
-        let parsed: Value = serde_json::from_str(&config.content)
-            .map_err(|e| ApiError::ValidationError(format!("Invalid JSON array content: {}", e)))?;
-        let items = parsed.as_array().unwrap().clone();
+    // 按合并后的选项同步选项值
+    if let Some(items) = items {
         sync_config_values(
             db.get_ref(),
             &response.id,
@@ -532,10 +684,12 @@ pub async fn update_config(
             },
             response.version as i32,
             &items,
+            is_secret,
         )
         .await?;
     }
 
+    hub.notify();
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -555,11 +709,13 @@ pub async fn update_config(
 pub async fn get_config_by_code(
     path: web::Path<String>,
     db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let config_code = path.into_inner();
+    let scope = request_scope(&db, &req).await?;
 
     // 查询所有具有相同code的配置
-    let configs = Configs::find()
+    let configs = scoped(Configs::find(), &scope)
         .filter(Column::Code.eq(&config_code))
         .order_by(Column::CreatedAt, Order::Desc)
         .all(db.get_ref())
@@ -571,26 +727,8 @@ pub async fn get_config_by_code(
     }
 
     // 转换为响应格式
-    let config_responses: Vec<ConfigResponse> = configs
-        .into_iter()
-        .map(|config| ConfigResponse {
-            id: config.id,
-            code: config.code,
-            environment: config.environment,
-            name: config.name,
-            config_type: config.config_type,
-            content: config.content,
-            description: config.description.unwrap_or_default(),
-            version: config.version as u32,
-            created_at: config.created_at.to_rfc3339(),
-            updated_at: config.updated_at.to_rfc3339(),
-            generate_values: config.generate_values,
-            schema: config
-                .schema
-                .as_ref()
-                .map(|v| serde_json::to_string(v).unwrap_or_default()),
-        })
-        .collect();
+    let config_responses: Vec<ConfigResponse> =
+        configs.into_iter().map(ConfigResponse::from).collect();
 
     let config_count = config_responses.len() as u32;
 
@@ -628,11 +766,13 @@ pub async fn get_config_by_code(
 pub async fn get_config_by_code_and_environment(
     path: web::Path<(String, String)>,
     db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let (config_code, environment) = path.into_inner();
+    let scope = request_scope(&db, &req).await?;
 
     // 查询具有相同code和environment的最新版本配置
-    let config_result = Configs::find()
+    let config_result = scoped(Configs::find(), &scope)
         .filter(Column::Code.eq(&config_code))
         .filter(Column::Environment.eq(&environment))
         .order_by(Column::Version, Order::Desc)
@@ -646,23 +786,7 @@ pub async fn get_config_by_code_and_environment(
     };
 
     // 转换为响应格式
-    let response = ConfigResponse {
-        id: config.id,
-        code: config.code,
-        environment: config.environment,
-        name: config.name,
-        config_type: config.config_type,
-        content: config.content,
-        description: config.description.unwrap_or_default(),
-        version: config.version as u32,
-        created_at: config.created_at.to_rfc3339(),
-        updated_at: config.updated_at.to_rfc3339(),
-        generate_values: config.generate_values,
-        schema: config
-            .schema
-            .as_ref()
-            .map(|v| serde_json::to_string(v).unwrap_or_default()),
-    };
+    let response = ConfigResponse::from(config);
 
     Ok(HttpResponse::Ok().json(response))
 }
@@ -685,11 +809,13 @@ pub async fn get_config_by_code_and_environment(
 pub async fn get_config_by_code_env_and_version(
     path: web::Path<(String, String, u32)>,
     db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let (config_code, environment, version) = path.into_inner();
+    let scope = request_scope(&db, &req).await?;
 
     // 查询具有相同code、environment和version的配置
-    let config_result = Configs::find()
+    let config_result = scoped(Configs::find(), &scope)
         .filter(Column::Code.eq(&config_code))
         .filter(Column::Environment.eq(&environment))
         .filter(Column::Version.eq(version as i32))
@@ -703,23 +829,7 @@ pub async fn get_config_by_code_env_and_version(
     };
 
     // 转换为响应格式
-    let response = ConfigResponse {
-        id: config.id,
-        code: config.code,
-        environment: config.environment,
-        name: config.name,
-        config_type: config.config_type,
-        content: config.content,
-        description: config.description.unwrap_or_default(),
-        version: config.version as u32,
-        created_at: config.created_at.to_rfc3339(),
-        updated_at: config.updated_at.to_rfc3339(),
-        generate_values: config.generate_values,
-        schema: config
-            .schema
-            .as_ref()
-            .map(|v| serde_json::to_string(v).unwrap_or_default()),
-    };
+    let response = ConfigResponse::from(config);
 
     Ok(HttpResponse::Ok().json(response))
 }
@@ -740,6 +850,7 @@ pub async fn get_config_by_code_env_and_version(
 pub async fn delete_config(
     path: web::Path<String>,
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
     req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let config_id = path.into_inner();
@@ -755,6 +866,20 @@ pub async fn delete_config(
         Some(config) => config,
         None => return Err(ApiError::NotFound("Config not found".to_string())),
     };
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, config.application_id.as_deref())?;
+
+    // 当前发布的版本不可删除，需先发布或回滚到其他版本
+    let is_published = ConfigReleases::find()
+        .filter(config_releases::Column::ConfigId.eq(&config.id))
+        .one(db.get_ref())
+        .await?
+        .is_some();
+    if is_published {
+        return Err(ApiError::BadRequest(
+            "当前发布的版本不可删除，请先发布或回滚到其他版本".to_string(),
+        ));
+    }
 
     // 实现软删除：更新deleted_at字段和revision字段
     let config_before = config.clone();
@@ -789,5 +914,120 @@ pub async fn delete_config(
     )
     .await;
 
+    hub.notify();
     Ok(HttpResponse::Ok().json("Config deleted successfully"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::configs::Model;
+    use sea_orm::{Database, ModelTrait};
+    use serde_json::json;
+
+    /// 需要已执行迁移的测试库，未设置 TEST_DATABASE_URL 时跳过
+    async fn test_db() -> Option<DatabaseConnection> {
+        let url = std::env::var("TEST_DATABASE_URL").ok()?;
+        Some(Database::connect(url).await.unwrap())
+    }
+
+    async fn insert_version(
+        db: &DatabaseConnection,
+        code: &str,
+        version: i32,
+        schema: Option<Value>,
+    ) -> Model {
+        let now = Utc::now();
+        ActiveModel {
+            id: Set(generate_snowflake_id()),
+            code: Set(code.to_string()),
+            environment: Set(Environment::Prod),
+            name: Set("limits".to_string()),
+            config_type: Set(ConfigType::Object),
+            content: Set(r#"{"max":10}"#.to_string()),
+            description: Set(None),
+            version: Set(version),
+            created_by: Set("test".to_string()),
+            updated_by: Set("test".to_string()),
+            deleted_at: Set(None),
+            revision: Set(1),
+            created_at: Set(now.into()),
+            updated_at: Set(now.into()),
+            generate_values: Set(false),
+            schema: Set(schema),
+            application_id: Set(None),
+            status: Set(ConfigStatus::Published),
+            submitted_by: Set(None),
+            submitted_at: Set(None),
+            reviewed_by: Set(None),
+            reviewed_at: Set(None),
+            review_comment: Set(None),
+            source_config_id: Set(None),
+            is_secret: Set(false),
+            base_code: Set(None),
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    fn update_request(code: &str, content: &str, schema: Option<&str>) -> ConfigUpdateRequest {
+        ConfigUpdateRequest {
+            code: code.to_string(),
+            environment: "prod".to_string(),
+            name: "limits".to_string(),
+            config_type: "object".to_string(),
+            content: content.to_string(),
+            description: String::new(),
+            generate_values: None,
+            schema: schema.map(str::to_string),
+            application_id: None,
+            is_secret: None,
+            base_code: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_update_config_inherits_stored_schema() {
+        let Some(db) = test_db().await else {
+            return;
+        };
+        let code = format!("test.schema.{}", generate_snowflake_id());
+        let schema = json!({
+            "type": "object",
+            "properties": { "max": { "type": "integer" } },
+            "required": ["max"],
+        });
+        let v1 = insert_version(&db, &code, 1, Some(schema.clone())).await;
+        // 最新版本的 schema 生效，而不是被修改的版本
+        let v2 = insert_version(&db, &code, 2, Some(json!({ "type": "object" }))).await;
+        let v3 = insert_version(&db, &code, 3, Some(schema)).await;
+
+        // 请求省略 schema 时仍按已保存的 schema 校验
+        let result = update_config(
+            web::Path::from(v1.id.clone()),
+            web::Json(update_request(&code, r#"{"max":"ten"}"#, None)),
+            web::Data::new(db.clone()),
+            web::Data::new(ConfigWatchHub::new()),
+            actix_web::test::TestRequest::default().to_http_request(),
+        )
+        .await;
+        let message = match result {
+            Err(ApiError::ValidationError(message)) => message,
+            Err(e) => panic!("expected schema violation, got {}", e),
+            Ok(_) => panic!("expected schema violation"),
+        };
+        assert!(message.contains("$.max"), "{}", message);
+
+        // 显式传空 schema 表示清除，不再沿用
+        assert_eq!(
+            update_schema(&db, Some(""), &code, "prod").await.unwrap(),
+            None
+        );
+        assert!(update_schema(&db, None, &code, "unknown").await.is_err());
+
+        for model in [v1, v2, v3] {
+            model.delete(&db).await.unwrap();
+        }
+    }
+}
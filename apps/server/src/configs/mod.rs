@@ -1,7 +1,16 @@
+pub mod client;
 pub mod handlers;
 pub mod models;
+pub mod publish;
 pub mod routes;
+pub mod schema;
+pub mod secrets;
+pub mod template;
 pub mod value_sync;
+pub mod variables;
+pub mod watch;
+
+pub use watch::ConfigWatchHub;
 
 use crate::entities::{configs, Configs};
 use sea_orm::DatabaseConnection;
@@ -1,4 +1,6 @@
-use crate::shared::enums::{ConfigType, Environment};
+use crate::audit::models::ChangeEntry;
+use crate::entities::{config_variables, configs};
+use crate::shared::enums::{ConfigStatus, ConfigType, Environment};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
@@ -35,6 +37,52 @@ pub struct ConfigResponse {
     pub updated_at: String,
     pub generate_values: bool,
     pub schema: Option<String>,
+    /// 所属应用，为空表示全局共享配置
+    pub application_id: Option<String>,
+    pub status: ConfigStatus,
+    pub submitted_by: Option<String>,
+    pub reviewed_by: Option<String>,
+    pub review_comment: Option<String>,
+    /// 晋级来源版本ID
+    pub source_config_id: Option<String>,
+    /// 密钥配置，content 打码返回
+    pub is_secret: bool,
+    /// 基础配置代码，设置后本配置作为覆盖层
+    pub base_code: Option<String>,
+}
+
+impl From<configs::Model> for ConfigResponse {
+    fn from(config: configs::Model) -> Self {
+        Self {
+            id: config.id,
+            code: config.code,
+            environment: config.environment,
+            name: config.name,
+            config_type: config.config_type,
+            content: if config.is_secret {
+                crate::configs::secrets::SECRET_MASK.to_string()
+            } else {
+                config.content
+            },
+            description: config.description.unwrap_or_default(),
+            version: config.version as u32,
+            created_at: config.created_at.to_rfc3339(),
+            updated_at: config.updated_at.to_rfc3339(),
+            generate_values: config.generate_values,
+            schema: config
+                .schema
+                .as_ref()
+                .map(|v| serde_json::to_string(v).unwrap_or_default()),
+            application_id: config.application_id,
+            status: config.status,
+            submitted_by: config.submitted_by,
+            reviewed_by: config.reviewed_by,
+            review_comment: config.review_comment,
+            source_config_id: config.source_config_id,
+            is_secret: config.is_secret,
+            base_code: config.base_code,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -47,6 +95,15 @@ pub struct ConfigCreateRequest {
     pub description: String,
     pub generate_values: Option<bool>,
     pub schema: Option<String>,
+    /// 所属应用，为空表示全局共享配置
+    #[serde(default)]
+    pub application_id: Option<String>,
+    /// 密钥配置：内容加密存储，管理接口打码返回
+    #[serde(default)]
+    pub is_secret: Option<bool>,
+    /// 基础配置代码，设置后本配置作为覆盖层与基础配置合并
+    #[serde(default)]
+    pub base_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -59,6 +116,15 @@ pub struct ConfigUpdateRequest {
     pub description: String,
     pub generate_values: Option<bool>,
     pub schema: Option<String>,
+    /// 所属应用，为空表示全局共享配置
+    #[serde(default)]
+    pub application_id: Option<String>,
+    /// 密钥配置：内容加密存储，管理接口打码返回
+    #[serde(default)]
+    pub is_secret: Option<bool>,
+    /// 基础配置代码，设置后本配置作为覆盖层与基础配置合并
+    #[serde(default)]
+    pub base_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -85,4 +151,268 @@ pub struct ConfigListQuery {
     pub config_type: Option<String>,
     pub environment: Option<String>,
     pub all_versions: Option<bool>,
+    pub application_id: Option<String>,
+}
+
+/// 审批/驳回请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigReviewRequest {
+    pub comment: Option<String>,
+}
+
+/// 回滚请求，version 为空时回滚到上一个发布过的版本
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigRollbackRequest {
+    pub version: Option<u32>,
+}
+
+/// 晋级请求，target_environment 为空时晋级到下一环境（dev→test→staging→prod）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigPromoteRequest {
+    pub target_environment: Option<String>,
+}
+
+/// 发布策略更新请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigReleasePolicyRequest {
+    pub require_approval: bool,
+}
+
+/// 配置发布状态（每个 code + environment 一条）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigReleaseResponse {
+    pub code: String,
+    pub environment: Environment,
+    pub application_id: Option<String>,
+    /// 当前发布的版本ID，为空表示尚未发布
+    pub config_id: Option<String>,
+    pub version: Option<u32>,
+    pub require_approval: bool,
+    pub published_by: Option<String>,
+    pub published_at: Option<String>,
+}
+
+/// 版本对比查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConfigDiffQuery {
+    /// 基准版本ID
+    pub from_id: String,
+    /// 对比版本ID
+    pub to_id: String,
+}
+
+/// 参与对比的版本
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigDiffSide {
+    pub id: String,
+    pub environment: Environment,
+    pub version: u32,
+    pub status: ConfigStatus,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigDiffResponse {
+    pub code: String,
+    pub from: ConfigDiffSide,
+    pub to: ConfigDiffSide,
+    /// 字段级差异（path / type: added|removed|changed / before / after）
+    #[schema(value_type = Vec<Object>)]
+    pub changes: Vec<ChangeEntry>,
+}
+
+/// Schema 检查请求：schema 为空时按各环境最新版本的 schema 检查
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfigSchemaCheckRequest {
+    pub schema: Option<String>,
+    pub environment: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigSchemaViolation {
+    pub id: String,
+    pub environment: Environment,
+    pub version: u32,
+    pub status: ConfigStatus,
+    /// 字段路径与错误信息，如 `$.servers[0].port: ...`
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigSchemaCheckResponse {
+    pub code: String,
+    /// 参与检查的版本数（没有 schema 的环境不检查）
+    pub checked: u32,
+    pub invalid: Vec<ConfigSchemaViolation>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigSecretRotateResponse {
+    /// 当前主密钥ID
+    pub active_key_id: String,
+    pub rotated_configs: u32,
+    pub rotated_values: u32,
+}
+
+/// 渲染查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConfigRenderQuery {
+    /// 渲染的版本ID，为空时渲染发布版本（尚未发布则为最新版本）
+    pub config_id: Option<String>,
+    /// 全局配置按该应用的变量渲染
+    pub application_id: Option<String>,
+}
+
+/// 参与合并的配置层，第一层为最底层的基础配置，最后一层为配置本身
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigRenderLayer {
+    pub config_id: String,
+    pub code: String,
+    pub environment: Environment,
+    pub version: u32,
+    pub status: ConfigStatus,
+}
+
+/// 字段来源，path 形如 `db.host`、`[redis].port`，html/text 为 `$`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigFieldSource {
+    pub path: String,
+    /// 来源层在 layers 中的序号
+    pub layer: usize,
+    pub code: String,
+    pub version: u32,
+}
+
+/// 渲染时使用的变量
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigVariableUsage {
+    pub name: String,
+    pub value: String,
+    /// 来源：project、project:{env}、application、application:{env}
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigRenderResponse {
+    pub code: String,
+    pub environment: Environment,
+    pub config_type: ConfigType,
+    /// 生效内容，任一层为密钥配置时打码
+    pub content: String,
+    pub layers: Vec<ConfigRenderLayer>,
+    pub sources: Vec<ConfigFieldSource>,
+    pub variables: Vec<ConfigVariableUsage>,
+    /// 未定义的变量，占位符原样保留
+    pub unresolved: Vec<String>,
+}
+
+/// 变量列表查询参数，project_id 与 application_id 至少指定一个
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConfigVariableQuery {
+    pub project_id: Option<String>,
+    pub application_id: Option<String>,
+}
+
+/// 新增或更新变量（按所属 + 环境 + 名称唯一），project_id 与 application_id 必须且只能指定一个
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfigVariableUpsertRequest {
+    pub project_id: Option<String>,
+    pub application_id: Option<String>,
+    /// 为空表示所有环境
+    pub environment: Option<String>,
+    pub name: String,
+    pub value: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigVariableResponse {
+    pub id: String,
+    pub project_id: Option<String>,
+    pub application_id: Option<String>,
+    pub environment: Option<Environment>,
+    pub name: String,
+    pub value: String,
+    pub description: Option<String>,
+    pub updated_by: String,
+    pub updated_at: String,
+}
+
+impl From<config_variables::Model> for ConfigVariableResponse {
+    fn from(variable: config_variables::Model) -> Self {
+        Self {
+            id: variable.id,
+            project_id: variable.project_id,
+            application_id: variable.application_id,
+            environment: variable.environment,
+            name: variable.name,
+            value: variable.value,
+            description: variable.description,
+            updated_by: variable.updated_by,
+            updated_at: variable.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 客户端读取配置的查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConfigClientQuery {
+    /// Agent 实例ID（用于实例令牌校验与确定所属应用）
+    pub agent_instance_id: String,
+    /// 客户端当前持有的内容修订标识，与最新修订一致时返回 304
+    pub revision: Option<String>,
+}
+
+/// 客户端读取的配置（应用自身配置优先，其次为全局共享配置）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigClientResponse {
+    pub code: String,
+    pub environment: Environment,
+    pub name: String,
+    pub config_type: ConfigType,
+    pub content: String,
+    pub version: u32,
+    /// 内容修订标识（由版本与渲染后内容计算），基础配置或变量变化时随之变化
+    pub revision: String,
+    /// 所属应用，为空表示全局共享配置
+    pub application_id: Option<String>,
+    pub updated_at: String,
+}
+
+/// watch 的单个配置及客户端当前持有的版本
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigWatchItem {
+    pub code: String,
+    pub environment: String,
+    /// 客户端当前持有的版本，为空或 0 表示尚未持有
+    pub version: Option<u32>,
+    /// 客户端当前持有的内容修订标识，提供时按修订判断变化（可感知基础配置与变量的修改）
+    pub revision: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigWatchRequest {
+    pub agent_instance_id: String,
+    pub configs: Vec<ConfigWatchItem>,
+    /// 最长等待秒数，默认 30，最大 60
+    pub timeout_seconds: Option<u64>,
+}
+
+/// 版本发生变化的配置
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigWatchChange {
+    pub code: String,
+    pub environment: String,
+    /// 最新版本，配置已删除时为 0
+    pub version: u32,
+    /// 最新内容修订标识，配置已删除时为空
+    pub revision: Option<String>,
+    /// 最新配置，已删除时为空
+    pub config: Option<ConfigClientResponse>,
+}
+
+/// watch 结果，等待超时仍无变化时 changes 为空
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigWatchResponse {
+    pub changes: Vec<ConfigWatchChange>,
+    pub timestamp: u64,
 }
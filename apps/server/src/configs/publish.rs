@@ -0,0 +1,810 @@
+/// 配置发布流程
+///
+/// 配置的新增与修改只生成草稿版本，发布后由 config_releases 指向该版本，应用客户端读取的是发布版本：
+/// - 发布策略 require_approval 开启时，版本需提交审核，由拥有 configs.approve 权限的其他用户审批后才能发布；
+/// - 回滚重新指向发布过的旧版本，无需再次审批；
+/// - 晋级将版本复制为下一环境（dev→test→staging→prod）的草稿，按目标环境的发布策略发布。
+use crate::audit::diff::diff_values;
+use crate::auth::middleware::get_user_id_from_request;
+use crate::configs::client::{environment_str, parse_environment};
+use crate::configs::handlers::{ensure_config_writable, scoped};
+use crate::configs::models::{
+    ConfigDiffQuery, ConfigDiffResponse, ConfigDiffSide, ConfigPromoteRequest,
+    ConfigReleasePolicyRequest, ConfigReleaseResponse, ConfigResponse, ConfigReviewRequest,
+    ConfigRollbackRequest,
+};
+use crate::configs::secrets::{plain_content, SECRET_MASK};
+use crate::configs::template::merge_with_base;
+use crate::configs::value_sync::sync_config_values;
+use crate::configs::watch::ConfigWatchHub;
+use crate::entities::configs::{self, Column, Entity as Configs};
+use crate::entities::{config_releases, ConfigReleases};
+use crate::members::request_scope;
+use crate::shared::enums::{ConfigStatus, ConfigType, Environment};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde_json::Value;
+
+/// 环境晋级顺序
+pub const PROMOTION_ORDER: [Environment; 4] = [
+    Environment::Dev,
+    Environment::Test,
+    Environment::Staging,
+    Environment::Prod,
+];
+
+fn environment_rank(environment: &Environment) -> usize {
+    PROMOTION_ORDER
+        .iter()
+        .position(|e| e == environment)
+        .unwrap_or(0)
+}
+
+/// 下一晋级环境，prod 没有下一环境
+pub fn next_environment(environment: &Environment) -> Option<Environment> {
+    PROMOTION_ORDER
+        .get(environment_rank(environment) + 1)
+        .cloned()
+}
+
+/// 校验版本是否可以发布
+pub fn ensure_publishable(status: &ConfigStatus, require_approval: bool) -> Result<(), ApiError> {
+    match status {
+        ConfigStatus::Approved | ConfigStatus::Published => Ok(()),
+        ConfigStatus::Draft | ConfigStatus::Pending if !require_approval => Ok(()),
+        ConfigStatus::Rejected => Err(ApiError::BadRequest("版本已被驳回，不能发布".to_string())),
+        _ => Err(ApiError::BadRequest(
+            "该配置发布前需要审批，请先提交审核".to_string(),
+        )),
+    }
+}
+
+/// 对比用的配置内容：object/array 按 JSON 字段对比，html/text 整体对比
+pub fn content_value(config_type: &ConfigType, content: &str) -> Value {
+    match config_type {
+        ConfigType::Object | ConfigType::Array => {
+            serde_json::from_str(content).unwrap_or_else(|_| Value::String(content.to_string()))
+        }
+        ConfigType::Html | ConfigType::Text => Value::String(content.to_string()),
+    }
+}
+
+fn release_response(release: config_releases::Model) -> ConfigReleaseResponse {
+    ConfigReleaseResponse {
+        code: release.code,
+        environment: release.environment,
+        application_id: release.application_id,
+        config_id: release.config_id,
+        version: release.version.map(|v| v as u32),
+        require_approval: release.require_approval,
+        published_by: release.published_by,
+        published_at: release.published_at.map(|t| t.to_rfc3339()),
+    }
+}
+
+fn release_snapshot(release: &config_releases::Model) -> Value {
+    serde_json::json!({
+        "id": release.id,
+        "code": release.code,
+        "environment": release.environment,
+        "config_id": release.config_id,
+        "version": release.version,
+        "require_approval": release.require_approval,
+    })
+}
+
+fn status_snapshot(config: &configs::Model) -> Value {
+    serde_json::json!({
+        "id": config.id,
+        "code": config.code,
+        "environment": config.environment,
+        "version": config.version,
+        "status": config.status,
+        "submitted_by": config.submitted_by,
+        "reviewed_by": config.reviewed_by,
+        "review_comment": config.review_comment,
+    })
+}
+
+async fn find_config(db: &DatabaseConnection, id: &str) -> Result<configs::Model, ApiError> {
+    Configs::find_by_id(id)
+        .filter(Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))
+}
+
+async fn find_release(
+    db: &DatabaseConnection,
+    code: &str,
+    environment: &Environment,
+) -> Result<Option<config_releases::Model>, ApiError> {
+    Ok(ConfigReleases::find()
+        .filter(config_releases::Column::Code.eq(code))
+        .filter(config_releases::Column::Environment.eq(environment.clone()))
+        .one(db)
+        .await?)
+}
+
+/// 查询 code + environment 的最新版本，用于确定归属应用与校验存在
+async fn find_latest(
+    db: &DatabaseConnection,
+    code: &str,
+    environment: &Environment,
+) -> Result<configs::Model, ApiError> {
+    Configs::find()
+        .filter(Column::Code.eq(code))
+        .filter(Column::Environment.eq(environment.clone()))
+        .filter(Column::DeletedAt.is_null())
+        .order_by_desc(Column::Version)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))
+}
+
+fn parse_path_environment(environment: &str) -> Result<Environment, ApiError> {
+    parse_environment(environment)
+        .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))
+}
+
+/// 将发布指针指向指定版本（不存在时创建），并标记版本为已发布
+async fn point_release_to(
+    db: &DatabaseConnection,
+    release: Option<config_releases::Model>,
+    config: configs::Model,
+    user_id: &str,
+) -> Result<config_releases::Model, ApiError> {
+    let now = Utc::now();
+    let release = match release {
+        Some(release) => {
+            let revision = release.revision;
+            let mut active: config_releases::ActiveModel = release.into();
+            active.config_id = Set(Some(config.id.clone()));
+            active.version = Set(Some(config.version));
+            active.application_id = Set(config.application_id.clone());
+            active.published_by = Set(Some(user_id.to_string()));
+            active.published_at = Set(Some(now.into()));
+            active.revision = Set(revision + 1);
+            active.updated_at = Set(now.into());
+            active.update(db).await?
+        }
+        None => {
+            config_releases::ActiveModel {
+                id: Set(generate_snowflake_id()),
+                code: Set(config.code.clone()),
+                environment: Set(config.environment.clone()),
+                application_id: Set(config.application_id.clone()),
+                config_id: Set(Some(config.id.clone())),
+                version: Set(Some(config.version)),
+                require_approval: Set(false),
+                published_by: Set(Some(user_id.to_string())),
+                published_at: Set(Some(now.into())),
+                revision: Set(1),
+                created_at: Set(now.into()),
+                updated_at: Set(now.into()),
+            }
+            .insert(db)
+            .await?
+        }
+    };
+
+    if config.status != ConfigStatus::Published {
+        let revision = config.revision;
+        let mut active: configs::ActiveModel = config.into();
+        active.status = Set(ConfigStatus::Published);
+        active.revision = Set(revision + 1);
+        active.updated_at = Set(now.into());
+        active.update(db).await?;
+    }
+    Ok(release)
+}
+
+/// 审核状态变更：提交、审批、驳回
+async fn change_review_status(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    id: &str,
+    to: ConfigStatus,
+    comment: Option<String>,
+) -> Result<ConfigResponse, ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    let config = find_config(db, id).await?;
+    let scope = request_scope(db, req).await?;
+    ensure_config_writable(&scope, config.application_id.as_deref())?;
+
+    let allowed = match to {
+        ConfigStatus::Pending => {
+            matches!(config.status, ConfigStatus::Draft | ConfigStatus::Rejected)
+        }
+        _ => config.status == ConfigStatus::Pending,
+    };
+    if !allowed {
+        return Err(ApiError::BadRequest(format!(
+            "版本当前状态为 {:?}，不能执行该操作",
+            config.status
+        )));
+    }
+    if to != ConfigStatus::Pending && config.submitted_by.as_deref() == Some(user_id.as_str()) {
+        return Err(ApiError::Forbidden("不能审批自己提交的版本".to_string()));
+    }
+
+    let before = status_snapshot(&config);
+    let now = Utc::now();
+    let revision = config.revision;
+    let mut active: configs::ActiveModel = config.into();
+    if to == ConfigStatus::Pending {
+        active.submitted_by = Set(Some(user_id.clone()));
+        active.submitted_at = Set(Some(now.into()));
+        active.reviewed_by = Set(None);
+        active.reviewed_at = Set(None);
+        active.review_comment = Set(None);
+    } else {
+        active.reviewed_by = Set(Some(user_id.clone()));
+        active.reviewed_at = Set(Some(now.into()));
+        active.review_comment = Set(comment.filter(|c| !c.is_empty()));
+    }
+    active.status = Set(to);
+    active.updated_by = Set(user_id);
+    active.revision = Set(revision + 1);
+    active.updated_at = Set(now.into());
+    let updated = active.update(db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db,
+        "configs",
+        "update",
+        req,
+        Some(before),
+        Some(status_snapshot(&updated)),
+    )
+    .await;
+    Ok(ConfigResponse::from(updated))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/{id}/submit",
+    params(("id" = String, Path, description = "Config ID")),
+    responses(
+        (status = 200, description = "Config submitted for review", body = ConfigResponse),
+        (status = 400, description = "Invalid status"),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn submit_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let response =
+        change_review_status(&db, &req, &path.into_inner(), ConfigStatus::Pending, None).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/{id}/approve",
+    params(("id" = String, Path, description = "Config ID")),
+    request_body = ConfigReviewRequest,
+    responses(
+        (status = 200, description = "Config approved", body = ConfigResponse),
+        (status = 400, description = "Invalid status"),
+        (status = 403, description = "Cannot approve own submission")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn approve_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<ConfigReviewRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let comment = body.into_inner().comment;
+    let response = change_review_status(
+        &db,
+        &req,
+        &path.into_inner(),
+        ConfigStatus::Approved,
+        comment,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/{id}/reject",
+    params(("id" = String, Path, description = "Config ID")),
+    request_body = ConfigReviewRequest,
+    responses(
+        (status = 200, description = "Config rejected", body = ConfigResponse),
+        (status = 400, description = "Invalid status"),
+        (status = 403, description = "Cannot reject own submission")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn reject_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<ConfigReviewRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let comment = body.into_inner().comment;
+    let response = change_review_status(
+        &db,
+        &req,
+        &path.into_inner(),
+        ConfigStatus::Rejected,
+        comment,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/{id}/publish",
+    params(("id" = String, Path, description = "Config ID")),
+    responses(
+        (status = 200, description = "Config published", body = ConfigReleaseResponse),
+        (status = 400, description = "Approval required or version rejected"),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn publish_config(
+    db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let config = find_config(&db, &path.into_inner()).await?;
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, config.application_id.as_deref())?;
+
+    let release = find_release(&db, &config.code, &config.environment).await?;
+    let require_approval = release.as_ref().is_some_and(|r| r.require_approval);
+    ensure_publishable(&config.status, require_approval)?;
+
+    let before = release.as_ref().map(release_snapshot);
+    let release = point_release_to(&db, release, config, &user_id).await?;
+    hub.notify();
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "config_releases",
+        "update",
+        &req,
+        before,
+        Some(release_snapshot(&release)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(release_response(release)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/code/{code}/environment/{environment}/rollback",
+    params(
+        ("code" = String, Path, description = "Config code"),
+        ("environment" = String, Path, description = "Environment name")
+    ),
+    request_body = ConfigRollbackRequest,
+    responses(
+        (status = 200, description = "Config rolled back", body = ConfigReleaseResponse),
+        (status = 400, description = "No published version to roll back to"),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn rollback_config(
+    db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
+    path: web::Path<(String, String)>,
+    body: web::Json<ConfigRollbackRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let (code, environment) = path.into_inner();
+    let environment = parse_path_environment(&environment)?;
+    let release = find_release(&db, &code, &environment)
+        .await?
+        .filter(|r| r.config_id.is_some())
+        .ok_or_else(|| ApiError::BadRequest("该配置尚未发布，无法回滚".to_string()))?;
+    let current_version = release.version.unwrap_or(0);
+
+    // 只能回滚到发布过的版本
+    let mut query = Configs::find()
+        .filter(Column::Code.eq(&code))
+        .filter(Column::Environment.eq(environment.clone()))
+        .filter(Column::Status.eq(ConfigStatus::Published))
+        .filter(Column::DeletedAt.is_null());
+    query = match body.version {
+        Some(version) => query.filter(Column::Version.eq(version as i32)),
+        None => query
+            .filter(Column::Version.lt(current_version))
+            .order_by_desc(Column::Version),
+    };
+    let target = query
+        .one(db.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("没有可回滚的已发布版本".to_string()))?;
+    if target.version == current_version {
+        return Err(ApiError::BadRequest(format!(
+            "版本 {} 已是当前发布版本",
+            target.version
+        )));
+    }
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, release.application_id.as_deref())?;
+    ensure_config_writable(&scope, target.application_id.as_deref())?;
+
+    let before = release_snapshot(&release);
+    let release = point_release_to(&db, Some(release), target, &user_id).await?;
+    hub.notify();
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "config_releases",
+        "rollback",
+        &req,
+        Some(before),
+        Some(release_snapshot(&release)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(release_response(release)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/{id}/promote",
+    params(("id" = String, Path, description = "Config ID")),
+    request_body = ConfigPromoteRequest,
+    responses(
+        (status = 200, description = "Draft created in target environment", body = ConfigResponse),
+        (status = 400, description = "Invalid target environment"),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn promote_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<ConfigPromoteRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let source = find_config(&db, &path.into_inner()).await?;
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, source.application_id.as_deref())?;
+
+    let target_environment = match body.target_environment.as_deref().filter(|e| !e.is_empty()) {
+        Some(environment) => parse_path_environment(environment)?,
+        None => next_environment(&source.environment)
+            .ok_or_else(|| ApiError::BadRequest("prod 环境没有下一晋级环境".to_string()))?,
+    };
+    if environment_rank(&target_environment) <= environment_rank(&source.environment) {
+        return Err(ApiError::BadRequest(format!(
+            "只能从 {} 晋级到后续环境",
+            environment_str(&source.environment)
+        )));
+    }
+
+    let version = Configs::find()
+        .filter(Column::Code.eq(&source.code))
+        .filter(Column::Environment.eq(target_environment.clone()))
+        .order_by_desc(Column::Version)
+        .one(db.get_ref())
+        .await?
+        .map(|c| c.version + 1)
+        .unwrap_or(1);
+
+    let now = Utc::now();
+    let promoted = configs::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        code: Set(source.code.clone()),
+        environment: Set(target_environment.clone()),
+        name: Set(source.name.clone()),
+        config_type: Set(source.config_type.clone()),
+        content: Set(source.content.clone()),
+        description: Set(source.description.clone()),
+        version: Set(version),
+        created_by: Set(user_id.clone()),
+        updated_by: Set(user_id),
+        deleted_at: Set(None),
+        revision: Set(1),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+        generate_values: Set(source.generate_values),
+        schema: Set(source.schema.clone()),
+        application_id: Set(source.application_id.clone()),
+        status: Set(ConfigStatus::Draft),
+        submitted_by: Set(None),
+        submitted_at: Set(None),
+        reviewed_by: Set(None),
+        reviewed_at: Set(None),
+        review_comment: Set(None),
+        source_config_id: Set(Some(source.id.clone())),
+        is_secret: Set(source.is_secret),
+        base_code: Set(source.base_code.clone()),
+    }
+    .insert(db.get_ref())
+    .await?;
+
+    let after = serde_json::json!({
+        "id": promoted.id,
+        "code": promoted.code,
+        "environment": promoted.environment,
+        "version": promoted.version,
+        "source_config_id": promoted.source_config_id,
+        "source_environment": source.environment,
+        "source_version": source.version,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "configs",
+        "promote",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+
+    if promoted.config_type == ConfigType::Array && promoted.generate_values {
+        // 覆盖层按目标环境的基础配置合并后同步选项值
+        let mut content = content_value(&promoted.config_type, &plain_content(&promoted)?);
+        if let Some(base_code) = promoted.base_code.as_deref() {
+            content = merge_with_base(
+                db.get_ref(),
+                &promoted.code,
+                base_code,
+                promoted.application_id.as_deref(),
+                &promoted.environment,
+                &promoted.config_type,
+                content,
+            )
+            .await?;
+        }
+        if let Value::Array(items) = content {
+            sync_config_values(
+                db.get_ref(),
+                &promoted.id,
+                &promoted.code,
+                environment_str(&promoted.environment),
+                promoted.version,
+                &items,
+                promoted.is_secret,
+            )
+            .await?;
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(ConfigResponse::from(promoted)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/configs/code/{code}/environment/{environment}/release",
+    params(
+        ("code" = String, Path, description = "Config code"),
+        ("environment" = String, Path, description = "Environment name")
+    ),
+    responses(
+        (status = 200, description = "Config release state", body = ConfigReleaseResponse),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn get_config_release(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let (code, environment) = path.into_inner();
+    let environment = parse_path_environment(&environment)?;
+    let scope = request_scope(&db, &req).await?;
+    let latest = scoped(Configs::find(), &scope)
+        .filter(Column::Code.eq(&code))
+        .filter(Column::Environment.eq(environment.clone()))
+        .filter(Column::DeletedAt.is_null())
+        .order_by_desc(Column::Version)
+        .one(db.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))?;
+
+    let response = match find_release(&db, &code, &environment).await? {
+        Some(release) => release_response(release),
+        None => ConfigReleaseResponse {
+            code,
+            environment,
+            application_id: latest.application_id,
+            config_id: None,
+            version: None,
+            require_approval: false,
+            published_by: None,
+            published_at: None,
+        },
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/configs/code/{code}/environment/{environment}/release-policy",
+    params(
+        ("code" = String, Path, description = "Config code"),
+        ("environment" = String, Path, description = "Environment name")
+    ),
+    request_body = ConfigReleasePolicyRequest,
+    responses(
+        (status = 200, description = "Release policy updated", body = ConfigReleaseResponse),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn update_config_release_policy(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    body: web::Json<ConfigReleasePolicyRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let (code, environment) = path.into_inner();
+    let environment = parse_path_environment(&environment)?;
+    let latest = find_latest(&db, &code, &environment).await?;
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, latest.application_id.as_deref())?;
+
+    let now = Utc::now();
+    let (before, release) = match find_release(&db, &code, &environment).await? {
+        Some(release) => {
+            let before = release_snapshot(&release);
+            let revision = release.revision;
+            let mut active: config_releases::ActiveModel = release.into();
+            active.require_approval = Set(body.require_approval);
+            active.revision = Set(revision + 1);
+            active.updated_at = Set(now.into());
+            (Some(before), active.update(db.get_ref()).await?)
+        }
+        None => {
+            let release = config_releases::ActiveModel {
+                id: Set(generate_snowflake_id()),
+                code: Set(code),
+                environment: Set(environment),
+                application_id: Set(latest.application_id),
+                config_id: Set(None),
+                version: Set(None),
+                require_approval: Set(body.require_approval),
+                published_by: Set(None),
+                published_at: Set(None),
+                revision: Set(1),
+                created_at: Set(now.into()),
+                updated_at: Set(now.into()),
+            }
+            .insert(db.get_ref())
+            .await?;
+            (None, release)
+        }
+    };
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "config_releases",
+        "update",
+        &req,
+        before,
+        Some(release_snapshot(&release)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(release_response(release)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/configs/diff",
+    params(ConfigDiffQuery),
+    responses(
+        (status = 200, description = "Field-level diff between two versions", body = ConfigDiffResponse),
+        (status = 400, description = "Versions belong to different configs"),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn diff_configs(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<ConfigDiffQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
+    let found = scoped(Configs::find(), &scope)
+        .filter(Column::Id.is_in([query.from_id.as_str(), query.to_id.as_str()]))
+        .all(db.get_ref())
+        .await?;
+    let pick = |id: &str| {
+        found
+            .iter()
+            .find(|c| c.id == id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("Config {} not found", id)))
+    };
+    let from = pick(&query.from_id)?;
+    let to = pick(&query.to_id)?;
+    if from.code != to.code {
+        return Err(ApiError::BadRequest("只能对比同一配置的版本".to_string()));
+    }
+
+    let mut changes = diff_values(
+        &content_value(&from.config_type, &plain_content(&from)?),
+        &content_value(&to.config_type, &plain_content(&to)?),
+    );
+    // 密钥配置只返回发生变化的字段路径，不返回值
+    if from.is_secret || to.is_secret {
+        let mask = |v: Option<Value>| v.map(|_| Value::String(SECRET_MASK.to_string()));
+        for change in &mut changes {
+            change.before = mask(change.before.take());
+            change.after = mask(change.after.take());
+        }
+    }
+    let side = |c: configs::Model| ConfigDiffSide {
+        id: c.id,
+        environment: c.environment,
+        version: c.version as u32,
+        status: c.status,
+    };
+    let response = ConfigDiffResponse {
+        code: from.code.clone(),
+        from: side(from),
+        to: side(to),
+        changes,
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_environment() {
+        assert_eq!(next_environment(&Environment::Dev), Some(Environment::Test));
+        assert_eq!(
+            next_environment(&Environment::Staging),
+            Some(Environment::Prod)
+        );
+        assert_eq!(next_environment(&Environment::Prod), None);
+    }
+
+    #[test]
+    fn test_ensure_publishable() {
+        assert!(ensure_publishable(&ConfigStatus::Draft, false).is_ok());
+        assert!(ensure_publishable(&ConfigStatus::Draft, true).is_err());
+        assert!(ensure_publishable(&ConfigStatus::Pending, true).is_err());
+        assert!(ensure_publishable(&ConfigStatus::Approved, true).is_ok());
+        // 发布过的版本可直接重新发布（回滚）
+        assert!(ensure_publishable(&ConfigStatus::Published, true).is_ok());
+        assert!(ensure_publishable(&ConfigStatus::Rejected, false).is_err());
+    }
+
+    #[test]
+    fn test_content_value() {
+        let object = content_value(&ConfigType::Object, r#"{"a":1}"#);
+        assert_eq!(object, serde_json::json!({"a": 1}));
+        let text = content_value(&ConfigType::Text, r#"{"a":1}"#);
+        assert_eq!(text, Value::String(r#"{"a":1}"#.to_string()));
+    }
+}
@@ -1,13 +1,34 @@
-use crate::configs::handlers;
+use crate::configs::{client, handlers, publish, schema, secrets, template, variables};
 use actix_web::web;
 
 pub fn config_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/configs", web::get().to(handlers::get_configs))
         .route("/configs", web::post().to(handlers::create_config))
+        .route("/configs/diff", web::get().to(publish::diff_configs))
+        .route(
+            "/configs/secrets/rotate",
+            web::post().to(secrets::rotate_config_secrets),
+        )
+        .route(
+            "/configs/variables",
+            web::get().to(variables::list_config_variables),
+        )
+        .route(
+            "/configs/variables",
+            web::put().to(variables::upsert_config_variable),
+        )
+        .route(
+            "/configs/variables/{id}",
+            web::delete().to(variables::delete_config_variable),
+        )
         .route(
             "/configs/code/{code}",
             web::get().to(handlers::get_config_by_code),
         )
+        .route(
+            "/configs/code/{code}/schema/check",
+            web::post().to(schema::check_config_schema),
+        )
         .route(
             "/configs/code/{code}/environment/{environment}",
             web::get().to(handlers::get_config_by_code_and_environment),
@@ -16,6 +37,58 @@ pub fn config_routes(cfg: &mut web::ServiceConfig) {
             "/configs/code/{code}/environment/{environment}/version/{version}",
             web::get().to(handlers::get_config_by_code_env_and_version),
         )
+        .route(
+            "/configs/code/{code}/environment/{environment}/render",
+            web::get().to(template::render_config),
+        )
+        .route(
+            "/configs/code/{code}/environment/{environment}/release",
+            web::get().to(publish::get_config_release),
+        )
+        .route(
+            "/configs/code/{code}/environment/{environment}/release-policy",
+            web::put().to(publish::update_config_release_policy),
+        )
+        .route(
+            "/configs/code/{code}/environment/{environment}/rollback",
+            web::post().to(publish::rollback_config),
+        )
+        .route(
+            "/configs/{id}/reveal",
+            web::get().to(secrets::reveal_config),
+        )
+        .route(
+            "/configs/{id}/submit",
+            web::post().to(publish::submit_config),
+        )
+        .route(
+            "/configs/{id}/approve",
+            web::post().to(publish::approve_config),
+        )
+        .route(
+            "/configs/{id}/reject",
+            web::post().to(publish::reject_config),
+        )
+        .route(
+            "/configs/{id}/publish",
+            web::post().to(publish::publish_config),
+        )
+        .route(
+            "/configs/{id}/promote",
+            web::post().to(publish::promote_config),
+        )
         .route("/configs/{id}", web::delete().to(handlers::delete_config))
         .route("/configs/{id}", web::put().to(handlers::update_config));
 }
+
+pub fn open_config_routes(cfg: &mut web::ServiceConfig) {
+    // 应用读取与 watch 配置（开放接口，使用实例令牌认证）
+    cfg.route(
+        "/configs/watch",
+        web::post().to(client::watch_client_configs),
+    )
+    .route(
+        "/configs/{code}/{environment}",
+        web::get().to(client::get_client_config),
+    );
+}
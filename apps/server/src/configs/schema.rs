@@ -0,0 +1,222 @@
+/// 配置内容的 JSON Schema 校验
+///
+/// 新增与修改配置时按版本上保存的 schema 校验 content，错误以字段路径（如 `$.servers[0].port`）返回；
+/// schema 变更前可通过检查接口找出不再符合的已有版本。
+use crate::configs::client::{environment_str, parse_environment};
+use crate::configs::handlers::scoped;
+use crate::configs::models::{
+    ConfigSchemaCheckRequest, ConfigSchemaCheckResponse, ConfigSchemaViolation,
+};
+use crate::configs::publish::content_value;
+use crate::configs::secrets::plain_content;
+use crate::configs::template::merge_with_base;
+use crate::entities::configs::{Column, Entity as Configs};
+use crate::members::request_scope;
+use crate::shared::error::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use jsonschema::paths::{Location, LocationSegment};
+use jsonschema::Validator;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 错误信息中最多列出的校验错误数
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// 解析并编译 schema，空字符串视为未设置
+pub fn parse_schema(schema: Option<&str>) -> Result<Option<Value>, ApiError> {
+    let Some(schema) = schema.filter(|s| !s.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let value: Value = serde_json::from_str(schema)
+        .map_err(|e| ApiError::ValidationError(format!("Invalid schema JSON: {}", e)))?;
+    compile(&value)?;
+    Ok(Some(value))
+}
+
+fn compile(schema: &Value) -> Result<Validator, ApiError> {
+    jsonschema::validator_for(schema)
+        .map_err(|e| ApiError::ValidationError(format!("Invalid JSON Schema: {}", e)))
+}
+
+/// 将 JSON Pointer 形式的位置转换为字段路径，如 `/servers/0/port` -> `$.servers[0].port`
+fn field_path(location: &Location) -> String {
+    let mut path = "$".to_string();
+    for segment in location {
+        match segment {
+            LocationSegment::Property(name) => {
+                path.push('.');
+                path.push_str(&name.replace("~1", "/").replace("~0", "~"));
+            }
+            LocationSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+/// 返回实例不符合 schema 的全部错误，每条形如 `字段路径: 错误信息`
+pub fn schema_errors(validator: &Validator, instance: &Value) -> Vec<String> {
+    validator
+        .iter_errors(instance)
+        .map(|e| format!("{}: {}", field_path(&e.instance_path), e))
+        .collect()
+}
+
+/// 校验配置内容符合 schema，不符合时返回 ValidationError
+pub fn ensure_conforms(schema: &Value, instance: &Value) -> Result<(), ApiError> {
+    let errors = schema_errors(&compile(schema)?, instance);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let mut message = errors
+        .iter()
+        .take(MAX_REPORTED_ERRORS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("; ");
+    if errors.len() > MAX_REPORTED_ERRORS {
+        message.push_str(&format!(" 等 {} 处错误", errors.len()));
+    }
+    Err(ApiError::ValidationError(format!(
+        "配置内容不符合 Schema: {}",
+        message
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/code/{code}/schema/check",
+    params(("code" = String, Path, description = "Config code")),
+    request_body = ConfigSchemaCheckRequest,
+    responses(
+        (status = 200, description = "Versions that do not conform to the schema", body = ConfigSchemaCheckResponse),
+        (status = 400, description = "Invalid schema")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn check_config_schema(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    body: web::Json<ConfigSchemaCheckRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let code = path.into_inner();
+    let body = body.into_inner();
+    let candidate = parse_schema(body.schema.as_deref())?;
+
+    let scope = request_scope(&db, &req).await?;
+    let mut query = scoped(Configs::find(), &scope)
+        .filter(Column::Code.eq(&code))
+        .filter(Column::DeletedAt.is_null());
+    if let Some(environment) = body.environment.as_deref().filter(|e| !e.is_empty()) {
+        let environment = parse_environment(environment)
+            .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))?;
+        query = query.filter(Column::Environment.eq(environment));
+    }
+    let versions = query
+        .order_by_desc(Column::Version)
+        .all(db.get_ref())
+        .await?;
+
+    // 未指定 schema 时，各环境按最新版本的 schema 检查
+    let mut validators: HashMap<&'static str, Option<Validator>> = HashMap::new();
+    let mut checked = 0;
+    let mut invalid = Vec::new();
+    for config in versions {
+        let environment = environment_str(&config.environment);
+        if !validators.contains_key(environment) {
+            let schema = candidate.as_ref().or(config.schema.as_ref());
+            let validator = schema.map(compile).transpose()?;
+            validators.insert(environment, validator);
+        }
+        let Some(validator) = &validators[environment] else {
+            continue;
+        };
+        checked += 1;
+        // 覆盖层按与基础配置合并后的生效内容检查
+        let mut content = content_value(&config.config_type, &plain_content(&config)?);
+        if let Some(base_code) = config.base_code.as_deref() {
+            content = merge_with_base(
+                &db,
+                &config.code,
+                base_code,
+                config.application_id.as_deref(),
+                &config.environment,
+                &config.config_type,
+                content,
+            )
+            .await?;
+        }
+        let errors = schema_errors(validator, &content);
+        if !errors.is_empty() {
+            invalid.push(ConfigSchemaViolation {
+                id: config.id,
+                environment: config.environment,
+                version: config.version as u32,
+                status: config.status,
+                errors,
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(ConfigSchemaCheckResponse {
+        code,
+        checked,
+        invalid,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn port_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["servers"],
+            "properties": {
+                "servers": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"port": {"type": "integer", "maximum": 65535}}
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        assert_eq!(parse_schema(None).unwrap(), None);
+        assert_eq!(parse_schema(Some("  ")).unwrap(), None);
+        assert!(parse_schema(Some("{")).is_err());
+        assert!(parse_schema(Some(r#"{"type": 1}"#)).is_err());
+        assert!(parse_schema(Some(r#"{"type": "object"}"#))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_schema_errors_report_field_path() {
+        let validator = compile(&port_schema()).unwrap();
+        let errors = schema_errors(
+            &validator,
+            &json!({"servers": [{"port": 80}, {"port": 70000}]}),
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("$.servers[1].port: "));
+        assert!(schema_errors(&validator, &json!({"servers": []})).is_empty());
+    }
+
+    #[test]
+    fn test_ensure_conforms() {
+        assert!(ensure_conforms(&port_schema(), &json!({"servers": []})).is_ok());
+        match ensure_conforms(&port_schema(), &json!({})) {
+            Err(ApiError::ValidationError(message)) => assert!(message.contains("$: ")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,358 @@
+/// 密钥配置的加密存储（信封加密）
+///
+/// 每个密钥配置版本使用随机数据密钥（DEK）以 AES-256-GCM 加密内容，DEK 再由服务端主密钥（KEK）加密，
+/// 一并保存为 `enc:v1:<主密钥ID>:<加密的DEK>:<密文>`。主密钥通过环境变量 `CONFIG_MASTER_KEYS`
+/// 配置（`id:base64密钥`，逗号分隔，第一个为当前密钥）；轮换主密钥时只需重新加密 DEK，内容密文不变。
+///
+/// 管理接口返回的密钥配置内容一律打码，明文仅通过带审计的查看接口或应用客户端接口获取。
+use crate::configs::handlers::ensure_config_writable;
+use crate::configs::models::{ConfigResponse, ConfigSecretRotateResponse};
+use crate::entities::configs::{self, Column, Entity as Configs};
+use crate::entities::{config_values, ConfigValues};
+use crate::members::request_scope;
+use crate::shared::error::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 打码后的内容，修改密钥配置时提交该值表示沿用原内容
+pub const SECRET_MASK: &str = "******";
+
+const ENVELOPE_PREFIX: &str = "enc:v1";
+const NONCE_LEN: usize = 12;
+
+static MASTER_KEYS: Lazy<Result<MasterKeys, String>> = Lazy::new(|| {
+    let raw = std::env::var("CONFIG_MASTER_KEYS").unwrap_or_default();
+    MasterKeys::parse(&raw)
+});
+
+/// 服务端主密钥
+pub struct MasterKeys {
+    active: String,
+    keys: HashMap<String, [u8; 32]>,
+}
+
+impl MasterKeys {
+    /// 解析 `id:base64密钥,...`，第一个为当前用于加密的密钥
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut active = None;
+        let mut keys = HashMap::new();
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("主密钥格式错误，应为 id:base64密钥: {}", entry))?;
+            if id.is_empty() {
+                return Err("主密钥ID不能为空".to_string());
+            }
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("主密钥 {} 不是有效的 base64: {}", id, e))?;
+            let key: [u8; 32] = bytes
+                .try_into()
+                .map_err(|_| format!("主密钥 {} 长度必须为 32 字节", id))?;
+            if keys.insert(id.to_string(), key).is_some() {
+                return Err(format!("主密钥ID重复: {}", id));
+            }
+            active.get_or_insert_with(|| id.to_string());
+        }
+        let active = active.ok_or_else(|| "未配置 CONFIG_MASTER_KEYS".to_string())?;
+        Ok(Self { active, keys })
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active
+    }
+
+    fn cipher(&self, key_id: &str) -> Result<Aes256Gcm, ApiError> {
+        let key = self.keys.get(key_id).ok_or_else(|| {
+            ApiError::InternalServerError(format!("未找到配置加密主密钥 {}", key_id))
+        })?;
+        Ok(Aes256Gcm::new_from_slice(key).expect("key length is 32"))
+    }
+
+    /// 使用新的数据密钥加密内容
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String, ApiError> {
+        let mut dek = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut dek);
+        let content = seal(
+            &Aes256Gcm::new_from_slice(&dek).expect("key length is 32"),
+            plaintext,
+        )?;
+        let wrapped = seal(&self.cipher(&self.active)?, &dek)?;
+        Ok(format!(
+            "{}:{}:{}:{}",
+            ENVELOPE_PREFIX, self.active, wrapped, content
+        ))
+    }
+
+    pub fn decrypt(&self, envelope: &str) -> Result<Vec<u8>, ApiError> {
+        let (key_id, wrapped, content) = split_envelope(envelope)?;
+        let dek = open(&self.cipher(key_id)?, wrapped)?;
+        let cipher = Aes256Gcm::new_from_slice(&dek)
+            .map_err(|_| ApiError::InternalServerError("配置数据密钥无效".to_string()))?;
+        open(&cipher, content)
+    }
+
+    /// 用当前主密钥重新加密数据密钥，已是当前主密钥时返回 None
+    pub fn rewrap(&self, envelope: &str) -> Result<Option<String>, ApiError> {
+        let (key_id, wrapped, content) = split_envelope(envelope)?;
+        if key_id == self.active {
+            return Ok(None);
+        }
+        let dek = open(&self.cipher(key_id)?, wrapped)?;
+        let wrapped = seal(&self.cipher(&self.active)?, &dek)?;
+        Ok(Some(format!(
+            "{}:{}:{}:{}",
+            ENVELOPE_PREFIX, self.active, wrapped, content
+        )))
+    }
+}
+
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8]) -> Result<String, ApiError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let mut sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| ApiError::InternalServerError("配置加密失败".to_string()))?;
+    sealed.splice(0..0, nonce);
+    Ok(base64::engine::general_purpose::STANDARD.encode(sealed))
+}
+
+fn open(cipher: &Aes256Gcm, encoded: &str) -> Result<Vec<u8>, ApiError> {
+    let invalid = || ApiError::InternalServerError("配置密文无效或主密钥不匹配".to_string());
+    let sealed = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| invalid())?;
+    if sealed.len() < NONCE_LEN {
+        return Err(invalid());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| invalid())
+}
+
+fn split_envelope(envelope: &str) -> Result<(&str, &str, &str), ApiError> {
+    let rest = envelope
+        .strip_prefix(ENVELOPE_PREFIX)
+        .and_then(|r| r.strip_prefix(':'))
+        .ok_or_else(|| ApiError::InternalServerError("配置内容不是密文".to_string()))?;
+    let mut parts = rest.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(key_id), Some(wrapped), Some(content)) => Ok((key_id, wrapped, content)),
+        _ => Err(ApiError::InternalServerError(
+            "配置密文格式错误".to_string(),
+        )),
+    }
+}
+
+/// 进程配置的主密钥，未配置时无法读写密钥配置
+pub fn master_keys() -> Result<&'static MasterKeys, ApiError> {
+    MASTER_KEYS
+        .as_ref()
+        .map_err(|e| ApiError::InternalServerError(format!("密钥配置不可用：{}", e)))
+}
+
+/// 按是否为密钥配置准备入库内容
+pub fn seal_content(is_secret: bool, content: &str) -> Result<String, ApiError> {
+    if is_secret {
+        master_keys()?.encrypt(content.as_bytes())
+    } else {
+        Ok(content.to_string())
+    }
+}
+
+/// 配置版本的明文内容
+pub fn plain_content(config: &configs::Model) -> Result<String, ApiError> {
+    if !config.is_secret {
+        return Ok(config.content.clone());
+    }
+    let bytes = master_keys()?.decrypt(&config.content)?;
+    String::from_utf8(bytes)
+        .map_err(|_| ApiError::InternalServerError("配置明文不是 UTF-8".to_string()))
+}
+
+/// 配置值入库：密钥配置的值整体加密为字符串
+pub fn seal_value(is_secret: bool, value: Value) -> Result<Value, ApiError> {
+    if is_secret {
+        Ok(Value::String(
+            master_keys()?.encrypt(value.to_string().as_bytes())?,
+        ))
+    } else {
+        Ok(value)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/configs/{id}/reveal",
+    params(("id" = String, Path, description = "Config ID")),
+    responses(
+        (status = 200, description = "Config with plaintext content", body = ConfigResponse),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn reveal_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let config = Configs::find_by_id(path.into_inner())
+        .filter(Column::DeletedAt.is_null())
+        .one(db.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))?;
+    let scope = request_scope(&db, &req).await?;
+    ensure_config_writable(&scope, config.application_id.as_deref())?;
+
+    let content = plain_content(&config)?;
+    if config.is_secret {
+        // 只记录查看行为，不记录内容
+        let after = serde_json::json!({
+            "id": config.id,
+            "code": config.code,
+            "environment": config.environment,
+            "version": config.version,
+        });
+        let _ = crate::shared::request_context::record_audit_log_simple(
+            db.get_ref(),
+            "configs",
+            "reveal",
+            &req,
+            None,
+            Some(after),
+        )
+        .await;
+    }
+    let mut response = ConfigResponse::from(config);
+    response.content = content;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/configs/secrets/rotate",
+    responses(
+        (status = 200, description = "Data keys re-encrypted with the active master key", body = ConfigSecretRotateResponse)
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn rotate_config_secrets(
+    db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
+    scope.ensure_unrestricted()?;
+    let keys = master_keys()?;
+
+    // 包含已删除版本，旧主密钥下线后仍可读取
+    let secret_configs = Configs::find()
+        .filter(Column::IsSecret.eq(true))
+        .all(db.get_ref())
+        .await?;
+    let config_ids: Vec<String> = secret_configs.iter().map(|c| c.id.clone()).collect();
+
+    let mut rotated_configs = 0;
+    for config in secret_configs {
+        if let Some(content) = keys.rewrap(&config.content)? {
+            let mut active: configs::ActiveModel = config.into();
+            active.content = Set(content);
+            active.update(db.get_ref()).await?;
+            rotated_configs += 1;
+        }
+    }
+
+    let mut rotated_values = 0;
+    if !config_ids.is_empty() {
+        let values = ConfigValues::find()
+            .filter(config_values::Column::ConfigId.is_in(config_ids))
+            .all(db.get_ref())
+            .await?;
+        for value in values {
+            let Some(Value::String(envelope)) = &value.value_data else {
+                continue;
+            };
+            if let Some(data) = keys.rewrap(envelope)? {
+                let mut active: config_values::ActiveModel = value.into();
+                active.value_data = Set(Some(Value::String(data)));
+                active.updated_at = Set(Utc::now().into());
+                active.update(db.get_ref()).await?;
+                rotated_values += 1;
+            }
+        }
+    }
+
+    let response = ConfigSecretRotateResponse {
+        active_key_id: keys.active_key_id().to_string(),
+        rotated_configs,
+        rotated_values,
+    };
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "configs",
+        "rotate_keys",
+        &req,
+        None,
+        Some(serde_json::to_value(&response).unwrap_or_default()),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        base64::engine::general_purpose::STANDARD.encode([byte; 32])
+    }
+
+    #[test]
+    fn test_parse_master_keys() {
+        let keys = MasterKeys::parse(&format!("k2:{}, k1:{}", key(2), key(1))).unwrap();
+        assert_eq!(keys.active_key_id(), "k2");
+        assert!(MasterKeys::parse("").is_err());
+        assert!(MasterKeys::parse("k1:c2hvcnQ=").is_err());
+        assert!(MasterKeys::parse(&format!("k1:{},k1:{}", key(1), key(2))).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let keys = MasterKeys::parse(&format!("k1:{}", key(1))).unwrap();
+        let envelope = keys.encrypt(b"{\"password\":\"p@ss\"}").unwrap();
+        assert!(envelope.starts_with("enc:v1:k1:"));
+        assert!(!envelope.contains("p@ss"));
+        assert_eq!(keys.decrypt(&envelope).unwrap(), b"{\"password\":\"p@ss\"}");
+
+        let other = MasterKeys::parse(&format!("k1:{}", key(9))).unwrap();
+        assert!(other.decrypt(&envelope).is_err());
+        assert!(keys.decrypt("plain text").is_err());
+    }
+
+    #[test]
+    fn test_rewrap_keeps_content() {
+        let old = MasterKeys::parse(&format!("k1:{}", key(1))).unwrap();
+        let envelope = old.encrypt(b"secret").unwrap();
+
+        let rotated = MasterKeys::parse(&format!("k2:{},k1:{}", key(2), key(1))).unwrap();
+        let rewrapped = rotated.rewrap(&envelope).unwrap().unwrap();
+        assert!(rewrapped.starts_with("enc:v1:k2:"));
+        // 内容密文不变，仅数据密钥重新加密
+        assert_eq!(rewrapped.rsplit(':').next(), envelope.rsplit(':').next());
+        assert_eq!(rotated.decrypt(&rewrapped).unwrap(), b"secret");
+        assert_eq!(rotated.rewrap(&rewrapped).unwrap(), None);
+
+        let new_only = MasterKeys::parse(&format!("k2:{}", key(2))).unwrap();
+        assert_eq!(new_only.decrypt(&rewrapped).unwrap(), b"secret");
+    }
+}
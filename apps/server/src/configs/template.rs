@@ -0,0 +1,666 @@
+/// 配置模板：基础配置 + 环境覆盖层 + 变量
+///
+/// 设置了 base_code 的配置作为覆盖层，与基础配置（可继续引用自己的基础配置）逐层合并，
+/// 基础配置取同一环境的发布版本，未发布时渲染与写入校验均失败：
+/// - object：深度合并，覆盖层中值为 null 的字段从结果中移除；
+/// - array：按项的 `code` 合并（与 `value_sync::sync_config_values` 的键一致），同 code 的项深度合并，新 code 追加；
+/// - html/text：覆盖层整体替换。
+///
+/// 合并后将字符串中的 `${name}` 占位符替换为项目/应用变量（`$${name}` 保留原文），
+/// 变量优先级：应用+环境 > 应用 > 项目+环境 > 项目。
+use crate::configs::client::{environment_str, parse_environment};
+use crate::configs::handlers::scoped;
+use crate::configs::models::{
+    ConfigFieldSource, ConfigRenderLayer, ConfigRenderQuery, ConfigRenderResponse,
+    ConfigVariableUsage,
+};
+use crate::configs::publish::content_value;
+use crate::configs::secrets::{plain_content, SECRET_MASK};
+use crate::configs::variables::valid_variable_name;
+use crate::entities::configs::{self, Column, Entity as Configs};
+use crate::entities::{
+    config_releases, config_variables, Applications, ConfigReleases, ConfigVariables,
+};
+use crate::members::request_scope;
+use crate::shared::enums::{ConfigType, Environment};
+use crate::shared::error::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// 基础配置最大引用层数
+const MAX_TEMPLATE_DEPTH: usize = 5;
+
+/// 字段路径 -> 来源层序号（0 为最底层的基础配置）
+pub type FieldSources = BTreeMap<String, usize>;
+
+fn child_path(base: &str, key: &str) -> String {
+    if base.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", base, key)
+    }
+}
+
+fn clear_sources(sources: &mut FieldSources, path: &str) {
+    sources.retain(|p, _| {
+        !(p == path || p.starts_with(&format!("{}.", path)) || p.starts_with(&format!("{}[", path)))
+    });
+}
+
+/// 记录值的叶子字段来源，数组整体视为一个字段
+fn record_leaves(value: &Value, path: &str, layer: usize, sources: &mut FieldSources) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                record_leaves(child, &child_path(path, key), layer, sources);
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), layer);
+        }
+    }
+}
+
+fn merge_object(
+    base: &mut Map<String, Value>,
+    overlay: Map<String, Value>,
+    path: &str,
+    layer: usize,
+    sources: &mut FieldSources,
+) {
+    for (key, value) in overlay {
+        let path = child_path(path, &key);
+        match (base.get_mut(&key), value) {
+            (_, Value::Null) => {
+                base.remove(&key);
+                clear_sources(sources, &path);
+            }
+            (Some(Value::Object(existing)), Value::Object(value)) => {
+                merge_object(existing, value, &path, layer, sources);
+            }
+            (_, value) => {
+                clear_sources(sources, &path);
+                record_leaves(&value, &path, layer, sources);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn item_code(item: &Value) -> Option<&str> {
+    item.get("code").and_then(Value::as_str)
+}
+
+fn merge_array(
+    base: &mut Vec<Value>,
+    overlay: Vec<Value>,
+    layer: usize,
+    sources: &mut FieldSources,
+) {
+    for item in overlay {
+        let existing = item_code(&item).and_then(|code| {
+            base.iter()
+                .position(|b| item_code(b) == Some(code))
+                .map(|i| (i, format!("[{}]", code)))
+        });
+        match (existing, item) {
+            (Some((index, path)), Value::Object(item)) if base[index].is_object() => {
+                if let Value::Object(target) = &mut base[index] {
+                    merge_object(target, item, &path, layer, sources);
+                }
+            }
+            (Some((index, path)), item) => {
+                clear_sources(sources, &path);
+                record_leaves(&item, &path, layer, sources);
+                base[index] = item;
+            }
+            (None, item) => {
+                let path = match item_code(&item) {
+                    Some(code) => format!("[{}]", code),
+                    None => format!("[{}]", base.len()),
+                };
+                record_leaves(&item, &path, layer, sources);
+                base.push(item);
+            }
+        }
+    }
+}
+
+/// 按配置类型逐层合并，返回合并结果与各字段来源
+pub fn merge_layers(config_type: &ConfigType, layers: Vec<Value>) -> (Value, FieldSources) {
+    let mut sources = FieldSources::new();
+    let last = layers.len().saturating_sub(1);
+    match config_type {
+        ConfigType::Object => {
+            let mut merged = Map::new();
+            for (layer, value) in layers.into_iter().enumerate() {
+                if let Value::Object(value) = value {
+                    merge_object(&mut merged, value, "", layer, &mut sources);
+                }
+            }
+            (Value::Object(merged), sources)
+        }
+        ConfigType::Array => {
+            let mut merged = Vec::new();
+            for (layer, value) in layers.into_iter().enumerate() {
+                if let Value::Array(items) = value {
+                    merge_array(&mut merged, items, layer, &mut sources);
+                }
+            }
+            (Value::Array(merged), sources)
+        }
+        ConfigType::Html | ConfigType::Text => {
+            let value = layers.into_iter().last().unwrap_or(Value::Null);
+            sources.insert("$".to_string(), last);
+            (value, sources)
+        }
+    }
+}
+
+/// 替换字符串中的 `${name}`，未定义的变量保留原文并记录
+fn substitute(
+    text: &str,
+    variables: &HashMap<String, String>,
+    used: &mut BTreeSet<String>,
+    unresolved: &mut BTreeSet<String>,
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        // `$${name}` 转义为字面量 `${name}`
+        if rest[..start].ends_with('$') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) if valid_variable_name(&after[..end]) => {
+                let name = &after[..end];
+                match variables.get(name) {
+                    Some(value) => {
+                        out.push_str(value);
+                        used.insert(name.to_string());
+                    }
+                    None => {
+                        out.push_str(&rest[start..start + end + 3]);
+                        unresolved.insert(name.to_string());
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push_str("${");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 替换值中所有字符串里的占位符
+pub fn resolve_placeholders(
+    value: &mut Value,
+    variables: &HashMap<String, String>,
+    used: &mut BTreeSet<String>,
+    unresolved: &mut BTreeSet<String>,
+) {
+    match value {
+        Value::String(s) if s.contains("${") => {
+            *s = substitute(s, variables, used, unresolved);
+        }
+        Value::Array(items) => {
+            for item in items {
+                resolve_placeholders(item, variables, used, unresolved);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                resolve_placeholders(item, variables, used, unresolved);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 按优先级选出某环境生效的变量：name -> (value, 来源)
+pub fn pick_variables(
+    rows: &[config_variables::Model],
+    environment: &Environment,
+) -> HashMap<String, (String, String)> {
+    let mut picked: HashMap<String, (u8, String, String)> = HashMap::new();
+    for row in rows {
+        if row.environment.as_ref().is_some_and(|e| e != environment) {
+            continue;
+        }
+        let owner = if row.application_id.is_some() {
+            "application"
+        } else {
+            "project"
+        };
+        let (rank, source) = match (&row.environment, owner) {
+            (Some(e), "application") => (4, format!("application:{}", environment_str(e))),
+            (None, "application") => (3, owner.to_string()),
+            (Some(e), _) => (2, format!("project:{}", environment_str(e))),
+            (None, _) => (1, owner.to_string()),
+        };
+        if picked.get(&row.name).is_none_or(|(r, _, _)| *r < rank) {
+            picked.insert(row.name.clone(), (rank, row.value.clone(), source));
+        }
+    }
+    picked
+        .into_iter()
+        .map(|(name, (_, value, source))| (name, (value, source)))
+        .collect()
+}
+
+/// 加载应用及其所属项目的变量，未指定应用时没有变量
+pub async fn load_variables(
+    db: &DatabaseConnection,
+    application_id: Option<&str>,
+) -> Result<Vec<config_variables::Model>, ApiError> {
+    let Some(application_id) = application_id else {
+        return Ok(Vec::new());
+    };
+    let mut owner =
+        Condition::any().add(config_variables::Column::ApplicationId.eq(application_id));
+    if let Some(application) = Applications::find_by_id(application_id).one(db).await? {
+        owner = owner.add(config_variables::Column::ProjectId.eq(application.project_id));
+    }
+    Ok(ConfigVariables::find().filter(owner).all(db).await?)
+}
+
+/// 查找基础配置在同一环境的发布版本，未发布时报错（不回退到其他环境或草稿版本）
+async fn find_base(
+    db: &DatabaseConnection,
+    base_code: &str,
+    environment: &Environment,
+) -> Result<configs::Model, ApiError> {
+    let config_id = ConfigReleases::find()
+        .filter(config_releases::Column::Code.eq(base_code))
+        .filter(config_releases::Column::Environment.eq(environment.clone()))
+        .one(db)
+        .await?
+        .and_then(|r| r.config_id);
+    let base = match config_id {
+        Some(id) => {
+            Configs::find_by_id(id)
+                .filter(Column::DeletedAt.is_null())
+                .one(db)
+                .await?
+        }
+        None => None,
+    };
+    base.ok_or_else(|| {
+        ApiError::ValidationError(format!(
+            "基础配置 {} 在 {} 环境没有发布版本",
+            base_code,
+            environment_str(environment)
+        ))
+    })
+}
+
+/// 加载基础配置链（从最底层开始），检测循环引用
+///
+/// 基础配置须为全局共享配置或与本配置属于同一应用。
+pub async fn load_base_layers(
+    db: &DatabaseConnection,
+    code: &str,
+    base_code: Option<&str>,
+    application_id: Option<&str>,
+    environment: &Environment,
+    config_type: &ConfigType,
+) -> Result<Vec<configs::Model>, ApiError> {
+    let mut seen = vec![code.to_string()];
+    let mut layers = Vec::new();
+    let mut next = base_code.map(str::to_string);
+    while let Some(base_code) = next {
+        if seen.contains(&base_code) {
+            return Err(ApiError::ValidationError(format!(
+                "基础配置存在循环引用: {} -> {}",
+                seen.join(" -> "),
+                base_code
+            )));
+        }
+        if seen.len() > MAX_TEMPLATE_DEPTH {
+            return Err(ApiError::ValidationError(format!(
+                "基础配置引用层数超过 {}",
+                MAX_TEMPLATE_DEPTH
+            )));
+        }
+        let base = find_base(db, &base_code, environment).await?;
+        if base.application_id.is_some() && base.application_id.as_deref() != application_id {
+            return Err(ApiError::ValidationError(format!(
+                "基础配置 {} 属于其他应用",
+                base_code
+            )));
+        }
+        if &base.config_type != config_type {
+            return Err(ApiError::ValidationError(format!(
+                "基础配置 {} 的类型与本配置不一致",
+                base_code
+            )));
+        }
+        seen.push(base_code);
+        next = base.base_code.clone();
+        layers.push(base);
+    }
+    layers.reverse();
+    Ok(layers)
+}
+
+/// 将覆盖层内容与基础配置合并（不解析变量），用于写入时的校验
+pub async fn merge_with_base(
+    db: &DatabaseConnection,
+    code: &str,
+    base_code: &str,
+    application_id: Option<&str>,
+    environment: &Environment,
+    config_type: &ConfigType,
+    overlay: Value,
+) -> Result<Value, ApiError> {
+    let bases = load_base_layers(
+        db,
+        code,
+        Some(base_code),
+        application_id,
+        environment,
+        config_type,
+    )
+    .await?;
+    let mut layers = Vec::with_capacity(bases.len() + 1);
+    for base in &bases {
+        layers.push(content_value(&base.config_type, &plain_content(base)?));
+    }
+    layers.push(overlay);
+    Ok(merge_layers(config_type, layers).0)
+}
+
+/// 配置的渲染结果
+pub struct RenderedConfig {
+    /// 生效内容（object/array 为 JSON 文本）
+    pub content: String,
+    /// 参与合并的各层，最后一层为配置本身
+    pub layers: Vec<configs::Model>,
+    pub sources: FieldSources,
+    /// 使用到的变量：name -> (value, 来源)
+    pub variables: BTreeMap<String, (String, String)>,
+    pub unresolved: Vec<String>,
+}
+
+/// 渲染配置：合并基础配置并解析变量。没有基础配置和占位符的配置原样返回内容
+pub async fn render(
+    db: &DatabaseConnection,
+    config: configs::Model,
+    variables: &[config_variables::Model],
+) -> Result<RenderedConfig, ApiError> {
+    let plain = plain_content(&config)?;
+    if config.base_code.is_none() && !plain.contains("${") {
+        let mut sources = FieldSources::new();
+        sources.insert("$".to_string(), 0);
+        return Ok(RenderedConfig {
+            content: plain,
+            layers: vec![config],
+            sources,
+            variables: BTreeMap::new(),
+            unresolved: Vec::new(),
+        });
+    }
+
+    let mut layers = load_base_layers(
+        db,
+        &config.code,
+        config.base_code.as_deref(),
+        config.application_id.as_deref(),
+        &config.environment,
+        &config.config_type,
+    )
+    .await?;
+    let mut values = Vec::with_capacity(layers.len() + 1);
+    for layer in &layers {
+        values.push(content_value(&layer.config_type, &plain_content(layer)?));
+    }
+    values.push(content_value(&config.config_type, &plain));
+    let config_type = config.config_type.clone();
+    let environment = config.environment.clone();
+    layers.push(config);
+
+    let (mut merged, sources) = merge_layers(&config_type, values);
+    let picked = pick_variables(variables, &environment);
+    let lookup: HashMap<String, String> = picked
+        .iter()
+        .map(|(name, (value, _))| (name.clone(), value.clone()))
+        .collect();
+    let mut used = BTreeSet::new();
+    let mut unresolved = BTreeSet::new();
+    resolve_placeholders(&mut merged, &lookup, &mut used, &mut unresolved);
+
+    let content = match merged {
+        Value::String(text) => text,
+        other => serde_json::to_string(&other)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?,
+    };
+    Ok(RenderedConfig {
+        content,
+        layers,
+        sources,
+        variables: used
+            .into_iter()
+            .filter_map(|name| picked.get(&name).cloned().map(|v| (name, v)))
+            .collect(),
+        unresolved: unresolved.into_iter().collect(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/configs/code/{code}/environment/{environment}/render",
+    params(
+        ("code" = String, Path, description = "Config code"),
+        ("environment" = String, Path, description = "Environment name"),
+        ConfigRenderQuery
+    ),
+    responses(
+        (status = 200, description = "Effective config with field sources", body = ConfigRenderResponse),
+        (status = 404, description = "Config not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn render_config(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    query: web::Query<ConfigRenderQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let (code, environment) = path.into_inner();
+    let environment = parse_environment(&environment)
+        .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))?;
+    let scope = request_scope(&db, &req).await?;
+
+    // 默认渲染发布版本，尚未发布时渲染最新版本
+    let visible = scoped(Configs::find(), &scope)
+        .filter(Column::Code.eq(&code))
+        .filter(Column::Environment.eq(environment.clone()))
+        .filter(Column::DeletedAt.is_null());
+    let config_id = match query.config_id.clone().filter(|id| !id.is_empty()) {
+        Some(id) => Some(id),
+        None => ConfigReleases::find()
+            .filter(config_releases::Column::Code.eq(&code))
+            .filter(config_releases::Column::Environment.eq(environment.clone()))
+            .one(db.get_ref())
+            .await?
+            .and_then(|r| r.config_id),
+    };
+    let config = match config_id {
+        Some(id) => visible.filter(Column::Id.eq(id)).one(db.get_ref()).await?,
+        None => {
+            visible
+                .order_by_desc(Column::Version)
+                .one(db.get_ref())
+                .await?
+        }
+    }
+    .ok_or_else(|| ApiError::NotFound("Config not found".to_string()))?;
+
+    // 全局配置可指定应用以预览该应用的变量
+    let application_id = match (&config.application_id, &query.application_id) {
+        (Some(own), _) => Some(own.clone()),
+        (None, Some(id)) if !id.is_empty() => {
+            scope.ensure_application(id)?;
+            Some(id.clone())
+        }
+        _ => None,
+    };
+    let variables = load_variables(&db, application_id.as_deref()).await?;
+    let config_type = config.config_type.clone();
+    let rendered = render(&db, config, &variables).await?;
+
+    let layers = &rendered.layers;
+    let response = ConfigRenderResponse {
+        code,
+        environment,
+        config_type,
+        // 任一层为密钥配置时内容打码
+        content: if layers.iter().any(|l| l.is_secret) {
+            SECRET_MASK.to_string()
+        } else {
+            rendered.content.clone()
+        },
+        layers: layers
+            .iter()
+            .map(|l| ConfigRenderLayer {
+                config_id: l.id.clone(),
+                code: l.code.clone(),
+                environment: l.environment.clone(),
+                version: l.version as u32,
+                status: l.status.clone(),
+            })
+            .collect(),
+        sources: rendered
+            .sources
+            .iter()
+            .map(|(path, layer)| ConfigFieldSource {
+                path: path.clone(),
+                layer: *layer,
+                code: layers[*layer].code.clone(),
+                version: layers[*layer].version as u32,
+            })
+            .collect(),
+        variables: rendered
+            .variables
+            .iter()
+            .map(|(name, (value, source))| ConfigVariableUsage {
+                name: name.clone(),
+                value: value.clone(),
+                source: source.clone(),
+            })
+            .collect(),
+        unresolved: rendered.unresolved.clone(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_object_layers() {
+        let (merged, sources) = merge_layers(
+            &ConfigType::Object,
+            vec![
+                json!({"db": {"host": "localhost", "port": 5432}, "debug": true, "tags": ["a"]}),
+                json!({"db": {"host": "prod-db"}, "debug": null, "tags": ["b", "c"]}),
+            ],
+        );
+        assert_eq!(
+            merged,
+            json!({"db": {"host": "prod-db", "port": 5432}, "tags": ["b", "c"]})
+        );
+        assert_eq!(sources.get("db.host"), Some(&1));
+        assert_eq!(sources.get("db.port"), Some(&0));
+        assert_eq!(sources.get("tags"), Some(&1));
+        assert!(!sources.contains_key("debug"));
+    }
+
+    #[test]
+    fn test_merge_array_by_code() {
+        let (merged, sources) = merge_layers(
+            &ConfigType::Array,
+            vec![
+                json!([{"code": "redis", "name": "Redis", "port": 6379}, {"code": "mq", "name": "MQ"}]),
+                json!([{"code": "redis", "port": 6380}, {"code": "es", "name": "ES"}]),
+            ],
+        );
+        assert_eq!(
+            merged,
+            json!([
+                {"code": "redis", "name": "Redis", "port": 6380},
+                {"code": "mq", "name": "MQ"},
+                {"code": "es", "name": "ES"}
+            ])
+        );
+        assert_eq!(sources.get("[redis].port"), Some(&1));
+        assert_eq!(sources.get("[redis].name"), Some(&0));
+        assert_eq!(sources.get("[es].name"), Some(&1));
+    }
+
+    #[test]
+    fn test_resolve_placeholders() {
+        let variables: HashMap<String, String> =
+            [("db_host".to_string(), "10.0.0.1".to_string())].into();
+        let mut value = json!({"url": "jdbc://${db_host}:${db_port}/app", "raw": "$${db_host}"});
+        let mut used = BTreeSet::new();
+        let mut unresolved = BTreeSet::new();
+        resolve_placeholders(&mut value, &variables, &mut used, &mut unresolved);
+        assert_eq!(
+            value,
+            json!({"url": "jdbc://10.0.0.1:${db_port}/app", "raw": "${db_host}"})
+        );
+        assert!(used.contains("db_host"));
+        assert!(unresolved.contains("db_port"));
+    }
+
+    #[test]
+    fn test_pick_variables_precedence() {
+        let row =
+            |project: Option<&str>, app: Option<&str>, env: Option<Environment>, value: &str| {
+                config_variables::Model {
+                    id: value.to_string(),
+                    project_id: project.map(str::to_string),
+                    application_id: app.map(str::to_string),
+                    environment: env,
+                    name: "host".to_string(),
+                    value: value.to_string(),
+                    description: None,
+                    created_by: "u".to_string(),
+                    updated_by: "u".to_string(),
+                    revision: 1,
+                    created_at: chrono::Utc::now().into(),
+                    updated_at: chrono::Utc::now().into(),
+                }
+            };
+        let rows = vec![
+            row(Some("p"), None, None, "project"),
+            row(Some("p"), None, Some(Environment::Prod), "project-prod"),
+            row(None, Some("a"), None, "app"),
+            row(None, Some("a"), Some(Environment::Dev), "app-dev"),
+        ];
+        let prod = pick_variables(&rows, &Environment::Prod);
+        assert_eq!(prod["host"], ("app".to_string(), "application".to_string()));
+        let dev = pick_variables(&rows, &Environment::Dev);
+        assert_eq!(dev["host"].0, "app-dev");
+        let project_only = pick_variables(&rows[..2], &Environment::Prod);
+        assert_eq!(
+            project_only["host"],
+            ("project-prod".to_string(), "project:prod".to_string())
+        );
+    }
+}
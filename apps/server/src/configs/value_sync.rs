@@ -2,6 +2,7 @@ use chrono::Utc;
 use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde_json::Value;
 
+use crate::configs::secrets::seal_value;
 use crate::entities::{config_values, ConfigValues};
 use crate::shared::error::ApiError;
 use crate::shared::generate_snowflake_id;
@@ -13,6 +14,7 @@ pub async fn sync_config_values(
     environment: &str,
     version: i32,
     items: &[Value],
+    is_secret: bool,
 ) -> Result<(), ApiError> {
     // collect incoming codes
     let mut incoming_codes = std::collections::HashSet::new();
@@ -28,6 +30,8 @@ pub async fn sync_config_values(
             ApiError::ValidationError("每个项必须包含字符串字段 name".to_string())
         })?;
         incoming_codes.insert(code.to_string());
+        // 密钥配置的选项值同样加密保存
+        let value_data = seal_value(is_secret, Value::Object(obj.clone()))?;
 
         // 查找当前有效记录
         let existing = ConfigValues::find()
@@ -44,7 +48,7 @@ pub async fn sync_config_values(
             // 更新现有记录，revision +1
             let mut am: config_values::ActiveModel = model.into();
             am.value_name = Set(name.to_string());
-            am.value_data = Set(Some(Value::Object(obj.clone())));
+            am.value_data = Set(Some(value_data.clone()));
             am.revision = Set(am.revision.unwrap() + 1);
             am.updated_at = Set(Utc::now().into());
             am.update(db)
@@ -65,7 +69,7 @@ pub async fn sync_config_values(
             if let Some(model) = soft_deleted {
                 let mut am: config_values::ActiveModel = model.into();
                 am.value_name = Set(name.to_string());
-                am.value_data = Set(Some(Value::Object(obj.clone())));
+                am.value_data = Set(Some(value_data.clone()));
                 am.deleted_at = Set(None);
                 am.revision = Set(am.revision.unwrap() + 1);
                 am.updated_at = Set(Utc::now().into());
@@ -82,7 +86,7 @@ pub async fn sync_config_values(
                     version: Set(version),
                     value_code: Set(code.to_string()),
                     value_name: Set(name.to_string()),
-                    value_data: Set(Some(Value::Object(obj.clone()))),
+                    value_data: Set(Some(value_data.clone())),
                     revision: Set(1),
                     deleted_at: Set(None),
                     created_at: Set(Utc::now().into()),
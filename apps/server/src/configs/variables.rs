@@ -0,0 +1,251 @@
+/// 配置变量管理
+///
+/// 变量属于项目或应用，可限定环境；渲染配置时用于替换内容中的 `${name}` 占位符。
+use crate::auth::middleware::get_user_id_from_request;
+use crate::configs::client::parse_environment;
+use crate::configs::models::{
+    ConfigVariableQuery, ConfigVariableResponse, ConfigVariableUpsertRequest,
+};
+use crate::configs::watch::ConfigWatchHub;
+use crate::entities::config_variables::{ActiveModel, Column, Entity as ConfigVariables};
+use crate::members::{request_scope, DataScope};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, ModelTrait,
+    QueryFilter, QueryOrder, Set,
+};
+
+fn variable_snapshot(variable: &ConfigVariableResponse) -> serde_json::Value {
+    serde_json::to_value(variable).unwrap_or_default()
+}
+
+/// 变量名：字母或下划线开头，后续为字母、数字、`_`、`.`、`-`
+pub fn valid_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// 校验变量所属的项目或应用在数据范围内
+fn ensure_owner(
+    scope: &DataScope,
+    project_id: Option<&str>,
+    application_id: Option<&str>,
+) -> Result<(), ApiError> {
+    match (project_id, application_id) {
+        (Some(project_id), None) => scope.ensure_project(project_id),
+        (None, Some(application_id)) => scope.ensure_application(application_id),
+        _ => Err(ApiError::ValidationError(
+            "project_id 与 application_id 必须且只能指定一个".to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/configs/variables",
+    params(ConfigVariableQuery),
+    responses(
+        (status = 200, description = "Config variables of the project or application", body = Vec<ConfigVariableResponse>)
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn list_config_variables(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<ConfigVariableQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let project_id = query.project_id.as_deref().filter(|id| !id.is_empty());
+    let application_id = query.application_id.as_deref().filter(|id| !id.is_empty());
+    if project_id.is_none() && application_id.is_none() {
+        return Err(ApiError::ValidationError(
+            "project_id 或 application_id 至少指定一个".to_string(),
+        ));
+    }
+
+    let scope = request_scope(&db, &req).await?;
+    let mut owner = Condition::any();
+    if let Some(project_id) = project_id {
+        scope.ensure_project(project_id)?;
+        owner = owner.add(Column::ProjectId.eq(project_id));
+    }
+    if let Some(application_id) = application_id {
+        scope.ensure_application(application_id)?;
+        owner = owner.add(Column::ApplicationId.eq(application_id));
+    }
+
+    let variables = ConfigVariables::find()
+        .filter(owner)
+        .order_by_asc(Column::Name)
+        .order_by_asc(Column::Environment)
+        .all(db.get_ref())
+        .await?;
+    let data: Vec<ConfigVariableResponse> = variables.into_iter().map(Into::into).collect();
+    Ok(HttpResponse::Ok().json(data))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/configs/variables",
+    request_body = ConfigVariableUpsertRequest,
+    responses(
+        (status = 200, description = "Config variable saved", body = ConfigVariableResponse),
+        (status = 400, description = "Invalid variable")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn upsert_config_variable(
+    db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
+    body: web::Json<ConfigVariableUpsertRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let body = body.into_inner();
+    let name = body.name.trim().to_string();
+    if !valid_variable_name(&name) {
+        return Err(ApiError::ValidationError(format!(
+            "变量名 {} 不合法：需以字母或下划线开头，仅包含字母、数字、_ . -",
+            name
+        )));
+    }
+    let project_id = body.project_id.filter(|id| !id.is_empty());
+    let application_id = body.application_id.filter(|id| !id.is_empty());
+    let environment = match body.environment.as_deref().filter(|e| !e.is_empty()) {
+        Some(environment) => Some(
+            parse_environment(environment)
+                .ok_or_else(|| ApiError::ValidationError("Invalid environment".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let scope = request_scope(&db, &req).await?;
+    ensure_owner(&scope, project_id.as_deref(), application_id.as_deref())?;
+    let user_id = get_user_id_from_request(&req)?;
+
+    let mut query = ConfigVariables::find().filter(Column::Name.eq(&name));
+    query = match &project_id {
+        Some(id) => query.filter(Column::ProjectId.eq(id)),
+        None => query.filter(Column::ProjectId.is_null()),
+    };
+    query = match &application_id {
+        Some(id) => query.filter(Column::ApplicationId.eq(id)),
+        None => query.filter(Column::ApplicationId.is_null()),
+    };
+    query = match &environment {
+        Some(environment) => query.filter(Column::Environment.eq(environment.clone())),
+        None => query.filter(Column::Environment.is_null()),
+    };
+
+    let (saved, before, action) = match query.one(db.get_ref()).await? {
+        Some(existing) => {
+            let before = variable_snapshot(&existing.clone().into());
+            let revision = existing.revision;
+            let mut active: ActiveModel = existing.into();
+            active.value = Set(body.value);
+            active.description = Set(body.description);
+            active.updated_by = Set(user_id);
+            active.revision = Set(revision + 1);
+            active.updated_at = Set(Utc::now().into());
+            (active.update(db.get_ref()).await?, Some(before), "update")
+        }
+        None => {
+            let active = ActiveModel {
+                id: Set(generate_snowflake_id()),
+                project_id: Set(project_id),
+                application_id: Set(application_id),
+                environment: Set(environment),
+                name: Set(name),
+                value: Set(body.value),
+                description: Set(body.description),
+                created_by: Set(user_id.clone()),
+                updated_by: Set(user_id),
+                revision: Set(1),
+                created_at: Set(Utc::now().into()),
+                updated_at: Set(Utc::now().into()),
+            };
+            (active.insert(db.get_ref()).await?, None, "create")
+        }
+    };
+
+    // 变量参与配置渲染，唤醒 watch 重新比对
+    hub.notify();
+    let response = ConfigVariableResponse::from(saved);
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "config_variables",
+        action,
+        &req,
+        before,
+        Some(variable_snapshot(&response)),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/configs/variables/{id}",
+    params(("id" = String, Path, description = "Variable ID")),
+    responses(
+        (status = 200, description = "Config variable deleted"),
+        (status = 404, description = "Variable not found")
+    ),
+    security(("bearer_auth" = [])),
+    tag = "Configs"
+)]
+pub async fn delete_config_variable(
+    db: web::Data<DatabaseConnection>,
+    hub: web::Data<ConfigWatchHub>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let variable = ConfigVariables::find_by_id(path.into_inner())
+        .one(db.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Variable not found".to_string()))?;
+    let scope = request_scope(&db, &req).await?;
+    ensure_owner(
+        &scope,
+        variable.project_id.as_deref(),
+        variable.application_id.as_deref(),
+    )?;
+
+    let before = variable_snapshot(&variable.clone().into());
+    variable.delete(db.get_ref()).await?;
+    hub.notify();
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db.get_ref(),
+        "config_variables",
+        "delete",
+        &req,
+        Some(before),
+        None,
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json("Variable deleted successfully"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_variable_name() {
+        assert!(valid_variable_name("db_host"));
+        assert!(valid_variable_name("_private"));
+        assert!(valid_variable_name("redis.port-1"));
+        assert!(!valid_variable_name(""));
+        assert!(!valid_variable_name("1abc"));
+        assert!(!valid_variable_name("a b"));
+        assert!(!valid_variable_name("a}"));
+    }
+}
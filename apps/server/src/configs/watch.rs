@@ -0,0 +1,36 @@
+/// 配置变更推送中心
+///
+/// 客户端长轮询 watch 配置时在此登记等待，配置发布、回滚、修改或变量修改后唤醒全部等待请求，
+/// 由各请求重新渲染并比对所关注配置的修订。配置变更由人工操作触发、频率低，
+/// 因此不按配置细分等待队列；等待机制与任务下发共用。
+use crate::instance_tasks::dispatch::TaskDispatchHub;
+use std::future::Future;
+use std::time::Duration;
+
+/// 所有 watch 请求共用的等待键
+const WATCH_KEY: &str = "configs";
+
+#[derive(Default)]
+pub struct ConfigWatchHub {
+    waiters: TaskDispatchHub,
+}
+
+impl ConfigWatchHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 通知配置已变更
+    pub fn notify(&self) {
+        self.waiters.notify([WATCH_KEY]);
+    }
+
+    /// 等待所关注配置的变更
+    pub async fn wait_for<T, E, F, Fut>(&self, max_wait: Duration, fetch: F) -> Result<Vec<T>, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Vec<T>, E>>,
+    {
+        self.waiters.wait_for(WATCH_KEY, max_wait, fetch).await
+    }
+}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "agent_releases")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub version: String,
+    pub platform: String,
+    pub file_id: String,
+    pub file_size: i64,
+    pub sha256: String,
+    pub signature: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,47 @@
+use crate::shared::enums::{AlertSeverity, AlertStatus};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "alert_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub rule_id: String,
+    pub fingerprint: String,
+    pub instance_id: Option<String>,
+    pub application_id: Option<String>,
+    pub status: AlertStatus,
+    pub severity: AlertSeverity,
+    pub summary: String,
+    pub value: Option<f64>,
+    pub started_at: DateTimeWithTimeZone,
+    pub fired_at: Option<DateTimeWithTimeZone>,
+    pub resolved_at: Option<DateTimeWithTimeZone>,
+    pub last_value_at: DateTimeWithTimeZone,
+    pub acknowledged_by: Option<String>,
+    pub acknowledged_at: Option<DateTimeWithTimeZone>,
+    pub last_notified_at: Option<DateTimeWithTimeZone>,
+    pub notify_count: i32,
+    pub notify_error: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::alert_rules::Entity",
+        from = "Column::RuleId",
+        to = "super::alert_rules::Column::Id"
+    )]
+    Rule,
+}
+
+impl Related<super::alert_rules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Rule.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
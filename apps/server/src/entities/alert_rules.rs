@@ -0,0 +1,41 @@
+use crate::shared::enums::{AlertRuleType, AlertSeverity};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "alert_rules")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub rule_type: AlertRuleType,
+    pub severity: AlertSeverity,
+    pub application_id: Option<String>,
+    pub instance_ids: Json,
+    pub condition: Json,
+    pub channel_ids: Json,
+    pub repeat_interval_minutes: i32,
+    pub notify_resolved: bool,
+    pub enabled: bool,
+    pub last_evaluated_at: Option<DateTimeWithTimeZone>,
+    pub created_by: String,
+    pub updated_by: String,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::alert_events::Entity")]
+    Events,
+}
+
+impl Related<super::alert_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Events.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -18,6 +18,7 @@ pub struct Model {
     pub updated_by: String,                       // 改为String支持雪花ID
     pub deleted_at: Option<DateTimeWithTimeZone>, // 支持毫秒精度
     pub revision: i32,
+    pub agent_target_version: Option<String>, // Agent 目标版本
     pub created_at: DateTimeWithTimeZone, // 支持毫秒精度
     pub updated_at: DateTimeWithTimeZone, // 支持毫秒精度
 }
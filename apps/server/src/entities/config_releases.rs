@@ -0,0 +1,26 @@
+use crate::shared::enums::Environment;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "config_releases")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub code: String,
+    pub environment: Environment,
+    pub application_id: Option<String>,
+    pub config_id: Option<String>, // 当前发布的版本，为空表示尚未发布
+    pub version: Option<i32>,
+    pub require_approval: bool,
+    pub published_by: Option<String>,
+    pub published_at: Option<DateTimeWithTimeZone>,
+    pub revision: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
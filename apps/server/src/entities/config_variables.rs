@@ -0,0 +1,26 @@
+use crate::shared::enums::Environment;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "config_variables")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub project_id: Option<String>,       // 项目级变量
+    pub application_id: Option<String>,   // 应用级变量
+    pub environment: Option<Environment>, // 为空表示所有环境
+    pub name: String,
+    pub value: String,
+    pub description: Option<String>,
+    pub created_by: String,
+    pub updated_by: String,
+    pub revision: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,4 @@
-use crate::shared::enums::{ConfigType, Environment};
+use crate::shared::enums::{ConfigStatus, ConfigType, Environment};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -22,6 +22,16 @@ pub struct Model {
     pub updated_at: DateTimeWithTimeZone, // 支持毫秒精度
     pub generate_values: bool,
     pub schema: Option<Json>,
+    pub application_id: Option<String>, // 所属应用，为空表示全局共享配置
+    pub status: ConfigStatus,
+    pub submitted_by: Option<String>,
+    pub submitted_at: Option<DateTimeWithTimeZone>,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTimeWithTimeZone>,
+    pub review_comment: Option<String>,
+    pub source_config_id: Option<String>, // 晋级来源版本
+    pub is_secret: bool,                  // 密钥配置，content 为密文
+    pub base_code: Option<String>,        // 基础配置，设置后本配置为覆盖层
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "instance_metric_rollups")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub instance_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub resolution: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub metric: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub bucket_start: DateTimeWithTimeZone,
+    pub sample_count: i64,
+    pub sum_value: f64,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub p95_value: f64,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::instances::Entity",
+        from = "Column::InstanceId",
+        to = "super::instances::Column::Id"
+    )]
+    Instance,
+}
+
+impl Related<super::instances::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Instance.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
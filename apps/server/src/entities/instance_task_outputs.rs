@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "instance_task_outputs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub record_id: String,
+    pub attempt: i32,
+    pub seq: i64,
+    pub stream: String,
+    pub content: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::instance_task_records::Entity",
+        from = "Column::RecordId",
+        to = "super::instance_task_records::Column::Id"
+    )]
+    Record,
+}
+
+impl Related<super::instance_task_records::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Record.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
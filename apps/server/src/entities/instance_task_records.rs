@@ -19,6 +19,7 @@ pub struct Model {
     pub result_data: Option<Json>,
     pub error_message: Option<String>,
     pub retry_attempt: Option<i32>,
+    pub batch_index: Option<i32>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
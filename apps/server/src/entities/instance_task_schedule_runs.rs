@@ -0,0 +1,35 @@
+use crate::shared::enums::ScheduleRunStatus;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "instance_task_schedule_runs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub schedule_id: String,
+    pub scheduled_at: DateTimeWithTimeZone,
+    pub triggered_at: DateTimeWithTimeZone,
+    pub status: ScheduleRunStatus,
+    pub task_id: Option<String>,
+    pub message: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::instance_task_schedules::Entity",
+        from = "Column::ScheduleId",
+        to = "super::instance_task_schedules::Column::Id"
+    )]
+    Schedule,
+}
+
+impl Related<super::instance_task_schedules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Schedule.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
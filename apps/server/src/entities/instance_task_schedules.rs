@@ -0,0 +1,40 @@
+use crate::shared::enums::MisfirePolicy;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "instance_task_schedules")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub application_id: Option<String>,
+    pub enabled: bool,
+    pub cron_expression: String,
+    pub timezone: String,
+    pub misfire_policy: MisfirePolicy,
+    pub allow_overlap: bool,
+    pub task_template: Json,
+    pub next_run_at: Option<DateTimeWithTimeZone>,
+    pub last_run_at: Option<DateTimeWithTimeZone>,
+    pub created_by: String,
+    pub updated_by: String,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::instance_task_schedule_runs::Entity")]
+    Runs,
+}
+
+impl Related<super::instance_task_schedule_runs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Runs.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
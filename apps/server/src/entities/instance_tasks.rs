@@ -10,6 +10,9 @@ pub struct Model {
     pub task_name: String,
     pub task_type: TaskType,
     pub target_instances: Json,
+    pub target_selector: Option<Json>,
+    pub rollout_strategy: Option<Json>,
+    pub rollout_state: Option<Json>,
     pub task_content: Json,
     pub priority: Option<i32>,
     pub timeout_seconds: Option<i32>,
@@ -26,6 +26,9 @@ pub struct Model {
     pub report_count: Option<i32>,
     pub custom_fields: Option<Json>,
     pub config: Option<Json>,
+    pub config_revision: i32,
+    pub config_applied_revision: Option<i32>,
+    pub config_applied_at: Option<DateTimeWithTimeZone>,
     pub agent_type: Option<AgentType>,
     pub agent_version: Option<String>,
     pub cpu_usage_percent: Option<Decimal>,
@@ -17,6 +17,7 @@ pub struct Model {
     pub log_type: enums::LogType,           // 新增：日志类型
     pub timestamp: DateTimeWithTimeZone,    // 支持毫秒精度
     pub created_at: DateTimeWithTimeZone,   // 支持毫秒精度
+    pub client_log_id: Option<String>,      // Agent 侧日志ID，用于重发去重
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,32 +1,60 @@
+pub mod agent_releases;
+pub mod alert_channels;
+pub mod alert_events;
+pub mod alert_rules;
+pub mod alert_silences;
 pub mod applications;
+pub mod config_releases;
 pub mod config_values;
+pub mod config_variables;
 pub mod configs;
 pub mod files;
+pub mod instance_credentials;
+pub mod instance_metric_rollups;
 pub mod instance_records;
+pub mod instance_task_outputs;
 pub mod instance_task_records;
+pub mod instance_task_schedule_runs;
+pub mod instance_task_schedules;
 pub mod instance_tasks;
 pub mod instances;
 pub mod logs;
+pub mod maintenance_checkpoints;
 pub mod password_reset_tokens;
 pub mod permissions;
 pub mod projects;
+pub mod resource_members;
 pub mod role_permissions;
 pub mod roles;
 pub mod user_roles;
 pub mod users;
 
+pub use agent_releases::Entity as AgentReleases;
+pub use alert_channels::Entity as AlertChannels;
+pub use alert_events::Entity as AlertEvents;
+pub use alert_rules::Entity as AlertRules;
+pub use alert_silences::Entity as AlertSilences;
 pub use applications::Entity as Applications;
+pub use config_releases::Entity as ConfigReleases;
 pub use config_values::Entity as ConfigValues;
+pub use config_variables::Entity as ConfigVariables;
 pub use configs::Entity as Configs;
 pub use files::Entity as Files;
+pub use instance_credentials::Entity as InstanceCredentials;
+pub use instance_metric_rollups::Entity as InstanceMetricRollups;
 pub use instance_records::Entity as InstanceRecords;
+pub use instance_task_outputs::Entity as InstanceTaskOutputs;
 pub use instance_task_records::Entity as InstanceTaskRecords;
+pub use instance_task_schedule_runs::Entity as InstanceTaskScheduleRuns;
+pub use instance_task_schedules::Entity as InstanceTaskSchedules;
 pub use instance_tasks::Entity as InstanceTasks;
 pub use instances::Entity as Instances;
 pub use logs::Entity as Logs;
+pub use maintenance_checkpoints::Entity as MaintenanceCheckpoints;
 pub use password_reset_tokens::Entity as PasswordResetTokens;
 pub use permissions::Entity as Permissions;
 pub use projects::Entity as Projects;
+pub use resource_members::Entity as ResourceMembers;
 pub use role_permissions::Entity as RolePermissions;
 pub use roles::Entity as Roles;
 pub use user_roles::Entity as UserRoles;
@@ -0,0 +1,21 @@
+use crate::shared::enums::{MemberResourceType, MemberType};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "resource_members")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: String,
+    pub resource_type: MemberResourceType,
+    pub resource_id: String,
+    pub member_type: MemberType,
+    pub member_id: String,
+    pub created_by: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -13,10 +13,39 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::agent_auth::verify_agent_token_for_instance;
 use crate::auth::middleware::get_user_id_from_request_not_throw;
-use crate::entities::files;
+use crate::entities::{files, instances};
+use crate::members::{ensure_instance_access, resolve_user_scope};
 use crate::shared::error::ApiError;
 
+/// 登录用户按数据范围校验实例归属；未登录的调用须指定实例，并按该实例的令牌规则校验 Agent
+async fn ensure_file_instance_access(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    instance_id: Option<&str>,
+) -> Result<(), ApiError> {
+    let instance_id = instance_id.filter(|id| !id.is_empty());
+    let user_id = get_user_id_from_request_not_throw(req);
+    if user_id.is_empty() {
+        let instance_id = instance_id
+            .ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+        let instance = instances::Entity::find_by_id(instance_id)
+            .filter(instances::Column::DeletedAt.is_null())
+            .one(db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Instance not found".to_string()))?;
+        // 与其他开放接口一致：应用未强制且实例尚未签发令牌时允许匿名访问
+        verify_agent_token_for_instance(db, req, &instance).await?;
+        return Ok(());
+    }
+    let scope = resolve_user_scope(db, &user_id).await?;
+    match instance_id {
+        Some(instance_id) => ensure_instance_access(db, &scope, instance_id).await,
+        None => scope.ensure_unrestricted(),
+    }
+}
+
 // 引入模型
 use super::models::{
     FileChunkUploadResponse, FileInfoWithUrl, FileListQuery, FileListResponseWithUrl,
@@ -69,9 +98,9 @@ pub async fn init_file_upload(
     // 获取用户ID
     let _user_id = get_user_id_from_request_not_throw(&req);
 
-    if upload_request.instance_id == None {
-        return Err(ApiError::BadRequest("Instance ID is required".to_string()));
-    }
+    // 未关联实例的文件（如 Agent 发布包）仅允许登录且不受数据范围限制的用户上传；
+    // 后续分片与完成请求凭本次生成的 upload_id 关联到已鉴权的会话
+    ensure_file_instance_access(&db, &req, upload_request.instance_id.as_deref()).await?;
 
     // 生成文件记录ID，作为会话与上传ID
     let file_id = Uuid::new_v4().to_string();
@@ -552,6 +581,7 @@ pub async fn download_file(
             ApiError::InternalServerError(format!("Failed to query file from database: {}", e))
         })?
         .ok_or_else(|| ApiError::NotFound("File not found".to_string()))?;
+    ensure_file_instance_access(&db, &req, file_record.instance_id.as_deref()).await?;
 
     // 检查文件是否存在
     let file_path = Path::new(&file_record.file_path);
@@ -578,6 +608,7 @@ pub async fn get_files(
             "task_id and instance_id are required".to_string(),
         ));
     }
+    ensure_file_instance_access(&db, &req, Some(&instance_id)).await?;
 
     let mut select = files::Entity::find()
         .filter(files::Column::TaskId.eq(Some(task_id)))
@@ -1,14 +1,19 @@
+use crate::agent_auth::verify_agent_token;
 use crate::entities::{applications, instance_records, instances, logs};
 use crate::instance_reports::models::{
-    InstanceRecordListQuery, InstanceRecordListResponse, InstanceRecordResponse,
-    InstanceReportRequest, InstanceReportResponse, Pagination,
+    AgentLogBatchRequest, AgentLogBatchResponse, AgentLogItem, InstanceRecordListQuery,
+    InstanceRecordListResponse, InstanceRecordResponse, InstanceReportRequest,
+    InstanceReportResponse, Pagination,
 };
-use crate::shared::enums::{LogSource, OnlineStatus, Status};
+use crate::instance_reports::redaction::redact_environment;
+use crate::members::request_scope;
+use crate::shared::enums::{LogLevel, LogSource, LogType, OnlineStatus, Status};
 use crate::shared::error::ApiError;
 use crate::shared::{generate_snowflake_id, get_trace_id_from_request};
 use actix_web::{web, HttpRequest, HttpResponse, Result};
 use chrono::Utc;
 use rust_decimal::Decimal;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
     QueryOrder, QuerySelect, Set,
@@ -16,8 +21,11 @@ use sea_orm::{
 use serde_json::json;
 use std::str::FromStr;
 
+/// 单批日志最大条数
+const MAX_LOG_BATCH_SIZE: usize = 1000;
+
 /// POST /api/open/instances/report
-/// 实例信息上报接口（开放接口，无需认证）
+/// 实例信息上报接口（开放接口，使用实例令牌认证）
 pub async fn report_instance_info(
     req: HttpRequest,
     db: web::Data<DatabaseConnection>,
@@ -39,8 +47,8 @@ pub async fn report_instance_info(
         .one(&**db)
         .await?;
 
-    let application_id = match application {
-        Some(app) => app.id,
+    let application = match application {
+        Some(app) => app,
         None => {
             return Err(ApiError::NotFound(format!(
                 "Application with code '{}' not found",
@@ -49,6 +57,16 @@ pub async fn report_instance_info(
         }
     };
 
+    // 3. 校验实例令牌（应用开启强制校验或请求携带令牌时）
+    verify_agent_token(&db, &req, &application, &request.agent_instance_id).await?;
+    let application_id = application.id;
+
+    // 4. 环境变量脱敏（兜底未在本地脱敏的旧版 Agent）
+    let environment = request.environment.clone().map(|mut env| {
+        redact_environment(&mut env);
+        env
+    });
+
     // 5. 验证实例是否存在，如果不存在则自动创建
     // 首先通过 agent_instance_id 查找实例
     let instance = instances::Entity::find()
@@ -68,7 +86,7 @@ pub async fn report_instance_info(
             ip_address: Set(request.network_info.ip_address.clone().unwrap_or_default()),
             status: Set(Status::Active),
             online_status: Set(OnlineStatus::Online),
-            environment: Set(request.environment.clone()),
+            environment: Set(environment.clone()),
             application_id: Set(application_id.clone()),
             mac_address: Set(request.network_info.mac_address.clone()),
             public_ip: Set(request.network_info.public_ip.clone()),
@@ -82,6 +100,9 @@ pub async fn report_instance_info(
             report_count: Set(None),
             custom_fields: Set(request.custom_fields.clone()),
             config: Set(None),
+            config_revision: Set(0),
+            config_applied_revision: Set(None),
+            config_applied_at: Set(None),
             agent_type: Set(Some(request.agent_type.clone())),
             agent_version: Set(request.agent_version.clone()),
             cpu_usage_percent: Set(None),
@@ -163,6 +184,10 @@ pub async fn report_instance_info(
     let first_report_at_is_none = instance.first_report_at.is_none();
 
     let config_value = instance.config.clone();
+    // 仅下发过配置的实例才返回版本号
+    let config_revision = (instance.config_revision > 0).then_some(instance.config_revision);
+    let applied_revision_changed = request.config_applied_revision.is_some()
+        && request.config_applied_revision != instance.config_applied_revision;
     let instance_id_db = instance.id.clone();
     let mut instance_update: instances::ActiveModel = instance.into();
     instance_update.agent_type = Set(Some(request.agent_type.clone()));
@@ -180,12 +205,17 @@ pub async fn report_instance_info(
     instance_update.mac_address = Set(request.network_info.mac_address.clone());
     instance_update.port = Set(request.network_info.port.clone());
     instance_update.program_path = Set(request.program_path.clone());
-    instance_update.environment = Set(request.environment.clone());
+    instance_update.environment = Set(environment);
     instance_update.application_id = Set(application_id.clone());
     instance_update.agent_instance_id = Set(Some(request.agent_instance_id.clone()));
     instance_update.profiles = Set(request.profiles.clone());
     instance_update.custom_fields = Set(request.custom_fields.clone());
     instance_update.last_report_at = Set(Some(Utc::now().into()));
+    // 记录 Agent 确认生效的配置版本
+    if applied_revision_changed {
+        instance_update.config_applied_revision = Set(request.config_applied_revision);
+        instance_update.config_applied_at = Set(Some(Utc::now().into()));
+    }
     // 恢复为 Active 并清空 offline_at
     instance_update.status = Set(Status::Active);
     instance_update.online_status = Set(OnlineStatus::Online);
@@ -211,40 +241,13 @@ pub async fn report_instance_info(
     let mut log_failure_count: u32 = 0;
     if let Some(agent_logs) = &request.agent_logs {
         for item in agent_logs {
-            let now = Utc::now();
-            let ts = match &item.timestamp {
-                Some(t) => chrono::DateTime::parse_from_rfc3339(t)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or(now),
-                None => now,
-            };
-
-            let ctx = match &item.context {
-                Some(c) => json!({
-                    "agent_instance_id": request.agent_instance_id,
-                    "instance_id": instance_id_db,
-                    "extra": c,
-                }),
-                None => json!({
-                    "agent_instance_id": request.agent_instance_id,
-                    "instance_id": instance_id_db,
-                }),
-            };
-
-            let active = logs::ActiveModel {
-                id: Set(generate_snowflake_id()),
-                application_id: Set(Some(application_id.clone())),
-                instance_id: Set(Some(instance_id_db.clone())),
-                trace_id: Set(Some(trace_id.clone())),
-                log_level: Set(item.log_level.clone()),
-                message: Set(item.message.clone()),
-                context: Set(Some(ctx)),
-                log_source: Set(LogSource::Agent),
-                log_type: Set(crate::shared::enums::LogType::System),
-                timestamp: Set(ts.into()),
-                created_at: Set(Utc::now().into()),
-            };
-
+            let active = agent_log_model(
+                item,
+                &application_id,
+                &instance_id_db,
+                &request.agent_instance_id,
+                &trace_id,
+            );
             match active.insert(&**db).await {
                 Ok(_) => log_success_count += 1,
                 Err(_) => log_failure_count += 1,
@@ -265,17 +268,182 @@ pub async fn report_instance_info(
         log_success_count: Some(log_success_count),
         log_failure_count: Some(log_failure_count),
         config: config_value,
+        config_revision,
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// 构造 Agent 日志入库记录
+fn agent_log_model(
+    item: &AgentLogItem,
+    application_id: &str,
+    instance_id: &str,
+    agent_instance_id: &str,
+    trace_id: &str,
+) -> logs::ActiveModel {
+    let now = Utc::now();
+    let ts = match &item.timestamp {
+        Some(t) => chrono::DateTime::parse_from_rfc3339(t)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(now),
+        None => now,
+    };
+
+    let ctx = match &item.context {
+        Some(c) => json!({
+            "agent_instance_id": agent_instance_id,
+            "instance_id": instance_id,
+            "extra": c,
+        }),
+        None => json!({
+            "agent_instance_id": agent_instance_id,
+            "instance_id": instance_id,
+        }),
+    };
+
+    logs::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        application_id: Set(Some(application_id.to_string())),
+        instance_id: Set(Some(instance_id.to_string())),
+        trace_id: Set(Some(trace_id.to_string())),
+        log_level: Set(item.log_level.clone()),
+        message: Set(item.message.clone()),
+        context: Set(Some(ctx)),
+        log_source: Set(LogSource::Agent),
+        log_type: Set(item.log_type.clone().unwrap_or(LogType::System)),
+        timestamp: Set(ts.into()),
+        created_at: Set(now.into()),
+        client_log_id: Set(item.log_id.clone()),
+    }
+}
+
+/// POST /api/open/instances/logs
+/// Agent 日志批量上报接口（开放接口，使用实例令牌认证）
+///
+/// 与实例信息上报分离，整批以单条语句写入，Agent 根据返回的计数确认或重试该批次；
+/// 重试时已入库的日志按 log_id 忽略，不会重复写入。
+pub async fn ship_agent_logs(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<AgentLogBatchRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if request.application_code.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "application_code cannot be empty".to_string(),
+        ));
+    }
+    if request.logs.len() > MAX_LOG_BATCH_SIZE {
+        return Err(ApiError::BadRequest(format!(
+            "too many logs in one batch (max {})",
+            MAX_LOG_BATCH_SIZE
+        )));
+    }
+
+    let trace_id = get_trace_id_from_request(&req);
+    let application = applications::Entity::find()
+        .filter(applications::Column::Code.eq(&request.application_code))
+        .filter(applications::Column::DeletedAt.is_null())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Application with code '{}' not found",
+                request.application_code
+            ))
+        })?;
+    verify_agent_token(&db, &req, &application, &request.agent_instance_id).await?;
+
+    // 实例由首次上报创建，尚未创建时 Agent 稍后重试
+    let instance = instances::Entity::find()
+        .filter(instances::Column::AgentInstanceId.eq(&request.agent_instance_id))
+        .filter(instances::Column::ApplicationId.eq(&application.id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Instance not found".to_string()))?;
+
+    let mut models: Vec<logs::ActiveModel> = request
+        .logs
+        .iter()
+        .map(|item| {
+            agent_log_model(
+                item,
+                &application.id,
+                &instance.id,
+                &request.agent_instance_id,
+                &trace_id,
+            )
+        })
+        .collect();
+    if request.dropped_count > 0 {
+        let notice = AgentLogItem {
+            log_level: LogLevel::Warn,
+            message: format!(
+                "Agent 日志缓冲溢出，已丢弃 {} 条最早的日志",
+                request.dropped_count
+            ),
+            context: Some(json!({ "dropped_count": request.dropped_count })),
+            log_type: None,
+            timestamp: None,
+            log_id: Some(format!("dropped:{}", request.batch_id)),
+        };
+        models.push(agent_log_model(
+            &notice,
+            &application.id,
+            &instance.id,
+            &request.agent_instance_id,
+            &trace_id,
+        ));
+    }
+
+    let total = request.logs.len() as u32;
+    let (log_success_count, log_failure_count) = if models.is_empty() {
+        (0, 0)
+    } else {
+        // 响应丢失后重发的日志已入库，按 client_log_id 忽略并视为成功
+        let insert = logs::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([logs::Column::InstanceId, logs::Column::ClientLogId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&**db)
+            .await;
+        match insert {
+            Ok(_) => (total, 0),
+            Err(e) => {
+                log::error!(
+                    "写入 Agent 日志失败 instance={} batch={}: {}",
+                    instance.id,
+                    request.batch_id,
+                    e
+                );
+                (0, total)
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(AgentLogBatchResponse {
+        status: if log_failure_count == 0 {
+            "success".to_string()
+        } else {
+            "failed".to_string()
+        },
+        batch_id: request.batch_id.clone(),
+        instance_id: instance.id,
+        log_success_count,
+        log_failure_count,
+    }))
+}
+
 /// GET /api/instances/{instance_id}/reports
 /// 查询实例上报历史记录（需要认证）
 pub async fn get_instance_reports(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
     query: web::Query<InstanceRecordListQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let instance_id = path.into_inner();
 
@@ -286,9 +454,12 @@ pub async fn get_instance_reports(
         .one(&**db)
         .await?;
 
-    if instance.is_none() {
+    let Some(instance) = instance else {
         return Err(ApiError::NotFound("Instance not found".to_string()));
-    }
+    };
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&instance.application_id)?;
 
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(20).min(100); // 最大100条
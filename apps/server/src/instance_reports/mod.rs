@@ -1,5 +1,6 @@
 pub mod handlers;
 pub mod models;
+pub mod redaction;
 pub mod routes;
 
 pub use routes::{instance_report_routes, open_instance_report_routes};
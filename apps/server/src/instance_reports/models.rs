@@ -29,6 +29,9 @@ pub struct InstanceReportRequest {
     pub report_timestamp: String, // ISO 8601 format
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_logs: Option<Vec<AgentLogItem>>,
+    /// Agent 当前已生效的配置版本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_applied_revision: Option<i32>,
 }
 
 /// 系统信息
@@ -86,10 +89,16 @@ pub struct RuntimeInfo {
 pub struct AgentLogItem {
     pub log_level: enums::LogLevel,
     pub message: String,
+    /// 日志类型，缺省为 Agent 自身的 system 日志
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_type: Option<enums::LogType>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<String>,
+    /// Agent 侧日志ID，同一实例内重复上报的日志只入库一次
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_id: Option<String>,
 }
 
 /// 实例信息上报响应
@@ -106,6 +115,36 @@ pub struct InstanceReportResponse {
     pub log_failure_count: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Option<JsonValue>,
+    /// 下发配置的版本，Agent 生效后在上报中回传
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_revision: Option<i32>,
+}
+
+// ===================================================================
+// Agent 日志批量上报模型
+// ===================================================================
+
+/// Agent 日志批量上报请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentLogBatchRequest {
+    pub agent_instance_id: String,
+    pub application_code: String,
+    /// 批次ID，重发同一批时保持不变，用于确认、排查与溢出提示去重
+    pub batch_id: String,
+    pub logs: Vec<AgentLogItem>,
+    /// 自上次成功上报以来因缓冲溢出丢弃的日志条数
+    #[serde(default)]
+    pub dropped_count: u64,
+}
+
+/// Agent 日志批量上报响应：整批写入成功或整批失败
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentLogBatchResponse {
+    pub status: String,
+    pub batch_id: String,
+    pub instance_id: String,
+    pub log_success_count: u32,
+    pub log_failure_count: u32,
 }
 
 // ===================================================================
@@ -10,6 +10,8 @@ pub fn instance_report_routes(cfg: &mut web::ServiceConfig) {
 }
 
 pub fn open_instance_report_routes(cfg: &mut web::ServiceConfig) {
-    // 实例信息上报（开放接口，无需认证）
+    // 实例信息上报（开放接口，使用实例令牌认证）
     cfg.route("/report", web::post().to(handlers::report_instance_info));
+    // Agent 日志批量上报（开放接口，使用实例令牌认证）
+    cfg.route("/logs", web::post().to(handlers::ship_agent_logs));
 }
@@ -0,0 +1,220 @@
+/// 任务推送中心
+///
+/// Agent 长轮询拉取任务时在此登记等待，创建、重试或重置任务记录后
+/// 由管理端通知对应实例，只唤醒受影响实例的等待连接，避免所有 Agent 每 2 秒查询一次数据库。
+/// 多实例部署时通知不会跨进程传播，因此等待期间仍按兜底间隔查询数据库。
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// 未收到通知时重新查询数据库的间隔
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct TaskDispatchHub {
+    /// 实例ID -> 任务版本号，每次通知递增
+    waiters: Mutex<HashMap<String, watch::Sender<u64>>>,
+}
+
+impl TaskDispatchHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 通知实例有新的待执行任务
+    pub fn notify<I, S>(&self, instance_ids: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let waiters = self.waiters.lock().unwrap();
+        for instance_id in instance_ids {
+            if let Some(sender) = waiters.get(instance_id.as_ref()) {
+                sender.send_modify(|version| *version = version.wrapping_add(1));
+            }
+        }
+    }
+
+    /// 当前等待中的实例数
+    pub fn waiting_instances(&self) -> usize {
+        self.waiters.lock().unwrap().len()
+    }
+
+    fn subscribe(&self, instance_id: &str) -> watch::Receiver<u64> {
+        let mut waiters = self.waiters.lock().unwrap();
+        waiters
+            .entry(instance_id.to_string())
+            .or_insert_with(|| watch::channel(0).0)
+            .subscribe()
+    }
+
+    fn unsubscribe(&self, instance_id: &str, receiver: watch::Receiver<u64>) {
+        let mut waiters = self.waiters.lock().unwrap();
+        drop(receiver);
+        if waiters
+            .get(instance_id)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            waiters.remove(instance_id);
+        }
+    }
+
+    /// 等待实例的待执行任务
+    ///
+    /// 先查询一次，无任务时挂起直到收到通知、到达兜底查询间隔或超时。
+    /// 登记等待发生在查询之前，查询期间到达的通知不会丢失。
+    pub async fn wait_for<T, E, F, Fut>(
+        &self,
+        instance_id: &str,
+        max_wait: Duration,
+        mut fetch: F,
+    ) -> Result<Vec<T>, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Vec<T>, E>>,
+    {
+        let deadline = Instant::now() + max_wait;
+        let mut receiver = self.subscribe(instance_id);
+        let result = loop {
+            receiver.borrow_and_update();
+            match fetch().await {
+                Ok(items) if items.is_empty() => {}
+                other => break other,
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                break Ok(Vec::new());
+            }
+            let wait = (deadline - now).min(FALLBACK_POLL_INTERVAL);
+            // 超时或收到通知都重新查询
+            let _ = tokio::time::timeout(wait, receiver.changed()).await;
+        };
+        self.unsubscribe(instance_id, receiver);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    type Store = Arc<Mutex<HashMap<String, Vec<u64>>>>;
+
+    fn fetch_from(store: &Store, instance_id: &str) -> Result<Vec<u64>, ()> {
+        Ok(store
+            .lock()
+            .unwrap()
+            .remove(instance_id)
+            .unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_task_delivered_immediately_after_notify() {
+        let hub = Arc::new(TaskDispatchHub::new());
+        let store: Store = Arc::default();
+
+        let waiter = {
+            let hub = hub.clone();
+            let store = store.clone();
+            tokio::spawn(async move {
+                hub.wait_for("instance-1", Duration::from_secs(30), || {
+                    let store = store.clone();
+                    async move { fetch_from(&store, "instance-1") }
+                })
+                .await
+            })
+        };
+
+        // 等待 Agent 进入等待状态
+        while hub.waiting_instances() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        let created = Instant::now();
+        store
+            .lock()
+            .unwrap()
+            .insert("instance-1".to_string(), vec![42]);
+        hub.notify(["instance-1"]);
+
+        let tasks = waiter.await.unwrap().unwrap();
+        let latency = created.elapsed();
+        assert_eq!(tasks, vec![42]);
+        assert!(
+            latency < Duration::from_millis(100),
+            "task delivered after {:?}",
+            latency
+        );
+        assert_eq!(hub.waiting_instances(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_notify_only_wakes_affected_instance() {
+        let hub = Arc::new(TaskDispatchHub::new());
+        let fetches = Arc::new(Mutex::new(0u32));
+
+        let waiter = {
+            let hub = hub.clone();
+            let fetches = fetches.clone();
+            tokio::spawn(async move {
+                hub.wait_for("instance-1", Duration::from_millis(300), || {
+                    let fetches = fetches.clone();
+                    async move {
+                        *fetches.lock().unwrap() += 1;
+                        Ok::<Vec<u64>, ()>(Vec::new())
+                    }
+                })
+                .await
+            })
+        };
+
+        while hub.waiting_instances() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        hub.notify(["instance-2"]);
+
+        let tasks = waiter.await.unwrap().unwrap();
+        assert!(tasks.is_empty());
+        // 仅首次查询与超时前的最后一次查询
+        assert_eq!(*fetches.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_notify_during_fetch_is_not_lost() {
+        let hub = Arc::new(TaskDispatchHub::new());
+        let store: Store = Arc::default();
+
+        let started = Instant::now();
+        let tasks = {
+            let hub_ref = hub.clone();
+            let store = store.clone();
+            let mut first = true;
+            hub.wait_for("instance-1", Duration::from_secs(30), move || {
+                let store = store.clone();
+                let hub = hub_ref.clone();
+                let first_fetch = std::mem::replace(&mut first, false);
+                async move {
+                    let tasks = fetch_from(&store, "instance-1");
+                    if first_fetch {
+                        // 查询结束后、进入等待前创建任务
+                        store
+                            .lock()
+                            .unwrap()
+                            .insert("instance-1".to_string(), vec![7]);
+                        hub.notify(["instance-1"]);
+                    }
+                    tasks
+                }
+            })
+            .await
+            .unwrap()
+        };
+
+        assert_eq!(tasks, vec![7]);
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+}
@@ -1,73 +1,151 @@
+use crate::agent_auth::verify_agent_token_for_instance;
 use crate::auth::middleware::get_user_id_from_request;
-use crate::entities::{instance_task_records, instance_tasks, instances};
+use crate::entities::{instance_task_outputs, instance_task_records, instance_tasks, instances};
+use crate::instance_tasks::dispatch::TaskDispatchHub;
 use crate::instance_tasks::models::*;
+use crate::instance_tasks::output::TaskOutputHub;
+use crate::instance_tasks::{output, rollout, selector};
+use crate::members::{ensure_instances_access, request_scope, resolve_user_scope, DataScope};
 use crate::shared::enums::TaskStatus;
 use crate::shared::error::ApiError;
 use crate::shared::generate_snowflake_id;
 use actix_web::{web, HttpRequest, HttpResponse, Result};
 use chrono::Utc;
+use sea_orm::sea_query::Expr;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, QuerySelect, Set,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
 };
 use serde_json::json;
 use std::time::Duration;
-use tokio::time::sleep;
 
 /// POST /api/instances/tasks
 /// 创建任务
 pub async fn create_task(
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<TaskDispatchHub>,
     request: web::Json<TaskCreateRequest>,
     req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let user_id = get_user_id_from_request(&req)?;
+    let scope = resolve_user_scope(&db, &user_id).await?;
+    ensure_targets_in_scope(
+        &db,
+        &scope,
+        request.application_id.as_deref(),
+        &request.target_instances,
+        request.target_selector.as_ref(),
+    )
+    .await?;
 
-    // 验证目标实例是否存在
-    for instance_id in &request.target_instances {
-        let instance = instances::Entity::find_by_id(instance_id)
-            .filter(instances::Column::DeletedAt.is_null())
-            .one(&**db)
+    let saved_task = insert_task(&db, &hub, &request, &user_id).await?;
+
+    let response = TaskResponse::from_entity(saved_task);
+    // 审计记录：创建任务
+    let after = serde_json::json!({
+        "id": response.id,
+        "task_name": response.task_name,
+        "task_type": response.task_type,
+        "application_id": response.application_id,
+        "created_at": response.created_at,
+        "updated_at": response.updated_at,
+    });
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &**db,
+        "instance_tasks",
+        "create",
+        &req,
+        None,
+        Some(after),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// 创建任务及其执行记录，并唤醒首批目标实例（手动创建与定时任务共用）
+pub(crate) async fn insert_task(
+    db: &DatabaseConnection,
+    hub: &TaskDispatchHub,
+    request: &TaskCreateRequest,
+    created_by: &str,
+) -> Result<instance_tasks::Model, ApiError> {
+    // 生成任务ID
+    let task_id = generate_snowflake_id();
+
+    // 解析目标实例：显式指定的实例与选择器匹配的实例取并集
+    let mut selector = request.target_selector.clone();
+    if let Some(selector) = selector.as_mut() {
+        selector.normalize(request.application_id.as_deref())?;
+    }
+    let target_instances =
+        resolve_target_instances(db, &request.target_instances, selector.as_ref(), &task_id)
             .await?;
+    let dispatch_mode = selector
+        .as_ref()
+        .is_some_and(|s| s.resolve_at == SelectorResolveAt::Dispatch);
+    if target_instances.is_empty() && !dispatch_mode {
+        return Err(ApiError::BadRequest(
+            "No target instances matched".to_string(),
+        ));
+    }
 
-        if instance.is_none() {
-            return Err(ApiError::NotFound(format!(
-                "Instance {} not found",
-                instance_id
-            )));
+    // 分批执行：首批置为pending，其余批次queued等待放行
+    let rollout_strategy = request.rollout_strategy.clone();
+    let mut rollout_state = None;
+    let mut batch_size = None;
+    if let Some(strategy) = &rollout_strategy {
+        strategy.validate()?;
+        if dispatch_mode {
+            return Err(ApiError::ValidationError(
+                "rollout_strategy cannot be combined with dispatch-time target resolution"
+                    .to_string(),
+            ));
         }
+        let size = strategy.batch_size_for(target_instances.len());
+        rollout_state = Some(TaskRolloutState::new(size, target_instances.len()));
+        batch_size = Some(size);
     }
 
-    // 生成任务ID
-    let task_id = generate_snowflake_id();
+    let selector_app_id = selector.as_ref().and_then(|s| s.application_id.clone());
 
     // 创建任务
     let task = instance_tasks::ActiveModel {
         id: Set(task_id.clone()),
         task_name: Set(request.task_name.clone()),
         task_type: Set(request.task_type.clone()),
-        target_instances: Set(json!(request.target_instances)),
+        target_instances: Set(json!(target_instances)),
+        target_selector: Set(selector.map(|s| json!(s))),
+        rollout_strategy: Set(rollout_strategy.map(|s| json!(s))),
+        rollout_state: Set(rollout_state.map(|s| json!(s))),
         task_content: Set(request.task_content.clone()),
         priority: Set(request.priority),
         timeout_seconds: Set(request.timeout_seconds),
         retry_count: Set(request.retry_count),
-        application_id: Set(request.application_id.clone()),
-        created_by: Set(user_id.clone()),
+        application_id: Set(request.application_id.clone().or(selector_app_id)),
+        created_by: Set(created_by.to_string()),
         created_at: Set(Utc::now().into()),
         updated_at: Set(Utc::now().into()),
         deleted_at: Set(None),
     };
 
-    let saved_task = task.insert(&**db).await?;
+    let saved_task = task.insert(db).await?;
 
     // 为每个目标实例创建执行记录
-    for instance_id in &request.target_instances {
+    let mut released = Vec::new();
+    for (index, instance_id) in target_instances.iter().enumerate() {
+        let batch_index = batch_size.map(|size| (index / size) as i32);
+        let status = if batch_index.unwrap_or(0) == 0 {
+            released.push(instance_id.as_str());
+            TaskStatus::Pending
+        } else {
+            TaskStatus::Queued
+        };
         let record_id = generate_snowflake_id();
         let record = instance_task_records::ActiveModel {
             id: Set(record_id),
             task_id: Set(task_id.clone()),
             instance_id: Set(instance_id.clone()),
-            status: Set(TaskStatus::Pending),
+            status: Set(status),
             dispatch_time: Set(None),
             start_time: Set(None),
             end_time: Set(None),
@@ -77,32 +155,184 @@ pub async fn create_task(
             result_data: Set(None),
             error_message: Set(None),
             retry_attempt: Set(Some(0)),
+            batch_index: Set(batch_index),
             created_at: Set(Utc::now().into()),
             updated_at: Set(Utc::now().into()),
         };
 
-        record.insert(&**db).await?;
+        record.insert(db).await?;
     }
 
-    let response = TaskResponse::from_entity(saved_task);
-    // 审计记录：创建任务
-    let after = serde_json::json!({
-        "id": response.id,
-        "task_name": response.task_name,
-        "task_type": response.task_type,
-        "application_id": response.application_id,
-        "created_at": response.created_at,
-        "updated_at": response.updated_at,
-    });
-    let _ = crate::shared::request_context::record_audit_log_simple(
-        &**db,
-        "instance_tasks",
-        "create",
-        &req,
-        None,
-        Some(after),
+    // 唤醒等待中的目标实例
+    hub.notify(released);
+
+    Ok(saved_task)
+}
+
+/// 解析任务目标实例（显式实例去重校验后与选择器结果合并）
+async fn resolve_target_instances(
+    db: &DatabaseConnection,
+    explicit: &[String],
+    selector: Option<&TaskTargetSelector>,
+    seed: &str,
+) -> Result<Vec<String>, ApiError> {
+    let mut targets: Vec<String> = Vec::new();
+
+    // 验证目标实例是否存在
+    for instance_id in explicit {
+        if targets.contains(instance_id) {
+            continue;
+        }
+        let instance = instances::Entity::find_by_id(instance_id)
+            .filter(instances::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        if instance.is_none() {
+            return Err(ApiError::NotFound(format!(
+                "Instance {} not found",
+                instance_id
+            )));
+        }
+        targets.push(instance_id.clone());
+    }
+
+    if let Some(selector) = selector {
+        let seed = selector.sample_seed.as_deref().unwrap_or(seed);
+        for instance in selector::resolve_targets(db, selector, seed).await? {
+            if !targets.contains(&instance.id) {
+                targets.push(instance.id);
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// 校验任务的应用、选择器应用与显式目标实例均在数据范围内
+pub(crate) async fn ensure_targets_in_scope(
+    db: &DatabaseConnection,
+    scope: &DataScope,
+    application_id: Option<&str>,
+    target_instances: &[String],
+    selector: Option<&TaskTargetSelector>,
+) -> Result<(), ApiError> {
+    if scope.is_all() {
+        return Ok(());
+    }
+    let selector_app_id = selector.and_then(|s| s.application_id.as_deref());
+    for app_id in [application_id, selector_app_id].into_iter().flatten() {
+        if !app_id.is_empty() {
+            scope.ensure_application(app_id)?;
+        }
+    }
+    ensure_instances_access(db, scope, target_instances).await
+}
+
+/// 任务是否在数据范围内：所属应用可访问，或未指定应用且为本人创建
+fn task_in_scope(scope: &DataScope, task: &instance_tasks::Model, user_id: &str) -> bool {
+    match &task.application_id {
+        Some(application_id) => scope.allows_application(application_id),
+        None => scope.is_all() || task.created_by == user_id,
+    }
+}
+
+/// 校验当前用户可访问指定任务
+async fn ensure_task_access(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    task: &instance_tasks::Model,
+) -> Result<(), ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    let scope = resolve_user_scope(db, &user_id).await?;
+    if task_in_scope(&scope, task, &user_id) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("无权访问该任务".to_string()))
+    }
+}
+
+/// 校验当前用户可访问执行记录所属的任务
+async fn ensure_record_access(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    record: &instance_task_records::Model,
+) -> Result<(), ApiError> {
+    let task = instance_tasks::Entity::find_by_id(&record.task_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
+    ensure_task_access(db, req, &task).await
+}
+
+/// POST /api/instances/tasks/targets/preview
+/// 预览任务目标实例（不创建任务）
+#[utoipa::path(
+    post,
+    path = "/api/instances/tasks/targets/preview",
+    request_body = TaskTargetPreviewRequest,
+    responses(
+        (status = 200, description = "返回匹配的实例", body = TaskTargetPreviewResponse),
+        (status = 400, description = "选择器参数错误"),
+        (status = 404, description = "实例不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Instance Tasks"
+)]
+pub async fn preview_task_targets(
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<TaskTargetPreviewRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let request = request.into_inner();
+    let scope = request_scope(&db, &req).await?;
+    ensure_targets_in_scope(
+        &db,
+        &scope,
+        request.application_id.as_deref(),
+        &request.target_instances,
+        request.target_selector.as_ref(),
     )
-    .await;
+    .await?;
+    let mut selector = request.target_selector;
+    if let Some(selector) = selector.as_mut() {
+        selector.normalize(request.application_id.as_deref())?;
+        if selector.percentage.is_some() && selector.sample_seed.is_none() {
+            selector.sample_seed = Some(generate_snowflake_id());
+        }
+    }
+    let sample_seed = selector.as_ref().and_then(|s| s.sample_seed.clone());
+
+    let target_ids = resolve_target_instances(
+        &db,
+        &request.target_instances,
+        selector.as_ref(),
+        sample_seed.as_deref().unwrap_or_default(),
+    )
+    .await?;
+
+    let mut data = Vec::with_capacity(target_ids.len());
+    if !target_ids.is_empty() {
+        let instances = instances::Entity::find()
+            .filter(instances::Column::Id.is_in(target_ids.clone()))
+            .all(&**db)
+            .await?;
+        for id in &target_ids {
+            if let Some(instance) = instances.iter().find(|i| &i.id == id) {
+                data.push(InstanceInfo::from_entity(instance.clone()));
+            }
+        }
+    }
+
+    let response = TaskTargetPreviewResponse {
+        total: data.len() as u32,
+        data,
+        sample_seed,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        trace_id: generate_snowflake_id(),
+    };
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -111,7 +341,10 @@ pub async fn create_task(
 pub async fn get_tasks(
     db: web::Data<DatabaseConnection>,
     query: web::Query<TaskListQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let scope = resolve_user_scope(&db, &user_id).await?;
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(20).min(100);
     let offset = (page - 1) * limit;
@@ -119,6 +352,19 @@ pub async fn get_tasks(
     let mut select =
         instance_tasks::Entity::find().filter(instance_tasks::Column::DeletedAt.is_null());
 
+    // 数据范围：可访问应用下的任务，以及本人创建的未指定应用的任务
+    if let Some(application_ids) = scope.application_filter() {
+        select = select.filter(
+            Condition::any()
+                .add(instance_tasks::Column::ApplicationId.is_in(application_ids))
+                .add(
+                    Condition::all()
+                        .add(instance_tasks::Column::ApplicationId.is_null())
+                        .add(instance_tasks::Column::CreatedBy.eq(&user_id)),
+                ),
+        );
+    }
+
     // 添加任务类型过滤
     if let Some(task_type) = &query.task_type {
         select = select.filter(instance_tasks::Column::TaskType.eq(task_type));
@@ -178,6 +424,7 @@ pub async fn get_tasks(
 pub async fn get_task(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let task_id = path.into_inner();
 
@@ -188,6 +435,7 @@ pub async fn get_task(
 
     match task {
         Some(task) => {
+            ensure_task_access(&db, &req, &task).await?;
             let response = TaskResponse::from_entity(task);
             Ok(HttpResponse::Ok().json(response))
         }
@@ -200,6 +448,7 @@ pub async fn get_task(
 pub async fn delete_task(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let task_id = path.into_inner();
 
@@ -210,6 +459,7 @@ pub async fn delete_task(
 
     match task {
         Some(task) => {
+            ensure_task_access(&db, &req, &task).await?;
             let mut active: instance_tasks::ActiveModel = task.clone().into();
             active.deleted_at = Set(Some(Utc::now().into()));
             active.updated_at = Set(Utc::now().into());
@@ -245,30 +495,81 @@ pub async fn delete_task(
     }
 }
 
-/// POST /api/instances/tasks/{task_id}/cancel
-/// 取消任务
-pub async fn cancel_task(
-    db: web::Data<DatabaseConnection>,
-    path: web::Path<String>,
-) -> Result<HttpResponse, ApiError> {
-    let task_id = path.into_inner();
+/// 取消任务的未完成记录（手动取消与分批执行中止共用）
+///
+/// 停止下发时解析、标记分批执行状态，并将 queued/pending/dispatched 记录置为 cancelled。
+pub(crate) async fn cancel_task_records(
+    db: &DatabaseConnection,
+    task_id: &str,
+    rollout_status: RolloutStatus,
+    message: Option<&str>,
+) -> Result<u64, ApiError> {
+    if let Some(task) = instance_tasks::Entity::find_by_id(task_id).one(db).await? {
+        let mut target_selector = task.target_selector.clone();
+        // 停止下发时解析，后续上线的实例不再加入
+        if let Some(selector) = target_selector.as_mut() {
+            selector::close_dispatch_window(selector);
+        }
+        let rollout_state = task
+            .rollout_state
+            .clone()
+            .and_then(|v| serde_json::from_value::<TaskRolloutState>(v).ok())
+            .filter(|state| state.status == RolloutStatus::Running)
+            .map(|mut state| {
+                state.status = rollout_status;
+                state.next_release_at = None;
+                state.message = message.map(|m| m.to_string());
+                json!(state)
+            });
+        if target_selector != task.target_selector || rollout_state.is_some() {
+            let mut active: instance_tasks::ActiveModel = task.clone().into();
+            active.target_selector = Set(target_selector);
+            if rollout_state.is_some() {
+                active.rollout_state = Set(rollout_state);
+            }
+            active.updated_at = Set(Utc::now().into());
+            active.update(db).await?;
+        }
+    }
 
-    // 将所有pending和dispatched状态的记录更新为cancelled
+    // 将所有queued、pending和dispatched状态的记录更新为cancelled
     let records = instance_task_records::Entity::find()
-        .filter(instance_task_records::Column::TaskId.eq(&task_id))
-        .filter(
-            instance_task_records::Column::Status
-                .is_in(vec![TaskStatus::Pending, TaskStatus::Dispatched]),
-        )
-        .all(&**db)
+        .filter(instance_task_records::Column::TaskId.eq(task_id))
+        .filter(instance_task_records::Column::Status.is_in(vec![
+            TaskStatus::Queued,
+            TaskStatus::Pending,
+            TaskStatus::Dispatched,
+        ]))
+        .all(db)
         .await?;
 
+    let mut cancelled = 0;
     for record in records {
         let mut active: instance_task_records::ActiveModel = record.into();
         active.status = Set(TaskStatus::Cancelled);
         active.updated_at = Set(Utc::now().into());
-        active.update(&**db).await?;
+        active.update(db).await?;
+        cancelled += 1;
     }
+    Ok(cancelled)
+}
+
+/// POST /api/instances/tasks/{task_id}/cancel
+/// 取消任务
+pub async fn cancel_task(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let task_id = path.into_inner();
+
+    let task = instance_tasks::Entity::find_by_id(&task_id)
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
+    ensure_task_access(&db, &req, &task).await?;
+
+    cancel_task_records(&db, &task_id, RolloutStatus::Cancelled, None).await?;
 
     // 审计记录：取消任务（状态批量更新）
     let _ = crate::audit::handlers::record_audit_log(
@@ -295,6 +596,7 @@ pub async fn get_task_records(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
     query: web::Query<TaskRecordListQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let task_id = path.into_inner();
 
@@ -304,9 +606,10 @@ pub async fn get_task_records(
         .one(&**db)
         .await?;
 
-    if task.is_none() {
+    let Some(task) = task else {
         return Err(ApiError::NotFound("Task not found".to_string()));
-    }
+    };
+    ensure_task_access(&db, &req, &task).await?;
 
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(20).min(100);
@@ -355,7 +658,9 @@ pub async fn get_task_records(
 /// 重试任务执行
 pub async fn retry_task_record(
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<TaskDispatchHub>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let record_id = path.into_inner();
 
@@ -365,17 +670,21 @@ pub async fn retry_task_record(
 
     match record {
         Some(record) => {
+            ensure_record_access(&db, &req, &record).await?;
             if record.status != TaskStatus::Failed && record.status != TaskStatus::Timeout {
                 return Err(ApiError::BadRequest(
                     "Only failed or timeout tasks can be retried".to_string(),
                 ));
             }
 
+            // 重新置为pending，由Agent下次拉取时执行
+            let instance_id = record.instance_id.clone();
             let mut active: instance_task_records::ActiveModel = record.into();
-            active.status = Set(TaskStatus::Dispatched);
+            active.status = Set(TaskStatus::Pending);
             active.retry_attempt = Set(Some(active.retry_attempt.unwrap().unwrap_or(0) + 1));
             active.updated_at = Set(Utc::now().into());
             active.update(&**db).await?;
+            hub.notify([instance_id]);
 
             Ok(HttpResponse::Ok().json(json!({
                 "status": "success",
@@ -390,7 +699,9 @@ pub async fn retry_task_record(
 /// 将任务记录状态置为Pending
 pub async fn set_task_record_pending(
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<TaskDispatchHub>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let record_id = path.into_inner();
 
@@ -400,10 +711,13 @@ pub async fn set_task_record_pending(
 
     match record {
         Some(record) => {
+            ensure_record_access(&db, &req, &record).await?;
+            let instance_id = record.instance_id.clone();
             let mut active: instance_task_records::ActiveModel = record.into();
             active.status = Set(TaskStatus::Pending);
             active.updated_at = Set(Utc::now().into());
             active.update(&**db).await?;
+            hub.notify([instance_id]);
 
             Ok(HttpResponse::Ok().json(json!({
                 "status": "success",
@@ -427,13 +741,17 @@ pub async fn set_task_record_pending(
     responses(
         (status = 200, description = "成功返回任务列表", body = TaskDispatchResponse),
         (status = 400, description = "缺少instance_id参数"),
+        (status = 401, description = "实例令牌无效"),
+        (status = 403, description = "实例令牌与实例不匹配"),
         (status = 404, description = "实例不存在"),
         (status = 500, description = "服务器错误")
     ),
     tag = "Instance Tasks"
 )]
 pub async fn get_instance_tasks(
+    req: HttpRequest,
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<TaskDispatchHub>,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> Result<HttpResponse, ApiError> {
     // 从查询参数中获取instance_id
@@ -467,6 +785,19 @@ pub async fn get_instance_tasks(
 
     let instance = instance.unwrap();
 
+    // 校验实例令牌，防止通过猜测 agent_instance_id 拉取其他实例的任务
+    verify_agent_token_for_instance(&db, &req, &instance).await?;
+
+    // 加入下发时解析且匹配该实例的任务
+    let added = selector::expand_dispatch_targets(&db, &instance).await?;
+    if added > 0 {
+        log::info!(
+            "[get_instance_tasks] Instance {} joined {} dispatch-time task(s)",
+            instance.id,
+            added
+        );
+    }
+
     // 检查是否启用长轮询
     let wait = query
         .get("wait")
@@ -478,80 +809,301 @@ pub async fn get_instance_tasks(
         .unwrap_or(30)
         .min(60);
 
-    let start_time = std::time::Instant::now();
     let max_duration = Duration::from_secs(timeout_secs);
+    let mut tasks = if wait {
+        hub.wait_for(&instance.id, max_duration, || {
+            fetch_pending_tasks(&db, &instance.id)
+        })
+        .await?
+    } else {
+        fetch_pending_tasks(&db, &instance.id).await?
+    };
 
-    loop {
-        // 查询pending状态的任务
-        let records = instance_task_records::Entity::find()
-            .filter(instance_task_records::Column::InstanceId.eq(&instance.id))
-            .filter(instance_task_records::Column::Status.eq(TaskStatus::Pending))
-            .order_by_asc(instance_task_records::Column::CreatedAt)
-            .all(&**db)
-            .await?;
+    // 按优先级降序排序
+    tasks.sort_by_key(|t| std::cmp::Reverse(t.priority));
 
-        if !records.is_empty() {
-            // 有待执行任务，构造响应
-            let mut tasks = Vec::new();
-
-            for record in records {
-                // 查询任务详情
-                let task = instance_tasks::Entity::find_by_id(&record.task_id)
-                    .filter(instance_tasks::Column::DeletedAt.is_null())
-                    .one(&**db)
-                    .await?;
-
-                if let Some(task) = task {
-                    tasks.push(TaskDispatchItem {
-                        task_id: task.id.clone(),
-                        record_id: record.id.clone(),
-                        instance_id: instance.id.clone(),
-                        task_type: task.task_type,
-                        task_content: task.task_content,
-                        timeout_seconds: task.timeout_seconds.unwrap_or(300),
-                        priority: task.priority.unwrap_or(5),
-                    });
-
-                    // 更新记录状态为dispatched
-                    let mut active: instance_task_records::ActiveModel = record.into();
-                    if active.status.unwrap() != TaskStatus::Dispatched {
-                        active.status = Set(TaskStatus::Dispatched);
-                        active.dispatch_time = Set(Some(Utc::now().into()));
-                        active.updated_at = Set(Utc::now().into());
-                        active.update(&**db).await?;
-                    }
-                }
-            }
+    let response = TaskDispatchResponse {
+        tasks,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
 
-            // 按优先级降序排序
-            tasks.sort_by(|a, b| b.priority.cmp(&a.priority));
+/// 查询实例的pending任务并标记为dispatched
+async fn fetch_pending_tasks(
+    db: &DatabaseConnection,
+    instance_id: &str,
+) -> Result<Vec<TaskDispatchItem>, ApiError> {
+    let records = instance_task_records::Entity::find()
+        .filter(instance_task_records::Column::InstanceId.eq(instance_id))
+        .filter(instance_task_records::Column::Status.eq(TaskStatus::Pending))
+        .order_by_asc(instance_task_records::Column::CreatedAt)
+        .all(db)
+        .await?;
 
-            let response = TaskDispatchResponse {
-                tasks,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            };
+    let mut tasks = Vec::new();
+    for record in records {
+        // 查询任务详情
+        let task = instance_tasks::Entity::find_by_id(&record.task_id)
+            .filter(instance_tasks::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
 
-            return Ok(HttpResponse::Ok().json(response));
-        }
+        if let Some(task) = task {
+            tasks.push(TaskDispatchItem {
+                task_id: task.id.clone(),
+                record_id: record.id.clone(),
+                instance_id: instance_id.to_string(),
+                task_type: task.task_type,
+                task_content: task.task_content,
+                timeout_seconds: task.timeout_seconds.unwrap_or(300),
+                priority: task.priority.unwrap_or(5),
+                retry_attempt: record.retry_attempt.unwrap_or(0),
+            });
 
-        // 如果不启用长轮询或已超时，返回空列表
-        if !wait || start_time.elapsed() >= max_duration {
-            let response = TaskDispatchResponse {
-                tasks: vec![],
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            };
-            return Ok(HttpResponse::Ok().json(response));
+            // 重新下发的记录清空上次执行的输出
+            output::clear_output(db, &record.id).await?;
+
+            // 更新记录状态为dispatched
+            let mut active: instance_task_records::ActiveModel = record.into();
+            active.status = Set(TaskStatus::Dispatched);
+            active.dispatch_time = Set(Some(Utc::now().into()));
+            active.updated_at = Set(Utc::now().into());
+            active.update(db).await?;
         }
+    }
+    Ok(tasks)
+}
+
+/// 查找 Agent 上报的执行记录，并校验记录所属实例与实例令牌，防止写入其他实例的执行记录
+async fn find_agent_record(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    record_id: &str,
+    agent_instance_id: &str,
+) -> Result<instance_task_records::Model, ApiError> {
+    let record = instance_task_records::Entity::find_by_id(record_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task record not found".to_string()))?;
+
+    let instance = instances::Entity::find_by_id(&record.instance_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Instance not found".to_string()))?;
+    if instance.agent_instance_id.as_deref() != Some(agent_instance_id) {
+        return Err(ApiError::Forbidden(
+            "Task record does not belong to this instance".to_string(),
+        ));
+    }
+    verify_agent_token_for_instance(db, req, &instance).await?;
+    Ok(record)
+}
+
+/// POST /api/open/instances/tasks/output
+/// Agent分段上报任务执行输出
+#[utoipa::path(
+    post,
+    path = "/api/open/instances/tasks/output",
+    request_body = TaskOutputSubmitRequest,
+    responses(
+        (status = 200, description = "输出接收成功", body = TaskOutputSubmitResponse),
+        (status = 400, description = "分段参数错误"),
+        (status = 401, description = "实例令牌无效"),
+        (status = 403, description = "任务记录不属于该实例"),
+        (status = 404, description = "任务记录不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Instance Tasks"
+)]
+pub async fn submit_task_output(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    output_hub: web::Data<TaskOutputHub>,
+    request: web::Json<TaskOutputSubmitRequest>,
+) -> Result<HttpResponse, ApiError> {
+    output::validate_chunks(&request.chunks)?;
+    let record =
+        find_agent_record(&db, &req, &request.record_id, &request.agent_instance_id).await?;
+
+    // 超时重试后，上一次执行迟到的输出不能混入本次执行
+    let attempt = request.retry_attempt.unwrap_or(0);
+    if attempt != record.retry_attempt.unwrap_or(0) {
+        return Err(ApiError::BadRequest(format!(
+            "Stale task output: retry attempt {} does not match current attempt {}",
+            attempt,
+            record.retry_attempt.unwrap_or(0)
+        )));
+    }
+    if output::is_finished(&record.status) {
+        return Err(ApiError::BadRequest(format!(
+            "Task record already finished with status {:?}",
+            record.status
+        )));
+    }
+
+    // 收到输出说明任务已开始执行
+    if record.status == TaskStatus::Dispatched {
+        let now: chrono::DateTime<chrono::FixedOffset> = Utc::now().into();
+        instance_task_records::Entity::update_many()
+            .col_expr(
+                instance_task_records::Column::Status,
+                Expr::value(TaskStatus::Running),
+            )
+            .col_expr(instance_task_records::Column::StartTime, Expr::value(now))
+            .col_expr(instance_task_records::Column::UpdatedAt, Expr::value(now))
+            .filter(instance_task_records::Column::Id.eq(&record.id))
+            .filter(instance_task_records::Column::Status.eq(TaskStatus::Dispatched))
+            .exec(&**db)
+            .await?;
+    }
+
+    let accepted = output::save_chunks(&db, &record.id, attempt, &request.chunks).await?;
+    if accepted > 0 {
+        output_hub.notify(&record.id);
+    }
+
+    let response = TaskOutputSubmitResponse {
+        status: "success".to_string(),
+        accepted,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
 
-        // 等待2秒后重试
-        sleep(Duration::from_secs(2)).await;
+/// 查询新输出，没有新输出且执行未结束时返回空列表
+async fn poll_task_output(
+    db: &DatabaseConnection,
+    record_id: &str,
+    after_seq: Option<i64>,
+    limit: u64,
+) -> Result<Vec<(Vec<instance_task_outputs::Model>, TaskStatus)>, ApiError> {
+    let record = instance_task_records::Entity::find_by_id(record_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task record not found".to_string()))?;
+    let attempt = record.retry_attempt.unwrap_or(0);
+    let chunks = output::fetch_chunks(db, record_id, attempt, after_seq, limit).await?;
+    if chunks.is_empty() && !output::is_finished(&record.status) {
+        return Ok(Vec::new());
     }
+    Ok(vec![(chunks, record.status)])
+}
+
+/// GET /api/instances/task-records/{record_id}/output
+/// 获取任务执行输出（wait=true 时持续跟随新输出）
+#[utoipa::path(
+    get,
+    path = "/api/instances/task-records/{record_id}/output",
+    params(
+        ("record_id" = String, Path, description = "任务执行记录ID"),
+        TaskOutputQuery
+    ),
+    responses(
+        (status = 200, description = "返回输出分段", body = TaskOutputResponse),
+        (status = 404, description = "任务记录不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Instance Tasks"
+)]
+pub async fn get_task_record_output(
+    db: web::Data<DatabaseConnection>,
+    output_hub: web::Data<TaskOutputHub>,
+    path: web::Path<String>,
+    query: web::Query<TaskOutputQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let record_id = path.into_inner();
+    let record = instance_task_records::Entity::find_by_id(&record_id)
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task record not found".to_string()))?;
+    ensure_record_access(&db, &req, &record).await?;
+    let limit = query.limit.unwrap_or(500).clamp(1, 1000);
+    let timeout_secs = query.timeout.unwrap_or(30).min(60);
+
+    let polled = if query.wait.unwrap_or(false) {
+        output_hub
+            .wait_for(&record_id, Duration::from_secs(timeout_secs), || {
+                poll_task_output(&db, &record_id, query.after_seq, limit)
+            })
+            .await?
+    } else {
+        Vec::new()
+    };
+    let (chunks, record_status) = match polled.into_iter().next() {
+        Some(polled) => polled,
+        None => {
+            let record = instance_task_records::Entity::find_by_id(&record_id)
+                .one(&**db)
+                .await?
+                .ok_or_else(|| ApiError::NotFound("Task record not found".to_string()))?;
+            let attempt = record.retry_attempt.unwrap_or(0);
+            let chunks =
+                output::fetch_chunks(&db, &record_id, attempt, query.after_seq, limit).await?;
+            (chunks, record.status)
+        }
+    };
+
+    let finished = output::is_finished(&record_status) && (chunks.len() as u64) < limit;
+    let last_seq = chunks.last().map(|c| c.seq).or(query.after_seq);
+    let response = TaskOutputResponse {
+        record_id,
+        record_status,
+        chunks: chunks
+            .into_iter()
+            .map(TaskOutputChunkResponse::from_entity)
+            .collect(),
+        last_seq,
+        finished,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// GET /api/instances/task-records/{record_id}/output/download
+/// 下载任务执行的完整输出
+#[utoipa::path(
+    get,
+    path = "/api/instances/task-records/{record_id}/output/download",
+    params(("record_id" = String, Path, description = "任务执行记录ID")),
+    responses(
+        (status = 200, description = "完整输出文本", content_type = "text/plain"),
+        (status = 404, description = "任务记录不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Instance Tasks"
+)]
+pub async fn download_task_record_output(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let record_id = path.into_inner();
+    let record = instance_task_records::Entity::find_by_id(&record_id)
+        .one(&**db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task record not found".to_string()))?;
+    ensure_record_access(&db, &req, &record).await?;
+
+    let content = output::full_output(&db, &record_id, record.retry_attempt.unwrap_or(0)).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.log\"", record_id),
+        ))
+        .body(content))
 }
 
 /// POST /api/open/instances/tasks/result
@@ -563,26 +1115,32 @@ pub async fn get_instance_tasks(
     responses(
         (status = 200, description = "任务结果接收成功", body = TaskResultSubmitResponse),
         (status = 400, description = "请求参数错误"),
+        (status = 401, description = "实例令牌无效"),
+        (status = 403, description = "任务记录不属于该实例"),
         (status = 404, description = "任务记录不存在"),
         (status = 500, description = "服务器错误")
     ),
     tag = "Instance Tasks"
 )]
 pub async fn submit_task_result(
+    req: HttpRequest,
     db: web::Data<DatabaseConnection>,
+    hub: web::Data<TaskDispatchHub>,
+    output_hub: web::Data<TaskOutputHub>,
     request: web::Json<TaskResultSubmitRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    // 验证记录是否存在
-    let record = instance_task_records::Entity::find_by_id(&request.record_id)
-        .one(&**db)
-        .await?;
-
-    let record = match record {
-        Some(r) => r,
-        None => {
-            return Err(ApiError::NotFound("Task record not found".to_string()));
-        }
-    };
+    let record =
+        find_agent_record(&db, &req, &request.record_id, &request.agent_instance_id).await?;
+
+    // 超时重试后，上一次执行迟到的结果不能覆盖本次执行
+    let attempt = request.retry_attempt.unwrap_or(0);
+    if attempt != record.retry_attempt.unwrap_or(0) {
+        return Err(ApiError::BadRequest(format!(
+            "Stale task result: retry attempt {} does not match current attempt {}",
+            attempt,
+            record.retry_attempt.unwrap_or(0)
+        )));
+    }
 
     // 解析时间
     let start_time = chrono::DateTime::parse_from_rfc3339(&request.start_time)
@@ -594,6 +1152,7 @@ pub async fn submit_task_result(
         .with_timezone(&Utc);
 
     // 更新记录
+    let task_id = record.task_id.clone();
     let mut active: instance_task_records::ActiveModel = record.into();
     active.status = Set(request.status.clone());
     active.start_time = Set(Some(start_time.into()));
@@ -601,11 +1160,35 @@ pub async fn submit_task_result(
     active.duration_ms = Set(Some(request.duration_ms));
     active.result_code = Set(request.result_code);
     active.result_message = Set(request.result_message.clone());
-    active.result_data = Set(request.result_data.clone());
+    active.result_data = Set(request.result_data.clone().map(|mut data| {
+        // 输出已分段上报时，结果中只保留对完整输出的引用
+        if let Some(output_ref) = data.get_mut("output_ref").and_then(|v| v.as_object_mut()) {
+            output_ref.insert(
+                "url".to_string(),
+                json!(format!(
+                    "/api/instances/task-records/{}/output/download",
+                    request.record_id
+                )),
+            );
+        }
+        data
+    }));
     active.error_message = Set(request.error_message.clone());
     active.updated_at = Set(Utc::now().into());
     active.update(&**db).await?;
 
+    // 唤醒正在 follow 输出的请求
+    output_hub.notify(&request.record_id);
+
+    // 分批执行：本批结束后放行下一批
+    if let Err(e) = rollout::advance_rollout(&db, &hub, &task_id).await {
+        log::error!(
+            "[submit_task_result] Failed to advance rollout for task {}: {}",
+            task_id,
+            e
+        );
+    }
+
     let response = TaskResultSubmitResponse {
         status: "success".to_string(),
         message: "Task result received successfully".to_string(),
@@ -637,6 +1220,7 @@ pub async fn get_task_instances_with_results(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
     query: web::Query<TaskRecordListQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let task_id = path.into_inner();
 
@@ -646,9 +1230,10 @@ pub async fn get_task_instances_with_results(
         .one(&**db)
         .await?;
 
-    if task.is_none() {
+    let Some(task) = task else {
         return Err(ApiError::NotFound("Task not found".to_string()));
-    }
+    };
+    ensure_task_access(&db, &req, &task).await?;
 
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(20).min(100);
@@ -682,24 +1267,7 @@ pub async fn get_task_instances_with_results(
             .await?;
 
         if let Some(instance) = instance {
-            let instance_info = crate::instance_tasks::models::InstanceInfo {
-                id: instance.id,
-                agent_instance_id: instance.agent_instance_id.unwrap_or_default(),
-                hostname: Some(instance.hostname),
-                ip_address: Some(instance.ip_address),
-                public_ip: instance.public_ip,
-                mac_address: instance.mac_address,
-                os_type: instance
-                    .os_type
-                    .unwrap_or(crate::shared::enums::OsType::Unknown),
-                os_version: instance.os_version,
-                online_status: instance.online_status,
-                last_heartbeat: instance.last_report_at.map(|t| t.to_rfc3339()),
-                agent_type: instance.agent_type,
-                agent_version: instance.agent_version,
-                created_at: instance.created_at.to_rfc3339(),
-                updated_at: instance.updated_at.to_rfc3339(),
-            };
+            let instance_info = InstanceInfo::from_entity(instance);
 
             let record_response =
                 crate::instance_tasks::models::TaskRecordResponse::from_entity(record);
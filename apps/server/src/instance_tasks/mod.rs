@@ -1,5 +1,11 @@
+pub mod dispatch;
 pub mod handlers;
 pub mod models;
+pub mod output;
+pub mod rollout;
 pub mod routes;
+pub mod selector;
 
+pub use dispatch::TaskDispatchHub;
+pub use output::TaskOutputHub;
 pub use routes::{instance_task_routes, open_instance_task_routes};
@@ -1,4 +1,4 @@
-use crate::shared::enums::{AgentType, TaskStatus, TaskType};
+use crate::shared::enums::{AgentType, OnlineStatus, OsType, TaskStatus, TaskType};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use utoipa::ToSchema;
@@ -24,7 +24,14 @@ use utoipa::ToSchema;
 pub struct TaskCreateRequest {
     pub task_name: String,
     pub task_type: TaskType,
+    #[serde(default)]
     pub target_instances: Vec<String>, // 实例ID数组
+    /// 目标实例选择器，与 target_instances 取并集
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_selector: Option<TaskTargetSelector>,
+    /// 分批执行策略，为空表示所有实例同时执行
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout_strategy: Option<TaskRolloutStrategy>,
     pub task_content: JsonValue,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub priority: Option<i32>,
@@ -36,6 +43,114 @@ pub struct TaskCreateRequest {
     pub application_id: Option<String>,
 }
 
+/// 选择器解析时机
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectorResolveAt {
+    /// 创建任务时解析一次
+    #[default]
+    Creation,
+    /// 创建时解析，并在有效期内将后续拉取任务且匹配的实例加入执行
+    Dispatch,
+}
+
+/// 任务目标实例选择器
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct TaskTargetSelector {
+    /// 应用ID，未指定时使用任务的 application_id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    /// 实例标签（custom_fields.tags），需全部包含
+    pub tags: Vec<String>,
+    /// 操作系统类型，匹配任一即可
+    pub os_types: Vec<OsType>,
+    /// 在线状态
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub online_status: Option<OnlineStatus>,
+    /// 按比例抽样（1-100）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u32>,
+    /// 抽样种子，相同种子下同一实例的入选结果不变，未指定时使用任务ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_seed: Option<String>,
+    pub resolve_at: SelectorResolveAt,
+    /// 下发时解析的有效时长（分钟），默认 1440，最长 10080
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dispatch_window_minutes: Option<u32>,
+    /// 下发时解析的截止时间（RFC3339，由服务端计算）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dispatch_until: Option<String>,
+}
+
+/// 分批执行策略
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct TaskRolloutStrategy {
+    /// 每批实例数，与 batch_percentage 二选一
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<u32>,
+    /// 每批实例占比（1-100）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_percentage: Option<u32>,
+    /// 上一批全部结束后到放行下一批的等待时间（秒）
+    pub pause_seconds: u64,
+    /// 失败率阈值（0-100），已放行记录的失败率超过该值时中止剩余批次
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_threshold_percent: Option<u32>,
+}
+
+/// 分批执行状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutStatus {
+    Running,
+    Completed,
+    Aborted,
+    Cancelled,
+}
+
+/// 分批执行进度
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaskRolloutState {
+    pub status: RolloutStatus,
+    /// 当前已放行的批次（从0开始）
+    pub current_batch: u32,
+    pub total_batches: u32,
+    pub batch_size: u32,
+    /// 下一批放行时间（RFC3339），批间暂停期间有值
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_release_at: Option<String>,
+    /// 最近一次计算的失败率（0-100）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_rate_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 目标实例预览请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskTargetPreviewRequest {
+    #[serde(default)]
+    pub target_instances: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_selector: Option<TaskTargetSelector>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+}
+
+/// 目标实例预览响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskTargetPreviewResponse {
+    pub total: u32,
+    pub data: Vec<InstanceInfo>,
+    /// 本次抽样使用的种子，创建任务时传入相同种子可得到相同结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_seed: Option<String>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
 /// 任务响应
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TaskResponse {
@@ -43,6 +158,12 @@ pub struct TaskResponse {
     pub task_name: String,
     pub task_type: TaskType,
     pub target_instances: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_selector: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout_strategy: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout_state: Option<JsonValue>,
     pub task_content: JsonValue,
     pub priority: i32,
     pub timeout_seconds: i32,
@@ -101,6 +222,8 @@ pub struct TaskRecordResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_message: Option<String>,
     pub retry_attempt: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_index: Option<i32>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -185,6 +308,9 @@ pub struct TaskResultSubmitRequest {
     pub start_time: String, // ISO 8601
     pub end_time: String,   // ISO 8601
     pub duration_ms: i64,
+    /// 下发时的重试次数（缺省视为 0），与记录当前次数不一致的迟到结果会被拒绝
+    #[serde(default)]
+    pub retry_attempt: Option<i32>,
 }
 
 /// 结果回传响应
@@ -195,6 +321,88 @@ pub struct TaskResultSubmitResponse {
     pub timestamp: u64,
 }
 
+/// 任务输出分段（Agent上报）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaskOutputChunk {
+    /// 分段序号，同一执行记录内从 0 开始递增
+    pub seq: i64,
+    /// 输出流：stdout 或 stderr
+    #[serde(default = "default_output_stream")]
+    pub stream: String,
+    pub content: String,
+}
+
+fn default_output_stream() -> String {
+    "stdout".to_string()
+}
+
+/// 任务输出上报请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskOutputSubmitRequest {
+    pub record_id: String,
+    pub agent_instance_id: String,
+    pub chunks: Vec<TaskOutputChunk>,
+    /// 下发时的重试次数（缺省视为 0），与记录当前次数不一致的迟到输出会被拒绝
+    #[serde(default)]
+    pub retry_attempt: Option<i32>,
+}
+
+/// 任务输出上报响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskOutputSubmitResponse {
+    pub status: String,
+    /// 本次新保存的分段数（重复上报的分段会被忽略）
+    pub accepted: u64,
+    pub timestamp: u64,
+}
+
+/// 任务输出查询参数
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct TaskOutputQuery {
+    /// 只返回序号大于该值的分段，首次查询不传
+    pub after_seq: Option<i64>,
+    pub limit: Option<u64>,
+    /// 没有新输出时是否等待（follow）
+    pub wait: Option<bool>,
+    /// 最长等待秒数，默认 30，最大 60
+    pub timeout: Option<u64>,
+}
+
+/// 任务输出分段响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskOutputChunkResponse {
+    pub seq: i64,
+    pub stream: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+impl TaskOutputChunkResponse {
+    pub fn from_entity(entity: crate::entities::instance_task_outputs::Model) -> Self {
+        Self {
+            seq: entity.seq,
+            stream: entity.stream,
+            content: entity.content,
+            created_at: entity.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// 任务输出查询响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskOutputResponse {
+    pub record_id: String,
+    pub record_status: TaskStatus,
+    pub chunks: Vec<TaskOutputChunkResponse>,
+    /// 下次查询使用的 after_seq
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seq: Option<i64>,
+    /// 执行已结束且输出已全部返回
+    pub finished: bool,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
 /// 任务下发响应（Agent拉取）
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TaskDispatchResponse {
@@ -212,6 +420,8 @@ pub struct TaskDispatchItem {
     pub task_content: JsonValue,
     pub timeout_seconds: i32,
     pub priority: i32,
+    /// 当前重试次数，回传结果时需原样带回
+    pub retry_attempt: i32,
 }
 
 // ===================================================================
@@ -236,6 +446,9 @@ impl TaskResponse {
             task_name: entity.task_name,
             task_type: entity.task_type,
             target_instances: entity.target_instances,
+            target_selector: entity.target_selector,
+            rollout_strategy: entity.rollout_strategy,
+            rollout_state: entity.rollout_state,
             task_content: entity.task_content,
             priority: entity.priority.unwrap_or(5),
             timeout_seconds: entity.timeout_seconds.unwrap_or(300),
@@ -248,6 +461,27 @@ impl TaskResponse {
     }
 }
 
+impl InstanceInfo {
+    pub fn from_entity(entity: crate::entities::instances::Model) -> Self {
+        Self {
+            id: entity.id,
+            agent_instance_id: entity.agent_instance_id.unwrap_or_default(),
+            hostname: Some(entity.hostname),
+            ip_address: Some(entity.ip_address),
+            public_ip: entity.public_ip,
+            mac_address: entity.mac_address,
+            os_type: entity.os_type.unwrap_or(OsType::Unknown),
+            os_version: entity.os_version,
+            online_status: entity.online_status,
+            last_heartbeat: entity.last_report_at.map(|t| t.to_rfc3339()),
+            agent_type: entity.agent_type,
+            agent_version: entity.agent_version,
+            created_at: entity.created_at.to_rfc3339(),
+            updated_at: entity.updated_at.to_rfc3339(),
+        }
+    }
+}
+
 impl TaskRecordResponse {
     pub fn from_entity(entity: crate::entities::instance_task_records::Model) -> Self {
         Self {
@@ -264,6 +498,7 @@ impl TaskRecordResponse {
             result_data: entity.result_data,
             error_message: entity.error_message,
             retry_attempt: entity.retry_attempt.unwrap_or(0),
+            batch_index: entity.batch_index,
             created_at: entity.created_at.to_rfc3339(),
             updated_at: entity.updated_at.to_rfc3339(),
         }
@@ -0,0 +1,198 @@
+/// 任务输出流
+///
+/// Agent 执行过程中按序号分段上报 stdout/stderr，服务端逐段保存并唤醒正在 follow 该执行记录的请求。
+/// (record_id, attempt, seq) 唯一，网络重试导致的重复上报会被忽略；
+/// 分段按执行次数区分，超时重试后上一次执行迟到的输出不会与新一次执行冲突，查询只返回当前执行次数的输出。
+use crate::entities::instance_task_outputs;
+use crate::instance_tasks::dispatch::TaskDispatchHub;
+use crate::instance_tasks::models::TaskOutputChunk;
+use crate::shared::enums::TaskStatus;
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use chrono::Utc;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::future::Future;
+use std::time::Duration;
+
+/// 单次上报最多分段数
+const MAX_CHUNKS_PER_REQUEST: usize = 500;
+/// 单个分段最大字节数
+const MAX_CHUNK_BYTES: usize = 64 * 1024;
+
+/// 任务输出推送中心
+///
+/// 按执行记录ID登记 follow 请求，新输出或执行结果到达时唤醒；等待机制与任务下发共用。
+#[derive(Default)]
+pub struct TaskOutputHub {
+    waiters: TaskDispatchHub,
+}
+
+impl TaskOutputHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 通知执行记录有新输出或已结束
+    pub fn notify(&self, record_id: &str) {
+        self.waiters.notify([record_id]);
+    }
+
+    /// 等待执行记录的新输出
+    pub async fn wait_for<T, E, F, Fut>(
+        &self,
+        record_id: &str,
+        max_wait: Duration,
+        fetch: F,
+    ) -> Result<Vec<T>, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Vec<T>, E>>,
+    {
+        self.waiters.wait_for(record_id, max_wait, fetch).await
+    }
+}
+
+/// 执行记录是否已结束（不再产生输出）
+pub fn is_finished(status: &TaskStatus) -> bool {
+    matches!(
+        status,
+        TaskStatus::Success | TaskStatus::Failed | TaskStatus::Cancelled | TaskStatus::Timeout
+    )
+}
+
+/// 校验上报的输出分段
+pub fn validate_chunks(chunks: &[TaskOutputChunk]) -> Result<(), ApiError> {
+    if chunks.len() > MAX_CHUNKS_PER_REQUEST {
+        return Err(ApiError::ValidationError(format!(
+            "Too many output chunks in one request (max {})",
+            MAX_CHUNKS_PER_REQUEST
+        )));
+    }
+    for chunk in chunks {
+        if chunk.seq < 0 {
+            return Err(ApiError::ValidationError(
+                "Output chunk seq must not be negative".to_string(),
+            ));
+        }
+        if chunk.stream != "stdout" && chunk.stream != "stderr" {
+            return Err(ApiError::ValidationError(format!(
+                "Invalid output stream: {}",
+                chunk.stream
+            )));
+        }
+        if chunk.content.len() > MAX_CHUNK_BYTES {
+            return Err(ApiError::ValidationError(format!(
+                "Output chunk {} exceeds {} bytes",
+                chunk.seq, MAX_CHUNK_BYTES
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 保存输出分段，返回新保存的分段数
+pub async fn save_chunks(
+    db: &DatabaseConnection,
+    record_id: &str,
+    attempt: i32,
+    chunks: &[TaskOutputChunk],
+) -> Result<u64, ApiError> {
+    if chunks.is_empty() {
+        return Ok(0);
+    }
+    let now = Utc::now();
+    let models = chunks
+        .iter()
+        .map(|chunk| instance_task_outputs::ActiveModel {
+            id: Set(generate_snowflake_id()),
+            record_id: Set(record_id.to_string()),
+            attempt: Set(attempt),
+            seq: Set(chunk.seq),
+            stream: Set(chunk.stream.clone()),
+            content: Set(chunk.content.clone()),
+            created_at: Set(now.into()),
+        });
+    let inserted = instance_task_outputs::Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::columns([
+                instance_task_outputs::Column::RecordId,
+                instance_task_outputs::Column::Attempt,
+                instance_task_outputs::Column::Seq,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(inserted)
+}
+
+/// 查询指定执行次数中序号大于 after_seq 的输出分段
+pub async fn fetch_chunks(
+    db: &DatabaseConnection,
+    record_id: &str,
+    attempt: i32,
+    after_seq: Option<i64>,
+    limit: u64,
+) -> Result<Vec<instance_task_outputs::Model>, ApiError> {
+    let mut select = instance_task_outputs::Entity::find()
+        .filter(instance_task_outputs::Column::RecordId.eq(record_id))
+        .filter(instance_task_outputs::Column::Attempt.eq(attempt));
+    if let Some(after_seq) = after_seq {
+        select = select.filter(instance_task_outputs::Column::Seq.gt(after_seq));
+    }
+    Ok(select
+        .order_by_asc(instance_task_outputs::Column::Seq)
+        .limit(limit)
+        .all(db)
+        .await?)
+}
+
+/// 拼接执行记录指定执行次数的完整输出
+pub async fn full_output(
+    db: &DatabaseConnection,
+    record_id: &str,
+    attempt: i32,
+) -> Result<String, ApiError> {
+    let chunks = instance_task_outputs::Entity::find()
+        .filter(instance_task_outputs::Column::RecordId.eq(record_id))
+        .filter(instance_task_outputs::Column::Attempt.eq(attempt))
+        .order_by_asc(instance_task_outputs::Column::Seq)
+        .all(db)
+        .await?;
+    Ok(chunks.into_iter().map(|c| c.content).collect())
+}
+
+/// 清空执行记录的输出（重新下发前调用，只保留新一次执行的输出）
+pub async fn clear_output(db: &DatabaseConnection, record_id: &str) -> Result<u64, ApiError> {
+    let res = instance_task_outputs::Entity::delete_many()
+        .filter(instance_task_outputs::Column::RecordId.eq(record_id))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(seq: i64, stream: &str, content: &str) -> TaskOutputChunk {
+        TaskOutputChunk {
+            seq,
+            stream: stream.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_chunks() {
+        assert!(validate_chunks(&[chunk(0, "stdout", "a"), chunk(1, "stderr", "b")]).is_ok());
+        assert!(validate_chunks(&[chunk(-1, "stdout", "a")]).is_err());
+        assert!(validate_chunks(&[chunk(0, "stdin", "a")]).is_err());
+        let big = "x".repeat(MAX_CHUNK_BYTES + 1);
+        assert!(validate_chunks(&[chunk(0, "stdout", &big)]).is_err());
+    }
+}
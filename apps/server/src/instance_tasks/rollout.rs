@@ -0,0 +1,293 @@
+/// 任务分批执行
+///
+/// 创建任务时按策略为执行记录划分批次，首批置为 pending，其余为 queued。
+/// 当前批次全部结束后计算已放行记录的失败率：超过阈值则按取消任务的方式中止剩余批次，
+/// 否则在批间暂停后放行下一批。推进由结果回传与后台巡检共同触发。
+use crate::entities::{instance_task_records, instance_tasks};
+use crate::instance_tasks::dispatch::TaskDispatchHub;
+use crate::instance_tasks::handlers::cancel_task_records;
+use crate::instance_tasks::models::{RolloutStatus, TaskRolloutState, TaskRolloutStrategy};
+use crate::shared::enums::TaskStatus;
+use crate::shared::error::ApiError;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+
+impl TaskRolloutStrategy {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        match (self.batch_size, self.batch_percentage) {
+            (Some(_), Some(_)) => Err(ApiError::ValidationError(
+                "rollout_strategy: batch_size and batch_percentage are mutually exclusive"
+                    .to_string(),
+            )),
+            (None, None) => Err(ApiError::ValidationError(
+                "rollout_strategy requires batch_size or batch_percentage".to_string(),
+            )),
+            (Some(0), _) => Err(ApiError::ValidationError(
+                "rollout_strategy.batch_size must be greater than 0".to_string(),
+            )),
+            (_, Some(p)) if p == 0 || p > 100 => Err(ApiError::ValidationError(
+                "rollout_strategy.batch_percentage must be between 1 and 100".to_string(),
+            )),
+            _ => match self.failure_threshold_percent {
+                Some(t) if t > 100 => Err(ApiError::ValidationError(
+                    "rollout_strategy.failure_threshold_percent must be between 0 and 100"
+                        .to_string(),
+                )),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// 按目标实例总数计算每批实例数
+    pub fn batch_size_for(&self, total: usize) -> usize {
+        let size = match (self.batch_size, self.batch_percentage) {
+            (Some(size), _) => size as usize,
+            (None, Some(p)) => (total * p as usize).div_ceil(100),
+            (None, None) => total,
+        };
+        size.max(1)
+    }
+}
+
+impl TaskRolloutState {
+    pub fn new(batch_size: usize, total: usize) -> Self {
+        Self {
+            status: RolloutStatus::Running,
+            current_batch: 0,
+            total_batches: total.div_ceil(batch_size).max(1) as u32,
+            batch_size: batch_size as u32,
+            next_release_at: None,
+            failure_rate_percent: None,
+            message: None,
+        }
+    }
+}
+
+/// 记录是否已结束（超时但仍可自动重试的记录视为未结束）
+fn is_terminal(record: &instance_task_records::Model, retry_count: i32) -> bool {
+    match record.status {
+        TaskStatus::Success | TaskStatus::Failed | TaskStatus::Cancelled => true,
+        TaskStatus::Timeout => record.retry_attempt.unwrap_or(0) >= retry_count,
+        _ => false,
+    }
+}
+
+fn is_failure(record: &instance_task_records::Model) -> bool {
+    matches!(record.status, TaskStatus::Failed | TaskStatus::Timeout)
+}
+
+async fn save_state(
+    db: &DatabaseConnection,
+    task: instance_tasks::Model,
+    state: &TaskRolloutState,
+) -> Result<(), ApiError> {
+    let mut active: instance_tasks::ActiveModel = task.into();
+    active.rollout_state = Set(Some(json!(state)));
+    active.updated_at = Set(Utc::now().into());
+    active.update(db).await?;
+    Ok(())
+}
+
+/// 推进单个任务的分批执行，返回本次是否放行了新批次或结束了分批
+pub async fn advance_rollout(
+    db: &DatabaseConnection,
+    hub: &TaskDispatchHub,
+    task_id: &str,
+) -> Result<bool, ApiError> {
+    let Some(task) = instance_tasks::Entity::find_by_id(task_id)
+        .filter(instance_tasks::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+    else {
+        return Ok(false);
+    };
+    let Some(strategy) = task
+        .rollout_strategy
+        .clone()
+        .and_then(|v| serde_json::from_value::<TaskRolloutStrategy>(v).ok())
+    else {
+        return Ok(false);
+    };
+    let Some(mut state) = task
+        .rollout_state
+        .clone()
+        .and_then(|v| serde_json::from_value::<TaskRolloutState>(v).ok())
+    else {
+        return Ok(false);
+    };
+    if state.status != RolloutStatus::Running {
+        return Ok(false);
+    }
+
+    // 已放行批次的记录
+    let released = instance_task_records::Entity::find()
+        .filter(instance_task_records::Column::TaskId.eq(&task.id))
+        .filter(instance_task_records::Column::BatchIndex.lte(state.current_batch as i32))
+        .all(db)
+        .await?;
+    let retry_count = task.retry_count.unwrap_or(0);
+    if released.iter().any(|r| !is_terminal(r, retry_count)) {
+        return Ok(false);
+    }
+
+    let failed = released.iter().filter(|r| is_failure(r)).count();
+    let failure_rate = if released.is_empty() {
+        0.0
+    } else {
+        failed as f64 * 100.0 / released.len() as f64
+    };
+    state.failure_rate_percent = Some((failure_rate * 100.0).round() / 100.0);
+
+    if let Some(threshold) = strategy.failure_threshold_percent {
+        if failure_rate > threshold as f64 {
+            let message = format!(
+                "失败率 {:.2}% 超过阈值 {}%，已中止剩余批次",
+                failure_rate, threshold
+            );
+            let cancelled =
+                cancel_task_records(db, &task.id, RolloutStatus::Aborted, Some(&message)).await?;
+            log::warn!(
+                "[rollout] Task {} aborted at batch {}: {} (cancelled {} records)",
+                task.id,
+                state.current_batch,
+                message,
+                cancelled
+            );
+            let _ = crate::audit::handlers::record_audit_log(
+                db,
+                "instance_tasks",
+                "rollout_abort",
+                "system",
+                "",
+                None,
+                None,
+                Some(json!({
+                    "task_id": task.id,
+                    "batch": state.current_batch,
+                    "failure_rate_percent": state.failure_rate_percent,
+                    "cancelled_records": cancelled,
+                })),
+            )
+            .await;
+            return Ok(true);
+        }
+    }
+
+    if state.current_batch + 1 >= state.total_batches {
+        state.status = RolloutStatus::Completed;
+        state.next_release_at = None;
+        save_state(db, task, &state).await?;
+        return Ok(true);
+    }
+
+    // 批间暂停
+    let now = Utc::now();
+    if strategy.pause_seconds > 0 {
+        match state
+            .next_release_at
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        {
+            None => {
+                state.next_release_at = Some(
+                    (now + Duration::seconds(strategy.pause_seconds as i64))
+                        .to_rfc3339_opts(SecondsFormat::Secs, true),
+                );
+                save_state(db, task, &state).await?;
+                return Ok(false);
+            }
+            Some(at) if now < at => return Ok(false),
+            Some(_) => {}
+        }
+    }
+
+    // 放行下一批
+    let next_batch = state.current_batch as i32 + 1;
+    let next_records = instance_task_records::Entity::find()
+        .filter(instance_task_records::Column::TaskId.eq(&task.id))
+        .filter(instance_task_records::Column::BatchIndex.eq(next_batch))
+        .filter(instance_task_records::Column::Status.eq(TaskStatus::Queued))
+        .all(db)
+        .await?;
+    instance_task_records::Entity::update_many()
+        .col_expr(
+            instance_task_records::Column::Status,
+            Expr::value(TaskStatus::Pending),
+        )
+        .col_expr(
+            instance_task_records::Column::UpdatedAt,
+            Expr::value(chrono::DateTime::<chrono::FixedOffset>::from(now)),
+        )
+        .filter(instance_task_records::Column::TaskId.eq(&task.id))
+        .filter(instance_task_records::Column::BatchIndex.eq(next_batch))
+        .filter(instance_task_records::Column::Status.eq(TaskStatus::Queued))
+        .exec(db)
+        .await?;
+
+    state.current_batch = next_batch as u32;
+    state.next_release_at = None;
+    log::info!(
+        "[rollout] Task {} released batch {}/{} ({} records)",
+        task.id,
+        state.current_batch + 1,
+        state.total_batches,
+        next_records.len()
+    );
+    save_state(db, task, &state).await?;
+    hub.notify(next_records.iter().map(|r| r.instance_id.as_str()));
+    Ok(true)
+}
+
+/// 推进所有进行中的分批任务
+pub async fn advance_all(db: &DatabaseConnection, hub: &TaskDispatchHub) -> Result<u64, ApiError> {
+    let tasks = instance_tasks::Entity::find()
+        .filter(instance_tasks::Column::DeletedAt.is_null())
+        .filter(Expr::cust("rollout_state ->> 'status' = 'running'"))
+        .all(db)
+        .await?;
+
+    let mut advanced = 0;
+    for task in tasks {
+        match advance_rollout(db, hub, &task.id).await {
+            Ok(true) => advanced += 1,
+            Ok(false) => {}
+            Err(e) => log::error!("[rollout] Failed to advance task {}: {}", task.id, e),
+        }
+    }
+    Ok(advanced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_size_and_total_batches() {
+        let by_percentage = TaskRolloutStrategy {
+            batch_percentage: Some(10),
+            ..Default::default()
+        };
+        assert!(by_percentage.validate().is_ok());
+        assert_eq!(by_percentage.batch_size_for(200), 20);
+        assert_eq!(by_percentage.batch_size_for(5), 1);
+        assert_eq!(TaskRolloutState::new(20, 200).total_batches, 10);
+
+        let by_size = TaskRolloutStrategy {
+            batch_size: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(
+            TaskRolloutState::new(by_size.batch_size_for(200), 200).total_batches,
+            7
+        );
+
+        let invalid = TaskRolloutStrategy {
+            batch_size: Some(10),
+            batch_percentage: Some(10),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
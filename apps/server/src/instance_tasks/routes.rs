@@ -5,6 +5,10 @@ pub fn instance_task_routes(cfg: &mut web::ServiceConfig) {
     // 任务管理（需要认证）
     cfg.route("/instances/tasks", web::post().to(handlers::create_task))
         .route("/instances/tasks", web::get().to(handlers::get_tasks))
+        .route(
+            "/instances/tasks/targets/preview",
+            web::post().to(handlers::preview_task_targets),
+        )
         .route(
             "/instances/tasks/{task_id}",
             web::get().to(handlers::get_task),
@@ -32,14 +36,26 @@ pub fn instance_task_routes(cfg: &mut web::ServiceConfig) {
         .route(
             "/instances/task-records/{record_id}/set-pending",
             web::post().to(handlers::set_task_record_pending),
+        )
+        .route(
+            "/instances/task-records/{record_id}/output",
+            web::get().to(handlers::get_task_record_output),
+        )
+        .route(
+            "/instances/task-records/{record_id}/output/download",
+            web::get().to(handlers::download_task_record_output),
         );
 }
 
 pub fn open_instance_task_routes(cfg: &mut web::ServiceConfig) {
-    // 任务下发和结果回传（开放接口，无需认证）
+    // 任务下发和结果回传（开放接口，使用实例令牌认证）
     cfg.route("/tasks", web::get().to(handlers::get_instance_tasks))
         .route(
             "/tasks/result",
             web::post().to(handlers::submit_task_result),
+        )
+        .route(
+            "/tasks/output",
+            web::post().to(handlers::submit_task_output),
         );
 }
@@ -0,0 +1,329 @@
+/// 任务目标实例选择器
+///
+/// 按应用、标签（custom_fields.tags）、操作系统、在线状态与比例解析目标实例。
+/// 比例抽样按 sha256(种子:实例ID) 计算哈希桶，桶号低于比例阈值的实例入选，结果只取决于种子与实例本身；
+/// 创建时解析与下发时解析使用同一规则，后续上线的实例与创建时的实例入选条件一致。
+use crate::entities::{instance_task_records, instance_tasks, instances};
+use crate::instance_tasks::models::{SelectorResolveAt, TaskTargetSelector};
+use crate::shared::enums::{OnlineStatus, Status, TaskStatus};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use chrono::{DateTime, Duration, FixedOffset, SecondsFormat, Utc};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// 下发时解析的默认有效时长（分钟）
+const DEFAULT_DISPATCH_WINDOW_MINUTES: u32 = 24 * 60;
+/// 下发时解析的最长有效时长（分钟）
+const MAX_DISPATCH_WINDOW_MINUTES: u32 = 7 * 24 * 60;
+/// 抽样哈希桶数量
+const SAMPLE_BUCKETS: u64 = 10_000;
+
+impl TaskTargetSelector {
+    /// 校验选择器并补全服务端字段
+    pub fn normalize(&mut self, default_application_id: Option<&str>) -> Result<(), ApiError> {
+        if self.application_id.as_deref().unwrap_or("").is_empty() {
+            self.application_id = default_application_id
+                .filter(|id| !id.is_empty())
+                .map(|id| id.to_string());
+        }
+        if self.application_id.is_none() {
+            return Err(ApiError::ValidationError(
+                "target_selector requires application_id".to_string(),
+            ));
+        }
+        if let Some(p) = self.percentage {
+            if p == 0 || p > 100 {
+                return Err(ApiError::ValidationError(
+                    "target_selector.percentage must be between 1 and 100".to_string(),
+                ));
+            }
+        }
+        self.tags.retain(|t| !t.trim().is_empty());
+        self.dispatch_until = None;
+        if self.resolve_at == SelectorResolveAt::Dispatch {
+            let minutes = self
+                .dispatch_window_minutes
+                .unwrap_or(DEFAULT_DISPATCH_WINDOW_MINUTES)
+                .clamp(1, MAX_DISPATCH_WINDOW_MINUTES);
+            self.dispatch_window_minutes = Some(minutes);
+            self.dispatch_until = Some(
+                (Utc::now() + Duration::minutes(minutes as i64))
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+            );
+        }
+        Ok(())
+    }
+
+    /// 实例是否满足选择器条件（不含比例抽样）
+    pub fn matches(&self, instance: &instances::Model, online_status: &OnlineStatus) -> bool {
+        if instance.deleted_at.is_some() || instance.status != Status::Active {
+            return false;
+        }
+        if let Some(app_id) = &self.application_id {
+            if &instance.application_id != app_id {
+                return false;
+            }
+        }
+        if let Some(expected) = &self.online_status {
+            if expected != online_status {
+                return false;
+            }
+        }
+        if !self.os_types.is_empty()
+            && !instance
+                .os_type
+                .as_ref()
+                .is_some_and(|os| self.os_types.contains(os))
+        {
+            return false;
+        }
+        if !self.tags.is_empty() {
+            let tags = instance_tags(instance);
+            if !self.tags.iter().all(|t| tags.iter().any(|it| it == t)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn dispatch_open(&self, now: DateTime<Utc>) -> bool {
+        self.resolve_at == SelectorResolveAt::Dispatch
+            && self
+                .dispatch_until
+                .as_deref()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .is_some_and(|until| now < until)
+    }
+}
+
+/// 读取实例标签（custom_fields.tags）
+fn instance_tags(instance: &instances::Model) -> Vec<String> {
+    instance
+        .custom_fields
+        .as_ref()
+        .and_then(|v| v.get("tags"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| t.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 实例的抽样哈希桶（0..SAMPLE_BUCKETS）
+fn sample_bucket(seed: &str, instance_id: &str) -> u64 {
+    let digest = Sha256::digest(format!("{}:{}", seed, instance_id).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes) % SAMPLE_BUCKETS
+}
+
+/// 实例是否按比例入选
+fn sampled(seed: &str, instance_id: &str, percentage: u32) -> bool {
+    percentage >= 100 || sample_bucket(seed, instance_id) < percentage as u64 * SAMPLE_BUCKETS / 100
+}
+
+/// 按比例从候选实例中抽样
+fn sample<T>(candidates: Vec<T>, percentage: u32, seed: &str, id: impl Fn(&T) -> &str) -> Vec<T> {
+    candidates
+        .into_iter()
+        .filter(|c| sampled(seed, id(c), percentage))
+        .collect()
+}
+
+/// 按选择器解析当前匹配的实例
+pub async fn resolve_targets(
+    db: &DatabaseConnection,
+    selector: &TaskTargetSelector,
+    seed: &str,
+) -> Result<Vec<instances::Model>, ApiError> {
+    let mut query = instances::Entity::find()
+        .filter(instances::Column::DeletedAt.is_null())
+        .filter(instances::Column::Status.eq(Status::Active));
+    if let Some(app_id) = &selector.application_id {
+        query = query.filter(instances::Column::ApplicationId.eq(app_id));
+    }
+    let candidates: Vec<instances::Model> = query
+        .order_by_asc(instances::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|i| selector.matches(i, &i.online_status))
+        .collect();
+
+    Ok(match selector.percentage {
+        Some(p) => sample(candidates, p, seed, |i| i.id.as_str()),
+        None => candidates,
+    })
+}
+
+/// 为拉取任务的实例补充下发时解析的任务记录
+///
+/// 查找有效期内 resolve_at = dispatch 的任务，实例匹配且尚无执行记录时创建 pending 记录，
+/// 并将实例追加到任务的 target_instances。拉取任务的实例视为在线。
+/// 并发拉取依赖 (task_id, instance_id) 唯一约束去重，target_instances 以条件更新追加。
+pub async fn expand_dispatch_targets(
+    db: &DatabaseConnection,
+    instance: &instances::Model,
+) -> Result<u64, ApiError> {
+    let now = Utc::now();
+    let tasks = instance_tasks::Entity::find()
+        .filter(instance_tasks::Column::DeletedAt.is_null())
+        .filter(
+            instance_tasks::Column::CreatedAt
+                .gte(now - Duration::minutes(MAX_DISPATCH_WINDOW_MINUTES as i64)),
+        )
+        .filter(Expr::cust("target_selector ->> 'resolve_at' = 'dispatch'"))
+        .filter(Expr::cust_with_values(
+            "target_selector ->> 'application_id' = $1",
+            [instance.application_id.clone()],
+        ))
+        .all(db)
+        .await?;
+
+    let mut added = 0;
+    for task in tasks {
+        let Some(selector) = task
+            .target_selector
+            .clone()
+            .and_then(|v| serde_json::from_value::<TaskTargetSelector>(v).ok())
+        else {
+            continue;
+        };
+        if !selector.dispatch_open(now) || !selector.matches(instance, &OnlineStatus::Online) {
+            continue;
+        }
+        if let Some(p) = selector.percentage {
+            let seed = selector.sample_seed.as_deref().unwrap_or(&task.id);
+            if !sampled(seed, &instance.id, p) {
+                continue;
+            }
+        }
+        let exists = instance_task_records::Entity::find()
+            .filter(instance_task_records::Column::TaskId.eq(&task.id))
+            .filter(instance_task_records::Column::InstanceId.eq(&instance.id))
+            .count(db)
+            .await?;
+        if exists > 0 {
+            continue;
+        }
+
+        let record = instance_task_records::ActiveModel {
+            id: Set(generate_snowflake_id()),
+            task_id: Set(task.id.clone()),
+            instance_id: Set(instance.id.clone()),
+            status: Set(TaskStatus::Pending),
+            dispatch_time: Set(None),
+            start_time: Set(None),
+            end_time: Set(None),
+            duration_ms: Set(None),
+            result_code: Set(None),
+            result_message: Set(None),
+            result_data: Set(None),
+            error_message: Set(None),
+            retry_attempt: Set(Some(0)),
+            batch_index: Set(None),
+            created_at: Set(now.into()),
+            updated_at: Set(now.into()),
+        };
+        let inserted = instance_task_records::Entity::insert(record)
+            .on_conflict(
+                OnConflict::columns([
+                    instance_task_records::Column::TaskId,
+                    instance_task_records::Column::InstanceId,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+        if inserted == 0 {
+            continue;
+        }
+
+        let updated_at: DateTime<FixedOffset> = now.into();
+        instance_tasks::Entity::update_many()
+            .col_expr(
+                instance_tasks::Column::TargetInstances,
+                Expr::cust_with_values(
+                    "target_instances || jsonb_build_array($1::text)",
+                    [instance.id.clone()],
+                ),
+            )
+            .col_expr(instance_tasks::Column::UpdatedAt, Expr::value(updated_at))
+            .filter(instance_tasks::Column::Id.eq(&task.id))
+            .filter(Expr::cust_with_values(
+                "NOT target_instances @> jsonb_build_array($1::text)",
+                [instance.id.clone()],
+            ))
+            .exec(db)
+            .await?;
+
+        added += 1;
+    }
+    Ok(added)
+}
+
+/// 停止下发时解析（任务取消后不再加入新实例）
+pub fn close_dispatch_window(selector: &mut serde_json::Value) {
+    if let Some(obj) = selector.as_object_mut() {
+        if obj.get("resolve_at").and_then(|v| v.as_str()) == Some("dispatch") {
+            obj.insert(
+                "dispatch_until".to_string(),
+                json!(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_is_stable_and_sized() {
+        let ids: Vec<String> = (0..1000).map(|i| format!("instance-{}", i)).collect();
+        let a = sample(ids.clone(), 10, "seed", |s| s.as_str());
+        let b = sample(ids.clone(), 10, "seed", |s| s.as_str());
+        assert_eq!(a, b);
+        assert!((60..=140).contains(&a.len()), "{}", a.len());
+        assert_eq!(sample(ids.clone(), 100, "seed", |s| s.as_str()).len(), 1000);
+    }
+
+    #[test]
+    fn test_sample_matches_dispatch_rule() {
+        // 创建时抽样与下发时逐个判断结果一致，实例入选与候选集合无关
+        let ids: Vec<String> = (0..200).map(|i| format!("instance-{}", i)).collect();
+        let picked = sample(ids.clone(), 25, "task-1", |s| s.as_str());
+        for id in &ids {
+            assert_eq!(picked.contains(id), sampled("task-1", id, 25));
+        }
+        assert_eq!(
+            sample(ids[..100].to_vec(), 25, "task-1", |s| s.as_str()),
+            picked
+                .iter()
+                .filter(|id| ids[..100].contains(id))
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_close_dispatch_window() {
+        let mut selector =
+            json!({ "resolve_at": "dispatch", "dispatch_until": "2999-01-01T00:00:00Z" });
+        close_dispatch_window(&mut selector);
+        let parsed: TaskTargetSelector = serde_json::from_value(selector).unwrap();
+        assert!(!parsed.dispatch_open(Utc::now() + Duration::seconds(1)));
+
+        let mut creation = json!({ "resolve_at": "creation" });
+        close_dispatch_window(&mut creation);
+        assert!(creation.get("dispatch_until").is_none());
+    }
+}
@@ -1,9 +1,11 @@
 use crate::auth::middleware::get_user_id_from_request;
 use crate::entities::instances::{ActiveModel, Entity as Instances};
+use crate::instances::metrics;
 use crate::instances::models::{
     InstanceConfig, InstanceListQuery, InstanceListResponse, InstanceMonitoringDataResponse,
-    InstanceResponse, Pagination, UpdateInstanceConfigRequest,
+    InstanceMonitoringQuery, InstanceResponse, Pagination, UpdateInstanceConfigRequest,
 };
+use crate::members::request_scope;
 use crate::permissions::handlers::get_user_permission_by_name;
 use crate::shared::enums::Status;
 use crate::shared::error::ApiError;
@@ -19,7 +21,9 @@ use serde_json::json;
 pub async fn get_instances(
     db: web::Data<DatabaseConnection>,
     query: web::Query<InstanceListQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
     let offset = (page - 1) * limit;
@@ -28,6 +32,12 @@ pub async fn get_instances(
     let mut select =
         Instances::find().filter(crate::entities::instances::Column::DeletedAt.is_null());
 
+    // 数据范围：仅返回可访问应用下的实例
+    if let Some(application_ids) = scope.application_filter() {
+        select =
+            select.filter(crate::entities::instances::Column::ApplicationId.is_in(application_ids));
+    }
+
     // 添加搜索过滤器
     if let Some(search) = &query.search {
         select = select.filter(
@@ -117,6 +127,7 @@ pub async fn get_instances(
 pub async fn get_instance(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let instance_id = path.into_inner();
 
@@ -128,6 +139,9 @@ pub async fn get_instance(
 
     match instance {
         Some(instance) => {
+            request_scope(&db, &req)
+                .await?
+                .ensure_application(&instance.application_id)?;
             let mut response = InstanceResponse::from_entity(instance);
             if response.config.is_none() {
                 let default_cfg = InstanceConfig::default();
@@ -195,6 +209,7 @@ pub async fn update_instance(
 pub async fn delete_instance(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let instance_id = path.into_inner();
 
@@ -208,6 +223,9 @@ pub async fn delete_instance(
         Some(instance) => instance,
         None => return Err(ApiError::NotFound("实例不存在".to_string())),
     };
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&existing_instance.application_id)?;
 
     // 当前用户ID（从认证中间件获取，这里暂时使用系统用户）
     let current_user_id = "system".to_string();
@@ -300,6 +318,9 @@ pub async fn enable_instance(
         Some(i) => i,
         None => return Err(ApiError::NotFound("实例不存在".to_string())),
     };
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&instance.application_id)?;
     let mut active: ActiveModel = instance.into();
     active.status = Set(Status::Active);
     active.updated_by = Set(user_id.to_string());
@@ -343,6 +364,9 @@ pub async fn disable_instance(
         Some(i) => i,
         None => return Err(ApiError::NotFound("实例不存在".to_string())),
     };
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&instance.application_id)?;
     let mut active: ActiveModel = instance.into();
     active.status = Set(Status::Disabled);
     active.updated_by = Set(user_id.to_string());
@@ -384,6 +408,9 @@ pub async fn update_config(
         Some(i) => i,
         None => return Err(ApiError::NotFound("实例不存在".to_string())),
     };
+    request_scope(&db, &req_header)
+        .await?
+        .ensure_application(&instance.application_id)?;
 
     // 将传入的 config 合并默认值（缺失字段使用默认值）
     let merged: InstanceConfig =
@@ -391,8 +418,11 @@ pub async fn update_config(
     let merged_value = serde_json::to_value(merged).unwrap_or(json!({}));
 
     let before_instance = instance.clone();
+    let config_revision = instance.config_revision + 1;
     let mut active: ActiveModel = instance.into();
     active.config = Set(Some(merged_value.clone()));
+    // 递增配置版本，Agent 下次上报时拉取并确认
+    active.config_revision = Set(config_revision);
     active.updated_by = Set(user_id.to_string());
     active.updated_at = Set(Utc::now().into());
     let saved = active.update(&**db).await?;
@@ -404,8 +434,11 @@ pub async fn update_config(
     }
 
     // 审计记录：更新实例配置
-    let before = serde_json::json!({ "config": before_instance.config });
-    let after = serde_json::json!({ "config": resp.config });
+    let before = serde_json::json!({
+        "config": before_instance.config,
+        "config_revision": before_instance.config_revision,
+    });
+    let after = serde_json::json!({ "config": resp.config, "config_revision": config_revision });
     let _ = crate::shared::request_context::record_audit_log_simple(
         &**db,
         "instances",
@@ -418,9 +451,13 @@ pub async fn update_config(
     Ok(HttpResponse::Ok().json(resp))
 }
 
+/// GET /api/instances/{id}/monitoring-data
+/// 查询实例监控指标序列（按步长聚合，长时间范围自动使用降采样数据）
 pub async fn get_instance_monitoring_data(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    query: web::Query<InstanceMonitoringQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let instance_id = path.into_inner();
 
@@ -430,11 +467,49 @@ pub async fn get_instance_monitoring_data(
         .one(&**db)
         .await?;
 
-    if instance.is_none() {
+    let Some(instance) = instance else {
         return Err(ApiError::NotFound("实例不存在".to_string()));
-    }
+    };
+    request_scope(&db, &req)
+        .await?
+        .ensure_application(&instance.application_id)?;
+
+    let parse_time = |value: &str, field: &str| {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|_| ApiError::BadRequest(format!("Invalid {} format", field)))
+    };
+    let end = match query.end.as_deref() {
+        Some(end) => parse_time(end, "end")?,
+        None => Utc::now(),
+    };
+    let start = match query.start.as_deref() {
+        Some(start) => parse_time(start, "start")?,
+        None => end - chrono::Duration::hours(1),
+    };
+    let metric_names = metrics::parse_metrics(query.metrics.as_deref())?;
+    let step = metrics::resolve_step(start, end, query.step)?;
+    let aggregation = query.agg.unwrap_or_default();
+
+    let (resolution, step, series) = metrics::query_series(
+        &db,
+        &instance_id,
+        &metric_names,
+        start,
+        end,
+        step,
+        aggregation,
+    )
+    .await?;
 
     let response = InstanceMonitoringDataResponse {
+        instance_id,
+        start: start.to_rfc3339(),
+        end: end.to_rfc3339(),
+        step_seconds: step,
+        resolution: resolution.as_str().to_string(),
+        aggregation,
+        series,
         timestamp: Utc::now().to_rfc3339(),
     };
 
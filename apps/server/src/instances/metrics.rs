@@ -0,0 +1,451 @@
+/// 实例指标时序查询
+///
+/// instance_records 保存原始快照并短期保留，后台任务按 1m/1h/1d 逐级降采样到 instance_metric_rollups。
+/// 查询时选择步长内可完整容纳、且保留期覆盖起始时间的最粗粒度作为数据源，再按步长分桶聚合。
+use crate::entities::{instance_metric_rollups, instance_records};
+use crate::instances::models::{MetricAggregation, MetricPoint, MetricSeries};
+use crate::shared::error::ApiError;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use std::collections::BTreeMap;
+
+/// instance_records 中可查询的数值列
+pub const FIXED_METRICS: &[&str] = &[
+    "cpu_usage_percent",
+    "memory_usage_percent",
+    "memory_used_mb",
+    "disk_usage_percent",
+    "disk_used_gb",
+    "thread_count",
+];
+/// custom_metrics 中数值项的指标名前缀
+pub const CUSTOM_METRIC_PREFIX: &str = "custom.";
+/// 未指定指标时默认查询
+const DEFAULT_METRICS: &[&str] = &[
+    "cpu_usage_percent",
+    "memory_usage_percent",
+    "disk_usage_percent",
+];
+/// 自动计算步长时的目标点数
+const DEFAULT_POINTS: i64 = 300;
+/// 单条序列最多点数
+const MAX_POINTS: i64 = 2000;
+/// 最小步长（秒）
+const MIN_STEP_SECONDS: i64 = 10;
+
+/// 数据粒度
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    Raw,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    /// 降采样粒度，按汇总顺序排列
+    pub const ROLLUPS: [Resolution; 3] = [Resolution::Minute, Resolution::Hour, Resolution::Day];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resolution::Raw => "raw",
+            Resolution::Minute => "1m",
+            Resolution::Hour => "1h",
+            Resolution::Day => "1d",
+        }
+    }
+
+    /// 分桶宽度（秒），原始数据按 Agent 默认上报间隔计
+    pub fn bucket_seconds(&self) -> i64 {
+        match self {
+            Resolution::Raw => 30,
+            Resolution::Minute => 60,
+            Resolution::Hour => 3600,
+            Resolution::Day => 86400,
+        }
+    }
+}
+
+/// 各粒度保留天数
+#[derive(Clone, Debug)]
+pub struct MetricsRetention {
+    pub raw_days: i64,
+    pub minute_days: i64,
+    pub hour_days: i64,
+    pub day_days: i64,
+}
+
+impl MetricsRetention {
+    pub fn from_env() -> Self {
+        let days = |key: &str, default: i64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            raw_days: days("METRICS_RAW_RETENTION_DAYS", 7),
+            minute_days: days("METRICS_1M_RETENTION_DAYS", 15),
+            hour_days: days("METRICS_1H_RETENTION_DAYS", 90),
+            day_days: days("METRICS_1D_RETENTION_DAYS", 730),
+        }
+    }
+
+    pub fn days(&self, resolution: Resolution) -> i64 {
+        match resolution {
+            Resolution::Raw => self.raw_days,
+            Resolution::Minute => self.minute_days,
+            Resolution::Hour => self.hour_days,
+            Resolution::Day => self.day_days,
+        }
+    }
+
+    /// 早于该时间的数据已被清理
+    pub fn cutoff(&self, resolution: Resolution, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(self.days(resolution))
+    }
+}
+
+/// 选择数据源：优先步长内可完整容纳且保留期覆盖起点的最粗粒度，否则取覆盖起点的最细粒度
+pub fn choose_resolution(
+    start: DateTime<Utc>,
+    step_seconds: i64,
+    now: DateTime<Utc>,
+    retention: &MetricsRetention,
+) -> Resolution {
+    let coarse_first = [
+        Resolution::Day,
+        Resolution::Hour,
+        Resolution::Minute,
+        Resolution::Raw,
+    ];
+    let covers = |r: &Resolution| start >= retention.cutoff(*r, now);
+    coarse_first
+        .iter()
+        .find(|r| r.bucket_seconds() <= step_seconds && covers(r))
+        .or_else(|| coarse_first.iter().rev().find(|r| covers(r)))
+        .copied()
+        .unwrap_or(Resolution::Day)
+}
+
+/// 按宽度向下对齐到 UTC 纪元分桶
+pub fn bucket_floor(ts: DateTime<Utc>, seconds: i64) -> DateTime<Utc> {
+    let secs = ts.timestamp().div_euclid(seconds) * seconds;
+    DateTime::from_timestamp(secs, 0).unwrap_or(ts)
+}
+
+/// 校验指标名
+pub fn parse_metrics(raw: Option<&str>) -> Result<Vec<String>, ApiError> {
+    let metrics: Vec<String> = match raw {
+        Some(raw) => raw
+            .split(',')
+            .map(|m| m.trim())
+            .filter(|m| !m.is_empty())
+            .map(|m| m.to_string())
+            .collect(),
+        None => Vec::new(),
+    };
+    if metrics.is_empty() {
+        return Ok(DEFAULT_METRICS.iter().map(|m| m.to_string()).collect());
+    }
+    for metric in &metrics {
+        let valid = FIXED_METRICS.contains(&metric.as_str())
+            || metric
+                .strip_prefix(CUSTOM_METRIC_PREFIX)
+                .is_some_and(|key| {
+                    !key.is_empty()
+                        && key.len() <= 120
+                        && key
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                });
+        if !valid {
+            return Err(ApiError::ValidationError(format!(
+                "Unknown metric: {}",
+                metric
+            )));
+        }
+    }
+    Ok(metrics)
+}
+
+/// 计算实际步长：未指定时按目标点数自动计算，并限制最大点数
+pub fn resolve_step(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: Option<i64>,
+) -> Result<i64, ApiError> {
+    let range = (end - start).num_seconds();
+    if range <= 0 {
+        return Err(ApiError::ValidationError(
+            "end must be later than start".to_string(),
+        ));
+    }
+    let step = match step {
+        Some(step) => step.max(MIN_STEP_SECONDS),
+        None => (range + DEFAULT_POINTS - 1) / DEFAULT_POINTS,
+    }
+    .max(MIN_STEP_SECONDS);
+    if range / step > MAX_POINTS {
+        return Err(ApiError::ValidationError(format!(
+            "Too many points for the requested range (max {}), increase step",
+            MAX_POINTS
+        )));
+    }
+    Ok(step)
+}
+
+/// 单个样本：原始快照或降采样分桶
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub count: i64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+impl Sample {
+    fn single(timestamp: DateTime<Utc>, value: f64) -> Self {
+        Self {
+            timestamp,
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+            p95: value,
+        }
+    }
+
+    fn from_rollup(row: &instance_metric_rollups::Model) -> Self {
+        Self {
+            timestamp: row.bucket_start.with_timezone(&Utc),
+            count: row.sample_count,
+            sum: row.sum_value,
+            min: row.min_value,
+            max: row.max_value,
+            p95: row.p95_value,
+        }
+    }
+}
+
+/// 线性插值分位数（与 Postgres percentile_cont 一致）
+fn percentile(mut values: Vec<f64>, p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = p * (values.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    Some(values[lo] + (values[hi] - values[lo]) * (rank - lo as f64))
+}
+
+/// 按步长分桶聚合，无数据的分桶值为 None
+pub fn aggregate(
+    samples: &[Sample],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: i64,
+    agg: MetricAggregation,
+) -> Vec<MetricPoint> {
+    let first = bucket_floor(start, step).timestamp();
+    let mut buckets: BTreeMap<i64, Vec<&Sample>> = BTreeMap::new();
+    for sample in samples {
+        let ts = sample.timestamp;
+        if ts < start || ts >= end || sample.count <= 0 {
+            continue;
+        }
+        let key = bucket_floor(ts, step).timestamp();
+        buckets.entry(key).or_default().push(sample);
+    }
+
+    let mut points = Vec::new();
+    let mut key = first;
+    while key < end.timestamp() {
+        let value = buckets.get(&key).and_then(|items| match agg {
+            MetricAggregation::Avg => {
+                let count: i64 = items.iter().map(|s| s.count).sum();
+                let sum: f64 = items.iter().map(|s| s.sum).sum();
+                (count > 0).then(|| sum / count as f64)
+            }
+            MetricAggregation::Max => items.iter().map(|s| s.max).reduce(f64::max),
+            MetricAggregation::Min => items.iter().map(|s| s.min).reduce(f64::min),
+            // 原始样本为精确值，降采样分桶按各分桶 p95 近似
+            MetricAggregation::P95 => percentile(items.iter().map(|s| s.p95).collect(), 0.95),
+        });
+        points.push(MetricPoint {
+            timestamp: DateTime::from_timestamp(key, 0)
+                .unwrap_or(start)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            value: value.map(|v| (v * 100.0).round() / 100.0),
+        });
+        key += step;
+    }
+    points
+}
+
+/// 读取原始快照中的指标值
+pub fn record_value(record: &instance_records::Model, metric: &str) -> Option<f64> {
+    match metric {
+        "cpu_usage_percent" => record.cpu_usage_percent.and_then(|v| v.to_f64()),
+        "memory_usage_percent" => record.memory_usage_percent.and_then(|v| v.to_f64()),
+        "memory_used_mb" => record.memory_used_mb.map(|v| v as f64),
+        "disk_usage_percent" => record.disk_usage_percent.and_then(|v| v.to_f64()),
+        "disk_used_gb" => record.disk_used_gb.map(|v| v as f64),
+        "thread_count" => record.thread_count.map(|v| v as f64),
+        _ => metric
+            .strip_prefix(CUSTOM_METRIC_PREFIX)
+            .and_then(|key| record.custom_metrics.as_ref()?.get(key)?.as_f64()),
+    }
+}
+
+/// 查询实例指标序列
+pub async fn query_series(
+    db: &DatabaseConnection,
+    instance_id: &str,
+    metrics: &[String],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: i64,
+    agg: MetricAggregation,
+) -> Result<(Resolution, i64, Vec<MetricSeries>), ApiError> {
+    let retention = MetricsRetention::from_env();
+    let resolution = choose_resolution(start, step, Utc::now(), &retention);
+    let step = if resolution == Resolution::Raw {
+        step
+    } else {
+        step.max(resolution.bucket_seconds())
+    };
+
+    let mut samples: BTreeMap<&str, Vec<Sample>> = BTreeMap::new();
+    if resolution == Resolution::Raw {
+        let records = instance_records::Entity::find()
+            .filter(instance_records::Column::InstanceId.eq(instance_id))
+            .filter(instance_records::Column::ReportTimestamp.gte(start))
+            .filter(instance_records::Column::ReportTimestamp.lt(end))
+            .order_by_asc(instance_records::Column::ReportTimestamp)
+            .all(db)
+            .await?;
+        for metric in metrics {
+            let values = records
+                .iter()
+                .filter_map(|r| {
+                    record_value(r, metric)
+                        .map(|v| Sample::single(r.report_timestamp.with_timezone(&Utc), v))
+                })
+                .collect();
+            samples.insert(metric.as_str(), values);
+        }
+    } else {
+        let rows = instance_metric_rollups::Entity::find()
+            .filter(instance_metric_rollups::Column::InstanceId.eq(instance_id))
+            .filter(instance_metric_rollups::Column::Resolution.eq(resolution.as_str()))
+            .filter(instance_metric_rollups::Column::Metric.is_in(metrics.iter().cloned()))
+            .filter(instance_metric_rollups::Column::BucketStart.gte(start))
+            .filter(instance_metric_rollups::Column::BucketStart.lt(end))
+            .order_by_asc(instance_metric_rollups::Column::BucketStart)
+            .all(db)
+            .await?;
+        for metric in metrics {
+            let values = rows
+                .iter()
+                .filter(|r| &r.metric == metric)
+                .map(Sample::from_rollup)
+                .collect();
+            samples.insert(metric.as_str(), values);
+        }
+    }
+
+    let series = metrics
+        .iter()
+        .map(|metric| MetricSeries {
+            metric: metric.clone(),
+            points: aggregate(
+                samples
+                    .get(metric.as_str())
+                    .map(Vec::as_slice)
+                    .unwrap_or(&[]),
+                start,
+                end,
+                step,
+                agg,
+            ),
+        })
+        .collect();
+    Ok((resolution, step, series))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_choose_resolution() {
+        let retention = MetricsRetention {
+            raw_days: 7,
+            minute_days: 15,
+            hour_days: 90,
+            day_days: 730,
+        };
+        let now = at(1_000 * 86400);
+        // 最近 1 小时、30 秒步长：原始数据
+        assert_eq!(
+            choose_resolution(now - Duration::hours(1), 30, now, &retention),
+            Resolution::Raw
+        );
+        // 5 分钟步长：1m 降采样
+        assert_eq!(
+            choose_resolution(now - Duration::days(1), 300, now, &retention),
+            Resolution::Minute
+        );
+        // 30 天前超出 1m 保留期：退到 1h
+        assert_eq!(
+            choose_resolution(now - Duration::days(30), 300, now, &retention),
+            Resolution::Hour
+        );
+        assert_eq!(
+            choose_resolution(now - Duration::days(365), 86400, now, &retention),
+            Resolution::Day
+        );
+    }
+
+    #[test]
+    fn test_aggregate_with_gaps() {
+        let samples: Vec<Sample> = (0..10)
+            .map(|i| Sample::single(at(i * 10), i as f64))
+            .chain(std::iter::once(Sample::single(at(250), 100.0)))
+            .collect();
+        let points = aggregate(&samples, at(0), at(300), 60, MetricAggregation::Avg);
+        assert_eq!(points.len(), 5);
+        assert_eq!(points[0].value, Some(2.5));
+        assert_eq!(points[1].value, Some(7.5));
+        assert_eq!(points[2].value, None);
+        assert_eq!(points[4].value, Some(100.0));
+
+        let max = aggregate(&samples, at(0), at(300), 60, MetricAggregation::Max);
+        assert_eq!(max[0].value, Some(5.0));
+        let p95 = aggregate(&samples, at(0), at(300), 300, MetricAggregation::P95);
+        assert_eq!(p95.len(), 1);
+        assert_eq!(p95[0].value, Some(54.5));
+    }
+
+    #[test]
+    fn test_parse_metrics_and_step() {
+        assert_eq!(parse_metrics(None).unwrap().len(), DEFAULT_METRICS.len());
+        assert!(parse_metrics(Some("cpu_usage_percent,custom.qps")).is_ok());
+        assert!(parse_metrics(Some("custom.bad key")).is_err());
+        assert!(parse_metrics(Some("password")).is_err());
+
+        assert_eq!(resolve_step(at(0), at(3600), None).unwrap(), 12);
+        assert!(resolve_step(at(0), at(86400), Some(10)).is_err());
+        assert!(resolve_step(at(10), at(0), None).is_err());
+    }
+}
@@ -60,10 +60,40 @@ pub struct InstanceResponse {
     pub custom_fields: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Option<JsonValue>,
+    pub config_revision: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_applied_revision: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_applied_at: Option<String>,
+    pub config_status: ConfigSyncStatus,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// 实例配置同步状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSyncStatus {
+    /// 未下发过配置，Agent 使用本地配置
+    Default,
+    /// 已修改，等待 Agent 生效
+    Pending,
+    /// Agent 已确认生效
+    Applied,
+}
+
+impl ConfigSyncStatus {
+    pub fn of(revision: i32, applied_revision: Option<i32>) -> Self {
+        if revision == 0 {
+            ConfigSyncStatus::Default
+        } else if applied_revision == Some(revision) {
+            ConfigSyncStatus::Applied
+        } else {
+            ConfigSyncStatus::Pending
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InstanceCreateRequest {
     pub environment: Option<JsonValue>,
@@ -98,8 +128,54 @@ pub struct InstanceUpdateRequest {
     pub agent_instance_id: Option<String>,
 }
 
+/// 监控数据聚合方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricAggregation {
+    #[default]
+    Avg,
+    Max,
+    Min,
+    P95,
+}
+
+/// 监控数据查询参数
+#[derive(Debug, Deserialize)]
+pub struct InstanceMonitoringQuery {
+    /// 指标名，逗号分隔；自定义指标使用 custom.<key>
+    pub metrics: Option<String>,
+    /// 起始时间（RFC3339），默认结束时间前 1 小时
+    pub start: Option<String>,
+    /// 结束时间（RFC3339），默认当前时间
+    pub end: Option<String>,
+    /// 步长（秒），默认按约 300 个点自动计算
+    pub step: Option<i64>,
+    pub agg: Option<MetricAggregation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricPoint {
+    pub timestamp: String,
+    /// 该步长内无数据时为 null
+    pub value: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricSeries {
+    pub metric: String,
+    pub points: Vec<MetricPoint>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InstanceMonitoringDataResponse {
+    pub instance_id: String,
+    pub start: String,
+    pub end: String,
+    pub step_seconds: i64,
+    /// 数据来源粒度：raw, 1m, 1h, 1d
+    pub resolution: String,
+    pub aggregation: MetricAggregation,
+    pub series: Vec<MetricSeries>,
     pub timestamp: String,
 }
 
@@ -161,6 +237,13 @@ impl InstanceResponse {
             report_count: entity.report_count,
             custom_fields: entity.custom_fields,
             config: entity.config,
+            config_status: ConfigSyncStatus::of(
+                entity.config_revision,
+                entity.config_applied_revision,
+            ),
+            config_revision: entity.config_revision,
+            config_applied_revision: entity.config_applied_revision,
+            config_applied_at: entity.config_applied_at.map(|v| v.to_rfc3339()),
             created_at: entity.created_at.to_rfc3339(),
             updated_at: entity.updated_at.to_rfc3339(),
         }
@@ -197,6 +280,9 @@ impl InstanceCreateRequest {
             report_count: Set(Some(0)),
             custom_fields: Set(self.custom_fields.clone()),
             config: Set(None),
+            config_revision: Set(0),
+            config_applied_revision: Set(None),
+            config_applied_at: Set(None),
             agent_type: Set(None),
             agent_version: Set(None),
             cpu_usage_percent: Set(None),
@@ -246,7 +332,9 @@ pub struct TaskConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct HttpConfig {
-    pub proxy_enabled: bool,
+    /// 未设置时不下发，Agent 沿用本地代理配置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_enabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_host: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -279,7 +367,7 @@ impl Default for TaskConfig {
 impl Default for HttpConfig {
     fn default() -> Self {
         Self {
-            proxy_enabled: false,
+            proxy_enabled: None,
             proxy_host: None,
             proxy_port: None,
             proxy_username: None,
@@ -303,3 +391,16 @@ impl Default for InstanceConfig {
 pub struct UpdateInstanceConfigRequest {
     pub config: JsonValue,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_sync_status() {
+        assert_eq!(ConfigSyncStatus::of(0, None), ConfigSyncStatus::Default);
+        assert_eq!(ConfigSyncStatus::of(2, Some(1)), ConfigSyncStatus::Pending);
+        assert_eq!(ConfigSyncStatus::of(2, None), ConfigSyncStatus::Pending);
+        assert_eq!(ConfigSyncStatus::of(2, Some(2)), ConfigSyncStatus::Applied);
+    }
+}
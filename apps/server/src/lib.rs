@@ -1,4 +1,7 @@
 // 导出所有模块
+pub mod agent_auth;
+pub mod agent_releases;
+pub mod alerts;
 pub mod applications;
 pub mod auth;
 pub mod configs;
@@ -12,10 +15,12 @@ pub mod logs;
 // pub mod migrator; // 已使用SQL迁移文件
 pub mod audit;
 pub mod maintenance;
+pub mod members;
 pub mod permissions;
 pub mod projects;
 pub mod roles;
 pub mod shared;
+pub mod task_schedules;
 pub mod users;
 pub mod websocket;
 
@@ -8,26 +8,39 @@ use crate::logs::models::{
     LogListQuery, LogListResponse as ModelLogListResponse, LogResponse as ModelLogResponse,
     Pagination as ModelPagination,
 };
+use crate::members::{request_scope, DataScope};
 use crate::shared::error::ApiError;
 use crate::shared::snowflake::generate_snowflake_id;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use sea_orm::sea_query::Expr;
 use sea_orm::Condition;
 use sea_orm::{
     ColumnTrait, DatabaseConnection, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder,
+    Select,
 };
 
+/// 数据范围过滤：仅可访问应用的日志
+fn scoped(select: Select<Logs>, scope: &DataScope) -> Select<Logs> {
+    match scope.application_filter() {
+        Some(application_ids) => select.filter(Column::ApplicationId.is_in(application_ids)),
+        None => select,
+    }
+}
+
 pub async fn get_logs(
     query: web::Query<LogListQuery>,
     db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
+
     // 获取分页参数
     let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(10).max(1).min(100);
     let _offset = (page - 1) * limit;
 
     // 构建查询
-    let mut query_builder = Logs::find();
+    let mut query_builder = scoped(Logs::find(), &scope);
 
     // TraceId
     if let Some(trace_id) = &query.trace_id {
@@ -278,9 +291,12 @@ pub async fn get_logs(
 pub async fn export_logs(
     query: web::Query<LogListQuery>,
     db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
+
     // 构建查询
-    let mut query_builder = Logs::find();
+    let mut query_builder = scoped(Logs::find(), &scope);
 
     // 添加日志级别过滤
     if let Some(log_level) = &query.log_level {
@@ -203,6 +203,7 @@ where
                     log_type: Set(enums::LogType::Request),
                     timestamp: Set(now.into()),
                     created_at: Set(now.into()),
+                    client_log_id: Set(None),
                 };
 
                 let _ = active.insert(db.get_ref()).await;
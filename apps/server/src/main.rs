@@ -2,6 +2,7 @@ use actix_cors::Cors;
 use actix_web::{web, App, HttpServer};
 use env_logger;
 use std::io;
+use std::sync::Arc;
 use utoipa::OpenApi;
 use utoipa::{
     openapi::security::{Http, HttpAuthScheme, SecurityScheme},
@@ -11,6 +12,7 @@ use utoipa_swagger_ui::SwaggerUi;
 
 // 使用新的模块结构
 use aione_monihub_server::auth::middleware::AuthMiddleware;
+use aione_monihub_server::instance_tasks::{TaskDispatchHub, TaskOutputHub};
 use aione_monihub_server::{DatabaseManager, WsServer};
 
 #[derive(OpenApi)]
@@ -60,12 +62,83 @@ use aione_monihub_server::{DatabaseManager, WsServer};
         aione_monihub_server::configs::handlers::get_config_by_code_and_environment,
         aione_monihub_server::configs::handlers::get_config_by_code_env_and_version,
         aione_monihub_server::configs::handlers::delete_config,
+        aione_monihub_server::configs::client::get_client_config,
+        aione_monihub_server::configs::client::watch_client_configs,
+        aione_monihub_server::configs::publish::submit_config,
+        aione_monihub_server::configs::publish::approve_config,
+        aione_monihub_server::configs::publish::reject_config,
+        aione_monihub_server::configs::publish::publish_config,
+        aione_monihub_server::configs::publish::rollback_config,
+        aione_monihub_server::configs::publish::promote_config,
+        aione_monihub_server::configs::publish::get_config_release,
+        aione_monihub_server::configs::publish::update_config_release_policy,
+        aione_monihub_server::configs::publish::diff_configs,
+        aione_monihub_server::configs::schema::check_config_schema,
+        aione_monihub_server::configs::secrets::reveal_config,
+        aione_monihub_server::configs::secrets::rotate_config_secrets,
+        aione_monihub_server::configs::template::render_config,
+        aione_monihub_server::configs::variables::list_config_variables,
+        aione_monihub_server::configs::variables::upsert_config_variable,
+        aione_monihub_server::configs::variables::delete_config_variable,
         aione_monihub_server::instance_tasks::handlers::get_instance_tasks,
+        aione_monihub_server::instance_tasks::handlers::preview_task_targets,
         aione_monihub_server::instance_tasks::handlers::submit_task_result,
+        aione_monihub_server::instance_tasks::handlers::submit_task_output,
+        aione_monihub_server::instance_tasks::handlers::get_task_record_output,
+        aione_monihub_server::instance_tasks::handlers::download_task_record_output,
         aione_monihub_server::instance_tasks::handlers::get_task_instances_with_results,
+        aione_monihub_server::task_schedules::handlers::get_task_schedules,
+        aione_monihub_server::task_schedules::handlers::create_task_schedule,
+        aione_monihub_server::task_schedules::handlers::get_task_schedule,
+        aione_monihub_server::task_schedules::handlers::update_task_schedule,
+        aione_monihub_server::task_schedules::handlers::delete_task_schedule,
+        aione_monihub_server::task_schedules::handlers::enable_task_schedule,
+        aione_monihub_server::task_schedules::handlers::disable_task_schedule,
+        aione_monihub_server::task_schedules::handlers::get_task_schedule_runs,
+        aione_monihub_server::alerts::handlers::get_alert_channels,
+        aione_monihub_server::alerts::handlers::create_alert_channel,
+        aione_monihub_server::alerts::handlers::get_alert_channel,
+        aione_monihub_server::alerts::handlers::update_alert_channel,
+        aione_monihub_server::alerts::handlers::delete_alert_channel,
+        aione_monihub_server::alerts::handlers::test_alert_channel,
+        aione_monihub_server::alerts::handlers::get_alert_rules,
+        aione_monihub_server::alerts::handlers::create_alert_rule,
+        aione_monihub_server::alerts::handlers::get_alert_rule,
+        aione_monihub_server::alerts::handlers::update_alert_rule,
+        aione_monihub_server::alerts::handlers::delete_alert_rule,
+        aione_monihub_server::alerts::handlers::enable_alert_rule,
+        aione_monihub_server::alerts::handlers::disable_alert_rule,
+        aione_monihub_server::alerts::handlers::get_alert_events,
+        aione_monihub_server::alerts::handlers::get_alert_event,
+        aione_monihub_server::alerts::handlers::acknowledge_alert_event,
+        aione_monihub_server::alerts::handlers::get_alert_silences,
+        aione_monihub_server::alerts::handlers::create_alert_silence,
+        aione_monihub_server::alerts::handlers::expire_alert_silence,
+        aione_monihub_server::members::handlers::get_project_members,
+        aione_monihub_server::members::handlers::add_project_member,
+        aione_monihub_server::members::handlers::remove_project_member,
+        aione_monihub_server::members::handlers::get_application_members,
+        aione_monihub_server::members::handlers::add_application_member,
+        aione_monihub_server::members::handlers::remove_application_member,
         aione_monihub_server::files::handlers::init_file_upload,
         aione_monihub_server::files::handlers::upload_file_chunk,
-        aione_monihub_server::files::handlers::download_file
+        aione_monihub_server::files::handlers::download_file,
+        aione_monihub_server::agent_auth::handlers::get_enrollment_secrets,
+        aione_monihub_server::agent_auth::handlers::create_enrollment_secret,
+        aione_monihub_server::agent_auth::handlers::revoke_enrollment_secret,
+        aione_monihub_server::agent_auth::handlers::update_agent_auth_policy,
+        aione_monihub_server::agent_auth::handlers::get_instance_credentials,
+        aione_monihub_server::agent_auth::handlers::revoke_instance_credentials,
+        aione_monihub_server::agent_auth::handlers::enroll_agent,
+        aione_monihub_server::agent_auth::handlers::rotate_agent_token,
+        aione_monihub_server::agent_releases::handlers::get_agent_releases,
+        aione_monihub_server::agent_releases::handlers::create_agent_release,
+        aione_monihub_server::agent_releases::handlers::delete_agent_release,
+        aione_monihub_server::agent_releases::handlers::get_agent_versions,
+        aione_monihub_server::agent_releases::handlers::update_agent_target_version,
+        aione_monihub_server::agent_releases::handlers::create_agent_upgrade,
+        aione_monihub_server::agent_releases::handlers::resolve_agent_release,
+        aione_monihub_server::agent_releases::handlers::download_agent_release
     ),
     components(
         schemas(
@@ -98,6 +171,28 @@ use aione_monihub_server::{DatabaseManager, WsServer};
             aione_monihub_server::configs::models::ConfigCreateRequest,
             aione_monihub_server::configs::models::ConfigUpdateRequest,
             aione_monihub_server::configs::models::ConfigListResponse,
+            aione_monihub_server::configs::models::ConfigClientResponse,
+            aione_monihub_server::configs::models::ConfigWatchItem,
+            aione_monihub_server::configs::models::ConfigWatchRequest,
+            aione_monihub_server::configs::models::ConfigWatchChange,
+            aione_monihub_server::configs::models::ConfigWatchResponse,
+            aione_monihub_server::configs::models::ConfigReviewRequest,
+            aione_monihub_server::configs::models::ConfigRollbackRequest,
+            aione_monihub_server::configs::models::ConfigPromoteRequest,
+            aione_monihub_server::configs::models::ConfigReleasePolicyRequest,
+            aione_monihub_server::configs::models::ConfigReleaseResponse,
+            aione_monihub_server::configs::models::ConfigDiffSide,
+            aione_monihub_server::configs::models::ConfigDiffResponse,
+            aione_monihub_server::configs::models::ConfigSchemaCheckRequest,
+            aione_monihub_server::configs::models::ConfigSchemaViolation,
+            aione_monihub_server::configs::models::ConfigSchemaCheckResponse,
+            aione_monihub_server::configs::models::ConfigSecretRotateResponse,
+            aione_monihub_server::configs::models::ConfigRenderLayer,
+            aione_monihub_server::configs::models::ConfigFieldSource,
+            aione_monihub_server::configs::models::ConfigVariableUsage,
+            aione_monihub_server::configs::models::ConfigRenderResponse,
+            aione_monihub_server::configs::models::ConfigVariableUpsertRequest,
+            aione_monihub_server::configs::models::ConfigVariableResponse,
             aione_monihub_server::roles::models::Role,
             aione_monihub_server::roles::models::RoleResponse,
             aione_monihub_server::roles::models::RoleCreateRequest,
@@ -115,21 +210,77 @@ use aione_monihub_server::{DatabaseManager, WsServer};
             aione_monihub_server::users::models::UserRoleResponse,
             aione_monihub_server::users::models::UserRoleListResponse,
             aione_monihub_server::instance_tasks::models::TaskCreateRequest,
+            aione_monihub_server::instance_tasks::models::TaskTargetSelector,
+            aione_monihub_server::instance_tasks::models::SelectorResolveAt,
+            aione_monihub_server::instance_tasks::models::TaskRolloutStrategy,
+            aione_monihub_server::instance_tasks::models::TaskRolloutState,
+            aione_monihub_server::instance_tasks::models::RolloutStatus,
+            aione_monihub_server::instance_tasks::models::TaskTargetPreviewRequest,
+            aione_monihub_server::instance_tasks::models::TaskTargetPreviewResponse,
             aione_monihub_server::instance_tasks::models::TaskResponse,
             aione_monihub_server::instance_tasks::models::TaskListResponse,
             aione_monihub_server::instance_tasks::models::TaskResultSubmitRequest,
             aione_monihub_server::instance_tasks::models::TaskResultSubmitResponse,
+            aione_monihub_server::instance_tasks::models::TaskOutputChunk,
+            aione_monihub_server::instance_tasks::models::TaskOutputSubmitRequest,
+            aione_monihub_server::instance_tasks::models::TaskOutputSubmitResponse,
+            aione_monihub_server::instance_tasks::models::TaskOutputChunkResponse,
+            aione_monihub_server::instance_tasks::models::TaskOutputResponse,
             aione_monihub_server::instance_tasks::models::TaskDispatchResponse,
             aione_monihub_server::instance_tasks::models::TaskDispatchItem,
             aione_monihub_server::instance_tasks::models::Pagination,
             aione_monihub_server::instance_tasks::models::InstanceInfo,
             aione_monihub_server::instance_tasks::models::TaskInstanceWithResult,
             aione_monihub_server::instance_tasks::models::TaskInstanceWithResultResponse,
+            aione_monihub_server::task_schedules::models::TaskScheduleCreateRequest,
+            aione_monihub_server::task_schedules::models::TaskScheduleUpdateRequest,
+            aione_monihub_server::task_schedules::models::TaskScheduleResponse,
+            aione_monihub_server::task_schedules::models::TaskScheduleListResponse,
+            aione_monihub_server::task_schedules::models::TaskScheduleRunResponse,
+            aione_monihub_server::task_schedules::models::TaskScheduleRunListResponse,
+            aione_monihub_server::alerts::models::ThresholdOperator,
+            aione_monihub_server::alerts::models::AlertCondition,
+            aione_monihub_server::alerts::models::AlertChannelCreateRequest,
+            aione_monihub_server::alerts::models::AlertChannelUpdateRequest,
+            aione_monihub_server::alerts::models::AlertChannelResponse,
+            aione_monihub_server::alerts::models::AlertChannelListResponse,
+            aione_monihub_server::alerts::models::AlertChannelTestResponse,
+            aione_monihub_server::alerts::models::AlertRuleCreateRequest,
+            aione_monihub_server::alerts::models::AlertRuleUpdateRequest,
+            aione_monihub_server::alerts::models::AlertRuleResponse,
+            aione_monihub_server::alerts::models::AlertRuleListResponse,
+            aione_monihub_server::alerts::models::AlertEventResponse,
+            aione_monihub_server::alerts::models::AlertEventListResponse,
+            aione_monihub_server::alerts::models::AlertSilenceCreateRequest,
+            aione_monihub_server::alerts::models::AlertSilenceResponse,
+            aione_monihub_server::alerts::models::AlertSilenceListResponse,
+            aione_monihub_server::members::models::ResourceMemberCreateRequest,
+            aione_monihub_server::members::models::ResourceMemberResponse,
+            aione_monihub_server::members::models::ResourceMemberListResponse,
             aione_monihub_server::files::models::FileUploadRequest,
             aione_monihub_server::files::models::FileUploadResponse,
             aione_monihub_server::files::models::FileChunkUploadRequest,
             aione_monihub_server::files::models::FileChunkUploadResponse,
-            aione_monihub_server::files::models::FileInfo
+            aione_monihub_server::files::models::FileInfo,
+            aione_monihub_server::agent_auth::models::EnrollmentSecretCreateRequest,
+            aione_monihub_server::agent_auth::models::EnrollmentSecretResponse,
+            aione_monihub_server::agent_auth::models::EnrollmentSecretCreateResponse,
+            aione_monihub_server::agent_auth::models::EnrollmentSecretListResponse,
+            aione_monihub_server::agent_auth::models::AgentAuthPolicyUpdateRequest,
+            aione_monihub_server::agent_auth::models::InstanceCredentialResponse,
+            aione_monihub_server::agent_auth::models::InstanceCredentialListResponse,
+            aione_monihub_server::agent_auth::models::InstanceCredentialRevokeResponse,
+            aione_monihub_server::agent_auth::models::AgentEnrollRequest,
+            aione_monihub_server::agent_auth::models::AgentTokenRotateRequest,
+            aione_monihub_server::agent_auth::models::AgentTokenResponse,
+            aione_monihub_server::agent_releases::models::AgentReleaseCreateRequest,
+            aione_monihub_server::agent_releases::models::AgentReleaseResponse,
+            aione_monihub_server::agent_releases::models::AgentReleaseListResponse,
+            aione_monihub_server::agent_releases::models::AgentVersionCount,
+            aione_monihub_server::agent_releases::models::AgentVersionDistributionResponse,
+            aione_monihub_server::agent_releases::models::AgentTargetVersionUpdateRequest,
+            aione_monihub_server::agent_releases::models::AgentUpgradeRequest,
+            aione_monihub_server::agent_releases::models::AgentReleaseManifest
         )
     ),
     info(
@@ -154,7 +305,12 @@ use aione_monihub_server::{DatabaseManager, WsServer};
         (name = "Permissions", description = "权限管理相关接口"),
         (name = "Authentication", description = "认证相关接口"),
         (name = "Configs", description = "配置管理相关接口"),
-        (name = "Instance Tasks", description = "实例任务管理相关接口")
+        (name = "Instance Tasks", description = "实例任务管理相关接口"),
+        (name = "Task Schedules", description = "定时任务管理相关接口"),
+        (name = "Alerts", description = "告警规则、事件、静默与通知渠道相关接口"),
+        (name = "Members", description = "项目/应用成员与数据范围相关接口"),
+        (name = "Agent Auth", description = "Agent 注册密钥与实例令牌相关接口"),
+        (name = "Agent Releases", description = "Agent 发布包与版本管理相关接口")
     )
 )]
 struct ApiDoc;
@@ -174,10 +330,16 @@ impl Modify for SecurityAddon {
 }
 
 // 导入所有模块的路由函数
+use aione_monihub_server::agent_auth::routes::{agent_auth_routes, open_agent_auth_routes};
+use aione_monihub_server::agent_releases::routes::{
+    agent_release_routes, open_agent_release_routes,
+};
+use aione_monihub_server::alerts::routes::alert_routes;
 use aione_monihub_server::applications::routes::application_routes;
 use aione_monihub_server::audit::routes::audit_routes;
 use aione_monihub_server::auth::routes::auth_routes;
-use aione_monihub_server::configs::routes::config_routes;
+use aione_monihub_server::configs::routes::{config_routes, open_config_routes};
+use aione_monihub_server::configs::ConfigWatchHub;
 use aione_monihub_server::files::routes::file_routes;
 use aione_monihub_server::health::routes::health_routes;
 use aione_monihub_server::instance_reports::routes::{
@@ -188,15 +350,18 @@ use aione_monihub_server::instance_tasks::routes::{
 };
 use aione_monihub_server::instances::routes::instance_routes;
 use aione_monihub_server::logs::routes::log_routes;
+use aione_monihub_server::members::member_routes;
+use aione_monihub_server::permissions::guard::PermissionGuard;
 use aione_monihub_server::permissions::routes::permission_routes;
 use aione_monihub_server::projects::routes::project_routes;
 use aione_monihub_server::roles::routes::role_routes;
+use aione_monihub_server::task_schedules::routes::task_schedule_routes;
 use aione_monihub_server::users::routes::user_routes;
 
 // 添加Actor trait导入以使用start方法
 use actix::Actor;
 use aione_monihub_server::maintenance::scheduler::start_all_scheduled_tasks;
-use aione_monihub_server::websocket::routes::websocket_routes;
+use aione_monihub_server::websocket::routes::{open_websocket_routes, websocket_routes};
 
 #[actix_web::main]
 async fn main() -> io::Result<()> {
@@ -212,9 +377,6 @@ async fn main() -> io::Result<()> {
         .await
         .expect("Failed to initialize database connection");
 
-    // Start WebSocket server
-    let ws_server = WsServer::new().start();
-
     // 获取服务器配置
     let server_host = std::env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let server_port = std::env::var("SERVER_PORT").unwrap_or_else(|_| "9080".to_string());
@@ -224,13 +386,26 @@ async fn main() -> io::Result<()> {
 
     let db_connection = db_manager.get_connection().clone();
 
+    // Start WebSocket server（终端会话转发，录像写入文件存储）
+    let ws_server = WsServer::new().with_database(db_connection.clone()).start();
+
+    // 任务推送中心（所有 worker 共享同一实例）
+    let task_hub = Arc::new(TaskDispatchHub::new());
+    // 任务输出推送中心（follow 输出的请求在此等待）
+    let output_hub = Arc::new(TaskOutputHub::new());
+    // 配置变更推送中心（watch 配置的请求在此等待）
+    let config_hub = Arc::new(ConfigWatchHub::new());
+
     // 启动所有后台定时任务
-    start_all_scheduled_tasks(db_connection.clone());
+    start_all_scheduled_tasks(db_connection.clone(), task_hub.clone());
 
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(db_connection.clone()))
             .app_data(web::Data::new(ws_server.clone()))
+            .app_data(web::Data::from(task_hub.clone()))
+            .app_data(web::Data::from(output_hub.clone()))
+            .app_data(web::Data::from(config_hub.clone()))
             .app_data(web::JsonConfig::default().error_handler(|err, req| {
                 let msg = format!(
                     "JSON 反序列化失败: {}; method={}; path={}",
@@ -261,6 +436,8 @@ async fn main() -> io::Result<()> {
             // API routes (all under /api scope)
             .service(
                 web::scope("/api")
+                    // 按路由登记的操作权限鉴权（公开路径与开放 API 除外）
+                    .wrap(PermissionGuard)
                     .configure(auth_routes)
                     .configure(project_routes)
                     .configure(application_routes)
@@ -269,17 +446,26 @@ async fn main() -> io::Result<()> {
                     .configure(permission_routes)
                     .configure(instance_report_routes)
                     .configure(instance_task_routes)
+                    .configure(task_schedule_routes)
+                    .configure(alert_routes)
+                    .configure(member_routes)
                     .configure(instance_routes)
                     .configure(config_routes)
                     .configure(websocket_routes)
                     .configure(log_routes)
                     .configure(audit_routes)
                     .configure(file_routes)
-                    // Open API routes (no authentication required)
+                    .configure(agent_auth_routes)
+                    .configure(agent_release_routes)
+                    // Open API routes (no user session; agents authenticate with X-Agent-Token)
                     .service(
                         web::scope("/open/instances")
+                            .configure(open_agent_auth_routes)
+                            .configure(open_agent_release_routes)
+                            .configure(open_config_routes)
                             .configure(open_instance_report_routes)
-                            .configure(open_instance_task_routes),
+                            .configure(open_instance_task_routes)
+                            .configure(open_websocket_routes),
                     ),
             )
     })
@@ -0,0 +1,23 @@
+use chrono::Utc;
+use log::{error, info};
+use sea_orm::DatabaseConnection;
+
+use crate::alerts::evaluator;
+
+/// 启动告警评估任务（每30秒执行一次）
+pub fn start_alert_evaluator(db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            match evaluator::evaluate_rules(&db, Utc::now()).await {
+                Ok(s) if s.opened + s.fired + s.resolved + s.notified > 0 => info!(
+                    "[alert_evaluator] 评估规则数={} 新建事件={} 触发={} 恢复={} 通知={}",
+                    s.rules, s.opened, s.fired, s.resolved, s.notified
+                ),
+                Ok(_) => {}
+                Err(e) => error!("[alert_evaluator] 告警评估失败: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,36 @@
+/// 后台任务进度读写
+///
+/// 进度保存在 maintenance_checkpoints 表，任务重启或停机恢复后从记录的位置继续。
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait, Set};
+
+use crate::entities::maintenance_checkpoints::{ActiveModel, Column, Entity as Checkpoints};
+
+/// 读取任务进度，尚未记录时返回 None
+pub async fn load(db: &DatabaseConnection, name: &str) -> Result<Option<DateTime<Utc>>, DbErr> {
+    let checkpoint = Checkpoints::find_by_id(name.to_string()).one(db).await?;
+    Ok(checkpoint.map(|c| c.position.with_timezone(&Utc)))
+}
+
+/// 保存任务进度
+pub async fn save(
+    db: &DatabaseConnection,
+    name: &str,
+    position: DateTime<Utc>,
+) -> Result<(), DbErr> {
+    let model = ActiveModel {
+        name: Set(name.to_string()),
+        position: Set(position.into()),
+        updated_at: Set(Utc::now().into()),
+    };
+    Checkpoints::insert(model)
+        .on_conflict(
+            OnConflict::column(Column::Name)
+                .update_columns([Column::Position, Column::UpdatedAt])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
@@ -3,8 +3,13 @@ use log::{error, info};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use std::time::Instant;
 
+use crate::entities::alert_events;
 use crate::entities::instance_records;
+use crate::entities::instance_task_outputs;
 use crate::entities::logs;
+use crate::instances::metrics::{MetricsRetention, Resolution};
+use crate::maintenance::metrics_rollup;
+use crate::shared::enums::AlertStatus;
 
 /// 启动每日数据清理任务，在每天凌晨0点执行
 pub fn start_data_cleaner(db: DatabaseConnection) {
@@ -34,17 +39,31 @@ pub fn start_data_cleaner(db: DatabaseConnection) {
                 }
                 Err(e) => error!("[data_cleaner] 日志清理失败: {}", e),
             }
+
+            match run_task_outputs_cleanup(&db).await {
+                Ok(deleted) => {
+                    info!("[data_cleaner] 清理任务输出完成，删除记录数: {}", deleted);
+                }
+                Err(e) => error!("[data_cleaner] 任务输出清理失败: {}", e),
+            }
+
+            match run_alert_events_cleanup(&db).await {
+                Ok(deleted) => {
+                    info!("[data_cleaner] 清理告警事件完成，删除记录数: {}", deleted);
+                }
+                Err(e) => error!("[data_cleaner] 告警事件清理失败: {}", e),
+            }
         }
     });
 }
 
 /// 执行数据清理任务
-/// 保留7天内的数据，删除更早的数据
+/// 原始上报快照默认保留7天（METRICS_RAW_RETENTION_DAYS），降采样数据按各粒度保留期清理
 async fn run_data_cleanup(db: &DatabaseConnection) -> Result<u64, sea_orm::DbErr> {
     let start = Instant::now();
+    let retention = MetricsRetention::from_env();
 
-    // 计算7天前的时间点
-    let cutoff = Utc::now() - Duration::days(7);
+    let cutoff = retention.cutoff(Resolution::Raw, Utc::now());
 
     // 删除instance_records表中7天前的数据
     let res = instance_records::Entity::delete_many()
@@ -61,7 +80,14 @@ async fn run_data_cleanup(db: &DatabaseConnection) -> Result<u64, sea_orm::DbErr
         cutoff.to_rfc3339(),
     );
 
-    Ok(res.rows_affected)
+    let rollups_deleted = metrics_rollup::cleanup_rollups(db, &retention).await?;
+    info!(
+        target: "data_cleaner",
+        "指标降采样数据清理完成 | 删除行数={}",
+        rollups_deleted,
+    );
+
+    Ok(res.rows_affected + rollups_deleted)
 }
 
 /// 执行日志清理任务
@@ -87,3 +113,52 @@ async fn run_logs_cleanup(db: &DatabaseConnection) -> Result<u64, sea_orm::DbErr
 
     Ok(res.rows_affected)
 }
+
+/// 执行任务输出清理任务
+/// 保留30天内的数据，删除更早的数据
+async fn run_task_outputs_cleanup(db: &DatabaseConnection) -> Result<u64, sea_orm::DbErr> {
+    let start = Instant::now();
+
+    let cutoff = Utc::now() - Duration::days(30);
+
+    let res = instance_task_outputs::Entity::delete_many()
+        .filter(instance_task_outputs::Column::CreatedAt.lt(cutoff))
+        .exec(db)
+        .await?;
+
+    let elapsed_ms = start.elapsed().as_millis();
+    info!(
+        target: "data_cleaner",
+        "任务输出清理完成 | 删除行数={} | 耗时={}ms | 保留数据截止时间={}",
+        res.rows_affected,
+        elapsed_ms,
+        cutoff.to_rfc3339(),
+    );
+
+    Ok(res.rows_affected)
+}
+
+/// 执行告警事件清理任务
+/// 已恢复的事件保留90天，删除更早的数据
+async fn run_alert_events_cleanup(db: &DatabaseConnection) -> Result<u64, sea_orm::DbErr> {
+    let start = Instant::now();
+
+    let cutoff = Utc::now() - Duration::days(90);
+
+    let res = alert_events::Entity::delete_many()
+        .filter(alert_events::Column::Status.eq(AlertStatus::Resolved))
+        .filter(alert_events::Column::ResolvedAt.lt(cutoff))
+        .exec(db)
+        .await?;
+
+    let elapsed_ms = start.elapsed().as_millis();
+    info!(
+        target: "data_cleaner",
+        "告警事件清理完成 | 删除行数={} | 耗时={}ms | 保留数据截止时间={}",
+        res.rows_affected,
+        elapsed_ms,
+        cutoff.to_rfc3339(),
+    );
+
+    Ok(res.rows_affected)
+}
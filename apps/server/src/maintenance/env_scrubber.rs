@@ -0,0 +1,98 @@
+use chrono::Utc;
+use log::{error, info};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde_json::Value as JsonValue;
+use std::time::Instant;
+
+use crate::entities::instances;
+use crate::instance_reports::redaction::redact_environment;
+use crate::maintenance::checkpoints;
+
+/// 每批扫描的实例数
+const BATCH_SIZE: u64 = 500;
+/// 完成标记，脱敏规则变化需要重新清理历史数据时递增版本号
+const CHECKPOINT: &str = "env_scrubber:v1";
+
+/// 启动一次性环境变量脱敏任务，清理脱敏上线前已入库的 instances.environment
+///
+/// 上报入库时已脱敏，全部扫描完成后写入完成标记，之后启动直接跳过；
+/// 中途失败不写标记，下次启动重新扫描（脱敏结果幂等）。
+pub fn start_env_scrubber(db: DatabaseConnection) {
+    tokio::spawn(async move {
+        match checkpoints::load(&db, CHECKPOINT).await {
+            Ok(Some(done_at)) => {
+                info!("[env_scrubber] 历史环境变量已于 {} 完成脱敏，跳过", done_at);
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("[env_scrubber] 读取脱敏完成标记失败: {}", e);
+                return;
+            }
+        }
+        let result = match run_env_scrub(&db).await {
+            Ok(updated) => checkpoints::save(&db, CHECKPOINT, Utc::now())
+                .await
+                .map(|_| updated),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(updated) => info!(
+                "[env_scrubber] 历史环境变量脱敏完成，更新实例数: {}",
+                updated
+            ),
+            Err(e) => error!("[env_scrubber] 历史环境变量脱敏失败: {}", e),
+        }
+    });
+}
+
+/// 按主键分批扫描实例并回写脱敏后的 environment
+async fn run_env_scrub(db: &DatabaseConnection) -> Result<u64, sea_orm::DbErr> {
+    let start = Instant::now();
+    let mut last_id = String::new();
+    let mut scanned = 0u64;
+    let mut updated = 0u64;
+    loop {
+        let rows: Vec<(String, Option<JsonValue>)> = instances::Entity::find()
+            .select_only()
+            .column(instances::Column::Id)
+            .column(instances::Column::Environment)
+            .filter(instances::Column::Id.gt(last_id.as_str()))
+            .filter(instances::Column::Environment.is_not_null())
+            .order_by_asc(instances::Column::Id)
+            .limit(BATCH_SIZE)
+            .into_tuple()
+            .all(db)
+            .await?;
+        let Some((id, _)) = rows.last() else {
+            break;
+        };
+        last_id = id.clone();
+        scanned += rows.len() as u64;
+
+        for (id, env) in rows {
+            let Some(mut env) = env else {
+                continue;
+            };
+            if !redact_environment(&mut env) {
+                continue;
+            }
+            instances::Entity::update_many()
+                .col_expr(instances::Column::Environment, Expr::value(env))
+                .filter(instances::Column::Id.eq(id))
+                .exec(db)
+                .await?;
+            updated += 1;
+        }
+    }
+
+    info!(
+        target: "env_scrubber",
+        "环境变量脱敏完成 | 扫描实例数={} | 更新实例数={} | 耗时={}ms",
+        scanned,
+        updated,
+        start.elapsed().as_millis()
+    );
+    Ok(updated)
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, error};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    Statement,
+};
+
+use crate::entities::instance_metric_rollups;
+use crate::instances::metrics::{bucket_floor, MetricsRetention, Resolution, FIXED_METRICS};
+use crate::maintenance::checkpoints;
+
+/// 1m 汇总回看时长（分钟），覆盖延迟到达的上报
+const MINUTE_LOOKBACK: i64 = 5;
+/// 进度记录名前缀，后接粒度，如 metrics_rollup:1m
+const CHECKPOINT_PREFIX: &str = "metrics_rollup:";
+
+const UPSERT_SUFFIX: &str = r#"
+ON CONFLICT (instance_id, resolution, metric, bucket_start) DO UPDATE SET
+    sample_count = EXCLUDED.sample_count,
+    sum_value = EXCLUDED.sum_value,
+    min_value = EXCLUDED.min_value,
+    max_value = EXCLUDED.max_value,
+    p95_value = EXCLUDED.p95_value,
+    updated_at = EXCLUDED.updated_at"#;
+
+/// 启动指标降采样任务（每分钟执行一次）
+///
+/// 每个粒度从上次汇总到的分桶开始（至少回看几个分桶以计入延迟上报）重新汇总到当前，
+/// 通过 upsert 覆盖；服务停机或任务失败后恢复时补齐中间缺失的分桶。
+pub fn start_metrics_rollup(db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            match run_rollup(&db, Utc::now()).await {
+                Ok(n) => debug!("[metrics_rollup] 降采样完成，写入分桶数: {}", n),
+                Err(e) => error!("[metrics_rollup] 降采样失败: {}", e),
+            }
+        }
+    });
+}
+
+/// 逐级汇总 1m / 1h / 1d
+pub async fn run_rollup(db: &DatabaseConnection, now: DateTime<Utc>) -> Result<u64, DbErr> {
+    let mut total = 0;
+    for resolution in Resolution::ROLLUPS {
+        let bucket = resolution.bucket_seconds();
+        let lookback = match resolution {
+            Resolution::Minute => Duration::minutes(MINUTE_LOOKBACK),
+            _ => Duration::seconds(bucket),
+        };
+        let current = bucket_floor(now, bucket);
+        let checkpoint = format!("{}{}", CHECKPOINT_PREFIX, resolution.as_str());
+        let watermark = checkpoints::load(db, &checkpoint).await?;
+        let start = rollup_start(current, lookback, watermark);
+        let sql = match resolution {
+            Resolution::Minute => raw_rollup_sql(),
+            Resolution::Hour => rollup_sql(Resolution::Minute, resolution),
+            _ => rollup_sql(Resolution::Hour, resolution),
+        };
+        let res = db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                [start.into(), now.into()],
+            ))
+            .await?;
+        total += res.rows_affected();
+        // 当前分桶尚未结束，下次从该分桶开始
+        checkpoints::save(db, &checkpoint, current).await?;
+    }
+    Ok(total)
+}
+
+/// 汇总起点：上次进度与固定回看起点中较早者，均对齐到分桶边界，避免用部分数据覆盖完整分桶
+fn rollup_start(
+    current: DateTime<Utc>,
+    lookback: Duration,
+    watermark: Option<DateTime<Utc>>,
+) -> DateTime<Utc> {
+    let recent = current - lookback;
+    watermark.map_or(recent, |w| w.min(recent))
+}
+
+/// 由原始快照汇总 1m 分桶（固定列与 custom_metrics 中的数值项）
+fn raw_rollup_sql() -> String {
+    let fixed = FIXED_METRICS
+        .iter()
+        .map(|m| format!("('{m}', r.{m}::float8)"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let bucket = Resolution::Minute.bucket_seconds();
+    format!(
+        r#"INSERT INTO instance_metric_rollups
+    (instance_id, resolution, metric, bucket_start, sample_count, sum_value, min_value, max_value, p95_value, updated_at)
+SELECT s.instance_id, '{res}', s.metric,
+       to_timestamp(floor(extract(epoch FROM s.ts) / {bucket}) * {bucket}) AS bucket,
+       count(*), sum(s.value), min(s.value), max(s.value),
+       percentile_cont(0.95) WITHIN GROUP (ORDER BY s.value), now()
+FROM (
+    SELECT r.instance_id, r.report_timestamp AS ts, m.metric, m.value
+    FROM instance_records r
+    CROSS JOIN LATERAL (VALUES {fixed}) AS m(metric, value)
+    WHERE r.report_timestamp >= $1 AND r.report_timestamp < $2 AND m.value IS NOT NULL
+    UNION ALL
+    SELECT r.instance_id, r.report_timestamp, 'custom.' || kv.key, (kv.value #>> '{{}}')::float8
+    FROM instance_records r
+    CROSS JOIN LATERAL jsonb_each(
+        CASE WHEN jsonb_typeof(r.custom_metrics) = 'object' THEN r.custom_metrics ELSE '{{}}'::jsonb END
+    ) AS kv
+    WHERE r.report_timestamp >= $1 AND r.report_timestamp < $2
+      AND jsonb_typeof(kv.value) = 'number' AND length(kv.key) <= 120
+) s
+GROUP BY s.instance_id, s.metric, bucket{suffix}"#,
+        res = Resolution::Minute.as_str(),
+        suffix = UPSERT_SUFFIX,
+    )
+}
+
+/// 由下级分桶汇总上级分桶，p95 取下级分桶 p95 的 95 分位近似
+fn rollup_sql(source: Resolution, target: Resolution) -> String {
+    let bucket = target.bucket_seconds();
+    format!(
+        r#"INSERT INTO instance_metric_rollups
+    (instance_id, resolution, metric, bucket_start, sample_count, sum_value, min_value, max_value, p95_value, updated_at)
+SELECT instance_id, '{target}', metric,
+       to_timestamp(floor(extract(epoch FROM bucket_start) / {bucket}) * {bucket}) AS bucket,
+       sum(sample_count), sum(sum_value), min(min_value), max(max_value),
+       percentile_cont(0.95) WITHIN GROUP (ORDER BY p95_value), now()
+FROM instance_metric_rollups
+WHERE resolution = '{source}' AND bucket_start >= $1 AND bucket_start < $2
+GROUP BY instance_id, metric, bucket{suffix}"#,
+        target = target.as_str(),
+        source = source.as_str(),
+        suffix = UPSERT_SUFFIX,
+    )
+}
+
+/// 按各粒度保留期清理降采样数据
+pub async fn cleanup_rollups(
+    db: &DatabaseConnection,
+    retention: &MetricsRetention,
+) -> Result<u64, DbErr> {
+    let now = Utc::now();
+    let mut deleted = 0;
+    for resolution in Resolution::ROLLUPS {
+        let res = instance_metric_rollups::Entity::delete_many()
+            .filter(instance_metric_rollups::Column::Resolution.eq(resolution.as_str()))
+            .filter(
+                instance_metric_rollups::Column::BucketStart.lt(retention.cutoff(resolution, now)),
+            )
+            .exec(db)
+            .await?;
+        deleted += res.rows_affected;
+    }
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_rollup_start() {
+        let current = Utc.with_ymd_and_hms(2024, 1, 1, 10, 30, 0).unwrap();
+        let lookback = Duration::minutes(MINUTE_LOOKBACK);
+        // 首次运行只回看固定时长
+        assert_eq!(rollup_start(current, lookback, None), current - lookback);
+        // 正常运行时进度较新，仍保留回看
+        assert_eq!(
+            rollup_start(current, lookback, Some(current - Duration::minutes(1))),
+            current - lookback
+        );
+        // 停机恢复后从进度处补齐
+        let stalled = current - Duration::hours(2);
+        assert_eq!(rollup_start(current, lookback, Some(stalled)), stalled);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod alert_evaluator;
+pub mod checkpoints;
 pub mod data_cleaner;
+pub mod env_scrubber;
+pub mod metrics_rollup;
 pub mod offline_checker;
 pub mod scheduler;
+pub mod task_rollout;
+pub mod task_scheduler;
+pub mod task_timeout_checker;
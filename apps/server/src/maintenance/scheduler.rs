@@ -1,16 +1,45 @@
 use log::info;
 use sea_orm::DatabaseConnection;
+use std::sync::Arc;
 
-use crate::maintenance::{data_cleaner, offline_checker};
+use crate::instance_tasks::TaskDispatchHub;
+use crate::maintenance::{
+    alert_evaluator, data_cleaner, env_scrubber, metrics_rollup, offline_checker, task_rollout,
+    task_scheduler, task_timeout_checker,
+};
 
 /// 启动所有后台定时任务
-pub fn start_all_scheduled_tasks(db: DatabaseConnection) {
+pub fn start_all_scheduled_tasks(db: DatabaseConnection, task_hub: Arc<TaskDispatchHub>) {
     info!("启动所有后台定时任务...");
 
     // 启动离线巡检后台任务（每分钟执行一次）
     offline_checker::start_offline_checker(db.clone());
     info!("已启动离线巡检任务（每分钟执行）");
 
+    // 启动任务超时巡检后台任务（每30秒执行一次）
+    task_timeout_checker::start_task_timeout_checker(db.clone(), task_hub.clone());
+    info!("已启动任务超时巡检任务（每30秒执行）");
+
+    // 启动分批执行推进任务（每10秒执行一次）
+    task_rollout::start_task_rollout(db.clone(), task_hub.clone());
+    info!("已启动分批执行推进任务（每10秒执行）");
+
+    // 启动定时任务调度（每15秒执行一次）
+    task_scheduler::start_task_scheduler(db.clone(), task_hub);
+    info!("已启动定时任务调度（每15秒执行）");
+
+    // 启动指标降采样任务（每分钟执行一次）
+    metrics_rollup::start_metrics_rollup(db.clone());
+    info!("已启动指标降采样任务（每分钟执行）");
+
+    // 启动告警评估任务（每30秒执行一次）
+    alert_evaluator::start_alert_evaluator(db.clone());
+    info!("已启动告警评估任务（每30秒执行）");
+
+    // 历史环境变量脱敏（完成后不再执行）
+    env_scrubber::start_env_scrubber(db.clone());
+    info!("已启动历史环境变量脱敏任务（完成后不再执行）");
+
     // 启动数据清理后台任务（每天凌晨0点执行）
     data_cleaner::start_data_cleaner(db.clone());
     info!("已启动数据清理任务（每天凌晨0点执行）");
@@ -0,0 +1,23 @@
+use log::{error, info};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::instance_tasks::rollout;
+use crate::instance_tasks::TaskDispatchHub;
+
+/// 启动分批执行推进任务（每10秒执行一次）
+///
+/// 结果回传时会立即推进，此处兜底处理批间暂停到期与超时结束的批次。
+pub fn start_task_rollout(db: DatabaseConnection, hub: Arc<TaskDispatchHub>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            match rollout::advance_all(&db, &hub).await {
+                Ok(0) => {}
+                Ok(n) => info!("[task_rollout] 推进分批任务数量: {}", n),
+                Err(e) => error!("[task_rollout] 推进分批任务失败: {}", e),
+            }
+        }
+    });
+}
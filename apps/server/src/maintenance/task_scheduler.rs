@@ -0,0 +1,23 @@
+use log::{error, info};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::instance_tasks::TaskDispatchHub;
+use crate::task_schedules::runner;
+
+/// 启动定时任务调度（每15秒执行一次）
+///
+/// cron 最小粒度为分钟，15 秒轮询可保证触发延迟不超过一个周期。
+pub fn start_task_scheduler(db: DatabaseConnection, hub: Arc<TaskDispatchHub>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+        loop {
+            interval.tick().await;
+            match runner::run_due_schedules(&db, &hub).await {
+                Ok(0) => {}
+                Ok(n) => info!("[task_scheduler] 触发定时任务数量: {}", n),
+                Err(e) => error!("[task_scheduler] 触发定时任务失败: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,418 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use log::{error, info, warn};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::entities::{instance_task_records, instance_tasks};
+use crate::instance_tasks::TaskDispatchHub;
+use crate::shared::enums::TaskStatus;
+
+/// 审计日志中的操作人
+const SYSTEM_USER: &str = "system";
+
+/// 任务超时巡检配置（环境变量）
+///
+/// - TASK_TIMEOUT_GRACE_SECONDS：超过任务 timeout_seconds 后的宽限时间，默认 30 秒
+/// - TASK_RETRY_BACKOFF_SECONDS：首次自动重试前的等待时间，之后每次翻倍，默认 30 秒
+/// - TASK_RETRY_BACKOFF_MAX_SECONDS：重试等待时间上限，默认 600 秒
+#[derive(Debug, Clone)]
+pub struct TaskTimeoutConfig {
+    pub grace_seconds: i64,
+    pub backoff_seconds: i64,
+    pub backoff_max_seconds: i64,
+}
+
+impl TaskTimeoutConfig {
+    pub fn from_env() -> Self {
+        fn env_i64(key: &str, default: i64) -> i64 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v >= 0)
+                .unwrap_or(default)
+        }
+        Self {
+            grace_seconds: env_i64("TASK_TIMEOUT_GRACE_SECONDS", 30),
+            backoff_seconds: env_i64("TASK_RETRY_BACKOFF_SECONDS", 30),
+            backoff_max_seconds: env_i64("TASK_RETRY_BACKOFF_MAX_SECONDS", 600),
+        }
+    }
+
+    /// 第 attempt 次重试（从 0 开始计）前的等待时间
+    fn backoff(&self, attempt: i32) -> chrono::Duration {
+        let factor = 1i64 << attempt.clamp(0, 20);
+        let secs = self
+            .backoff_seconds
+            .saturating_mul(factor)
+            .min(self.backoff_max_seconds);
+        chrono::Duration::seconds(secs)
+    }
+}
+
+/// 记录的超时截止时间：running 以开始时间计，dispatched 以下发时间计，再加上宽限时间
+fn timeout_deadline(
+    record: &instance_task_records::Model,
+    timeout_seconds: i64,
+    config: &TaskTimeoutConfig,
+) -> DateTime<FixedOffset> {
+    let since = if record.status == TaskStatus::Running {
+        record.start_time.or(record.dispatch_time)
+    } else {
+        record.dispatch_time
+    }
+    .unwrap_or(record.updated_at);
+    since + chrono::Duration::seconds(timeout_seconds + config.grace_seconds)
+}
+
+/// timeout 记录是否已到重试时间（重试次数上限由查询条件保证）
+fn retry_due(
+    record: &instance_task_records::Model,
+    now: DateTime<Utc>,
+    config: &TaskTimeoutConfig,
+) -> bool {
+    let timed_out_at = record.end_time.unwrap_or(record.updated_at);
+    now >= timed_out_at + config.backoff(record.retry_attempt.unwrap_or(0))
+}
+
+/// 启动任务超时巡检（每30秒执行一次）
+///
+/// 将超过 timeout_seconds 仍未回传结果的 dispatched/running 记录标记为 timeout，
+/// 并在任务 retry_count 范围内按退避时间重新置为 pending。
+pub fn start_task_timeout_checker(db: DatabaseConnection, hub: Arc<TaskDispatchHub>) {
+    let config = TaskTimeoutConfig::from_env();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            match run_timeout_check(&db, &config).await {
+                Ok(0) => {}
+                Ok(n) => info!("[task_timeout_checker] 标记超时记录数量: {}", n),
+                Err(e) => error!("[task_timeout_checker] 超时检查失败: {}", e),
+            }
+            match run_retry(&db, &hub, &config).await {
+                Ok(0) => {}
+                Ok(n) => info!("[task_timeout_checker] 自动重试记录数量: {}", n),
+                Err(e) => error!("[task_timeout_checker] 自动重试失败: {}", e),
+            }
+        }
+    });
+}
+
+/// 标记超时的 dispatched/running 记录
+async fn run_timeout_check(
+    db: &DatabaseConnection,
+    config: &TaskTimeoutConfig,
+) -> Result<u64, sea_orm::DbErr> {
+    let start = Instant::now();
+    let now = Utc::now();
+
+    let records = instance_task_records::Entity::find()
+        .filter(
+            instance_task_records::Column::Status
+                .is_in(vec![TaskStatus::Dispatched, TaskStatus::Running]),
+        )
+        .find_also_related(instance_tasks::Entity)
+        .all(db)
+        .await?;
+
+    let mut updated = 0;
+    for (record, task) in records {
+        let Some(task) = task else {
+            continue;
+        };
+        let timeout_seconds = task.timeout_seconds.unwrap_or(300).max(0) as i64;
+        if now < timeout_deadline(&record, timeout_seconds, config) {
+            continue;
+        }
+
+        let end_time: DateTime<FixedOffset> = now.into();
+        let duration_ms = record
+            .start_time
+            .map(|s| (now - s.with_timezone(&Utc)).num_milliseconds());
+        let error_message = format!("任务执行超时：超过 {} 秒未回传结果", timeout_seconds);
+
+        // 仅在状态未变化时更新，避免覆盖 Agent 同时回传的结果
+        let res = instance_task_records::Entity::update_many()
+            .col_expr(
+                instance_task_records::Column::Status,
+                Expr::value(TaskStatus::Timeout),
+            )
+            .col_expr(
+                instance_task_records::Column::EndTime,
+                Expr::value(end_time),
+            )
+            .col_expr(
+                instance_task_records::Column::DurationMs,
+                Expr::value(duration_ms),
+            )
+            .col_expr(
+                instance_task_records::Column::ErrorMessage,
+                Expr::value(error_message.clone()),
+            )
+            .col_expr(
+                instance_task_records::Column::UpdatedAt,
+                Expr::value(end_time),
+            )
+            .filter(instance_task_records::Column::Id.eq(&record.id))
+            .filter(instance_task_records::Column::Status.eq(record.status.clone()))
+            .exec(db)
+            .await?;
+        if res.rows_affected == 0 {
+            continue;
+        }
+        updated += 1;
+
+        let before = json!({
+            "id": record.id,
+            "task_id": record.task_id,
+            "instance_id": record.instance_id,
+            "status": record.status,
+            "retry_attempt": record.retry_attempt,
+        });
+        let after = json!({
+            "id": record.id,
+            "task_id": record.task_id,
+            "instance_id": record.instance_id,
+            "status": TaskStatus::Timeout,
+            "retry_attempt": record.retry_attempt,
+            "error_message": error_message,
+        });
+        if let Err(e) = crate::audit::handlers::record_audit_log(
+            db,
+            "instance_task_records",
+            "timeout",
+            SYSTEM_USER,
+            "",
+            None,
+            Some(before),
+            Some(after),
+        )
+        .await
+        {
+            warn!("[task_timeout_checker] 写入审计日志失败: {}", e);
+        }
+    }
+
+    let elapsed_ms = start.elapsed().as_millis();
+    info!(
+        target: "task_timeout_checker",
+        "任务超时巡检完成 | 更新行数={} | 耗时={}ms | 宽限时间={}s",
+        updated,
+        elapsed_ms,
+        config.grace_seconds,
+    );
+
+    Ok(updated)
+}
+
+/// 将仍有重试次数的 timeout 记录按退避时间重新置为 pending
+///
+/// 重新排队时清空上一次执行的结果；上一次执行迟到的结果因 retry_attempt 不一致会被拒绝。
+async fn run_retry(
+    db: &DatabaseConnection,
+    hub: &TaskDispatchHub,
+    config: &TaskTimeoutConfig,
+) -> Result<u64, sea_orm::DbErr> {
+    let now = Utc::now();
+
+    let records = instance_task_records::Entity::find()
+        .filter(instance_task_records::Column::Status.eq(TaskStatus::Timeout))
+        .find_also_related(instance_tasks::Entity)
+        .filter(instance_tasks::Column::DeletedAt.is_null())
+        .filter(
+            Expr::col((
+                instance_task_records::Entity,
+                instance_task_records::Column::RetryAttempt,
+            ))
+            .lt(Expr::col((
+                instance_tasks::Entity,
+                instance_tasks::Column::RetryCount,
+            ))),
+        )
+        .all(db)
+        .await?;
+
+    let mut requeued = 0;
+    for (record, task) in records {
+        if task.is_none() {
+            continue;
+        }
+        if !retry_due(&record, now, config) {
+            continue;
+        }
+        let attempt = record.retry_attempt.unwrap_or(0);
+
+        let updated_at: DateTime<FixedOffset> = now.into();
+        let res = instance_task_records::Entity::update_many()
+            .col_expr(
+                instance_task_records::Column::Status,
+                Expr::value(TaskStatus::Pending),
+            )
+            .col_expr(
+                instance_task_records::Column::RetryAttempt,
+                Expr::value(attempt + 1),
+            )
+            .col_expr(
+                instance_task_records::Column::DispatchTime,
+                Expr::value(Option::<DateTime<FixedOffset>>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::StartTime,
+                Expr::value(Option::<DateTime<FixedOffset>>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::EndTime,
+                Expr::value(Option::<DateTime<FixedOffset>>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::DurationMs,
+                Expr::value(Option::<i64>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::ResultCode,
+                Expr::value(Option::<i32>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::ResultMessage,
+                Expr::value(Option::<String>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::ResultData,
+                Expr::value(Option::<serde_json::Value>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::ErrorMessage,
+                Expr::value(Option::<String>::None),
+            )
+            .col_expr(
+                instance_task_records::Column::UpdatedAt,
+                Expr::value(updated_at),
+            )
+            .filter(instance_task_records::Column::Id.eq(&record.id))
+            .filter(instance_task_records::Column::Status.eq(TaskStatus::Timeout))
+            .exec(db)
+            .await?;
+        if res.rows_affected == 0 {
+            continue;
+        }
+        requeued += 1;
+        hub.notify([record.instance_id.as_str()]);
+
+        let before = json!({
+            "id": record.id,
+            "task_id": record.task_id,
+            "instance_id": record.instance_id,
+            "status": TaskStatus::Timeout,
+            "retry_attempt": attempt,
+        });
+        let after = json!({
+            "id": record.id,
+            "task_id": record.task_id,
+            "instance_id": record.instance_id,
+            "status": TaskStatus::Pending,
+            "retry_attempt": attempt + 1,
+        });
+        if let Err(e) = crate::audit::handlers::record_audit_log(
+            db,
+            "instance_task_records",
+            "auto_retry",
+            SYSTEM_USER,
+            "",
+            None,
+            Some(before),
+            Some(after),
+        )
+        .await
+        {
+            warn!("[task_timeout_checker] 写入审计日志失败: {}", e);
+        }
+    }
+
+    Ok(requeued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TaskTimeoutConfig {
+        TaskTimeoutConfig {
+            grace_seconds: 30,
+            backoff_seconds: 30,
+            backoff_max_seconds: 600,
+        }
+    }
+
+    fn at(seconds: i64) -> DateTime<FixedOffset> {
+        (DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap()
+            + chrono::Duration::seconds(seconds))
+        .into()
+    }
+
+    fn record(status: TaskStatus) -> instance_task_records::Model {
+        instance_task_records::Model {
+            id: "r1".to_string(),
+            task_id: "t1".to_string(),
+            instance_id: "i1".to_string(),
+            status,
+            dispatch_time: Some(at(0)),
+            start_time: None,
+            end_time: None,
+            duration_ms: None,
+            result_code: None,
+            result_message: None,
+            result_data: None,
+            error_message: None,
+            retry_attempt: Some(0),
+            batch_index: None,
+            created_at: at(0),
+            updated_at: at(0),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_until_max() {
+        let config = config();
+        assert_eq!(config.backoff(0).num_seconds(), 30);
+        assert_eq!(config.backoff(1).num_seconds(), 60);
+        assert_eq!(config.backoff(4).num_seconds(), 480);
+        assert_eq!(config.backoff(5).num_seconds(), 600);
+        assert_eq!(config.backoff(100).num_seconds(), 600);
+        assert_eq!(config.backoff(-1).num_seconds(), 30);
+    }
+
+    #[test]
+    fn test_timeout_deadline() {
+        let config = config();
+        let dispatched = record(TaskStatus::Dispatched);
+        assert_eq!(timeout_deadline(&dispatched, 60, &config), at(90));
+
+        // running 以开始时间计
+        let mut running = record(TaskStatus::Running);
+        running.start_time = Some(at(20));
+        assert_eq!(timeout_deadline(&running, 60, &config), at(110));
+
+        // 缺少时间时退回更新时间
+        let mut unknown = record(TaskStatus::Dispatched);
+        unknown.dispatch_time = None;
+        unknown.updated_at = at(5);
+        assert_eq!(timeout_deadline(&unknown, 60, &config), at(95));
+    }
+
+    #[test]
+    fn test_retry_due_after_backoff() {
+        let config = config();
+        let mut timed_out = record(TaskStatus::Timeout);
+        timed_out.end_time = Some(at(100));
+        let now = |s| at(s).with_timezone(&Utc);
+        assert!(!retry_due(&timed_out, now(129), &config));
+        assert!(retry_due(&timed_out, now(130), &config));
+
+        timed_out.retry_attempt = Some(2);
+        assert!(!retry_due(&timed_out, now(219), &config));
+        assert!(retry_due(&timed_out, now(220), &config));
+    }
+}
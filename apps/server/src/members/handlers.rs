@@ -0,0 +1,431 @@
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::{applications, projects, resource_members, roles, users};
+use crate::members::models::*;
+use crate::members::scope::{request_scope, DataScope};
+use crate::shared::enums::{MemberResourceType, MemberType};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::Utc;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn audit_table(resource_type: &MemberResourceType) -> &'static str {
+    match resource_type {
+        MemberResourceType::Project => "project_members",
+        MemberResourceType::Application => "application_members",
+    }
+}
+
+/// 校验项目/应用存在且在当前用户的数据范围内
+async fn ensure_resource(
+    db: &DatabaseConnection,
+    scope: &DataScope,
+    resource_type: &MemberResourceType,
+    resource_id: &str,
+) -> Result<(), ApiError> {
+    match resource_type {
+        MemberResourceType::Project => {
+            projects::Entity::find_by_id(resource_id)
+                .filter(projects::Column::DeletedAt.is_null())
+                .one(db)
+                .await?
+                .ok_or_else(|| ApiError::NotFound("项目不存在".to_string()))?;
+            scope.ensure_project(resource_id)
+        }
+        MemberResourceType::Application => {
+            applications::Entity::find_by_id(resource_id)
+                .filter(applications::Column::DeletedAt.is_null())
+                .one(db)
+                .await?
+                .ok_or_else(|| ApiError::NotFound("应用不存在".to_string()))?;
+            scope.ensure_application(resource_id)
+        }
+    }
+}
+
+/// 将创建者加入新建的项目/应用，保证其创建后仍可见
+pub async fn add_creator_membership(
+    db: &DatabaseConnection,
+    resource_type: MemberResourceType,
+    resource_id: &str,
+    user_id: &str,
+) -> Result<(), ApiError> {
+    let membership = resource_members::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        resource_type: Set(resource_type),
+        resource_id: Set(resource_id.to_string()),
+        member_type: Set(MemberType::User),
+        member_id: Set(user_id.to_string()),
+        created_by: Set(user_id.to_string()),
+        created_at: Set(Utc::now().into()),
+    };
+    resource_members::Entity::insert(membership)
+        .on_conflict(
+            OnConflict::columns([
+                resource_members::Column::ResourceType,
+                resource_members::Column::ResourceId,
+                resource_members::Column::MemberType,
+                resource_members::Column::MemberId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// 查询成员对应的用户名/角色名
+async fn member_names(
+    db: &DatabaseConnection,
+    members: &[resource_members::Model],
+) -> Result<HashMap<String, String>, ApiError> {
+    let user_ids: Vec<String> = members
+        .iter()
+        .filter(|m| m.member_type == MemberType::User)
+        .map(|m| m.member_id.clone())
+        .collect();
+    let role_ids: Vec<String> = members
+        .iter()
+        .filter(|m| m.member_type == MemberType::Role)
+        .map(|m| m.member_id.clone())
+        .collect();
+
+    let mut names = HashMap::new();
+    if !user_ids.is_empty() {
+        for user in users::Entity::find()
+            .filter(users::Column::Id.is_in(user_ids))
+            .all(db)
+            .await?
+        {
+            names.insert(user.id, user.username);
+        }
+    }
+    if !role_ids.is_empty() {
+        for role in roles::Entity::find()
+            .filter(roles::Column::Id.is_in(role_ids))
+            .all(db)
+            .await?
+        {
+            names.insert(role.id, role.name);
+        }
+    }
+    Ok(names)
+}
+
+async fn list_members(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    resource_type: MemberResourceType,
+    resource_id: String,
+) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(db, req).await?;
+    ensure_resource(db, &scope, &resource_type, &resource_id).await?;
+
+    let members = resource_members::Entity::find()
+        .filter(resource_members::Column::ResourceType.eq(resource_type))
+        .filter(resource_members::Column::ResourceId.eq(resource_id))
+        .order_by_asc(resource_members::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let names = member_names(db, &members).await?;
+
+    Ok(HttpResponse::Ok().json(ResourceMemberListResponse {
+        data: members
+            .into_iter()
+            .map(|m| {
+                let name = names.get(&m.member_id).cloned();
+                ResourceMemberResponse::from_entity(m, name)
+            })
+            .collect(),
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    }))
+}
+
+async fn add_member(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    resource_type: MemberResourceType,
+    resource_id: String,
+    request: ResourceMemberCreateRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    let scope = request_scope(db, req).await?;
+    ensure_resource(db, &scope, &resource_type, &resource_id).await?;
+
+    let member_name = match request.member_type {
+        MemberType::User => users::Entity::find_by_id(&request.member_id)
+            .filter(users::Column::DeletedAt.is_null())
+            .one(db)
+            .await?
+            .map(|u| u.username)
+            .ok_or_else(|| ApiError::NotFound("用户不存在".to_string()))?,
+        MemberType::Role => roles::Entity::find_by_id(&request.member_id)
+            .filter(roles::Column::DeletedAt.is_null())
+            .one(db)
+            .await?
+            .map(|r| r.name)
+            .ok_or_else(|| ApiError::NotFound("角色不存在".to_string()))?,
+    };
+
+    let existing = resource_members::Entity::find()
+        .filter(resource_members::Column::ResourceType.eq(resource_type.clone()))
+        .filter(resource_members::Column::ResourceId.eq(&resource_id))
+        .filter(resource_members::Column::MemberType.eq(request.member_type.clone()))
+        .filter(resource_members::Column::MemberId.eq(&request.member_id))
+        .one(db)
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::BadRequest("成员已存在".to_string()));
+    }
+
+    let membership = resource_members::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        resource_type: Set(resource_type.clone()),
+        resource_id: Set(resource_id),
+        member_type: Set(request.member_type),
+        member_id: Set(request.member_id),
+        created_by: Set(user_id),
+        created_at: Set(Utc::now().into()),
+    }
+    .insert(db)
+    .await?;
+
+    let response = ResourceMemberResponse::from_entity(membership, Some(member_name));
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db,
+        audit_table(&resource_type),
+        "create",
+        req,
+        None,
+        Some(json!(response)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+async fn remove_member(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    resource_type: MemberResourceType,
+    resource_id: String,
+    membership_id: String,
+) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(db, req).await?;
+    ensure_resource(db, &scope, &resource_type, &resource_id).await?;
+
+    let membership = resource_members::Entity::find_by_id(&membership_id)
+        .filter(resource_members::Column::ResourceType.eq(resource_type.clone()))
+        .filter(resource_members::Column::ResourceId.eq(&resource_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("成员不存在".to_string()))?;
+
+    resource_members::Entity::delete_by_id(membership_id)
+        .exec(db)
+        .await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db,
+        audit_table(&resource_type),
+        "delete",
+        req,
+        Some(json!(ResourceMemberResponse::from_entity(membership, None))),
+        None,
+    )
+    .await;
+    Ok(HttpResponse::Ok().json("成员移除成功"))
+}
+
+/// GET /api/projects/{id}/members
+/// 获取项目成员
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/members",
+    params(("id" = String, Path, description = "项目ID")),
+    responses(
+        (status = 200, description = "返回项目成员", body = ResourceMemberListResponse),
+        (status = 403, description = "不在数据范围内"),
+        (status = 404, description = "项目不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Members"
+)]
+pub async fn get_project_members(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    list_members(&db, &req, MemberResourceType::Project, path.into_inner()).await
+}
+
+/// POST /api/projects/{id}/members
+/// 添加项目成员（用户或角色）
+#[utoipa::path(
+    post,
+    path = "/api/projects/{id}/members",
+    params(("id" = String, Path, description = "项目ID")),
+    request_body = ResourceMemberCreateRequest,
+    responses(
+        (status = 200, description = "添加成功", body = ResourceMemberResponse),
+        (status = 400, description = "成员已存在"),
+        (status = 403, description = "不在数据范围内"),
+        (status = 404, description = "项目、用户或角色不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Members"
+)]
+pub async fn add_project_member(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    request: web::Json<ResourceMemberCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    add_member(
+        &db,
+        &req,
+        MemberResourceType::Project,
+        path.into_inner(),
+        request.into_inner(),
+    )
+    .await
+}
+
+/// DELETE /api/projects/{id}/members/{member_id}
+/// 移除项目成员
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{id}/members/{member_id}",
+    params(
+        ("id" = String, Path, description = "项目ID"),
+        ("member_id" = String, Path, description = "成员记录ID")
+    ),
+    responses(
+        (status = 200, description = "移除成功"),
+        (status = 403, description = "不在数据范围内"),
+        (status = 404, description = "成员不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Members"
+)]
+pub async fn remove_project_member(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, membership_id) = path.into_inner();
+    remove_member(
+        &db,
+        &req,
+        MemberResourceType::Project,
+        project_id,
+        membership_id,
+    )
+    .await
+}
+
+/// GET /api/applications/{id}/members
+/// 获取应用成员
+#[utoipa::path(
+    get,
+    path = "/api/applications/{id}/members",
+    params(("id" = String, Path, description = "应用ID")),
+    responses(
+        (status = 200, description = "返回应用成员", body = ResourceMemberListResponse),
+        (status = 403, description = "不在数据范围内"),
+        (status = 404, description = "应用不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Members"
+)]
+pub async fn get_application_members(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    list_members(
+        &db,
+        &req,
+        MemberResourceType::Application,
+        path.into_inner(),
+    )
+    .await
+}
+
+/// POST /api/applications/{id}/members
+/// 添加应用成员（用户或角色）
+#[utoipa::path(
+    post,
+    path = "/api/applications/{id}/members",
+    params(("id" = String, Path, description = "应用ID")),
+    request_body = ResourceMemberCreateRequest,
+    responses(
+        (status = 200, description = "添加成功", body = ResourceMemberResponse),
+        (status = 400, description = "成员已存在"),
+        (status = 403, description = "不在数据范围内"),
+        (status = 404, description = "应用、用户或角色不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Members"
+)]
+pub async fn add_application_member(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    request: web::Json<ResourceMemberCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    add_member(
+        &db,
+        &req,
+        MemberResourceType::Application,
+        path.into_inner(),
+        request.into_inner(),
+    )
+    .await
+}
+
+/// DELETE /api/applications/{id}/members/{member_id}
+/// 移除应用成员
+#[utoipa::path(
+    delete,
+    path = "/api/applications/{id}/members/{member_id}",
+    params(
+        ("id" = String, Path, description = "应用ID"),
+        ("member_id" = String, Path, description = "成员记录ID")
+    ),
+    responses(
+        (status = 200, description = "移除成功"),
+        (status = 403, description = "不在数据范围内"),
+        (status = 404, description = "成员不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Members"
+)]
+pub async fn remove_application_member(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let (application_id, membership_id) = path.into_inner();
+    remove_member(
+        &db,
+        &req,
+        MemberResourceType::Application,
+        application_id,
+        membership_id,
+    )
+    .await
+}
@@ -0,0 +1,9 @@
+pub mod handlers;
+pub mod models;
+pub mod routes;
+pub mod scope;
+
+pub use routes::member_routes;
+pub use scope::{
+    ensure_instance_access, ensure_instances_access, request_scope, resolve_user_scope, DataScope,
+};
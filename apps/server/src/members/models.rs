@@ -0,0 +1,47 @@
+use crate::entities::resource_members;
+use crate::shared::enums::{MemberResourceType, MemberType};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 添加成员请求
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResourceMemberCreateRequest {
+    pub member_type: MemberType,
+    /// 用户ID或角色ID
+    pub member_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResourceMemberResponse {
+    pub id: String,
+    pub resource_type: MemberResourceType,
+    pub resource_id: String,
+    pub member_type: MemberType,
+    pub member_id: String,
+    /// 用户名或角色名
+    pub member_name: Option<String>,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+impl ResourceMemberResponse {
+    pub fn from_entity(model: resource_members::Model, member_name: Option<String>) -> Self {
+        Self {
+            id: model.id,
+            resource_type: model.resource_type,
+            resource_id: model.resource_id,
+            member_type: model.member_type,
+            member_id: model.member_id,
+            member_name,
+            created_by: model.created_by,
+            created_at: model.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResourceMemberListResponse {
+    pub data: Vec<ResourceMemberResponse>,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
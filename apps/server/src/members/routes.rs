@@ -0,0 +1,30 @@
+use super::handlers;
+use actix_web::web;
+
+pub fn member_routes(cfg: &mut web::ServiceConfig) {
+    // 项目/应用成员（需要认证）
+    cfg.route(
+        "/projects/{id}/members",
+        web::get().to(handlers::get_project_members),
+    )
+    .route(
+        "/projects/{id}/members",
+        web::post().to(handlers::add_project_member),
+    )
+    .route(
+        "/projects/{id}/members/{member_id}",
+        web::delete().to(handlers::remove_project_member),
+    )
+    .route(
+        "/applications/{id}/members",
+        web::get().to(handlers::get_application_members),
+    )
+    .route(
+        "/applications/{id}/members",
+        web::post().to(handlers::add_application_member),
+    )
+    .route(
+        "/applications/{id}/members/{member_id}",
+        web::delete().to(handlers::remove_application_member),
+    );
+}
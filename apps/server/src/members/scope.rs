@@ -0,0 +1,239 @@
+/// 数据范围
+///
+/// 管理员不受限；其他用户的可见范围由 resource_members 决定：直接或通过角色加入的项目（含其下全部应用）
+/// 以及直接加入的应用。实例、任务、配置、日志、文件均按应用归属过滤。
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::{applications, instances, resource_members, user_roles};
+use crate::permissions::cache;
+use crate::shared::enums::{MemberResourceType, MemberType};
+use crate::shared::error::ApiError;
+use actix_web::HttpRequest;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::HashSet;
+
+/// 用户可访问的数据范围
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataScope {
+    /// 不受限（管理员）
+    All,
+    /// 仅限指定项目与应用
+    Limited {
+        project_ids: HashSet<String>,
+        /// 已展开项目下的应用
+        application_ids: HashSet<String>,
+    },
+}
+
+impl DataScope {
+    pub fn is_all(&self) -> bool {
+        matches!(self, DataScope::All)
+    }
+
+    pub fn allows_application(&self, application_id: &str) -> bool {
+        match self {
+            DataScope::All => true,
+            DataScope::Limited {
+                application_ids, ..
+            } => application_ids.contains(application_id),
+        }
+    }
+
+    pub fn allows_project(&self, project_id: &str) -> bool {
+        match self {
+            DataScope::All => true,
+            DataScope::Limited { project_ids, .. } => project_ids.contains(project_id),
+        }
+    }
+
+    /// 用于 `is_in` 过滤的应用ID列表，None 表示不过滤
+    pub fn application_filter(&self) -> Option<Vec<String>> {
+        match self {
+            DataScope::All => None,
+            DataScope::Limited {
+                application_ids, ..
+            } => Some(application_ids.iter().cloned().collect()),
+        }
+    }
+
+    /// 用于 `is_in` 过滤的项目ID列表，None 表示不过滤
+    pub fn project_filter(&self) -> Option<Vec<String>> {
+        match self {
+            DataScope::All => None,
+            DataScope::Limited { project_ids, .. } => Some(project_ids.iter().cloned().collect()),
+        }
+    }
+
+    /// 校验应用是否在范围内
+    pub fn ensure_application(&self, application_id: &str) -> Result<(), ApiError> {
+        if self.allows_application(application_id) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(format!(
+                "无权访问应用 {} 的数据",
+                application_id
+            )))
+        }
+    }
+
+    /// 校验项目是否在范围内
+    pub fn ensure_project(&self, project_id: &str) -> Result<(), ApiError> {
+        if self.allows_project(project_id) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(format!(
+                "无权访问项目 {} 的数据",
+                project_id
+            )))
+        }
+    }
+
+    /// 无应用归属的数据（全局配置、未指定应用的任务等）仅不受限用户可修改
+    pub fn ensure_unrestricted(&self) -> Result<(), ApiError> {
+        if self.is_all() {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden("无权修改未归属应用的数据".to_string()))
+        }
+    }
+}
+
+/// 获取当前请求用户的数据范围
+pub async fn request_scope(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+) -> Result<DataScope, ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    resolve_user_scope(db, &user_id).await
+}
+
+/// 解析用户的数据范围
+pub async fn resolve_user_scope(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<DataScope, ApiError> {
+    if cache::get_user_permissions(db, user_id).await?.is_admin {
+        return Ok(DataScope::All);
+    }
+
+    let role_ids: Vec<String> = user_roles::Entity::find()
+        .select_only()
+        .column(user_roles::Column::RoleId)
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut member_condition = Condition::any().add(
+        Condition::all()
+            .add(resource_members::Column::MemberType.eq(MemberType::User))
+            .add(resource_members::Column::MemberId.eq(user_id)),
+    );
+    if !role_ids.is_empty() {
+        member_condition = member_condition.add(
+            Condition::all()
+                .add(resource_members::Column::MemberType.eq(MemberType::Role))
+                .add(resource_members::Column::MemberId.is_in(role_ids)),
+        );
+    }
+    let memberships = resource_members::Entity::find()
+        .filter(member_condition)
+        .all(db)
+        .await?;
+
+    let mut project_ids = HashSet::new();
+    let mut application_ids = HashSet::new();
+    for membership in memberships {
+        match membership.resource_type {
+            MemberResourceType::Project => project_ids.insert(membership.resource_id),
+            MemberResourceType::Application => application_ids.insert(membership.resource_id),
+        };
+    }
+
+    if !project_ids.is_empty() {
+        let project_apps: Vec<String> = applications::Entity::find()
+            .select_only()
+            .column(applications::Column::Id)
+            .filter(applications::Column::ProjectId.is_in(project_ids.iter().cloned()))
+            .filter(applications::Column::DeletedAt.is_null())
+            .into_tuple()
+            .all(db)
+            .await?;
+        application_ids.extend(project_apps);
+    }
+
+    Ok(DataScope::Limited {
+        project_ids,
+        application_ids,
+    })
+}
+
+/// 校验实例所属应用在范围内；实例不存在时返回 NotFound
+pub async fn ensure_instance_access(
+    db: &DatabaseConnection,
+    scope: &DataScope,
+    instance_id: &str,
+) -> Result<(), ApiError> {
+    if scope.is_all() {
+        return Ok(());
+    }
+    let application_id: Option<String> = instances::Entity::find_by_id(instance_id)
+        .select_only()
+        .column(instances::Column::ApplicationId)
+        .into_tuple()
+        .one(db)
+        .await?;
+    match application_id {
+        Some(application_id) => scope.ensure_application(&application_id),
+        None => Err(ApiError::NotFound("实例不存在".to_string())),
+    }
+}
+
+/// 校验一组实例均在范围内
+pub async fn ensure_instances_access(
+    db: &DatabaseConnection,
+    scope: &DataScope,
+    instance_ids: &[String],
+) -> Result<(), ApiError> {
+    if scope.is_all() || instance_ids.is_empty() {
+        return Ok(());
+    }
+    let rows: Vec<(String, String)> = instances::Entity::find()
+        .select_only()
+        .column(instances::Column::Id)
+        .column(instances::Column::ApplicationId)
+        .filter(instances::Column::Id.is_in(instance_ids.iter().cloned()))
+        .into_tuple()
+        .all(db)
+        .await?;
+    for (instance_id, application_id) in rows {
+        if !scope.allows_application(&application_id) {
+            return Err(ApiError::Forbidden(format!("无权操作实例 {}", instance_id)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limited_scope() {
+        let scope = DataScope::Limited {
+            project_ids: ["p1".to_string()].into_iter().collect(),
+            application_ids: ["a1".to_string(), "a2".to_string()].into_iter().collect(),
+        };
+        assert!(scope.allows_application("a1"));
+        assert!(!scope.allows_application("a3"));
+        assert!(scope.allows_project("p1"));
+        assert!(scope.ensure_application("a3").is_err());
+        assert!(scope.ensure_unrestricted().is_err());
+        let mut ids = scope.application_filter().unwrap();
+        ids.sort();
+        assert_eq!(ids, vec!["a1".to_string(), "a2".to_string()]);
+
+        assert!(DataScope::All.allows_application("a3"));
+        assert!(DataScope::All.application_filter().is_none());
+        assert!(DataScope::All.ensure_unrestricted().is_ok());
+    }
+}
@@ -0,0 +1,180 @@
+/// 用户权限缓存
+///
+/// 路由守卫每个请求都要用到用户的完整操作权限集，这里按用户缓存解析结果。
+/// 角色权限、用户角色变更时主动失效；多实例部署下其它节点的变更依赖 TTL 兜底。
+use crate::entities::permissions::{Column as PermissionColumn, Entity as Permissions};
+use crate::entities::role_permissions::{
+    Column as RolePermissionColumn, Entity as RolePermissions,
+};
+use crate::entities::roles::Entity as Roles;
+use crate::entities::user_roles::{Column as UserRoleColumn, Entity as UserRoles};
+use crate::shared::enums::PermissionType;
+use once_cell::sync::Lazy;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// 缓存有效期
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// 用户解析后的权限集
+#[derive(Clone, Debug, Default)]
+pub struct UserPermissions {
+    pub is_admin: bool,
+    /// 操作类权限名称，如 `projects.view`
+    pub names: HashSet<String>,
+}
+
+impl UserPermissions {
+    /// 是否拥有指定权限；管理员拥有全部权限，`<resource>.manage` 涵盖该资源下的所有操作
+    pub fn allows(&self, permission: &str) -> bool {
+        if self.is_admin || self.names.contains(permission) {
+            return true;
+        }
+        permission
+            .rsplit_once('.')
+            .is_some_and(|(resource, _)| self.names.contains(&format!("{}.manage", resource)))
+    }
+}
+
+struct CacheEntry {
+    loaded_at: Instant,
+    permissions: Arc<UserPermissions>,
+}
+
+static CACHE: Lazy<RwLock<HashMap<String, CacheEntry>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 每次失效递增，避免加载期间发生的变更被旧结果覆盖
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 获取用户权限集，优先读缓存
+pub async fn get_user_permissions(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Arc<UserPermissions>, sea_orm::DbErr> {
+    if let Some(entry) = CACHE.read().unwrap().get(user_id) {
+        if entry.loaded_at.elapsed() < CACHE_TTL {
+            return Ok(entry.permissions.clone());
+        }
+    }
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    let permissions = Arc::new(load_user_permissions(db, user_id).await?);
+
+    let mut cache = CACHE.write().unwrap();
+    if GENERATION.load(Ordering::SeqCst) == generation {
+        cache.insert(
+            user_id.to_string(),
+            CacheEntry {
+                loaded_at: Instant::now(),
+                permissions: permissions.clone(),
+            },
+        );
+    }
+    Ok(permissions)
+}
+
+/// 直接写入用户权限集（仅测试使用，绕过角色加载）
+#[cfg(test)]
+pub fn set_user_permissions(user_id: &str, permissions: UserPermissions) {
+    CACHE.write().unwrap().insert(
+        user_id.to_string(),
+        CacheEntry {
+            loaded_at: Instant::now(),
+            permissions: Arc::new(permissions),
+        },
+    );
+}
+
+/// 用户角色变更后失效该用户的缓存
+pub fn invalidate_user(user_id: &str) {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    CACHE.write().unwrap().remove(user_id);
+}
+
+/// 角色或权限定义变更后失效全部缓存
+pub fn invalidate_all() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    CACHE.write().unwrap().clear();
+}
+
+/// 从数据库解析用户权限：用户角色 → 角色权限 → 未删除的操作类权限
+async fn load_user_permissions(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<UserPermissions, sea_orm::DbErr> {
+    let roles: Vec<_> = UserRoles::find()
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .find_with_related(Roles)
+        .all(db)
+        .await?
+        .into_iter()
+        .flat_map(|(_, roles)| roles)
+        .filter(|role| role.deleted_at.is_none())
+        .collect();
+
+    if roles.iter().any(|role| role.name.to_lowercase() == "admin") {
+        return Ok(UserPermissions {
+            is_admin: true,
+            names: HashSet::new(),
+        });
+    }
+    if roles.is_empty() {
+        return Ok(UserPermissions::default());
+    }
+
+    let role_ids: Vec<String> = roles.into_iter().map(|role| role.id).collect();
+    let permission_ids: Vec<String> = RolePermissions::find()
+        .select_only()
+        .column(RolePermissionColumn::PermissionId)
+        .filter(RolePermissionColumn::RoleId.is_in(role_ids))
+        .into_tuple()
+        .all(db)
+        .await?;
+    if permission_ids.is_empty() {
+        return Ok(UserPermissions::default());
+    }
+
+    let names: Vec<String> = Permissions::find()
+        .select_only()
+        .column(PermissionColumn::Name)
+        .filter(PermissionColumn::Id.is_in(permission_ids))
+        .filter(PermissionColumn::DeletedAt.is_null())
+        .filter(PermissionColumn::PermissionType.eq(PermissionType::Action))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(UserPermissions {
+        is_admin: false,
+        names: names.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_with_manage_permission() {
+        let permissions = UserPermissions {
+            is_admin: false,
+            names: ["projects.view", "alerts.manage"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        };
+        assert!(permissions.allows("projects.view"));
+        assert!(!permissions.allows("projects.delete"));
+        assert!(permissions.allows("alerts.delete"));
+        assert!(!permissions.allows("configs.view"));
+
+        let admin = UserPermissions {
+            is_admin: true,
+            names: HashSet::new(),
+        };
+        assert!(admin.allows("configs.delete"));
+    }
+}
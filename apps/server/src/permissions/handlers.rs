@@ -433,6 +433,7 @@ pub async fn update_permission(
         Some(after),
     )
     .await;
+    crate::permissions::cache::invalidate_all();
 
     Ok(HttpResponse::Ok().json(response))
 }
@@ -506,6 +507,8 @@ pub async fn delete_permission(
     )
     .await;
 
+    crate::permissions::cache::invalidate_all();
+
     Ok(HttpResponse::Ok().json("权限删除成功"))
 }
 
@@ -821,6 +824,8 @@ pub async fn assign_permissions(
         role_permission.insert(&**db).await?;
     }
 
+    crate::permissions::cache::invalidate_all();
+
     Ok(HttpResponse::Ok().json("权限分配成功"))
 }
 
@@ -837,6 +842,8 @@ pub async fn revoke_permissions(
         .exec(&**db)
         .await?;
 
+    crate::permissions::cache::invalidate_all();
+
     Ok(HttpResponse::Ok().json("权限撤销成功"))
 }
 
@@ -1,3 +1,5 @@
+pub mod cache;
+pub mod guard;
 pub mod handlers;
 pub mod models;
 pub mod routes;
@@ -1,10 +1,14 @@
 use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::applications;
 use crate::entities::applications::Entity as Applications;
 use crate::entities::projects::{ActiveModel, Entity as Projects};
+use crate::members::handlers::add_creator_membership;
+use crate::members::{request_scope, DataScope};
 use crate::permissions::handlers::get_user_permission_by_name;
 use crate::projects::models::{
     Pagination, ProjectCreateRequest, ProjectListQuery, ProjectListResponse, ProjectResponse,
 };
+use crate::shared::enums::MemberResourceType;
 use crate::shared::error::ApiError;
 use crate::shared::snowflake::generate_snowflake_id;
 use crate::shared::{enums, request_context};
@@ -16,6 +20,29 @@ use sea_orm::{
 };
 use serde_json::json;
 
+/// 可见项目：成员项目以及可访问应用所属的项目，None 表示不受限
+async fn visible_project_ids(
+    db: &DatabaseConnection,
+    scope: &DataScope,
+) -> Result<Option<Vec<String>>, ApiError> {
+    let (Some(mut project_ids), Some(application_ids)) =
+        (scope.project_filter(), scope.application_filter())
+    else {
+        return Ok(None);
+    };
+    if !application_ids.is_empty() {
+        let app_project_ids: Vec<String> = Applications::find()
+            .select_only()
+            .column(applications::Column::ProjectId)
+            .filter(applications::Column::Id.is_in(application_ids))
+            .into_tuple()
+            .all(db)
+            .await?;
+        project_ids.extend(app_project_ids);
+    }
+    Ok(Some(project_ids))
+}
+
 #[utoipa::path(
     get,
     path = "/api/projects",
@@ -36,7 +63,9 @@ use serde_json::json;
 pub async fn get_projects(
     db: web::Data<DatabaseConnection>,
     query: web::Query<ProjectListQuery>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    let scope = request_scope(&db, &req).await?;
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
     let offset = (page - 1) * limit;
@@ -45,6 +74,11 @@ pub async fn get_projects(
     let mut select =
         Projects::find().filter(crate::entities::projects::Column::DeletedAt.is_null());
 
+    // 数据范围：仅返回可见的项目
+    if let Some(project_ids) = visible_project_ids(&db, &scope).await? {
+        select = select.filter(crate::entities::projects::Column::Id.is_in(project_ids));
+    }
+
     // 如果有搜索关键词，按名称、编码、描述搜索（任意匹配）
     if let Some(search) = &query.search {
         if !search.is_empty() {
@@ -156,6 +190,14 @@ pub async fn create_project(
     };
 
     let saved_project = new_project.insert(&**db).await?;
+    // 创建者自动成为项目成员
+    add_creator_membership(
+        &db,
+        MemberResourceType::Project,
+        &saved_project.id,
+        &current_user_id,
+    )
+    .await?;
 
     let response = ProjectResponse {
         id: saved_project.id,
@@ -206,8 +248,18 @@ pub async fn create_project(
 pub async fn get_project(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let project_id = path.into_inner();
+    let scope = request_scope(&db, &req).await?;
+    if let Some(project_ids) = visible_project_ids(&db, &scope).await? {
+        if !project_ids.contains(&project_id) {
+            return Err(ApiError::Forbidden(format!(
+                "无权访问项目 {} 的数据",
+                project_id
+            )));
+        }
+    }
 
     let project = Projects::find_by_id(project_id).one(&**db).await?;
 
@@ -253,6 +305,9 @@ pub async fn update_project(
     req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let project_id = path.into_inner();
+    request_scope(&db, &req)
+        .await?
+        .ensure_project(&project_id)?;
 
     // 查找项目
     let existing_project = Projects::find_by_id(&project_id).one(&**db).await?;
@@ -349,8 +404,12 @@ pub async fn update_project(
 pub async fn delete_project(
     db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let project_id = path.into_inner();
+    request_scope(&db, &req)
+        .await?
+        .ensure_project(&project_id)?;
 
     // 查找项目
     let existing_project = Projects::find_by_id(&project_id).one(&**db).await?;
@@ -441,6 +500,9 @@ pub async fn enable_project(
         return Err(ApiError::Forbidden("没有权限启用项目".to_string()));
     }
 
+    request_scope(&db, &req)
+        .await?
+        .ensure_project(&project_id)?;
     let existing = Projects::find_by_id(&project_id).one(&**db).await?;
     let project = match existing {
         Some(p) => {
@@ -493,6 +555,9 @@ pub async fn disable_project(
         return Err(ApiError::Forbidden("没有权限禁用项目".to_string()));
     }
 
+    request_scope(&db, &req)
+        .await?
+        .ensure_project(&project_id)?;
     let existing = Projects::find_by_id(&project_id).one(&**db).await?;
     let project = match existing {
         Some(p) => {
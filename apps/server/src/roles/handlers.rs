@@ -439,6 +439,7 @@ pub async fn update_role(
         Some(after),
     )
     .await;
+    crate::permissions::cache::invalidate_all();
 
     Ok(HttpResponse::Ok().json(response))
 }
@@ -512,6 +513,8 @@ pub async fn delete_role(
     )
     .await;
 
+    crate::permissions::cache::invalidate_all();
+
     Ok(HttpResponse::Ok().json("角色删除成功"))
 }
 
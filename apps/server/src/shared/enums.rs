@@ -52,6 +52,9 @@ pub enum OnlineStatus {
 #[sea_orm(rs_type = "String", db_type = "Text")]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
+    /// 分批执行中尚未放行的记录
+    #[sea_orm(string_value = "queued")]
+    Queued,
     #[sea_orm(string_value = "pending")]
     Pending,
     #[sea_orm(string_value = "dispatched")]
@@ -68,6 +71,36 @@ pub enum TaskStatus {
     Cancelled,
 }
 
+// 定时任务错过触发时间的处理策略
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    /// 补触发一次
+    #[sea_orm(string_value = "fire_once")]
+    FireOnce,
+    /// 跳过错过的触发
+    #[sea_orm(string_value = "skip")]
+    Skip,
+}
+
+// 定时任务触发结果
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleRunStatus {
+    #[sea_orm(string_value = "created")]
+    Created,
+    #[sea_orm(string_value = "skipped")]
+    Skipped,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
 // 任务类型
 #[derive(
     Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
@@ -85,6 +118,8 @@ pub enum TaskType {
     CustomCommand,
     #[sea_orm(string_value = "http_request")]
     HttpRequest,
+    #[sea_orm(string_value = "agent_upgrade")]
+    AgentUpgrade,
 }
 
 #[derive(
@@ -158,6 +193,25 @@ pub enum Environment {
     Prod,
 }
 
+// 配置版本状态
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigStatus {
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    #[sea_orm(string_value = "published")]
+    Published,
+}
+
 // Agent 类型
 #[derive(
     Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
@@ -263,4 +317,101 @@ pub enum LogType {
     Request,
     #[sea_orm(string_value = "operation")]
     Operation,
+    /// Agent 采集的应用日志文件
+    #[sea_orm(string_value = "application")]
+    Application,
+}
+
+// 告警规则类型
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AlertRuleType {
+    /// 指标超过阈值并持续一段时间
+    #[sea_orm(string_value = "metric_threshold")]
+    MetricThreshold,
+    /// 实例离线超过指定时长
+    #[sea_orm(string_value = "instance_offline")]
+    InstanceOffline,
+    /// 时间窗口内任务失败率超过阈值
+    #[sea_orm(string_value = "task_failure_rate")]
+    TaskFailureRate,
+}
+
+// 告警级别
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    #[sea_orm(string_value = "info")]
+    Info,
+    #[sea_orm(string_value = "warning")]
+    Warning,
+    #[sea_orm(string_value = "critical")]
+    Critical,
+}
+
+// 告警事件状态
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    /// 条件已满足，等待持续时间
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "firing")]
+    Firing,
+    #[sea_orm(string_value = "resolved")]
+    Resolved,
+}
+
+// 告警通知渠道类型
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AlertChannelType {
+    #[sea_orm(string_value = "webhook")]
+    Webhook,
+    #[sea_orm(string_value = "email")]
+    Email,
+    /// 钉钉群机器人
+    #[sea_orm(string_value = "dingtalk")]
+    Dingtalk,
+    /// 企业微信群机器人
+    #[sea_orm(string_value = "wecom")]
+    Wecom,
+}
+
+// 成员关系所属资源类型
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum MemberResourceType {
+    #[sea_orm(string_value = "project")]
+    Project,
+    #[sea_orm(string_value = "application")]
+    Application,
+}
+
+// 成员类型
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DeriveActiveEnum, EnumIter, ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum MemberType {
+    #[sea_orm(string_value = "user")]
+    User,
+    #[sea_orm(string_value = "role")]
+    Role,
 }
@@ -0,0 +1,141 @@
+/// cron 表达式解析
+///
+/// 支持标准 5 段表达式（分 时 日 月 周，周日为 0 或 7）与带秒的 6 段表达式，
+/// 按 IANA 时区计算触发时间后转换为 UTC。
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+pub struct CronSchedule {
+    schedule: cron::Schedule,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str, timezone: &str) -> Result<Self, String> {
+        let timezone = Tz::from_str(timezone).map_err(|_| format!("无效的时区: {}", timezone))?;
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let normalized = match fields.len() {
+            5 => format!(
+                "0 {} {} {} {} {}",
+                fields[0],
+                fields[1],
+                fields[2],
+                fields[3],
+                standard_weekdays(fields[4])?
+            ),
+            6 | 7 => fields.join(" "),
+            _ => return Err("cron 表达式应为 5 段或 6 段".to_string()),
+        };
+        let schedule = cron::Schedule::from_str(&normalized)
+            .map_err(|e| format!("无效的 cron 表达式: {}", e))?;
+        Ok(Self { schedule, timezone })
+    }
+
+    /// 指定时间之后的下一次触发时间
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .next()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// 指定时间之后的若干次触发时间
+    pub fn upcoming(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .take(count)
+            .map(|t| t.with_timezone(&Utc))
+            .collect()
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+}
+
+/// 将标准 cron 的周字段（0-7，周日为 0 或 7）转换为星期名称
+fn standard_weekdays(field: &str) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for part in field.split(',') {
+        let (base, step) = match part.split_once('/') {
+            Some((base, step)) => (
+                base,
+                step.parse::<usize>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("无效的周字段: {}", field))?,
+            ),
+            None => (part, 1),
+        };
+        if base == "*" || base == "?" || base.chars().any(|c| c.is_ascii_alphabetic()) {
+            parts.push(part.to_string());
+            continue;
+        }
+        let parse = |v: &str| {
+            v.parse::<usize>()
+                .ok()
+                .filter(|d| *d <= 7)
+                .ok_or_else(|| format!("无效的周字段: {}", field))
+        };
+        let (start, end) = match base.split_once('-') {
+            Some((a, b)) => (parse(a)?, parse(b)?),
+            None if part.contains('/') => (parse(base)?, 6),
+            None => {
+                let d = parse(base)?;
+                (d, d)
+            }
+        };
+        if start > end {
+            return Err(format!("无效的周字段: {}", field));
+        }
+        for day in (start..=end).step_by(step) {
+            parts.push(WEEKDAY_NAMES[day % 7].to_string());
+        }
+    }
+    Ok(parts.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone, Timelike, Weekday};
+
+    #[test]
+    fn test_standard_expression_with_timezone() {
+        let cron = CronSchedule::parse("0 2 * * *", "Asia/Shanghai").unwrap();
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let next = cron.next_after(after).unwrap();
+        // 北京时间 02:00 即 UTC 前一日 18:00
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 18, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_sunday_and_weekday_ranges() {
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        for expr in ["0 3 * * 0", "0 3 * * 7", "0 3 * * SUN"] {
+            let next = CronSchedule::parse(expr, "UTC")
+                .unwrap()
+                .next_after(after)
+                .unwrap();
+            assert_eq!(next.weekday(), Weekday::Sun, "{}", expr);
+            assert_eq!(next.hour(), 3);
+        }
+        let workdays = CronSchedule::parse("30 9 * * 1-5", "UTC")
+            .unwrap()
+            .upcoming(after, 7);
+        assert!(workdays
+            .iter()
+            .all(|t| t.weekday() != Weekday::Sat && t.weekday() != Weekday::Sun));
+        assert_eq!(workdays[0].weekday(), Weekday::Wed);
+    }
+
+    #[test]
+    fn test_invalid_expression() {
+        assert!(CronSchedule::parse("0 2 * *", "UTC").is_err());
+        assert!(CronSchedule::parse("0 2 * * 8", "UTC").is_err());
+        assert!(CronSchedule::parse("0 2 * * *", "Mars/Base").is_err());
+    }
+}
@@ -0,0 +1,544 @@
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::{instance_task_schedule_runs, instance_task_schedules};
+use crate::instance_tasks::handlers::ensure_targets_in_scope;
+use crate::instance_tasks::models::{Pagination, SelectorResolveAt, TaskCreateRequest};
+use crate::members::{resolve_user_scope, DataScope};
+use crate::shared::enums::MisfirePolicy;
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use crate::task_schedules::cron::CronSchedule;
+use crate::task_schedules::models::*;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde_json::json;
+
+/// 校验 cron 表达式与时区，返回下次触发时间
+fn next_run_at(cron_expression: &str, timezone: &str) -> Result<DateTime<FixedOffset>, ApiError> {
+    let cron = CronSchedule::parse(cron_expression, timezone).map_err(ApiError::ValidationError)?;
+    cron.next_after(Utc::now())
+        .map(DateTime::<FixedOffset>::from)
+        .ok_or_else(|| ApiError::ValidationError("cron_expression has no upcoming run".to_string()))
+}
+
+/// 校验任务模板（目标实例在每次触发时解析，这里只校验结构）
+fn validate_template(template: &TaskCreateRequest) -> Result<(), ApiError> {
+    if template.task_name.trim().is_empty() {
+        return Err(ApiError::ValidationError(
+            "task_template.task_name is required".to_string(),
+        ));
+    }
+    if template.target_instances.is_empty() && template.target_selector.is_none() {
+        return Err(ApiError::ValidationError(
+            "task_template requires target_instances or target_selector".to_string(),
+        ));
+    }
+    if let Some(strategy) = &template.rollout_strategy {
+        strategy.validate()?;
+        if template
+            .target_selector
+            .as_ref()
+            .is_some_and(|s| s.resolve_at == SelectorResolveAt::Dispatch)
+        {
+            return Err(ApiError::ValidationError(
+                "rollout_strategy cannot be combined with dispatch-time target resolution"
+                    .to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn audit_snapshot(schedule: &instance_task_schedules::Model) -> serde_json::Value {
+    json!({
+        "id": schedule.id,
+        "name": schedule.name,
+        "application_id": schedule.application_id,
+        "enabled": schedule.enabled,
+        "cron_expression": schedule.cron_expression,
+        "timezone": schedule.timezone,
+        "misfire_policy": schedule.misfire_policy,
+        "allow_overlap": schedule.allow_overlap,
+        "task_template": schedule.task_template,
+        "updated_at": schedule.updated_at.to_rfc3339(),
+    })
+}
+
+/// 查询定时任务并校验数据范围：所属应用可访问，或未指定应用且为本人创建
+async fn find_schedule(
+    db: &DatabaseConnection,
+    req: &HttpRequest,
+    schedule_id: &str,
+) -> Result<instance_task_schedules::Model, ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    let schedule = instance_task_schedules::Entity::find_by_id(schedule_id)
+        .filter(instance_task_schedules::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task schedule not found".to_string()))?;
+    let scope = resolve_user_scope(db, &user_id).await?;
+    let allowed = match &schedule.application_id {
+        Some(application_id) => scope.allows_application(application_id),
+        None => scope.is_all() || schedule.created_by == user_id,
+    };
+    if allowed {
+        Ok(schedule)
+    } else {
+        Err(ApiError::Forbidden("无权访问该定时任务".to_string()))
+    }
+}
+
+/// 校验定时任务及其任务模板的目标在数据范围内
+async fn ensure_template_in_scope(
+    db: &DatabaseConnection,
+    scope: &DataScope,
+    application_id: Option<&str>,
+    template: &TaskCreateRequest,
+) -> Result<(), ApiError> {
+    if let Some(application_id) = application_id.filter(|id| !id.is_empty()) {
+        scope.ensure_application(application_id)?;
+    }
+    ensure_targets_in_scope(
+        db,
+        scope,
+        template.application_id.as_deref(),
+        &template.target_instances,
+        template.target_selector.as_ref(),
+    )
+    .await
+}
+
+fn timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// GET /api/instances/task-schedules
+/// 获取定时任务列表
+#[utoipa::path(
+    get,
+    path = "/api/instances/task-schedules",
+    params(TaskScheduleListQuery),
+    responses(
+        (status = 200, description = "返回定时任务列表", body = TaskScheduleListResponse),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn get_task_schedules(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<TaskScheduleListQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let scope = resolve_user_scope(&db, &user_id).await?;
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = (page - 1) * limit;
+
+    let mut select = instance_task_schedules::Entity::find()
+        .filter(instance_task_schedules::Column::DeletedAt.is_null());
+    if let Some(application_ids) = scope.application_filter() {
+        select = select.filter(
+            Condition::any()
+                .add(instance_task_schedules::Column::ApplicationId.is_in(application_ids))
+                .add(
+                    Condition::all()
+                        .add(instance_task_schedules::Column::ApplicationId.is_null())
+                        .add(instance_task_schedules::Column::CreatedBy.eq(&user_id)),
+                ),
+        );
+    }
+    if let Some(application_id) = &query.application_id {
+        select = select.filter(instance_task_schedules::Column::ApplicationId.eq(application_id));
+    }
+    if let Some(enabled) = query.enabled {
+        select = select.filter(instance_task_schedules::Column::Enabled.eq(enabled));
+    }
+    if let Some(search) = query.search.as_deref().filter(|s| !s.is_empty()) {
+        select = select.filter(instance_task_schedules::Column::Name.contains(search));
+    }
+
+    let total = select.clone().count(&**db).await?;
+    let schedules = select
+        .order_by_desc(instance_task_schedules::Column::CreatedAt)
+        .offset(offset as u64)
+        .limit(limit as u64)
+        .all(&**db)
+        .await?;
+
+    let response = TaskScheduleListResponse {
+        data: schedules
+            .into_iter()
+            .map(TaskScheduleResponse::from_entity)
+            .collect(),
+        pagination: Pagination {
+            page,
+            limit,
+            total: total as u32,
+        },
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/instances/task-schedules
+/// 创建定时任务
+#[utoipa::path(
+    post,
+    path = "/api/instances/task-schedules",
+    request_body = TaskScheduleCreateRequest,
+    responses(
+        (status = 200, description = "创建成功", body = TaskScheduleResponse),
+        (status = 400, description = "cron 表达式、时区或任务模板无效"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn create_task_schedule(
+    db: web::Data<DatabaseConnection>,
+    request: web::Json<TaskScheduleCreateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+
+    if request.name.trim().is_empty() {
+        return Err(ApiError::ValidationError("name is required".to_string()));
+    }
+    let timezone = request.timezone.unwrap_or_else(|| "UTC".to_string());
+    let next_run = next_run_at(&request.cron_expression, &timezone)?;
+    validate_template(&request.task_template)?;
+    let scope = resolve_user_scope(&db, &user_id).await?;
+    ensure_template_in_scope(
+        &db,
+        &scope,
+        request.application_id.as_deref(),
+        &request.task_template,
+    )
+    .await?;
+    let enabled = request.enabled.unwrap_or(true);
+    let application_id = request
+        .application_id
+        .or_else(|| request.task_template.application_id.clone());
+
+    let now = Utc::now();
+    let schedule = instance_task_schedules::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        name: Set(request.name),
+        description: Set(request.description),
+        application_id: Set(application_id),
+        enabled: Set(enabled),
+        cron_expression: Set(request.cron_expression.trim().to_string()),
+        timezone: Set(timezone),
+        misfire_policy: Set(request.misfire_policy.unwrap_or(MisfirePolicy::FireOnce)),
+        allow_overlap: Set(request.allow_overlap.unwrap_or(false)),
+        task_template: Set(json!(request.task_template)),
+        next_run_at: Set(enabled.then_some(next_run)),
+        last_run_at: Set(None),
+        created_by: Set(user_id.clone()),
+        updated_by: Set(user_id),
+        deleted_at: Set(None),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    }
+    .insert(&**db)
+    .await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "instance_task_schedules",
+        "create",
+        &req,
+        None,
+        Some(audit_snapshot(&schedule)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(TaskScheduleResponse::from_entity(schedule)))
+}
+
+/// GET /api/instances/task-schedules/{schedule_id}
+/// 获取定时任务详情
+#[utoipa::path(
+    get,
+    path = "/api/instances/task-schedules/{schedule_id}",
+    params(("schedule_id" = String, Path, description = "定时任务ID")),
+    responses(
+        (status = 200, description = "返回定时任务详情", body = TaskScheduleResponse),
+        (status = 404, description = "定时任务不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn get_task_schedule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let schedule = find_schedule(&db, &req, &path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(TaskScheduleResponse::from_entity(schedule)))
+}
+
+/// PUT /api/instances/task-schedules/{schedule_id}
+/// 更新定时任务
+#[utoipa::path(
+    put,
+    path = "/api/instances/task-schedules/{schedule_id}",
+    params(("schedule_id" = String, Path, description = "定时任务ID")),
+    request_body = TaskScheduleUpdateRequest,
+    responses(
+        (status = 200, description = "更新成功", body = TaskScheduleResponse),
+        (status = 400, description = "cron 表达式、时区或任务模板无效"),
+        (status = 404, description = "定时任务不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn update_task_schedule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    request: web::Json<TaskScheduleUpdateRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(&req)?;
+    let request = request.into_inner();
+    let schedule = find_schedule(&db, &req, &path.into_inner()).await?;
+    let before = audit_snapshot(&schedule);
+
+    let cron_expression = request
+        .cron_expression
+        .map(|c| c.trim().to_string())
+        .unwrap_or_else(|| schedule.cron_expression.clone());
+    let timezone = request
+        .timezone
+        .unwrap_or_else(|| schedule.timezone.clone());
+    let next_run = next_run_at(&cron_expression, &timezone)?;
+    if let Some(template) = &request.task_template {
+        validate_template(template)?;
+        let scope = resolve_user_scope(&db, &user_id).await?;
+        ensure_template_in_scope(&db, &scope, schedule.application_id.as_deref(), template).await?;
+    }
+
+    let enabled = schedule.enabled;
+    let mut active: instance_task_schedules::ActiveModel = schedule.into();
+    if let Some(name) = request.name {
+        if name.trim().is_empty() {
+            return Err(ApiError::ValidationError("name is required".to_string()));
+        }
+        active.name = Set(name);
+    }
+    if let Some(description) = request.description {
+        active.description = Set(Some(description));
+    }
+    if let Some(policy) = request.misfire_policy {
+        active.misfire_policy = Set(policy);
+    }
+    if let Some(allow_overlap) = request.allow_overlap {
+        active.allow_overlap = Set(allow_overlap);
+    }
+    if let Some(template) = request.task_template {
+        active.task_template = Set(json!(template));
+    }
+    active.cron_expression = Set(cron_expression);
+    active.timezone = Set(timezone);
+    // 触发规则可能变化，重新计算下次触发时间
+    active.next_run_at = Set(enabled.then_some(next_run));
+    active.updated_by = Set(user_id);
+    active.updated_at = Set(Utc::now().into());
+    let schedule = active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "instance_task_schedules",
+        "update",
+        &req,
+        Some(before),
+        Some(audit_snapshot(&schedule)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(TaskScheduleResponse::from_entity(schedule)))
+}
+
+/// DELETE /api/instances/task-schedules/{schedule_id}
+/// 删除定时任务（软删除，已生成的任务不受影响）
+#[utoipa::path(
+    delete,
+    path = "/api/instances/task-schedules/{schedule_id}",
+    params(("schedule_id" = String, Path, description = "定时任务ID")),
+    responses(
+        (status = 200, description = "删除成功"),
+        (status = 404, description = "定时任务不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn delete_task_schedule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let schedule = find_schedule(&db, &req, &path.into_inner()).await?;
+    let before = audit_snapshot(&schedule);
+
+    let mut active: instance_task_schedules::ActiveModel = schedule.into();
+    active.enabled = Set(false);
+    active.next_run_at = Set(None);
+    active.deleted_at = Set(Some(Utc::now().into()));
+    active.updated_at = Set(Utc::now().into());
+    active.update(&**db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        &db,
+        "instance_task_schedules",
+        "delete",
+        &req,
+        Some(before),
+        None,
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "message": "Task schedule deleted successfully"
+    })))
+}
+
+/// 启用或停用定时任务
+async fn set_enabled(
+    db: &DatabaseConnection,
+    schedule_id: &str,
+    enabled: bool,
+    req: &HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = get_user_id_from_request(req)?;
+    let schedule = find_schedule(db, req, schedule_id).await?;
+    let before = audit_snapshot(&schedule);
+    // 启用时从当前时间起计算，停用期间错过的触发不补
+    let next_run = if enabled {
+        Some(next_run_at(&schedule.cron_expression, &schedule.timezone)?)
+    } else {
+        None
+    };
+
+    let mut active: instance_task_schedules::ActiveModel = schedule.into();
+    active.enabled = Set(enabled);
+    active.next_run_at = Set(next_run);
+    active.updated_by = Set(user_id);
+    active.updated_at = Set(Utc::now().into());
+    let schedule = active.update(db).await?;
+
+    let _ = crate::shared::request_context::record_audit_log_simple(
+        db,
+        "instance_task_schedules",
+        if enabled { "enable" } else { "disable" },
+        req,
+        Some(before),
+        Some(audit_snapshot(&schedule)),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(TaskScheduleResponse::from_entity(schedule)))
+}
+
+/// POST /api/instances/task-schedules/{schedule_id}/enable
+/// 启用定时任务
+#[utoipa::path(
+    post,
+    path = "/api/instances/task-schedules/{schedule_id}/enable",
+    params(("schedule_id" = String, Path, description = "定时任务ID")),
+    responses(
+        (status = 200, description = "启用成功", body = TaskScheduleResponse),
+        (status = 404, description = "定时任务不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn enable_task_schedule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    set_enabled(&db, &path.into_inner(), true, &req).await
+}
+
+/// POST /api/instances/task-schedules/{schedule_id}/disable
+/// 停用定时任务
+#[utoipa::path(
+    post,
+    path = "/api/instances/task-schedules/{schedule_id}/disable",
+    params(("schedule_id" = String, Path, description = "定时任务ID")),
+    responses(
+        (status = 200, description = "停用成功", body = TaskScheduleResponse),
+        (status = 404, description = "定时任务不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn disable_task_schedule(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    set_enabled(&db, &path.into_inner(), false, &req).await
+}
+
+/// GET /api/instances/task-schedules/{schedule_id}/runs
+/// 获取定时任务触发历史
+#[utoipa::path(
+    get,
+    path = "/api/instances/task-schedules/{schedule_id}/runs",
+    params(
+        ("schedule_id" = String, Path, description = "定时任务ID"),
+        TaskScheduleRunListQuery
+    ),
+    responses(
+        (status = 200, description = "返回触发历史", body = TaskScheduleRunListResponse),
+        (status = 404, description = "定时任务不存在"),
+        (status = 500, description = "服务器错误")
+    ),
+    tag = "Task Schedules"
+)]
+pub async fn get_task_schedule_runs(
+    db: web::Data<DatabaseConnection>,
+    path: web::Path<String>,
+    query: web::Query<TaskScheduleRunListQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let schedule = find_schedule(&db, &req, &path.into_inner()).await?;
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = (page - 1) * limit;
+
+    let mut select = instance_task_schedule_runs::Entity::find()
+        .filter(instance_task_schedule_runs::Column::ScheduleId.eq(&schedule.id));
+    if let Some(status) = &query.status {
+        select = select.filter(instance_task_schedule_runs::Column::Status.eq(status.clone()));
+    }
+
+    let total = select.clone().count(&**db).await?;
+    let runs = select
+        .order_by_desc(instance_task_schedule_runs::Column::ScheduledAt)
+        .offset(offset as u64)
+        .limit(limit as u64)
+        .all(&**db)
+        .await?;
+
+    let response = TaskScheduleRunListResponse {
+        data: runs
+            .into_iter()
+            .map(TaskScheduleRunResponse::from_entity)
+            .collect(),
+        pagination: Pagination {
+            page,
+            limit,
+            total: total as u32,
+        },
+        timestamp: timestamp(),
+        trace_id: generate_snowflake_id(),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
@@ -0,0 +1,7 @@
+pub mod cron;
+pub mod handlers;
+pub mod models;
+pub mod routes;
+pub mod runner;
+
+pub use routes::task_schedule_routes;
@@ -0,0 +1,176 @@
+use crate::instance_tasks::models::{Pagination, TaskCreateRequest};
+use crate::shared::enums::{MisfirePolicy, ScheduleRunStatus};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use utoipa::{IntoParams, ToSchema};
+
+/// 创建定时任务请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskScheduleCreateRequest {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    /// cron 表达式：5 段（分 时 日 月 周）或带秒的 6 段
+    pub cron_expression: String,
+    /// IANA 时区，默认 UTC
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub misfire_policy: Option<MisfirePolicy>,
+    /// 上次生成的任务未结束时是否仍然触发，默认 false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_overlap: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// 任务模板，每次触发时按此创建任务
+    pub task_template: TaskCreateRequest,
+}
+
+/// 更新定时任务请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskScheduleUpdateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron_expression: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub misfire_policy: Option<MisfirePolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_overlap: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_template: Option<TaskCreateRequest>,
+}
+
+/// 定时任务响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskScheduleResponse {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    pub enabled: bool,
+    pub cron_expression: String,
+    pub timezone: String,
+    pub misfire_policy: MisfirePolicy,
+    pub allow_overlap: bool,
+    pub task_template: JsonValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<String>,
+    /// 接下来的触发时间（最多5次）
+    pub upcoming_runs: Vec<String>,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 定时任务列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskScheduleListResponse {
+    pub data: Vec<TaskScheduleResponse>,
+    pub pagination: Pagination,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 定时任务列表查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TaskScheduleListQuery {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub application_id: Option<String>,
+    pub enabled: Option<bool>,
+    pub search: Option<String>,
+}
+
+/// 触发历史
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskScheduleRunResponse {
+    pub id: String,
+    pub schedule_id: String,
+    pub scheduled_at: String,
+    pub triggered_at: String,
+    pub status: ScheduleRunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 触发历史列表响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TaskScheduleRunListResponse {
+    pub data: Vec<TaskScheduleRunResponse>,
+    pub pagination: Pagination,
+    pub timestamp: u64,
+    pub trace_id: String,
+}
+
+/// 触发历史查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TaskScheduleRunListQuery {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub status: Option<ScheduleRunStatus>,
+}
+
+impl TaskScheduleRunResponse {
+    pub fn from_entity(entity: crate::entities::instance_task_schedule_runs::Model) -> Self {
+        Self {
+            id: entity.id,
+            schedule_id: entity.schedule_id,
+            scheduled_at: entity.scheduled_at.to_rfc3339(),
+            triggered_at: entity.triggered_at.to_rfc3339(),
+            status: entity.status,
+            task_id: entity.task_id,
+            message: entity.message,
+        }
+    }
+}
+
+impl TaskScheduleResponse {
+    pub fn from_entity(entity: crate::entities::instance_task_schedules::Model) -> Self {
+        let upcoming_runs = if entity.enabled {
+            crate::task_schedules::cron::CronSchedule::parse(
+                &entity.cron_expression,
+                &entity.timezone,
+            )
+            .map(|cron| {
+                cron.upcoming(chrono::Utc::now(), 5)
+                    .into_iter()
+                    .map(|t| t.to_rfc3339())
+                    .collect()
+            })
+            .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Self {
+            id: entity.id,
+            name: entity.name,
+            description: entity.description,
+            application_id: entity.application_id,
+            enabled: entity.enabled,
+            cron_expression: entity.cron_expression,
+            timezone: entity.timezone,
+            misfire_policy: entity.misfire_policy,
+            allow_overlap: entity.allow_overlap,
+            task_template: entity.task_template,
+            next_run_at: entity.next_run_at.map(|t| t.to_rfc3339()),
+            last_run_at: entity.last_run_at.map(|t| t.to_rfc3339()),
+            upcoming_runs,
+            created_by: entity.created_by,
+            created_at: entity.created_at.to_rfc3339(),
+            updated_at: entity.updated_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use super::handlers;
+use actix_web::web;
+
+pub fn task_schedule_routes(cfg: &mut web::ServiceConfig) {
+    // 定时任务管理（需要认证）
+    cfg.route(
+        "/instances/task-schedules",
+        web::get().to(handlers::get_task_schedules),
+    )
+    .route(
+        "/instances/task-schedules",
+        web::post().to(handlers::create_task_schedule),
+    )
+    .route(
+        "/instances/task-schedules/{schedule_id}",
+        web::get().to(handlers::get_task_schedule),
+    )
+    .route(
+        "/instances/task-schedules/{schedule_id}",
+        web::put().to(handlers::update_task_schedule),
+    )
+    .route(
+        "/instances/task-schedules/{schedule_id}",
+        web::delete().to(handlers::delete_task_schedule),
+    )
+    .route(
+        "/instances/task-schedules/{schedule_id}/enable",
+        web::post().to(handlers::enable_task_schedule),
+    )
+    .route(
+        "/instances/task-schedules/{schedule_id}/disable",
+        web::post().to(handlers::disable_task_schedule),
+    )
+    .route(
+        "/instances/task-schedules/{schedule_id}/runs",
+        web::get().to(handlers::get_task_schedule_runs),
+    );
+}
@@ -0,0 +1,301 @@
+/// 定时任务触发
+///
+/// 后台调度器周期性查找到期（next_run_at <= now）的定时任务，先以条件更新抢占本次触发
+/// （多个服务实例同时运行时只有一个会成功），再按错过触发策略与防重叠规则决定是否按模板创建任务，
+/// 每次触发都会写入 instance_task_schedule_runs。
+use crate::entities::{
+    instance_task_records, instance_task_schedule_runs, instance_task_schedules, instance_tasks,
+};
+use crate::instance_tasks::handlers::insert_task;
+use crate::instance_tasks::models::TaskCreateRequest;
+use crate::instance_tasks::TaskDispatchHub;
+use crate::shared::enums::{MisfirePolicy, ScheduleRunStatus, TaskStatus};
+use crate::shared::error::ApiError;
+use crate::shared::generate_snowflake_id;
+use crate::task_schedules::cron::CronSchedule;
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use serde_json::json;
+
+/// 超过计划时间多久视为错过触发（秒）
+const MISFIRE_THRESHOLD_SECONDS: i64 = 60;
+
+/// 处理所有到期的定时任务，返回触发次数
+pub async fn run_due_schedules(
+    db: &DatabaseConnection,
+    hub: &TaskDispatchHub,
+) -> Result<u64, ApiError> {
+    let now = Utc::now();
+    let schedules = instance_task_schedules::Entity::find()
+        .filter(instance_task_schedules::Column::DeletedAt.is_null())
+        .filter(instance_task_schedules::Column::Enabled.eq(true))
+        .filter(instance_task_schedules::Column::NextRunAt.lte(now))
+        .order_by_asc(instance_task_schedules::Column::NextRunAt)
+        .all(db)
+        .await?;
+
+    let mut fired = 0;
+    for schedule in schedules {
+        let schedule_id = schedule.id.clone();
+        match fire_schedule(db, hub, schedule, now).await {
+            Ok(true) => fired += 1,
+            Ok(false) => {}
+            Err(e) => log::error!(
+                "[task_scheduler] Failed to fire schedule {}: {}",
+                schedule_id,
+                e
+            ),
+        }
+    }
+    Ok(fired)
+}
+
+/// 触发单个定时任务，返回是否抢占到本次触发
+async fn fire_schedule(
+    db: &DatabaseConnection,
+    hub: &TaskDispatchHub,
+    schedule: instance_task_schedules::Model,
+    now: DateTime<Utc>,
+) -> Result<bool, ApiError> {
+    let Some(scheduled_at) = schedule.next_run_at else {
+        return Ok(false);
+    };
+
+    // 计算下次触发时间；表达式失效时停用，避免每轮重复报错
+    let (next_run_at, parse_error) =
+        match CronSchedule::parse(&schedule.cron_expression, &schedule.timezone) {
+            Ok(cron) => (
+                cron.next_after(now).map(DateTime::<FixedOffset>::from),
+                None,
+            ),
+            Err(e) => (None, Some(e)),
+        };
+
+    // 条件更新抢占本次触发
+    let now_fixed: DateTime<FixedOffset> = now.into();
+    let claimed = instance_task_schedules::Entity::update_many()
+        .col_expr(
+            instance_task_schedules::Column::NextRunAt,
+            Expr::value(next_run_at),
+        )
+        .col_expr(
+            instance_task_schedules::Column::LastRunAt,
+            Expr::value(now_fixed),
+        )
+        .col_expr(
+            instance_task_schedules::Column::Enabled,
+            Expr::value(parse_error.is_none()),
+        )
+        .filter(instance_task_schedules::Column::Id.eq(&schedule.id))
+        .filter(instance_task_schedules::Column::NextRunAt.eq(scheduled_at))
+        .exec(db)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Ok(false);
+    }
+
+    if let Some(error) = parse_error {
+        record_run(
+            db,
+            &schedule.id,
+            scheduled_at,
+            ScheduleRunStatus::Failed,
+            None,
+            Some(format!("{}，已停用定时任务", error)),
+        )
+        .await?;
+        return Ok(true);
+    }
+
+    // 错过触发时间
+    let delay = (now - scheduled_at.with_timezone(&Utc)).num_seconds();
+    if delay > MISFIRE_THRESHOLD_SECONDS && schedule.misfire_policy == MisfirePolicy::Skip {
+        record_run(
+            db,
+            &schedule.id,
+            scheduled_at,
+            ScheduleRunStatus::Skipped,
+            None,
+            Some(format!("错过触发时间 {} 秒，按策略跳过", delay)),
+        )
+        .await?;
+        return Ok(true);
+    }
+
+    // 防止重叠：上次生成的任务仍有未结束的记录
+    if !schedule.allow_overlap {
+        if let Some(task_id) = last_created_task(db, &schedule.id).await? {
+            if task_is_active(db, &task_id).await? {
+                record_run(
+                    db,
+                    &schedule.id,
+                    scheduled_at,
+                    ScheduleRunStatus::Skipped,
+                    None,
+                    Some(format!("上次生成的任务 {} 尚未结束", task_id)),
+                )
+                .await?;
+                return Ok(true);
+            }
+        }
+    }
+
+    let mut request: TaskCreateRequest =
+        match serde_json::from_value(schedule.task_template.clone()) {
+            Ok(request) => request,
+            Err(e) => {
+                record_run(
+                    db,
+                    &schedule.id,
+                    scheduled_at,
+                    ScheduleRunStatus::Failed,
+                    None,
+                    Some(format!("任务模板无效: {}", e)),
+                )
+                .await?;
+                return Ok(true);
+            }
+        };
+    if request.application_id.is_none() {
+        request.application_id = schedule.application_id.clone();
+    }
+    if let Ok(cron) = CronSchedule::parse(&schedule.cron_expression, &schedule.timezone) {
+        request.task_name = format!(
+            "{} ({})",
+            request.task_name,
+            scheduled_at
+                .with_timezone(&cron.timezone())
+                .format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    match insert_task(db, hub, &request, &schedule.created_by).await {
+        Ok(task) => {
+            log::info!(
+                "[task_scheduler] Schedule {} created task {}",
+                schedule.id,
+                task.id
+            );
+            record_run(
+                db,
+                &schedule.id,
+                scheduled_at,
+                ScheduleRunStatus::Created,
+                Some(task.id.clone()),
+                None,
+            )
+            .await?;
+            let _ = crate::audit::handlers::record_audit_log(
+                db,
+                "instance_tasks",
+                "create",
+                "system",
+                "",
+                None,
+                None,
+                Some(json!({
+                    "id": task.id,
+                    "task_name": task.task_name,
+                    "task_type": task.task_type,
+                    "application_id": task.application_id,
+                    "schedule_id": schedule.id,
+                })),
+            )
+            .await;
+        }
+        Err(e) => {
+            record_run(
+                db,
+                &schedule.id,
+                scheduled_at,
+                ScheduleRunStatus::Failed,
+                None,
+                Some(e.to_string()),
+            )
+            .await?;
+        }
+    }
+    Ok(true)
+}
+
+async fn last_created_task(
+    db: &DatabaseConnection,
+    schedule_id: &str,
+) -> Result<Option<String>, ApiError> {
+    Ok(instance_task_schedule_runs::Entity::find()
+        .filter(instance_task_schedule_runs::Column::ScheduleId.eq(schedule_id))
+        .filter(instance_task_schedule_runs::Column::Status.eq(ScheduleRunStatus::Created))
+        .order_by_desc(instance_task_schedule_runs::Column::ScheduledAt)
+        .one(db)
+        .await?
+        .and_then(|run| run.task_id))
+}
+
+/// 任务是否仍在执行：存在未结束的记录，或超时后仍会自动重试的记录
+async fn task_is_active(db: &DatabaseConnection, task_id: &str) -> Result<bool, ApiError> {
+    // 已删除的任务不再重试
+    let retry_count = instance_tasks::Entity::find_by_id(task_id)
+        .filter(instance_tasks::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .and_then(|task| task.retry_count)
+        .unwrap_or(0);
+    let active = instance_task_records::Entity::find()
+        .filter(instance_task_records::Column::TaskId.eq(task_id))
+        .filter(
+            Condition::any()
+                .add(instance_task_records::Column::Status.is_in(vec![
+                    TaskStatus::Queued,
+                    TaskStatus::Pending,
+                    TaskStatus::Dispatched,
+                    TaskStatus::Running,
+                ]))
+                .add(
+                    Condition::all()
+                        .add(instance_task_records::Column::Status.eq(TaskStatus::Timeout))
+                        .add(Expr::cust_with_values(
+                            "COALESCE(retry_attempt, 0) < $1",
+                            [retry_count],
+                        )),
+                ),
+        )
+        .count(db)
+        .await?;
+    Ok(active > 0)
+}
+
+async fn record_run(
+    db: &DatabaseConnection,
+    schedule_id: &str,
+    scheduled_at: DateTime<FixedOffset>,
+    status: ScheduleRunStatus,
+    task_id: Option<String>,
+    message: Option<String>,
+) -> Result<(), ApiError> {
+    if status != ScheduleRunStatus::Created {
+        log::warn!(
+            "[task_scheduler] Schedule {} run {:?}: {}",
+            schedule_id,
+            status,
+            message.as_deref().unwrap_or_default()
+        );
+    }
+    let now = Utc::now();
+    instance_task_schedule_runs::ActiveModel {
+        id: Set(generate_snowflake_id()),
+        schedule_id: Set(schedule_id.to_string()),
+        scheduled_at: Set(scheduled_at),
+        triggered_at: Set(now.into()),
+        status: Set(status),
+        task_id: Set(task_id),
+        message: Set(message),
+        created_at: Set(now.into()),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
@@ -799,6 +799,8 @@ pub async fn assign_user_roles(
         user_role.insert(&**db).await?;
     }
 
+    crate::permissions::cache::invalidate_user(&user_id);
+
     Ok(HttpResponse::Ok().json("角色分配成功"))
 }
 
@@ -838,6 +840,8 @@ pub async fn remove_user_role(
         return Err(ApiError::NotFound("用户角色关联不存在".to_string()));
     }
 
+    crate::permissions::cache::invalidate_user(&user_id);
+
     Ok(HttpResponse::Ok().json("角色移除成功"))
 }
 
@@ -0,0 +1,104 @@
+use crate::shared::snowflake::generate_snowflake_id;
+use crate::websocket::models::{AgentConnect, AgentDisconnect, AgentMessage, WsClose, WsMessage};
+use crate::websocket::server::WsServer;
+use actix::prelude::*;
+use actix_web_actors::ws;
+use std::time::{Duration, Instant};
+
+// Agent 终端通道：Agent 主动建立的出站长连接，便于 NAT 后的主机接入
+pub struct AgentSession {
+    id: String,
+    agent_instance_id: String,
+    heartbeat: Instant,
+    addr: Addr<WsServer>,
+}
+
+impl AgentSession {
+    pub fn new(agent_instance_id: String, addr: Addr<WsServer>) -> Self {
+        Self {
+            id: generate_snowflake_id(),
+            agent_instance_id,
+            heartbeat: Instant::now(),
+            addr,
+        }
+    }
+
+    fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(Duration::from_secs(10), |act, ctx| {
+            if Instant::now().duration_since(act.heartbeat) > Duration::from_secs(30) {
+                ctx.stop();
+                return;
+            }
+
+            ctx.ping(b"");
+        });
+    }
+}
+
+impl Actor for AgentSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.heartbeat(ctx);
+
+        self.addr.do_send(AgentConnect {
+            addr: ctx.address(),
+            connection_id: self.id.clone(),
+            agent_instance_id: self.agent_instance_id.clone(),
+        });
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.addr.do_send(AgentDisconnect {
+            connection_id: self.id.clone(),
+            agent_instance_id: self.agent_instance_id.clone(),
+        });
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for AgentSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.heartbeat = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.heartbeat = Instant::now();
+            }
+            Ok(ws::Message::Text(text)) => {
+                self.heartbeat = Instant::now();
+                self.addr.do_send(AgentMessage {
+                    agent_instance_id: self.agent_instance_id.clone(),
+                    msg: text.to_string(),
+                });
+            }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(_) => ctx.stop(),
+        }
+    }
+}
+
+impl Handler<WsMessage> for AgentSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: WsMessage, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
+impl Handler<WsClose> for AgentSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: WsClose, ctx: &mut Self::Context) {
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Normal,
+            description: Some(msg.0),
+        }));
+        ctx.stop();
+    }
+}
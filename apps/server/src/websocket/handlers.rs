@@ -1,21 +1,97 @@
+use crate::agent_auth::verify_agent_token_for_instance;
+use crate::auth::middleware::get_user_id_from_request;
+use crate::entities::instances;
+use crate::members::{ensure_instance_access, resolve_user_scope};
+use crate::permissions::handlers::get_user_permission_by_name;
+use crate::shared::enums::Status;
+use crate::shared::error::ApiError;
+use crate::websocket::agent_session::AgentSession;
+use crate::websocket::models::{AgentTerminalQuery, TerminalQuery};
 use crate::websocket::server::WsServer;
 use crate::websocket::server::WsSession;
 use actix::prelude::*;
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 
-// WebSocket endpoint handler
+// WebSocket endpoint handler：浏览器连接实例终端
 pub async fn terminal_websocket(
     req: HttpRequest,
     stream: web::Payload,
     srv: web::Data<Addr<WsServer>>,
+    db: web::Data<DatabaseConnection>,
     path: web::Path<String>,
+    query: web::Query<TerminalQuery>,
 ) -> Result<HttpResponse, Error> {
-    let deployment_id = path.into_inner();
+    let user_id = get_user_id_from_request(&req)?;
+    // 远程终端等同于在主机上执行任意命令，需要实例编辑权限
+    if get_user_permission_by_name(&user_id, "instances.edit", &db)
+        .await
+        .map_err(ApiError::from)?
+        .is_none()
+    {
+        return Err(ApiError::Forbidden("没有权限打开实例终端".to_string()).into());
+    }
+
+    let instance_id = path.into_inner();
+    let scope = resolve_user_scope(&db, &user_id).await?;
+    ensure_instance_access(&db, &scope, &instance_id).await?;
+    let instance = instances::Entity::find_by_id(&instance_id)
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(db.get_ref())
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::NotFound("实例不存在".to_string()))?;
+    if instance.status != Status::Active {
+        return Err(ApiError::Forbidden("Instance is disabled".to_string()).into());
+    }
+    let agent_instance_id = instance
+        .agent_instance_id
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("实例尚未接入 Agent".to_string()))?;
+
+    let cols = query.cols.unwrap_or(80).clamp(10, 500);
+    let rows = query.rows.unwrap_or(24).clamp(5, 200);
 
     // Start WebSocket session
     ws::start(
-        WsSession::new(deployment_id, srv.get_ref().clone()),
+        WsSession::new(
+            instance.id,
+            agent_instance_id,
+            user_id,
+            cols,
+            rows,
+            srv.get_ref().clone(),
+        ),
+        &req,
+        stream,
+    )
+}
+
+// Agent 终端通道（开放接口，使用实例令牌认证）
+pub async fn agent_terminal_websocket(
+    req: HttpRequest,
+    stream: web::Payload,
+    srv: web::Data<Addr<WsServer>>,
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<AgentTerminalQuery>,
+) -> Result<HttpResponse, Error> {
+    let instance = instances::Entity::find()
+        .filter(instances::Column::AgentInstanceId.eq(&query.agent_instance_id))
+        .filter(instances::Column::DeletedAt.is_null())
+        .one(db.get_ref())
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Instance {} not found", query.agent_instance_id))
+        })?;
+    verify_agent_token_for_instance(&db, &req, &instance).await?;
+    if instance.status != Status::Active {
+        return Err(ApiError::Forbidden("Instance is disabled".to_string()).into());
+    }
+
+    ws::start(
+        AgentSession::new(query.agent_instance_id.clone(), srv.get_ref().clone()),
         &req,
         stream,
     )
@@ -1,4 +1,6 @@
+pub mod agent_session;
 pub mod handlers;
 pub mod models;
+pub mod recording;
 pub mod routes;
 pub mod server;
@@ -1,29 +1,141 @@
-use actix::prelude::Message;
-use actix::prelude::Recipient;
+use crate::websocket::agent_session::AgentSession;
+use crate::websocket::server::WsSession;
+use actix::prelude::{Addr, Message};
+use serde::{Deserialize, Serialize};
 
 // WebSocket message types
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct WsMessage(pub String);
 
+/// 通知会话关闭连接
 #[derive(Message)]
 #[rtype(result = "()")]
+pub struct WsClose(pub String);
+
+/// 浏览器终端会话接入，要求目标 Agent 在线
+#[derive(Message)]
+#[rtype(result = "Result<(), String>")]
 pub struct Connect {
-    pub addr: Recipient<WsMessage>,
-    pub deployment_id: String,
+    pub addr: Addr<WsSession>,
+    pub session_id: String,
+    pub instance_id: String,
+    pub agent_instance_id: String,
+    pub user_id: String,
+    pub cols: u16,
+    pub rows: u16,
 }
 
+/// 浏览器终端会话断开
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct Disconnect {
-    pub id: String,
-    pub deployment_id: String,
+    pub session_id: String,
 }
 
+/// 浏览器发送的终端帧（输入、调整窗口大小）
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct ClientMessage {
-    pub id: String,
-    pub deployment_id: String,
+    pub session_id: String,
+    pub msg: String,
+}
+
+/// Agent 建立终端通道
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct AgentConnect {
+    pub addr: Addr<AgentSession>,
+    pub connection_id: String,
+    pub agent_instance_id: String,
+}
+
+/// Agent 终端通道断开
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct AgentDisconnect {
+    pub connection_id: String,
+    pub agent_instance_id: String,
+}
+
+/// Agent 发送的终端帧（输出、退出、错误）
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct AgentMessage {
+    pub agent_instance_id: String,
     pub msg: String,
 }
+
+/// 终端协议帧（浏览器 <-> 服务端 <-> Agent 共用）
+///
+/// 浏览器发送的帧无需携带 session_id，由服务端按连接补齐。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TerminalFrame {
+    Open {
+        session_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    Input {
+        #[serde(default)]
+        session_id: String,
+        data: String,
+    },
+    Resize {
+        #[serde(default)]
+        session_id: String,
+        cols: u16,
+        rows: u16,
+    },
+    Output {
+        #[serde(default)]
+        session_id: String,
+        data: String,
+    },
+    Exit {
+        #[serde(default)]
+        session_id: String,
+        code: Option<i32>,
+    },
+    Close {
+        #[serde(default)]
+        session_id: String,
+    },
+    Error {
+        #[serde(default)]
+        session_id: String,
+        message: String,
+    },
+}
+
+impl TerminalFrame {
+    pub fn session_id(&self) -> &str {
+        match self {
+            TerminalFrame::Open { session_id, .. }
+            | TerminalFrame::Input { session_id, .. }
+            | TerminalFrame::Resize { session_id, .. }
+            | TerminalFrame::Output { session_id, .. }
+            | TerminalFrame::Exit { session_id, .. }
+            | TerminalFrame::Close { session_id }
+            | TerminalFrame::Error { session_id, .. } => session_id,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// 终端连接查询参数
+#[derive(Debug, Deserialize)]
+pub struct TerminalQuery {
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+}
+
+/// Agent 终端通道查询参数
+#[derive(Debug, Deserialize)]
+pub struct AgentTerminalQuery {
+    pub agent_instance_id: String,
+}
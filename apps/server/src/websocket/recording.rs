@@ -0,0 +1,189 @@
+/// 终端会话录像
+///
+/// 采用 asciicast v2 格式：首行为头信息，其后每行一个 `[秒数, 类型, 数据]` 事件，
+/// 类型为 o（终端输出）、i（操作员输入）、r（窗口大小）。
+/// 会话结束后写入 ./uploads/terminal 并登记到 files 表，供审计回放。
+use crate::entities::files;
+use chrono::{DateTime, Utc};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use std::time::Instant;
+use tokio::fs;
+
+/// 单个录像的最大字节数，超出后停止记录
+const MAX_RECORDING_BYTES: usize = 16 * 1024 * 1024;
+
+const RECORDING_DIR: &str = "./uploads/terminal";
+
+pub struct TerminalRecording {
+    started: Instant,
+    started_at: DateTime<Utc>,
+    header: String,
+    events: Vec<String>,
+    size: usize,
+    truncated: bool,
+}
+
+impl TerminalRecording {
+    pub fn new(cols: u16, rows: u16, title: &str) -> Self {
+        let started_at = Utc::now();
+        let header = serde_json::json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": started_at.timestamp(),
+            "title": title,
+            "env": { "TERM": "xterm-256color" },
+        })
+        .to_string();
+        Self {
+            started: Instant::now(),
+            started_at,
+            size: header.len(),
+            header,
+            events: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// 记录终端输出
+    pub fn output(&mut self, data: &str) {
+        self.push("o", data);
+    }
+
+    /// 记录操作员输入
+    pub fn input(&mut self, data: &str) {
+        self.push("i", data);
+    }
+
+    /// 记录窗口大小变化
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        self.push("r", &format!("{}x{}", cols, rows));
+    }
+
+    fn push(&mut self, code: &str, data: &str) {
+        if self.truncated {
+            return;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let line = serde_json::json!([elapsed, code, data]).to_string();
+        if self.size + line.len() > MAX_RECORDING_BYTES {
+            self.truncated = true;
+            log::warn!(
+                "终端录像超过 {} 字节，后续输出不再记录",
+                MAX_RECORDING_BYTES
+            );
+            return;
+        }
+        self.size += line.len() + 1;
+        self.events.push(line);
+    }
+
+    fn render(&self) -> String {
+        let mut content = String::with_capacity(self.size + 1);
+        content.push_str(&self.header);
+        content.push('\n');
+        for line in &self.events {
+            content.push_str(line);
+            content.push('\n');
+        }
+        content
+    }
+}
+
+/// 将录像写入文件存储并登记到 files 表
+pub async fn save_recording(
+    db: DatabaseConnection,
+    session_id: String,
+    instance_id: String,
+    user_id: String,
+    recording: TerminalRecording,
+) -> Result<String, String> {
+    fs::create_dir_all(RECORDING_DIR)
+        .await
+        .map_err(|e| format!("创建录像目录失败: {}", e))?;
+
+    let file_name = format!(
+        "terminal_{}_{}.cast",
+        instance_id,
+        recording.started_at.format("%Y%m%d%H%M%S")
+    );
+    let file_path = format!("{}/{}.cast", RECORDING_DIR, session_id);
+    let content = recording.render();
+    fs::write(&file_path, content.as_bytes())
+        .await
+        .map_err(|e| format!("写入录像文件失败: {}", e))?;
+
+    let now = Utc::now();
+    let model = files::ActiveModel {
+        id: Set(session_id.clone()),
+        file_name: Set(file_name),
+        file_size: Set(content.len() as i64),
+        file_path: Set(file_path.clone()),
+        uploaded_by: Set(user_id),
+        uploaded_at: Set(now.naive_utc()),
+        updated_at: Set(now.naive_utc()),
+        task_id: Set(None),
+        instance_id: Set(Some(instance_id)),
+        file_extension: Set(Some("cast".to_string())),
+        original_file_path: Set(None),
+    };
+    files::Entity::insert(model)
+        .exec(&db)
+        .await
+        .map_err(|e| format!("登记录像文件失败: {}", e))?;
+
+    Ok(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn lines(recording: &TerminalRecording) -> Vec<Value> {
+        recording
+            .render()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_recording_format() {
+        let mut recording = TerminalRecording::new(120, 40, "web-01");
+        recording.input("ls\r");
+        recording.output("a.txt\r\n");
+        recording.resize(100, 30);
+
+        let lines = lines(&recording);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 120);
+        assert_eq!(lines[0]["height"], 40);
+        assert_eq!(lines[0]["title"], "web-01");
+
+        let events: Vec<(&str, &str)> = lines[1..]
+            .iter()
+            .map(|e| (e[1].as_str().unwrap(), e[2].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![("i", "ls\r"), ("o", "a.txt\r\n"), ("r", "100x30")]
+        );
+        let times: Vec<f64> = lines[1..].iter().map(|e| e[0].as_f64().unwrap()).collect();
+        assert!(times.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_recording_stops_at_limit() {
+        let mut recording = TerminalRecording::new(80, 24, "web-01");
+        let chunk = "x".repeat(1024 * 1024);
+        for _ in 0..20 {
+            recording.output(&chunk);
+        }
+        recording.input("exit\r");
+        assert!(recording.truncated);
+        assert!(recording.render().len() <= MAX_RECORDING_BYTES + 1);
+        assert!(!recording.render().contains("exit"));
+    }
+}
@@ -3,7 +3,15 @@ use actix_web::web;
 
 pub fn websocket_routes(cfg: &mut web::ServiceConfig) {
     cfg.route(
-        "/websocket/terminal/{deployment_id}",
+        "/websocket/terminal/{instance_id}",
         web::get().to(handlers::terminal_websocket),
     );
 }
+
+pub fn open_websocket_routes(cfg: &mut web::ServiceConfig) {
+    // Agent 终端通道（开放接口，使用实例令牌认证）
+    cfg.route(
+        "/terminal/ws",
+        web::get().to(handlers::agent_terminal_websocket),
+    );
+}
@@ -1,22 +1,47 @@
 use crate::shared::snowflake::generate_snowflake_id;
-use crate::websocket::models::{ClientMessage, Connect, Disconnect, WsMessage};
+use crate::websocket::agent_session::AgentSession;
+use crate::websocket::models::{
+    AgentConnect, AgentDisconnect, AgentMessage, ClientMessage, Connect, Disconnect, TerminalFrame,
+    WsClose, WsMessage,
+};
+use crate::websocket::recording::{save_recording, TerminalRecording};
 use actix::prelude::*;
 use actix_web_actors::ws;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
-// WebSocket session
+/// 终端空闲超时默认值（秒），可通过 TERMINAL_IDLE_TIMEOUT_SECONDS 覆盖
+const DEFAULT_IDLE_TIMEOUT_SECONDS: u64 = 900;
+
+// WebSocket session（浏览器侧终端会话）
 pub struct WsSession {
     id: String,
-    deployment_id: String,
+    instance_id: String,
+    agent_instance_id: String,
+    user_id: String,
+    cols: u16,
+    rows: u16,
     heartbeat: Instant,
     addr: Addr<WsServer>,
 }
 
 impl WsSession {
-    pub fn new(deployment_id: String, addr: Addr<WsServer>) -> Self {
+    pub fn new(
+        instance_id: String,
+        agent_instance_id: String,
+        user_id: String,
+        cols: u16,
+        rows: u16,
+        addr: Addr<WsServer>,
+    ) -> Self {
         Self {
             id: generate_snowflake_id(),
-            deployment_id,
+            instance_id,
+            agent_instance_id,
+            user_id,
+            cols,
+            rows,
             heartbeat: Instant::now(),
             addr,
         }
@@ -30,19 +55,42 @@ impl Actor for WsSession {
         // Start heartbeat
         self.heartbeat(ctx);
 
-        // Register with server
-        let addr = ctx.address();
-        self.addr.do_send(Connect {
-            addr: addr.recipient(),
-            deployment_id: self.deployment_id.clone(),
-        });
+        // 向服务端登记会话，Agent 不在线时直接关闭
+        self.addr
+            .send(Connect {
+                addr: ctx.address(),
+                session_id: self.id.clone(),
+                instance_id: self.instance_id.clone(),
+                agent_instance_id: self.agent_instance_id.clone(),
+                user_id: self.user_id.clone(),
+                cols: self.cols,
+                rows: self.rows,
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                let err = match res {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e),
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(message) = err {
+                    let frame = TerminalFrame::Error {
+                        session_id: act.id.clone(),
+                        message,
+                    };
+                    ctx.text(frame.to_json());
+                    ctx.close(None);
+                    ctx.stop();
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
     }
 
     fn stopped(&mut self, _: &mut Self::Context) {
         // Unregister from server
         self.addr.do_send(Disconnect {
-            id: self.id.clone(),
-            deployment_id: self.deployment_id.clone(),
+            session_id: self.id.clone(),
         });
     }
 }
@@ -60,12 +108,21 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
             Ok(ws::Message::Text(text)) => {
                 // Forward message to server
                 self.addr.do_send(ClientMessage {
-                    id: self.id.clone(),
-                    deployment_id: self.deployment_id.clone(),
-                    msg: text.to_string(), // 修复类型错误，确保是String类型
+                    session_id: self.id.clone(),
+                    msg: text.to_string(),
+                });
+            }
+            Ok(ws::Message::Binary(bin)) => {
+                // 二进制帧按原始输入处理
+                let frame = TerminalFrame::Input {
+                    session_id: self.id.clone(),
+                    data: String::from_utf8_lossy(&bin).to_string(),
+                };
+                self.addr.do_send(ClientMessage {
+                    session_id: self.id.clone(),
+                    msg: frame.to_json(),
                 });
             }
-            Ok(ws::Message::Binary(bin)) => ctx.binary(bin),
             Ok(ws::Message::Close(reason)) => {
                 ctx.close(reason);
                 ctx.stop();
@@ -83,6 +140,18 @@ impl Handler<WsMessage> for WsSession {
     }
 }
 
+impl Handler<WsClose> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: WsClose, ctx: &mut Self::Context) {
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Normal,
+            description: Some(msg.0),
+        }));
+        ctx.stop();
+    }
+}
+
 impl WsSession {
     fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
         ctx.run_interval(Duration::from_secs(5), |act, ctx| {
@@ -96,16 +165,126 @@ impl WsSession {
     }
 }
 
-// WebSocket server
+/// 已接入的 Agent 终端通道
+struct AgentConnection {
+    connection_id: String,
+    addr: Addr<AgentSession>,
+}
+
+/// 进行中的终端会话
+struct TerminalSession {
+    browser: Addr<WsSession>,
+    instance_id: String,
+    agent_instance_id: String,
+    user_id: String,
+    last_activity: Instant,
+    recording: TerminalRecording,
+}
+
+// WebSocket server：在浏览器终端会话与 Agent 终端通道之间转发帧
 pub struct WsServer {
-    sessions:
-        std::collections::HashMap<String, std::collections::HashMap<String, Recipient<WsMessage>>>,
+    agents: HashMap<String, AgentConnection>,
+    sessions: HashMap<String, TerminalSession>,
+    db: Option<DatabaseConnection>,
+    idle_timeout: Duration,
 }
 
 impl WsServer {
     pub fn new() -> Self {
+        let idle_timeout = std::env::var("TERMINAL_IDLE_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_IDLE_TIMEOUT_SECONDS);
         Self {
-            sessions: std::collections::HashMap::new(),
+            agents: HashMap::new(),
+            sessions: HashMap::new(),
+            db: None,
+            idle_timeout: Duration::from_secs(idle_timeout),
+        }
+    }
+
+    /// 设置数据库连接，用于保存终端录像
+    pub fn with_database(mut self, db: DatabaseConnection) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// 判断 Agent 终端通道是否在线
+    pub fn is_agent_online(&self, agent_instance_id: &str) -> bool {
+        self.agents.contains_key(agent_instance_id)
+    }
+
+    fn send_to_agent(&self, agent_instance_id: &str, frame: &TerminalFrame) -> bool {
+        match self.agents.get(agent_instance_id) {
+            Some(agent) => {
+                agent.addr.do_send(WsMessage(frame.to_json()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 结束终端会话：通知双方并保存录像
+    fn end_session(&mut self, session_id: &str, reason: &str, notify_browser: bool) {
+        let Some(session) = self.sessions.remove(session_id) else {
+            return;
+        };
+
+        self.send_to_agent(
+            &session.agent_instance_id,
+            &TerminalFrame::Close {
+                session_id: session_id.to_string(),
+            },
+        );
+        if notify_browser {
+            session.browser.do_send(WsClose(reason.to_string()));
+        }
+
+        log::info!(
+            "[terminal] session {} closed ({}), instance={}, user={}",
+            session_id,
+            reason,
+            session.instance_id,
+            session.user_id
+        );
+
+        if let Some(db) = self.db.clone() {
+            let session_id = session_id.to_string();
+            actix::spawn(async move {
+                match save_recording(
+                    db,
+                    session_id.clone(),
+                    session.instance_id,
+                    session.user_id,
+                    session.recording,
+                )
+                .await
+                {
+                    Ok(path) => log::info!("[terminal] recording saved: {}", path),
+                    Err(e) => {
+                        log::error!("[terminal] failed to save recording {}: {}", session_id, e)
+                    }
+                }
+            });
+        }
+    }
+
+    fn check_idle_sessions(&mut self) {
+        let expired: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.last_activity.elapsed() > self.idle_timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for session_id in expired {
+            if let Some(session) = self.sessions.get(&session_id) {
+                let frame = TerminalFrame::Error {
+                    session_id: session_id.clone(),
+                    message: "终端空闲超时，会话已关闭".to_string(),
+                };
+                session.browser.do_send(WsMessage(frame.to_json()));
+            }
+            self.end_session(&session_id, "idle timeout", true);
         }
     }
 }
@@ -118,17 +297,43 @@ impl Default for WsServer {
 
 impl Actor for WsServer {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // 定期清理空闲会话
+        ctx.run_interval(Duration::from_secs(30), |act, _| {
+            act.check_idle_sessions();
+        });
+    }
 }
 
 impl Handler<Connect> for WsServer {
-    type Result = ();
+    type Result = Result<(), String>;
+
+    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
+        let open = TerminalFrame::Open {
+            session_id: msg.session_id.clone(),
+            cols: msg.cols,
+            rows: msg.rows,
+        };
+        if !self.send_to_agent(&msg.agent_instance_id, &open) {
+            return Err("实例终端通道不在线".to_string());
+        }
 
-    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) {
-        // Add session to deployment group
-        self.sessions
-            .entry(msg.deployment_id.clone())
-            .or_insert_with(std::collections::HashMap::new)
-            .insert(generate_snowflake_id(), msg.addr);
+        let mut recording =
+            TerminalRecording::new(msg.cols, msg.rows, &format!("instance {}", msg.instance_id));
+        recording.resize(msg.cols, msg.rows);
+        self.sessions.insert(
+            msg.session_id,
+            TerminalSession {
+                browser: msg.addr,
+                instance_id: msg.instance_id,
+                agent_instance_id: msg.agent_instance_id,
+                user_id: msg.user_id,
+                last_activity: Instant::now(),
+                recording,
+            },
+        );
+        Ok(())
     }
 }
 
@@ -136,10 +341,7 @@ impl Handler<Disconnect> for WsServer {
     type Result = ();
 
     fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
-        // Remove session from deployment group
-        if let Some(deployment_sessions) = self.sessions.get_mut(&msg.deployment_id) {
-            deployment_sessions.remove(&msg.id);
-        }
+        self.end_session(&msg.session_id, "client disconnected", false);
     }
 }
 
@@ -147,12 +349,139 @@ impl Handler<ClientMessage> for WsServer {
     type Result = ();
 
     fn handle(&mut self, msg: ClientMessage, _: &mut Context<Self>) {
-        // Echo message back to client (in a real implementation, this would process the command)
-        if let Some(deployment_sessions) = self.sessions.get(&msg.deployment_id) {
-            if let Some(addr) = deployment_sessions.get(&msg.id) {
-                let response = format!("Received: {}", msg.msg);
-                addr.do_send(WsMessage(response));
+        let frame = match serde_json::from_str::<TerminalFrame>(&msg.msg) {
+            Ok(frame) => frame,
+            Err(e) => {
+                log::warn!("[terminal] invalid client frame: {}", e);
+                return;
+            }
+        };
+        let Some(session) = self.sessions.get_mut(&msg.session_id) else {
+            return;
+        };
+        session.last_activity = Instant::now();
+
+        // 浏览器只允许发送输入、调整大小与关闭帧，session_id 以连接为准
+        let forward = match frame {
+            TerminalFrame::Input { data, .. } => {
+                session.recording.input(&data);
+                TerminalFrame::Input {
+                    session_id: msg.session_id.clone(),
+                    data,
+                }
+            }
+            TerminalFrame::Resize { cols, rows, .. } => {
+                session.recording.resize(cols, rows);
+                TerminalFrame::Resize {
+                    session_id: msg.session_id.clone(),
+                    cols,
+                    rows,
+                }
+            }
+            TerminalFrame::Close { .. } => {
+                self.end_session(&msg.session_id, "closed by client", true);
+                return;
+            }
+            _ => return,
+        };
+        let agent_instance_id = session.agent_instance_id.clone();
+        self.send_to_agent(&agent_instance_id, &forward);
+    }
+}
+
+impl Handler<AgentConnect> for WsServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: AgentConnect, _: &mut Context<Self>) {
+        log::info!("[terminal] agent connected: {}", msg.agent_instance_id);
+        // 同一实例重复接入时，以新连接为准并关闭旧连接
+        if let Some(old) = self.agents.insert(
+            msg.agent_instance_id,
+            AgentConnection {
+                connection_id: msg.connection_id,
+                addr: msg.addr,
+            },
+        ) {
+            old.addr
+                .do_send(WsClose("replaced by a new connection".to_string()));
+        }
+    }
+}
+
+impl Handler<AgentDisconnect> for WsServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: AgentDisconnect, _: &mut Context<Self>) {
+        let is_current = self
+            .agents
+            .get(&msg.agent_instance_id)
+            .map(|a| a.connection_id == msg.connection_id)
+            .unwrap_or(false);
+        if !is_current {
+            return;
+        }
+        self.agents.remove(&msg.agent_instance_id);
+        log::info!("[terminal] agent disconnected: {}", msg.agent_instance_id);
+
+        let orphaned: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.agent_instance_id == msg.agent_instance_id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for session_id in orphaned {
+            if let Some(session) = self.sessions.get(&session_id) {
+                let frame = TerminalFrame::Error {
+                    session_id: session_id.clone(),
+                    message: "实例终端通道已断开".to_string(),
+                };
+                session.browser.do_send(WsMessage(frame.to_json()));
+            }
+            self.end_session(&session_id, "agent disconnected", true);
+        }
+    }
+}
+
+impl Handler<AgentMessage> for WsServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: AgentMessage, _: &mut Context<Self>) {
+        let frame = match serde_json::from_str::<TerminalFrame>(&msg.msg) {
+            Ok(frame) => frame,
+            Err(e) => {
+                log::warn!("[terminal] invalid agent frame: {}", e);
+                return;
+            }
+        };
+        let session_id = frame.session_id().to_string();
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        // 只接受会话所属 Agent 发来的帧
+        if session.agent_instance_id != msg.agent_instance_id {
+            log::warn!(
+                "[terminal] agent {} sent frame for session {} it does not own",
+                msg.agent_instance_id,
+                session_id
+            );
+            return;
+        }
+        session.last_activity = Instant::now();
+
+        match &frame {
+            TerminalFrame::Output { data, .. } => {
+                session.recording.output(data);
+                session.browser.do_send(WsMessage(frame.to_json()));
+            }
+            TerminalFrame::Exit { .. } => {
+                session.browser.do_send(WsMessage(frame.to_json()));
+                self.end_session(&session_id, "shell exited", true);
+            }
+            TerminalFrame::Error { .. } => {
+                session.browser.do_send(WsMessage(frame.to_json()));
+                self.end_session(&session_id, "agent error", true);
             }
+            _ => {}
         }
     }
 }